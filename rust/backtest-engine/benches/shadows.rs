@@ -0,0 +1,42 @@
+//! Single-pass shadow runs against the same configurations run one by one.
+//!
+//! cargo bench --bench shadows
+
+use std::time::Instant;
+
+use backtest_engine::{generate_synthetic_bars_seeded, BacktestEngine, BacktestParameters};
+
+fn main() {
+    let bars = generate_synthetic_bars_seeded(200_000, 50.0, 42);
+    let primary = BacktestParameters::default().without_vwap_filter();
+
+    println!(
+        "{:>8} {:>12} {:>12} {:>8}",
+        "configs", "single pass", "standalone", "ratio"
+    );
+    for shadow_count in [0, 1, 3, 7, 15] {
+        // Threshold variants share the primary's indicator series
+        let shadows: Vec<BacktestParameters> = (0..shadow_count)
+            .map(|i| primary.clone().with_rsi_thresholds(10.0 + i as f64, 75.0))
+            .collect();
+
+        let start = Instant::now();
+        let results =
+            BacktestEngine::run_with_shadows(&bars, None, primary.clone(), shadows.clone());
+        let shared = start.elapsed();
+
+        let start = Instant::now();
+        for params in std::iter::once(primary.clone()).chain(shadows) {
+            BacktestEngine::new(params).run(&bars, None);
+        }
+        let separate = start.elapsed();
+
+        println!(
+            "{:>8} {:>10.1}ms {:>10.1}ms {:>8.2}",
+            results.len(),
+            shared.as_secs_f64() * 1000.0,
+            separate.as_secs_f64() * 1000.0,
+            shared.as_secs_f64() / separate.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Embed the commit the engine is built from, for run manifests. A
+//! `BACKTEST_GIT_HASH` set in the build environment (e.g. in CI without a
+//! checkout) is used as is.
+
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=BACKTEST_GIT_HASH");
+    if std::env::var_os("BACKTEST_GIT_HASH").is_some() {
+        return;
+    }
+
+    // Rebuild when HEAD moves, whether by checkout or by commit
+    if let Some(dir) = git(&["rev-parse", "--git-dir"]) {
+        println!("cargo:rerun-if-changed={}/HEAD", dir);
+        if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed={}/{}", dir, branch);
+        }
+    }
+    if let Some(hash) = git(&["rev-parse", "--short=12", "HEAD"]) {
+        println!("cargo:rustc-env=BACKTEST_GIT_HASH={}", hash);
+    }
+}
@@ -0,0 +1,52 @@
+//! Run the default strategy on reproducible synthetic data and print a report.
+//!
+//! cargo run --example basic_backtest
+
+use backtest_engine::{generate_synthetic_bars_seeded, BacktestEngine, BacktestParameters};
+
+fn main() {
+    let bars = generate_synthetic_bars_seeded(750, 50.0, 42);
+    let params = BacktestParameters::default()
+        .with_capital(25_000.0)
+        .without_vwap_filter();
+
+    let result = BacktestEngine::new(params).run(&bars, None);
+    let m = &result.metrics;
+
+    println!(
+        "Period:        {} to {}",
+        result.start_date, result.end_date
+    );
+    println!("Initial:       ${:.2}", result.initial_capital);
+    println!("Final:         ${:.2}", result.final_equity);
+    println!("Return:        {:+.2}%", m.total_return_pct);
+    println!("Sharpe:        {:.2}", m.sharpe_ratio);
+    println!("Max drawdown:  {:.2}%", m.max_drawdown);
+    println!(
+        "Trades:        {} ({:.1}% win rate)",
+        m.total_trades, m.win_rate
+    );
+
+    for trade in result.trades.iter().take(5) {
+        println!(
+            "  {} -> {}  {:>8.2}  {}",
+            trade.entry_date.format("%Y-%m-%d"),
+            trade
+                .exit_date
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_else(|| "open".to_string()),
+            trade.pnl,
+            trade.exit_reason
+        );
+    }
+
+    assert_eq!(result.initial_capital, 25_000.0);
+    assert_eq!(result.equity_curve.len(), bars.len() - 20);
+    assert!(
+        m.total_trades > 0,
+        "the default strategy should trade on 750 bars"
+    );
+    assert_eq!(m.total_trades as usize, result.trades.len());
+    let pnl: f64 = result.trades.iter().map(|t| t.pnl).sum();
+    assert!((result.final_equity - result.initial_capital - pnl).abs() < 1e-6);
+}
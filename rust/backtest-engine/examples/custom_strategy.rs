@@ -0,0 +1,65 @@
+//! Build a parameter set programmatically and post-process its trades.
+//!
+//! cargo run --example custom_strategy
+
+use std::collections::BTreeMap;
+
+use backtest_engine::{generate_synthetic_bars_seeded, BacktestEngine, BacktestParameters, Preset};
+use common::VwapMode;
+
+fn main() {
+    let bars = generate_synthetic_bars_seeded(1000, 50.0, 7);
+
+    // Start from a preset and adjust it: slower RSI, tighter stop, time exit
+    let mut params = Preset::Conservative.parameters();
+    params.strategy.rsi_period = 3;
+    params.strategy.vwap_mode = VwapMode::Disabled;
+    params.strategy.max_holding_days = Some(10);
+    let params = params.with_rsi_thresholds(25.0, 70.0).with_stop_loss(0.04);
+    params.validate().expect("custom parameters must be valid");
+
+    // Invalid combinations are caught up front
+    assert!(BacktestParameters::default()
+        .with_rsi_thresholds(70.0, 30.0)
+        .validate()
+        .is_err());
+
+    let result = BacktestEngine::new(params.clone()).run(&bars, None);
+
+    // Group closed trades by kind of exit
+    let mut by_reason: BTreeMap<&str, (u32, f64)> = BTreeMap::new();
+    for trade in &result.trades {
+        let entry = by_reason.entry(exit_kind(&trade.exit_reason)).or_default();
+        entry.0 += 1;
+        entry.1 += trade.pnl;
+    }
+
+    println!("{} trades", result.trades.len());
+    for (reason, (count, pnl)) in &by_reason {
+        println!("  {:<12} {:>4}  {:>10.2}", reason, count, pnl);
+    }
+
+    assert!(!result.trades.is_empty());
+    for trade in &result.trades {
+        // Neither the stop nor the time exit may be overstayed by more than a bar
+        assert!(
+            trade.holding_days <= 11,
+            "time exit not applied: {:?}",
+            trade
+        );
+    }
+    let grouped: u32 = by_reason.values().map(|(count, _)| count).sum();
+    assert_eq!(grouped as usize, result.trades.len());
+}
+
+fn exit_kind(reason: &str) -> &'static str {
+    if reason.contains("stop loss") {
+        "stop loss"
+    } else if reason.starts_with("Time exit") {
+        "time exit"
+    } else if reason.contains("take profit") {
+        "take profit"
+    } else {
+        "other"
+    }
+}
@@ -0,0 +1,85 @@
+//! Save bars and a parameter file to disk, load them back and compare two
+//! strategies on the same data.
+//!
+//! cargo run --example load_and_compare
+
+use std::fs;
+
+use backtest_engine::{
+    generate_synthetic_bars_seeded, load_file, load_parameters, BacktestEngine, BacktestParameters,
+    PerformanceMetrics, Preset,
+};
+
+type MetricFn = fn(&PerformanceMetrics) -> f64;
+
+fn main() {
+    let dir = std::env::temp_dir().join("backtest_engine_load_and_compare");
+    fs::create_dir_all(&dir).unwrap();
+
+    let data_path = dir.join("bars.json");
+    let bars = generate_synthetic_bars_seeded(1000, 50.0, 3);
+    fs::write(&data_path, serde_json::to_string(&bars).unwrap()).unwrap();
+
+    // A config only lists what it changes; the rest falls back to defaults
+    let config_path = dir.join("tight.toml");
+    fs::write(
+        &config_path,
+        "rsi_oversold = 15.0\nstop_loss_pct = 0.03\nvwap_mode = \"disabled\"\n",
+    )
+    .unwrap();
+
+    let loaded = load_file(&data_path).expect("bars round-trip through JSON");
+    assert_eq!(loaded.len(), bars.len());
+
+    let tight = load_parameters(&config_path).expect("config parses");
+    assert_eq!(tight.strategy.rsi_oversold, 15.0);
+    assert_eq!(
+        tight.strategy.rsi_overbought,
+        BacktestParameters::default().strategy.rsi_overbought
+    );
+
+    let candidates = [
+        ("tight.toml", tight),
+        ("aggressive", Preset::Aggressive.parameters()),
+    ];
+    let results: Vec<_> = candidates
+        .iter()
+        .map(|(name, params)| {
+            (
+                *name,
+                BacktestEngine::new(params.clone()).run(&loaded, None),
+            )
+        })
+        .collect();
+
+    let rows: [(&str, MetricFn); 5] = [
+        ("Return %", |m| m.total_return_pct),
+        ("Sharpe", |m| m.sharpe_ratio),
+        ("Max DD %", |m| m.max_drawdown),
+        ("Win rate %", |m| m.win_rate),
+        ("Trades", |m| m.total_trades as f64),
+    ];
+    println!("{:<12} {:>12} {:>12}", "", results[0].0, results[1].0);
+    for (label, metric) in rows {
+        println!(
+            "{:<12} {:>12.2} {:>12.2}",
+            label,
+            metric(&results[0].1.metrics),
+            metric(&results[1].1.metrics)
+        );
+    }
+
+    for (name, result) in &results {
+        assert_eq!(
+            result.start_date,
+            loaded[0].timestamp.date_naive(),
+            "{}",
+            name
+        );
+        assert!(result.final_equity > 0.0, "{}", name);
+    }
+    // Wider entries and bigger positions must not trade less
+    assert!(results[1].1.metrics.total_trades >= results[0].1.metrics.total_trades);
+
+    fs::remove_dir_all(&dir).ok();
+}
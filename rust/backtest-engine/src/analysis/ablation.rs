@@ -0,0 +1,189 @@
+//! Filter ablation: rerun the strategy with each entry filter removed
+//!
+//! The baseline comes first, followed by one row per filter. Filters that
+//! are already off in the baseline are reported without a rerun (their
+//! deltas are zero). Every run shares one indicator computation since the
+//! filters do not change indicator periods.
+
+use serde::Serialize;
+
+use common::{BacktestParameters, Bar, PerformanceMetrics, VwapMode};
+
+use super::indicators_for;
+use crate::engine::BacktestEngine;
+
+/// Entry filter that can be switched off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    Vwap,
+    SmaTrend,
+    AnchoredVwap,
+    Bollinger,
+    Edge,
+    Momentum,
+    RocFloor,
+    Adx,
+    Obv,
+    Keltner,
+}
+
+impl Filter {
+    pub const ALL: [Filter; 10] = [
+        Filter::Vwap,
+        Filter::SmaTrend,
+        Filter::AnchoredVwap,
+        Filter::Bollinger,
+        Filter::Edge,
+        Filter::Momentum,
+        Filter::RocFloor,
+        Filter::Adx,
+        Filter::Obv,
+        Filter::Keltner,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Filter::Vwap => "VWAP",
+            Filter::SmaTrend => "SMA trend",
+            Filter::AnchoredVwap => "Anchored VWAP",
+            Filter::Bollinger => "Bollinger",
+            Filter::Edge => "Expected edge",
+            Filter::Momentum => "Momentum",
+            Filter::RocFloor => "ROC floor",
+            Filter::Adx => "ADX",
+            Filter::Obv => "OBV slope",
+            Filter::Keltner => "Keltner",
+        }
+    }
+
+    pub fn is_enabled(self, params: &BacktestParameters) -> bool {
+        match self {
+            Filter::Vwap => params.strategy.vwap_mode != VwapMode::Disabled,
+            Filter::SmaTrend => params.strategy.sma_filter_enabled,
+            Filter::AnchoredVwap => params.strategy.anchored_vwap_filter_enabled,
+            Filter::Bollinger => params.strategy.bb_filter_enabled,
+            Filter::Edge => params.risk.edge_filter_enabled,
+            Filter::Momentum => params.strategy.momentum_filter_enabled,
+            Filter::RocFloor => params.strategy.momentum_min_pct.is_some(),
+            Filter::Adx => params.strategy.adx_filter_enabled,
+            Filter::Obv => params.strategy.obv_filter_enabled,
+            Filter::Keltner => params.strategy.keltner_filter_enabled,
+        }
+    }
+
+    fn disable(self, params: &mut BacktestParameters) {
+        match self {
+            Filter::Vwap => params.strategy.vwap_mode = VwapMode::Disabled,
+            Filter::SmaTrend => params.strategy.sma_filter_enabled = false,
+            Filter::AnchoredVwap => params.strategy.anchored_vwap_filter_enabled = false,
+            Filter::Bollinger => params.strategy.bb_filter_enabled = false,
+            Filter::Edge => params.risk.edge_filter_enabled = false,
+            Filter::Momentum => params.strategy.momentum_filter_enabled = false,
+            Filter::RocFloor => params.strategy.momentum_min_pct = None,
+            Filter::Adx => params.strategy.adx_filter_enabled = false,
+            Filter::Obv => params.strategy.obv_filter_enabled = false,
+            Filter::Keltner => params.strategy.keltner_filter_enabled = false,
+        }
+    }
+}
+
+/// Metrics of one ablation run and their change versus the baseline
+#[derive(Debug, Clone, Serialize)]
+pub struct AblationRow {
+    /// Filter removed for this run; `None` for the baseline
+    pub removed: Option<Filter>,
+    /// Whether the filter was on in the baseline (always true for the baseline)
+    pub enabled_in_baseline: bool,
+    pub metrics: PerformanceMetrics,
+    pub sharpe_delta: f64,
+    pub return_delta_pct: f64,
+    pub max_drawdown_delta: f64,
+    pub trades_delta: i64,
+}
+
+impl AblationRow {
+    fn new(
+        removed: Option<Filter>,
+        enabled_in_baseline: bool,
+        metrics: PerformanceMetrics,
+        baseline: &PerformanceMetrics,
+    ) -> Self {
+        Self {
+            removed,
+            enabled_in_baseline,
+            sharpe_delta: metrics.sharpe_ratio - baseline.sharpe_ratio,
+            return_delta_pct: metrics.total_return_pct - baseline.total_return_pct,
+            max_drawdown_delta: metrics.max_drawdown - baseline.max_drawdown,
+            trades_delta: metrics.total_trades as i64 - baseline.total_trades as i64,
+            metrics,
+        }
+    }
+}
+
+/// Baseline plus one run per filter in [`Filter::ALL`] order
+pub fn ablation_study(params: &BacktestParameters, bars: &[Bar]) -> Vec<AblationRow> {
+    let indicators = indicators_for(bars, params);
+    let run = |params: BacktestParameters| {
+        BacktestEngine::new(params)
+            .run_with_indicators(bars, None, &indicators)
+            .metrics
+    };
+
+    let baseline = run(params.clone());
+    let mut rows = vec![AblationRow::new(None, true, baseline.clone(), &baseline)];
+
+    for filter in Filter::ALL {
+        let enabled = filter.is_enabled(params);
+        let metrics = if enabled {
+            let mut ablated = params.clone();
+            filter.disable(&mut ablated);
+            run(ablated)
+        } else {
+            baseline.clone()
+        };
+        rows.push(AblationRow::new(Some(filter), enabled, metrics, &baseline));
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+
+    #[test]
+    fn test_one_row_per_filter_and_baseline_matches() {
+        let bars = generate_synthetic_bars_seeded(400, 50.0, 8);
+        let params = BacktestParameters::default().without_short();
+
+        let rows = ablation_study(&params, &bars);
+        assert_eq!(rows.len(), Filter::ALL.len() + 1);
+        assert!(rows[0].removed.is_none());
+        for (row, filter) in rows[1..].iter().zip(Filter::ALL) {
+            assert_eq!(row.removed, Some(filter));
+        }
+
+        let direct = BacktestEngine::new(params).run(&bars, None).metrics;
+        let baseline = &rows[0].metrics;
+        assert_eq!(baseline.total_trades, direct.total_trades);
+        assert_eq!(baseline.sharpe_ratio, direct.sharpe_ratio);
+        assert_eq!(baseline.total_return_pct, direct.total_return_pct);
+        assert_eq!(rows[0].sharpe_delta, 0.0);
+    }
+
+    #[test]
+    fn test_disabled_filters_are_not_rerun() {
+        let bars = generate_synthetic_bars_seeded(400, 50.0, 8);
+        let params = BacktestParameters::default().without_short();
+
+        let rows = ablation_study(&params, &bars);
+        let row = |f: Filter| rows.iter().find(|r| r.removed == Some(f)).unwrap();
+
+        // Bollinger and edge filters are off by default: nothing to remove
+        assert!(!row(Filter::Bollinger).enabled_in_baseline);
+        assert_eq!(row(Filter::Bollinger).trades_delta, 0);
+        assert!(row(Filter::Vwap).enabled_in_baseline);
+        assert!(row(Filter::SmaTrend).metrics.total_trades > 0);
+    }
+}
@@ -0,0 +1,238 @@
+//! Random-entry baseline: does the strategy's timing beat chance?
+//!
+//! Each random run enters long on uniformly drawn bars, as many as the real
+//! strategy entered, and otherwise follows the same exit rules, stops and
+//! guards. The real result's percentile within the random distribution works
+//! as a permutation test. Hedging is switched off for the real run and the
+//! random runs alike so only long entry timing is compared.
+
+use rand::rngs::StdRng;
+use rand::seq::index::sample;
+use rand::SeedableRng;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use common::{BacktestParameters, Bar, PerformanceMetrics, Side};
+
+use super::indicators_for;
+use crate::engine::BacktestEngine;
+use crate::limits::{JobCompletion, JobLimits};
+
+/// Outcome of one random-entry run
+#[derive(Debug, Clone, Serialize)]
+pub struct BaselineRun {
+    pub final_equity: f64,
+    pub sharpe_ratio: f64,
+    pub total_trades: u32,
+}
+
+/// Real strategy result placed within the random-entry distribution
+#[derive(Debug, Clone, Serialize)]
+pub struct RandomBaseline {
+    pub strategy: PerformanceMetrics,
+    pub strategy_final_equity: f64,
+    /// Long entries of the real run; each random run draws this many bars
+    pub entries: usize,
+    pub runs: Vec<BaselineRun>,
+    /// Share of random runs the strategy beat (ties count half), 0-100
+    pub equity_percentile: f64,
+    pub sharpe_percentile: f64,
+    /// Probability of a random run doing at least as well on final equity
+    pub p_value: f64,
+    /// Random runs completed out of those asked for
+    #[serde(flatten)]
+    pub completion: JobCompletion,
+}
+
+/// Percentile of `value` within `samples`, counting ties as half
+fn percentile(value: f64, samples: impl Iterator<Item = f64>) -> f64 {
+    let (mut below, mut ties, mut n) = (0.0, 0.0, 0.0);
+    for sample in samples {
+        n += 1.0;
+        if sample < value {
+            below += 1.0;
+        } else if sample == value {
+            ties += 1.0;
+        }
+    }
+    if n == 0.0 {
+        return 0.0;
+    }
+    (below + ties / 2.0) / n * 100.0
+}
+
+/// Compare the strategy with `n_runs` random-entry runs on the same bars.
+///
+/// Run `i` draws its entry bars from `seed + i`, so results are reproducible
+/// regardless of how rayon schedules the runs.
+pub fn random_baseline(
+    params: &BacktestParameters,
+    bars: &[Bar],
+    n_runs: usize,
+    seed: u64,
+) -> RandomBaseline {
+    random_baseline_with_limits(params, bars, n_runs, seed, JobLimits::default())
+}
+
+/// `random_baseline` within `limits`: once one is hit the strategy is
+/// placed within the random runs completed so far
+pub fn random_baseline_with_limits(
+    params: &BacktestParameters,
+    bars: &[Bar],
+    n_runs: usize,
+    seed: u64,
+    limits: JobLimits,
+) -> RandomBaseline {
+    let params = params.clone().without_short();
+    let indicators = indicators_for(bars, &params);
+
+    let real = BacktestEngine::new(params.clone()).run_with_indicators(bars, None, &indicators);
+    let entries = real.trades.iter().filter(|t| t.side == Side::Sell).count();
+
+    // Entries can only fire once the engine's warmup has passed
+    let warmup = params.warmup_bars();
+    let candidates = bars.len().saturating_sub(warmup);
+    let draws = entries.min(candidates);
+
+    let budget = limits.start();
+    let runs: Vec<BaselineRun> = (0..n_runs)
+        .into_par_iter()
+        .filter(|_| budget.claim())
+        .map(|run| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(run as u64));
+            let mut schedule = vec![false; bars.len()];
+            for offset in sample(&mut rng, candidates, draws) {
+                schedule[warmup + offset] = true;
+            }
+            let result = BacktestEngine::new(params.clone())
+                .with_entry_schedule(schedule)
+                .run_with_indicators(bars, None, &indicators);
+            BaselineRun {
+                final_equity: result.final_equity,
+                sharpe_ratio: result.metrics.sharpe_ratio,
+                total_trades: result.metrics.total_trades,
+            }
+        })
+        .collect();
+
+    let at_least_as_good = runs
+        .iter()
+        .filter(|r| r.final_equity >= real.final_equity)
+        .count();
+
+    RandomBaseline {
+        equity_percentile: percentile(real.final_equity, runs.iter().map(|r| r.final_equity)),
+        sharpe_percentile: percentile(
+            real.metrics.sharpe_ratio,
+            runs.iter().map(|r| r.sharpe_ratio),
+        ),
+        p_value: (at_least_as_good + 1) as f64 / (runs.len() + 1) as f64,
+        completion: budget.completion(runs.len(), n_runs),
+        strategy_final_equity: real.final_equity,
+        strategy: real.metrics,
+        entries,
+        runs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+    use chrono::{Duration, TimeZone, Utc};
+
+    /// Repeating 5-bar cycle: a sharp dip, a jump that recovers more than
+    /// the dip, then a slow bleed. Only the dip is oversold and only the
+    /// jump is overbought, so the strategy buys each dip and sells the jump.
+    fn dip_and_rip_bars(cycles: usize) -> Vec<Bar> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 16, 0, 0).unwrap();
+        let moves = [-0.03, 0.06, -0.004, -0.004, -0.004];
+        let mut price = 100.0;
+        (0..cycles * moves.len())
+            .map(|i| {
+                let open = price;
+                price *= 1.0 + moves[i % moves.len()];
+                Bar {
+                    timestamp: start + Duration::days(i as i64),
+                    open,
+                    high: open.max(price) * 1.001,
+                    low: open.min(price) * 0.999,
+                    close: price,
+                    volume: 1_000_000,
+                    vwap: None,
+                    halted: false,
+                }
+            })
+            .collect()
+    }
+
+    fn params() -> BacktestParameters {
+        BacktestParameters::default()
+            .without_vwap_filter()
+            .with_sma_period(1)
+    }
+
+    #[test]
+    fn test_perfect_timing_beats_random_entries() {
+        let bars = dip_and_rip_bars(40);
+        let baseline = random_baseline(&params(), &bars, 200, 7);
+
+        assert!(baseline.entries > 10);
+        assert_eq!(baseline.runs.len(), 200);
+        assert!(
+            baseline.equity_percentile >= 99.0,
+            "percentile was {}",
+            baseline.equity_percentile
+        );
+        assert!(baseline.p_value < 0.02);
+
+        // Seeded: the distribution is reproducible
+        let again = random_baseline(&params(), &bars, 200, 7);
+        assert_eq!(again.equity_percentile, baseline.equity_percentile);
+        assert_eq!(again.runs[17].final_equity, baseline.runs[17].final_equity);
+    }
+
+    #[test]
+    fn test_noise_lands_mid_distribution() {
+        // Averaged over a few noise series, real timing is no better than
+        // chance
+        let seeds = [1, 2, 3, 4, 5, 6];
+        let mean = seeds
+            .iter()
+            .map(|&seed| {
+                let bars = generate_synthetic_bars_seeded(500, 50.0, seed);
+                random_baseline(&params(), &bars, 100, seed).equity_percentile
+            })
+            .sum::<f64>()
+            / seeds.len() as f64;
+
+        assert!(
+            (20.0..=80.0).contains(&mean),
+            "mean percentile was {}",
+            mean
+        );
+    }
+
+    #[test]
+    fn test_run_budget_truncates_baseline() {
+        let bars = dip_and_rip_bars(40);
+        let limits = JobLimits::default().with_max_backtests(25);
+        let baseline = random_baseline_with_limits(&params(), &bars, 200, 7, limits);
+
+        assert_eq!(baseline.runs.len(), 25);
+        assert_eq!(
+            baseline.completion,
+            JobCompletion {
+                truncated: true,
+                completed: 25,
+                planned: 200
+            }
+        );
+        assert!(baseline.p_value >= 1.0 / 26.0);
+        assert!(
+            !random_baseline(&params(), &bars, 20, 7)
+                .completion
+                .truncated
+        );
+    }
+}
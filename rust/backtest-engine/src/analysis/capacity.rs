@@ -0,0 +1,203 @@
+//! Capacity: how much capital the strategy can deploy before costs eat it
+//!
+//! The strategy is rerun at multiples of its initial capital with realistic
+//! execution and liquidity-aware sizing switched on, whatever the parameters
+//! say, so larger accounts pay more market impact and get capped by volume.
+//! Other execution settings (impact factor, participation limit) are kept.
+//! Every level draws its slippage from the same seed, so the levels differ
+//! only through order size.
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use common::{BacktestError, BacktestParameters, Bar, Result};
+
+use super::indicators_for;
+use crate::engine::BacktestEngine;
+
+/// Outcome at one capital level
+#[derive(Debug, Clone, Serialize)]
+pub struct CapacityLevel {
+    pub multiplier: f64,
+    pub initial_capital: f64,
+    pub cagr: f64,
+    pub total_return_pct: f64,
+    pub sharpe_ratio: f64,
+    pub total_trades: u32,
+    pub liquidity_capped_entries: u32,
+    pub avg_round_trip_cost_bps: f64,
+    /// Drop in CAGR versus the base (smallest) level, as a % of the base
+    /// CAGR's magnitude; any drop from a zero base counts as 100%
+    pub cagr_degradation_pct: f64,
+}
+
+/// Capital levels in ascending order and where the edge gives out
+#[derive(Debug, Clone, Serialize)]
+pub struct CapacityReport {
+    pub base_capital: f64,
+    pub degradation_threshold_pct: f64,
+    pub levels: Vec<CapacityLevel>,
+    /// Smallest capital whose CAGR degraded by more than the threshold
+    pub degraded_at: Option<f64>,
+    /// Largest capital tested before `degraded_at` (the largest tested when
+    /// no level degraded)
+    pub capacity: f64,
+}
+
+fn degradation_pct(base: f64, cagr: f64) -> f64 {
+    if base != 0.0 {
+        (base - cagr) / base.abs() * 100.0
+    } else if cagr < base {
+        100.0
+    } else {
+        0.0
+    }
+}
+
+/// Rerun the strategy at `initial_capital * m` for each of `multipliers`
+/// (sorted, duplicates dropped). Slippage draws come from `seed`.
+pub fn capacity_study(
+    params: &BacktestParameters,
+    bars: &[Bar],
+    multipliers: &[f64],
+    degradation_threshold_pct: f64,
+    seed: u64,
+) -> Result<CapacityReport> {
+    if multipliers.is_empty() {
+        return Err(BacktestError::InvalidParameter(
+            "capacity study needs at least one capital multiplier".to_string(),
+        ));
+    }
+    if let Some(m) = multipliers.iter().find(|m| !(**m > 0.0 && m.is_finite())) {
+        return Err(BacktestError::InvalidParameter(format!(
+            "capital multipliers must be positive, got {}",
+            m
+        )));
+    }
+    let mut multipliers = multipliers.to_vec();
+    multipliers.sort_by(f64::total_cmp);
+    multipliers.dedup();
+
+    let mut params = params.clone();
+    params.execution.simulation.enabled = true;
+    params.execution.slippage_pct = 0.0;
+    params.risk.liquidity_aware_sizing = true;
+    let indicators = indicators_for(bars, &params);
+
+    let mut levels: Vec<CapacityLevel> = multipliers
+        .par_iter()
+        .map(|&multiplier| {
+            let capital = params.initial_capital * multiplier;
+            let result = BacktestEngine::new(params.clone().with_capital(capital))
+                .with_execution_seed(seed)
+                .run_with_indicators(bars, None, &indicators);
+            CapacityLevel {
+                multiplier,
+                initial_capital: capital,
+                cagr: result.metrics.cagr,
+                total_return_pct: result.metrics.total_return_pct,
+                sharpe_ratio: result.metrics.sharpe_ratio,
+                total_trades: result.metrics.total_trades,
+                liquidity_capped_entries: result.metrics.liquidity_capped_entries,
+                avg_round_trip_cost_bps: result.metrics.avg_round_trip_cost_bps,
+                cagr_degradation_pct: 0.0,
+            }
+        })
+        .collect();
+
+    let base_cagr = levels[0].cagr;
+    for level in &mut levels {
+        level.cagr_degradation_pct = degradation_pct(base_cagr, level.cagr);
+    }
+    let degraded = levels
+        .iter()
+        .position(|l| l.cagr_degradation_pct > degradation_threshold_pct);
+    let capacity = match degraded {
+        Some(0) | None => levels[levels.len() - 1].initial_capital,
+        Some(i) => levels[i - 1].initial_capital,
+    };
+
+    Ok(CapacityReport {
+        base_capital: levels[0].initial_capital,
+        degradation_threshold_pct,
+        degraded_at: degraded.map(|i| levels[i].initial_capital),
+        capacity,
+        levels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{generate_synthetic_bars_seeded, ScenarioBuilder};
+    use common::VwapMode;
+
+    const MULTIPLIERS: [f64; 5] = [1.0, 2.0, 5.0, 10.0, 20.0];
+
+    #[test]
+    fn test_returns_scale_without_impact() {
+        let bars = generate_synthetic_bars_seeded(300, 50.0, 11);
+        let mut params = BacktestParameters::default().without_short();
+        params.execution.simulation.market_impact_enabled = false;
+
+        let report = capacity_study(&params, &bars, &MULTIPLIERS, 25.0, 3).unwrap();
+        assert_eq!(report.levels.len(), 5);
+        assert_eq!(report.degraded_at, None);
+        assert_eq!(report.capacity, params.initial_capital * 20.0);
+
+        // Only whole-share rounding separates the levels
+        let base = &report.levels[0];
+        assert!(base.total_trades > 0);
+        for level in &report.levels {
+            assert_eq!(level.total_trades, base.total_trades);
+            assert_eq!(level.liquidity_capped_entries, 0);
+            assert!(
+                (level.total_return_pct - base.total_return_pct).abs() < 0.05,
+                "{}x: {} vs {}",
+                level.multiplier,
+                level.total_return_pct,
+                base.total_return_pct
+            );
+        }
+
+        let again = capacity_study(&params, &bars, &MULTIPLIERS, 25.0, 3).unwrap();
+        assert_eq!(again.levels[3].cagr, report.levels[3].cagr);
+    }
+
+    #[test]
+    fn test_low_volume_degrades_with_capital() {
+        // Profitable dips on a thin tape
+        let mut scenario = ScenarioBuilder::new(160, 100.0);
+        for day in (25..150).step_by(20) {
+            scenario = scenario.oversold(day).overbought(day + 2);
+        }
+        let mut bars = scenario.build();
+        for bar in &mut bars {
+            // Filled at the close rather than the bar's VWAP
+            bar.volume = 40_000;
+            bar.vwap = None;
+        }
+        let mut params = BacktestParameters::default()
+            .without_short()
+            .with_sma_period(1);
+        params.strategy.vwap_mode = VwapMode::Disabled;
+
+        let report = capacity_study(&params, &bars, &MULTIPLIERS, 25.0, 3).unwrap();
+        assert!(report.levels[0].cagr > 0.0);
+        for pair in report.levels.windows(2) {
+            assert!(
+                pair[1].cagr < pair[0].cagr,
+                "{}x: {} !< {}",
+                pair[1].multiplier,
+                pair[1].cagr,
+                pair[0].cagr
+            );
+        }
+        assert!(report.levels[4].liquidity_capped_entries > 0);
+        let degraded_at = report.degraded_at.unwrap();
+        assert!(report.capacity < degraded_at);
+
+        assert!(capacity_study(&params, &bars, &[], 25.0, 3).is_err());
+        assert!(capacity_study(&params, &bars, &[1.0, -2.0], 25.0, 3).is_err());
+    }
+}
@@ -0,0 +1,182 @@
+//! How well a synthesized inverse series tracks the real inverse ETF
+//!
+//! A synthetic `-leverage`x series rebalances daily: each bar it moves by
+//! `-leverage` times the primary's return. A real leveraged inverse ETF
+//! also pays fees and financing and suffers path-dependent decay, so the
+//! two drift apart over time. [`inverse_synthesis_error`] starts the
+//! synthetic series at the real series' first close and measures the gap.
+//!
+//! Bars are matched by timestamp; bars present in only one series are
+//! skipped, and returns are taken between consecutive matched bars.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use common::{BacktestError, Bar, Result};
+
+/// Drift (%) over the whole series past which the synthetic series should
+/// not stand in for the real one
+pub const SYNTHESIS_DRIFT_WARN_PCT: f64 = 5.0;
+
+/// Deviation of the synthetic inverse series from the real one
+#[derive(Debug, Clone, Serialize)]
+pub struct SynthesisErrorReport {
+    pub leverage: f64,
+    /// Bars present in both series
+    pub matched_bars: usize,
+    /// Bars of either series without a counterpart
+    pub unmatched_bars: usize,
+    /// Standard deviation of the per-bar return differences (%)
+    pub tracking_error_pct: f64,
+    /// Synthetic final close above the real one (% of the real close)
+    pub cumulative_drift_pct: f64,
+    /// Largest absolute per-bar return difference (%) and its bar
+    pub worst_divergence_pct: f64,
+    pub worst_divergence_at: Option<DateTime<Utc>>,
+    pub drift_threshold_pct: f64,
+    /// `|cumulative_drift_pct|` exceeds `drift_threshold_pct`
+    pub drift_warning: bool,
+}
+
+impl SynthesisErrorReport {
+    /// Re-judge the drift against `threshold_pct` instead of
+    /// [`SYNTHESIS_DRIFT_WARN_PCT`]
+    pub fn with_drift_threshold(mut self, threshold_pct: f64) -> Self {
+        self.drift_threshold_pct = threshold_pct;
+        self.drift_warning = self.cumulative_drift_pct.abs() > threshold_pct;
+        self
+    }
+}
+
+/// Compare `-leverage`x daily returns of `primary_bars` with
+/// `real_inverse_bars`
+pub fn inverse_synthesis_error(
+    primary_bars: &[Bar],
+    real_inverse_bars: &[Bar],
+    leverage: f64,
+) -> Result<SynthesisErrorReport> {
+    if !(leverage.is_finite() && leverage > 0.0) {
+        return Err(BacktestError::InvalidParameter(format!(
+            "leverage must be positive, got {}",
+            leverage
+        )));
+    }
+
+    let mut pairs: Vec<(DateTime<Utc>, f64, f64)> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < primary_bars.len() && j < real_inverse_bars.len() {
+        let (primary, inverse) = (&primary_bars[i], &real_inverse_bars[j]);
+        match primary.timestamp.cmp(&inverse.timestamp) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                pairs.push((primary.timestamp, primary.close, inverse.close));
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    if pairs.len() < 2 {
+        return Err(BacktestError::InsufficientData {
+            required: 2,
+            actual: pairs.len(),
+        });
+    }
+    if let Some((at, _, _)) = pairs.iter().find(|(_, p, inv)| !(*p > 0.0 && *inv > 0.0)) {
+        return Err(BacktestError::InvalidParameter(format!(
+            "non-positive close at {}",
+            at
+        )));
+    }
+
+    let mut synthetic = pairs[0].2;
+    let mut differences = Vec::with_capacity(pairs.len() - 1);
+    let mut worst: Option<(f64, DateTime<Utc>)> = None;
+    for w in pairs.windows(2) {
+        let ((_, p0, inv0), (at, p1, inv1)) = (w[0], w[1]);
+        let synthetic_return = -leverage * (p1 / p0 - 1.0);
+        let real_return = inv1 / inv0 - 1.0;
+        synthetic *= (1.0 + synthetic_return).max(0.0);
+
+        let difference = (synthetic_return - real_return) * 100.0;
+        if worst.is_none_or(|(w, _)| difference.abs() > w) {
+            worst = Some((difference.abs(), at));
+        }
+        differences.push(difference);
+    }
+
+    let mean = differences.iter().sum::<f64>() / differences.len() as f64;
+    let variance =
+        differences.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / differences.len() as f64;
+    let real_final = pairs[pairs.len() - 1].2;
+    let report = SynthesisErrorReport {
+        leverage,
+        matched_bars: pairs.len(),
+        unmatched_bars: primary_bars.len() + real_inverse_bars.len() - 2 * pairs.len(),
+        tracking_error_pct: variance.sqrt(),
+        cumulative_drift_pct: (synthetic / real_final - 1.0) * 100.0,
+        worst_divergence_pct: worst.map_or(0.0, |(w, _)| w),
+        worst_divergence_at: worst.map(|(_, at)| at),
+        drift_threshold_pct: SYNTHESIS_DRIFT_WARN_PCT,
+        drift_warning: false,
+    };
+    Ok(report.with_drift_threshold(SYNTHESIS_DRIFT_WARN_PCT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+
+    /// An inverse series paying `-3x` each bar, less `decay` per bar
+    fn inverse_of(primary: &[Bar], decay: f64) -> Vec<Bar> {
+        let mut close = 50.0;
+        let mut inverse = Vec::with_capacity(primary.len());
+        for (k, bar) in primary.iter().enumerate() {
+            if k > 0 {
+                let r = bar.close / primary[k - 1].close - 1.0;
+                close *= (1.0 - 3.0 * r) * (1.0 - decay);
+            }
+            inverse.push(Bar {
+                open: close,
+                high: close,
+                low: close,
+                close,
+                ..bar.clone()
+            });
+        }
+        inverse
+    }
+
+    #[test]
+    fn test_exact_inverse_tracks() {
+        let primary = generate_synthetic_bars_seeded(250, 100.0, 11);
+        let report = inverse_synthesis_error(&primary, &inverse_of(&primary, 0.0), 3.0).unwrap();
+
+        assert_eq!(report.matched_bars, 250);
+        assert_eq!(report.unmatched_bars, 0);
+        assert!(report.tracking_error_pct < 1e-9);
+        assert!(report.cumulative_drift_pct.abs() < 1e-9);
+        assert!(report.worst_divergence_pct < 1e-9);
+        assert!(!report.drift_warning);
+    }
+
+    #[test]
+    fn test_decay_is_reported_as_drift() {
+        let primary = generate_synthetic_bars_seeded(250, 100.0, 11);
+        let mut inverse = inverse_of(&primary, 0.001);
+        inverse.remove(100);
+        let report = inverse_synthesis_error(&primary, &inverse, 3.0).unwrap();
+
+        assert_eq!(report.unmatched_bars, 1);
+        assert!(report.tracking_error_pct > 0.0);
+        assert!(report.worst_divergence_pct > 0.0);
+        // The real series loses 0.1% a bar that the synthetic one keeps
+        assert!(report.cumulative_drift_pct > 20.0);
+        assert!(report.drift_warning);
+        assert!(!report.with_drift_threshold(100.0).drift_warning);
+
+        assert!(inverse_synthesis_error(&primary, &inverse, 0.0).is_err());
+        assert!(inverse_synthesis_error(&primary, &inverse[..1], 3.0).is_err());
+    }
+}
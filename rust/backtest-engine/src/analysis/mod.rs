@@ -0,0 +1,22 @@
+//! Post-run analysis tools built on top of the engine
+//!
+//! Everything here consumes finished results and/or reruns the engine; nothing
+//! in this module feeds back into a live simulation.
+
+pub mod ablation;
+pub mod baseline;
+pub mod capacity;
+pub mod inverse_fit;
+pub mod opportunity;
+pub mod reconstruct;
+pub mod regime;
+pub mod replay;
+
+pub use ablation::{ablation_study, AblationRow, Filter};
+pub use baseline::{random_baseline, random_baseline_with_limits, BaselineRun, RandomBaseline};
+pub use capacity::{capacity_study, CapacityLevel, CapacityReport};
+pub use inverse_fit::{inverse_synthesis_error, SynthesisErrorReport, SYNTHESIS_DRIFT_WARN_PCT};
+pub use opportunity::{opportunity_cost, MissedEntry, MissedEntryStatus, OpportunityReport};
+pub use reconstruct::{reconstruct_equity, reconstructed_metrics, ReconstructionResolution};
+pub use regime::{regime_breakdown, RegimeBreakdown, RegimeStats, VolRegime};
+pub use replay::{indicators_for, replay_exit, ReplayedExit};
@@ -0,0 +1,236 @@
+//! Counterfactual cost of skipped entries
+//!
+//! Every Buy signal the engine suppressed is replayed as if it had been taken,
+//! using the same stop and exit rules. The result is hypothetical: it ignores
+//! that taking the trade would have changed cash and every later decision.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use common::{BacktestParameters, BacktestResult, Bar, Side, SignalType};
+
+use super::replay::{indicators_for, replay_exit};
+use crate::signals::SignalGenerator;
+
+/// Label attached to every opportunity report
+pub const COUNTERFACTUAL_NOTE: &str =
+    "COUNTERFACTUAL: hypothetical P&L of entries that were not taken; not part of the backtest result";
+
+/// How a replayed skipped entry is accounted for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissedEntryStatus {
+    /// Counted in the foregone P&L
+    Counted,
+    /// The real run entered later within the same hypothetical holding
+    /// period, so the opportunity was not actually lost
+    CapturedLater,
+}
+
+/// One skipped entry replayed with the standard exit rules
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissedEntry {
+    pub timestamp: DateTime<Utc>,
+    pub price: f64,
+    /// Why the engine skipped the entry
+    pub skip_reason: String,
+    pub quantity: f64,
+    pub exit_timestamp: DateTime<Utc>,
+    pub exit_price: f64,
+    pub exit_reason: String,
+    pub hypothetical_pnl: f64,
+    pub hypothetical_pnl_pct: f64,
+    pub status: MissedEntryStatus,
+}
+
+/// Opportunity cost of all skipped entries in a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpportunityReport {
+    pub note: String,
+    /// Suppressed Buy signals found in the result
+    pub skipped_signals: usize,
+    /// Skipped signals that fell inside an earlier replayed trade (same dip)
+    pub covered_signals: usize,
+    pub missed_entries: Vec<MissedEntry>,
+    /// Sum of hypothetical P&L over entries with status `Counted`
+    pub foregone_pnl: f64,
+}
+
+impl OpportunityReport {
+    pub fn counted(&self) -> impl Iterator<Item = &MissedEntry> {
+        self.missed_entries
+            .iter()
+            .filter(|m| m.status == MissedEntryStatus::Counted)
+    }
+}
+
+/// Replay every suppressed Buy signal in `result` against `bars`.
+///
+/// `params` must be the parameters that produced `result` so the replay uses
+/// the same indicators and exit rules. Consecutive suppressed bars of the same
+/// dip are replayed once, and entries the real run took shortly afterwards are
+/// reported as `CapturedLater` rather than counted.
+pub fn opportunity_cost(
+    result: &BacktestResult,
+    bars: &[Bar],
+    params: &BacktestParameters,
+) -> OpportunityReport {
+    let mut skipped: Vec<_> = result
+        .suppressed_signals
+        .iter()
+        .filter(|s| s.signal_type == SignalType::Buy)
+        .collect();
+    skipped.sort_by_key(|s| s.timestamp);
+
+    let mut report = OpportunityReport {
+        note: COUNTERFACTUAL_NOTE.to_string(),
+        skipped_signals: skipped.len(),
+        covered_signals: 0,
+        missed_entries: Vec::new(),
+        foregone_pnl: 0.0,
+    };
+
+    if bars.is_empty() {
+        return report;
+    }
+
+    let indicators = indicators_for(bars, params);
+    let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+    let mut busy_until: Option<DateTime<Utc>> = None;
+
+    for signal in skipped {
+        if busy_until.is_some_and(|until| signal.timestamp <= until) {
+            report.covered_signals += 1;
+            continue;
+        }
+
+        let Some(entry_index) = bars.iter().position(|b| b.timestamp == signal.timestamp) else {
+            continue;
+        };
+
+        let exit = replay_exit(
+            bars,
+            &indicators,
+            &generator,
+            params,
+            entry_index,
+            signal.price,
+        );
+        let exit_timestamp = bars[exit.exit_index].timestamp;
+        busy_until = Some(exit_timestamp);
+
+        let equity = equity_at(result, signal.timestamp);
+        let quantity =
+            (equity * (1.0 - params.risk.cash_reserve_pct) * params.risk.position_size_pct
+                / signal.price)
+                .floor()
+                .max(0.0);
+        let pnl = (exit.exit_price - signal.price) * quantity - 2.0 * params.execution.commission;
+
+        let captured_later = result.trades.iter().any(|t| {
+            t.side == Side::Sell
+                && t.entry_date > signal.timestamp
+                && t.entry_date <= exit_timestamp
+        });
+        let status = if captured_later {
+            MissedEntryStatus::CapturedLater
+        } else {
+            report.foregone_pnl += pnl;
+            MissedEntryStatus::Counted
+        };
+
+        report.missed_entries.push(MissedEntry {
+            timestamp: signal.timestamp,
+            price: signal.price,
+            skip_reason: signal.reason.clone(),
+            quantity,
+            exit_timestamp,
+            exit_price: exit.exit_price,
+            exit_reason: exit.exit_reason,
+            hypothetical_pnl: pnl,
+            hypothetical_pnl_pct: (exit.exit_price / signal.price - 1.0) * 100.0,
+            status,
+        });
+    }
+
+    report
+}
+
+/// Equity recorded at (or just before) a timestamp
+fn equity_at(result: &BacktestResult, timestamp: DateTime<Utc>) -> f64 {
+    result
+        .equity_curve
+        .iter()
+        .take_while(|(ts, _)| *ts <= timestamp)
+        .last()
+        .map(|(_, equity)| *equity)
+        .unwrap_or(result.initial_capital)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fixtures::{pattern_bars, pattern_params};
+    use crate::engine::BacktestEngine;
+    use common::SuppressedSignal;
+
+    #[test]
+    fn test_limited_run_reports_foregone_trades() {
+        let bars = pattern_bars(64, &[22, 30, 38, 46, 54]);
+        let params = pattern_params().with_trade_limits(Some(2), None);
+        let result = BacktestEngine::new(params.clone()).run(&bars, None);
+
+        let report = opportunity_cost(&result, &bars, &params);
+
+        assert_eq!(report.note, COUNTERFACTUAL_NOTE);
+        assert_eq!(report.skipped_signals, result.suppressed_signals.len());
+        // One replay per skipped dip; the remaining oversold bars are covered
+        assert_eq!(report.counted().count(), 3);
+        assert_eq!(
+            report.covered_signals + report.missed_entries.len(),
+            report.skipped_signals
+        );
+        assert!(report.foregone_pnl > 0.0);
+    }
+
+    #[test]
+    fn test_entry_taken_later_is_not_double_counted() {
+        let bars = pattern_bars(40, &[22]);
+        let params = pattern_params();
+        let mut result = BacktestEngine::new(params.clone()).run(&bars, None);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].entry_date, bars[22].timestamp);
+
+        // Pretend the bar before the real entry was skipped
+        result.suppressed_signals = vec![SuppressedSignal {
+            timestamp: bars[21].timestamp,
+            signal_type: SignalType::Buy,
+            symbol: "TQQQ".to_string(),
+            price: bars[21].close,
+            rsi: 25.0,
+            reason: "insufficient cash".to_string(),
+        }];
+
+        let report = opportunity_cost(&result, &bars, &params);
+
+        assert_eq!(report.missed_entries.len(), 1);
+        assert_eq!(
+            report.missed_entries[0].status,
+            MissedEntryStatus::CapturedLater
+        );
+        assert_eq!(report.foregone_pnl, 0.0);
+    }
+
+    #[test]
+    fn test_no_suppressions_no_cost() {
+        let bars = pattern_bars(40, &[22]);
+        let params = pattern_params();
+        let result = BacktestEngine::new(params.clone()).run(&bars, None);
+
+        let report = opportunity_cost(&result, &bars, &params);
+
+        assert_eq!(report.skipped_signals, 0);
+        assert!(report.missed_entries.is_empty());
+        assert_eq!(report.foregone_pnl, 0.0);
+    }
+}
@@ -0,0 +1,224 @@
+//! Equity rebuilt from a trade list
+//!
+//! For trades without the run that produced them (an old export, another
+//! system), [`reconstruct_equity`] lays out one equity point per weekday
+//! from the first entry to the last exit, plus the dates trades close on,
+//! each holding the equity at the end of its date. The curve is an
+//! approximation:
+//!
+//! - Step mode books each trade's P&L on its exit date, so it has no
+//!   drawdown inside a trade. Its values are equity as it stood after
+//!   exits, and its max drawdown never exceeds that of the original curve
+//!   for trades that did not overlap.
+//! - Smooth mode spreads the P&L linearly over the holding period, which
+//!   smooths losses into the days before the exit rather than showing the
+//!   path the trade took.
+//! - Entry commissions are not part of a trade's P&L and trades still open
+//!   have none, so neither is reflected.
+//! - Annualized figures count every point as a trading day.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use common::{PerformanceMetrics, Trade};
+
+use crate::metrics::MetricsCalculator;
+
+/// When a trade's P&L enters the reconstructed equity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReconstructionResolution {
+    /// All of it on the exit date
+    #[default]
+    Step,
+    /// Linearly from entry to exit
+    Smooth,
+}
+
+impl std::str::FromStr for ReconstructionResolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "step" => Ok(Self::Step),
+            "smooth" => Ok(Self::Smooth),
+            other => Err(format!(
+                "unknown reconstruction '{}' (expected step or smooth)",
+                other
+            )),
+        }
+    }
+}
+
+/// Equity curve implied by the closed trades in `trades`; empty when none
+/// has closed
+pub fn reconstruct_equity(
+    trades: &[Trade],
+    initial_capital: f64,
+    resolution: ReconstructionResolution,
+) -> Vec<(DateTime<Utc>, f64)> {
+    let closed: Vec<(&Trade, DateTime<Utc>)> = trades
+        .iter()
+        .filter_map(|t| t.exit_date.map(|exit| (t, exit)))
+        .collect();
+    let Some(first) = closed.iter().map(|(t, _)| t.entry_date.date_naive()).min() else {
+        return Vec::new();
+    };
+    let last = closed
+        .iter()
+        .map(|(_, exit)| exit.date_naive())
+        .max()
+        .unwrap_or(first);
+
+    let mut dates: Vec<NaiveDate> = first
+        .iter_days()
+        .take_while(|d| *d <= last)
+        .filter(|d| !matches!(d.weekday(), Weekday::Sat | Weekday::Sun))
+        .chain(closed.iter().map(|(_, exit)| exit.date_naive()))
+        .collect();
+    dates.sort();
+    dates.dedup();
+
+    dates
+        .into_iter()
+        .map(|date| {
+            let start = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            let end_of_day = start + Duration::days(1);
+            let pnl: f64 = closed
+                .iter()
+                .map(|(trade, exit)| trade.pnl * booked_share(trade, *exit, end_of_day, resolution))
+                .sum();
+            (start, initial_capital + pnl)
+        })
+        .collect()
+}
+
+/// Metrics of the reconstructed equity and the trades
+pub fn reconstructed_metrics(
+    trades: &[Trade],
+    initial_capital: f64,
+    resolution: ReconstructionResolution,
+) -> PerformanceMetrics {
+    let equity = reconstruct_equity(trades, initial_capital, resolution);
+    MetricsCalculator::calculate(&equity, trades, initial_capital)
+}
+
+/// Share of a trade's P&L booked by `at`
+fn booked_share(
+    trade: &Trade,
+    exit: DateTime<Utc>,
+    at: DateTime<Utc>,
+    resolution: ReconstructionResolution,
+) -> f64 {
+    if at > exit {
+        return 1.0;
+    }
+    match resolution {
+        ReconstructionResolution::Step => 0.0,
+        ReconstructionResolution::Smooth => {
+            let held = (exit - trade.entry_date).num_seconds() as f64;
+            let elapsed = (at - trade.entry_date).num_seconds() as f64;
+            if held > 0.0 {
+                (elapsed / held).clamp(0.0, 1.0)
+            } else {
+                0.0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+    use crate::engine::BacktestEngine;
+    use common::BacktestParameters;
+
+    #[test]
+    fn test_step_reconstruction_matches_run() {
+        let bars = generate_synthetic_bars_seeded(400, 50.0, 7);
+        let params = BacktestParameters::default()
+            .without_vwap_filter()
+            .without_short();
+        let result = BacktestEngine::new(params).run(&bars, None);
+        assert!(result.trades.len() > 5);
+
+        let capital = result.initial_capital;
+        let curve = reconstruct_equity(&result.trades, capital, ReconstructionResolution::Step);
+        assert!((curve.last().unwrap().1 - result.final_equity).abs() < 1e-6);
+        assert!(curve.windows(2).all(|w| w[0].0 < w[1].0));
+
+        let metrics =
+            reconstructed_metrics(&result.trades, capital, ReconstructionResolution::Step);
+        assert!((metrics.total_return_pct - result.metrics.total_return_pct).abs() < 1e-9);
+        assert_eq!(metrics.total_trades, result.metrics.total_trades);
+        // The documented bound: no intratrade drawdown, so never deeper
+        assert!(metrics.max_drawdown <= result.metrics.max_drawdown + 1e-9);
+    }
+
+    #[test]
+    fn test_smooth_spreads_pnl_over_holding() {
+        let day = |d: u32| {
+            NaiveDate::from_ymd_opt(2024, 1, d)
+                .unwrap()
+                .and_hms_opt(16, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        // Tuesday to Friday for +300, then an open trade
+        let trade = Trade {
+            entry_date: day(2),
+            entry_price: 100.0,
+            exit_date: Some(day(5)),
+            exit_price: Some(103.0),
+            quantity: 100.0,
+            side: common::Side::Sell,
+            pnl: 300.0,
+            pnl_pct: 3.0,
+            gross_pnl: 300.0,
+            net_pnl: 300.0,
+            holding_days: 3,
+            entry_reason: String::new(),
+            exit_reason: String::new(),
+            entry_rsi: None,
+            exit_rsi: None,
+            initial_risk: None,
+            r_multiple: None,
+            initial_stop_price: None,
+            stop_spec: None,
+            stop_triggered_at: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            exit_order_id: None,
+            fills: Vec::new(),
+            entry_context: None,
+            exit_context: None,
+            entry_efficiency: None,
+            exit_efficiency: None,
+        };
+        let open = Trade {
+            exit_date: None,
+            exit_price: None,
+            ..trade.clone()
+        };
+        let trades = [trade, open];
+
+        let step = reconstruct_equity(&trades, 1000.0, ReconstructionResolution::Step);
+        let values: Vec<f64> = step.iter().map(|(_, e)| *e).collect();
+        assert_eq!(values, vec![1000.0, 1000.0, 1000.0, 1300.0]);
+
+        // Day ends at 8h, 32h and 56h of the 72h held
+        let smooth = reconstruct_equity(&trades, 1000.0, ReconstructionResolution::Smooth);
+        let expected = [
+            1000.0 + 300.0 / 9.0,
+            1000.0 + 300.0 * 4.0 / 9.0,
+            1000.0 + 300.0 * 7.0 / 9.0,
+        ];
+        for ((_, equity), expected) in smooth.iter().zip(expected) {
+            assert!((equity - expected).abs() < 1e-9);
+        }
+        assert_eq!(smooth[3].1, 1300.0);
+
+        assert!(reconstruct_equity(&[], 1000.0, ReconstructionResolution::Step).is_empty());
+    }
+}
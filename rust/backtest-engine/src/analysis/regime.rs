@@ -0,0 +1,302 @@
+//! Trade results split by the volatility regime at entry
+//!
+//! Trailing realized volatility is computed for every bar of the run and cut
+//! into terciles; each trade lands in the tercile of its entry bar. Trades
+//! are matched to bars by entry timestamp.
+
+use serde::Serialize;
+
+use common::{BacktestResult, Bar, Trade};
+
+/// Realized-volatility tercile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VolRegime {
+    Low,
+    Medium,
+    High,
+}
+
+impl VolRegime {
+    pub const ALL: [VolRegime; 3] = [VolRegime::Low, VolRegime::Medium, VolRegime::High];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            VolRegime::Low => "low vol",
+            VolRegime::Medium => "medium vol",
+            VolRegime::High => "high vol",
+        }
+    }
+}
+
+/// Trade statistics of one regime
+#[derive(Debug, Clone, Serialize)]
+pub struct RegimeStats {
+    pub regime: VolRegime,
+    pub trades: usize,
+    pub win_rate: f64,
+    pub avg_pnl: f64,
+    pub profit_factor: f64,
+}
+
+/// Trades bucketed by the volatility tercile of their entry bar
+#[derive(Debug, Clone, Serialize)]
+pub struct RegimeBreakdown {
+    /// Returns in the trailing volatility window
+    pub lookback: usize,
+    /// Annualized volatility (%) separating low / medium and medium / high
+    pub low_cutoff: f64,
+    pub high_cutoff: f64,
+    pub regimes: Vec<RegimeStats>,
+    /// Trades entered before the window filled or off the given bars
+    pub unclassified: usize,
+}
+
+/// Annualized volatility (%) of the `lookback` log returns ending at each
+/// bar; None until enough bars are available
+pub fn trailing_volatility(bars: &[Bar], lookback: usize) -> Vec<Option<f64>> {
+    let mut vols = vec![None; bars.len()];
+    if lookback < 2 {
+        return vols;
+    }
+
+    let returns: Vec<f64> = bars
+        .windows(2)
+        .map(|w| (w[1].close / w[0].close).ln())
+        .collect();
+    for i in lookback..bars.len() {
+        let window = &returns[i - lookback..i];
+        let mean = window.iter().sum::<f64>() / lookback as f64;
+        let variance =
+            window.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (lookback - 1) as f64;
+        vols[i] = Some(variance.sqrt() * 252_f64.sqrt() * 100.0);
+    }
+    vols
+}
+
+/// Linear-interpolated quantile of sorted values
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = q * (sorted.len() - 1) as f64;
+    let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+    sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+}
+
+fn stats(regime: VolRegime, trades: &[&Trade]) -> RegimeStats {
+    let n = trades.len();
+    let wins: f64 = trades.iter().map(|t| t.pnl).filter(|p| *p > 0.0).sum();
+    let losses: f64 = -trades
+        .iter()
+        .map(|t| t.pnl)
+        .filter(|p| *p < 0.0)
+        .sum::<f64>();
+    let winners = trades.iter().filter(|t| t.pnl > 0.0).count();
+
+    RegimeStats {
+        regime,
+        trades: n,
+        win_rate: if n > 0 {
+            winners as f64 / n as f64 * 100.0
+        } else {
+            0.0
+        },
+        avg_pnl: if n > 0 {
+            trades.iter().map(|t| t.pnl).sum::<f64>() / n as f64
+        } else {
+            0.0
+        },
+        profit_factor: if losses > 0.0 {
+            wins / losses
+        } else if wins > 0.0 {
+            f64::INFINITY
+        } else {
+            0.0
+        },
+    }
+}
+
+/// Split the trades of `result` by the volatility tercile of their entry
+/// bar. `bars` must be the bars the result was produced from.
+pub fn regime_breakdown(result: &BacktestResult, bars: &[Bar], lookback: usize) -> RegimeBreakdown {
+    let vols = trailing_volatility(bars, lookback);
+    let mut sorted: Vec<f64> = vols.iter().flatten().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+
+    let (low_cutoff, high_cutoff) = if sorted.is_empty() {
+        (0.0, 0.0)
+    } else {
+        (quantile(&sorted, 1.0 / 3.0), quantile(&sorted, 2.0 / 3.0))
+    };
+
+    let mut buckets: [Vec<&Trade>; 3] = Default::default();
+    let mut unclassified = 0;
+    for trade in &result.trades {
+        let index = bars.partition_point(|b| b.timestamp < trade.entry_date);
+        let vol = bars
+            .get(index)
+            .filter(|b| b.timestamp == trade.entry_date)
+            .and_then(|_| vols[index]);
+        let Some(vol) = vol else {
+            unclassified += 1;
+            continue;
+        };
+
+        let bucket = if vol <= low_cutoff {
+            0
+        } else if vol <= high_cutoff {
+            1
+        } else {
+            2
+        };
+        buckets[bucket].push(trade);
+    }
+
+    RegimeBreakdown {
+        lookback,
+        low_cutoff,
+        high_cutoff,
+        regimes: VolRegime::ALL
+            .iter()
+            .zip(&buckets)
+            .map(|(regime, trades)| stats(*regime, trades))
+            .collect(),
+        unclassified,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone, Utc};
+    use common::{PerformanceMetrics, Side};
+
+    /// 30 calm, 30 choppy and 30 violent bars: alternating moves of 0.1%,
+    /// 1% and 5%
+    fn segmented_bars() -> Vec<Bar> {
+        let start = Utc.with_ymd_and_hms(2024, 1, 2, 16, 0, 0).unwrap();
+        let mut price = 100.0;
+        (0..90)
+            .map(|i| {
+                let size = [0.001, 0.01, 0.05][i / 30];
+                let open = price;
+                price *= if i % 2 == 0 {
+                    1.0 + size
+                } else {
+                    1.0 / (1.0 + size)
+                };
+                Bar {
+                    timestamp: start + Duration::days(i as i64),
+                    open,
+                    high: open.max(price),
+                    low: open.min(price),
+                    close: price,
+                    volume: 1_000_000,
+                    vwap: None,
+                    halted: false,
+                }
+            })
+            .collect()
+    }
+
+    fn trade(bars: &[Bar], entry: usize, pnl: f64) -> Trade {
+        Trade {
+            entry_date: bars[entry].timestamp,
+            entry_price: bars[entry].close,
+            exit_date: Some(bars[entry + 1].timestamp),
+            exit_price: Some(bars[entry + 1].close),
+            quantity: 10.0,
+            side: Side::Sell,
+            pnl,
+            pnl_pct: 0.0,
+            gross_pnl: pnl,
+            net_pnl: pnl,
+            holding_days: 1,
+            entry_reason: String::new(),
+            exit_reason: String::new(),
+            entry_rsi: None,
+            exit_rsi: None,
+            initial_risk: None,
+            r_multiple: None,
+            initial_stop_price: None,
+            stop_spec: None,
+            stop_triggered_at: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            exit_order_id: None,
+            fills: Vec::new(),
+            entry_context: None,
+            exit_context: None,
+            entry_efficiency: None,
+            exit_efficiency: None,
+        }
+    }
+
+    fn result_with(trades: Vec<Trade>, bars: &[Bar]) -> BacktestResult {
+        BacktestResult {
+            metrics: PerformanceMetrics::default(),
+            metrics_scope: Default::default(),
+            hedge_metrics: None,
+            combined_metrics: None,
+            equity_curve: vec![],
+            drawdown_curve: vec![],
+            heat_curve: vec![],
+            net_exposure_curve: vec![],
+            trades,
+            signals: vec![],
+            fills: vec![],
+            suppressed_signals: vec![],
+            pre_trade_events: vec![],
+            warnings: vec![],
+            halted_bars: 0,
+            start_date: bars[0].timestamp.date_naive(),
+            end_date: bars[bars.len() - 1].timestamp.date_naive(),
+            initial_capital: 10_000.0,
+            final_equity: 10_000.0,
+            sizing_mode: Default::default(),
+            execution_time_ms: 0,
+            manifest: Default::default(),
+            spill: None,
+            approximate: false,
+        }
+    }
+
+    #[test]
+    fn test_planted_trades_land_in_their_regime() {
+        let bars = segmented_bars();
+        let trades = vec![
+            trade(&bars, 2, 5.0), // window not yet full
+            trade(&bars, 15, 10.0),
+            trade(&bars, 20, -5.0),
+            trade(&bars, 45, 30.0),
+            trade(&bars, 75, -40.0),
+            trade(&bars, 80, -20.0),
+            trade(&bars, 85, 30.0),
+        ];
+        let breakdown = regime_breakdown(&result_with(trades, &bars), &bars, 5);
+
+        assert_eq!(breakdown.unclassified, 1);
+        let counts: Vec<usize> = breakdown.regimes.iter().map(|r| r.trades).collect();
+        assert_eq!(counts, vec![2, 1, 3]);
+        assert!(breakdown.low_cutoff < breakdown.high_cutoff);
+
+        let low = &breakdown.regimes[0];
+        assert_eq!(low.win_rate, 50.0);
+        assert_eq!(low.avg_pnl, 2.5);
+        assert_eq!(low.profit_factor, 2.0);
+        let high = &breakdown.regimes[2];
+        assert!((high.win_rate - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(high.profit_factor, 0.5);
+        assert_eq!(breakdown.regimes[1].profit_factor, f64::INFINITY);
+    }
+
+    #[test]
+    fn test_trailing_volatility_warmup() {
+        let bars = segmented_bars();
+        let vols = trailing_volatility(&bars, 5);
+
+        assert!(vols[..5].iter().all(|v| v.is_none()));
+        // Alternating ±0.1% log returns: sample sd of ~0.1%, annualized
+        let calm = vols[10].unwrap();
+        assert!((calm - 0.1095 * 252_f64.sqrt()).abs() < 0.01, "{}", calm);
+        assert!(vols[89].unwrap() > 40.0 * calm);
+    }
+}
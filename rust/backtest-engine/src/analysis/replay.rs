@@ -0,0 +1,212 @@
+//! Single-trade replay using the strategy's standard exit rules
+
+use common::{BacktestParameters, Bar, Position, PositionSide, SignalType, VwapMode};
+
+use crate::indicators::{
+    IndicatorConfig, IndicatorSeries, IndicatorValues, ADX_PERIOD, KELTNER_ATR_PERIOD,
+    KELTNER_EMA_PERIOD, KELTNER_MULTIPLIER, OBV_SLOPE_LOOKBACK,
+};
+use crate::signals::SignalGenerator;
+
+/// Where a replayed trade would have exited
+#[derive(Debug, Clone)]
+pub struct ReplayedExit {
+    pub exit_index: usize,
+    pub exit_price: f64,
+    pub exit_reason: String,
+}
+
+/// Compute the indicator series the engine would use for these parameters
+pub fn indicators_for(bars: &[Bar], params: &BacktestParameters) -> IndicatorSeries {
+    let series = IndicatorSeries::from_bars(bars, &IndicatorConfig::from(params));
+    with_strategy_indicators(series, bars, params)
+}
+
+/// Add to `series`, the [`IndicatorConfig`] indicators of `bars`, those the
+/// strategy in `params` filters on
+pub(crate) fn with_strategy_indicators(
+    series: IndicatorSeries,
+    bars: &[Bar],
+    params: &BacktestParameters,
+) -> IndicatorSeries {
+    let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+    let series = series.with_momentum(&closes, params.strategy.momentum_roc_period);
+    let series = match params.strategy.momentum_min_pct {
+        Some(_) => series.with_lookback_roc(&closes, params.strategy.momentum_lookback),
+        None => series,
+    };
+
+    let series = if params.strategy.vwap_mode != VwapMode::Disabled {
+        series.with_rolling_vwap(bars, params.strategy.vwap_window)
+    } else {
+        series
+    };
+    let series = match params.strategy.sma_slope_filter {
+        Some(filter) => series.with_sma_slope(filter.lookback_bars),
+        None => series,
+    };
+    let series = match params.strategy.zscore_window {
+        Some(window) => series.with_normalization(&closes, window),
+        None => series,
+    };
+    let series = if params.strategy.adx_filter_enabled {
+        series.with_adx(bars, ADX_PERIOD)
+    } else {
+        series
+    };
+    let series = if params.strategy.obv_filter_enabled {
+        series.with_obv(bars, OBV_SLOPE_LOOKBACK)
+    } else {
+        series
+    };
+    let series = if params.strategy.anchored_vwap_filter_enabled {
+        series.with_monthly_vwap(bars)
+    } else {
+        series
+    };
+    let series = if params.strategy.keltner_filter_enabled {
+        series.with_keltner(
+            bars,
+            KELTNER_EMA_PERIOD,
+            KELTNER_ATR_PERIOD,
+            KELTNER_MULTIPLIER,
+        )
+    } else {
+        series
+    };
+    if params.anomaly_rules_enabled() {
+        series.with_anomalies(&closes, params.risk.anomaly_zscore)
+    } else {
+        series
+    }
+}
+
+/// Replay a long entry filled at `entry_price` on `entry_index` until the
+/// stop loss or the signal generator's exit fires.
+///
+/// Mirrors the engine's ordering: the stop is checked first on each bar,
+/// then the RSI/time exits. Trades still open at the end of the data exit at
+/// the last close.
+pub fn replay_exit(
+    bars: &[Bar],
+    indicators: &IndicatorSeries,
+    generator: &SignalGenerator,
+    params: &BacktestParameters,
+    entry_index: usize,
+    entry_price: f64,
+) -> ReplayedExit {
+    let mut position = replay_position(bars, indicators, params, entry_index, entry_price);
+    let stop_loss_price = position.stop_loss_price;
+
+    for i in (entry_index + 1)..bars.len() {
+        let bar = &bars[i];
+
+        if let Some(stop) = stop_loss_price {
+            if bar.close <= stop {
+                return ReplayedExit {
+                    exit_index: i,
+                    exit_price: bar.close,
+                    exit_reason: "stop loss".to_string(),
+                };
+            }
+        }
+
+        let ind_values = exit_values(bars, indicators, i, entry_index);
+        if let Some(signal) = generator.generate(bar, &ind_values, true, Some(&position), false) {
+            if signal.signal_type == SignalType::Sell {
+                return ReplayedExit {
+                    exit_index: i,
+                    exit_price: bar.close,
+                    exit_reason: signal.reason,
+                };
+            }
+        }
+
+        position.current_price = bar.close;
+    }
+
+    let last = bars.len() - 1;
+    ReplayedExit {
+        exit_index: last,
+        exit_price: bars[last].close,
+        exit_reason: "end of data".to_string(),
+    }
+}
+
+/// The long [`replay_exit`] holds: filled at `entry_price` on `entry_index`
+/// and stopped as the risk parameters say
+pub(crate) fn replay_position(
+    bars: &[Bar],
+    indicators: &IndicatorSeries,
+    params: &BacktestParameters,
+    entry_index: usize,
+    entry_price: f64,
+) -> Position {
+    let atr = indicators.atr.get(entry_index).copied().unwrap_or(f64::NAN);
+    let stop_loss_price = params.risk.stop_loss.stop_price(entry_price, atr);
+    Position {
+        symbol: params.strategy.symbol.clone(),
+        quantity: 1.0,
+        avg_entry_price: entry_price,
+        entry_date: bars[entry_index].timestamp,
+        current_price: entry_price,
+        side: PositionSide::Long,
+        stop_loss_price,
+        initial_stop_price: stop_loss_price,
+        stop_spec: None,
+        entry_reason: String::new(),
+        entry_rsi: None,
+        size_multiplier: None,
+        entry_order_id: None,
+        entry_commission: 0.0,
+    }
+}
+
+/// Indicator values the exit of a replayed long sees on bar `i`
+pub(crate) fn exit_values(
+    bars: &[Bar],
+    indicators: &IndicatorSeries,
+    i: usize,
+    entry_index: usize,
+) -> IndicatorValues {
+    let mut values = indicators.get(i);
+    values.vwap = bars[i].vwap.or(values.vwap);
+    values.bars_held = Some(i - entry_index);
+    values.prev_high = Some(bars[i - 1].high);
+    values.prev_low = Some(bars[i - 1].low);
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_bars_with_rsi_pattern;
+
+    #[test]
+    fn test_replay_exits_on_overbought() {
+        let bars = generate_bars_with_rsi_pattern(40, 100.0, &[20], &[]);
+        let params = BacktestParameters::default().without_vwap_filter();
+        let indicators = indicators_for(&bars, &params);
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+
+        let exit = replay_exit(&bars, &indicators, &generator, &params, 20, bars[20].close);
+
+        assert!(exit.exit_index > 20);
+        assert!(exit.exit_reason.contains("take profit"));
+        assert!(exit.exit_price > bars[20].close);
+    }
+
+    #[test]
+    fn test_replay_stop_loss() {
+        // Three consecutive 3% drops breach a 5% stop
+        let bars = generate_bars_with_rsi_pattern(40, 100.0, &[20, 21, 22], &[]);
+        let params = BacktestParameters::default().without_vwap_filter();
+        let indicators = indicators_for(&bars, &params);
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+
+        let exit = replay_exit(&bars, &indicators, &generator, &params, 20, bars[20].close);
+
+        assert_eq!(exit.exit_index, 22);
+        assert_eq!(exit.exit_reason, "stop loss");
+    }
+}
@@ -0,0 +1,288 @@
+//! Parameter set loading from config files
+
+use std::fs;
+use std::path::Path;
+
+use common::{BacktestError, BacktestParameters, Result, SymbolOverrides};
+
+use crate::multi::MultiSymbolConfig;
+
+/// Load parameters from a JSON or TOML file, detecting format from extension.
+///
+/// Fields missing from the file keep their `BacktestParameters::default()`
+/// values, so a config only needs to list what it changes.
+pub fn load_parameters(path: &Path) -> Result<BacktestParameters> {
+    load_parameters_onto(BacktestParameters::default(), path)
+}
+
+/// Load a parameter file over `base` instead of the defaults, e.g. over
+/// parameters read from the environment (which a file takes precedence over)
+pub fn load_parameters_onto(base: BacktestParameters, path: &Path) -> Result<BacktestParameters> {
+    let mut value = read_config(path)?;
+    // Per-symbol tables belong to multi-symbol configs
+    if let serde_json::Value::Object(map) = &mut value {
+        map.remove(OVERRIDES_KEY);
+    }
+    merge_onto(base, value)
+}
+
+/// Load a multi-symbol config: the base parameters plus `[overrides.<SYMBOL>]`
+/// tables. Every merged symbol configuration is validated.
+pub fn load_multi_config(path: &Path) -> Result<MultiSymbolConfig> {
+    let mut value = read_config(path)?;
+    let overrides = match &mut value {
+        serde_json::Value::Object(map) => map.remove(OVERRIDES_KEY),
+        _ => None,
+    };
+
+    let overrides: SymbolOverrides = match overrides {
+        Some(table) => serde_json::from_value(table).map_err(|e| {
+            BacktestError::ConfigError(format!("{}: [{}]: {}", path.display(), OVERRIDES_KEY, e))
+        })?,
+        None => SymbolOverrides::new(),
+    };
+
+    let config = MultiSymbolConfig {
+        base: merge_onto(BacktestParameters::default(), value)?,
+        overrides,
+    };
+    config.validate()?;
+    Ok(config)
+}
+
+/// Table holding per-symbol overrides
+const OVERRIDES_KEY: &str = "overrides";
+
+/// Parse a JSON or TOML file into a generic value
+fn read_config(path: &Path) -> Result<serde_json::Value> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| BacktestError::ConfigError(format!("{}: {}", path.display(), e)))?;
+
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let value: serde_json::Value = match ext.as_str() {
+        "json" => serde_json::from_str(&content)?,
+        "toml" => toml::from_str(&content)
+            .map_err(|e| BacktestError::ConfigError(format!("{}: {}", path.display(), e)))?,
+        _ => {
+            return Err(BacktestError::ConfigError(format!(
+                "Unsupported config format: {}",
+                ext
+            )))
+        }
+    };
+
+    Ok(value)
+}
+
+/// Overlay a partial config object on top of `base`
+fn merge_onto(base: BacktestParameters, value: serde_json::Value) -> Result<BacktestParameters> {
+    let mut merged = serde_json::to_value(base)?;
+    overlay(&mut merged, value);
+    Ok(serde_json::from_value(merged)?)
+}
+
+fn overlay(base: &mut serde_json::Value, patch: serde_json::Value) {
+    match (base, patch) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(&key) {
+                    Some(existing) => overlay(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{StopSpec, VwapMode};
+
+    fn write_temp(name: &str, content: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("bt_config_{}_{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_partial_toml_keeps_defaults() {
+        let path = write_temp(
+            "partial.toml",
+            "rsi_oversold = 25.0\nsma_period = 50\n\n[execution]\nlatency_bars = 1\n",
+        );
+        let params = load_parameters(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(params.strategy.rsi_oversold, 25.0);
+        assert_eq!(params.strategy.sma_period, 50);
+        assert_eq!(params.execution.simulation.latency_bars, 1);
+        assert_eq!(
+            params.strategy.rsi_overbought,
+            BacktestParameters::default().strategy.rsi_overbought
+        );
+        assert!(!params.execution.simulation.enabled);
+    }
+
+    #[test]
+    fn test_vwap_mode_and_legacy_fields() {
+        let load = |name: &str, content: &str| {
+            let path = write_temp(name, content);
+            let params = load_parameters(&path).unwrap();
+            fs::remove_file(&path).ok();
+            params.strategy.vwap_mode
+        };
+
+        assert_eq!(
+            load("vwap_above.toml", "vwap_mode = \"above\"\n"),
+            VwapMode::Above
+        );
+        assert_eq!(
+            load("vwap_band.toml", "vwap_mode = { within_band_pct = 0.5 }\n"),
+            VwapMode::WithinBandPct(0.5)
+        );
+        assert_eq!(
+            load("vwap_default.toml", "sma_period = 50\n"),
+            VwapMode::Below
+        );
+        // Configs written before vwap_mode existed
+        assert_eq!(
+            load("vwap_legacy_off.toml", "vwap_filter_enabled = false\n"),
+            VwapMode::Disabled
+        );
+        assert_eq!(
+            load(
+                "vwap_legacy_inert.json",
+                r#"{"vwap_filter_enabled": true, "vwap_entry_below": false}"#
+            ),
+            VwapMode::Disabled
+        );
+
+        let json = serde_json::to_value(BacktestParameters::default()).unwrap();
+        assert_eq!(json["vwap_mode"], "below");
+        assert!(json.get("vwap_filter_enabled").is_none());
+    }
+
+    #[test]
+    fn test_file_takes_precedence_over_env() {
+        let path = write_temp(
+            "over_env.toml",
+            "sma_period = 50
+",
+        );
+        std::env::set_var("BTCFG_SMA_PERIOD", "100");
+        std::env::set_var("BTCFG_RSI_OVERSOLD", "20");
+        let env = BacktestParameters::from_env("BTCFG_");
+        std::env::remove_var("BTCFG_SMA_PERIOD");
+        std::env::remove_var("BTCFG_RSI_OVERSOLD");
+        let env = env.unwrap();
+        let params = load_parameters_onto(env.clone(), &path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(env.strategy.sma_period, 100);
+        assert_eq!(params.strategy.sma_period, 50);
+        assert_eq!(params.strategy.rsi_oversold, 20.0);
+    }
+
+    #[test]
+    fn test_json_config() {
+        let path = write_temp(
+            "params.json",
+            r#"{"symbol": "SOXL", "stop_loss_pct": 0.08}"#,
+        );
+        let params = load_parameters(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(params.strategy.symbol, "SOXL");
+        assert_eq!(params.risk.stop_loss, StopSpec::Percent(0.08));
+    }
+
+    #[test]
+    fn test_stop_spec_config() {
+        let path = write_temp(
+            "stops.toml",
+            "stop_loss_pct = \"2atr\"\nshort_stop_loss_pct = \"1.5usd\"\n",
+        );
+        let params = load_parameters(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(params.risk.stop_loss, StopSpec::AtrMultiple(2.0));
+        assert_eq!(params.hedge.short_stop_loss, StopSpec::Dollars(1.5));
+
+        // Percent stops keep the legacy bare fraction; the others are text
+        let json = serde_json::to_value(&params).unwrap();
+        assert_eq!(json["stop_loss_pct"], "2atr");
+        assert_eq!(json["short_stop_loss_pct"], "1.5usd");
+        let legacy = serde_json::to_value(BacktestParameters::default()).unwrap();
+        assert_eq!(legacy["stop_loss_pct"], 0.05);
+
+        let path = write_temp("no_stop.json", r#"{"stop_loss_pct": 0}"#);
+        let params = load_parameters(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(params.risk.stop_loss, StopSpec::None);
+
+        // The CLI forms
+        let parse = |s: &str| s.parse::<StopSpec>();
+        assert_eq!(parse("2ATR"), Ok(StopSpec::AtrMultiple(2.0)));
+        assert_eq!(parse("1.5usd"), Ok(StopSpec::Dollars(1.5)));
+        assert_eq!(parse("$1.5"), Ok(StopSpec::Dollars(1.5)));
+        assert_eq!(parse("5%"), Ok(StopSpec::Percent(0.05)));
+        assert_eq!(parse("0.05"), Ok(StopSpec::Percent(0.05)));
+        assert_eq!(parse("none"), Ok(StopSpec::None));
+        assert!(parse("2 bars").is_err() && parse("-1usd").is_err());
+    }
+
+    #[test]
+    fn test_symbol_overrides_toml() {
+        let path = write_temp(
+            "multi.toml",
+            "stop_loss_pct = 0.05\n\n[overrides.SOXL]\nstop_loss_pct = 0.10\nrsi_oversold = 20.0\n",
+        );
+        let config = load_multi_config(&path).unwrap();
+        let base = load_parameters(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        let soxl = config.params_for("SOXL").unwrap();
+        assert_eq!(soxl.risk.stop_loss, StopSpec::Percent(0.10));
+        assert_eq!(soxl.strategy.rsi_oversold, 20.0);
+        let tqqq = config.params_for("TQQQ").unwrap();
+        assert_eq!(tqqq.risk.stop_loss, StopSpec::Percent(0.05));
+        assert_eq!(base.risk.stop_loss, StopSpec::Percent(0.05));
+    }
+
+    #[test]
+    fn test_invalid_override_rejected() {
+        let bad_value = write_temp(
+            "bad_override.toml",
+            "[overrides.SOXL]\nstop_loss_pct = 2.0\n",
+        );
+        let unknown_key = write_temp("typo_override.toml", "[overrides.SOXL]\nstop_los = 0.1\n");
+
+        assert!(matches!(
+            load_multi_config(&bad_value),
+            Err(BacktestError::InvalidParameter(_))
+        ));
+        assert!(matches!(
+            load_multi_config(&unknown_key),
+            Err(BacktestError::ConfigError(_))
+        ));
+        fs::remove_file(&bad_value).ok();
+        fs::remove_file(&unknown_key).ok();
+    }
+
+    #[test]
+    fn test_unsupported_extension() {
+        let path = write_temp("params.yaml", "symbol: TQQQ");
+        let result = load_parameters(&path);
+        fs::remove_file(&path).ok();
+
+        assert!(matches!(result, Err(BacktestError::ConfigError(_))));
+    }
+}
@@ -0,0 +1,31 @@
+//! Bars and parameters shared by the engine's and the analyses' unit tests
+
+use chrono::Duration;
+use common::{BacktestParameters, Bar};
+
+use super::ScenarioBuilder;
+
+/// Scenario bars every 6 hours from 2024-01-01 so that the whole series
+/// falls within a single calendar month
+pub(crate) fn scenario(days: usize) -> ScenarioBuilder {
+    ScenarioBuilder::new(days, 100.0).with_interval(Duration::hours(6))
+}
+
+/// Drift with an RSI(2) dip on each of `oversold_days`
+pub(crate) fn pattern_bars(days: usize, oversold_days: &[usize]) -> Vec<Bar> {
+    oversold_days
+        .iter()
+        .fold(scenario(days), |s, &day| s.oversold(day))
+        .build()
+}
+
+/// Parameters under which the dips of [`pattern_bars`] are the only entries
+pub(crate) fn pattern_params() -> BacktestParameters {
+    // SMA(1) keeps the trend filter inert so only RSI drives entries,
+    // and without slippage fills land on bar prices
+    BacktestParameters::default()
+        .without_vwap_filter()
+        .without_short()
+        .with_sma_period(1)
+        .with_slippage(0.0)
+}
@@ -2,8 +2,8 @@ use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
 
-use chrono::{DateTime, TimeZone, Utc};
-use common::{BacktestError, Bar, Result};
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use common::{BacktestError, Bar, Result, Trade};
 
 /// Load bars from CSV file
 pub fn load_csv(path: &Path) -> Result<Vec<Bar>> {
@@ -14,12 +14,24 @@ pub fn load_csv(path: &Path) -> Result<Vec<Bar>> {
         .flexible(true)
         .from_reader(reader);
 
+    let header = csv_reader
+        .headers()
+        .map_err(|e| BacktestError::CsvError(e.to_string()))?;
+    if header.len() < 6 {
+        return Err(BacktestError::CsvError(format!(
+            "{}: expected timestamp, open, high, low, close and volume columns, found {}",
+            path.display(),
+            header.iter().collect::<Vec<_>>().join(", ")
+        )));
+    }
+
     let mut bars = Vec::new();
 
     for result in csv_reader.records() {
         let record = result.map_err(|e| BacktestError::CsvError(e.to_string()))?;
 
-        // Expected columns: timestamp, open, high, low, close, volume, [vwap]
+        // Expected columns: timestamp, open, high, low, close, volume, [vwap],
+        // [halted]
         if record.len() < 6 {
             continue;
         }
@@ -46,6 +58,9 @@ pub fn load_csv(path: &Path) -> Result<Vec<Bar>> {
         } else {
             None
         };
+        let halted = record
+            .get(7)
+            .is_some_and(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes"));
 
         bars.push(Bar {
             timestamp,
@@ -55,6 +70,7 @@ pub fn load_csv(path: &Path) -> Result<Vec<Bar>> {
             close,
             volume,
             vwap,
+            halted,
         });
     }
 
@@ -69,6 +85,55 @@ pub fn load_json(path: &Path) -> Result<Vec<Bar>> {
     Ok(bars)
 }
 
+/// Load a cash flow schedule from a CSV file with `date,amount` columns;
+/// deposits are positive, withdrawals negative
+pub fn load_cash_flows(path: &Path) -> Result<Vec<(NaiveDate, f64)>> {
+    let file = File::open(path).map_err(|e| BacktestError::DataLoadError(e.to_string()))?;
+    let mut csv_reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .trim(csv::Trim::All)
+        .from_reader(BufReader::new(file));
+
+    let mut flows = Vec::new();
+    for result in csv_reader.records() {
+        let record = result.map_err(|e| BacktestError::CsvError(e.to_string()))?;
+        if record.len() < 2 {
+            return Err(BacktestError::CsvError(
+                "Cash flows need date and amount columns".to_string(),
+            ));
+        }
+        let date = parse_timestamp(&record[0])?.date_naive();
+        let amount: f64 = record[1].parse().map_err(|_| {
+            BacktestError::CsvError(format!("Invalid cash flow amount: {}", &record[1]))
+        })?;
+        flows.push((date, amount));
+    }
+
+    Ok(flows)
+}
+
+/// Load trades from a CSV file with the columns of `Trade`, a JSON list
+/// of trades, or a result JSON (its `trades`)
+pub fn load_trades(path: &Path) -> Result<Vec<Trade>> {
+    let file = File::open(path).map_err(|e| BacktestError::DataLoadError(e.to_string()))?;
+    let is_csv = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+    if is_csv {
+        return csv::Reader::from_reader(BufReader::new(file))
+            .deserialize()
+            .collect::<std::result::Result<Vec<Trade>, _>>()
+            .map_err(|e| BacktestError::CsvError(e.to_string()));
+    }
+
+    let value: serde_json::Value = serde_json::from_reader(BufReader::new(file))?;
+    let trades = match value {
+        serde_json::Value::Object(mut result) => result.remove("trades").unwrap_or_default(),
+        list => list,
+    };
+    Ok(serde_json::from_value(trades)?)
+}
+
 /// Parse timestamp from various formats
 fn parse_timestamp(s: &str) -> Result<DateTime<Utc>> {
     // Try ISO 8601 format first
@@ -90,8 +155,7 @@ fn parse_timestamp(s: &str) -> Result<DateTime<Utc>> {
             return Ok(Utc.from_utc_datetime(&dt));
         }
         if let Ok(date) = chrono::NaiveDate::parse_from_str(s, fmt) {
-            return Ok(Utc
-                .from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()));
+            return Ok(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap()));
         }
     }
 
@@ -139,4 +203,22 @@ mod tests {
         let ts = parse_timestamp("1705312200").unwrap();
         assert!(ts.year() >= 2024);
     }
+
+    #[test]
+    fn test_load_cash_flows() {
+        let path = std::env::temp_dir().join(format!("cash_flows_{}.csv", std::process::id()));
+        std::fs::write(&path, "date,amount\n2024-03-01, 5000\n2024-06-03,-2500.5\n").unwrap();
+        let flows = load_cash_flows(&path).unwrap();
+        assert_eq!(
+            flows,
+            vec![
+                (NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(), 5000.0),
+                (NaiveDate::from_ymd_opt(2024, 6, 3).unwrap(), -2500.5),
+            ]
+        );
+
+        std::fs::write(&path, "date,amount\n2024-03-01,lots\n").unwrap();
+        assert!(load_cash_flows(&path).is_err());
+        std::fs::remove_file(&path).ok();
+    }
 }
@@ -1,10 +1,22 @@
+#[cfg(test)]
+pub(crate) mod fixtures;
 pub mod loader;
+pub mod scenario;
 pub mod synthetic;
+pub mod validation;
 
-pub use loader::{load_csv, load_json};
-pub use synthetic::{generate_bars_with_rsi_pattern, generate_synthetic_bars};
+pub use loader::{load_cash_flows, load_csv, load_json, load_trades};
+pub use scenario::{DaySpec, Relation, RsiTarget, ScenarioBuilder};
+pub use synthetic::{
+    generate_bars_with_rsi_pattern, generate_crisis_bars_seeded, generate_synthetic_bars,
+    generate_synthetic_bars_seeded, LIMIT_DOWN,
+};
+pub use validation::{
+    auto_rescale, data_quality, detect_price_scale, export_quality, low_quality_warnings,
+    rescale_prices, validate_bars, DataQuality, PriceScale,
+};
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use common::{BacktestError, Bar, Result};
 
@@ -25,3 +37,67 @@ pub fn load_file(path: &Path) -> Result<Vec<Bar>> {
         ))),
     }
 }
+
+/// Bars contributed by one file of a multi-file load
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedFile {
+    pub path: PathBuf,
+    /// Bars kept from this file
+    pub bars: usize,
+    /// Bars dropped because an earlier file already covered them
+    pub overlapping: usize,
+}
+
+/// Load several files, in order, as one continuous series.
+///
+/// Fails when a file starts at or before the previous file's last bar.
+pub fn load_files(paths: &[PathBuf]) -> Result<Vec<Bar>> {
+    load_files_with(paths, false).map(|(bars, _)| bars)
+}
+
+/// Load several files, in order, as one continuous series, with per-file
+/// bar counts.
+///
+/// With `dedupe_overlap`, bars at or before the previous file's last bar
+/// are dropped (the earlier file wins) instead of failing the load.
+pub fn load_files_with(
+    paths: &[PathBuf],
+    dedupe_overlap: bool,
+) -> Result<(Vec<Bar>, Vec<LoadedFile>)> {
+    let mut bars: Vec<Bar> = Vec::new();
+    let mut files = Vec::with_capacity(paths.len());
+    // File that contributed the last bar so far
+    let mut previous: Option<&Path> = None;
+
+    for path in paths {
+        let loaded = load_file(path)?;
+        let last = bars.last().map(|b| b.timestamp);
+        let overlapping = match last {
+            Some(last) => loaded.iter().take_while(|b| b.timestamp <= last).count(),
+            None => 0,
+        };
+
+        if overlapping > 0 && !dedupe_overlap {
+            return Err(BacktestError::DataLoadError(format!(
+                "{}: {} bar(s) from {} overlap {}, which ends at {}",
+                path.display(),
+                overlapping,
+                loaded[0].timestamp,
+                previous.unwrap().display(),
+                last.unwrap()
+            )));
+        }
+        if loaded.len() > overlapping {
+            previous = Some(path);
+        }
+
+        files.push(LoadedFile {
+            path: path.clone(),
+            bars: loaded.len() - overlapping,
+            overlapping,
+        });
+        bars.extend(loaded.into_iter().skip(overlapping));
+    }
+
+    Ok((bars, files))
+}
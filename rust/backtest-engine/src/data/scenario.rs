@@ -0,0 +1,330 @@
+//! Deterministic multi-condition bar scenarios for engine tests
+//!
+//! [`ScenarioBuilder`] lays out a slow drift (the same shape as
+//! [`generate_bars_with_rsi_pattern`](super::generate_bars_with_rsi_pattern))
+//! and lets individual days be pinned: RSI(2) direction, close-to-close
+//! return, close versus VWAP, close versus a trailing SMA, opening gap and
+//! volume. `build` checks every pinned condition on the finished bars and
+//! panics when one does not hold, so a scenario that builds is known to
+//! satisfy its specification.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use common::Bar;
+
+use crate::indicators::{calculate_rsi, calculate_sma};
+
+/// RSI(2) levels the builder checks pinned days against; the default entry
+/// and exit thresholds
+pub const SCENARIO_OVERSOLD_RSI: f64 = 30.0;
+pub const SCENARIO_OVERBOUGHT_RSI: f64 = 75.0;
+
+/// Return of unpinned days
+const DRIFT: f64 = 0.001;
+/// Return of oversold / overbought days
+const SPIKE: f64 = 0.03;
+/// Return of the bars leading into an SMA-pinned day
+const TREND: f64 = 0.01;
+/// Distance of VWAP from the close on VWAP-pinned days
+const VWAP_OFFSET: f64 = 0.005;
+const BASE_VOLUME: u64 = 50_000_000;
+
+/// RSI(2) direction of a pinned day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsiTarget {
+    /// 3% drop; RSI(2) must end below [`SCENARIO_OVERSOLD_RSI`]
+    Oversold,
+    /// 3% rise; RSI(2) must end above [`SCENARIO_OVERBOUGHT_RSI`]
+    Overbought,
+}
+
+/// Where the close sits relative to a reference level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    Below,
+    Above,
+}
+
+impl Relation {
+    fn holds(self, value: f64, reference: f64) -> bool {
+        match self {
+            Relation::Below => value < reference,
+            Relation::Above => value > reference,
+        }
+    }
+}
+
+/// Specification of one scenario day; unset fields keep the drift
+#[derive(Debug, Clone, Default)]
+pub struct DaySpec {
+    pub rsi: Option<RsiTarget>,
+    /// Close-to-close return (%), overriding the RSI move and any trend
+    pub return_pct: Option<f64>,
+    pub vwap: Option<Relation>,
+    /// Close versus the SMA of this many closes, reached by trending the
+    /// bars before the day
+    pub sma: Option<(Relation, usize)>,
+    /// Open versus the previous close (%)
+    pub gap_pct: f64,
+    /// Volume relative to the base volume (0 for a halted bar)
+    pub volume_multiplier: Option<f64>,
+    /// Mark the bar as a trading halt
+    pub halted: bool,
+}
+
+/// Builds bars that provably satisfy per-day specifications
+#[derive(Debug, Clone)]
+pub struct ScenarioBuilder {
+    initial_price: f64,
+    start: DateTime<Utc>,
+    interval: Duration,
+    days: Vec<DaySpec>,
+}
+
+impl ScenarioBuilder {
+    /// `days` daily bars starting 2024-01-01 UTC at `initial_price`
+    pub fn new(days: usize, initial_price: f64) -> Self {
+        Self {
+            initial_price,
+            start: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            interval: Duration::days(1),
+            days: vec![DaySpec::default(); days],
+        }
+    }
+
+    pub fn with_start(mut self, start: DateTime<Utc>) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Time between bars (one day by default)
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Replace the whole specification of `day`
+    pub fn day(mut self, day: usize, spec: DaySpec) -> Self {
+        *self.spec(day) = spec;
+        self
+    }
+
+    pub fn oversold(mut self, day: usize) -> Self {
+        self.spec(day).rsi = Some(RsiTarget::Oversold);
+        self
+    }
+
+    pub fn overbought(mut self, day: usize) -> Self {
+        self.spec(day).rsi = Some(RsiTarget::Overbought);
+        self
+    }
+
+    /// Close-to-close return of `day` in percent
+    pub fn return_pct(mut self, day: usize, pct: f64) -> Self {
+        self.spec(day).return_pct = Some(pct);
+        self
+    }
+
+    pub fn vwap(mut self, day: usize, close_is: Relation) -> Self {
+        self.spec(day).vwap = Some(close_is);
+        self
+    }
+
+    pub fn sma(mut self, day: usize, close_is: Relation, period: usize) -> Self {
+        self.spec(day).sma = Some((close_is, period));
+        self
+    }
+
+    pub fn gap(mut self, day: usize, pct: f64) -> Self {
+        self.spec(day).gap_pct = pct;
+        self
+    }
+
+    pub fn volume(mut self, day: usize, multiplier: f64) -> Self {
+        self.spec(day).volume_multiplier = Some(multiplier);
+        self
+    }
+
+    pub fn halted(mut self, day: usize) -> Self {
+        self.spec(day).halted = true;
+        self
+    }
+
+    fn spec(&mut self, day: usize) -> &mut DaySpec {
+        let len = self.days.len();
+        self.days
+            .get_mut(day)
+            .unwrap_or_else(|| panic!("scenario day {} out of range ({} days)", day, len))
+    }
+
+    /// Close-to-close return of each day
+    fn returns(&self) -> Vec<f64> {
+        let mut returns = vec![DRIFT; self.days.len()];
+        for (day, spec) in self.days.iter().enumerate() {
+            if let Some((relation, period)) = spec.sma {
+                let trend = match relation {
+                    Relation::Above => TREND,
+                    Relation::Below => -TREND,
+                };
+                for r in &mut returns[(day + 1).saturating_sub(period)..day] {
+                    *r = trend;
+                }
+            }
+        }
+        for (r, spec) in returns.iter_mut().zip(&self.days) {
+            if let Some(target) = spec.rsi {
+                *r = match target {
+                    RsiTarget::Oversold => -SPIKE,
+                    RsiTarget::Overbought => SPIKE,
+                };
+            }
+            if let Some(pct) = spec.return_pct {
+                *r = pct / 100.0;
+            }
+        }
+        returns
+    }
+
+    /// Generate the bars, panicking if any pinned condition does not hold
+    pub fn build(&self) -> Vec<Bar> {
+        let mut bars = Vec::with_capacity(self.days.len());
+        let mut price = self.initial_price;
+
+        for (i, (spec, r)) in self.days.iter().zip(self.returns()).enumerate() {
+            let open = price * (1.0 + spec.gap_pct / 100.0);
+            let close = price * (1.0 + r);
+            let range = price * 0.01;
+            let vwap = match spec.vwap {
+                Some(Relation::Below) => close * (1.0 + VWAP_OFFSET),
+                Some(Relation::Above) => close * (1.0 - VWAP_OFFSET),
+                None => (open + close) / 2.0,
+            };
+            let volume = BASE_VOLUME as f64 * spec.volume_multiplier.unwrap_or(1.0);
+
+            bars.push(Bar {
+                timestamp: self.start + self.interval * i as i32,
+                open,
+                high: open.max(close) + range,
+                low: open.min(close) - range,
+                close,
+                volume: volume.round() as u64,
+                vwap: Some(vwap),
+                halted: spec.halted,
+            });
+            price = close;
+        }
+
+        self.check(&bars);
+        bars
+    }
+
+    fn check(&self, bars: &[Bar]) {
+        let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+        let rsi = calculate_rsi(&closes, 2);
+
+        for (day, spec) in self.days.iter().enumerate() {
+            let bar = &bars[day];
+            match spec.rsi {
+                Some(RsiTarget::Oversold) => assert!(
+                    rsi[day] < SCENARIO_OVERSOLD_RSI,
+                    "scenario day {}: RSI(2) {:.1} is not oversold",
+                    day,
+                    rsi[day]
+                ),
+                Some(RsiTarget::Overbought) => assert!(
+                    rsi[day] > SCENARIO_OVERBOUGHT_RSI,
+                    "scenario day {}: RSI(2) {:.1} is not overbought",
+                    day,
+                    rsi[day]
+                ),
+                None => {}
+            }
+            if let Some(relation) = spec.vwap {
+                let vwap = bar.vwap.unwrap();
+                assert!(
+                    relation.holds(bar.close, vwap),
+                    "scenario day {}: close {:.4} is not {:?} VWAP {:.4}",
+                    day,
+                    bar.close,
+                    relation,
+                    vwap
+                );
+            }
+            if let Some((relation, period)) = spec.sma {
+                let sma = calculate_sma(&closes, period)[day].unwrap_or_else(|| {
+                    panic!(
+                        "scenario day {}: fewer than {} bars for the SMA",
+                        day, period
+                    )
+                });
+                assert!(
+                    relation.holds(bar.close, sma),
+                    "scenario day {}: close {:.4} is not {:?} SMA({}) {:.4}",
+                    day,
+                    bar.close,
+                    relation,
+                    period,
+                    sma
+                );
+            }
+            if day > 0 {
+                let gap = (bar.open / bars[day - 1].close - 1.0) * 100.0;
+                assert!(
+                    (gap - spec.gap_pct).abs() < 1e-9,
+                    "scenario day {}: gap {:.4}% is not {}%",
+                    day,
+                    gap,
+                    spec.gap_pct
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_bars_with_rsi_pattern;
+
+    #[test]
+    fn test_default_days_match_rsi_pattern() {
+        let scenario = ScenarioBuilder::new(40, 100.0).oversold(22).build();
+        let pattern = generate_bars_with_rsi_pattern(40, 100.0, &[22], &[]);
+
+        for (a, b) in scenario.iter().zip(&pattern) {
+            assert_eq!(
+                (a.open, a.high, a.low, a.close),
+                (b.open, b.high, b.low, b.close)
+            );
+            assert_eq!((a.volume, a.vwap), (b.volume, b.vwap));
+        }
+    }
+
+    #[test]
+    fn test_pinned_conditions() {
+        let bars = ScenarioBuilder::new(60, 100.0)
+            .oversold(30)
+            .sma(30, Relation::Above, 20)
+            .vwap(30, Relation::Above)
+            .oversold(50)
+            .sma(50, Relation::Below, 10)
+            .gap(51, -4.0)
+            .volume(52, 0.0)
+            .build();
+
+        assert!(bars[30].close > bars[30].vwap.unwrap());
+        assert!((bars[51].open / bars[50].close - 0.96).abs() < 1e-12);
+        assert_eq!(bars[52].volume, 0);
+        assert_eq!(bars[1].timestamp - bars[0].timestamp, Duration::days(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not overbought")]
+    fn test_unsatisfiable_day_panics() {
+        // A 0.5% rise straight after a 3% drop cannot lift RSI(2) that far
+        ScenarioBuilder::new(30, 100.0)
+            .oversold(20)
+            .overbought(21)
+            .return_pct(21, 0.5)
+            .build();
+    }
+}
@@ -1,14 +1,67 @@
-use chrono::{Duration, TimeZone, Utc};
+use chrono::{DateTime, Duration, TimeZone, Utc};
 use common::Bar;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Per-bar chance of a crash regime starting in [`generate_crisis_bars_seeded`]
+const CRISIS_START_PROBABILITY: f64 = 0.01;
+/// Length of a crash regime in bars, and its volatility multiplier
+const CRISIS_BARS: std::ops::Range<usize> = 10..30;
+const CRISIS_VOLATILITY_MULTIPLIER: f64 = 2.5;
+/// Close-to-close drop at which a bar goes limit-down and trading halts
+pub const LIMIT_DOWN: f64 = 0.07;
 
 /// Generate synthetic TQQQ-like price data for testing
 pub fn generate_synthetic_bars(days: usize, initial_price: f64) -> Vec<Bar> {
-    let mut rng = rand::thread_rng();
-    let mut bars = Vec::with_capacity(days);
+    let start_date = Utc::now() - Duration::days(days as i64);
+    generate_with_rng(
+        &mut rand::thread_rng(),
+        days,
+        initial_price,
+        start_date,
+        false,
+    )
+}
 
+/// Reproducible variant of [`generate_synthetic_bars`].
+///
+/// The same seed always yields the same bars, starting 2020-01-01 UTC.
+pub fn generate_synthetic_bars_seeded(days: usize, initial_price: f64, seed: u64) -> Vec<Bar> {
+    let start_date = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+    generate_with_rng(
+        &mut StdRng::seed_from_u64(seed),
+        days,
+        initial_price,
+        start_date,
+        false,
+    )
+}
+
+/// [`generate_synthetic_bars_seeded`] with occasional crash regimes of
+/// higher volatility, in which bars falling [`LIMIT_DOWN`] or more are
+/// marked halted
+pub fn generate_crisis_bars_seeded(days: usize, initial_price: f64, seed: u64) -> Vec<Bar> {
+    let start_date = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+    generate_with_rng(
+        &mut StdRng::seed_from_u64(seed),
+        days,
+        initial_price,
+        start_date,
+        true,
+    )
+}
+
+fn generate_with_rng<R: Rng>(
+    rng: &mut R,
+    days: usize,
+    initial_price: f64,
+    start_date: DateTime<Utc>,
+    crises: bool,
+) -> Vec<Bar> {
+    let mut bars = Vec::with_capacity(days);
     let mut price = initial_price;
-    let start_date = Utc::now() - Duration::days(days as i64);
+    // Bars left in the current crash regime
+    let mut crisis_left = 0;
 
     // TQQQ-like parameters
     let daily_volatility = 0.03; // ~3% daily volatility (3x leveraged)
@@ -17,9 +70,19 @@ pub fn generate_synthetic_bars(days: usize, initial_price: f64) -> Vec<Bar> {
     for i in 0..days {
         let date = start_date + Duration::days(i as i64);
 
+        if crises && crisis_left == 0 && rng.gen_bool(CRISIS_START_PROBABILITY) {
+            crisis_left = rng.gen_range(CRISIS_BARS);
+        }
+        let volatility = if crisis_left > 0 {
+            crisis_left -= 1;
+            daily_volatility * CRISIS_VOLATILITY_MULTIPLIER
+        } else {
+            daily_volatility
+        };
+
         // Generate daily return with mean-reverting tendency
         let random_return: f64 = rng.gen_range(-1.0..1.0);
-        let daily_return = drift + daily_volatility * random_return;
+        let daily_return = drift + volatility * random_return;
 
         // Apply return
         let new_price = price * (1.0 + daily_return);
@@ -48,6 +111,7 @@ pub fn generate_synthetic_bars(days: usize, initial_price: f64) -> Vec<Bar> {
             close,
             volume,
             vwap: Some(vwap),
+            halted: daily_return <= -LIMIT_DOWN,
         });
 
         price = new_price;
@@ -90,6 +154,7 @@ pub fn generate_bars_with_rsi_pattern(
             close: new_price,
             volume: 50_000_000,
             vwap: Some((price + new_price) / 2.0),
+            halted: false,
         });
 
         price = new_price;
@@ -118,6 +183,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_seeded_bars_are_reproducible() {
+        let a = generate_synthetic_bars_seeded(50, 50.0, 7);
+        let b = generate_synthetic_bars_seeded(50, 50.0, 7);
+        let c = generate_synthetic_bars_seeded(50, 50.0, 8);
+
+        assert_eq!(a.len(), 50);
+        for (x, y) in a.iter().zip(&b) {
+            assert_eq!(x.timestamp, y.timestamp);
+            assert_eq!(x.close, y.close);
+            assert_eq!(x.volume, y.volume);
+        }
+        assert_ne!(a[49].close, c[49].close);
+    }
+
     #[test]
     fn test_generate_pattern_bars() {
         let bars = generate_bars_with_rsi_pattern(50, 100.0, &[10, 11, 12], &[30, 31, 32]);
@@ -130,4 +210,17 @@ mod tests {
         // Verify rises on overbought days
         assert!(bars[32].close > bars[29].close);
     }
+
+    #[test]
+    fn test_crisis_bars_halt_on_limit_down() {
+        let bars = generate_crisis_bars_seeded(2000, 50.0, 4);
+        let halted: Vec<usize> = (1..bars.len()).filter(|&i| bars[i].halted).collect();
+        assert!(!halted.is_empty());
+        for i in halted {
+            assert!(bars[i].close / bars[i - 1].close - 1.0 <= -LIMIT_DOWN + 1e-12);
+        }
+        assert!(!generate_synthetic_bars_seeded(2000, 50.0, 4)
+            .iter()
+            .any(|b| b.halted));
+    }
 }
@@ -0,0 +1,469 @@
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use common::{Bar, DataWarning, DataWarningKind, ExecutionParams, Result};
+
+use crate::output::{write_csv, Overwrite};
+
+/// Trailing bars forming the volume and range baseline of a quality score
+pub const QUALITY_WINDOW: usize = 100;
+
+/// Unchanged closes in a row at which the staleness part reaches zero
+const STALE_RUN_BARS: usize = 3;
+
+/// Percentile (0-1) of the trailing volumes a bar's volume is measured
+/// against, and the fraction of it at and above which volume looks healthy
+const VOLUME_PERCENTILE: f64 = 0.10;
+const VOLUME_FLOOR: f64 = 0.1;
+
+/// Fraction of the trailing median range at and above which a bar's range
+/// looks healthy
+const RANGE_FLOOR: f64 = 0.1;
+
+/// Scale factors tried, in order, to bring an implausible median close into
+/// the sane range; cents come first as the usual culprit
+const SCALE_FACTORS: [f64; 6] = [100.0, 10.0, 1_000.0, 10_000.0, 100_000.0, 1_000_000.0];
+
+/// A supplementary price column whose median ratio to the close leaves
+/// this band is on another scale
+const SUPPLEMENTARY_RATIO_BAND: (f64, f64) = (0.5, 2.0);
+
+/// Median traded value per bar ($) beyond which volume is taken for dollar
+/// volume; above the busiest single stocks' daily turnover
+const MAX_BAR_NOTIONAL: f64 = 1e11;
+
+/// Check bars for data quality problems that affect the simulation.
+///
+/// Bars with volume below `min_bar_volume` are flagged as halted (no orders
+/// execute on them); 0 skips that check. Series-wide scale problems (see
+/// [`detect_price_scale`]) get one warning each, without a timestamp.
+pub fn validate_bars(bars: &[Bar], execution: &ExecutionParams) -> Vec<DataWarning> {
+    let min_bar_volume = execution.min_bar_volume;
+    let mut warnings = scale_warnings(bars, execution.sane_price_range);
+
+    for (i, bar) in bars.iter().enumerate() {
+        let warn = |kind, message| DataWarning {
+            kind,
+            timestamp: Some(bar.timestamp),
+            message,
+        };
+
+        if i > 0 && bar.timestamp <= bars[i - 1].timestamp {
+            warnings.push(warn(
+                DataWarningKind::NonMonotonicTimestamp,
+                format!(
+                    "bar {} at {} does not follow {}",
+                    i,
+                    bar.timestamp,
+                    bars[i - 1].timestamp
+                ),
+            ));
+        }
+
+        let prices = [bar.open, bar.high, bar.low, bar.close];
+        if prices.iter().any(|p| !p.is_finite() || *p <= 0.0) {
+            warnings.push(warn(
+                DataWarningKind::InvalidPrice,
+                format!("bar {} has a non-positive or non-finite price", i),
+            ));
+        } else if bar.high < bar.low {
+            warnings.push(warn(
+                DataWarningKind::InvalidRange,
+                format!(
+                    "bar {} has high {:.4} below low {:.4}",
+                    i, bar.high, bar.low
+                ),
+            ));
+        }
+
+        if bar.volume < min_bar_volume {
+            warnings.push(warn(
+                DataWarningKind::HaltedBar,
+                format!(
+                    "bar {} volume {} below minimum {}; treated as halted",
+                    i, bar.volume, min_bar_volume
+                ),
+            ));
+        }
+    }
+
+    warnings
+}
+
+/// A median close outside the sane price range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceScale {
+    pub median_close: f64,
+    /// What prices are multiplied by relative to a sane scale (100 for
+    /// cents); None when no power of ten lands the median in range
+    pub factor: Option<f64>,
+}
+
+/// Check the median close against `range` (min, max)
+pub fn detect_price_scale(bars: &[Bar], range: (f64, f64)) -> Option<PriceScale> {
+    let median_close = median(bars.iter().map(|b| b.close))?;
+    let (min, max) = range;
+    if median_close >= min && median_close <= max {
+        return None;
+    }
+    let factor = SCALE_FACTORS
+        .iter()
+        .map(|&f| if median_close > max { f } else { 1.0 / f })
+        .find(|f| (min..=max).contains(&(median_close / f)));
+    Some(PriceScale {
+        median_close,
+        factor,
+    })
+}
+
+/// Divide every price by `factor`; volumes are left as they are
+pub fn rescale_prices(bars: &mut [Bar], factor: f64) {
+    for bar in bars {
+        bar.open /= factor;
+        bar.high /= factor;
+        bar.low /= factor;
+        bar.close /= factor;
+        if let Some(vwap) = bar.vwap.as_mut() {
+            *vwap /= factor;
+        }
+    }
+}
+
+/// Rescale `bars` when their median close is off by a detectable factor,
+/// returning the warning recording it
+pub fn auto_rescale(bars: &mut [Bar], range: (f64, f64)) -> Option<DataWarning> {
+    let scale = detect_price_scale(bars, range)?;
+    let factor = scale.factor?;
+    rescale_prices(bars, factor);
+    Some(DataWarning {
+        kind: DataWarningKind::PricesRescaled,
+        timestamp: None,
+        message: format!(
+            "prices divided by {} (median close {:.2} outside {}-{})",
+            factor, scale.median_close, range.0, range.1
+        ),
+    })
+}
+
+/// Series-wide checks: price scale, supplementary column scale and volume
+/// units
+fn scale_warnings(bars: &[Bar], range: (f64, f64)) -> Vec<DataWarning> {
+    let warn = |kind, message| DataWarning {
+        kind,
+        timestamp: None,
+        message,
+    };
+    let mut warnings = Vec::new();
+
+    let scale = detect_price_scale(bars, range);
+    if let Some(scale) = scale {
+        let hint = match scale.factor {
+            Some(factor) => format!("; prices look scaled by {} (see --auto-rescale)", factor),
+            None => String::new(),
+        };
+        warnings.push(warn(
+            DataWarningKind::PriceScale,
+            format!(
+                "median close {:.2} outside the sane range {}-{}{}",
+                scale.median_close, range.0, range.1, hint
+            ),
+        ));
+    }
+
+    let vwap_ratio = median(
+        bars.iter()
+            .filter(|b| b.close > 0.0)
+            .filter_map(|b| b.vwap.map(|v| v / b.close)),
+    );
+    if let Some(ratio) = vwap_ratio {
+        let (low, high) = SUPPLEMENTARY_RATIO_BAND;
+        if ratio < low || ratio > high {
+            warnings.push(warn(
+                DataWarningKind::SupplementaryScale,
+                format!(
+                    "median vwap is {:.4}x the close; the column looks scaled by {}",
+                    ratio,
+                    10f64.powi(ratio.log10().round() as i32)
+                ),
+            ));
+        }
+    }
+
+    // Measured at the corrected scale, so cents alone do not trip it
+    let factor = scale.and_then(|s| s.factor).unwrap_or(1.0);
+    if let Some(notional) = median(bars.iter().map(|b| b.volume as f64 * b.close / factor)) {
+        if notional > MAX_BAR_NOTIONAL {
+            warnings.push(warn(
+                DataWarningKind::DollarVolume,
+                format!(
+                    "median traded value ${:.3e} per bar is implausible; volume looks like dollar volume rather than shares",
+                    notional
+                ),
+            ));
+        }
+    }
+
+    warnings
+}
+
+fn median(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let mut values: Vec<f64> = values.filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    Some(values[values.len() / 2])
+}
+
+/// Per-bar data quality, each part in [0, 1] with 1 healthy.
+///
+/// Only the bar and the bars before it are read, so a bad stretch is
+/// recognized as it unfolds rather than ahead of time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DataQuality {
+    pub timestamp: DateTime<Utc>,
+    /// Falls with each repeat of the previous close
+    pub stale: f64,
+    /// Volume against the trailing `QUALITY_WINDOW` bars' low percentile
+    pub volume: f64,
+    /// High-low range against the trailing median range
+    pub range: f64,
+    /// The weakest part: any one symptom is enough
+    pub score: f64,
+}
+
+/// Score every bar; see [`DataQuality`]
+pub fn data_quality(bars: &[Bar]) -> Vec<DataQuality> {
+    let mut repeats = 0;
+    bars.iter()
+        .enumerate()
+        .map(|(i, bar)| {
+            let history = &bars[i.saturating_sub(QUALITY_WINDOW)..i];
+            repeats = match i {
+                0 => 0,
+                _ if bar.close == bars[i - 1].close => repeats + 1,
+                _ => 0,
+            };
+            let stale = 1.0 - (repeats as f64 / STALE_RUN_BARS as f64).min(1.0);
+
+            let mut volumes: Vec<u64> = history.iter().map(|b| b.volume).collect();
+            volumes.sort_unstable();
+            let low = (volumes.len() as f64 * VOLUME_PERCENTILE) as usize;
+            let volume = match volumes.get(low) {
+                Some(&reference) if reference > 0 => {
+                    (bar.volume as f64 / (reference as f64 * VOLUME_FLOOR)).min(1.0)
+                }
+                // No baseline to compare against
+                _ => 1.0,
+            };
+
+            let mut ranges: Vec<f64> = history.iter().map(|b| b.high - b.low).collect();
+            ranges.sort_by(f64::total_cmp);
+            let range = match ranges.get(ranges.len() / 2) {
+                Some(&median) if median > 0.0 => {
+                    ((bar.high - bar.low) / (median * RANGE_FLOOR)).clamp(0.0, 1.0)
+                }
+                _ => 1.0,
+            };
+
+            DataQuality {
+                timestamp: bar.timestamp,
+                stale,
+                volume,
+                range,
+                score: stale.min(volume).min(range),
+            }
+        })
+        .collect()
+}
+
+/// One warning per run of consecutive bars scoring below `min_quality`,
+/// naming its bars and dates
+pub fn low_quality_warnings(
+    quality: &[DataQuality],
+    min_quality: f64,
+    closes_positions: bool,
+) -> Vec<DataWarning> {
+    let action = if closes_positions {
+        "entries suppressed and positions closed"
+    } else {
+        "entries suppressed"
+    };
+    let mut warnings = Vec::new();
+    let mut i = 0;
+    while i < quality.len() {
+        if quality[i].score >= min_quality {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < quality.len() && quality[i].score < min_quality {
+            i += 1;
+        }
+        let (first, last) = (&quality[start], &quality[i - 1]);
+        warnings.push(DataWarning {
+            kind: DataWarningKind::LowDataQuality,
+            timestamp: Some(first.timestamp),
+            message: format!(
+                "bars {}-{} ({} to {}) below data quality {:.2}; {}",
+                start,
+                i - 1,
+                first.timestamp.date_naive(),
+                last.timestamp.date_naive(),
+                min_quality,
+                action
+            ),
+        });
+    }
+    warnings
+}
+
+/// Write the quality series to a CSV file, returning the row count
+pub fn export_quality(bars: &[Bar], path: &Path, overwrite: Overwrite) -> Result<usize> {
+    let quality = data_quality(bars);
+    write_csv(path, overwrite, &quality)?;
+    Ok(quality.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{generate_bars_with_rsi_pattern, generate_synthetic_bars_seeded};
+
+    fn execution(min_bar_volume: u64) -> ExecutionParams {
+        ExecutionParams {
+            min_bar_volume,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_clean_bars_have_no_warnings() {
+        let bars = generate_bars_with_rsi_pattern(30, 100.0, &[10], &[20]);
+        assert!(validate_bars(&bars, &execution(1)).is_empty());
+    }
+
+    #[test]
+    fn test_flags_halted_bar() {
+        let mut bars = generate_bars_with_rsi_pattern(30, 100.0, &[], &[]);
+        bars[12].volume = 0;
+
+        let warnings = validate_bars(&bars, &execution(1));
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, DataWarningKind::HaltedBar);
+        assert_eq!(warnings[0].timestamp, Some(bars[12].timestamp));
+
+        // Disabled when no minimum is configured
+        assert!(validate_bars(&bars, &execution(0)).is_empty());
+    }
+
+    #[test]
+    fn test_flags_bad_prices_and_order() {
+        let mut bars = generate_bars_with_rsi_pattern(10, 100.0, &[], &[]);
+        bars[3].close = 0.0;
+        bars[5].high = bars[5].low - 1.0;
+        bars[7].timestamp = bars[6].timestamp;
+
+        let kinds: Vec<_> = validate_bars(&bars, &execution(0))
+            .iter()
+            .map(|w| w.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DataWarningKind::InvalidPrice,
+                DataWarningKind::InvalidRange,
+                DataWarningKind::NonMonotonicTimestamp
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quality_of_stale_and_dead_bars() {
+        let mut bars = generate_synthetic_bars_seeded(60, 100.0, 5);
+        assert!(data_quality(&bars).iter().all(|q| q.score == 1.0));
+
+        let close = bars[29].close;
+        for bar in &mut bars[30..40] {
+            bar.close = close;
+        }
+        bars[50].volume = 0;
+        let quality = data_quality(&bars);
+        let stale: Vec<f64> = quality[29..34].iter().map(|q| q.stale).collect();
+        assert_eq!(stale, vec![1.0, 1.0 - 1.0 / 3.0, 1.0 - 2.0 / 3.0, 0.0, 0.0]);
+        assert_eq!(quality[40].stale, 1.0);
+        assert_eq!((quality[50].volume, quality[50].score), (0.0, 0.0));
+
+        // One warning per stretch below the threshold
+        let warnings = low_quality_warnings(&quality, 0.5, false);
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].kind, DataWarningKind::LowDataQuality);
+        assert_eq!(warnings[0].timestamp, Some(bars[31].timestamp));
+        assert!(warnings[0].message.starts_with("bars 31-39 ("));
+        assert!(warnings[1].message.starts_with("bars 50-50 ("));
+    }
+
+    #[test]
+    fn test_range_collapse() {
+        let mut bars = generate_synthetic_bars_seeded(40, 100.0, 9);
+        let bar = &mut bars[30];
+        (bar.high, bar.low) = (bar.close, bar.close);
+
+        let quality = data_quality(&bars);
+        assert_eq!(quality[30].range, 0.0);
+        assert_eq!(quality[0].range, 1.0);
+        assert!(quality[31..].iter().all(|q| q.range == 1.0));
+    }
+
+    #[test]
+    fn test_cents_scaled_bars_detected_and_rescaled() {
+        let reference = generate_synthetic_bars_seeded(120, 50.0, 11);
+        let mut cents = reference.clone();
+        rescale_prices(&mut cents, 0.01);
+        let range = (1.0, 1_000.0);
+        let mut params = execution(0);
+        params.sane_price_range = range;
+
+        assert!(detect_price_scale(&reference, range).is_none());
+        assert!(validate_bars(&reference, &params).is_empty());
+        let warnings = validate_bars(&cents, &params);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].kind, DataWarningKind::PriceScale);
+        assert_eq!(
+            detect_price_scale(&cents, range).unwrap().factor,
+            Some(100.0)
+        );
+
+        let recorded = auto_rescale(&mut cents, range).unwrap();
+        assert_eq!(recorded.kind, DataWarningKind::PricesRescaled);
+        assert!(recorded.message.starts_with("prices divided by 100"));
+        for (a, b) in cents.iter().zip(&reference) {
+            assert!((a.close - b.close).abs() < 1e-9 && (a.low - b.low).abs() < 1e-9);
+            assert_eq!(a.volume, b.volume);
+        }
+        assert!(auto_rescale(&mut cents, range).is_none());
+    }
+
+    #[test]
+    fn test_vwap_scale_and_dollar_volume() {
+        let mut bars = generate_synthetic_bars_seeded(60, 100.0, 3);
+        for bar in &mut bars {
+            // A percent-style vwap column, and volume in dollars
+            bar.vwap = Some(bar.close * 100.0);
+            bar.volume = (bar.volume as f64 * bar.close) as u64;
+        }
+        let kinds: Vec<_> = validate_bars(&bars, &execution(0))
+            .iter()
+            .map(|w| w.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DataWarningKind::SupplementaryScale,
+                DataWarningKind::DollarVolume
+            ]
+        );
+    }
+}
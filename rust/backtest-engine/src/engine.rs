@@ -1,84 +1,782 @@
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
-use chrono::{DateTime, Utc};
-use common::{BacktestParameters, BacktestResult, Bar, PositionSide, Side, SignalType};
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{
+    manifest, BacktestParameters, BacktestResult, Bar, ConcurrentHedgePolicy, DataSource,
+    DataWarning, DataWarningKind, Fill, HaltedStopPolicy, MetricsScope, PartialFillPolicy,
+    PerformanceMetrics, PositionSide, PreTradeEvent, RunManifest, Side, Signal, SignalType,
+    SuppressedSignal, Trade, VwapMode,
+};
 
-use crate::execution::ExecutionSimulator;
-use crate::indicators::{IndicatorSeries, IndicatorValues};
-use crate::metrics::MetricsCalculator;
+use crate::analysis::indicators_for;
+use crate::analysis::replay::with_strategy_indicators;
+use crate::data::{data_quality, low_quality_warnings, validate_bars, DataQuality};
+use crate::execution::{ExecutionResult, ExecutionSimulator, PendingOrder};
+use crate::indicators::{
+    calculate_avg_volume, CacheOutcome, IndicatorConfig, IndicatorSeries, IndicatorValues,
+};
+use crate::metrics::{efficiency, largest_either_way, net_exposure_pct, MetricsCalculator};
+use crate::orders::{BracketEvent, BracketOrder, OrderAudit, OrderManager};
+use crate::output::Overwrite;
 use crate::portfolio::Portfolio;
+use crate::pretrade::{PortfolioSnapshot, PreTradeCheck, PreTradeChecks, ProposedOrder};
+use crate::risk::{EdgeFilter, EntryLimiter};
 use crate::signals::SignalGenerator;
+use crate::sink::{Spill, Spilled};
+
+/// Suppression reasons for entries the engine could not execute
+const SKIP_SIZE_TOO_SMALL: &str = "position size below 1 share";
+const SKIP_REJECTED: &str = "order rejected by execution simulator";
+const SKIP_INSUFFICIENT_FILL: &str = "fill below 1 share (volume constraint)";
+const SKIP_HEAT_CAP: &str = "portfolio heat cap reached";
+const SKIP_BRACKET_ACTIVE: &str = "bracket exits still working";
+const SKIP_LIQUIDITY_CAP: &str = "liquidity cap below 1 share";
+const SKIP_HEDGE_OPEN: &str = "hedge position open";
+const SKIP_PARTIAL_CANCELLED: &str = "partial fill cancelled (volume constraint)";
+const SKIP_ANOMALY: &str = "volatility burst quarantine";
+const SKIP_LOW_DATA_QUALITY: &str = "low data quality";
+const SKIP_HYSTERESIS: &str = "opposite entry within signal hysteresis";
+
+/// Exit reason of a hedge closed to make way for a long entry
+const HEDGE_CLOSED_FOR_LONG: &str = "closed for long entry";
+
+/// Exit reason of positions force-closed on the last bar of a date range
+const RANGE_END: &str = "range end";
+
+/// Exit reason of positions force-closed on a low data quality bar
+const LOW_DATA_QUALITY: &str = "low data quality";
+
+/// Exit reason of longs closed at the bar's close by `overnight_flat`
+const OVERNIGHT_FLAT: &str = "overnight flat";
+
+/// Exit reason of shares sold to cover a withdrawal larger than cash
+const WITHDRAWAL: &str = "withdrawal";
+
+/// Bars in the trailing average volume used for liquidity-aware sizing
+pub(crate) const LIQUIDITY_AVG_PERIOD: usize = 20;
+
+/// One configuration's simulation, advanced bar by bar so several
+/// configurations can share a pass over the data
+struct Simulation<'a> {
+    indicators: &'a IndicatorSeries,
+    portfolio: Portfolio,
+    signal_generator: SignalGenerator,
+    execution_sim: ExecutionSimulator,
+    state: RunState,
+    avg_volumes: Vec<Option<f64>>,
+    /// Per-bar data quality, when `min_data_quality` is set
+    quality: Vec<DataQuality>,
+    /// Equity curve tracking, with whether each point fell inside a range
+    equity_curve: Vec<(DateTime<Utc>, f64)>,
+    heat_curve: Vec<(DateTime<Utc>, f64)>,
+    net_exposure_curve: Vec<(DateTime<Utc>, f64)>,
+    in_range: Vec<bool>,
+    /// The next of the context's cash flows to book, and the amounts
+    /// booked at each bar
+    next_flow: usize,
+    booked_flows: Vec<(DateTime<Utc>, f64)>,
+    /// Streamed output in place of the curves and trades, when the engine
+    /// has a spill directory it could open
+    spill: Option<Spill>,
+    /// Why the spill directory went unused
+    spill_warning: Option<String>,
+}
+
+impl Simulation<'_> {
+    /// Record the bar's equity, heat and net exposure; points outside every
+    /// range stay out of the metrics
+    fn record_point(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        in_range: bool,
+        params: &BacktestParameters,
+    ) {
+        let (equity, heat) = (self.portfolio.equity(), self.portfolio.heat_pct());
+        let net = self
+            .portfolio
+            .net_exposure(&params.hedge, &params.strategy.symbol);
+        match self.spill.as_mut() {
+            Some(spill) => spill.record_point(timestamp, equity, heat, net, in_range),
+            None => {
+                self.equity_curve.push((timestamp, equity));
+                self.heat_curve.push((timestamp, heat));
+                self.net_exposure_curve.push((timestamp, net));
+                self.in_range.push(in_range);
+            }
+        }
+    }
+}
+
+/// Settings that determine the indicator series: its [`IndicatorConfig`]
+/// and the strategy indicators added to it. Configurations agreeing on all
+/// of them can share one
+#[derive(PartialEq)]
+struct IndicatorKey {
+    config: IndicatorConfig,
+    sma_slope_lookback: Option<usize>,
+    momentum_roc_period: usize,
+    momentum_lookback: Option<usize>,
+    vwap_window: Option<usize>,
+    zscore_window: Option<usize>,
+    adx: bool,
+    obv: bool,
+    keltner: bool,
+    anchored_vwap: bool,
+    anomaly_zscore: Option<u64>,
+}
+
+impl IndicatorKey {
+    fn of(params: &BacktestParameters) -> Self {
+        Self {
+            config: IndicatorConfig::from(params),
+            sma_slope_lookback: params.strategy.sma_slope_filter.map(|f| f.lookback_bars),
+            momentum_roc_period: params.strategy.momentum_roc_period,
+            momentum_lookback: params
+                .strategy
+                .momentum_min_pct
+                .map(|_| params.strategy.momentum_lookback),
+            vwap_window: (params.strategy.vwap_mode != VwapMode::Disabled)
+                .then_some(params.strategy.vwap_window),
+            zscore_window: params.strategy.zscore_window,
+            adx: params.strategy.adx_filter_enabled,
+            obv: params.strategy.obv_filter_enabled,
+            keltner: params.strategy.keltner_filter_enabled,
+            anchored_vwap: params.strategy.anchored_vwap_filter_enabled,
+            anomaly_zscore: params
+                .anomaly_rules_enabled()
+                .then(|| params.risk.anomaly_zscore.to_bits()),
+        }
+    }
+}
+
+/// Long exit the volume cap let fill only in part; the rest works on the
+/// following bars until the position is flat
+struct WorkingExit {
+    order_id: u64,
+    reason: String,
+    rsi: f64,
+    /// Entry of the position being sold, so a later one is left alone
+    entry_date: DateTime<Utc>,
+    stop_triggered_at: Option<DateTime<Utc>>,
+    /// Bar of the latest tranche; one fills per bar
+    last_fill_bar: usize,
+}
+
+/// Mutable per-run state shared by the signal processing steps
+struct RunState {
+    entry_limiter: EntryLimiter,
+    edge_filter: EdgeFilter,
+    suppressed_signals: Vec<SuppressedSignal>,
+    /// A halt occurred while holding; check the stop at the next open
+    stop_check_at_open: bool,
+    orders: OrderManager,
+    /// Signal behind the working bracket entry, annotated onto the position
+    bracket_signal: Option<Signal>,
+    /// Entries shrunk by liquidity-aware sizing
+    liquidity_capped: u32,
+    /// Trigger bar of a stop exit waiting out the order latency
+    pending_stop: Option<DateTime<Utc>>,
+    /// Long exit still filling against the volume cap
+    working_exit: Option<WorkingExit>,
+    /// Running equity peak, for the drawdown throttle
+    equity_peak: f64,
+    /// Drawdown throttle multiplier applied to the last sized long entry
+    entry_size_multiplier: Option<f64>,
+    /// Entry reason and RSI of a long to open at the next open: flattened
+    /// overnight, or entered on a close while `overnight_flat` is set (a
+    /// new entry, counted against the trade limits once it fills)
+    reopen_long: Option<(String, f64, bool)>,
+    /// Order ids, and the signals and fills stamped with them
+    audit: OrderAudit,
+    /// Last bar of the entry quarantine after a volatility burst
+    quarantine_until: Option<usize>,
+    /// The current bar scores below `min_data_quality`
+    low_data_quality: bool,
+    /// Bars skipped by a halt
+    halted_bars: usize,
+    /// Whether a long / a hedge was held when last observed
+    holdings: (bool, bool),
+    /// Bar, side (long or hedge) and RSI of the last position change
+    last_change: Option<(usize, PositionSide, f64)>,
+    /// Entries the pre-trade checks rejected or shrank
+    pre_trade_events: Vec<PreTradeEvent>,
+}
+
+impl RunState {
+    fn new(params: &BacktestParameters) -> Self {
+        Self {
+            entry_limiter: EntryLimiter::new(params),
+            edge_filter: EdgeFilter::new(&params.risk, &params.execution),
+            suppressed_signals: Vec::new(),
+            stop_check_at_open: false,
+            orders: OrderManager::new(params.execution.same_bar_exit),
+            bracket_signal: None,
+            liquidity_capped: 0,
+            pending_stop: None,
+            working_exit: None,
+            equity_peak: params.initial_capital,
+            entry_size_multiplier: None,
+            reopen_long: None,
+            audit: OrderAudit::default(),
+            quarantine_until: None,
+            low_data_quality: false,
+            halted_bars: 0,
+            holdings: (false, false),
+            last_change: None,
+            pre_trade_events: Vec::new(),
+        }
+    }
+
+    /// Note a long or hedge position change since the last observation;
+    /// the long wins when both changed
+    fn observe_holdings(&mut self, portfolio: &Portfolio, bar_index: usize, rsi: f64) {
+        let holdings = (portfolio.has_position(), portfolio.is_hedged());
+        if holdings.0 != self.holdings.0 {
+            self.last_change = Some((bar_index, PositionSide::Long, rsi));
+        } else if holdings.1 != self.holdings.1 {
+            self.last_change = Some((bar_index, PositionSide::Hedge, rsi));
+        }
+        self.holdings = holdings;
+    }
+
+    /// Record a signal the engine declined to act on
+    fn suppress(&mut self, signal: &Signal, reason: String) {
+        self.suppressed_signals.push(SuppressedSignal {
+            timestamp: signal.timestamp,
+            signal_type: signal.signal_type,
+            symbol: signal.symbol.clone(),
+            price: signal.price,
+            rsi: signal.rsi,
+            reason,
+        });
+    }
+}
+
+/// What a run reads but never changes, fixed as it starts: a copy of the
+/// parameters, the engine's settings and the schedules derived from the
+/// bars. The per-bar logic works off the context, never the engine, and
+/// only ever borrows it shared, so the parameters cannot shift under a run
+/// and shadow runs share nothing mutable.
+struct RunContext<'a> {
+    params: BacktestParameters,
+    /// Why `params` fail validation. Deliberately not an error: the run goes
+    /// ahead on them and reports this as an `InvalidParameters` warning, so
+    /// exploratory and legacy parameter sets still produce results.
+    invalid_params: Option<String>,
+    entry_schedule: Option<&'a [bool]>,
+    execution_seed: Option<u64>,
+    data_source: Option<&'a str>,
+    spill_dir: Option<&'a Path>,
+    spill_overwrite: Overwrite,
+    pre_trade_checks: &'a PreTradeChecks,
+    /// Date range of each bar; bars between ranges are never traded
+    ranges: Vec<Option<usize>>,
+    /// Scheduled cash flows in date order
+    cash_flows: Vec<(NaiveDate, f64)>,
+}
+
+impl<'a> RunContext<'a> {
+    fn new(engine: &'a BacktestEngine, bars: &[Bar]) -> Self {
+        let params = engine.params.clone();
+        let mut cash_flows = params.cash_flows.clone().unwrap_or_default();
+        cash_flows.sort_by_key(|(date, _)| *date);
+        Self {
+            invalid_params: params.validate().err().map(|e| e.to_string()),
+            entry_schedule: engine.entry_schedule.as_deref(),
+            execution_seed: engine.execution_seed,
+            data_source: engine.data_source.as_deref(),
+            spill_dir: engine.spill_dir.as_deref(),
+            spill_overwrite: engine.spill_overwrite,
+            pre_trade_checks: &engine.pre_trade_checks,
+            ranges: bars
+                .iter()
+                .map(|b| params.date_range_index(b.timestamp.date_naive()))
+                .collect(),
+            cash_flows,
+            params,
+        }
+    }
+
+    /// Warnings about the parameters themselves
+    fn parameter_warnings(&self) -> Vec<DataWarning> {
+        let mut warnings: Vec<DataWarning> = self
+            .invalid_params
+            .iter()
+            .map(|reason| DataWarning {
+                kind: DataWarningKind::InvalidParameters,
+                timestamp: None,
+                message: format!("ran with parameters that fail validation: {}", reason),
+            })
+            .collect();
+        let execution = &self.params.execution;
+        if execution.simulation.enabled && execution.slippage_pct != 0.0 {
+            warnings.push(DataWarning {
+                kind: DataWarningKind::FlatSlippageIgnored,
+                timestamp: None,
+                message: format!(
+                    "slippage_pct {} ignored: the execution simulation models slippage",
+                    execution.slippage_pct
+                ),
+            });
+        }
+        warnings
+    }
+}
 
 /// High-performance backtest engine
 pub struct BacktestEngine {
     params: BacktestParameters,
+    /// Bars on which long entries are forced, replacing the entry rule
+    entry_schedule: Option<Vec<bool>>,
+    /// Seed for the execution simulator's random draws; fresh entropy per
+    /// run when unset
+    execution_seed: Option<u64>,
+    /// Where the bars come from, recorded in the result's manifest
+    data_source: Option<String>,
+    /// Directory to stream trades, equity, signals and fills to
+    spill_dir: Option<PathBuf>,
+    /// Whether the spill may replace files an earlier run left there
+    spill_overwrite: Overwrite,
+    /// Directory caching indicator series between runs
+    indicator_cache: Option<PathBuf>,
+    /// Built-in checks from the risk parameters, then those added
+    pre_trade_checks: PreTradeChecks,
+    /// Process every Nth bar; 1 is every bar
+    bar_stride: usize,
 }
 
 impl BacktestEngine {
     pub fn new(params: BacktestParameters) -> Self {
-        Self { params }
+        Self {
+            pre_trade_checks: PreTradeChecks::from_risk(&params.risk),
+            params,
+            entry_schedule: None,
+            execution_seed: None,
+            data_source: None,
+            spill_dir: None,
+            spill_overwrite: Overwrite::Refuse,
+            indicator_cache: None,
+            bar_stride: 1,
+        }
+    }
+
+    /// Enter long on the bars flagged in `schedule` (indexed like the bars)
+    /// instead of on RSI entry signals. Exits, stops and entry guards are
+    /// unchanged, so an entry scheduled while holding is simply skipped.
+    pub fn with_entry_schedule(mut self, schedule: Vec<bool>) -> Self {
+        self.entry_schedule = Some(schedule);
+        self
+    }
+
+    /// Make realistic execution (slippage, rejections) reproducible
+    pub fn with_execution_seed(mut self, seed: u64) -> Self {
+        self.execution_seed = Some(seed);
+        self
+    }
+
+    /// Describe where the bars come from (file paths, generator settings)
+    /// for the result's manifest
+    pub fn with_data_source(mut self, description: impl Into<String>) -> Self {
+        self.data_source = Some(description.into());
+        self
+    }
+
+    /// Stream trades, equity points, signals and fills to JSON lines files
+    /// in `dir` as the run goes, keeping only running metrics (see
+    /// [`crate::sink`]). The result's trades, signals, fills and curves are
+    /// then empty and its `spill` summary says where they went. Runs with
+    /// cash flows or trade context need the whole run and stay in memory,
+    /// with a warning, as do runs whose files would replace an earlier
+    /// run's unless `overwrite` is [`Overwrite::Force`].
+    pub fn with_spill_dir(mut self, dir: impl Into<PathBuf>, overwrite: Overwrite) -> Self {
+        self.spill_dir = Some(dir.into());
+        self.spill_overwrite = overwrite;
+        self
+    }
+
+    /// Read the indicator series from `dir` when a run over the same bars
+    /// with the same indicator settings cached them there, and cache them
+    /// there otherwise (see [`IndicatorSeries::load_or_compute`]). Only
+    /// [`BacktestEngine::run`] uses the cache; one that cannot be used is
+    /// recomputed, with a warning.
+    pub fn with_indicator_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.indicator_cache = Some(dir.into());
+        self
+    }
+
+    /// Review every entry with `check` after the checks already added (see
+    /// [`crate::pretrade`])
+    pub fn with_pre_trade_check(mut self, check: impl PreTradeCheck + 'static) -> Self {
+        self.pre_trade_checks.push(std::sync::Arc::new(check));
+        self
+    }
+
+    /// Review every entry with `checks` after the checks already added
+    pub fn with_pre_trade_checks(mut self, checks: &PreTradeChecks) -> Self {
+        self.pre_trade_checks.extend(checks);
+        self
+    }
+
+    /// Run on every `stride`th bar only, indicators included, for a quick
+    /// coarse estimate; the result is marked `approximate`. Bar counts in
+    /// the parameters (periods, holding days) then count strided bars.
+    /// Only [`BacktestEngine::run`] strides.
+    pub fn with_bar_stride(mut self, stride: usize) -> Self {
+        self.bar_stride = stride.max(1);
+        self
     }
 
-    /// Run backtest on provided bar data
+    /// Run backtest on provided bar data. Parameters that fail validation
+    /// are not rejected: the run uses them and warns.
     pub fn run(&self, bars: &[Bar], hedge_bars: Option<&[Bar]>) -> BacktestResult {
+        if self.bar_stride > 1 {
+            let stride = |bars: &[Bar]| -> Vec<Bar> {
+                bars.iter().step_by(self.bar_stride).cloned().collect()
+            };
+            let (bars, hedge_bars) = (stride(bars), hedge_bars.map(stride));
+            let (indicators, cache_warning) = self.indicators(&bars);
+            let mut result = self.run_with_indicators(&bars, hedge_bars.as_deref(), &indicators);
+            result.warnings.extend(cache_warning);
+            result.approximate = true;
+            return result;
+        }
+
+        // Calculate all indicators upfront (vectorized)
+        let (indicators, cache_warning) = self.indicators(bars);
+        let mut result = self.run_with_indicators(bars, hedge_bars, &indicators);
+        result.warnings.extend(cache_warning);
+        result
+    }
+
+    /// Indicator series of `bars` for these parameters, through the cache
+    /// when there is one, with a warning when it could not be used
+    fn indicators(&self, bars: &[Bar]) -> (IndicatorSeries, Option<DataWarning>) {
+        let Some(dir) = &self.indicator_cache else {
+            return (indicators_for(bars, &self.params), None);
+        };
+        let config = IndicatorConfig::from(&self.params);
+        let (series, outcome) = IndicatorSeries::load_or_compute(bars, &config, dir);
+        let warning = match outcome {
+            CacheOutcome::Failed(message) => Some(DataWarning {
+                kind: DataWarningKind::IndicatorCacheUnavailable,
+                timestamp: None,
+                message,
+            }),
+            CacheOutcome::Hit | CacheOutcome::Computed => None,
+        };
+        (
+            with_strategy_indicators(series, bars, &self.params),
+            warning,
+        )
+    }
+
+    /// Run backtest with indicators computed beforehand, so several runs
+    /// that differ only in rules (not indicator periods) can share them
+    pub fn run_with_indicators(
+        &self,
+        bars: &[Bar],
+        hedge_bars: Option<&[Bar]>,
+        indicators: &IndicatorSeries,
+    ) -> BacktestResult {
         let start_time = Instant::now();
+        let ctx = RunContext::new(self, bars);
 
         // Minimum data check
-        let warmup = self.params.sma_period.max(self.params.bb_period);
+        let warmup = ctx.params.warmup_bars();
         if bars.len() < warmup + 1 {
-            return self.empty_result(bars);
+            return ctx.empty_result(bars, hedge_bars);
         }
 
-        // Extract price data
+        // Calculate volatility for each bar (for execution simulation)
         let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
-        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
-        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        let volatilities = calculate_volatilities(&closes, 20);
 
-        // Calculate all indicators upfront (vectorized)
-        let indicators = IndicatorSeries::calculate(
-            &closes,
-            &highs,
-            &lows,
-            self.params.rsi_period,
-            self.params.sma_period,
-            self.params.bb_period,
-            self.params.bb_std_dev,
-            14, // ATR period
+        let mut sim = ctx.simulation(bars, indicators);
+        for i in warmup..bars.len() {
+            ctx.step(&mut sim, bars, hedge_bars, &volatilities, i);
+        }
+        ctx.finish(sim, bars, hedge_bars, start_time)
+    }
+
+    /// Run `primary` and each shadow configuration in a single pass over the
+    /// bars, with independent portfolios. Indicator series are computed once
+    /// per distinct set of indicator settings. Results are in input order,
+    /// primary first, and match standalone runs of each configuration.
+    pub fn run_with_shadows(
+        bars: &[Bar],
+        hedge_bars: Option<&[Bar]>,
+        primary: BacktestParameters,
+        shadows: Vec<BacktestParameters>,
+    ) -> Vec<BacktestResult> {
+        let start_time = Instant::now();
+        let engines: Vec<BacktestEngine> = std::iter::once(primary)
+            .chain(shadows)
+            .map(BacktestEngine::new)
+            .collect();
+        let contexts: Vec<RunContext> = engines.iter().map(|e| RunContext::new(e, bars)).collect();
+
+        let mut series: Vec<(IndicatorKey, IndicatorSeries)> = Vec::new();
+        let series_index: Vec<usize> = contexts
+            .iter()
+            .map(|ctx| {
+                let key = IndicatorKey::of(&ctx.params);
+                series
+                    .iter()
+                    .position(|(k, _)| *k == key)
+                    .unwrap_or_else(|| {
+                        series.push((key, indicators_for(bars, &ctx.params)));
+                        series.len() - 1
+                    })
+            })
+            .collect();
+
+        let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+        let volatilities = calculate_volatilities(&closes, 20);
+
+        // Configurations without enough data get empty results, as standalone
+        let mut sims: Vec<Option<Simulation>> = contexts
+            .iter()
+            .zip(&series_index)
+            .map(|(ctx, &index)| {
+                (bars.len() > ctx.params.warmup_bars())
+                    .then(|| ctx.simulation(bars, &series[index].1))
+            })
+            .collect();
+
+        for i in 0..bars.len() {
+            for (ctx, sim) in contexts.iter().zip(sims.iter_mut()) {
+                if let Some(sim) = sim.as_mut().filter(|_| i >= ctx.params.warmup_bars()) {
+                    ctx.step(sim, bars, hedge_bars, &volatilities, i);
+                }
+            }
+        }
+
+        contexts
+            .iter()
+            .zip(sims)
+            .map(|(ctx, sim)| match sim {
+                Some(sim) => ctx.finish(sim, bars, hedge_bars, start_time),
+                None => ctx.empty_result(bars, hedge_bars),
+            })
+            .collect()
+    }
+}
+
+impl RunContext<'_> {
+    /// Fresh simulation state for the run's parameters
+    fn simulation<'a>(&self, bars: &[Bar], indicators: &'a IndicatorSeries) -> Simulation<'a> {
+        let mut portfolio = Portfolio::new_with_positions(
+            self.params.initial_capital,
+            self.params.initial_positions.clone(),
         );
+        if self.params.precise_accounting {
+            portfolio = portfolio.with_precise_accounting();
+        }
+        let (spill, spill_warning) = self.open_spill();
+        let avg_volumes = if self.params.risk.liquidity_aware_sizing {
+            let volumes: Vec<u64> = bars.iter().map(|b| b.volume).collect();
+            calculate_avg_volume(&volumes, LIQUIDITY_AVG_PERIOD)
+        } else {
+            Vec::new()
+        };
 
-        // Initialize components
-        let mut portfolio = Portfolio::new(self.params.initial_capital);
-        let signal_generator = SignalGenerator::new(&self.params);
-        let mut execution_sim = ExecutionSimulator::new(self.params.execution.clone());
+        Simulation {
+            indicators,
+            portfolio,
+            signal_generator: SignalGenerator::new(&self.params.strategy, &self.params.hedge),
+            execution_sim: match self.execution_seed {
+                Some(seed) => {
+                    ExecutionSimulator::with_seed(self.params.execution.simulation.clone(), seed)
+                }
+                None => ExecutionSimulator::new(self.params.execution.simulation.clone()),
+            }
+            .with_flat_slippage(self.params.execution.slippage_pct),
+            state: RunState::new(&self.params),
+            avg_volumes,
+            quality: if self.params.risk.min_data_quality > 0.0 {
+                data_quality(bars)
+            } else {
+                Vec::new()
+            },
+            equity_curve: Vec::with_capacity(bars.len()),
+            heat_curve: Vec::with_capacity(bars.len()),
+            net_exposure_curve: Vec::with_capacity(bars.len()),
+            in_range: Vec::with_capacity(bars.len()),
+            next_flow: 0,
+            booked_flows: Vec::new(),
+            spill,
+            spill_warning,
+        }
+    }
 
-        // Calculate volatility for each bar (for execution simulation)
-        let volatilities = self.calculate_volatilities(&closes, 20);
+    /// The run's spill, or why it has none though a directory is set
+    fn open_spill(&self) -> (Option<Spill>, Option<String>) {
+        let Some(dir) = &self.spill_dir else {
+            return (None, None);
+        };
+        let has_flows = self
+            .params
+            .cash_flows
+            .as_ref()
+            .is_some_and(|f| !f.is_empty());
+        if has_flows || self.params.record_trade_context {
+            let message = format!(
+                "kept the run in memory rather than spilling to {}: \
+                 cash flows and trade context need every point",
+                dir.display()
+            );
+            return (None, Some(message));
+        }
+        match Spill::create(dir, self.params.initial_capital, self.spill_overwrite) {
+            Ok(spill) => (Some(spill), None),
+            Err(e) => {
+                let message = format!(
+                    "kept the run in memory: cannot spill to {}: {}",
+                    dir.display(),
+                    e
+                );
+                (None, Some(message))
+            }
+        }
+    }
+
+    /// Hand the trades closed, signals stamped and fills made since the last
+    /// call to the spill, keeping the trades adaptive sizing looks back on
+    fn drain_spill(&self, sim: &mut Simulation, bars: &[Bar], hedge_bars: Option<&[Bar]>) {
+        let Some(spill) = sim.spill.as_mut() else {
+            return;
+        };
+        let keep = self
+            .params
+            .risk
+            .adaptive_sizing
+            .as_ref()
+            .map_or(0, |s| s.lookback_trades);
+        let edge_filter = &sim.state.edge_filter;
+        let mut trades = sim.portfolio.spill_trades(keep);
+        efficiency(&mut trades, bars, hedge_bars);
+        spill.record_trades(trades, |trade| {
+            let notional = trade.entry_price * trade.quantity;
+            let cost = edge_filter.round_trip_cost_pct(notional) / 100.0 * notional;
+            let gap = match trade.side {
+                Side::HedgeSell => {
+                    hedge_bars.and_then(|h| MetricsCalculator::worst_overnight_gap(trade, h))
+                }
+                _ => MetricsCalculator::worst_overnight_gap(trade, bars),
+            };
+            (cost, gap)
+        });
+        let (signals, fills) = sim.state.audit.take_records();
+        spill.record_audit(signals, fills);
+    }
 
-        // Equity curve tracking
-        let mut equity_curve: Vec<(DateTime<Utc>, f64)> = Vec::with_capacity(bars.len());
+    /// Simulate bar `i`
+    fn step(
+        &self,
+        sim: &mut Simulation,
+        bars: &[Bar],
+        hedge_bars: Option<&[Bar]>,
+        volatilities: &[f64],
+        i: usize,
+    ) {
+        let bar = &bars[i];
+        let hedge_bar = hedge_bars.and_then(|h| h.get(i));
+        let volatility = volatilities.get(i).copied();
+        sim.state.low_data_quality = sim
+            .quality
+            .get(i)
+            .is_some_and(|q| q.score < self.params.risk.min_data_quality);
 
-        // Run simulation
-        for i in warmup..bars.len() {
-            let bar = &bars[i];
-            let hedge_bar = hedge_bars.and_then(|h| h.get(i));
-            let volatility = volatilities.get(i).copied();
+        if self.ranges[i].is_none() {
+            // Between ranges: out of the market, equity stays flat
+            self.book_cash_flows(sim, bar, None, 50.0);
+            if !self.params.omit_range_gaps {
+                sim.record_point(bar.timestamp, false, &self.params);
+            }
+            self.drain_spill(sim, bars, hedge_bars);
+            return;
+        }
 
-            // Get indicator values for this bar
-            let mut ind_values = indicators.get(i);
-            ind_values.vwap = bar.vwap;
-            if i > 0 {
-                ind_values.prev_high = Some(bars[i - 1].high);
-                ind_values.prev_low = Some(bars[i - 1].low);
+        // Get indicator values for this bar
+        let mut ind_values = sim.indicators.get(i);
+        ind_values.vwap = bar.vwap.or(ind_values.vwap);
+        ind_values.avg_volume = sim.avg_volumes.get(i).copied().flatten();
+        ind_values.bars_held = sim
+            .portfolio
+            .current_position()
+            .map(|p| i.saturating_sub(bars.partition_point(|b| b.timestamp < p.entry_date)));
+        if i > 0 {
+            ind_values.prev_high = Some(bars[i - 1].high);
+            ind_values.prev_low = Some(bars[i - 1].low);
+        }
+        sim.signal_generator.observe(bar, &ind_values);
+
+        if bar.halted || bar.volume < self.params.execution.min_bar_volume {
+            // Halted bar: nothing executes and pending orders roll forward.
+            // Flagged halts always reopen with the stop checked at the open.
+            sim.state.halted_bars += 1;
+            if sim.portfolio.has_position()
+                && (bar.halted
+                    || self.params.execution.halted_stop_policy == HaltedStopPolicy::NextOpen)
+            {
+                sim.state.stop_check_at_open = true;
+            }
+            self.suppress_on_halt(
+                &mut sim.state,
+                &sim.portfolio,
+                &sim.signal_generator,
+                bar,
+                &ind_values,
+            );
+        } else if sim.state.stop_check_at_open
+            && self.stop_at_open_after_halt(&mut sim.portfolio, &mut sim.state, bar)
+        {
+            // Stop filled at the open of the first tradable bar after a halt
+            sim.state.stop_check_at_open = false;
+        } else {
+            sim.state.stop_check_at_open = false;
+
+            if let Some((reason, rsi, new_entry)) = sim.state.reopen_long.take() {
+                self.reopen_at_open(
+                    &mut sim.portfolio,
+                    &mut sim.state,
+                    &mut sim.execution_sim,
+                    bar,
+                    &ind_values,
+                    i,
+                    volatility,
+                    (&reason, rsi),
+                    new_entry,
+                );
+            }
+
+            if self.params.execution.use_bracket_orders {
+                self.process_bracket(&mut sim.portfolio, &mut sim.state, bar, i, &ind_values);
             }
 
             // Process any pending orders from latency simulation
-            self.process_pending_orders(&mut portfolio, &mut execution_sim, bar, hedge_bar, i, volatility);
+            self.process_pending_orders(
+                &mut sim.portfolio,
+                &mut sim.state,
+                &mut sim.execution_sim,
+                bar,
+                hedge_bar,
+                i,
+                (ind_values.rsi, ind_values.atr),
+                volatility,
+            );
+
+            if ind_values.anomaly {
+                self.on_anomaly(&mut sim.portfolio, &mut sim.state, bar, i);
+            }
 
             // Generate and execute signals
             self.process_signals(
-                &mut portfolio,
-                &signal_generator,
-                &mut execution_sim,
+                &mut sim.portfolio,
+                &mut sim.state,
+                &sim.signal_generator,
+                &mut sim.execution_sim,
                 bar,
                 hedge_bar,
                 &ind_values,
@@ -86,379 +784,3305 @@ impl BacktestEngine {
                 volatility,
             );
 
-            // Update portfolio prices
-            portfolio.update_prices(bar.close, hedge_bar.map(|h| h.close));
+            if self.params.risk.overnight_flat && sim.portfolio.has_position() {
+                self.flatten_overnight(
+                    &mut sim.portfolio,
+                    &mut sim.state,
+                    &mut sim.execution_sim,
+                    bar,
+                    &ind_values,
+                    volatility,
+                );
+            }
+        }
+
+        // Update portfolio prices
+        sim.portfolio
+            .update_prices(bar.close, hedge_bar.map(|h| h.close));
 
-            // Record equity
-            equity_curve.push((bar.timestamp, portfolio.equity()));
+        let range_ends = self.ranges.get(i + 1).copied().flatten() != self.ranges[i];
+        if self.params.date_ranges.is_some() && range_ends {
+            let rsi = sim.indicators.rsi.get(i).copied().unwrap_or(50.0);
+            self.close_all(
+                &mut sim.portfolio,
+                &mut sim.state,
+                &mut sim.execution_sim,
+                bar,
+                hedge_bar,
+                (RANGE_END, rsi),
+            );
+        } else if sim.state.low_data_quality && self.params.risk.close_on_low_data_quality {
+            let rsi = sim.indicators.rsi.get(i).copied().unwrap_or(50.0);
+            self.close_all(
+                &mut sim.portfolio,
+                &mut sim.state,
+                &mut sim.execution_sim,
+                bar,
+                hedge_bar,
+                (LOW_DATA_QUALITY, rsi),
+            );
         }
 
+        let rsi = sim.indicators.rsi.get(i).copied().unwrap_or(50.0);
+        self.book_cash_flows(sim, bar, hedge_bar, rsi);
+
+        sim.state.observe_holdings(&sim.portfolio, i, rsi);
+
+        // Record equity
+        sim.state.equity_peak = sim.state.equity_peak.max(sim.portfolio.equity());
+        sim.record_point(bar.timestamp, true, &self.params);
+        self.drain_spill(sim, bars, hedge_bars);
+    }
+
+    /// Close what is still open and compute the result
+    fn finish(
+        &self,
+        mut sim: Simulation,
+        bars: &[Bar],
+        hedge_bars: Option<&[Bar]>,
+        start_time: Instant,
+    ) -> BacktestResult {
         // Close any remaining positions at end
         if let Some(last_bar) = bars.last() {
-            if portfolio.has_position() {
-                portfolio.close_position(last_bar.close, last_bar.timestamp, "end of backtest", 0.0);
+            let last_rsi = sim.indicators.rsi.last().copied().unwrap_or(50.0);
+            if sim.portfolio.has_position() {
+                sim.portfolio.close_position(
+                    last_bar.close,
+                    last_bar.timestamp,
+                    "end of backtest",
+                    0.0,
+                );
+                sim.portfolio.annotate_last_exit(last_rsi);
+                let order_id = sim.state.audit.place(None);
+                self.audit_exit(&mut sim.state, &mut sim.portfolio, order_id, Vec::new());
             }
-            if portfolio.has_hedge_position() {
+            if sim.portfolio.has_hedge_position() {
                 if let Some(hedge_bar) = hedge_bars.and_then(|h| h.last()) {
-                    portfolio.close_hedge_position(
+                    sim.portfolio.close_hedge_position(
                         hedge_bar.close,
                         hedge_bar.timestamp,
                         "end of backtest",
                         0.0,
                     );
+                    sim.portfolio.annotate_last_exit(last_rsi);
+                    let order_id = sim.state.audit.place(None);
+                    self.audit_exit(&mut sim.state, &mut sim.portfolio, order_id, Vec::new());
                 }
             }
+            if sim.portfolio.has_synthetic_short() {
+                sim.portfolio.close_synthetic_short(
+                    last_bar.close,
+                    last_bar.timestamp,
+                    "end of backtest",
+                    0.0,
+                );
+                sim.portfolio.annotate_last_exit(last_rsi);
+                let order_id = sim.state.audit.place(None);
+                self.audit_exit(&mut sim.state, &mut sim.portfolio, order_id, Vec::new());
+            }
         }
+        self.drain_spill(&mut sim, bars, hedge_bars);
+        let spilled = sim.spill.take().map(Spill::finish);
+        let (signals, fills) = std::mem::take(&mut sim.state.audit).into_records();
 
-        // Calculate metrics
-        let trades = portfolio.trades().to_vec();
-        let metrics =
-            MetricsCalculator::calculate(&equity_curve, &trades, self.params.initial_capital);
-        let drawdown_curve = MetricsCalculator::calculate_drawdown_curve(&equity_curve);
+        // Calculate metrics over in-range bars only, so gaps neither dilute
+        // volatility nor stretch the annualization period
+        let mut trades = match spilled {
+            Some(_) => Vec::new(),
+            None => sim.portfolio.trades().to_vec(),
+        };
+        efficiency(&mut trades, bars, hedge_bars);
+        if self.params.record_trade_context {
+            attach_trade_context(&mut trades, bars, sim.indicators);
+        }
+        let ranged = |curve: &[(DateTime<Utc>, f64)]| -> Vec<(DateTime<Utc>, f64)> {
+            curve
+                .iter()
+                .zip(&sim.in_range)
+                .filter(|(_, inside)| **inside)
+                .map(|(point, _)| *point)
+                .collect()
+        };
+        let (metric_equity, metric_heat, metric_exposure) = if self.params.date_ranges.is_some() {
+            (
+                ranged(&sim.equity_curve),
+                ranged(&sim.heat_curve),
+                ranged(&sim.net_exposure_curve),
+            )
+        } else {
+            (
+                sim.equity_curve.clone(),
+                sim.heat_curve.clone(),
+                sim.net_exposure_curve.clone(),
+            )
+        };
+        let exposure_pcts: Vec<f64> = metric_equity
+            .iter()
+            .zip(&metric_exposure)
+            .map(|((_, equity), (_, net))| net_exposure_pct(*net, *equity))
+            .collect();
+        // With external cash flows, return metrics are time-weighted
+        let flows = &sim.booked_flows;
+        let time_weighted = |curve: &[(DateTime<Utc>, f64)]| {
+            MetricsCalculator::time_weighted_curve(curve, flows, self.params.initial_capital)
+        };
+        let performance = if flows.is_empty() {
+            metric_equity.clone()
+        } else {
+            time_weighted(&metric_equity)
+        };
+        // Trade statistics over a subset of the trades; the equity-curve
+        // figures are the whole portfolio's whichever subset it is
+        let scoped = |trades: &[Trade]| {
+            let mut metrics =
+                MetricsCalculator::calculate(&performance, trades, self.params.initial_capital);
+            if !flows.is_empty() {
+                MetricsCalculator::apply_cash_flows(
+                    &mut metrics,
+                    &metric_equity,
+                    flows,
+                    self.params.initial_capital,
+                );
+            }
+            metrics.suppressed_entries = sim.state.suppressed_signals.len() as u32;
+            metrics.liquidity_capped_entries = sim.state.liquidity_capped;
+            let costs: Vec<f64> = trades
+                .iter()
+                .map(|t| {
+                    let notional = t.entry_price * t.quantity;
+                    sim.state.edge_filter.round_trip_cost_pct(notional) / 100.0 * notional
+                })
+                .collect();
+            MetricsCalculator::apply_costs(&mut metrics, trades, &costs);
+            MetricsCalculator::apply_overnight_gaps(&mut metrics, trades, bars, hedge_bars);
+            if !metric_heat.is_empty() {
+                metrics.max_heat_pct = metric_heat.iter().map(|(_, h)| *h).fold(0.0, f64::max);
+                metrics.avg_heat_pct =
+                    metric_heat.iter().map(|(_, h)| h).sum::<f64>() / metric_heat.len() as f64;
+            }
+            if !exposure_pcts.is_empty() {
+                metrics.max_net_exposure_pct =
+                    exposure_pcts.iter().copied().fold(0.0, largest_either_way);
+                metrics.avg_net_exposure_pct =
+                    exposure_pcts.iter().sum::<f64>() / exposure_pcts.len() as f64;
+            }
+            metrics
+        };
+        let (metrics, hedge_metrics, combined_metrics) = match &spilled {
+            Some(spilled) => self.spilled_metrics(spilled, &sim.state),
+            None => {
+                let (hedge_trades, main_trades): (Vec<Trade>, Vec<Trade>) =
+                    trades.iter().cloned().partition(Trade::is_hedge);
+                let combined_metrics = scoped(&trades);
+                let metrics = match self.metrics_scope() {
+                    MetricsScope::Main if !hedge_trades.is_empty() => scoped(&main_trades),
+                    _ => combined_metrics.clone(),
+                };
+                if hedge_trades.is_empty() {
+                    (metrics, None, None)
+                } else {
+                    (metrics, Some(scoped(&hedge_trades)), Some(combined_metrics))
+                }
+            }
+        };
+        let drawdown_curve = if flows.is_empty() {
+            MetricsCalculator::calculate_drawdown_curve(&sim.equity_curve)
+        } else {
+            MetricsCalculator::calculate_drawdown_curve(&time_weighted(&sim.equity_curve))
+        };
 
         let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
+        let mut spill_warnings: Vec<String> = sim.spill_warning.take().into_iter().collect();
+        if let Some(Spilled {
+            summary,
+            error: Some(e),
+            ..
+        }) = &spilled
+        {
+            spill_warnings.push(format!(
+                "stopped spilling to {} after a write error, its files are incomplete: {}",
+                summary.dir.display(),
+                e
+            ));
+        }
+
         BacktestResult {
             metrics,
-            equity_curve,
+            metrics_scope: self.metrics_scope(),
+            hedge_metrics,
+            combined_metrics,
+            equity_curve: sim.equity_curve,
             drawdown_curve,
+            heat_curve: sim.heat_curve,
+            net_exposure_curve: sim.net_exposure_curve,
             trades,
+            signals,
+            fills,
+            suppressed_signals: sim.state.suppressed_signals,
+            pre_trade_events: sim.state.pre_trade_events,
+            halted_bars: sim.state.halted_bars,
+            warnings: {
+                let mut warnings = validate_bars(bars, &self.params.execution);
+                warnings.extend(low_quality_warnings(
+                    &sim.quality,
+                    self.params.risk.min_data_quality,
+                    self.params.risk.close_on_low_data_quality,
+                ));
+                warnings.extend(self.parameter_warnings());
+                warnings.extend(spill_warnings.into_iter().map(|message| DataWarning {
+                    kind: DataWarningKind::SpillUnavailable,
+                    timestamp: None,
+                    message,
+                }));
+                warnings
+            },
             start_date: bars.first().unwrap().timestamp.date_naive(),
             end_date: bars.last().unwrap().timestamp.date_naive(),
             initial_capital: self.params.initial_capital,
-            final_equity: portfolio.equity(),
+            final_equity: sim.portfolio.equity(),
+            sizing_mode: self.params.risk.sizing_mode,
             execution_time_ms,
+            manifest: self.manifest(bars, hedge_bars),
+            spill: spilled.map(|spilled| spilled.summary),
+            approximate: false,
         }
     }
 
-    /// Process signals and execute trades
-    fn process_signals(
+    /// Headline, hedge and combined metrics of a spilled run, scoped as
+    /// those of a run held in memory
+    fn spilled_metrics(
         &self,
-        portfolio: &mut Portfolio,
-        signal_generator: &SignalGenerator,
-        execution_sim: &mut ExecutionSimulator,
-        bar: &Bar,
-        hedge_bar: Option<&Bar>,
-        indicators: &IndicatorValues,
-        bar_index: usize,
-        volatility: Option<f64>,
+        spilled: &Spilled,
+        state: &RunState,
+    ) -> (
+        PerformanceMetrics,
+        Option<PerformanceMetrics>,
+        Option<PerformanceMetrics>,
     ) {
-        // Check for stop loss first
-        if portfolio.has_position() && portfolio.check_stop_loss(bar.close) {
-            let exec_result = execution_sim.simulate_execution(bar, Side::Sell, 0.0, volatility);
-            let exit_price = if exec_result.executed {
-                exec_result.fill_price
-            } else {
-                bar.close
-            };
-            portfolio.close_position(exit_price, bar.timestamp, "stop loss", self.params.commission);
-            return;
+        let counted = |metrics: &PerformanceMetrics| PerformanceMetrics {
+            suppressed_entries: state.suppressed_signals.len() as u32,
+            liquidity_capped_entries: state.liquidity_capped,
+            ..metrics.clone()
+        };
+        let combined = counted(&spilled.combined);
+        let metrics = match self.metrics_scope() {
+            MetricsScope::Main if spilled.hedge_trades > 0 => counted(&spilled.main),
+            _ => combined.clone(),
+        };
+        if spilled.hedge_trades == 0 {
+            (metrics, None, None)
+        } else {
+            (metrics, Some(counted(&spilled.hedge)), Some(combined))
         }
+    }
 
-        // Generate signal
-        let signal = signal_generator.generate(
-            bar,
-            indicators,
-            portfolio.has_position(),
-            portfolio.current_position(),
-            portfolio.has_hedge_position(),
-        );
+    /// Trades the headline metrics cover: all of them once the inverse side
+    /// trades in its own right, as its trades are then no hedge
+    fn metrics_scope(&self) -> MetricsScope {
+        if self.params.hedge.inverse_primary() {
+            MetricsScope::Combined
+        } else {
+            self.params.metrics_scope
+        }
+    }
 
-        if let Some(sig) = signal {
-            match sig.signal_type {
-                SignalType::Buy => {
-                    if execution_sim.has_latency() {
-                        // Queue order for delayed execution
-                        let quantity = portfolio.calculate_position_size(
-                            bar.close,
-                            self.params.position_size_pct,
-                            self.params.cash_reserve_pct,
-                        );
-                        if quantity >= 1.0 {
-                            execution_sim.queue_order(
-                                self.params.symbol.clone(),
-                                Side::Buy,
-                                quantity,
-                                bar_index,
-                            );
-                        }
-                    } else {
-                        self.execute_buy(portfolio, execution_sim, bar, indicators, volatility);
-                    }
-                }
-                SignalType::Sell => {
-                    let exec_result = execution_sim.simulate_execution(bar, Side::Sell, 0.0, volatility);
-                    let exit_price = if exec_result.executed {
-                        exec_result.fill_price
-                    } else {
-                        bar.close
-                    };
-                    portfolio.close_position(
-                        exit_price,
-                        bar.timestamp,
-                        &sig.reason,
-                        self.params.commission,
-                    );
-                }
-                SignalType::HedgeBuy => {
-                    if let Some(hbar) = hedge_bar {
-                        if execution_sim.has_latency() {
-                            let quantity = portfolio.calculate_position_size(
-                                hbar.close,
-                                self.params.short_position_size_pct,
-                                self.params.cash_reserve_pct,
-                            );
-                            if quantity >= 1.0 {
-                                execution_sim.queue_order(
-                                    self.params.inverse_symbol.clone(),
-                                    Side::HedgeBuy,
-                                    quantity,
-                                    bar_index,
-                                );
-                            }
-                        } else {
-                            self.execute_hedge_buy(portfolio, execution_sim, hbar, volatility);
-                        }
-                    }
-                }
-                SignalType::HedgeSell => {
-                    if let Some(hbar) = hedge_bar {
-                        let exec_result = execution_sim.simulate_execution(hbar, Side::HedgeSell, 0.0, volatility);
-                        let exit_price = if exec_result.executed {
-                            exec_result.fill_price
-                        } else {
-                            hbar.close
-                        };
-                        portfolio.close_hedge_position(
-                            exit_price,
-                            hbar.timestamp,
-                            &sig.reason,
-                            self.params.commission,
-                        );
-                    }
-                }
-                _ => {}
-            }
+    /// What produced a result from these bars
+    fn manifest(&self, bars: &[Bar], hedge_bars: Option<&[Bar]>) -> RunManifest {
+        RunManifest {
+            parameters: self.params.clone(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_hash: option_env!("BACKTEST_GIT_HASH").map(str::to_string),
+            data: DataSource::from_bars(bars)
+                .with_description(self.data_source.map(str::to_string)),
+            hedge_data: hedge_bars.map(DataSource::from_bars),
+            execution_seed: self.execution_seed,
+            hostname: manifest::hostname(),
+            created_at: Utc::now(),
         }
     }
 
-    /// Execute buy order with realistic execution simulation
-    fn execute_buy(
+    /// Close everything at the bar's close and drop working orders: on the
+    /// last bar of a date range, so nothing carries over into the gap, or
+    /// on a low data quality bar
+    fn close_all(
         &self,
         portfolio: &mut Portfolio,
+        state: &mut RunState,
         execution_sim: &mut ExecutionSimulator,
         bar: &Bar,
-        _indicators: &IndicatorValues,
-        volatility: Option<f64>,
+        hedge_bar: Option<&Bar>,
+        (reason, rsi): (&str, f64),
     ) {
-        let quantity = portfolio.calculate_position_size(
-            bar.close,
-            self.params.position_size_pct,
-            self.params.cash_reserve_pct,
-        );
-
-        if quantity < 1.0 {
-            return;
+        if portfolio.has_position() {
+            portfolio.close_position(
+                bar.close,
+                bar.timestamp,
+                reason,
+                self.params.execution.commission,
+            );
+            portfolio.annotate_last_exit(rsi);
+            let order_id = state.audit.place(None);
+            self.audit_exit(state, portfolio, order_id, Vec::new());
         }
+        if let Some(hbar) = hedge_bar.filter(|_| portfolio.has_hedge_position()) {
+            portfolio.close_hedge_position(
+                hbar.close,
+                hbar.timestamp,
+                reason,
+                self.params.execution.commission,
+            );
+            portfolio.annotate_last_exit(rsi);
+            let order_id = state.audit.place(None);
+            self.audit_exit(state, portfolio, order_id, Vec::new());
+        }
+        self.close_synthetic_short(portfolio, state, bar, (reason, rsi), None);
+        state.orders.cancel();
+        state.bracket_signal = None;
+        state.stop_check_at_open = false;
+        state.pending_stop = None;
+        state.reopen_long = None;
+        execution_sim.clear_pending_orders();
+    }
 
-        // Simulate execution
-        let exec_result = execution_sim.simulate_execution(bar, Side::Buy, quantity, volatility);
-
-        if !exec_result.executed || exec_result.fill_quantity < 1.0 {
-            return; // Order rejected or insufficient fill
+    /// Book the cash flows dated up to this bar at its close. A withdrawal
+    /// beyond the cash on hand first sells down the long, then the hedge.
+    fn book_cash_flows(&self, sim: &mut Simulation, bar: &Bar, hedge_bar: Option<&Bar>, rsi: f64) {
+        let date = bar.timestamp.date_naive();
+        while let Some(&(flow_date, amount)) = self.cash_flows.get(sim.next_flow) {
+            if flow_date > date {
+                break;
+            }
+            sim.next_flow += 1;
+            if amount < 0.0 {
+                self.raise_cash(sim, -amount, bar, hedge_bar, rsi);
+            }
+            let booked = sim.portfolio.apply_cash_flow(amount);
+            sim.booked_flows.push((bar.timestamp, booked));
         }
+    }
 
-        // Calculate stop loss price based on actual fill price
-        let stop_loss_price = if self.params.stop_loss_pct > 0.0 {
-            Some(exec_result.fill_price * (1.0 - self.params.stop_loss_pct))
-        } else {
-            None
+    /// Sell just enough shares at the close to hold `amount` in cash
+    fn raise_cash(
+        &self,
+        sim: &mut Simulation,
+        amount: f64,
+        bar: &Bar,
+        hedge_bar: Option<&Bar>,
+        rsi: f64,
+    ) {
+        let commission = self.params.execution.commission;
+        let had_long = sim.portfolio.has_position();
+        let legs = [
+            (PositionSide::Long, Some(bar)),
+            (PositionSide::Hedge, hedge_bar),
+        ];
+        for (side, leg_bar) in legs {
+            let shortfall = amount - sim.portfolio.cash();
+            if shortfall <= 0.0 {
+                break;
+            }
+            let held = match side {
+                PositionSide::Hedge => sim.portfolio.current_hedge_position(),
+                _ => sim.portfolio.current_position(),
+            };
+            let (Some(held), Some(leg_bar)) = (held.map(|p| p.quantity), leg_bar) else {
+                continue;
+            };
+            if leg_bar.close <= 0.0 {
+                continue;
+            }
+            let shares = ((shortfall + commission) / leg_bar.close).ceil().min(held);
+            sim.portfolio.reduce_position(
+                side,
+                shares,
+                leg_bar.close,
+                leg_bar.timestamp,
+                WITHDRAWAL,
+                commission,
+            );
+            sim.portfolio.annotate_last_exit(rsi);
+            let order_id = sim.state.audit.place(None);
+            self.audit_exit(&mut sim.state, &mut sim.portfolio, order_id, Vec::new());
+        }
+
+        if had_long && !sim.portfolio.has_position() {
+            // Nothing is left for working exits to act on
+            sim.state.orders.cancel();
+            sim.state.bracket_signal = None;
+            sim.state.pending_stop = None;
+        }
+    }
+
+    /// Close the long at this bar's close, priced by the execution simulator
+    /// for the whole position, to reopen at the next open unless a delayed
+    /// stop exit was already working
+    fn flatten_overnight(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        execution_sim: &mut ExecutionSimulator,
+        bar: &Bar,
+        indicators: &IndicatorValues,
+        volatility: Option<f64>,
+    ) {
+        let Some(position) = portfolio.current_position() else {
+            return;
         };
+        let reopen = (
+            position.entry_reason.clone(),
+            position.entry_rsi.unwrap_or(indicators.rsi),
+            false,
+        );
+        let quantity = position.quantity;
 
-        let _ = portfolio.open_position(
-            &self.params.symbol,
-            exec_result.fill_quantity,
-            exec_result.fill_price,
-            PositionSide::Long,
+        let order_id = state.audit.place(None);
+        let exec_result = execution_sim
+            .simulate_execution(bar, Side::Sell, quantity, volatility)
+            .for_order(order_id);
+        let exit_price = if exec_result.executed {
+            exec_result.fill_price
+        } else {
+            bar.close
+        };
+        portfolio.close_position(
+            exit_price,
             bar.timestamp,
-            stop_loss_price,
-            self.params.commission,
+            OVERNIGHT_FLAT,
+            self.params.execution.commission,
         );
+        portfolio.annotate_last_exit(indicators.rsi);
+        self.audit_exit(state, portfolio, order_id, exec_result.notes);
+
+        if state.pending_stop.take().is_some() {
+            execution_sim.clear_pending_orders();
+        } else {
+            state.reopen_long = Some(reopen);
+        }
     }
 
-    /// Execute hedge buy order with realistic execution simulation
-    fn execute_hedge_buy(
+    /// Open a long flattened overnight (or deferred from a close) at this
+    /// bar's open. The position is new: its stop and holding period restart
+    /// from this fill. A `new_entry` counts against the trade limits only
+    /// once it fills; a reopen after flattening continues the earlier entry.
+    #[allow(clippy::too_many_arguments)]
+    fn reopen_at_open(
         &self,
         portfolio: &mut Portfolio,
+        state: &mut RunState,
         execution_sim: &mut ExecutionSimulator,
         bar: &Bar,
+        indicators: &IndicatorValues,
+        bar_index: usize,
         volatility: Option<f64>,
+        (reason, rsi): (&str, f64),
+        new_entry: bool,
     ) {
-        let quantity = portfolio.calculate_position_size(
-            bar.close,
-            self.params.short_position_size_pct,
-            self.params.cash_reserve_pct,
+        let at_open = Bar {
+            close: bar.open,
+            ..bar.clone()
+        };
+        let at = (&at_open, bar_index);
+        match self.execute_buy(
+            portfolio,
+            state,
+            execution_sim,
+            at,
+            indicators,
+            volatility,
+            None,
+        ) {
+            Ok(()) => {
+                portfolio.annotate_entry(PositionSide::Long, reason, rsi);
+                if new_entry {
+                    state.entry_limiter.record(bar.timestamp);
+                    state
+                        .entry_limiter
+                        .record_fill(&self.params.strategy.symbol, bar_index);
+                }
+            }
+            Err(skip) => state.suppressed_signals.push(SuppressedSignal {
+                timestamp: bar.timestamp,
+                signal_type: SignalType::Buy,
+                symbol: self.params.strategy.symbol.clone(),
+                price: bar.open,
+                rsi,
+                reason: skip,
+            }),
+        }
+    }
+
+    /// Apply the circuit-breaker rules on a volatility-burst bar
+    fn on_anomaly(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        bar: &Bar,
+        bar_index: usize,
+    ) {
+        if self.params.risk.skip_entries_on_anomaly {
+            state.quarantine_until = Some(bar_index + self.params.risk.anomaly_quarantine_bars);
+        }
+        if let Some(pct) = self.params.risk.tighten_stops_on_anomaly_pct {
+            portfolio.tighten_stop(bar.close * (1.0 - pct));
+        }
+    }
+
+    /// Log entry signals that fire on a halted bar
+    fn suppress_on_halt(
+        &self,
+        state: &mut RunState,
+        portfolio: &Portfolio,
+        signal_generator: &SignalGenerator,
+        bar: &Bar,
+        indicators: &IndicatorValues,
+    ) {
+        let signal = signal_generator.generate(
+            bar,
+            indicators,
+            portfolio.has_position(),
+            portfolio.current_position(),
+            portfolio.is_hedged(),
         );
 
-        if quantity < 1.0 {
+        if let Some(sig) = signal {
+            if matches!(sig.signal_type, SignalType::Buy | SignalType::HedgeBuy) {
+                let reason = if bar.halted {
+                    "bar halted (trading halt)".to_string()
+                } else {
+                    format!(
+                        "bar halted (volume {} below minimum {})",
+                        bar.volume, self.params.execution.min_bar_volume
+                    )
+                };
+                state.suppress(&sig, reason);
+            }
+        }
+    }
+
+    /// Close the position at the bar's open if it gapped through the stop
+    /// while trading was halted. Returns true when the position was closed.
+    fn stop_at_open_after_halt(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        bar: &Bar,
+    ) -> bool {
+        let stop = portfolio.current_position().and_then(|p| p.stop_loss_price);
+
+        match stop {
+            Some(stop) if bar.open <= stop => {
+                portfolio.close_position(
+                    bar.open,
+                    bar.timestamp,
+                    "stop loss (after halt)",
+                    self.params.execution.commission,
+                );
+                let order_id = state.audit.place(None);
+                self.audit_exit(state, portfolio, order_id, Vec::new());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `sig` enters on the other side of a long or hedge position
+    /// change less than `signal_hysteresis_bars` ago (same-bar flips
+    /// included) without RSI having moved `hysteresis_rsi_delta` since
+    fn within_hysteresis(&self, state: &RunState, sig: &Signal, bar_index: usize) -> bool {
+        let Some((at, side, rsi)) = state.last_change else {
+            return false;
+        };
+        let opposite = match sig.signal_type {
+            SignalType::Buy => side == PositionSide::Hedge,
+            SignalType::HedgeBuy => side == PositionSide::Long,
+            _ => false,
+        };
+        opposite
+            && bar_index - at < self.params.hedge.signal_hysteresis_bars
+            && (sig.rsi - rsi).abs() < self.params.hedge.hysteresis_rsi_delta
+    }
+
+    /// Why the SMA slope filter holds off a long entry, if it does
+    fn sma_slope_shortfall(&self, indicators: &IndicatorValues) -> Option<String> {
+        let filter = self.params.strategy.sma_slope_filter?;
+        match indicators.sma_slope {
+            Some(slope) if slope >= filter.min_slope_pct => None,
+            Some(slope) => Some(format!(
+                "SMA slope {:+.2}% over {} bars below {:+.2}%",
+                slope, filter.lookback_bars, filter.min_slope_pct
+            )),
+            None => Some(format!(
+                "SMA slope over {} bars not yet available",
+                filter.lookback_bars
+            )),
+        }
+    }
+
+    /// Process signals and execute trades
+    #[allow(clippy::too_many_arguments)]
+    fn process_signals(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        signal_generator: &SignalGenerator,
+        execution_sim: &mut ExecutionSimulator,
+        bar: &Bar,
+        hedge_bar: Option<&Bar>,
+        indicators: &IndicatorValues,
+        bar_index: usize,
+        volatility: Option<f64>,
+    ) {
+        // A delayed stop exit is already working
+        if state.pending_stop.is_some() && portfolio.has_position() {
+            return;
+        }
+        // So is an exit the volume cap only let fill in part: it keeps
+        // selling, one tranche a bar, until flat
+        if let Some(exit) = state.working_exit.take() {
+            let selling = portfolio
+                .current_position()
+                .is_some_and(|p| p.entry_date == exit.entry_date);
+            if selling && exit.last_fill_bar == bar_index {
+                state.working_exit = Some(exit);
+                return;
+            }
+            if selling {
+                self.fill_exit(
+                    portfolio,
+                    state,
+                    execution_sim,
+                    (bar, bar_index),
+                    volatility,
+                    exit,
+                );
+                return;
+            }
+        }
+
+        // Check for stop loss first (brackets carry their own stop order)
+        if !self.params.execution.use_bracket_orders
+            && portfolio.has_position()
+            && portfolio.check_stop_loss(bar.close)
+        {
+            if execution_sim.stops_delayed() {
+                let quantity = portfolio.current_position().map_or(0.0, |p| p.quantity);
+                let order_id = state.audit.place(None);
+                execution_sim.queue_order(
+                    self.params.strategy.symbol.clone(),
+                    Side::Sell,
+                    quantity,
+                    bar_index,
+                    order_id,
+                );
+                state.pending_stop = Some(bar.timestamp);
+                return;
+            }
+
+            let order_id = state.audit.place(None);
+            if let Some(exit) =
+                self.exit_order(portfolio, order_id, "stop loss", indicators.rsi, bar_index)
+            {
+                self.fill_exit(
+                    portfolio,
+                    state,
+                    execution_sim,
+                    (bar, bar_index),
+                    volatility,
+                    exit,
+                );
+            }
             return;
         }
 
-        // Simulate execution
-        let exec_result = execution_sim.simulate_execution(bar, Side::HedgeBuy, quantity, volatility);
+        // Generate signal
+        let signal = signal_generator.generate(
+            bar,
+            indicators,
+            portfolio.has_position(),
+            portfolio.current_position(),
+            portfolio.is_hedged(),
+        );
+        let signal = match self.entry_schedule {
+            Some(schedule) => {
+                self.scheduled_signal(signal, schedule, portfolio, bar, indicators, bar_index)
+            }
+            None => signal,
+        };
+
+        let Some(sig) = signal else {
+            return;
+        };
+        // An extreme reading can reverse the book on the same bar; the
+        // second leg is sized after the first has settled
+        let flip = signal_generator.flip_signal(&sig, bar, indicators);
+        let closing = sig.signal_type;
+        self.execute_signal(
+            sig,
+            portfolio,
+            state,
+            execution_sim,
+            bar,
+            hedge_bar,
+            indicators,
+            bar_index,
+            volatility,
+        );
+
+        let closed = match closing {
+            SignalType::Sell => !portfolio.has_position(),
+            SignalType::HedgeSell => !portfolio.is_hedged(),
+            _ => false,
+        };
+        if let (Some(flip), true) = (flip, closed) {
+            self.execute_signal(
+                flip,
+                portfolio,
+                state,
+                execution_sim,
+                bar,
+                hedge_bar,
+                indicators,
+                bar_index,
+                volatility,
+            );
+        }
+    }
 
-        if !exec_result.executed || exec_result.fill_quantity < 1.0 {
-            return; // Order rejected or insufficient fill
+    /// Act on one signal for the current bar
+    #[allow(clippy::too_many_arguments)]
+    fn execute_signal(
+        &self,
+        sig: Signal,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        execution_sim: &mut ExecutionSimulator,
+        bar: &Bar,
+        hedge_bar: Option<&Bar>,
+        indicators: &IndicatorValues,
+        bar_index: usize,
+        volatility: Option<f64>,
+    ) {
+        state.observe_holdings(portfolio, bar_index, indicators.rsi);
+        match sig.signal_type {
+            SignalType::Buy | SignalType::HedgeBuy
+                if self.within_hysteresis(state, &sig, bar_index) =>
+            {
+                state.suppress(&sig, SKIP_HYSTERESIS.to_string());
+            }
+            SignalType::Buy | SignalType::HedgeBuy
+                if state
+                    .quarantine_until
+                    .is_some_and(|until| bar_index <= until) =>
+            {
+                state.suppress(&sig, SKIP_ANOMALY.to_string());
+            }
+            SignalType::Buy | SignalType::HedgeBuy if state.low_data_quality => {
+                state.suppress(&sig, SKIP_LOW_DATA_QUALITY.to_string());
+            }
+            SignalType::Buy => {
+                if let Some(reason) = self.sma_slope_shortfall(indicators) {
+                    state.suppress(&sig, reason);
+                    return;
+                }
+                let limit = state.entry_limiter.check(bar.timestamp).or_else(|| {
+                    state
+                        .entry_limiter
+                        .check_spacing(&self.params.strategy.symbol, bar_index)
+                });
+                if let Some(reason) = limit {
+                    state.suppress(&sig, reason);
+                    return;
+                }
+
+                if portfolio.is_hedged() {
+                    let closable = portfolio.has_synthetic_short() || hedge_bar.is_some();
+                    match self.params.hedge.concurrent_hedge_policy {
+                        ConcurrentHedgePolicy::Allow => {}
+                        ConcurrentHedgePolicy::CloseHedgeFirst if closable => {
+                            // Vet the entry on the hedge's proceeds before
+                            // giving the hedge up for it
+                            let projected = portfolio.without_hedges();
+                            let blocked = self.long_entry_blocker(
+                                state,
+                                &projected,
+                                execution_sim,
+                                bar,
+                                indicators,
+                            );
+                            if let Some(reason) = blocked {
+                                state.suppress(&sig, reason);
+                                return;
+                            }
+                            let closing = (HEDGE_CLOSED_FOR_LONG, sig.rsi);
+                            if portfolio.has_synthetic_short() {
+                                self.close_synthetic_short(portfolio, state, bar, closing, None);
+                            } else if let Some(hbar) = hedge_bar {
+                                self.close_hedge(
+                                    portfolio,
+                                    state,
+                                    execution_sim,
+                                    hbar,
+                                    closing,
+                                    None,
+                                    volatility,
+                                );
+                            }
+                        }
+                        // Without a hedge bar the hedge cannot be priced, so block instead
+                        ConcurrentHedgePolicy::Block | ConcurrentHedgePolicy::CloseHedgeFirst => {
+                            state.suppress(&sig, SKIP_HEDGE_OPEN.to_string());
+                            return;
+                        }
+                    }
+                }
+
+                let notional = self.long_entry_size(state, portfolio, bar.close) * bar.close;
+                if let Some(reason) = state.edge_filter.check(bar.close, indicators.atr, notional) {
+                    state.suppress(&sig, reason);
+                    return;
+                }
+
+                if self.params.risk.overnight_flat {
+                    // Opened at the next open instead, so that it never
+                    // spans a close
+                    state.reopen_long = Some((sig.reason.clone(), sig.rsi, true));
+                } else if self.params.execution.use_bracket_orders {
+                    match self.submit_bracket(
+                        portfolio,
+                        state,
+                        execution_sim,
+                        bar,
+                        bar_index,
+                        indicators,
+                    ) {
+                        Ok(()) => {
+                            let order_id = state.audit.place(Some(&sig));
+                            state.bracket_signal = Some(Signal {
+                                order_id: Some(order_id),
+                                ..sig
+                            });
+                        }
+                        Err(reason) => state.suppress(&sig, reason),
+                    }
+                } else if execution_sim.has_latency() {
+                    // Queue order for delayed execution
+                    let quantity = self.size_long_entry(state, portfolio, bar.close);
+                    let stop = self
+                        .params
+                        .risk
+                        .stop_loss
+                        .distance(bar.close, indicators.atr);
+                    let capped = self.cap_for_heat(portfolio, bar.close, stop, quantity);
+                    let sized =
+                        self.cap_for_liquidity(state, execution_sim, bar, indicators, capped);
+                    if quantity >= 1.0 && capped < 1.0 {
+                        state.suppress(&sig, SKIP_HEAT_CAP.to_string());
+                    } else if quantity >= 1.0 && sized < 1.0 {
+                        state.suppress(&sig, SKIP_LIQUIDITY_CAP.to_string());
+                    } else if quantity >= 1.0 {
+                        let symbol = &self.params.strategy.symbol;
+                        match self.review_entry(state, portfolio, (symbol, Side::Buy), bar, sized) {
+                            Ok(sized) => {
+                                let order_id = state.audit.place(Some(&sig));
+                                execution_sim.queue_order(
+                                    symbol.clone(),
+                                    Side::Buy,
+                                    sized,
+                                    bar_index,
+                                    order_id,
+                                );
+                            }
+                            Err(reason) => state.suppress(&sig, reason),
+                        }
+                    } else {
+                        state.suppress(&sig, SKIP_SIZE_TOO_SMALL.to_string());
+                    }
+                } else {
+                    let at = (bar, bar_index);
+                    match self.execute_buy(
+                        portfolio,
+                        state,
+                        execution_sim,
+                        at,
+                        indicators,
+                        volatility,
+                        Some(&sig),
+                    ) {
+                        Ok(()) => {
+                            state.entry_limiter.record(bar.timestamp);
+                            state
+                                .entry_limiter
+                                .record_fill(&self.params.strategy.symbol, bar_index);
+                            portfolio.annotate_entry(PositionSide::Long, &sig.reason, sig.rsi);
+                        }
+                        Err(reason) => state.suppress(&sig, reason),
+                    }
+                }
+            }
+            SignalType::Sell => {
+                let order_id = state.audit.place(Some(&sig));
+                if let Some(exit) =
+                    self.exit_order(portfolio, order_id, &sig.reason, sig.rsi, bar_index)
+                {
+                    self.fill_exit(
+                        portfolio,
+                        state,
+                        execution_sim,
+                        (bar, bar_index),
+                        volatility,
+                        exit,
+                    );
+                }
+            }
+            SignalType::HedgeBuy if self.params.hedge.synthetic_short => {
+                let limit = state.entry_limiter.check(bar.timestamp).or_else(|| {
+                    state
+                        .entry_limiter
+                        .check_spacing(&self.params.hedge.inverse_symbol, bar_index)
+                });
+                if let Some(reason) = limit {
+                    state.suppress(&sig, reason);
+                    return;
+                }
+
+                match self.open_synthetic_short(portfolio, state, bar, &sig) {
+                    Ok(()) => {
+                        state.entry_limiter.record(bar.timestamp);
+                        state
+                            .entry_limiter
+                            .record_fill(&self.params.hedge.inverse_symbol, bar_index);
+                    }
+                    Err(reason) => state.suppress(&sig, reason),
+                }
+            }
+            SignalType::HedgeBuy => {
+                if let Some(hbar) = hedge_bar {
+                    let limit = state.entry_limiter.check(bar.timestamp).or_else(|| {
+                        state
+                            .entry_limiter
+                            .check_spacing(&self.params.hedge.inverse_symbol, bar_index)
+                    });
+                    if let Some(reason) = limit {
+                        state.suppress(&sig, reason);
+                        return;
+                    }
+
+                    if execution_sim.has_latency() {
+                        let quantity = portfolio.calculate_position_size(
+                            hbar.close,
+                            self.params.hedge.short_position_size_pct,
+                            self.params.risk.cash_reserve_pct,
+                        );
+                        let atr = hedge_atr(indicators.atr, bar, hbar);
+                        let stop = self.params.hedge.short_stop_loss.distance(hbar.close, atr);
+                        let capped = self.cap_for_heat(portfolio, hbar.close, stop, quantity);
+                        if quantity >= 1.0 && capped < 1.0 {
+                            state.suppress(&sig, SKIP_HEAT_CAP.to_string());
+                        } else if quantity >= 1.0 {
+                            let symbol = &self.params.hedge.inverse_symbol;
+                            let order = (symbol.as_str(), Side::HedgeBuy);
+                            match self.review_entry(state, portfolio, order, hbar, capped) {
+                                Ok(capped) => {
+                                    let order_id = state.audit.place(Some(&sig));
+                                    execution_sim.queue_order(
+                                        symbol.clone(),
+                                        Side::HedgeBuy,
+                                        capped,
+                                        bar_index,
+                                        order_id,
+                                    );
+                                }
+                                Err(reason) => state.suppress(&sig, reason),
+                            }
+                        } else {
+                            state.suppress(&sig, SKIP_SIZE_TOO_SMALL.to_string());
+                        }
+                    } else {
+                        let at = (hbar, bar_index);
+                        let atr = hedge_atr(indicators.atr, bar, hbar);
+                        let buy = self.execute_hedge_buy(
+                            portfolio,
+                            state,
+                            execution_sim,
+                            at,
+                            atr,
+                            volatility,
+                            &sig,
+                        );
+                        match buy {
+                            Ok(()) => {
+                                state.entry_limiter.record(bar.timestamp);
+                                state
+                                    .entry_limiter
+                                    .record_fill(&self.params.hedge.inverse_symbol, bar_index);
+                                portfolio.annotate_entry(PositionSide::Hedge, &sig.reason, sig.rsi);
+                            }
+                            Err(reason) => state.suppress(&sig, reason),
+                        }
+                    }
+                }
+            }
+            SignalType::HedgeSell if portfolio.has_synthetic_short() => {
+                self.close_synthetic_short(
+                    portfolio,
+                    state,
+                    bar,
+                    (&sig.reason, sig.rsi),
+                    Some(&sig),
+                );
+            }
+            SignalType::HedgeSell => {
+                if let Some(hbar) = hedge_bar {
+                    self.close_hedge(
+                        portfolio,
+                        state,
+                        execution_sim,
+                        hbar,
+                        (&sig.reason, sig.rsi),
+                        Some(&sig),
+                        volatility,
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Replace the generator's long entries with the entry schedule
+    fn scheduled_signal(
+        &self,
+        signal: Option<Signal>,
+        schedule: &[bool],
+        portfolio: &Portfolio,
+        bar: &Bar,
+        indicators: &IndicatorValues,
+        bar_index: usize,
+    ) -> Option<Signal> {
+        match signal {
+            Some(sig) if sig.signal_type != SignalType::Buy => Some(sig),
+            _ if portfolio.has_position() || !schedule.get(bar_index).copied().unwrap_or(false) => {
+                None
+            }
+            _ => Some(Signal {
+                timestamp: bar.timestamp,
+                signal_type: SignalType::Buy,
+                symbol: self.params.strategy.symbol.clone(),
+                price: bar.close,
+                rsi: indicators.rsi,
+                reason: "scheduled entry".to_string(),
+                strength: 1.0,
+                vwap: indicators.vwap.or(bar.vwap),
+                sma: indicators.sma,
+                order_id: None,
+                confirmation_bars: None,
+            }),
+        }
+    }
+
+    /// Sell the whole hedge position on `hbar` at the execution simulator's
+    /// price for it, on `signal` or (None) on the engine's own account
+    #[allow(clippy::too_many_arguments)]
+    fn close_hedge(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        execution_sim: &mut ExecutionSimulator,
+        hbar: &Bar,
+        (reason, rsi): (&str, f64),
+        signal: Option<&Signal>,
+        volatility: Option<f64>,
+    ) {
+        let quantity = portfolio
+            .current_hedge_position()
+            .map_or(0.0, |p| p.quantity);
+        let order_id = state.audit.place(signal);
+        let exec_result = execution_sim
+            .simulate_execution(hbar, Side::HedgeSell, quantity, volatility)
+            .for_order(order_id);
+        let exit_price = if exec_result.executed {
+            exec_result.fill_price
+        } else {
+            hbar.close
+        };
+        if portfolio
+            .close_hedge_position(
+                exit_price,
+                hbar.timestamp,
+                reason,
+                self.params.execution.commission,
+            )
+            .is_some()
+        {
+            portfolio.annotate_last_exit(rsi);
+            self.audit_exit(state, portfolio, order_id, exec_result.notes);
+        }
+    }
+
+    /// Open the synthetic short at the bar's close. It settles against the
+    /// primary symbol, so it needs no hedge bar and fills outside the
+    /// execution simulator, latency included.
+    ///
+    /// Returns the skip reason when nothing was opened.
+    fn open_synthetic_short(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        bar: &Bar,
+        signal: &Signal,
+    ) -> Result<(), String> {
+        let notional = portfolio.equity() * self.params.hedge.short_position_size_pct;
+        if notional < bar.close {
+            return Err(SKIP_SIZE_TOO_SMALL.to_string());
+        }
+        let order = (self.params.strategy.symbol.as_str(), Side::SyntheticShort);
+        let quantity = notional / bar.close;
+        let reviewed = self.review_entry(state, portfolio, order, bar, quantity)?;
+        // A shrunk short keeps its notional in proportion
+        let notional = if reviewed < quantity {
+            reviewed * bar.close
+        } else {
+            notional
+        };
+
+        let order_id = state.audit.place(Some(signal));
+        portfolio
+            .open_synthetic_short(
+                &self.params.strategy.symbol,
+                (notional, self.params.hedge.synthetic_short_leverage),
+                bar.close,
+                bar.timestamp,
+                self.params.execution.commission,
+            )
+            .map_err(|e| e.to_string())?;
+        portfolio.annotate_synthetic_entry(&signal.reason, signal.rsi, order_id);
+        state.audit.record_fill(Fill {
+            order_id,
+            timestamp: bar.timestamp,
+            symbol: self.params.strategy.symbol.clone(),
+            side: Side::SyntheticShort,
+            quantity: notional / bar.close,
+            price: bar.close,
+            notes: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Settle the synthetic short, if one is open, at the bar's close
+    fn close_synthetic_short(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        bar: &Bar,
+        (reason, rsi): (&str, f64),
+        signal: Option<&Signal>,
+    ) {
+        if !portfolio.has_synthetic_short() {
+            return;
+        }
+        let order_id = state.audit.place(signal);
+        portfolio.close_synthetic_short(
+            bar.close,
+            bar.timestamp,
+            reason,
+            self.params.execution.commission,
+        );
+        portfolio.annotate_last_exit(rsi);
+        self.audit_exit(state, portfolio, order_id, Vec::new());
+    }
+
+    /// Stamp `order_id` on the position just opened on `side` and log its fill
+    fn audit_entry(
+        &self,
+        state: &mut RunState,
+        portfolio: &mut Portfolio,
+        side: PositionSide,
+        order_id: u64,
+        notes: Vec<String>,
+    ) {
+        portfolio.annotate_entry_order(side, order_id);
+        let position = match side {
+            PositionSide::Hedge => portfolio.current_hedge_position(),
+            _ => portfolio.current_position(),
+        };
+        if let Some(position) = position {
+            state.audit.record_fill(Fill {
+                order_id,
+                timestamp: position.entry_date,
+                symbol: position.symbol.clone(),
+                side: match side {
+                    PositionSide::Long => Side::Buy,
+                    PositionSide::Short => Side::Short,
+                    PositionSide::Hedge => Side::HedgeBuy,
+                },
+                quantity: position.quantity,
+                price: position.avg_entry_price,
+                notes,
+            });
         }
+    }
 
-        let stop_loss_price = if self.params.short_stop_loss_pct > 0.0 {
-            Some(exec_result.fill_price * (1.0 - self.params.short_stop_loss_pct))
+    /// Stamp `order_id` on the trade just closed and log its fill
+    fn audit_exit(
+        &self,
+        state: &mut RunState,
+        portfolio: &mut Portfolio,
+        order_id: u64,
+        notes: Vec<String>,
+    ) {
+        portfolio.annotate_exit_order(order_id);
+        let Some(trade) = portfolio.trades().last() else {
+            return;
+        };
+        let symbol = if trade.is_hedge() && trade.side != Side::SyntheticCover {
+            &self.params.hedge.inverse_symbol
         } else {
-            None
+            &self.params.strategy.symbol
+        };
+        // A staged exit's earlier tranches were recorded as they filled
+        let (quantity, price) = match trade.fills.last() {
+            Some(last) => (last.quantity, last.price),
+            None => (
+                trade.quantity,
+                trade.exit_price.unwrap_or(trade.entry_price),
+            ),
         };
+        state.audit.record_fill(Fill {
+            order_id,
+            timestamp: trade.exit_date.unwrap_or(trade.entry_date),
+            symbol: symbol.clone(),
+            side: trade.side,
+            quantity,
+            price,
+            notes,
+        });
+    }
+
+    /// Exit order `order_id` for the long position, if one is held
+    fn exit_order(
+        &self,
+        portfolio: &Portfolio,
+        order_id: u64,
+        reason: &str,
+        rsi: f64,
+        bar_index: usize,
+    ) -> Option<WorkingExit> {
+        portfolio.current_position().map(|p| WorkingExit {
+            order_id,
+            reason: reason.to_string(),
+            rsi,
+            entry_date: p.entry_date,
+            stop_triggered_at: None,
+            last_fill_bar: bar_index,
+        })
+    }
+
+    /// Sell the long on `exit`'s order as far as the volume cap lets this
+    /// bar fill it, leaving the rest working. An order the simulator does
+    /// not fill at all closes at the bar's close.
+    fn fill_exit(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        execution_sim: &mut ExecutionSimulator,
+        (bar, bar_index): (&Bar, usize),
+        volatility: Option<f64>,
+        exit: WorkingExit,
+    ) {
+        let quantity = portfolio.current_position().map_or(0.0, |p| p.quantity);
+        let exec_result = execution_sim
+            .simulate_execution(bar, Side::Sell, quantity, volatility)
+            .for_order(exit.order_id);
+        let commission = self.params.execution.commission;
+        if exec_result.executed && exec_result.fill_quantity < quantity {
+            let (filled, price) = (exec_result.fill_quantity, exec_result.fill_price);
+            portfolio.partial_exit(filled, price, bar.timestamp, &exit.reason, commission);
+            state.audit.record_fill(Fill {
+                order_id: exit.order_id,
+                timestamp: bar.timestamp,
+                symbol: self.params.strategy.symbol.clone(),
+                side: Side::Sell,
+                quantity: filled,
+                price,
+                notes: exec_result.notes,
+            });
+            state.working_exit = Some(WorkingExit {
+                last_fill_bar: bar_index,
+                ..exit
+            });
+            return;
+        }
+
+        let exit_price = if exec_result.executed {
+            exec_result.fill_price
+        } else {
+            bar.close
+        };
+        if portfolio
+            .close_position(exit_price, bar.timestamp, &exit.reason, commission)
+            .is_some()
+        {
+            portfolio.annotate_last_exit(exit.rsi);
+            if let Some(timestamp) = exit.stop_triggered_at {
+                portfolio.annotate_stop_trigger(timestamp);
+            }
+            self.audit_exit(state, portfolio, exit.order_id, exec_result.notes);
+        }
+    }
+
+    /// Execute buy order with realistic execution simulation, on `signal`
+    /// or (None) on the engine's own account
+    ///
+    /// Returns the skip reason when no position was opened.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_buy(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        execution_sim: &mut ExecutionSimulator,
+        (bar, bar_index): (&Bar, usize),
+        indicators: &IndicatorValues,
+        volatility: Option<f64>,
+        signal: Option<&Signal>,
+    ) -> Result<(), String> {
+        let quantity = self.size_long_entry(state, portfolio, bar.close);
+
+        if quantity < 1.0 {
+            return Err(SKIP_SIZE_TOO_SMALL.to_string());
+        }
+        let stop = self
+            .params
+            .risk
+            .stop_loss
+            .distance(bar.close, indicators.atr);
+        let quantity = self.cap_for_heat(portfolio, bar.close, stop, quantity);
+        if quantity < 1.0 {
+            return Err(SKIP_HEAT_CAP.to_string());
+        }
+        let quantity = self.cap_for_liquidity(state, execution_sim, bar, indicators, quantity);
+        if quantity < 1.0 {
+            return Err(SKIP_LIQUIDITY_CAP.to_string());
+        }
+        let order = (self.params.strategy.symbol.as_str(), Side::Buy);
+        let quantity = self.review_entry(state, portfolio, order, bar, quantity)?;
+
+        // Simulate execution
+        let order_id = state.audit.place(signal);
+        let exec_result = execution_sim
+            .simulate_execution(bar, Side::Buy, quantity, volatility)
+            .for_order(order_id);
+
+        if !exec_result.executed {
+            return Err(SKIP_REJECTED.to_string());
+        }
+        if exec_result.fill_quantity < 1.0 {
+            return Err(SKIP_INSUFFICIENT_FILL.to_string());
+        }
+        if self.cancels_partial(execution_sim, &exec_result) {
+            return Err(SKIP_PARTIAL_CANCELLED.to_string());
+        }
+
+        // Calculate stop loss price based on actual fill price
+        let stop = self.params.risk.stop_loss;
+        let stop_loss_price = stop.stop_price(exec_result.fill_price, indicators.atr);
+
+        portfolio
+            .open_position(
+                &self.params.strategy.symbol,
+                exec_result.fill_quantity,
+                exec_result.fill_price,
+                PositionSide::Long,
+                bar.timestamp,
+                stop_loss_price,
+                self.params.execution.commission,
+            )
+            .map_err(|e| e.to_string())?;
+        portfolio.annotate_stop_spec(PositionSide::Long, stop);
+        portfolio.annotate_size_multiplier(state.entry_size_multiplier);
+        let filled = exec_result.fill_quantity;
+        self.audit_entry(
+            state,
+            portfolio,
+            PositionSide::Long,
+            order_id,
+            exec_result.notes,
+        );
+        let order = PendingOrder {
+            order_id,
+            symbol: self.params.strategy.symbol.clone(),
+            side: Side::Buy,
+            quantity,
+            signal_bar_index: bar_index,
+            execute_at_bar_index: bar_index,
+            work_until_bar_index: None,
+        };
+        self.work_remainder(execution_sim, order, filled, bar_index);
+        Ok(())
+    }
+
+    /// Whether the partial fill policy turns `exec_result` into a rejection
+    fn cancels_partial(
+        &self,
+        execution_sim: &ExecutionSimulator,
+        exec_result: &ExecutionResult,
+    ) -> bool {
+        exec_result.fill_quantity < exec_result.requested_quantity
+            && execution_sim.partial_fill_policy() == PartialFillPolicy::Cancel
+    }
+
+    /// Queue what `order` has left after filling `filled` shares at
+    /// `bar_index`, when the policy works remainders. The order keeps its
+    /// id; its first fill starts the `max_bars` clock.
+    fn work_remainder(
+        &self,
+        execution_sim: &mut ExecutionSimulator,
+        order: PendingOrder,
+        filled: f64,
+        bar_index: usize,
+    ) {
+        let work_until = match (
+            order.work_until_bar_index,
+            execution_sim.partial_fill_policy(),
+        ) {
+            (Some(until), _) => until,
+            (None, PartialFillPolicy::WorkRemainder { max_bars }) => bar_index + max_bars,
+            (None, _) => return,
+        };
+        let rest = order.quantity - filled;
+        if rest >= 1.0 {
+            execution_sim.queue_remainder(&order, rest, bar_index, work_until);
+        }
+    }
+
+    /// Fill a tranche of the remainder `order` is working into the position
+    /// its first fill opened; dropped once that position has closed
+    #[allow(clippy::too_many_arguments)]
+    fn fill_remainder(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        execution_sim: &mut ExecutionSimulator,
+        bar: &Bar,
+        bar_index: usize,
+        order: PendingOrder,
+        volatility: Option<f64>,
+    ) {
+        let side = match order.side {
+            Side::HedgeBuy => PositionSide::Hedge,
+            _ => PositionSide::Long,
+        };
+        let position = match side {
+            PositionSide::Hedge => portfolio.current_hedge_position(),
+            _ => portfolio.current_position(),
+        };
+        if position.and_then(|p| p.entry_order_id) != Some(order.order_id) {
+            return;
+        }
+
+        let exec_result = execution_sim
+            .simulate_execution(bar, order.side, order.quantity, volatility)
+            .for_order(order.order_id);
+        let added = exec_result.executed
+            && exec_result.fill_quantity >= 1.0
+            && portfolio
+                .add_to_position(
+                    side,
+                    exec_result.fill_quantity,
+                    exec_result.fill_price,
+                    self.params.execution.commission,
+                )
+                .is_ok();
+        let filled = if added {
+            state.audit.record_fill(Fill {
+                order_id: order.order_id,
+                timestamp: bar.timestamp,
+                symbol: order.symbol.clone(),
+                side: order.side,
+                quantity: exec_result.fill_quantity,
+                price: exec_result.fill_price,
+                notes: exec_result.notes,
+            });
+            exec_result.fill_quantity
+        } else {
+            0.0
+        };
+        self.work_remainder(execution_sim, order, filled, bar_index);
+    }
+
+    /// Submit a bracket: buy limit at the close with an OCO stop and target
+    ///
+    /// Returns the skip reason when no bracket was submitted.
+    fn submit_bracket(
+        &self,
+        portfolio: &Portfolio,
+        state: &mut RunState,
+        execution_sim: &ExecutionSimulator,
+        bar: &Bar,
+        bar_index: usize,
+        indicators: &IndicatorValues,
+    ) -> Result<(), String> {
+        let quantity = self.size_long_entry(state, portfolio, bar.close);
+        if quantity < 1.0 {
+            return Err(SKIP_SIZE_TOO_SMALL.to_string());
+        }
+        let stop = self.params.risk.stop_loss;
+        let stop_distance = stop.distance(bar.close, indicators.atr);
+        let quantity = self.cap_for_heat(portfolio, bar.close, stop_distance, quantity);
+        if quantity < 1.0 {
+            return Err(SKIP_HEAT_CAP.to_string());
+        }
+        let quantity = self.cap_for_liquidity(state, execution_sim, bar, indicators, quantity);
+        if quantity < 1.0 {
+            return Err(SKIP_LIQUIDITY_CAP.to_string());
+        }
+        let order = (self.params.strategy.symbol.as_str(), Side::Buy);
+        let quantity = self.review_entry(state, portfolio, order, bar, quantity)?;
+
+        let take_profit = self.params.risk.take_profit_pct.unwrap_or_default();
+        let order = BracketOrder {
+            quantity,
+            entry: bar.close,
+            stop: stop.stop_price(bar.close, indicators.atr).unwrap_or(0.0),
+            target: bar.close * (1.0 + take_profit),
+        };
+        if state.orders.submit(order, bar_index) {
+            Ok(())
+        } else {
+            Err(SKIP_BRACKET_ACTIVE.to_string())
+        }
+    }
+
+    /// Fill the working bracket's entry or exits on this bar
+    fn process_bracket(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        bar: &Bar,
+        bar_index: usize,
+        indicators: &IndicatorValues,
+    ) {
+        // The position was closed elsewhere (signal exit): drop its exits
+        if state.orders.has_active_exits() && !portfolio.has_position() {
+            state.orders.cancel();
+        }
+
+        match state.orders.process_bar(bar, bar_index) {
+            Some(BracketEvent::EntryFilled {
+                price,
+                quantity,
+                stop,
+            }) => {
+                let signal = state.bracket_signal.take();
+                let order_id = match signal.as_ref().and_then(|s| s.order_id) {
+                    Some(order_id) => order_id,
+                    None => state.audit.place(None),
+                };
+                let opened = portfolio.open_position(
+                    &self.params.strategy.symbol,
+                    quantity,
+                    price,
+                    PositionSide::Long,
+                    bar.timestamp,
+                    Some(stop),
+                    self.params.execution.commission,
+                );
+                if opened.is_ok() {
+                    portfolio.annotate_stop_spec(PositionSide::Long, self.params.risk.stop_loss);
+                    portfolio.annotate_size_multiplier(state.entry_size_multiplier);
+                    state
+                        .entry_limiter
+                        .record_fill(&self.params.strategy.symbol, bar_index);
+                    self.audit_entry(state, portfolio, PositionSide::Long, order_id, Vec::new());
+                }
+                match (opened, signal) {
+                    (Ok(()), Some(sig)) => {
+                        state.entry_limiter.record(bar.timestamp);
+                        portfolio.annotate_entry(PositionSide::Long, &sig.reason, sig.rsi);
+                    }
+                    (Ok(()), None) => state.entry_limiter.record(bar.timestamp),
+                    (Err(e), signal) => {
+                        state.orders.cancel();
+                        if let Some(sig) = signal {
+                            state.suppress(&sig, e.to_string());
+                        }
+                    }
+                }
+            }
+            Some(BracketEvent::StopFilled { price }) => {
+                portfolio.close_position(
+                    price,
+                    bar.timestamp,
+                    "stop loss",
+                    self.params.execution.commission,
+                );
+                portfolio.annotate_last_exit(indicators.rsi);
+                let order_id = state.audit.place(None);
+                self.audit_exit(state, portfolio, order_id, Vec::new());
+            }
+            Some(BracketEvent::TargetFilled { price }) => {
+                portfolio.close_position(
+                    price,
+                    bar.timestamp,
+                    "take profit",
+                    self.params.execution.commission,
+                );
+                portfolio.annotate_last_exit(indicators.rsi);
+                let order_id = state.audit.place(None);
+                self.audit_exit(state, portfolio, order_id, Vec::new());
+            }
+            None => {}
+        }
+    }
+
+    /// Execute hedge buy order with realistic execution simulation, `atr`
+    /// being in the hedge's price
+    ///
+    /// Returns the skip reason when no hedge position was opened.
+    #[allow(clippy::too_many_arguments)]
+    fn execute_hedge_buy(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        execution_sim: &mut ExecutionSimulator,
+        (bar, bar_index): (&Bar, usize),
+        atr: f64,
+        volatility: Option<f64>,
+        signal: &Signal,
+    ) -> Result<(), String> {
+        let quantity = portfolio.calculate_position_size(
+            bar.close,
+            self.params.hedge.short_position_size_pct,
+            self.params.risk.cash_reserve_pct,
+        );
+
+        if quantity < 1.0 {
+            return Err(SKIP_SIZE_TOO_SMALL.to_string());
+        }
+        let stop = self.params.hedge.short_stop_loss;
+        let stop_distance = stop.distance(bar.close, atr);
+        let quantity = self.cap_for_heat(portfolio, bar.close, stop_distance, quantity);
+        if quantity < 1.0 {
+            return Err(SKIP_HEAT_CAP.to_string());
+        }
+        let order = (self.params.hedge.inverse_symbol.as_str(), Side::HedgeBuy);
+        let quantity = self.review_entry(state, portfolio, order, bar, quantity)?;
+
+        // Simulate execution
+        let order_id = state.audit.place(Some(signal));
+        let exec_result = execution_sim
+            .simulate_execution(bar, Side::HedgeBuy, quantity, volatility)
+            .for_order(order_id);
+
+        if !exec_result.executed {
+            return Err(SKIP_REJECTED.to_string());
+        }
+        if exec_result.fill_quantity < 1.0 {
+            return Err(SKIP_INSUFFICIENT_FILL.to_string());
+        }
+        if self.cancels_partial(execution_sim, &exec_result) {
+            return Err(SKIP_PARTIAL_CANCELLED.to_string());
+        }
+
+        let stop_loss_price = stop.stop_price(exec_result.fill_price, atr);
+
+        portfolio
+            .open_position(
+                &self.params.hedge.inverse_symbol,
+                exec_result.fill_quantity,
+                exec_result.fill_price,
+                PositionSide::Hedge,
+                bar.timestamp,
+                stop_loss_price,
+                self.params.execution.commission,
+            )
+            .map_err(|e| e.to_string())?;
+        portfolio.annotate_stop_spec(PositionSide::Hedge, stop);
+        let filled = exec_result.fill_quantity;
+        self.audit_entry(
+            state,
+            portfolio,
+            PositionSide::Hedge,
+            order_id,
+            exec_result.notes,
+        );
+        let order = PendingOrder {
+            order_id,
+            symbol: self.params.hedge.inverse_symbol.clone(),
+            side: Side::HedgeBuy,
+            quantity,
+            signal_bar_index: bar_index,
+            execute_at_bar_index: bar_index,
+            work_until_bar_index: None,
+        };
+        self.work_remainder(execution_sim, order, filled, bar_index);
+        Ok(())
+    }
+
+    /// Put a sized entry at `bar`'s close through the pre-trade checks: the
+    /// quantity to go ahead with, or the suppression reason
+    fn review_entry(
+        &self,
+        state: &mut RunState,
+        portfolio: &Portfolio,
+        (symbol, side): (&str, Side),
+        bar: &Bar,
+        quantity: f64,
+    ) -> Result<f64, String> {
+        if self.pre_trade_checks.is_empty() {
+            return Ok(quantity);
+        }
+        let order = ProposedOrder {
+            timestamp: bar.timestamp,
+            symbol: symbol.to_string(),
+            side,
+            quantity,
+            price: bar.close,
+        };
+        let snapshot = PortfolioSnapshot::from(portfolio);
+        self.pre_trade_checks
+            .review(&order, &snapshot, &mut state.pre_trade_events)
+    }
+
+    /// Why a long entry on `bar` would be turned away by the edge filter,
+    /// sizing, heat and liquidity caps or the pre-trade checks, were it
+    /// entered from `portfolio`; None when it would go through. Records
+    /// nothing, unlike the checks themselves.
+    fn long_entry_blocker(
+        &self,
+        state: &RunState,
+        portfolio: &Portfolio,
+        execution_sim: &ExecutionSimulator,
+        bar: &Bar,
+        indicators: &IndicatorValues,
+    ) -> Option<String> {
+        let quantity = self.long_entry_size(state, portfolio, bar.close);
+        if let Some(reason) =
+            state
+                .edge_filter
+                .check(bar.close, indicators.atr, quantity * bar.close)
+        {
+            return Some(reason);
+        }
+        if quantity < 1.0 {
+            return Some(SKIP_SIZE_TOO_SMALL.to_string());
+        }
+        let stop = self
+            .params
+            .risk
+            .stop_loss
+            .distance(bar.close, indicators.atr);
+        let quantity = self.cap_for_heat(portfolio, bar.close, stop, quantity);
+        if quantity < 1.0 {
+            return Some(SKIP_HEAT_CAP.to_string());
+        }
+        if self.params.risk.liquidity_aware_sizing {
+            let volume = indicators.avg_volume.unwrap_or(bar.volume as f64);
+            if execution_sim.volume_limit(volume, bar.close).floor() < 1.0 {
+                return Some(SKIP_LIQUIDITY_CAP.to_string());
+            }
+        }
+        if self.pre_trade_checks.is_empty() {
+            return None;
+        }
+        let order = ProposedOrder {
+            timestamp: bar.timestamp,
+            symbol: self.params.strategy.symbol.clone(),
+            side: Side::Buy,
+            quantity,
+            price: bar.close,
+        };
+        let snapshot = PortfolioSnapshot::from(portfolio);
+        self.pre_trade_checks
+            .review(&order, &snapshot, &mut Vec::new())
+            .err()
+    }
+
+    /// Shrink an entry so portfolio heat stays within `max_portfolio_heat_pct`,
+    /// `stop_distance` being the dollars per share to its stop (0 for none)
+    fn cap_for_heat(
+        &self,
+        portfolio: &Portfolio,
+        price: f64,
+        stop_distance: f64,
+        quantity: f64,
+    ) -> f64 {
+        let Some(cap) = self.params.risk.max_portfolio_heat_pct else {
+            return quantity;
+        };
+        let risk_per_share = if stop_distance > 0.0 {
+            stop_distance
+        } else {
+            price
+        };
+        let budget = portfolio.equity() * cap / 100.0 - portfolio.risk_amount();
+        if budget <= 0.0 {
+            return 0.0;
+        }
+        quantity.min((budget / risk_per_share).floor())
+    }
+
+    /// Shrink a long entry to the volume participation limit applied to the
+    /// trailing average volume (the bar's own volume during warmup)
+    fn cap_for_liquidity(
+        &self,
+        state: &mut RunState,
+        execution_sim: &ExecutionSimulator,
+        bar: &Bar,
+        indicators: &IndicatorValues,
+        quantity: f64,
+    ) -> f64 {
+        if !self.params.risk.liquidity_aware_sizing {
+            return quantity;
+        }
+        let volume = indicators.avg_volume.unwrap_or(bar.volume as f64);
+        let cap = execution_sim.volume_limit(volume, bar.close).floor();
+        if cap < quantity {
+            state.liquidity_capped += 1;
+            cap
+        } else {
+            quantity
+        }
+    }
+
+    /// Shares for a long entry at `price` under the configured sizing mode,
+    /// scaled by the drawdown throttle and adaptive sizing and kept within
+    /// the cash available
+    fn long_entry_size(&self, state: &RunState, portfolio: &Portfolio, price: f64) -> f64 {
+        let quantity = portfolio.calculate_entry_size(
+            price,
+            self.params.risk.sizing_mode,
+            self.params.risk.position_size_pct,
+            self.params.risk.cash_reserve_pct,
+        );
+        match self.entry_multiplier(state, portfolio) {
+            Some(multiplier) => {
+                let available = portfolio.cash() * (1.0 - self.params.risk.cash_reserve_pct);
+                (quantity * multiplier)
+                    .floor()
+                    .min((available / price).floor())
+            }
+            None => quantity,
+        }
+    }
+
+    /// Size a long entry and remember the multiplier applied, for the
+    /// position it opens
+    fn size_long_entry(&self, state: &mut RunState, portfolio: &Portfolio, price: f64) -> f64 {
+        state.entry_size_multiplier = self.entry_multiplier(state, portfolio);
+        self.long_entry_size(state, portfolio, price)
+    }
+
+    /// Product of the drawdown throttle and adaptive sizing multipliers;
+    /// None when neither is configured
+    fn entry_multiplier(&self, state: &RunState, portfolio: &Portfolio) -> Option<f64> {
+        let throttle = self.drawdown_multiplier(state, portfolio);
+        let adaptive = self
+            .params
+            .risk
+            .adaptive_sizing
+            .map(|sizing| sizing.multiplier(portfolio.trades()));
+        match (throttle, adaptive) {
+            (None, None) => None,
+            (throttle, adaptive) => Some(throttle.unwrap_or(1.0) * adaptive.unwrap_or(1.0)),
+        }
+    }
+
+    /// Drawdown throttle multiplier at the current equity; None without a
+    /// throttle
+    fn drawdown_multiplier(&self, state: &RunState, portfolio: &Portfolio) -> Option<f64> {
+        self.params.risk.drawdown_throttle.as_ref()?;
+        let equity = portfolio.equity();
+        let peak = state.equity_peak.max(equity);
+        Some(
+            self.params
+                .drawdown_size_multiplier((peak - equity) / peak * 100.0),
+        )
+    }
+
+    /// Process pending orders from latency simulation, with the bar's RSI
+    /// and ATR
+    #[allow(clippy::too_many_arguments)]
+    fn process_pending_orders(
+        &self,
+        portfolio: &mut Portfolio,
+        state: &mut RunState,
+        execution_sim: &mut ExecutionSimulator,
+        bar: &Bar,
+        hedge_bar: Option<&Bar>,
+        bar_index: usize,
+        (rsi, atr): (f64, f64),
+        volatility: Option<f64>,
+    ) {
+        let pending_orders = execution_sim.get_executable_orders(bar_index);
+
+        for order in pending_orders {
+            if order.work_until_bar_index.is_some() {
+                let Some(fill_bar) = (match order.side {
+                    Side::HedgeBuy => hedge_bar,
+                    _ => Some(bar),
+                }) else {
+                    continue;
+                };
+                self.fill_remainder(
+                    portfolio,
+                    state,
+                    execution_sim,
+                    fill_bar,
+                    bar_index,
+                    order,
+                    volatility,
+                );
+                continue;
+            }
+            match order.side {
+                Side::Buy => {
+                    let exec_result = execution_sim
+                        .simulate_execution(bar, Side::Buy, order.quantity, volatility)
+                        .for_order(order.order_id);
+                    if exec_result.executed
+                        && exec_result.fill_quantity >= 1.0
+                        && !self.cancels_partial(execution_sim, &exec_result)
+                    {
+                        let stop = self.params.risk.stop_loss;
+                        let stop_loss_price = stop.stop_price(exec_result.fill_price, atr);
+                        let opened = portfolio.open_position(
+                            &order.symbol,
+                            exec_result.fill_quantity,
+                            exec_result.fill_price,
+                            PositionSide::Long,
+                            bar.timestamp,
+                            stop_loss_price,
+                            self.params.execution.commission,
+                        );
+                        if opened.is_ok() {
+                            portfolio.annotate_stop_spec(PositionSide::Long, stop);
+                            portfolio.annotate_size_multiplier(state.entry_size_multiplier);
+                            // The trade limits and the spacing clock count the
+                            // fill, not the signal
+                            state.entry_limiter.record(bar.timestamp);
+                            state.entry_limiter.record_fill(&order.symbol, bar_index);
+                            let notes = exec_result.notes;
+                            self.audit_entry(
+                                state,
+                                portfolio,
+                                PositionSide::Long,
+                                order.order_id,
+                                notes,
+                            );
+                            self.work_remainder(
+                                execution_sim,
+                                order,
+                                exec_result.fill_quantity,
+                                bar_index,
+                            );
+                        }
+                    }
+                }
+                Side::HedgeBuy => {
+                    if let Some(hbar) = hedge_bar {
+                        let exec_result = execution_sim
+                            .simulate_execution(hbar, Side::HedgeBuy, order.quantity, volatility)
+                            .for_order(order.order_id);
+                        if exec_result.executed
+                            && exec_result.fill_quantity >= 1.0
+                            && !self.cancels_partial(execution_sim, &exec_result)
+                        {
+                            let stop = self.params.hedge.short_stop_loss;
+                            let hedge_atr = hedge_atr(atr, bar, hbar);
+                            let stop_loss_price =
+                                stop.stop_price(exec_result.fill_price, hedge_atr);
+                            let opened = portfolio.open_position(
+                                &order.symbol,
+                                exec_result.fill_quantity,
+                                exec_result.fill_price,
+                                PositionSide::Hedge,
+                                hbar.timestamp,
+                                stop_loss_price,
+                                self.params.execution.commission,
+                            );
+                            if opened.is_ok() {
+                                portfolio.annotate_stop_spec(PositionSide::Hedge, stop);
+                                state.entry_limiter.record(bar.timestamp);
+                                state.entry_limiter.record_fill(&order.symbol, bar_index);
+                                let notes = exec_result.notes;
+                                self.audit_entry(
+                                    state,
+                                    portfolio,
+                                    PositionSide::Hedge,
+                                    order.order_id,
+                                    notes,
+                                );
+                                self.work_remainder(
+                                    execution_sim,
+                                    order,
+                                    exec_result.fill_quantity,
+                                    bar_index,
+                                );
+                            }
+                        }
+                    }
+                }
+                Side::Sell => {
+                    // Delayed stop exit: fills at this bar's price, not the trigger bar's
+                    let triggered_at = state.pending_stop.take();
+                    if let Some(exit) =
+                        self.exit_order(portfolio, order.order_id, "stop loss", rsi, bar_index)
+                    {
+                        let exit = WorkingExit {
+                            stop_triggered_at: triggered_at,
+                            ..exit
+                        };
+                        self.fill_exit(
+                            portfolio,
+                            state,
+                            execution_sim,
+                            (bar, bar_index),
+                            volatility,
+                            exit,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Create empty result for insufficient data
+    fn empty_result(&self, bars: &[Bar], hedge_bars: Option<&[Bar]>) -> BacktestResult {
+        BacktestResult {
+            metrics: Default::default(),
+            metrics_scope: self.metrics_scope(),
+            hedge_metrics: None,
+            combined_metrics: None,
+            equity_curve: vec![],
+            drawdown_curve: vec![],
+            heat_curve: vec![],
+            net_exposure_curve: vec![],
+            trades: vec![],
+            signals: vec![],
+            fills: vec![],
+            suppressed_signals: vec![],
+            pre_trade_events: vec![],
+            warnings: validate_bars(bars, &self.params.execution),
+            halted_bars: 0,
+            start_date: bars
+                .first()
+                .map(|b| b.timestamp.date_naive())
+                .unwrap_or_else(|| chrono::Utc::now().date_naive()),
+            end_date: bars
+                .last()
+                .map(|b| b.timestamp.date_naive())
+                .unwrap_or_else(|| chrono::Utc::now().date_naive()),
+            initial_capital: self.params.initial_capital,
+            final_equity: self.params.initial_capital,
+            sizing_mode: self.params.risk.sizing_mode,
+            execution_time_ms: 0,
+            manifest: self.manifest(bars, hedge_bars),
+            spill: None,
+            approximate: false,
+        }
+    }
+}
+
+/// Calculate rolling volatility for each bar
+fn calculate_volatilities(closes: &[f64], period: usize) -> Vec<f64> {
+    let mut volatilities = vec![0.0; closes.len()];
+
+    if closes.len() < period + 1 {
+        return volatilities;
+    }
+
+    // Calculate returns
+    let returns: Vec<f64> = closes.windows(2).map(|w| (w[1] / w[0]).ln()).collect();
+
+    // Calculate rolling standard deviation of returns
+    for i in period..returns.len() {
+        let window = &returns[i - period..i];
+        let mean: f64 = window.iter().sum::<f64>() / period as f64;
+        let variance: f64 = window.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / period as f64;
+        volatilities[i + 1] = variance.sqrt() * (252_f64).sqrt(); // Annualized
+    }
+
+    volatilities
+}
+
+/// The main symbol's ATR in the hedge's price: the same fraction of its close
+fn hedge_atr(atr: f64, bar: &Bar, hedge_bar: &Bar) -> f64 {
+    atr / bar.close * hedge_bar.close
+}
+
+/// Snapshot the indicators of each trade's entry and exit bars. Hedge
+/// trades get the main symbol's, whose bars share their timestamps; trades
+/// opened before the data have no entry context.
+fn attach_trade_context(trades: &mut [Trade], bars: &[Bar], indicators: &IndicatorSeries) {
+    let volumes: Vec<u64> = bars.iter().map(|b| b.volume).collect();
+    let avg_volumes = calculate_avg_volume(&volumes, LIQUIDITY_AVG_PERIOD);
+    let snapshot = |timestamp: DateTime<Utc>| {
+        let i = bars
+            .binary_search_by_key(&timestamp, |b| b.timestamp)
+            .ok()?;
+        let mut values = indicators.get(i);
+        values.vwap = bars[i].vwap.or(values.vwap);
+        values.avg_volume = avg_volumes[i];
+        Some(values.snapshot(bars[i].volume))
+    };
+    for trade in trades {
+        trade.entry_context = snapshot(trade.entry_date);
+        trade.exit_context = trade.exit_date.and_then(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::fixtures::{pattern_bars, pattern_params, scenario};
+    use crate::data::{generate_bars_with_rsi_pattern, Relation, ScenarioBuilder};
+    use chrono::TimeZone;
+    use common::{AdaptiveSizing, DirectionMode, ExitDecay, Position, SizingMode, StopSpec};
+
+    fn generate_test_bars(n: usize, base_price: f64) -> Vec<Bar> {
+        use chrono::Duration;
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
+        (0..n)
+            .map(|i| {
+                let price = base_price + (i as f64 * 0.1).sin() * 5.0;
+                Bar {
+                    timestamp: start + Duration::days(i as i64),
+                    open: price - 0.1,
+                    high: price + 0.5,
+                    low: price - 0.5,
+                    close: price,
+                    volume: 1000000,
+                    vwap: Some(price + 0.2),
+                    halted: false,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_no_trades_before_indicators_warm_up() {
+        // RSI(30) only has a value from bar 30, past the Bollinger period;
+        // its placeholder 50 would pass for oversold under 55
+        let bars = pattern_bars(60, &[40]);
+        let mut params = pattern_params();
+        params.strategy.rsi_period = 30;
+        params.strategy.rsi_oversold = 55.0;
+        assert_eq!(params.warmup_bars(), 30);
+        let indicators = crate::analysis::indicators_for(&bars, &params);
+        assert_eq!(indicators.warm_from, params.warmup_bars());
+
+        let result = BacktestEngine::new(params).run(&bars, None);
+        assert!(!result.trades.is_empty());
+        assert_eq!(result.trades[0].entry_date, bars[40].timestamp);
+    }
+
+    #[test]
+    fn test_monthly_trade_limit_suppresses_entries() {
+        let oversold = [22, 30, 38, 46, 54];
+        let bars = pattern_bars(64, &oversold);
+
+        let unlimited = BacktestEngine::new(pattern_params()).run(&bars, None);
+        assert_eq!(unlimited.trades.len(), 5);
+        assert!(unlimited.suppressed_signals.is_empty());
+
+        let params = pattern_params().with_trade_limits(Some(2), None);
+        let result = BacktestEngine::new(params).run(&bars, None);
+
+        assert_eq!(result.trades.len(), 2);
+        assert!(result
+            .suppressed_signals
+            .iter()
+            .all(|s| s.signal_type == SignalType::Buy && s.reason.contains("monthly")));
+        assert_eq!(
+            result.metrics.suppressed_entries as usize,
+            result.suppressed_signals.len()
+        );
+
+        // RSI(2) stays oversold for a few bars after each dip, so group the
+        // suppressed bars back into the opportunities that produced them
+        let mut opportunities: Vec<usize> = result
+            .suppressed_signals
+            .iter()
+            .map(|s| {
+                let idx = bars
+                    .iter()
+                    .position(|b| b.timestamp == s.timestamp)
+                    .unwrap();
+                *oversold.iter().rev().find(|&&d| d <= idx).unwrap()
+            })
+            .collect();
+        opportunities.dedup();
+        assert_eq!(opportunities, vec![38, 46, 54]);
+    }
+
+    #[test]
+    fn test_roc_floor_skips_free_fall_dips() {
+        // Seven straight 3% drops from day 40: the 10-bar ROC passes -15%
+        // on day 45
+        let crash: Vec<usize> = (40..=46).collect();
+        let bars = generate_bars_with_rsi_pattern(80, 100.0, &crash, &[]);
+        let entry_days = |params: BacktestParameters| -> Vec<usize> {
+            let result = BacktestEngine::new(params).run(&bars, None);
+            result
+                .trades
+                .iter()
+                .map(|t| {
+                    bars.iter()
+                        .position(|b| b.timestamp == t.entry_date)
+                        .unwrap()
+                })
+                .collect()
+        };
+
+        // Stopped out twice, the dip buyer keeps catching the knife
+        let unfloored = entry_days(pattern_params());
+        assert_eq!(unfloored, vec![40, 43, 46]);
+
+        let mut params = pattern_params();
+        params.strategy.momentum_min_pct = Some(-15.0);
+        assert_eq!(entry_days(params), vec![40, 43]);
+    }
+
+    #[test]
+    fn test_sma_slope_filter_rejects_flat_trend() {
+        // Flat at 100, a 5% step on day 30, then a dip on day 33 that stays
+        // above a barely-rising SMA(20)
+        let mut scenario = scenario(40).return_pct(30, 5.0).oversold(33);
+        for day in (1..30).chain(31..33) {
+            scenario = scenario.return_pct(day, 0.0);
+        }
+        let bars = scenario.sma(33, Relation::Above, 20).build();
+        let params = pattern_params().with_sma_period(20);
+
+        let unfiltered = BacktestEngine::new(params.clone()).run(&bars, None);
+        assert_eq!(unfiltered.trades.len(), 1);
+        assert_eq!(unfiltered.trades[0].entry_date, bars[33].timestamp);
+
+        let result = BacktestEngine::new(params.with_sma_slope_filter(5, 1.0)).run(&bars, None);
+        assert!(result.trades.is_empty());
+        let suppressed = &result.suppressed_signals[0];
+        assert_eq!(suppressed.timestamp, bars[33].timestamp);
+        assert!(
+            suppressed.reason.starts_with("SMA slope +0.8"),
+            "{}",
+            suppressed.reason
+        );
+    }
+
+    #[test]
+    fn test_pending_order_rolls_past_halted_bar() {
+        let mut bars = pattern_bars(40, &[22]);
+        let mut params = pattern_params();
+        params.execution.simulation = common::RealisticExecutionConfig::realistic();
+        params.execution.simulation.latency_bars = 1;
+        params.execution.min_bar_volume = 1;
+
+        let baseline = BacktestEngine::new(params.clone()).run(&bars, None);
+        assert_eq!(baseline.trades[0].entry_date, bars[23].timestamp);
+
+        // Halt the bar the order would have filled on
+        bars[23].volume = 0;
+        let result = BacktestEngine::new(params).run(&bars, None);
+
+        assert_eq!(result.trades[0].entry_date, bars[24].timestamp);
+        assert!(result
+            .warnings
+            .iter()
+            .any(|w| w.kind == common::DataWarningKind::HaltedBar
+                && w.timestamp == Some(bars[23].timestamp)));
+    }
+
+    #[test]
+    fn test_shadows_match_standalone_runs() {
+        let bars = crate::data::generate_synthetic_bars_seeded(400, 50.0, 11);
+        let primary = BacktestParameters::default().without_vwap_filter();
+        let shadows = vec![
+            primary.clone().with_rsi_thresholds(20.0, 70.0),
+            primary
+                .clone()
+                .with_stop_loss(0.02)
+                .with_max_holding_days(5),
+            // Own indicator series
+            {
+                let mut params = primary.clone().with_sma_period(50);
+                params.strategy.rsi_period = 3;
+                params
+            },
+            // Differs only in the gap reset, which re-seeds RSI and ATR
+            {
+                let mut params = primary.clone();
+                params.strategy.rsi_reset_on_gap_pct = Some(0.5);
+                params
+            },
+            // More warmup than there are bars
+            primary.clone().with_sma_period(500),
+        ];
+
+        let results =
+            BacktestEngine::run_with_shadows(&bars, None, primary.clone(), shadows.clone());
+        assert_eq!(results.len(), 6);
+
+        let key = |r: &BacktestResult| {
+            let trades: Vec<_> = r
+                .trades
+                .iter()
+                .map(|t| (t.entry_date, t.exit_date, t.pnl, t.exit_reason.clone()))
+                .collect();
+            (trades, r.equity_curve.clone(), r.final_equity)
+        };
+        for (params, shadow) in std::iter::once(primary).chain(shadows).zip(&results) {
+            let standalone = BacktestEngine::new(params).run(&bars, None);
+            assert_eq!(key(shadow), key(&standalone));
+            assert_eq!(shadow.metrics.sharpe_ratio, standalone.metrics.sharpe_ratio);
+        }
+        assert!(results[..4].iter().all(|r| !r.trades.is_empty()));
+        assert_ne!(key(&results[4]), key(&results[0]));
+        assert!(results[5].equity_curve.is_empty());
+    }
+
+    #[test]
+    fn test_drawdown_throttle() {
+        // Slow drift with scheduled entries; the bar after each entry either
+        // stops out 12% lower or spikes 60% into an RSI exit
+        let entries = [25, 30, 35, 40, 45];
+        let moves = [-12.0, -12.0, -12.0, 60.0, -12.0];
+        let bars = entries
+            .iter()
+            .zip(moves)
+            .fold(scenario(60), |s, (&e, pct)| s.return_pct(e + 1, pct))
+            .build();
+        let schedule: Vec<bool> = (0..bars.len()).map(|i| entries.contains(&i)).collect();
+
+        let params = pattern_params()
+            .with_sizing_mode(SizingMode::FixedNotional(5000.0))
+            .with_stop_loss(0.05);
+        let mut throttled = params.clone();
+        throttled.risk.drawdown_throttle = Some(vec![(10.0, 0.5)]);
+        let run = |params| {
+            BacktestEngine::new(params)
+                .with_entry_schedule(schedule.clone())
+                .run(&bars, None)
+        };
+        let full = run(params);
+        let result = run(throttled);
+
+        assert_eq!(result.trades.len(), 5);
+        let multipliers: Vec<Option<f64>> =
+            result.trades.iter().map(|t| t.size_multiplier).collect();
+        // Two 12% stops put equity ~11.8% under its peak; the spike recovers it
+        assert_eq!(
+            multipliers,
+            vec![Some(1.0), Some(1.0), Some(0.5), Some(0.5), Some(1.0)]
+        );
+        for (trade, unthrottled) in result.trades.iter().zip(&full.trades) {
+            let expected = (5000.0 / trade.entry_price).floor();
+            let expected = (expected * trade.size_multiplier.unwrap()).floor();
+            assert_eq!(trade.quantity, expected);
+            assert_eq!(unthrottled.entry_date, trade.entry_date);
+            assert_eq!(unthrottled.size_multiplier, None);
+        }
+    }
+
+    #[test]
+    fn test_adaptive_sizing_follows_win_rate() {
+        // Five entries that spike 60% into an RSI exit, then five that stop
+        // out 12% lower
+        let entries: Vec<usize> = (0..10).map(|i| 25 + 5 * i).collect();
+        let bars = entries
+            .iter()
+            .enumerate()
+            .fold(scenario(80), |s, (i, &e)| {
+                s.return_pct(e + 1, if i < 5 { 60.0 } else { -12.0 })
+            })
+            .build();
+        let schedule: Vec<bool> = (0..bars.len()).map(|i| entries.contains(&i)).collect();
+
+        let mut params = pattern_params()
+            .with_sizing_mode(SizingMode::FixedNotional(2000.0))
+            .with_stop_loss(0.05);
+        let sizing = AdaptiveSizing {
+            lookback_trades: 5,
+            min_mult: 0.6,
+            max_mult: 1.4,
+            sensitivity: 1.0,
+        };
+        params.risk.adaptive_sizing = Some(sizing);
+        let result = BacktestEngine::new(params)
+            .with_entry_schedule(schedule)
+            .run(&bars, None);
+
+        assert_eq!(result.trades.len(), 10);
+        let multipliers: Vec<f64> = result
+            .trades
+            .iter()
+            .map(|t| t.size_multiplier.unwrap())
+            .collect();
+        let expected = [1.0, 1.1, 1.2, 1.3, 1.4, 1.4, 1.3, 1.1, 0.9, 0.7];
+        for (multiplier, expected) in multipliers.iter().zip(expected) {
+            assert!((multiplier - expected).abs() < 1e-9, "{:?}", multipliers);
+        }
+        assert!(multipliers
+            .iter()
+            .all(|m| (sizing.min_mult..=sizing.max_mult).contains(m)));
+        for trade in &result.trades {
+            let base = (2000.0 / trade.entry_price).floor();
+            assert_eq!(
+                trade.quantity,
+                (base * trade.size_multiplier.unwrap()).floor()
+            );
+        }
+    }
+
+    #[test]
+    fn test_fixed_notional_sizing() {
+        let oversold = [22, 30, 38, 46, 54];
+        let bars = pattern_bars(64, &oversold);
+
+        let params = pattern_params().with_sizing_mode(SizingMode::FixedNotional(5000.0));
+        let result = BacktestEngine::new(params).run(&bars, None);
+
+        assert_eq!(result.trades.len(), 5);
+        assert_eq!(result.sizing_mode, SizingMode::FixedNotional(5000.0));
+        // Equity moves between entries but the bet size does not
+        assert_ne!(result.final_equity, result.initial_capital);
+        for trade in &result.trades {
+            let notional = trade.entry_price * trade.quantity;
+            assert!(
+                notional <= 5000.0 && notional > 5000.0 - trade.entry_price,
+                "{}",
+                notional
+            );
+        }
+    }
+
+    #[test]
+    fn test_trade_limits_count_latency_fills() {
+        // The entry queued on the dip at bar 22 finds no volume to fill
+        // against on bar 23, so the month's one entry is still open to the
+        // signal RSI(2) repeats there
+        let bars = scenario(40).oversold(22).volume(23, 0.0).build();
+        let mut params = pattern_params().with_trade_limits(Some(1), None);
+        params.execution.simulation = common::RealisticExecutionConfig {
+            enabled: true,
+            slippage_min_pct: 0.0,
+            slippage_max_pct: 0.0,
+            spread_enabled: false,
+            volume_limit_enabled: true,
+            market_impact_enabled: false,
+            latency_bars: 1,
+            ..Default::default()
+        };
+
+        let result = BacktestEngine::new(params).run(&bars, None);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].entry_date, bars[24].timestamp);
+        assert!(result.suppressed_signals.is_empty());
+    }
+
+    #[test]
+    fn test_stops_subject_to_latency() {
+        // Entry fills at bar 23; bar 24 closes through the 5% stop and the
+        // decline continues into bar 25
+        let bars = scenario(40)
+            .oversold(22)
+            .return_pct(24, -7.0)
+            .return_pct(25, -5.0)
+            .build();
+        let mut params = pattern_params();
+        params.execution.simulation = common::RealisticExecutionConfig {
+            enabled: true,
+            slippage_min_pct: 0.0,
+            slippage_max_pct: 0.0,
+            spread_enabled: false,
+            volume_limit_enabled: false,
+            market_impact_enabled: false,
+            latency_bars: 1,
+            ..Default::default()
+        };
+
+        let immediate = BacktestEngine::new(params.clone()).run(&bars, None);
+        let trade = &immediate.trades[0];
+        assert_eq!(trade.entry_date, bars[23].timestamp);
+        assert_eq!(trade.exit_reason, "stop loss");
+        assert_eq!(trade.exit_date, Some(bars[24].timestamp));
+        assert_eq!(trade.stop_triggered_at, None);
+
+        params.execution.simulation.stops_subject_to_latency = true;
+        let delayed = BacktestEngine::new(params).run(&bars, None);
+        let delayed_trade = &delayed.trades[0];
+        assert_eq!(delayed_trade.exit_reason, "stop loss");
+        assert_eq!(delayed_trade.stop_triggered_at, Some(bars[24].timestamp));
+        assert_eq!(delayed_trade.exit_date, Some(bars[25].timestamp));
+        let fill_bar = &bars[25];
+        let fill = fill_bar.vwap.unwrap().clamp(fill_bar.low, fill_bar.high);
+        assert_eq!(delayed_trade.exit_price, Some(fill));
+        assert!(delayed_trade.pnl < trade.pnl);
+    }
+
+    #[test]
+    fn test_halted_stop_policies() {
+        // Halt, then reopen through the 5% stop and recover by the close
+        let bars = scenario(40)
+            .oversold(22)
+            .volume(23, 0.0)
+            .gap(24, -10.0)
+            .return_pct(24, 0.0)
+            .build();
+
+        let mut params = pattern_params();
+        params.execution.min_bar_volume = 1;
+
+        let skip = BacktestEngine::new(params.clone()).run(&bars, None);
+        assert_ne!(skip.trades[0].exit_reason, "stop loss (after halt)");
+
+        params.execution.halted_stop_policy = HaltedStopPolicy::NextOpen;
+        let next_open = BacktestEngine::new(params).run(&bars, None);
+        let trade = &next_open.trades[0];
+        assert_eq!(trade.exit_reason, "stop loss (after halt)");
+        assert_eq!(trade.exit_date, Some(bars[24].timestamp));
+        assert_eq!(trade.exit_price, Some(bars[24].open));
+    }
+
+    #[test]
+    fn test_stop_during_trading_halt_fills_at_reopen() {
+        // Two halted bars fall through the 5% stop; trading reopens lower
+        let bars = scenario(40)
+            .oversold(22)
+            .halted(23)
+            .return_pct(23, -4.0)
+            .halted(24)
+            .return_pct(24, -4.0)
+            .gap(25, -2.0)
+            .return_pct(25, 1.0)
+            .build();
+
+        let result = BacktestEngine::new(pattern_params()).run(&bars, None);
+        assert_eq!(result.halted_bars, 2);
+        let trade = &result.trades[0];
+        assert_eq!(trade.entry_date, bars[22].timestamp);
+        assert_eq!(trade.exit_reason, "stop loss (after halt)");
+        assert_eq!(trade.exit_date, Some(bars[25].timestamp));
+        assert_eq!(trade.exit_price, Some(bars[25].open));
+
+        // The whole gap, well past the stop, is lost
+        let expected = (bars[25].open - trade.entry_price) * trade.quantity;
+        assert!(trade.pnl_pct < -9.0, "pnl_pct {}", trade.pnl_pct);
+        assert!(
+            (trade.pnl - expected).abs() < 1e-6,
+            "pnl {} vs {}",
+            trade.pnl,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_bar_stride() {
+        let bars = crate::data::generate_synthetic_bars_seeded(300, 50.0, 42);
+        let hedge = crate::data::generate_synthetic_bars_seeded(300, 30.0, 43);
+        let params = BacktestParameters::default().without_vwap_filter();
+        let run = |stride: usize| {
+            BacktestEngine::new(params.clone())
+                .with_execution_seed(7)
+                .with_bar_stride(stride)
+                .run(&bars, Some(&hedge))
+        };
+        let full = BacktestEngine::new(params.clone())
+            .with_execution_seed(7)
+            .run(&bars, Some(&hedge));
+        assert!(full.trades.len() > 2);
+
+        // Stride 1 is the normal path
+        let strided = run(1);
+        assert!(!strided.approximate);
+        let json = |r: &BacktestResult| {
+            serde_json::to_value((&r.metrics, &r.trades, &r.equity_curve, &r.fills)).unwrap()
+        };
+        assert_eq!(json(&strided), json(&full));
+
+        let coarse = run(3);
+        assert!(coarse.approximate);
+        // 100 strided bars, less the same warmup
+        assert_eq!(coarse.equity_curve.len(), 100 - params.warmup_bars());
+        assert!(serde_json::to_string(&coarse)
+            .unwrap()
+            .contains("\"approximate\":true"));
+        assert!(!serde_json::to_string(&full)
+            .unwrap()
+            .contains("approximate"));
+    }
+
+    #[test]
+    fn test_flat_slippage_only_without_simulation() {
+        let bars = pattern_bars(40, &[22]);
+        let close_at = |ts| bars.iter().find(|b| b.timestamp == ts).unwrap().close;
+        let run = |simulated: bool, slippage: f64| {
+            let mut params = pattern_params().with_slippage(slippage);
+            params.execution.simulation.enabled = simulated;
+            let result = BacktestEngine::new(params.clone())
+                .with_execution_seed(7)
+                .run(&bars, None);
+            (params.validate().is_ok(), result)
+        };
+        let ignored = |result: &BacktestResult| {
+            result
+                .warnings
+                .iter()
+                .any(|w| w.kind == DataWarningKind::FlatSlippageIgnored)
+        };
+
+        let (valid, frictionless) = run(false, 0.0);
+        assert!(valid && !ignored(&frictionless));
+        let trade = &frictionless.trades[0];
+        assert_eq!(trade.entry_price, close_at(trade.entry_date));
+
+        // Both legs pay: the entry above the close, the exit below it
+        let (valid, slipped) = run(false, 0.001);
+        assert!(valid && !ignored(&slipped));
+        let trade = &slipped.trades[0];
+        let exit_close = close_at(trade.exit_date.unwrap());
+        assert!((trade.entry_price - close_at(trade.entry_date) * 1.001).abs() < 1e-9);
+        assert!((trade.exit_price.unwrap() - exit_close * 0.999).abs() < 1e-9);
+        assert!(slipped.final_equity < frictionless.final_equity);
+
+        // With the simulation on, the flat figure is ignored and rejected
+        let (valid, simulated) = run(true, 0.0);
+        assert!(valid && !ignored(&simulated));
+        let (valid, both) = run(true, 0.001);
+        assert!(!valid && ignored(&both));
+        assert_eq!(both.final_equity, simulated.final_equity);
+        assert!(both
+            .warnings
+            .iter()
+            .any(|w| w.kind == DataWarningKind::InvalidParameters));
+    }
+
+    #[test]
+    fn test_trade_context_recorded_on_request() {
+        let bars = pattern_bars(60, &[22, 40]);
+        let plain = BacktestEngine::new(pattern_params()).run(&bars, None);
+        let mut params = pattern_params();
+        params.record_trade_context = true;
+        let recorded = BacktestEngine::new(params).run(&bars, None);
+        assert!(recorded.trades.len() >= 2);
+
+        for trade in &recorded.trades {
+            let entry = trade.entry_context.unwrap();
+            assert_eq!(Some(entry.rsi), trade.entry_rsi);
+            assert_eq!(trade.exit_context.map(|c| c.rsi), trade.exit_rsi);
+            assert!(entry.atr > 0.0 && entry.bb_lower < entry.bb_upper);
+            assert!(entry.volume_ratio.is_some());
+        }
+
+        // Off by default, and absent from the JSON rather than null
+        assert!(plain
+            .trades
+            .iter()
+            .all(|t| t.entry_context.is_none() && t.exit_context.is_none()));
+        let plain_json = serde_json::to_string(&plain.trades).unwrap();
+        assert!(!plain_json.contains("context"));
+        let mut stripped = recorded.trades.clone();
+        for trade in &mut stripped {
+            trade.entry_context = None;
+            trade.exit_context = None;
+        }
+        assert_eq!(serde_json::to_string(&stripped).unwrap(), plain_json);
+    }
+
+    #[test]
+    fn test_exit_staged_over_bars_by_volume_cap() {
+        let mut bars = scenario(60).oversold(22).overbought(30).build();
+        for bar in &mut bars {
+            bar.vwap = None;
+        }
+        let mut params = pattern_params();
+        params.execution.simulation = common::RealisticExecutionConfig {
+            enabled: true,
+            slippage_min_pct: 0.0,
+            slippage_max_pct: 0.0,
+            spread_enabled: false,
+            market_impact_enabled: false,
+            ..Default::default()
+        };
+        let run = |bars: &[Bar]| BacktestEngine::new(params.clone()).run(bars, None);
+        let quantity = run(&bars).trades[0].quantity;
+
+        // After the entry bar, each bar takes a tenth of the position
+        let pct = params.execution.simulation.volume_participation_max_pct;
+        for bar in &mut bars[23..] {
+            bar.volume = (quantity / 10.0 * bar.close / pct).ceil() as u64;
+        }
+        let result = run(&bars);
+        let trade = &result.trades[0];
+        assert_eq!(trade.quantity, quantity);
+        assert!(trade.fills.len() >= 10, "{} fills", trade.fills.len());
+        assert_eq!(trade.exit_date, Some(trade.fills.last().unwrap().timestamp));
+        let sold: f64 = trade.fills.iter().map(|f| f.quantity).sum();
+        assert!((sold - quantity).abs() < 1e-9);
+        assert_eq!(result.metrics.multi_bar_exits, 1);
+
+        // Between tranches the equity moves with the shares still held
+        let equity_at = |ts| {
+            result
+                .equity_curve
+                .iter()
+                .find(|(t, _)| *t == ts)
+                .unwrap()
+                .1
+        };
+        let close_at = |ts| bars.iter().find(|b| b.timestamp == ts).unwrap().close;
+        let mut held = quantity;
+        for pair in trade.fills.windows(2) {
+            held -= pair[0].quantity;
+            let (from, to) = (pair[0].timestamp, pair[1].timestamp);
+            let moved = equity_at(to) - equity_at(from);
+            assert!((moved - held * (close_at(to) - close_at(from))).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_heat_of_single_position() {
+        let bars = pattern_bars(40, &[22]);
+        let mut params = pattern_params();
+        params.risk.cash_reserve_pct = 0.0;
+
+        let result = BacktestEngine::new(params).run(&bars, None);
+
+        // Entry on bar 22; the curves start at the warmup bar (20)
+        assert_eq!(result.trades[0].entry_date, bars[22].timestamp);
+        let (ts, heat) = result.heat_curve[2];
+        assert_eq!(ts, bars[22].timestamp);
+        assert!((heat - 4.5).abs() < 0.1, "heat was {}", heat);
+        assert_eq!(result.heat_curve[1].1, 0.0);
+        assert!(result.metrics.max_heat_pct >= heat);
+        assert!(result.metrics.avg_heat_pct > 0.0);
+
+        // The unhedged long is its whole market value of net exposure
+        let equity = result.equity_curve[2].1;
+        assert!((result.net_exposure_curve[2].1 / equity - 0.9).abs() < 0.01);
+        assert_eq!(result.net_exposure_curve[1].1, 0.0);
+        assert!((result.metrics.max_net_exposure_pct - 90.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_heat_cap_shrinks_entry() {
+        let bars = pattern_bars(40, &[22]);
+        let mut params = pattern_params();
+        params.risk.cash_reserve_pct = 0.0;
+        let uncapped = BacktestEngine::new(params.clone()).run(&bars, None);
+
+        params.risk.max_portfolio_heat_pct = Some(3.0);
+        let capped = BacktestEngine::new(params.clone()).run(&bars, None);
+
+        assert!(capped.trades[0].quantity < uncapped.trades[0].quantity);
+        assert!(capped.heat_curve[2].1 <= 3.0);
+
+        // A cap below one share's risk blocks the entry outright
+        params.risk.max_portfolio_heat_pct = Some(0.001);
+        let blocked = BacktestEngine::new(params).run(&bars, None);
+        assert!(blocked.trades.is_empty());
+        assert!(blocked
+            .suppressed_signals
+            .iter()
+            .all(|s| s.reason == SKIP_HEAT_CAP));
+        assert!(!blocked.suppressed_signals.is_empty());
+    }
+
+    #[test]
+    fn test_liquidity_aware_sizing() {
+        // Thin tape: 2% of 300k volume at ~$100 is ~60 shares, far below
+        // the ~900 shares the position size asks for
+        let mut bars = pattern_bars(40, &[22]);
+        for bar in bars.iter_mut() {
+            bar.volume = 300_000;
+        }
+        let mut params = pattern_params();
+        params.execution.simulation = common::RealisticExecutionConfig::realistic();
+        params.execution.simulation.rejection_enabled = false;
+        params.risk.liquidity_aware_sizing = true;
+
+        let result = BacktestEngine::new(params.clone()).run(&bars, None);
+        let trade = &result.trades[0];
+        let cap = (300_000.0 * params.execution.simulation.volume_participation_max_pct
+            / bars[22].close)
+            .floor();
+        assert_eq!(trade.entry_date, bars[22].timestamp);
+        assert_eq!(trade.quantity, cap);
+        assert!(result.metrics.liquidity_capped_entries >= 1);
+
+        // The capped order fits the bar's volume, so it fills without truncation
+        let mut sim = ExecutionSimulator::new(params.execution.simulation.clone());
+        let fill = sim.simulate_execution(&bars[22], Side::Buy, cap, None);
+        assert_eq!(fill.fill_quantity, cap);
+        assert!(fill.notes.is_empty(), "notes: {:?}", fill.notes);
+
+        params.risk.liquidity_aware_sizing = false;
+        let plain = BacktestEngine::new(params).run(&bars, None);
+        assert_eq!(plain.metrics.liquidity_capped_entries, 0);
+    }
+
+    /// Daily bars whose return starts at `drift` and changes by `accel` per
+    /// bar, with a 3% dip every `dip_every` bars (0 for none)
+    fn momentum_bars(days: usize, drift: f64, accel: f64, dip_every: usize) -> Vec<Bar> {
+        let start = Utc.with_ymd_and_hms(2022, 1, 3, 16, 0, 0).unwrap();
+        let mut price = 100.0;
+        (0..days)
+            .map(|i| {
+                let open = price;
+                let dip = dip_every > 0 && i % dip_every == dip_every - 1;
+                price *= 1.0 + if dip { -0.03 } else { drift + accel * i as f64 };
+                Bar {
+                    timestamp: start + chrono::Duration::days(i as i64),
+                    open,
+                    high: open.max(price) * 1.002,
+                    low: open.min(price) * 0.998,
+                    close: price,
+                    volume: 1_000_000,
+                    vwap: None,
+                    halted: false,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_momentum_filter() {
+        let mut params = pattern_params();
+        params.strategy.momentum_filter_enabled = true;
+        // First bar with a ROC percentile
+        let warm = params.strategy.momentum_roc_period + crate::indicators::ROC_RANK_WINDOW - 1;
+        let entries_after_warmup = |bars: &[Bar], params: BacktestParameters| {
+            let result = BacktestEngine::new(params).run(bars, None);
+            result
+                .trades
+                .iter()
+                .filter(|t| t.entry_date >= bars[warm].timestamp)
+                .count()
+        };
+
+        // A decline that keeps steepening: ROC makes new lows every bar,
+        // and RSI(2) is pinned oversold
+        let falling = momentum_bars(500, -0.002, -0.00001, 0);
+        assert!(entries_after_warmup(&falling, pattern_params()) > 0);
+        assert_eq!(entries_after_warmup(&falling, params.clone()), 0);
+
+        // A strengthening uptrend: dips are bought with or without the filter
+        let rising = momentum_bars(500, 0.001, 0.00001, 10);
+        let unfiltered = entries_after_warmup(&rising, pattern_params());
+        assert!(unfiltered > 0);
+        assert_eq!(entries_after_warmup(&rising, params), unfiltered);
+    }
+
+    #[test]
+    fn test_edge_filter() {
+        let bars = pattern_bars(64, &[22, 38, 54]);
+        let mut params = pattern_params();
+        let baseline = BacktestEngine::new(params.clone()).run(&bars, None);
+        assert!(!baseline.trades.is_empty());
+
+        // Zero costs and no margin: every entry still clears the filter
+        params.risk.edge_filter_enabled = true;
+        params.risk.min_expected_profit_pct = 0.0;
+        let free = BacktestEngine::new(params.clone()).run(&bars, None);
+        assert_eq!(free.trades.len(), baseline.trades.len());
+        assert!(free.suppressed_signals.is_empty());
+
+        // Commissions that dwarf any ATR move block every entry
+        params.execution.commission = 5_000.0;
+        let costly = BacktestEngine::new(params).run(&bars, None);
+        assert!(costly.trades.is_empty());
+        assert!(!costly.suppressed_signals.is_empty());
+        assert!(costly
+            .suppressed_signals
+            .iter()
+            .all(|s| s.reason.starts_with("insufficient edge")));
+    }
+
+    /// The steady drift pins RSI(2) near 100, so the hedge opens at warmup;
+    /// with the hedge exit level at 0 only the policy can close it before
+    /// the dip on bar 22
+    fn hedged_params(policy: ConcurrentHedgePolicy) -> BacktestParameters {
+        let mut params = pattern_params();
+        params.hedge.short_enabled = true;
+        params.hedge.rsi_oversold_short = 0.0;
+        params.hedge.concurrent_hedge_policy = policy;
+        params
+    }
+
+    /// The spike on bar 25 opens the hedge and the dip on bar 32 is the
+    /// long entry; the chop around them triggers neither
+    fn hedged_run(policy: ConcurrentHedgePolicy) -> (Vec<Bar>, BacktestResult) {
+        let bars = choppy(50, &[25, 32]).overbought(25).oversold(32).build();
+        let result = BacktestEngine::new(hedged_params(policy)).run(&bars, Some(&bars));
+        (bars, result)
+    }
+
+    #[test]
+    fn test_concurrent_hedge_policies() {
+        let dip = |bars: &[Bar]| bars[32].timestamp;
+        let long = |t: &&common::Trade| t.side == Side::Sell;
+        let hedge = |t: &&common::Trade| t.side == Side::HedgeSell;
+
+        let (bars, allow) = hedged_run(ConcurrentHedgePolicy::Allow);
+        let long_trade = allow.trades.iter().find(long).unwrap();
+        let hedge_trade = allow.trades.iter().find(hedge).unwrap();
+        assert_eq!(long_trade.entry_date, dip(&bars));
+        // Both legs were open together
+        assert_eq!(hedge_trade.entry_date, bars[25].timestamp);
+        assert!(hedge_trade.exit_date.unwrap() > dip(&bars));
+        assert_eq!(allow.trades.iter().filter(hedge).count(), 1);
+
+        let (bars, block) = hedged_run(ConcurrentHedgePolicy::Block);
+        assert!(!block.trades.iter().any(|t| t.side == Side::Sell));
+        assert!(block
+            .suppressed_signals
+            .iter()
+            .any(|s| s.timestamp == dip(&bars) && s.reason == SKIP_HEDGE_OPEN));
+
+        let (bars, close_first) = hedged_run(ConcurrentHedgePolicy::CloseHedgeFirst);
+        let hedge_trade = close_first.trades.iter().find(hedge).unwrap();
+        assert_eq!(hedge_trade.exit_date, Some(dip(&bars)));
+        assert_eq!(hedge_trade.exit_reason, HEDGE_CLOSED_FOR_LONG);
+        let long_trade = close_first.trades.iter().find(long).unwrap();
+        assert_eq!(long_trade.entry_date, dip(&bars));
+        assert!(close_first.suppressed_signals.is_empty());
+
+        // A long the edge filter turns away leaves the hedge open
+        let mut params = hedged_params(ConcurrentHedgePolicy::CloseHedgeFirst);
+        params.risk.edge_filter_enabled = true;
+        params.risk.min_expected_profit_pct = 50.0;
+        let vetted = BacktestEngine::new(params).run(&bars, Some(&bars));
+        assert!(!vetted.trades.iter().any(|t| t.side == Side::Sell));
+        assert!(vetted
+            .trades
+            .iter()
+            .all(|t| t.exit_reason != HEDGE_CLOSED_FOR_LONG));
+        let hedge_trade = vetted.trades.iter().find(hedge).unwrap();
+        assert_ne!(hedge_trade.exit_date, Some(dip(&bars)));
+        assert!(vetted
+            .suppressed_signals
+            .iter()
+            .any(|s| s.timestamp == dip(&bars) && s.reason.contains("edge")));
+    }
+
+    #[test]
+    fn test_flip_on_extreme_readings() {
+        // The drift opens the hedge at warmup, the dip on bar 22 closes it
+        // and the spike on bar 25 lifts RSI(2) past 90 while long
+        let bars = scenario(40).oversold(22).return_pct(25, 10.0).build();
+        let run = |flip: bool| {
+            let mut params = pattern_params();
+            params.hedge.short_enabled = true;
+            params.hedge.flip_to_hedge_on_extreme = flip;
+            params.hedge.flip_to_long_on_oversold = flip;
+            BacktestEngine::new(params).run(&bars, Some(&bars))
+        };
+        let legs = |result: &BacktestResult| {
+            let long = result
+                .trades
+                .iter()
+                .find(|t| t.side == Side::Sell)
+                .cloned()
+                .unwrap();
+            let hedges: Vec<_> = result
+                .trades
+                .iter()
+                .filter(|t| t.side == Side::HedgeSell)
+                .cloned()
+                .collect();
+            (long, hedges)
+        };
+
+        // Each reversal waits for the next bar
+        let (long, hedges) = legs(&run(false));
+        assert_eq!(hedges[0].exit_date, Some(bars[22].timestamp));
+        assert_eq!(long.entry_date, bars[23].timestamp);
+        assert_eq!(long.exit_date, Some(bars[25].timestamp));
+        assert_eq!(hedges[1].entry_date, bars[26].timestamp);
+
+        let (long, hedges) = legs(&run(true));
+        assert_eq!(hedges[0].exit_date, Some(bars[22].timestamp));
+        assert_eq!(long.entry_date, bars[22].timestamp);
+        assert_eq!(long.exit_date, Some(bars[25].timestamp));
+        assert!(long.exit_rsi.unwrap() >= 90.0);
+        assert_eq!(hedges[1].entry_date, bars[25].timestamp);
+    }
+
+    #[test]
+    fn test_simulated_overnight_and_hedge_exits_pay_the_spread() {
+        // Without VWAP the simulator prices off the close, so a spread-only
+        // fill lands just under it
+        let mut bars = choppy(50, &[25, 32]).overbought(25).oversold(32).build();
+        for bar in &mut bars {
+            bar.vwap = None;
+        }
+        let mut params = pattern_params();
+        params.execution.simulation = common::RealisticExecutionConfig {
+            enabled: true,
+            slippage_min_pct: 0.0,
+            slippage_max_pct: 0.0,
+            market_impact_enabled: false,
+            ..Default::default()
+        };
+        let below_close = |trade: &Trade| {
+            let exit = trade.exit_date.unwrap();
+            let close = bars.iter().find(|b| b.timestamp == exit).unwrap().close;
+            trade.exit_price.unwrap() < close
+        };
+
+        let mut hedged = params.clone();
+        hedged.hedge.short_enabled = true;
+        let result = BacktestEngine::new(hedged).run(&bars, Some(&bars));
+        let hedge = result.trades.iter().find(|t| t.is_hedge()).unwrap();
+        assert!(below_close(hedge), "hedge exit at {:?}", hedge.exit_price);
+
+        params.risk.overnight_flat = true;
+        let result = BacktestEngine::new(params).run(&bars, None);
+        let flattened: Vec<_> = result
+            .trades
+            .iter()
+            .filter(|t| t.exit_reason == OVERNIGHT_FLAT)
+            .collect();
+        assert!(!flattened.is_empty());
+        assert!(flattened.iter().all(|t| below_close(t)));
+    }
+
+    #[test]
+    fn test_overnight_flat() {
+        // Holding from the dip on bar 22, the long sits through a 6% gap
+        // down at the open of bar 24
+        let bars = scenario(40).oversold(22).gap(24, -6.0).build();
+        let held = BacktestEngine::new(pattern_params()).run(&bars, None);
+        assert_eq!(held.trades[0].entry_date, bars[22].timestamp);
+        assert!((held.metrics.worst_overnight_gap_in_trade_pct + 6.0).abs() < 1e-9);
+
+        let mut params = pattern_params();
+        params.risk.overnight_flat = true;
+        let flat = BacktestEngine::new(params).run(&bars, None);
+
+        // Entered at the next open, and never held across bars
+        assert_eq!(flat.trades[0].entry_date, bars[23].timestamp);
+        assert!((flat.trades[0].entry_price - bars[23].open).abs() < 1e-9);
+        assert!(flat
+            .trades
+            .iter()
+            .all(|t| t.exit_date == Some(t.entry_date)));
+        assert_eq!(flat.trades[0].exit_reason, OVERNIGHT_FLAT);
+        assert_eq!(flat.metrics.worst_overnight_gap_in_trade_pct, 0.0);
+        assert!(flat.metrics.overnight_gap_histogram.is_empty());
 
-        let _ = portfolio.open_position(
-            &self.params.inverse_symbol,
-            exec_result.fill_quantity,
-            exec_result.fill_price,
-            PositionSide::Hedge,
-            bar.timestamp,
-            stop_loss_price,
-            self.params.commission,
+        // The gap is sidestepped: bar 24 is bought after it, at the open
+        let reopened = flat
+            .trades
+            .iter()
+            .find(|t| t.entry_date == bars[24].timestamp)
+            .unwrap();
+        assert!((reopened.entry_price - bars[24].open).abs() < 1e-9);
+        assert!(reopened.pnl > 0.0);
+        assert_eq!(
+            flat.trades.last().unwrap().exit_reason,
+            held.trades[0].exit_reason
         );
     }
 
-    /// Process pending orders from latency simulation
-    fn process_pending_orders(
-        &self,
-        portfolio: &mut Portfolio,
-        execution_sim: &mut ExecutionSimulator,
-        bar: &Bar,
-        hedge_bar: Option<&Bar>,
-        bar_index: usize,
-        volatility: Option<f64>,
-    ) {
-        let pending_orders = execution_sim.get_executable_orders(bar_index);
+    #[test]
+    fn test_failed_reopen_leaves_the_daily_limit() {
+        // The dip on bar 20 is bought at the open of bar 21, which has no
+        // volume to fill against; the signal repeats within the same day
+        // and takes the day's one new position at the open of bar 22
+        let bars = scenario(40).oversold(20).volume(21, 0.0).build();
+        let mut params = pattern_params().with_trade_limits(None, Some(1));
+        params.risk.overnight_flat = true;
+        params.execution.simulation = common::RealisticExecutionConfig {
+            enabled: true,
+            slippage_min_pct: 0.0,
+            slippage_max_pct: 0.0,
+            spread_enabled: false,
+            volume_limit_enabled: true,
+            market_impact_enabled: false,
+            ..Default::default()
+        };
 
-        for order in pending_orders {
-            match order.side {
-                Side::Buy => {
-                    let exec_result = execution_sim.simulate_execution(bar, Side::Buy, order.quantity, volatility);
-                    if exec_result.executed && exec_result.fill_quantity >= 1.0 {
-                        let stop_loss_price = if self.params.stop_loss_pct > 0.0 {
-                            Some(exec_result.fill_price * (1.0 - self.params.stop_loss_pct))
-                        } else {
-                            None
-                        };
-                        let _ = portfolio.open_position(
-                            &order.symbol,
-                            exec_result.fill_quantity,
-                            exec_result.fill_price,
-                            PositionSide::Long,
-                            bar.timestamp,
-                            stop_loss_price,
-                            self.params.commission,
-                        );
-                    }
-                }
-                Side::HedgeBuy => {
-                    if let Some(hbar) = hedge_bar {
-                        let exec_result = execution_sim.simulate_execution(hbar, Side::HedgeBuy, order.quantity, volatility);
-                        if exec_result.executed && exec_result.fill_quantity >= 1.0 {
-                            let stop_loss_price = if self.params.short_stop_loss_pct > 0.0 {
-                                Some(exec_result.fill_price * (1.0 - self.params.short_stop_loss_pct))
-                            } else {
-                                None
-                            };
-                            let _ = portfolio.open_position(
-                                &order.symbol,
-                                exec_result.fill_quantity,
-                                exec_result.fill_price,
-                                PositionSide::Hedge,
-                                hbar.timestamp,
-                                stop_loss_price,
-                                self.params.commission,
-                            );
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
+        let result = BacktestEngine::new(params).run(&bars, None);
+        assert_eq!(result.trades[0].entry_date, bars[22].timestamp);
+        assert_eq!(result.suppressed_signals.len(), 1);
+        assert_eq!(result.suppressed_signals[0].timestamp, bars[21].timestamp);
     }
 
-    /// Calculate rolling volatility for each bar
-    fn calculate_volatilities(&self, closes: &[f64], period: usize) -> Vec<f64> {
-        let mut volatilities = vec![0.0; closes.len()];
+    #[test]
+    fn test_date_ranges() {
+        // 4 bars a day from Jan 1: dips on Jan 6, Jan 11 (in the gap), Jan 18
+        // and late on Jan 25, the last day of the second range
+        let bars = pattern_bars(120, &[22, 40, 70, 98]);
+        let date = |d| chrono::NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        let params =
+            pattern_params().with_date_ranges(vec![(date(1), date(6)), (date(15), date(25))]);
+        let result = BacktestEngine::new(params.clone()).run(&bars, None);
 
-        if closes.len() < period + 1 {
-            return volatilities;
-        }
+        let gap = date(7)..=date(14);
+        assert!(!result.trades.is_empty());
+        assert!(result.trades.iter().all(|t| {
+            !gap.contains(&t.entry_date.date_naive())
+                && !gap.contains(&t.exit_date.unwrap().date_naive())
+        }));
+        // Each range closes its open position exactly once
+        let range_ends: Vec<_> = result
+            .trades
+            .iter()
+            .filter(|t| t.exit_reason == RANGE_END)
+            .map(|t| t.exit_date.unwrap().date_naive())
+            .collect();
+        assert_eq!(range_ends, vec![date(6), date(25)]);
 
-        // Calculate returns
-        let returns: Vec<f64> = closes
-            .windows(2)
-            .map(|w| (w[1] / w[0]).ln())
+        // The gap is flat in the curve and left out of the metrics
+        let gap_equity: Vec<f64> = result
+            .equity_curve
+            .iter()
+            .filter(|(ts, _)| gap.contains(&ts.date_naive()))
+            .map(|(_, e)| *e)
             .collect();
+        assert_eq!(gap_equity.len(), 32);
+        assert!(gap_equity.windows(2).all(|w| w[0] == w[1]));
 
-        // Calculate rolling standard deviation of returns
-        for i in period..returns.len() {
-            let window = &returns[i - period..i];
-            let mean: f64 = window.iter().sum::<f64>() / period as f64;
-            let variance: f64 = window.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / period as f64;
-            volatilities[i + 1] = variance.sqrt() * (252_f64).sqrt(); // Annualized
-        }
+        let omitted = BacktestEngine::new(BacktestParameters {
+            omit_range_gaps: true,
+            ..params
+        })
+        .run(&bars, None);
+        // Minus the gap and Jan 26-30 after the second range
+        assert_eq!(
+            omitted.equity_curve.len(),
+            result.equity_curve.len() - 32 - 20
+        );
+        assert_eq!(omitted.metrics.cagr, result.metrics.cagr);
+        assert_eq!(omitted.final_equity, result.final_equity);
+    }
+
+    #[test]
+    fn test_exit_threshold_decays_while_held() {
+        let bars = generate_bars_with_rsi_pattern(80, 100.0, &[40], &[]);
+        let exits = |params: BacktestParameters| -> Vec<(usize, String)> {
+            let result = BacktestEngine::new(params).run(&bars, None);
+            result
+                .trades
+                .iter()
+                .map(|t| {
+                    let exit = t.exit_date.unwrap();
+                    let day = bars.iter().position(|b| b.timestamp == exit).unwrap();
+                    (day, t.exit_reason.clone())
+                })
+                .collect()
+        };
+        // Bought on day 40, the RSI recovers past 75 on day 46
+        assert_eq!(
+            exits(pattern_params()),
+            vec![(46, "RSI(80.5) >= 75 - take profit".into())]
+        );
 
-        volatilities
+        // and past the decayed 65 the day before
+        let mut params = pattern_params();
+        params.strategy.exit_threshold_decay = Some(ExitDecay {
+            per_bar_decay: 2.0,
+            floor: 60.0,
+        });
+        let decayed = "RSI(67.2) >= 65 - take profit (decayed from 75 after 5 bars)";
+        assert_eq!(exits(params), vec![(45, decayed.into())]);
     }
 
-    /// Create empty result for insufficient data
-    fn empty_result(&self, bars: &[Bar]) -> BacktestResult {
-        BacktestResult {
-            metrics: Default::default(),
-            equity_curve: vec![],
-            drawdown_curve: vec![],
-            trades: vec![],
-            start_date: bars
-                .first()
-                .map(|b| b.timestamp.date_naive())
-                .unwrap_or_else(|| chrono::Utc::now().date_naive()),
-            end_date: bars
-                .last()
-                .map(|b| b.timestamp.date_naive())
-                .unwrap_or_else(|| chrono::Utc::now().date_naive()),
-            initial_capital: self.params.initial_capital,
-            final_equity: self.params.initial_capital,
-            execution_time_ms: 0,
+    #[test]
+    fn test_rolling_vwap_filters_bars_without_vwap() {
+        // A dip after a ten-day rally still closes above its 20-bar VWAP
+        // (42); one after a drift does not (70)
+        let rally: Vec<usize> = (30..40).collect();
+        let mut bars = generate_bars_with_rsi_pattern(90, 100.0, &[42, 70], &rally);
+        for bar in &mut bars {
+            bar.vwap = None;
         }
+        let entry_days = |params: BacktestParameters| -> Vec<usize> {
+            let result = BacktestEngine::new(params).run(&bars, None);
+            result
+                .trades
+                .iter()
+                .map(|t| {
+                    bars.iter()
+                        .position(|b| b.timestamp == t.entry_date)
+                        .unwrap()
+                })
+                .collect()
+        };
+
+        assert_eq!(entry_days(pattern_params()), vec![42, 70]);
+        let mut params = pattern_params();
+        params.strategy.vwap_mode = VwapMode::Below;
+        assert_eq!(entry_days(params.clone()), vec![70]);
+
+        // A 60-bar VWAP lets day 42 through while warming up, and by day
+        // 70 the bars before the rally keep it under the close
+        params.strategy.vwap_window = 60;
+        assert_eq!(entry_days(params), vec![42]);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::TimeZone;
+    #[test]
+    fn test_vwap_and_trend_filters() {
+        // Dips with the close above VWAP (25), below it (35), under a falling
+        // SMA(10) (45) and over a rising one (55)
+        let bars = scenario(64)
+            .oversold(25)
+            .vwap(25, Relation::Above)
+            .oversold(35)
+            .vwap(35, Relation::Below)
+            .oversold(45)
+            .sma(45, Relation::Below, 10)
+            .oversold(55)
+            .sma(55, Relation::Above, 10)
+            .build();
+        let entries = |params| -> Vec<DateTime<Utc>> {
+            let result = BacktestEngine::new(params).run(&bars, None);
+            result.trades.iter().map(|t| t.entry_date).collect()
+        };
 
-    fn generate_test_bars(n: usize, base_price: f64) -> Vec<Bar> {
-        use chrono::Duration;
-        let start = Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, 0).unwrap();
-        (0..n)
-            .map(|i| {
-                let price = base_price + (i as f64 * 0.1).sin() * 5.0;
-                Bar {
-                    timestamp: start + Duration::days(i as i64),
-                    open: price - 0.1,
-                    high: price + 0.5,
-                    low: price - 0.5,
-                    close: price,
-                    volume: 1000000,
-                    vwap: Some(price + 0.2),
-                }
-            })
-            .collect()
+        // The falling SMA run-up triggers dips of its own, so only the VWAP
+        // pinned days are checked here
+        let vwap_only = BacktestParameters::default()
+            .without_short()
+            .with_sma_period(1);
+        let taken = entries(vwap_only);
+        assert!(!taken.contains(&bars[25].timestamp));
+        assert!(taken.contains(&bars[35].timestamp));
+
+        // Drift dips close under SMA(10) as well
+        let trend_only = pattern_params().with_sma_period(10);
+        assert_eq!(entries(trend_only), vec![bars[55].timestamp]);
+    }
+
+    #[test]
+    fn test_bracket_orders() {
+        // Entry limit at the dip close fills on the next bar, whose low
+        // trades back down to it
+        let bars = pattern_bars(40, &[22]);
+        let entry = bars[22].close;
+
+        let result =
+            BacktestEngine::new(pattern_params().with_bracket_orders(0.01)).run(&bars, None);
+        let trade = &result.trades[0];
+        assert_eq!(trade.entry_date, bars[23].timestamp);
+        assert!((trade.entry_price - entry).abs() < 1e-9);
+        assert_eq!(trade.exit_reason, "take profit");
+        assert!((trade.exit_price.unwrap() - entry * 1.01).abs() < 1e-9);
+
+        // A second dip the bar after the fill hits the stop instead
+        let bars = scenario(40).oversold(22).oversold(24).build();
+        let mut params = pattern_params().with_bracket_orders(0.05);
+        params.risk.stop_loss = StopSpec::Percent(0.03);
+        let result = BacktestEngine::new(params).run(&bars, None);
+        let trade = &result.trades[0];
+        assert_eq!(trade.exit_reason, "stop loss");
+        assert_eq!(trade.exit_date, Some(bars[24].timestamp));
+        assert!((trade.exit_price.unwrap() - bars[22].close * 0.97).abs() < 1e-9);
     }
 
     #[test]
+    #[allow(unused_comparisons, clippy::absurd_extreme_comparisons)]
     fn test_backtest_runs() {
         let params = BacktestParameters::default();
         let engine = BacktestEngine::new(params);
@@ -494,4 +4118,812 @@ mod tests {
         // Should complete in under 100ms for 1000 bars
         assert!(result.execution_time_ms < 100);
     }
+
+    #[test]
+    fn test_deposit_does_not_inflate_returns() {
+        let bars = ScenarioBuilder::new(60, 100.0).build();
+        let params =
+            pattern_params().with_cash_flows(vec![(bars[40].timestamp.date_naive(), 5000.0)]);
+        let initial = params.initial_capital;
+        let result = BacktestEngine::new(params).run(&bars, None);
+
+        assert!(result.trades.is_empty());
+        assert_eq!(result.final_equity, initial + 5000.0);
+        let point = result
+            .equity_curve
+            .iter()
+            .position(|(t, _)| *t == bars[40].timestamp);
+        assert_eq!(result.equity_curve[point.unwrap()].1, initial + 5000.0);
+        // A naive return on the final equity would be +50%
+        assert!(result.metrics.twr_pct.abs() < 1e-9);
+        assert!(result.metrics.total_return_pct.abs() < 1e-9);
+        assert!(result.metrics.total_return.abs() < 1e-9);
+        assert!(result.metrics.mwr_pct.abs() < 1e-6);
+        assert!(result.metrics.max_drawdown.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_withdrawal_sells_down_position() {
+        let bars = ScenarioBuilder::new(40, 100.0).oversold(22).build();
+        let held = BacktestEngine::new(pattern_params()).run(&bars, None);
+        let cash = held.initial_capital - held.trades[0].entry_price * held.trades[0].quantity;
+        let withdrawal = cash + 1000.0;
+
+        let params =
+            pattern_params().with_cash_flows(vec![(bars[25].timestamp.date_naive(), -withdrawal)]);
+        let result = BacktestEngine::new(params).run(&bars, None);
+
+        let sold = &result.trades[0];
+        assert_eq!(sold.exit_reason, WITHDRAWAL);
+        assert_eq!(sold.exit_date, Some(bars[25].timestamp));
+        assert_eq!(sold.quantity, (1000.0 / bars[25].close).ceil());
+        // The rest is held to the take profit
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(
+            sold.quantity + result.trades[1].quantity,
+            held.trades[0].quantity
+        );
+
+        let equity_at = |r: &BacktestResult, i: usize| {
+            r.equity_curve
+                .iter()
+                .find(|(t, _)| *t == bars[i].timestamp)
+                .unwrap()
+                .1
+        };
+        assert!((equity_at(&held, 25) - equity_at(&result, 25) - withdrawal).abs() < 1e-6);
+        // The withdrawal is not a loss
+        let gain = result.final_equity + withdrawal - result.initial_capital;
+        assert!((result.metrics.total_return - gain).abs() < 1e-6);
+        assert!(result.metrics.twr_pct > 0.0);
+    }
+
+    #[test]
+    fn test_seeded_position_exits_on_signal() {
+        let bars = ScenarioBuilder::new(40, 100.0).overbought(25).build();
+        let entry_date = bars[0].timestamp - chrono::Duration::days(30);
+        let seeded = Position {
+            symbol: "TQQQ".to_string(),
+            quantity: 50.0,
+            avg_entry_price: 80.0,
+            entry_date,
+            current_price: 80.0,
+            side: PositionSide::Long,
+            stop_loss_price: Some(60.0),
+            initial_stop_price: None,
+            stop_spec: None,
+            entry_reason: "carried over".to_string(),
+            entry_rsi: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            entry_commission: 0.0,
+        };
+        let params = pattern_params().with_initial_positions(vec![seeded.clone()]);
+        params.validate().unwrap();
+        let result = BacktestEngine::new(params).run(&bars, None);
+
+        assert_eq!(result.trades.len(), 1);
+        let trade = &result.trades[0];
+        assert_eq!(trade.side, Side::Sell);
+        assert!(
+            trade.exit_reason.contains("take profit"),
+            "{}",
+            trade.exit_reason
+        );
+        assert_eq!(trade.entry_price, 80.0);
+        assert_eq!(trade.entry_date, entry_date);
+        assert_eq!(trade.quantity, 50.0);
+        let exit_date = trade.exit_date.unwrap();
+        assert!(exit_date > bars[0].timestamp);
+        assert_eq!(trade.holding_days, (exit_date - entry_date).num_days());
+        assert!((trade.pnl - 50.0 * (trade.exit_price.unwrap() - 80.0)).abs() < 1e-9);
+        assert!((result.final_equity - result.initial_capital - trade.pnl).abs() < 1e-6);
+
+        let invalid = |position: Position| {
+            pattern_params()
+                .with_initial_positions(vec![position])
+                .validate()
+                .is_err()
+        };
+        assert!(invalid(Position {
+            symbol: "SPY".to_string(),
+            ..seeded.clone()
+        }));
+        assert!(invalid(Position {
+            avg_entry_price: 0.0,
+            ..seeded
+        }));
+    }
+
+    #[test]
+    fn test_result_manifest() {
+        let bars = ScenarioBuilder::new(40, 100.0).oversold(22).build();
+        let mut params = pattern_params().with_capital(25_000.0);
+        params.execution.simulation = common::RealisticExecutionConfig::realistic();
+        let result = BacktestEngine::new(params.clone())
+            .with_execution_seed(7)
+            .with_data_source("scenario")
+            .run(&bars, None);
+
+        let manifest = &result.manifest;
+        assert_eq!(manifest.crate_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(manifest.execution_seed, Some(7));
+        assert_eq!(manifest.data.description.as_deref(), Some("scenario"));
+        assert_eq!(manifest.data.bars, bars.len());
+        assert_eq!(manifest.data.last_timestamp, Some(bars[39].timestamp));
+
+        // Parameters survive the result's JSON round trip unchanged
+        let json = serde_json::to_string(&result).unwrap();
+        let restored: BacktestResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            serde_json::to_value(&restored.manifest.parameters).unwrap(),
+            serde_json::to_value(&params).unwrap()
+        );
+        assert!(restored.manifest.differences(manifest).is_empty());
+
+        let mut moved = bars.clone();
+        moved[30].close *= 1.001;
+        let rerun = BacktestEngine::new(params).run(&moved, None);
+        assert_ne!(rerun.manifest.data.content_hash, manifest.data.content_hash);
+    }
+
+    #[test]
+    fn test_min_bars_between_entries() {
+        // Back-to-back oversold days, each long held for one day
+        let mut scenario = ScenarioBuilder::new(45, 100.0);
+        for day in 22..40 {
+            scenario = scenario.oversold(day);
+        }
+        let bars = scenario.build();
+        let pattern_params = || pattern_params().with_max_holding_days(1);
+        let entry_bars = |result: &BacktestResult| -> Vec<usize> {
+            result
+                .trades
+                .iter()
+                .map(|t| {
+                    bars.iter()
+                        .position(|b| b.timestamp == t.entry_date)
+                        .unwrap()
+                })
+                .collect()
+        };
+
+        let free = BacktestEngine::new(pattern_params()).run(&bars, None);
+        let spaced = entry_bars(&free);
+        assert!(spaced.windows(2).any(|w| w[1] - w[0] < 3), "{:?}", spaced);
+
+        let mut params = pattern_params();
+        params.risk.min_bars_between_entries = 3;
+        let throttled = BacktestEngine::new(params.clone()).run(&bars, None);
+        let entries = entry_bars(&throttled);
+        assert!(
+            entries.len() > 1 && entries.len() < spaced.len(),
+            "{:?}",
+            entries
+        );
+        assert!(
+            entries.windows(2).all(|w| w[1] - w[0] >= 3),
+            "{:?}",
+            entries
+        );
+        assert!(throttled
+            .suppressed_signals
+            .iter()
+            .any(|s| s.reason.contains("since the last TQQQ entry")));
+
+        // With latency the clock starts at the (later) fill
+        params.execution.simulation.latency_bars = 1;
+        let delayed = BacktestEngine::new(params).run(&bars, None);
+        let entries = entry_bars(&delayed);
+        assert!(entries.len() > 1, "{:?}", entries);
+        assert!(
+            entries.windows(2).all(|w| w[1] - w[0] >= 3),
+            "{:?}",
+            entries
+        );
+
+        let mut params = pattern_params();
+        params.risk.min_bars_between_entries = 0;
+        let unthrottled = BacktestEngine::new(params).run(&bars, None);
+        assert_eq!(
+            serde_json::to_value(&unthrottled.trades).unwrap(),
+            serde_json::to_value(&free.trades).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_headline_metrics_exclude_hedges() {
+        let dips: Vec<usize> = (0..10).map(|i| 22 + 16 * i).collect();
+        let bars = pattern_bars(22 + 16 * 9 + 8, &dips);
+        let unhedged = BacktestEngine::new(pattern_params()).run(&bars, None);
+
+        let mut params = pattern_params();
+        params.hedge.short_enabled = true;
+        params.hedge.rsi_oversold_short = 0.0;
+        let hedged = BacktestEngine::new(params.clone()).run(&bars, Some(&bars));
+        // Ten winning longs, each followed by a hedge that loses a little
+        let hedges: Vec<_> = hedged.trades.iter().filter(|t| t.is_hedge()).collect();
+        assert_eq!(hedged.trades.len(), 20);
+        assert_eq!(hedges.len(), 10);
+        assert!(hedges.iter().all(|t| t.pnl < 0.0));
+        assert!(unhedged.hedge_metrics.is_none() && unhedged.combined_metrics.is_none());
+
+        assert_eq!(hedged.metrics_scope, MetricsScope::Main);
+        assert_eq!(hedged.metrics.total_trades, 10);
+        assert_eq!(hedged.metrics.win_rate, unhedged.metrics.win_rate);
+        assert_eq!(hedged.metrics.win_rate, 100.0);
+        let hedge_metrics = hedged.hedge_metrics.as_ref().unwrap();
+        assert_eq!(hedge_metrics.total_trades, 10);
+        assert_eq!(hedge_metrics.win_rate, 0.0);
+        let combined = hedged.combined_metrics.as_ref().unwrap();
+        assert_eq!(combined.total_trades, 20);
+        assert_eq!(combined.win_rate, 50.0);
+
+        params.metrics_scope = MetricsScope::Combined;
+        let combined_scope = BacktestEngine::new(params).run(&bars, Some(&bars));
+        assert_eq!(combined_scope.metrics.win_rate, 50.0);
+        assert_eq!(combined_scope.hedge_metrics.unwrap().total_trades, 10);
+    }
+
+    #[test]
+    fn test_order_ids_link_signal_fill_and_trade() {
+        let bars = pattern_bars(40, &[22]);
+        let result = BacktestEngine::new(pattern_params()).run(&bars, None);
+        assert_eq!(result.trades.len(), 1);
+        let trade = &result.trades[0];
+        let (entry_id, exit_id) = (trade.entry_order_id.unwrap(), trade.exit_order_id.unwrap());
+        assert!(entry_id < exit_id);
+
+        let entry = result.trace(entry_id);
+        let signal = entry.signal.unwrap();
+        assert_eq!(signal.signal_type, SignalType::Buy);
+        assert_eq!(signal.order_id, Some(entry_id));
+        assert_eq!(entry.fills.len(), 1);
+        let fill = &entry.fills[0];
+        assert_eq!((fill.side, fill.price), (Side::Buy, trade.entry_price));
+        assert_eq!(fill.timestamp, trade.entry_date);
+        assert_eq!(entry.opened.len(), 1);
+        assert!(entry.closed.is_none());
+
+        let exit = result.trace(exit_id);
+        assert_eq!(exit.fills[0].price, trade.exit_price.unwrap());
+        assert_eq!(exit.closed.unwrap().entry_date, trade.entry_date);
+        assert!(exit.opened.is_empty());
+        assert_eq!(result.fills.len(), 2);
+        assert!(result.trace(exit_id + 1).fills.is_empty());
+    }
+
+    #[test]
+    fn test_delayed_order_keeps_its_signal_id() {
+        let bars = pattern_bars(40, &[22]);
+        let mut params = pattern_params();
+        params.execution.simulation = common::RealisticExecutionConfig::realistic();
+        params.execution.simulation.latency_bars = 1;
+        let result = BacktestEngine::new(params).run(&bars, None);
+
+        let trade = &result.trades[0];
+        let entry = result.trace(trade.entry_order_id.unwrap());
+        // Signalled on the dip, filled on the next bar
+        assert_eq!(entry.signal.unwrap().timestamp, bars[22].timestamp);
+        let fill = &entry.fills[0];
+        assert_eq!(fill.timestamp, bars[23].timestamp);
+        assert_eq!(fill.price, trade.entry_price);
+    }
+
+    #[test]
+    fn test_partial_entry_remainder_worked_to_full_size() {
+        let mut bars = pattern_bars(40, &[22]);
+        let mut params = pattern_params();
+        params.execution.simulation = common::RealisticExecutionConfig::realistic();
+        let full = BacktestEngine::new(params.clone()).run(&bars, None).trades[0].quantity;
+
+        // The entry bar trades only enough for about 60% of the order
+        let limit = params.execution.simulation.volume_participation_max_pct;
+        bars[22].volume = (full * 0.6 * bars[22].close / limit) as u64;
+        let partial = BacktestEngine::new(params.clone()).run(&bars, None);
+        assert!(partial.trades[0].quantity < full);
+
+        params.execution.simulation.partial_fill_policy =
+            PartialFillPolicy::WorkRemainder { max_bars: 2 };
+        let worked = BacktestEngine::new(params.clone()).run(&bars, None);
+        let trade = &worked.trades[0];
+        assert_eq!(trade.quantity, full);
+        assert_eq!(trade.entry_date, bars[22].timestamp);
+        let entry = worked.trace(trade.entry_order_id.unwrap());
+        assert_eq!(entry.fills.len(), 2);
+        assert_eq!(entry.fills[1].timestamp, bars[23].timestamp);
+        assert_eq!(entry.fills[0].quantity + entry.fills[1].quantity, full);
+        let cost: f64 = entry.fills.iter().map(|f| f.quantity * f.price).sum();
+        assert!((trade.entry_price - cost / full).abs() < 1e-9);
+
+        params.execution.simulation.partial_fill_policy = PartialFillPolicy::Cancel;
+        let cancelled = BacktestEngine::new(params).run(&bars, None);
+        assert!(cancelled
+            .trades
+            .iter()
+            .all(|t| t.entry_date != bars[22].timestamp));
+        assert!(cancelled
+            .suppressed_signals
+            .iter()
+            .any(|s| s.reason == SKIP_PARTIAL_CANCELLED));
+    }
+
+    #[test]
+    fn test_volatility_burst_quarantines_entries() {
+        let bars = scenario(60).return_pct(30, -15.0).oversold(32).build();
+        let params = pattern_params();
+        let free = BacktestEngine::new(params.clone()).run(&bars, None);
+        assert_eq!(free.trades[0].entry_date, bars[30].timestamp);
+
+        let params = params.with_anomaly_quarantine(3);
+        let flags = indicators_for(&bars, &params).anomaly;
+        let flagged: Vec<usize> = (0..flags.len()).filter(|&i| flags[i]).collect();
+        assert_eq!(flagged, vec![30]);
+
+        let result = BacktestEngine::new(params).run(&bars, None);
+        // RSI is still oversold when the quarantine lifts
+        assert_eq!(result.trades[0].entry_date, bars[34].timestamp);
+        let quarantined: Vec<_> = result
+            .suppressed_signals
+            .iter()
+            .filter(|s| s.reason == SKIP_ANOMALY)
+            .map(|s| s.timestamp)
+            .collect();
+        let window: Vec<_> = bars[30..=33].iter().map(|b| b.timestamp).collect();
+        assert_eq!(quarantined, window);
+    }
+
+    #[test]
+    fn test_low_data_quality_stretch_blocks_entries() {
+        // 30 identical closes after the dip at 35, with the range collapsed
+        let mut bars = pattern_bars(90, &[20, 35]);
+        let stale = bars[35].close * 0.99;
+        for bar in &mut bars[36..66] {
+            (bar.open, bar.high, bar.low, bar.close) = (stale, stale, stale, stale);
+        }
+        let stretch = bars[36].timestamp..=bars[65].timestamp;
+        let mut params = pattern_params().with_max_holding_days(1);
+        params.risk.min_data_quality = 0.5;
+
+        let result = BacktestEngine::new(params.clone()).run(&bars, None);
+        let entries: Vec<_> = result.trades.iter().map(|t| t.entry_date).collect();
+        assert_eq!(entries[..2], [bars[20].timestamp, bars[35].timestamp]);
+        assert!(!entries.iter().any(|t| stretch.contains(t)));
+        // The oversold RSI keeps signalling through the stretch
+        let blocked: Vec<_> = result
+            .suppressed_signals
+            .iter()
+            .filter(|s| s.reason == SKIP_LOW_DATA_QUALITY)
+            .collect();
+        assert!(!blocked.is_empty());
+        assert!(blocked.iter().all(|s| stretch.contains(&s.timestamp)));
+
+        let warning = result
+            .warnings
+            .iter()
+            .find(|w| w.kind == common::DataWarningKind::LowDataQuality)
+            .unwrap();
+        assert_eq!(
+            warning.message,
+            format!(
+                "bars 36-65 ({} to {}) below data quality 0.50; entries suppressed",
+                bars[36].timestamp.date_naive(),
+                bars[65].timestamp.date_naive()
+            )
+        );
+
+        params.risk.close_on_low_data_quality = true;
+        let result = BacktestEngine::new(params).run(&bars, None);
+        assert_eq!(result.trades[1].exit_date, Some(bars[36].timestamp));
+        assert_eq!(result.trades[1].exit_reason, LOW_DATA_QUALITY);
+    }
+
+    #[test]
+    fn test_entry_confirmation_debounces_single_bar_dips() {
+        // A dip that recovers on the next bar, and one held for three bars
+        let bars = [40, 41, 42]
+            .iter()
+            .fold(scenario(70).oversold(20).return_pct(21, 5.0), |s, &day| {
+                s.oversold(day)
+            })
+            .build();
+        let params = pattern_params().with_max_holding_days(1);
+        let result = BacktestEngine::new(params.clone()).run(&bars, None);
+        let entries: Vec<_> = result.trades.iter().map(|t| t.entry_date).collect();
+        assert_eq!(entries[..2], [bars[20].timestamp, bars[40].timestamp]);
+
+        let mut params = params;
+        params.strategy.entry_confirmation_bars = 2;
+        let result = BacktestEngine::new(params).run(&bars, None);
+        let entries: Vec<_> = result.trades.iter().map(|t| t.entry_date).collect();
+        assert_eq!(entries, vec![bars[41].timestamp]);
+        let buy = result
+            .signals
+            .iter()
+            .find(|s| s.signal_type == SignalType::Buy)
+            .unwrap();
+        assert_eq!(buy.confirmation_bars, Some(2));
+    }
+
+    #[test]
+    fn test_signal_hysteresis_damps_thrashing() {
+        // Every 8 bars a dip opens the long and a spike three bars later
+        // takes profit and lifts RSI(2) past the short threshold for one
+        // bar: without hysteresis each cycle trades both sides
+        let mut builder = scenario(120);
+        for day in (22..110).step_by(8) {
+            builder = builder
+                .return_pct(day, -8.0)
+                .return_pct(day + 3, 10.0)
+                .return_pct(day + 5, -2.0);
+        }
+        let oscillating = builder.build();
+        let trending = scenario(120).build();
+        let run = |bars: &[Bar], hysteresis_bars: usize| {
+            let mut params = pattern_params();
+            params.hedge.short_enabled = true;
+            params.hedge.signal_hysteresis_bars = hysteresis_bars;
+            params.hedge.hysteresis_rsi_delta = 15.0;
+            BacktestEngine::new(params).run(bars, Some(bars))
+        };
+
+        let free = run(&oscillating, 0);
+        let damped = run(&oscillating, 3);
+        assert!(free.suppressed_signals.is_empty());
+        assert!(damped.trades.len() * 3 < free.trades.len() * 2);
+        let suppressed_hedges = damped
+            .suppressed_signals
+            .iter()
+            .filter(|s| s.reason == SKIP_HYSTERESIS && s.signal_type == SignalType::HedgeBuy)
+            .count();
+        assert!(suppressed_hedges >= 10);
+
+        assert_eq!(
+            run(&trending, 0).trades.len(),
+            run(&trending, 3).trades.len()
+        );
+    }
+
+    #[test]
+    fn test_synthetic_short_without_hedge_bars() {
+        // The drift opens the hedge at warmup; the 10% drop on bar 30 is
+        // oversold, so the long entry there closes it
+        let bars = scenario(40).return_pct(30, -10.0).build();
+        let mut params = pattern_params();
+        params.hedge.short_enabled = true;
+        params.hedge.rsi_oversold_short = 0.0;
+        params.hedge.synthetic_short = true;
+        let result = BacktestEngine::new(params).run(&bars, None);
+
+        let short = result
+            .trades
+            .iter()
+            .find(|t| t.side == Side::SyntheticCover)
+            .unwrap();
+        assert_eq!(short.exit_date, Some(bars[30].timestamp));
+        assert_eq!(short.exit_reason, HEDGE_CLOSED_FOR_LONG);
+        assert!(short.pnl > 0.0);
+        assert!(result.fills.iter().any(|f| f.side == Side::SyntheticShort));
+
+        // Short 30% of equity at 1x through a 10% decline
+        let equity = |i: usize| {
+            let at = result
+                .equity_curve
+                .iter()
+                .find(|(t, _)| *t == bars[i].timestamp);
+            at.unwrap().1
+        };
+        let gain = equity(30) / equity(29) - 1.0;
+        assert!((gain - 0.03).abs() < 0.002, "gain {}", gain);
+    }
+
+    /// Alternating ±0.2% days keep RSI(2) between the entry and exit
+    /// levels; the `pinned` days are left for the caller to shape
+    fn choppy(days: usize, pinned: &[usize]) -> ScenarioBuilder {
+        (0..days)
+            .filter(|day| !pinned.contains(day))
+            .fold(scenario(days), |s, day| {
+                s.return_pct(day, if day % 2 == 0 { 0.2 } else { -0.2 })
+            })
+    }
+
+    #[test]
+    fn test_inverse_only_mirrors_the_signal() {
+        let bars = choppy(50, &[25, 32]).overbought(25).oversold(32).build();
+        let params = pattern_params().with_direction_mode(DirectionMode::LongInverseOnly);
+        let result = BacktestEngine::new(params).run(&bars, Some(&bars));
+
+        assert_eq!(result.trades.len(), 1);
+        assert!(!result.trades.iter().any(|t| t.side == Side::Sell));
+        let inverse = &result.trades[0];
+        assert_eq!(inverse.side, Side::HedgeSell);
+        assert_eq!(inverse.entry_date, bars[25].timestamp);
+        assert_eq!(inverse.exit_date, Some(bars[32].timestamp));
+        // The inverse trades are the strategy, not a hedge of it
+        assert_eq!(result.metrics_scope, MetricsScope::Combined);
+        assert_eq!(result.metrics.total_trades, 1);
+    }
+
+    #[test]
+    fn test_auto_flip_at_regime_boundary() {
+        // A dip in an uptrend of +0.6% / -0.2% days, then a 10% crash that
+        // takes the close below SMA(20) for good, with a spike and a dip
+        // after it
+        let bars = (0..40)
+            .step_by(2)
+            .fold(choppy(70, &[25, 40, 48, 55]), |s, day| {
+                s.return_pct(day, 0.6)
+            })
+            .return_pct(25, -1.0)
+            .return_pct(40, -10.0)
+            .overbought(48)
+            .oversold(55)
+            .build();
+        let run = |auto_flip: bool| {
+            let mut params = pattern_params().with_sma_period(20);
+            params.strategy.sma_filter_enabled = false;
+            params.hedge.auto_flip_on_regime = auto_flip;
+            BacktestEngine::new(params).run(&bars, Some(&bars))
+        };
+        let entries = |result: &BacktestResult, side: Side| -> Vec<_> {
+            let bar = |t: &Trade| bars.iter().position(|b| b.timestamp == t.entry_date);
+            result
+                .trades
+                .iter()
+                .filter(|t| t.side == side)
+                .filter_map(bar)
+                .collect()
+        };
+
+        // Without the flip the crash and the late dip are long entries
+        let fixed = run(false);
+        assert_eq!(entries(&fixed, Side::Sell), vec![25, 40, 55]);
+        assert!(entries(&fixed, Side::HedgeSell).is_empty());
+
+        // With it the long side stops on the crash bar, where the inverse
+        // side takes over
+        let flipped = run(true);
+        assert_eq!(entries(&flipped, Side::Sell), vec![25]);
+        assert_eq!(entries(&flipped, Side::HedgeSell), vec![48]);
+        let inverse = flipped
+            .trades
+            .iter()
+            .find(|t| t.side == Side::HedgeSell)
+            .unwrap();
+        assert_eq!(inverse.exit_date, Some(bars[55].timestamp));
+    }
+
+    #[test]
+    fn test_stop_spec_resolved_at_entry() {
+        let bars = pattern_bars(40, &[22]);
+        let run = |stop: StopSpec| {
+            let params = pattern_params().with_stop_loss(stop);
+            let atr = indicators_for(&bars, &params).atr[22];
+            let result = BacktestEngine::new(params).run(&bars, None);
+            (result.trades[0].clone(), atr)
+        };
+        let entry = bars[22].close;
+
+        let (trade, _) = run(StopSpec::Percent(0.05));
+        assert_eq!(trade.entry_price, entry);
+        assert_eq!(trade.initial_stop_price, Some(entry * 0.95));
+        assert_eq!(trade.stop_spec, Some(StopSpec::Percent(0.05)));
+
+        let (trade, atr) = run(StopSpec::AtrMultiple(2.0));
+        assert!(atr > 0.0);
+        assert!((trade.initial_stop_price.unwrap() - (entry - 2.0 * atr)).abs() < 1e-9);
+        assert_eq!(trade.stop_spec, Some(StopSpec::AtrMultiple(2.0)));
+        assert!((trade.initial_risk.unwrap() - 2.0 * atr * trade.quantity).abs() < 1e-6);
+
+        let (trade, _) = run(StopSpec::Dollars(1.5));
+        assert!((trade.initial_stop_price.unwrap() - (entry - 1.5)).abs() < 1e-9);
+
+        let (trade, _) = run(StopSpec::None);
+        assert_eq!(trade.initial_stop_price, None);
+        assert_eq!(trade.stop_spec, None);
+    }
+
+    struct HalveEvery;
+
+    impl crate::pretrade::PreTradeCheck for HalveEvery {
+        fn review(
+            &self,
+            order: &crate::pretrade::ProposedOrder,
+            _: &crate::pretrade::PortfolioSnapshot,
+        ) -> crate::pretrade::PreTradeDecision {
+            crate::pretrade::PreTradeDecision::ModifyQuantity(order.quantity / 2.0)
+        }
+    }
+
+    struct RejectEvery;
+
+    impl crate::pretrade::PreTradeCheck for RejectEvery {
+        fn review(
+            &self,
+            _: &crate::pretrade::ProposedOrder,
+            _: &crate::pretrade::PortfolioSnapshot,
+        ) -> crate::pretrade::PreTradeDecision {
+            crate::pretrade::PreTradeDecision::Reject("desk closed".to_string())
+        }
+
+        fn name(&self) -> &str {
+            "desk"
+        }
+    }
+
+    #[test]
+    fn test_pre_trade_checks_shrink_and_reject() {
+        let bars = pattern_bars(64, &[22, 38, 54]);
+        let full = BacktestEngine::new(pattern_params()).run(&bars, None);
+        assert_eq!(full.trades.len(), 3);
+        assert!(full.pre_trade_events.is_empty());
+
+        let halved = BacktestEngine::new(pattern_params())
+            .with_pre_trade_check(HalveEvery)
+            .run(&bars, None);
+        assert_eq!(halved.trades.len(), 3);
+        assert_eq!(
+            halved.trades[0].quantity,
+            (full.trades[0].quantity / 2.0).floor()
+        );
+        assert_eq!(halved.pre_trade_events.len(), 3);
+        for (trade, event) in halved.trades.iter().zip(&halved.pre_trade_events) {
+            assert_eq!(
+                event.approved_quantity,
+                (event.requested_quantity / 2.0).floor()
+            );
+            assert_eq!(trade.quantity, event.approved_quantity);
+            assert_eq!(event.timestamp, trade.entry_date);
+        }
+
+        let rejected = BacktestEngine::new(pattern_params())
+            .with_pre_trade_check(RejectEvery)
+            .run(&bars, None);
+        assert!(rejected.trades.is_empty());
+        // Every bar of each oversold stretch is offered and turned down
+        let events = &rejected.pre_trade_events;
+        assert!(events.len() >= 3);
+        assert!(events
+            .iter()
+            .all(|e| e.check == "desk" && e.reason.as_deref() == Some("desk closed")));
+        assert_eq!(rejected.suppressed_signals.len(), events.len());
+        assert!(rejected
+            .suppressed_signals
+            .iter()
+            .all(|s| s.reason == "pre-trade check desk: desk closed"));
+
+        // The built-in notional cap, from the parameters
+        let mut params = pattern_params();
+        params.risk.max_order_notional = Some(1_000.0);
+        let capped = BacktestEngine::new(params).run(&bars, None);
+        assert!(capped
+            .trades
+            .iter()
+            .all(|t| t.quantity * t.entry_price <= 1_000.0));
+        assert_eq!(capped.pre_trade_events[0].check, "max_notional");
+    }
+
+    #[test]
+    fn test_cached_indicators_match_fresh_run() {
+        let bars = crate::data::generate_synthetic_bars_seeded(400, 50.0, 5);
+        let params = BacktestParameters::default().without_vwap_filter();
+        let dir = std::env::temp_dir().join(format!("engine_cache_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let json = |result: &BacktestResult| {
+            serde_json::to_value((&result.trades, &result.equity_curve)).unwrap()
+        };
+
+        let fresh = BacktestEngine::new(params.clone()).run(&bars, None);
+        let cached = || BacktestEngine::new(params.clone()).with_indicator_cache(&dir);
+        let first = cached().run(&bars, None);
+        let second = cached().run(&bars, None);
+        assert!(!fresh.trades.is_empty());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        assert_eq!(json(&first), json(&fresh));
+        assert_eq!(json(&second), json(&fresh));
+        assert!(second.warnings.is_empty());
+
+        // A corrupt cache is recomputed, with a warning
+        let file = std::fs::read_dir(&dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        std::fs::write(&file, "not json").unwrap();
+        let recomputed = cached().run(&bars, None);
+        assert_eq!(json(&recomputed), json(&fresh));
+        assert!(recomputed
+            .warnings
+            .iter()
+            .any(|w| w.kind == DataWarningKind::IndicatorCacheUnavailable));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_spilled_run_matches_in_memory() {
+        let bars = crate::data::generate_synthetic_bars_seeded(600, 50.0, 11);
+        let mut params = BacktestParameters::default().without_vwap_filter();
+        params.risk.adaptive_sizing = Some(AdaptiveSizing {
+            lookback_trades: 3,
+            min_mult: 0.5,
+            max_mult: 1.5,
+            sensitivity: 1.0,
+        });
+        let dir = std::env::temp_dir().join(format!("engine_spill_{}", std::process::id()));
+        let seeded = |engine: BacktestEngine| engine.with_execution_seed(3);
+        let memory = seeded(BacktestEngine::new(params.clone())).run(&bars, Some(&bars));
+        let _ = std::fs::remove_dir_all(&dir);
+        let spiller =
+            |overwrite| seeded(BacktestEngine::new(params.clone()).with_spill_dir(&dir, overwrite));
+        let spilled = spiller(Overwrite::Refuse).run(&bars, Some(&bars));
+        assert!(memory.trades.iter().any(Trade::is_hedge));
+        assert!(memory
+            .trades
+            .iter()
+            .any(|t| t.size_multiplier.is_some_and(|m| m != 1.0)));
+
+        assert!(spilled.trades.is_empty() && spilled.equity_curve.is_empty());
+        assert!(spilled.signals.is_empty() && spilled.fills.is_empty());
+        assert_eq!(spilled.final_equity, memory.final_equity);
+        let summary = spilled.spill.as_ref().unwrap();
+        assert_eq!(summary.trades, memory.trades.len() as u64);
+        assert_eq!(summary.equity_points, memory.equity_curve.len() as u64);
+        assert_eq!(summary.signals, memory.signals.len() as u64);
+        assert_eq!(summary.fills, memory.fills.len() as u64);
+        let read: Vec<Trade> = crate::sink::read_trades(summary)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        let json = |trades: &[Trade]| serde_json::to_value(trades).unwrap();
+        assert_eq!(json(&read), json(&memory.trades));
+
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-9 * a.abs().max(1.0);
+        let pairs = [
+            (&spilled.metrics, &memory.metrics),
+            (
+                spilled.hedge_metrics.as_ref().unwrap(),
+                memory.hedge_metrics.as_ref().unwrap(),
+            ),
+            (
+                spilled.combined_metrics.as_ref().unwrap(),
+                memory.combined_metrics.as_ref().unwrap(),
+            ),
+        ];
+        for (streamed, held) in pairs {
+            assert_eq!(streamed.total_trades, held.total_trades);
+            assert_eq!(streamed.suppressed_entries, held.suppressed_entries);
+            for (a, b) in [
+                (streamed.total_return_pct, held.total_return_pct),
+                (streamed.sharpe_ratio, held.sharpe_ratio),
+                (streamed.volatility, held.volatility),
+                (streamed.max_drawdown, held.max_drawdown),
+                (streamed.win_rate, held.win_rate),
+                (streamed.profit_factor, held.profit_factor),
+                (streamed.avg_heat_pct, held.avg_heat_pct),
+                (streamed.avg_round_trip_cost, held.avg_round_trip_cost),
+                (
+                    streamed.worst_overnight_gap_in_trade_pct,
+                    held.worst_overnight_gap_in_trade_pct,
+                ),
+            ] {
+                assert!(close(a, b), "{} vs {}", a, b);
+            }
+        }
+
+        let refused = spiller(Overwrite::Refuse).run(&bars, Some(&bars));
+        assert!(refused.spill.is_none());
+        assert_eq!(refused.trades.len(), memory.trades.len());
+        assert!(refused
+            .warnings
+            .iter()
+            .any(|w| w.message.contains("already exists")));
+        let kept = crate::sink::read_trades(summary).unwrap().count();
+        assert_eq!(kept, memory.trades.len());
+        assert!(spiller(Overwrite::Force)
+            .run(&bars, Some(&bars))
+            .spill
+            .is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -8,8 +8,9 @@
 //! - Market impact for large orders
 //! - Random order rejection
 
-use common::{Bar, RealisticExecutionConfig, Side};
-use rand::Rng;
+use common::{Bar, PartialFillPolicy, RealisticExecutionConfig, Side};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 /// Result of an execution attempt
 #[derive(Debug, Clone)]
@@ -26,6 +27,16 @@ pub struct ExecutionResult {
     pub price_adjustments: PriceAdjustments,
     /// Reason if order was rejected or partially filled
     pub notes: Vec<String>,
+    /// Engine order this execution belongs to
+    pub order_id: Option<u64>,
+}
+
+impl ExecutionResult {
+    /// Stamp the engine order this execution belongs to
+    pub fn for_order(mut self, order_id: u64) -> Self {
+        self.order_id = Some(order_id);
+        self
+    }
 }
 
 /// Breakdown of price adjustments applied
@@ -41,18 +52,25 @@ pub struct PriceAdjustments {
 /// Pending order waiting for execution (for latency simulation)
 #[derive(Debug, Clone)]
 pub struct PendingOrder {
+    pub order_id: u64,
     pub symbol: String,
     pub side: Side,
     pub quantity: f64,
     pub signal_bar_index: usize,
     pub execute_at_bar_index: usize,
+    /// Last bar the unfilled remainder of a partial entry may fill on;
+    /// None for an order that has not filled at all yet
+    pub work_until_bar_index: Option<usize>,
 }
 
 /// Execution simulator with realistic market conditions
 pub struct ExecutionSimulator {
     config: RealisticExecutionConfig,
     pending_orders: Vec<PendingOrder>,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
+    /// Adverse slippage (fraction) on every fill while the simulation is
+    /// disabled; the simulation's own model replaces it when enabled
+    flat_slippage_pct: f64,
 }
 
 impl ExecutionSimulator {
@@ -60,10 +78,28 @@ impl ExecutionSimulator {
         Self {
             config,
             pending_orders: Vec::new(),
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
+            flat_slippage_pct: 0.0,
         }
     }
 
+    /// Simulator whose slippage and rejection draws repeat for `seed`
+    pub fn with_seed(config: RealisticExecutionConfig, seed: u64) -> Self {
+        Self {
+            config,
+            pending_orders: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+            flat_slippage_pct: 0.0,
+        }
+    }
+
+    /// Slip fills by `pct` against the order while the simulation is
+    /// disabled
+    pub fn with_flat_slippage(mut self, pct: f64) -> Self {
+        self.flat_slippage_pct = pct;
+        self
+    }
+
     /// Check if realistic execution is enabled
     pub fn is_enabled(&self) -> bool {
         self.config.enabled
@@ -77,24 +113,53 @@ impl ExecutionSimulator {
         quantity: f64,
         volatility: Option<f64>,
     ) -> ExecutionResult {
+        if bar.halted {
+            return ExecutionResult {
+                executed: false,
+                fill_price: 0.0,
+                fill_quantity: 0.0,
+                requested_quantity: quantity,
+                price_adjustments: Default::default(),
+                notes: vec!["Order rejected: trading halted".to_string()],
+                order_id: None,
+            };
+        }
+
         if !self.config.enabled {
-            // If disabled, return simple execution at close price
+            // If disabled, fill at the close less the flat slippage
+            let slippage = adverse(side, bar.close * self.flat_slippage_pct);
             return ExecutionResult {
                 executed: true,
-                fill_price: bar.close,
+                fill_price: bar.close + slippage,
                 fill_quantity: quantity,
                 requested_quantity: quantity,
                 price_adjustments: PriceAdjustments {
                     base_price: bar.close,
+                    slippage,
+                    total_adjustment: slippage,
                     ..Default::default()
                 },
                 notes: vec![],
+                order_id: None,
             };
         }
 
         let mut notes = Vec::new();
         let volatility = volatility.unwrap_or(0.02); // Default 2% volatility
 
+        // 0. Nothing trades on a bar without volume
+        if bar.volume == 0 {
+            return ExecutionResult {
+                executed: false,
+                fill_price: 0.0,
+                fill_quantity: 0.0,
+                requested_quantity: quantity,
+                price_adjustments: Default::default(),
+                notes: vec!["Order rejected: no volume on bar (halted)".to_string()],
+                order_id: None,
+            };
+        }
+
         // 1. Check for order rejection
         if self.should_reject_order(volatility) {
             return ExecutionResult {
@@ -104,6 +169,7 @@ impl ExecutionSimulator {
                 requested_quantity: quantity,
                 price_adjustments: Default::default(),
                 notes: vec!["Order rejected due to market conditions".to_string()],
+                order_id: None,
             };
         }
 
@@ -117,6 +183,7 @@ impl ExecutionSimulator {
                 requested_quantity: quantity,
                 price_adjustments: Default::default(),
                 notes,
+                order_id: None,
             };
         }
 
@@ -148,6 +215,7 @@ impl ExecutionSimulator {
                 total_adjustment,
             },
             notes,
+            order_id: None,
         }
     }
 
@@ -173,13 +241,7 @@ impl ExecutionSimulator {
             }
         };
 
-        let slippage = price * slippage_pct;
-
-        // For buys, adverse means higher price; for sells, adverse means lower price
-        match side {
-            Side::Buy | Side::HedgeBuy | Side::Cover => slippage,
-            Side::Sell | Side::HedgeSell | Side::Short => -slippage,
-        }
+        adverse(side, price * slippage_pct)
     }
 
     /// Calculate spread cost based on side and volatility
@@ -194,8 +256,8 @@ impl ExecutionSimulator {
 
         // Buys pay the ask (higher), sells receive bid (lower)
         match side {
-            Side::Buy | Side::HedgeBuy | Side::Cover => half_spread,
-            Side::Sell | Side::HedgeSell | Side::Short => -half_spread,
+            Side::Buy | Side::HedgeBuy | Side::Cover | Side::SyntheticCover => half_spread,
+            Side::Sell | Side::HedgeSell | Side::Short | Side::SyntheticShort => -half_spread,
         }
     }
 
@@ -213,7 +275,6 @@ impl ExecutionSimulator {
 
         // Calculate what percentage of daily volume this order represents
         let order_value = quantity * price;
-        let avg_trade_value = price * (bar_volume as f64 / 100.0); // Assume ~100 trades per bar
         let volume_participation = order_value / (bar_volume as f64 * price);
 
         // Impact increases quadratically with participation rate
@@ -222,19 +283,24 @@ impl ExecutionSimulator {
 
         // Buys push price up, sells push price down
         match side {
-            Side::Buy | Side::HedgeBuy | Side::Cover => impact,
-            Side::Sell | Side::HedgeSell | Side::Short => -impact,
+            Side::Buy | Side::HedgeBuy | Side::Cover | Side::SyntheticCover => impact,
+            Side::Sell | Side::HedgeSell | Side::Short | Side::SyntheticShort => -impact,
         }
     }
 
+    /// Largest order the volume participation limit allows for `volume`
+    /// at `price` (the same bound fills are truncated to)
+    pub fn volume_limit(&self, volume: f64, price: f64) -> f64 {
+        volume * self.config.volume_participation_max_pct / price
+    }
+
     /// Calculate actual fill quantity based on volume constraints
     fn calculate_fill_quantity(&self, bar: &Bar, quantity: f64, notes: &mut Vec<String>) -> f64 {
         if !self.config.volume_limit_enabled {
             return quantity;
         }
 
-        let max_quantity_by_volume =
-            (bar.volume as f64) * self.config.volume_participation_max_pct / bar.close;
+        let max_quantity_by_volume = self.volume_limit(bar.volume as f64, bar.close);
 
         if quantity <= max_quantity_by_volume {
             return quantity;
@@ -273,17 +339,45 @@ impl ExecutionSimulator {
         side: Side,
         quantity: f64,
         current_bar_index: usize,
+        order_id: u64,
     ) {
         let execute_at = current_bar_index + self.config.latency_bars;
         self.pending_orders.push(PendingOrder {
+            order_id,
             symbol,
             side,
             quantity,
             signal_bar_index: current_bar_index,
             execute_at_bar_index: execute_at,
+            work_until_bar_index: None,
         });
     }
 
+    /// Queue the unfilled `quantity` of a partial entry for the next bar,
+    /// unless it may not fill past `work_until`
+    pub fn queue_remainder(
+        &mut self,
+        order: &PendingOrder,
+        quantity: f64,
+        current_bar_index: usize,
+        work_until: usize,
+    ) {
+        if current_bar_index >= work_until {
+            return;
+        }
+        self.pending_orders.push(PendingOrder {
+            quantity,
+            execute_at_bar_index: current_bar_index + 1,
+            work_until_bar_index: Some(work_until),
+            ..order.clone()
+        });
+    }
+
+    /// What becomes of the unfilled part of a partial entry
+    pub fn partial_fill_policy(&self) -> PartialFillPolicy {
+        self.config.partial_fill_policy
+    }
+
     /// Get orders ready to execute at current bar
     pub fn get_executable_orders(&mut self, current_bar_index: usize) -> Vec<PendingOrder> {
         let (ready, pending): (Vec<_>, Vec<_>) = self
@@ -300,6 +394,11 @@ impl ExecutionSimulator {
         self.config.enabled && self.config.latency_bars > 0
     }
 
+    /// Check if stop-loss exits are queued like other orders
+    pub fn stops_delayed(&self) -> bool {
+        self.has_latency() && self.config.stops_subject_to_latency
+    }
+
     /// Get pending order count
     pub fn pending_order_count(&self) -> usize {
         self.pending_orders.len()
@@ -311,6 +410,15 @@ impl ExecutionSimulator {
     }
 }
 
+/// `amount` signed against the order: for buys, adverse means a higher
+/// price; for sells, a lower one
+fn adverse(side: Side, amount: f64) -> f64 {
+    match side {
+        Side::Buy | Side::HedgeBuy | Side::Cover | Side::SyntheticCover => amount,
+        Side::Sell | Side::HedgeSell | Side::Short | Side::SyntheticShort => -amount,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -325,6 +433,7 @@ mod tests {
             close,
             volume,
             vwap: Some(close + 0.1),
+            halted: false,
         }
     }
 
@@ -339,6 +448,29 @@ mod tests {
         assert!(result.executed);
         assert_eq!(result.fill_price, 100.0);
         assert_eq!(result.fill_quantity, 100.0);
+
+        // Flat slippage goes against the order on both sides
+        let mut sim =
+            ExecutionSimulator::new(RealisticExecutionConfig::default()).with_flat_slippage(0.001);
+        let buy = sim.simulate_execution(&bar, Side::Buy, 100.0, None);
+        let sell = sim.simulate_execution(&bar, Side::Sell, 100.0, None);
+        assert!((buy.fill_price - 100.1).abs() < 1e-9);
+        assert!((sell.fill_price - 99.9).abs() < 1e-9);
+        assert!((sell.price_adjustments.slippage + 0.1).abs() < 1e-9);
+
+        // The enabled simulation brings its own model instead
+        let mut sim = ExecutionSimulator::new(RealisticExecutionConfig {
+            enabled: true,
+            slippage_min_pct: 0.0,
+            slippage_max_pct: 0.0,
+            spread_enabled: false,
+            market_impact_enabled: false,
+            ..RealisticExecutionConfig::realistic()
+        })
+        .with_flat_slippage(0.001);
+        let sell = sim.simulate_execution(&bar, Side::Sell, 100.0, None);
+        assert_eq!(sell.price_adjustments.slippage, 0.0);
+        assert_eq!(sell.fill_price, sell.price_adjustments.base_price);
     }
 
     #[test]
@@ -372,6 +504,20 @@ mod tests {
         assert!(result.notes.iter().any(|n| n.contains("Partial fill")));
     }
 
+    #[test]
+    fn test_zero_volume_bar_rejects() {
+        let mut config = RealisticExecutionConfig::realistic();
+        config.volume_limit_enabled = false;
+        let mut sim = ExecutionSimulator::new(config);
+
+        let bar = sample_bar(100.0, 0);
+        let result = sim.simulate_execution(&bar, Side::Buy, 100.0, None);
+
+        assert!(!result.executed);
+        assert_eq!(result.fill_quantity, 0.0);
+        assert!(result.notes.iter().any(|n| n.contains("halted")));
+    }
+
     #[test]
     fn test_latency_queue() {
         let mut config = RealisticExecutionConfig::realistic();
@@ -380,7 +526,7 @@ mod tests {
         let mut sim = ExecutionSimulator::new(config);
 
         // Queue order at bar 0
-        sim.queue_order("TQQQ".to_string(), Side::Buy, 100.0, 0);
+        sim.queue_order("TQQQ".to_string(), Side::Buy, 100.0, 0, 1);
         assert_eq!(sim.pending_order_count(), 1);
 
         // Check at bar 0 - should not be ready
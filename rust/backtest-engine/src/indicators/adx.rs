@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+use super::true_range;
+
+/// Period of the ADX behind the entry filter (Wilder's 14)
+pub const ADX_PERIOD: usize = 14;
+
+/// Directional movement lines (0-100), aligned with the input closes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AdxResult {
+    pub plus_di: Vec<Option<f64>>,
+    pub minus_di: Vec<Option<f64>>,
+    /// Trend strength, whichever the direction
+    pub adx: Vec<Option<f64>>,
+}
+
+/// Calculate +DI, -DI and ADX (Wilder smoothing)
+///
+/// True range and directional movement start at the second bar. Their
+/// Wilder sums give the DI lines from bar `period`; the ADX, Wilder's
+/// average of DX, follows from bar `2 * period - 1`.
+///
+/// # Returns
+/// AdxResult with None for warmup bars, or throughout for a zero period
+pub fn calculate_adx(highs: &[f64], lows: &[f64], closes: &[f64], period: usize) -> AdxResult {
+    let mut result = AdxResult {
+        plus_di: vec![None; closes.len()],
+        minus_di: vec![None; closes.len()],
+        adx: vec![None; closes.len()],
+    };
+    let n = closes.len().min(highs.len()).min(lows.len());
+    if period == 0 || n <= period {
+        return result;
+    }
+
+    let movement = |i: usize| {
+        let up = highs[i] - highs[i - 1];
+        let down = lows[i - 1] - lows[i];
+        let plus = if up > down && up > 0.0 { up } else { 0.0 };
+        let minus = if down > up && down > 0.0 { down } else { 0.0 };
+        (true_range(highs[i], lows[i], closes[i - 1]), plus, minus)
+    };
+
+    let (mut tr, mut plus, mut minus) = (0.0, 0.0, 0.0);
+    let mut dx = Vec::with_capacity(n - period);
+    let p = period as f64;
+    for i in 1..n {
+        let (tr_i, plus_i, minus_i) = movement(i);
+        if i <= period {
+            tr += tr_i;
+            plus += plus_i;
+            minus += minus_i;
+            if i < period {
+                continue;
+            }
+        } else {
+            tr += tr_i - tr / p;
+            plus += plus_i - plus / p;
+            minus += minus_i - minus / p;
+        }
+
+        let (plus_di, minus_di) = if tr > 0.0 {
+            (plus / tr * 100.0, minus / tr * 100.0)
+        } else {
+            (0.0, 0.0)
+        };
+        result.plus_di[i] = Some(plus_di);
+        result.minus_di[i] = Some(minus_di);
+        let sum = plus_di + minus_di;
+        dx.push(if sum > 0.0 {
+            (plus_di - minus_di).abs() / sum * 100.0
+        } else {
+            0.0
+        });
+    }
+
+    if dx.len() < period {
+        return result;
+    }
+    let mut adx = dx[..period].iter().sum::<f64>() / p;
+    result.adx[2 * period - 1] = Some(adx);
+    for (k, value) in dx.iter().enumerate().skip(period) {
+        adx = (adx * (p - 1.0) + value) / p;
+        result.adx[period + k] = Some(adx);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adx_of(closes: &[f64], period: usize) -> AdxResult {
+        let highs: Vec<f64> = closes.iter().map(|c| c + 0.5).collect();
+        let lows: Vec<f64> = closes.iter().map(|c| c - 0.5).collect();
+        calculate_adx(&highs, &lows, closes, period)
+    }
+
+    #[test]
+    fn test_trend_has_high_adx() {
+        let closes: Vec<f64> = (0..60).map(|i| 100.0 + i as f64 * 2.0).collect();
+        let result = adx_of(&closes, 14);
+
+        assert_eq!(result.adx.len(), closes.len());
+        assert_eq!(result.plus_di[13], None);
+        assert!(result.plus_di[14].is_some());
+        assert_eq!(result.adx[26], None);
+        assert!(result.adx[27].is_some());
+        // Every bar moves up: all the movement is +DM
+        assert_eq!(result.minus_di[59], Some(0.0));
+        assert!(result.adx[59].unwrap() > 90.0);
+    }
+
+    #[test]
+    fn test_range_has_low_adx() {
+        let closes: Vec<f64> = (0..60)
+            .map(|i| if i % 2 == 0 { 100.0 } else { 101.0 })
+            .collect();
+        let result = adx_of(&closes, 14);
+
+        let adx = result.adx[59].unwrap();
+        assert!(adx < 20.0, "adx {}", adx);
+        assert!(adx_of(&closes[..14], 14)
+            .plus_di
+            .iter()
+            .all(|v| v.is_none()));
+        assert!(adx_of(&closes, 0).adx.iter().all(|v| v.is_none()));
+    }
+}
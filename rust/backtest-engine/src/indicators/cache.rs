@@ -0,0 +1,187 @@
+//! Indicator series cached on disk between runs
+//!
+//! [`IndicatorSeries::load_or_compute`] keeps one JSON file per set of bars
+//! and indicator settings, named by a hash of both and of
+//! [`INDICATOR_CACHE_VERSION`]. Hashes come from the standard library's
+//! hasher, so a new toolchain may miss caches written by an old one; it
+//! never reads the wrong one, as the file records what it was computed from.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use common::Bar;
+use serde::{Deserialize, Serialize};
+
+use super::{IndicatorConfig, IndicatorSeries};
+use crate::output::{write_bytes, Overwrite};
+
+/// Bump with any change to the indicator math or to the fields of
+/// `IndicatorSeries`, so that series cached before it are recomputed
+pub const INDICATOR_CACHE_VERSION: u32 = 6;
+
+/// Where [`IndicatorSeries::load_or_compute`] got the series from
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheOutcome {
+    /// Read from the cache
+    Hit,
+    /// Computed and cached, nothing being cached yet
+    Computed,
+    /// Computed because the cache could not be read or written; the
+    /// message says why
+    Failed(String),
+}
+
+/// Contents of a cache file
+#[derive(Serialize, Deserialize)]
+struct CacheFile<C, S> {
+    version: u32,
+    data_hash: u64,
+    config: C,
+    series: S,
+}
+
+impl IndicatorSeries {
+    /// The series [`IndicatorSeries::from_bars`] computes, read from
+    /// `cache_dir` when it was cached there for the same bars and `config`,
+    /// otherwise computed and written there. A cache that cannot be read
+    /// (or written) is recomputed (or skipped) and the outcome says why.
+    pub fn load_or_compute(
+        bars: &[Bar],
+        config: &IndicatorConfig,
+        cache_dir: &Path,
+    ) -> (Self, CacheOutcome) {
+        let data_hash = data_hash(bars);
+        let path = cache_path(cache_dir, data_hash, config);
+
+        let unreadable = match std::fs::read(&path) {
+            Ok(bytes) => match serde_json::from_slice::<CacheFile<IndicatorConfig, Self>>(&bytes) {
+                Ok(file)
+                    if file.version == INDICATOR_CACHE_VERSION
+                        && file.data_hash == data_hash
+                        && file.config == *config
+                        && file.series.rsi.len() == bars.len() =>
+                {
+                    return (file.series, CacheOutcome::Hit);
+                }
+                Ok(_) => Some(format!("{} is for other bars or settings", path.display())),
+                Err(e) => Some(format!("cannot parse {}: {}", path.display(), e)),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => Some(format!("cannot read {}: {}", path.display(), e)),
+        };
+
+        let series = Self::from_bars(bars, config);
+        let written = write_cache(&path, data_hash, config, &series);
+        let outcome = match (unreadable, written) {
+            (Some(reason), _) => CacheOutcome::Failed(format!("recomputed indicators: {}", reason)),
+            (None, Err(e)) => CacheOutcome::Failed(format!("indicator cache not written: {}", e)),
+            (None, Ok(())) => CacheOutcome::Computed,
+        };
+        (series, outcome)
+    }
+}
+
+/// Hash of every bar field, in order
+fn data_hash(bars: &[Bar]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bars.len().hash(&mut hasher);
+    for bar in bars {
+        bar.timestamp.hash(&mut hasher);
+        for price in [bar.open, bar.high, bar.low, bar.close] {
+            price.to_bits().hash(&mut hasher);
+        }
+        bar.volume.hash(&mut hasher);
+        bar.vwap.map(f64::to_bits).hash(&mut hasher);
+        bar.halted.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn cache_path(dir: &Path, data_hash: u64, config: &IndicatorConfig) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    INDICATOR_CACHE_VERSION.hash(&mut hasher);
+    data_hash.hash(&mut hasher);
+    serde_json::to_string(config)
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    dir.join(format!("indicators-{:016x}.json", hasher.finish()))
+}
+
+/// Write the cache file, replacing any there
+fn write_cache(
+    path: &Path,
+    data_hash: u64,
+    config: &IndicatorConfig,
+    series: &IndicatorSeries,
+) -> common::Result<()> {
+    let file = CacheFile {
+        version: INDICATOR_CACHE_VERSION,
+        data_hash,
+        config,
+        series,
+    };
+    write_bytes(path, Overwrite::Force, &serde_json::to_vec(&file)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+
+    fn cache_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_second_load_hits_cache() {
+        let dir = cache_dir("indicator_cache_hit");
+        let bars = generate_synthetic_bars_seeded(200, 100.0, 7);
+        let config = IndicatorConfig::default();
+
+        let (computed, outcome) = IndicatorSeries::load_or_compute(&bars, &config, &dir);
+        assert_eq!(outcome, CacheOutcome::Computed);
+        let (cached, outcome) = IndicatorSeries::load_or_compute(&bars, &config, &dir);
+        assert_eq!(outcome, CacheOutcome::Hit);
+        assert_eq!(
+            serde_json::to_string(&cached).unwrap(),
+            serde_json::to_string(&computed).unwrap()
+        );
+
+        // Other settings or other bars are cached apart
+        let other = IndicatorConfig {
+            rsi_period: 5,
+            ..config.clone()
+        };
+        let (_, outcome) = IndicatorSeries::load_or_compute(&bars, &other, &dir);
+        assert_eq!(outcome, CacheOutcome::Computed);
+        let (_, outcome) = IndicatorSeries::load_or_compute(&bars[1..], &config, &dir);
+        assert_eq!(outcome, CacheOutcome::Computed);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt_cache_is_recomputed() {
+        let dir = cache_dir("indicator_cache_corrupt");
+        let bars = generate_synthetic_bars_seeded(200, 100.0, 7);
+        let config = IndicatorConfig::default();
+        let (computed, _) = IndicatorSeries::load_or_compute(&bars, &config, &dir);
+
+        let path = cache_path(&dir, data_hash(&bars), &config);
+        std::fs::write(&path, b"{\"version\": 6, \"series\": [").unwrap();
+        let (series, outcome) = IndicatorSeries::load_or_compute(&bars, &config, &dir);
+        assert!(
+            matches!(&outcome, CacheOutcome::Failed(m) if m.contains("cannot parse")),
+            "{:?}",
+            outcome
+        );
+        assert_eq!(series.rsi, computed.rsi);
+
+        // The recomputed series replaced the corrupt file
+        let (_, outcome) = IndicatorSeries::load_or_compute(&bars, &config, &dir);
+        assert_eq!(outcome, CacheOutcome::Hit);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
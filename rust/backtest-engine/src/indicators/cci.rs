@@ -0,0 +1,76 @@
+/// Usual Commodity Channel Index period
+pub const CCI_PERIOD: usize = 20;
+
+/// Lambert's constant, scaling most readings into -100..100
+const CCI_SCALE: f64 = 0.015;
+
+/// Calculate the Commodity Channel Index: the typical price
+/// `(high + low + close) / 3` less its `period`-bar mean, over 0.015 times
+/// the mean absolute deviation from that mean
+///
+/// A window with no deviation (a flat typical price) reads 0.
+///
+/// # Returns
+/// Values aligned with the input closes, None for the first `period - 1`
+/// bars or throughout for a zero period
+pub fn calculate_cci(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    period: usize,
+) -> Vec<Option<f64>> {
+    let mut values = vec![None; closes.len()];
+    let n = closes.len().min(highs.len()).min(lows.len());
+    if period == 0 || n < period {
+        return values;
+    }
+
+    let typical: Vec<f64> = (0..n)
+        .map(|i| (highs[i] + lows[i] + closes[i]) / 3.0)
+        .collect();
+    for (end, window) in typical
+        .windows(period)
+        .enumerate()
+        .map(|(i, w)| (i + period - 1, w))
+    {
+        let mean = window.iter().sum::<f64>() / period as f64;
+        let deviation = window.iter().map(|tp| (tp - mean).abs()).sum::<f64>() / period as f64;
+        values[end] = Some(if deviation > 0.0 {
+            (typical[end] - mean) / (CCI_SCALE * deviation)
+        } else {
+            0.0
+        });
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cci_values() {
+        // Typical prices equal the closes
+        let closes = [10.0, 12.0, 14.0, 10.0];
+        let cci = calculate_cci(&closes, &closes, &closes, 3);
+
+        assert_eq!(&cci[..2], &[None, None]);
+        // Mean 12, mean deviation 4/3
+        assert!((cci[2].unwrap() - 2.0 / (0.015 * 4.0 / 3.0)).abs() < 1e-9);
+        assert!(cci[3].unwrap() < 0.0);
+    }
+
+    #[test]
+    fn test_cci_flat_window_reads_zero() {
+        let flat = [7.0; 5];
+        let cci = calculate_cci(&flat, &flat, &flat, 3);
+        assert!(cci[2..].iter().all(|v| *v == Some(0.0)));
+        assert_eq!(
+            calculate_cci(&flat[..2], &flat[..2], &flat[..2], 3),
+            vec![None; 2]
+        );
+        assert!(calculate_cci(&flat, &flat, &flat, 0)
+            .iter()
+            .all(|v| v.is_none()));
+    }
+}
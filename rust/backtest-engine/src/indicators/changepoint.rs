@@ -0,0 +1,104 @@
+//! Volatility-burst detection for circuit breakers
+//!
+//! A bar is an anomaly when its close-to-close log return is an outlier
+//! against the returns of the `window` bars before it. The bar's own return
+//! is left out of the baseline so that a single crash cannot mask itself,
+//! and nothing after the bar is read.
+
+/// Trailing returns forming the baseline of each bar's z-score
+pub const ANOMALY_WINDOW: usize = 20;
+
+/// Per-bar flag: the log return into the bar lies more than `threshold`
+/// sample standard deviations from the mean of the `window` returns before
+/// it. False until `window + 1` returns are available.
+///
+/// A flat baseline flags any return that differs from it.
+pub fn volatility_bursts(closes: &[f64], window: usize, threshold: f64) -> Vec<bool> {
+    let mut flags = vec![false; closes.len()];
+    if window < 2 || closes.len() < window + 2 {
+        return flags;
+    }
+
+    let returns: Vec<f64> = closes
+        .windows(2)
+        .map(|w| {
+            if w[0] > 0.0 && w[1] > 0.0 {
+                (w[1] / w[0]).ln()
+            } else {
+                0.0
+            }
+        })
+        .collect();
+    // returns[i - 1] is the return into bar i
+    for i in window + 1..closes.len() {
+        let baseline = &returns[i - 1 - window..i - 1];
+        let mean = baseline.iter().sum::<f64>() / window as f64;
+        let variance =
+            baseline.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (window - 1) as f64;
+        flags[i] = (returns[i - 1] - mean).abs() > threshold * variance.sqrt();
+    }
+    flags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn calm_closes(n: usize) -> Vec<f64> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(11);
+        let mut price = 100.0;
+        (0..n)
+            .map(|_| {
+                price *= 1.0 + rng.gen_range(-0.01..0.01);
+                price
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_single_crash_bar_flagged() {
+        let mut closes = calm_closes(80);
+        assert!(!volatility_bursts(&closes, ANOMALY_WINDOW, 6.0).contains(&true));
+
+        for close in &mut closes[50..] {
+            *close *= 0.85;
+        }
+        let flags = volatility_bursts(&closes, ANOMALY_WINDOW, 6.0);
+        let flagged: Vec<usize> = (0..flags.len()).filter(|&i| flags[i]).collect();
+        assert_eq!(flagged, vec![50]);
+
+        // Too little history to judge
+        assert!(
+            !volatility_bursts(&closes[..ANOMALY_WINDOW + 1], ANOMALY_WINDOW, 6.0).contains(&true)
+        );
+        let flat = [100.0, 100.0, 100.0, 100.0, 90.0];
+        assert_eq!(
+            volatility_bursts(&flat, 3, 6.0),
+            vec![false, false, false, false, true]
+        );
+    }
+
+    /// Changing the closes after bar i must leave every flag at or before i
+    #[test]
+    fn test_no_lookahead() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+        let mut closes = calm_closes(90);
+        closes[60] *= 0.8;
+        let original = volatility_bursts(&closes, ANOMALY_WINDOW, 4.0);
+
+        for i in 0..closes.len() - 1 {
+            let mut perturbed = closes.clone();
+            for close in &mut perturbed[i + 1..] {
+                *close *= rng.gen_range(0.5..1.5);
+            }
+            let flags = volatility_bursts(&perturbed, ANOMALY_WINDOW, 4.0);
+            assert_eq!(
+                original[..=i],
+                flags[..=i],
+                "flag changed at or before {}",
+                i
+            );
+        }
+    }
+}
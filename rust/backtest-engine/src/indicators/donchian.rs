@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+/// Usual Donchian Channel period (the Turtle 20-bar breakout)
+pub const DONCHIAN_PERIOD: usize = 20;
+
+/// Donchian Channel lines, aligned with the input highs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DonchianChannels {
+    /// Highest high of the window ending at each bar
+    pub upper: Vec<Option<f64>>,
+    /// Lowest low of the window ending at each bar
+    pub lower: Vec<Option<f64>>,
+    /// Halfway between the two
+    pub middle: Vec<Option<f64>>,
+}
+
+/// Calculate Donchian Channels: the highest high and lowest low over the
+/// `period` bars ending at each bar
+///
+/// The window includes its own bar, so a breakout compares a close with
+/// the previous bar's line (see [`is_breakout_up`]).
+///
+/// # Returns
+/// DonchianChannels with None for the first `period - 1` bars, or
+/// throughout for a zero period
+pub fn calculate_donchian(highs: &[f64], lows: &[f64], period: usize) -> DonchianChannels {
+    let mut channels = DonchianChannels {
+        upper: vec![None; highs.len()],
+        lower: vec![None; highs.len()],
+        middle: vec![None; highs.len()],
+    };
+    let n = highs.len().min(lows.len());
+    if period == 0 || n < period {
+        return channels;
+    }
+
+    for end in period - 1..n {
+        let window = end + 1 - period..=end;
+        let upper = highs[window.clone()]
+            .iter()
+            .copied()
+            .fold(f64::MIN, f64::max);
+        let lower = lows[window].iter().copied().fold(f64::MAX, f64::min);
+        channels.upper[end] = Some(upper);
+        channels.lower[end] = Some(lower);
+        channels.middle[end] = Some((upper + lower) / 2.0);
+    }
+    channels
+}
+
+/// Whether `close` breaks above the previous bar's upper line; false while
+/// that line is still warming up
+pub fn is_breakout_up(close: f64, upper_prev: Option<f64>) -> bool {
+    upper_prev.is_some_and(|upper| close > upper)
+}
+
+/// Whether `close` breaks below the previous bar's lower line; false while
+/// that line is still warming up
+pub fn is_breakout_down(close: f64, lower_prev: Option<f64>) -> bool {
+    lower_prev.is_some_and(|lower| close < lower)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_donchian_channels() {
+        let highs = [10.0, 12.0, 11.0, 9.0, 13.0];
+        let lows = [8.0, 9.0, 7.0, 8.0, 10.0];
+        let channels = calculate_donchian(&highs, &lows, 3);
+
+        assert_eq!(&channels.upper[..2], &[None, None]);
+        assert_eq!(&channels.upper[2..], &[Some(12.0), Some(12.0), Some(13.0)]);
+        assert_eq!(&channels.lower[2..], &[Some(7.0), Some(7.0), Some(7.0)]);
+        assert_eq!(channels.middle[4], Some(10.0));
+        assert!(calculate_donchian(&highs[..2], &lows[..2], 3)
+            .upper
+            .iter()
+            .all(|v| v.is_none()));
+        assert!(calculate_donchian(&highs, &lows, 0)
+            .lower
+            .iter()
+            .all(|v| v.is_none()));
+    }
+
+    #[test]
+    fn test_rising_closes_keep_breaking_out() {
+        let closes: Vec<f64> = (0..30).map(|i| 100.0 + i as f64).collect();
+        let highs: Vec<f64> = closes.iter().map(|c| c + 0.5).collect();
+        let lows: Vec<f64> = closes.iter().map(|c| c - 0.5).collect();
+        let channels = calculate_donchian(&highs, &lows, 5);
+
+        // Each close clears the prior bar's highest high by half a point
+        for (i, &close) in closes.iter().enumerate().skip(5) {
+            assert!(is_breakout_up(close, channels.upper[i - 1]), "bar {}", i);
+            assert!(!is_breakout_down(close, channels.lower[i - 1]));
+        }
+        // Against its own bar's line a close never breaks out
+        assert!(!is_breakout_up(closes[10], channels.upper[10]));
+        assert!(!is_breakout_up(closes[4], channels.upper[3]));
+        assert!(is_breakout_down(90.0, channels.lower[10]));
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+
+use super::{calculate_atr, calculate_ema_with_sma_seed};
+
+/// EMA period, ATR period and ATR multiple of the channel behind the
+/// entry filter
+pub const KELTNER_EMA_PERIOD: usize = 20;
+pub const KELTNER_ATR_PERIOD: usize = 10;
+pub const KELTNER_MULTIPLIER: f64 = 2.0;
+
+/// Keltner Channel lines, aligned with the input closes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeltnerChannels {
+    pub upper: Vec<Option<f64>>,
+    /// EMA of the closes
+    pub middle: Vec<Option<f64>>,
+    pub lower: Vec<Option<f64>>,
+}
+
+/// Calculate Keltner Channels: an SMA-seeded EMA of the closes, `multiplier`
+/// ATRs either side
+///
+/// # Returns
+/// KeltnerChannels with None until both the EMA and the ATR have warmed up,
+/// or throughout for a zero period
+pub fn calculate_keltner(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    ema_period: usize,
+    atr_period: usize,
+    multiplier: f64,
+) -> KeltnerChannels {
+    let mut channels = KeltnerChannels {
+        upper: vec![None; closes.len()],
+        middle: vec![None; closes.len()],
+        lower: vec![None; closes.len()],
+    };
+    let n = closes.len().min(highs.len()).min(lows.len());
+    let start = ema_period.max(atr_period);
+    if ema_period == 0 || atr_period == 0 || n < start {
+        return channels;
+    }
+
+    let ema = calculate_ema_with_sma_seed(&closes[..n], ema_period);
+    let atr = calculate_atr(&highs[..n], &lows[..n], &closes[..n], atr_period);
+    for i in start - 1..n {
+        let width = multiplier * atr[i];
+        channels.upper[i] = Some(ema[i] + width);
+        channels.middle[i] = Some(ema[i]);
+        channels.lower[i] = Some(ema[i] - width);
+    }
+    channels
+}
+
+/// Position of `price` within the channel, like %B within Bollinger Bands:
+/// 0 at the lower line, 1 at the upper, outside 0..1 beyond them
+pub fn percent_k(price: f64, lower: f64, upper: f64) -> f64 {
+    if upper == lower {
+        return 0.5;
+    }
+    (price - lower) / (upper - lower)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keltner_channels() {
+        let closes: Vec<f64> = (0..30).map(|i| 100.0 + i as f64).collect();
+        let highs: Vec<f64> = closes.iter().map(|c| c + 1.0).collect();
+        let lows: Vec<f64> = closes.iter().map(|c| c - 1.0).collect();
+        let channels = calculate_keltner(&highs, &lows, &closes, 10, 5, 2.0);
+
+        assert_eq!(channels.middle.len(), closes.len());
+        assert_eq!(channels.middle[8], None);
+        let ema = calculate_ema_with_sma_seed(&closes, 10);
+        let atr = calculate_atr(&highs, &lows, &closes, 5);
+        assert_eq!(channels.middle[9], Some(ema[9]));
+        assert_eq!(channels.upper[29], Some(ema[29] + 2.0 * atr[29]));
+        assert_eq!(channels.lower[29], Some(ema[29] - 2.0 * atr[29]));
+
+        let short = calculate_keltner(&highs[..4], &lows[..4], &closes[..4], 10, 5, 2.0);
+        assert!(short.upper.iter().all(|v| v.is_none()));
+        assert!(calculate_keltner(&highs, &lows, &closes, 0, 5, 2.0)
+            .middle
+            .iter()
+            .all(|v| v.is_none()));
+    }
+
+    #[test]
+    fn test_percent_k() {
+        assert_eq!(percent_k(95.0, 90.0, 110.0), 0.25);
+        assert_eq!(percent_k(85.0, 90.0, 110.0), -0.25);
+        assert_eq!(percent_k(100.0, 100.0, 100.0), 0.5);
+    }
+}
@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use super::calculate_ema_with_sma_seed;
+
+/// MACD line, signal line and histogram, aligned with the input prices
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MacdResult {
+    /// Fast EMA minus slow EMA
+    pub macd: Vec<Option<f64>>,
+    /// EMA of the MACD line
+    pub signal: Vec<Option<f64>>,
+    /// MACD line minus signal line
+    pub histogram: Vec<Option<f64>>,
+}
+
+/// Calculate MACD (12, 26, 9 being the usual periods)
+///
+/// Both price EMAs and the signal EMA are seeded with an SMA, so the MACD
+/// line starts at bar `max(fast, slow) - 1` and the signal line and
+/// histogram `signal - 1` bars after it.
+///
+/// # Returns
+/// MacdResult with None for warmup bars, or throughout for a zero period
+pub fn calculate_macd(prices: &[f64], fast: usize, slow: usize, signal: usize) -> MacdResult {
+    let n = prices.len();
+    let mut result = MacdResult {
+        macd: vec![None; n],
+        signal: vec![None; n],
+        histogram: vec![None; n],
+    };
+    if fast == 0 || slow == 0 || signal == 0 {
+        return result;
+    }
+    let start = fast.max(slow) - 1;
+    if n <= start {
+        return result;
+    }
+
+    let fast_ema = calculate_ema_with_sma_seed(prices, fast);
+    let slow_ema = calculate_ema_with_sma_seed(prices, slow);
+    let line: Vec<f64> = (start..n).map(|i| fast_ema[i] - slow_ema[i]).collect();
+    for (i, value) in line.iter().enumerate() {
+        result.macd[start + i] = Some(*value);
+    }
+
+    if line.len() < signal {
+        return result;
+    }
+    let signal_ema = calculate_ema_with_sma_seed(&line, signal);
+    for i in signal - 1..line.len() {
+        result.signal[start + i] = Some(signal_ema[i]);
+        result.histogram[start + i] = Some(line[i] - signal_ema[i]);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::calculate_sma;
+
+    #[test]
+    fn test_macd_warmup_and_values() {
+        let prices: Vec<f64> = (0..40)
+            .map(|i| 100.0 + (i as f64 * 0.7).sin() * 5.0)
+            .collect();
+        let macd = calculate_macd(&prices, 3, 6, 4);
+
+        assert_eq!(macd.macd.len(), prices.len());
+        assert!(macd.macd[..5].iter().all(|v| v.is_none()));
+        assert!(macd.signal[..8].iter().all(|v| v.is_none()));
+        assert!(macd.histogram[..8].iter().all(|v| v.is_none()));
+        assert!(macd.signal[8..].iter().all(|v| v.is_some()));
+
+        let fast = calculate_ema_with_sma_seed(&prices, 3);
+        let slow = calculate_ema_with_sma_seed(&prices, 6);
+        assert_eq!(macd.macd[5], Some(fast[5] - slow[5]));
+        // The signal line is seeded with the mean of the first four MACD values
+        let line: Vec<f64> = macd.macd.iter().flatten().copied().collect();
+        assert_eq!(macd.signal[8], calculate_sma(&line, 4)[3]);
+        let last = prices.len() - 1;
+        let histogram = macd.macd[last].unwrap() - macd.signal[last].unwrap();
+        assert_eq!(macd.histogram[last], Some(histogram));
+    }
+
+    #[test]
+    fn test_macd_short_or_degenerate_input() {
+        let prices = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+        let short = calculate_macd(&prices, 2, 4, 3);
+        assert_eq!(short.macd.iter().flatten().count(), 2);
+        assert!(short.signal.iter().all(|v| v.is_none()));
+
+        assert!(calculate_macd(&prices, 0, 4, 3)
+            .macd
+            .iter()
+            .all(|v| v.is_none()));
+        assert!(calculate_macd(&[], 12, 26, 9).macd.is_empty());
+    }
+}
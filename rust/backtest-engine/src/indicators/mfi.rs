@@ -0,0 +1,100 @@
+/// Usual Money Flow Index period
+pub const MFI_PERIOD: usize = 14;
+
+/// Calculate the Money Flow Index (0-100): RSI of the typical price
+/// `(high + low + close) / 3`, weighted by volume
+///
+/// Each bar after the first contributes its typical price times volume as
+/// positive flow when the typical price rose, negative when it fell, and
+/// nothing when it held. A window with no negative flow reads 100; one with
+/// no flow at all reads 50.
+///
+/// # Returns
+/// Values aligned with the input closes, None for the first `period` bars
+/// or throughout for a zero period
+pub fn calculate_mfi(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    volumes: &[u64],
+    period: usize,
+) -> Vec<Option<f64>> {
+    let mut mfi = vec![None; closes.len()];
+    let n = closes
+        .len()
+        .min(highs.len())
+        .min(lows.len())
+        .min(volumes.len());
+    if period == 0 || n <= period {
+        return mfi;
+    }
+
+    let typical: Vec<f64> = (0..n)
+        .map(|i| (highs[i] + lows[i] + closes[i]) / 3.0)
+        .collect();
+    // (positive, negative) flow of each bar, the first having none
+    let flows: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let flow = typical[i] * volumes[i] as f64;
+            if i > 0 && typical[i] > typical[i - 1] {
+                (flow, 0.0)
+            } else if i > 0 && typical[i] < typical[i - 1] {
+                (0.0, flow)
+            } else {
+                (0.0, 0.0)
+            }
+        })
+        .collect();
+
+    for (i, value) in mfi.iter_mut().enumerate().take(n).skip(period) {
+        let (positive, negative) = flows[i + 1 - period..=i]
+            .iter()
+            .fold((0.0, 0.0), |(p, m), (fp, fm)| (p + fp, m + fm));
+        *value = Some(if negative > 0.0 {
+            100.0 - 100.0 / (1.0 + positive / negative)
+        } else if positive > 0.0 {
+            100.0
+        } else {
+            50.0
+        });
+    }
+    mfi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mfi_of(closes: &[f64], volumes: &[u64], period: usize) -> Vec<Option<f64>> {
+        let highs: Vec<f64> = closes.iter().map(|c| c + 1.0).collect();
+        let lows: Vec<f64> = closes.iter().map(|c| c - 1.0).collect();
+        calculate_mfi(&highs, &lows, closes, volumes, period)
+    }
+
+    #[test]
+    fn test_mfi_values() {
+        let closes = [10.0, 11.0, 10.5, 12.0, 11.0];
+        let volumes = [100, 200, 100, 300, 400];
+        let mfi = mfi_of(&closes, &volumes, 3);
+
+        assert_eq!(mfi.len(), closes.len());
+        assert_eq!(&mfi[..3], &[None, None, None]);
+        // Bars 1-3: +11*200, -10.5*100, +12*300
+        let (positive, negative) = (11.0 * 200.0 + 12.0 * 300.0, 10.5 * 100.0);
+        let expected = 100.0 - 100.0 / (1.0 + positive / negative);
+        assert!((mfi[3].unwrap() - expected).abs() < 1e-9);
+        assert!(mfi[4].unwrap() < mfi[3].unwrap());
+    }
+
+    #[test]
+    fn test_mfi_one_sided_and_short_input() {
+        let rising: Vec<f64> = (0..10).map(|i| 100.0 + i as f64).collect();
+        let mfi = mfi_of(&rising, &[1_000; 10], 4);
+        assert!(mfi[4..].iter().all(|v| *v == Some(100.0)));
+
+        let flat = mfi_of(&[50.0; 6], &[1_000; 6], 3);
+        assert!(flat[3..].iter().all(|v| *v == Some(50.0)));
+        assert_eq!(mfi_of(&rising[..3], &[1_000; 3], 4), vec![None; 3]);
+        assert!(mfi_of(&rising, &[1_000; 10], 0).iter().all(|v| v.is_none()));
+    }
+}
@@ -1,42 +1,333 @@
-pub mod atr;
-pub mod bollinger;
-pub mod ema;
-pub mod rsi;
-pub mod sma;
-
-pub use atr::{calculate_atr, true_range};
-pub use bollinger::{calculate_bollinger_bands, bandwidth, percent_b, BollingerBands};
-pub use ema::{calculate_ema, calculate_ema_with_sma_seed};
-pub use rsi::calculate_rsi;
-pub use sma::{calculate_sma, calculate_sma_filled};
+pub mod adx;
+pub mod cache;
+pub mod cci;
+pub mod changepoint;
+pub mod donchian;
+pub mod keltner;
+pub mod macd;
+pub mod mfi;
+pub mod momentum;
+pub mod normalize;
+pub mod obv;
+pub mod roc;
+pub mod stochastic;
+pub mod volume;
+pub mod vwap;
+pub mod williams_r;
+
+use common::config::ATR_PERIOD;
+use common::{BacktestParameters, Bar, IndicatorSnapshot, Smoothing};
+use serde::{Deserialize, Serialize};
+
+// The price-slice math lives in `common` so it also builds without `std`
+pub use adx::{calculate_adx, AdxResult, ADX_PERIOD};
+pub use cache::{CacheOutcome, INDICATOR_CACHE_VERSION};
+pub use cci::{calculate_cci, CCI_PERIOD};
+pub use changepoint::{volatility_bursts, ANOMALY_WINDOW};
+pub use common::indicators::{atr, bollinger, ema, gap_reset_starts, rsi, sma};
+pub use common::indicators::{
+    bandwidth, calculate_atr, calculate_atr_with, calculate_atr_with_gap_reset,
+    calculate_bollinger_bands, calculate_ema, calculate_ema_with_sma_seed, calculate_rsi,
+    calculate_rsi_with, calculate_rsi_with_gap_reset, calculate_sma, calculate_sma_filled,
+    percent_b, true_range, BollingerBands,
+};
+pub use donchian::{
+    calculate_donchian, is_breakout_down, is_breakout_up, DonchianChannels, DONCHIAN_PERIOD,
+};
+pub use keltner::{
+    calculate_keltner, percent_k, KeltnerChannels, KELTNER_ATR_PERIOD, KELTNER_EMA_PERIOD,
+    KELTNER_MULTIPLIER,
+};
+pub use macd::{calculate_macd, MacdResult};
+pub use mfi::{calculate_mfi, MFI_PERIOD};
+pub use momentum::calculate_momentum;
+pub use normalize::{log_returns, min_max_scaled, zscore};
+pub use obv::{calculate_obv, obv_slope, OBV_SLOPE_LOOKBACK};
+pub use roc::{calculate_percentile_rank, calculate_roc, ROC_RANK_WINDOW};
+pub use stochastic::{calculate_stochastic, StochasticResult};
+pub use volume::calculate_avg_volume;
+pub use vwap::{
+    calculate_anchored_vwap, calculate_monthly_vwap, calculate_rolling_vwap, VWAP_WINDOW,
+};
+pub use williams_r::{calculate_williams_r, WILLIAMS_R_PERIOD};
+
+fn closes_of(bars: &[Bar]) -> Vec<f64> {
+    bars.iter().map(|b| b.close).collect()
+}
+
+/// RSI of the bar closes (Wilder smoothing)
+pub fn rsi_of_bars(bars: &[Bar], period: usize) -> Vec<f64> {
+    calculate_rsi(&closes_of(bars), period)
+}
+
+/// ATR of the bars (Wilder smoothing)
+pub fn atr_of_bars(bars: &[Bar], period: usize) -> Vec<f64> {
+    let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+    let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+    calculate_atr(&highs, &lows, &closes_of(bars), period)
+}
+
+/// Bollinger Bands of the bar closes
+pub fn bollinger_of_bars(bars: &[Bar], period: usize, std_dev: f64) -> BollingerBands {
+    calculate_bollinger_bands(&closes_of(bars), period, std_dev)
+}
+
+/// Periods and smoothing of the indicators in an [`IndicatorSeries`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndicatorConfig {
+    pub rsi_period: usize,
+    /// Period of both the SMA and the EMA
+    pub sma_period: usize,
+    pub bb_period: usize,
+    pub bb_std_dev: f64,
+    pub atr_period: usize,
+    pub rsi_smoothing: Smoothing,
+    pub atr_smoothing: Smoothing,
+    /// Restart RSI and ATR after bars moving more than this many percent
+    pub rsi_reset_on_gap_pct: Option<f64>,
+}
+
+impl Default for IndicatorConfig {
+    fn default() -> Self {
+        Self::from(&BacktestParameters::default())
+    }
+}
+
+impl From<&BacktestParameters> for IndicatorConfig {
+    /// The configuration the engine uses for these parameters
+    fn from(params: &BacktestParameters) -> Self {
+        Self {
+            rsi_period: params.strategy.rsi_period,
+            sma_period: params.strategy.sma_period,
+            bb_period: params.strategy.bb_period,
+            bb_std_dev: params.strategy.bb_std_dev,
+            atr_period: ATR_PERIOD,
+            rsi_smoothing: params.strategy.rsi_smoothing,
+            atr_smoothing: params.strategy.atr_smoothing,
+            rsi_reset_on_gap_pct: params.strategy.rsi_reset_on_gap_pct,
+        }
+    }
+}
 
 /// Container for all calculated indicators at a specific point
 #[derive(Debug, Clone, Default)]
 pub struct IndicatorValues {
     pub rsi: f64,
     pub sma: Option<f64>,
+    /// Change (%) of the SMA over the slope filter's lookback
+    pub sma_slope: Option<f64>,
     pub ema: f64,
     pub atr: f64,
     pub bb_upper: f64,
     pub bb_middle: f64,
     pub bb_lower: f64,
+    /// The rolling VWAP when computed, which the engine overrides with the
+    /// bar's own
     pub vwap: Option<f64>,
+    /// Trailing average volume (set by the engine)
+    pub avg_volume: Option<f64>,
+    /// Bars since the open long's entry bar (set by the engine)
+    pub bars_held: Option<usize>,
+    /// VWAP anchored at the month's first bar (see [`calculate_monthly_vwap`])
+    pub anchored_vwap: Option<f64>,
+    /// Rate of change (%) and its percentile rank over `ROC_RANK_WINDOW` bars
+    pub roc: Option<f64>,
+    pub roc_percentile: Option<f64>,
+    /// Rate of change over `momentum_lookback` bars, against the free-fall
+    /// floor
+    pub lookback_roc: Option<f64>,
+    /// Rolling z-score of the close and log return from the previous close
+    pub close_zscore: Option<f64>,
+    pub log_return: Option<f64>,
     pub prev_high: Option<f64>,
     pub prev_low: Option<f64>,
+    /// MACD line, signal line and histogram (see [`calculate_macd`])
+    pub macd: Option<f64>,
+    pub macd_signal: Option<f64>,
+    pub macd_histogram: Option<f64>,
+    /// Stochastic %K and %D (see [`calculate_stochastic`])
+    pub stoch_k: Option<f64>,
+    pub stoch_d: Option<f64>,
+    /// ADX and its directional lines (see [`calculate_adx`])
+    pub adx: Option<f64>,
+    pub plus_di: Option<f64>,
+    pub minus_di: Option<f64>,
+    /// On-Balance Volume and its slope over `OBV_SLOPE_LOOKBACK` bars
+    pub obv: Option<f64>,
+    pub obv_slope: Option<f64>,
+    /// Money Flow Index (see [`calculate_mfi`])
+    pub mfi: Option<f64>,
+    /// Williams %R (see [`calculate_williams_r`])
+    pub williams_r: Option<f64>,
+    /// Commodity Channel Index (see [`calculate_cci`])
+    pub cci: Option<f64>,
+    /// Keltner Channel lines (see [`calculate_keltner`])
+    pub keltner_upper: Option<f64>,
+    pub keltner_middle: Option<f64>,
+    pub keltner_lower: Option<f64>,
+    /// Donchian Channel lines (see [`calculate_donchian`]), and the previous
+    /// bar's for breakouts without lookahead
+    pub donchian_upper: Option<f64>,
+    pub donchian_middle: Option<f64>,
+    pub donchian_lower: Option<f64>,
+    pub prev_donchian_upper: Option<f64>,
+    pub prev_donchian_lower: Option<f64>,
+    /// RSI or ATR is re-seeding after a gap day; entries are held off
+    pub reseeding: bool,
+    /// RSI, ATR or the Bollinger Bands have yet to warm up, their readings
+    /// placeholders; no signal is generated
+    pub warming_up: bool,
+    /// The bar's return is a volatility burst (see [`volatility_bursts`])
+    pub anomaly: bool,
+}
+
+impl IndicatorValues {
+    /// The values kept on a trade, for a bar that traded `volume`
+    pub fn snapshot(&self, volume: u64) -> IndicatorSnapshot {
+        IndicatorSnapshot {
+            rsi: self.rsi,
+            sma: self.sma,
+            ema: self.ema,
+            atr: self.atr,
+            bb_upper: self.bb_upper,
+            bb_middle: self.bb_middle,
+            bb_lower: self.bb_lower,
+            vwap: self.vwap,
+            volume_ratio: self
+                .avg_volume
+                .filter(|avg| *avg > 0.0)
+                .map(|avg| volume as f64 / avg),
+        }
+    }
 }
 
 /// Pre-computed indicators for all bars
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct IndicatorSeries {
     pub rsi: Vec<f64>,
     pub sma: Vec<Option<f64>>,
     pub ema: Vec<f64>,
     pub atr: Vec<f64>,
     pub bb: BollingerBands,
+    /// Empty unless computed with [`IndicatorSeries::with_sma_slope`]
+    pub sma_slope: Vec<Option<f64>>,
+    /// Empty unless computed with [`IndicatorSeries::with_momentum`]
+    pub roc: Vec<Option<f64>>,
+    pub roc_percentile: Vec<Option<f64>>,
+    /// Empty unless computed with [`IndicatorSeries::with_lookback_roc`]
+    pub lookback_roc: Vec<Option<f64>>,
+    /// Empty unless computed with [`IndicatorSeries::with_normalization`]
+    pub close_zscore: Vec<Option<f64>>,
+    pub log_return: Vec<Option<f64>>,
+    /// Bars on which RSI or ATR is re-seeding after a gap day (empty without
+    /// `rsi_reset_on_gap_pct`)
+    pub reseeding: Vec<bool>,
+    /// First bar on which RSI, ATR and the Bollinger Bands all have values
+    pub warm_from: usize,
+    /// Empty unless computed with [`IndicatorSeries::with_anomalies`]
+    pub anomaly: Vec<bool>,
+    /// None unless computed with [`IndicatorSeries::with_macd`]
+    pub macd: Option<MacdResult>,
+    /// None unless computed with [`IndicatorSeries::with_stochastic`]
+    pub stochastic: Option<StochasticResult>,
+    /// None unless computed with [`IndicatorSeries::with_adx`]
+    pub adx: Option<AdxResult>,
+    /// Empty unless computed with [`IndicatorSeries::with_obv`]
+    pub obv: Vec<f64>,
+    pub obv_slope: Vec<Option<f64>>,
+    /// Empty unless computed with [`IndicatorSeries::with_mfi`]
+    pub mfi: Vec<Option<f64>>,
+    /// Empty unless computed with [`IndicatorSeries::with_williams_r`]
+    pub williams_r: Vec<Option<f64>>,
+    /// Empty unless computed with [`IndicatorSeries::with_cci`]
+    pub cci: Vec<Option<f64>>,
+    /// None unless computed with [`IndicatorSeries::with_keltner`]
+    pub keltner: Option<KeltnerChannels>,
+    /// None unless computed with [`IndicatorSeries::with_donchian`]
+    pub donchian: Option<DonchianChannels>,
+    /// Empty unless computed with [`IndicatorSeries::with_rolling_vwap`]
+    pub rolling_vwap: Vec<Option<f64>>,
+    /// Empty unless computed with [`IndicatorSeries::with_monthly_vwap`]
+    pub anchored_vwap: Vec<Option<f64>>,
 }
 
 impl IndicatorSeries {
+    /// Calculate all indicators from bars
+    pub fn from_bars(bars: &[Bar], config: &IndicatorConfig) -> Self {
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        Self::calculate_with(&closes_of(bars), &highs, &lows, config)
+    }
+
     /// Calculate all indicators from price data
+    pub fn calculate_with(
+        closes: &[f64],
+        highs: &[f64],
+        lows: &[f64],
+        config: &IndicatorConfig,
+    ) -> Self {
+        let reset = config.rsi_reset_on_gap_pct;
+        let reseeding = match reset {
+            Some(_) => {
+                // ATR's first value lands a bar earlier than RSI's
+                let window = config.rsi_period.max(config.atr_period.saturating_sub(1));
+                let mut mask = vec![false; closes.len()];
+                for start in gap_reset_starts(closes, reset).into_iter().skip(1) {
+                    let end = (start + window).min(closes.len());
+                    mask[start..end].fill(true);
+                }
+                mask
+            }
+            None => Vec::new(),
+        };
+        Self {
+            rsi: calculate_rsi_with_gap_reset(
+                closes,
+                config.rsi_period,
+                config.rsi_smoothing,
+                reset,
+            ),
+            sma: calculate_sma(closes, config.sma_period),
+            ema: calculate_ema(closes, config.sma_period),
+            atr: calculate_atr_with_gap_reset(
+                highs,
+                lows,
+                closes,
+                config.atr_period,
+                config.atr_smoothing,
+                reset,
+            ),
+            bb: calculate_bollinger_bands(closes, config.bb_period, config.bb_std_dev),
+            sma_slope: Vec::new(),
+            roc: Vec::new(),
+            roc_percentile: Vec::new(),
+            lookback_roc: Vec::new(),
+            close_zscore: Vec::new(),
+            log_return: Vec::new(),
+            reseeding,
+            warm_from: config
+                .rsi_period
+                .max(config.atr_period.saturating_sub(1))
+                .max(config.bb_period.saturating_sub(1)),
+            anomaly: Vec::new(),
+            macd: None,
+            stochastic: None,
+            adx: None,
+            obv: Vec::new(),
+            obv_slope: Vec::new(),
+            mfi: Vec::new(),
+            williams_r: Vec::new(),
+            cci: Vec::new(),
+            keltner: None,
+            donchian: None,
+            rolling_vwap: Vec::new(),
+            anchored_vwap: Vec::new(),
+        }
+    }
+
+    /// Calculate all indicators from price data
+    #[deprecated(note = "use `from_bars` or `calculate_with` with an `IndicatorConfig`")]
+    #[allow(clippy::too_many_arguments)]
     pub fn calculate(
         closes: &[f64],
         highs: &[f64],
@@ -47,28 +338,468 @@ impl IndicatorSeries {
         bb_std_dev: f64,
         atr_period: usize,
     ) -> Self {
-        Self {
-            rsi: calculate_rsi(closes, rsi_period),
-            sma: calculate_sma(closes, sma_period),
-            ema: calculate_ema(closes, sma_period),
-            atr: calculate_atr(highs, lows, closes, atr_period),
-            bb: calculate_bollinger_bands(closes, bb_period, bb_std_dev),
-        }
+        let config = IndicatorConfig {
+            rsi_period,
+            sma_period,
+            bb_period,
+            bb_std_dev,
+            atr_period,
+            rsi_smoothing: Smoothing::Wilder,
+            atr_smoothing: Smoothing::Wilder,
+            rsi_reset_on_gap_pct: None,
+        };
+        Self::calculate_with(closes, highs, lows, &config)
+    }
+
+    /// Calculate all indicators with explicit RSI/ATR smoothing modes
+    #[deprecated(note = "use `from_bars` or `calculate_with` with an `IndicatorConfig`")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_smoothed(
+        closes: &[f64],
+        highs: &[f64],
+        lows: &[f64],
+        rsi_period: usize,
+        sma_period: usize,
+        bb_period: usize,
+        bb_std_dev: f64,
+        atr_period: usize,
+        rsi_smoothing: Smoothing,
+        atr_smoothing: Smoothing,
+    ) -> Self {
+        let config = IndicatorConfig {
+            rsi_period,
+            sma_period,
+            bb_period,
+            bb_std_dev,
+            atr_period,
+            rsi_smoothing,
+            atr_smoothing,
+            rsi_reset_on_gap_pct: None,
+        };
+        Self::calculate_with(closes, highs, lows, &config)
+    }
+
+    /// Add the change (%) of the SMA over the last `lookback` bars; None
+    /// until the SMA has `lookback` earlier values, or where it was 0
+    pub fn with_sma_slope(mut self, lookback: usize) -> Self {
+        self.sma_slope = (0..self.sma.len())
+            .map(|i| {
+                let then = self.sma[i.checked_sub(lookback)?]?;
+                let now = self.sma[i]?;
+                (then != 0.0).then(|| (now / then - 1.0) * 100.0)
+            })
+            .collect();
+        self
+    }
+
+    /// Add rate of change over `roc_period` bars and its rolling percentile
+    pub fn with_momentum(mut self, closes: &[f64], roc_period: usize) -> Self {
+        self.roc = calculate_roc(closes, roc_period);
+        self.roc_percentile = calculate_percentile_rank(&self.roc, ROC_RANK_WINDOW);
+        self
+    }
+
+    /// Add the rate of change over `lookback` bars, apart from the one
+    /// [`IndicatorSeries::with_momentum`] ranks
+    pub fn with_lookback_roc(mut self, closes: &[f64], lookback: usize) -> Self {
+        self.lookback_roc = calculate_roc(closes, lookback);
+        self
+    }
+
+    /// Add the rolling z-score of the close over `zscore_window` bars and
+    /// close-to-close log returns
+    pub fn with_normalization(mut self, closes: &[f64], zscore_window: usize) -> Self {
+        self.close_zscore = zscore(closes, zscore_window);
+        self.log_return = log_returns(closes);
+        self
+    }
+
+    /// Flag volatility-burst bars: returns more than `zscore` standard
+    /// deviations out against the trailing `ANOMALY_WINDOW` returns
+    pub fn with_anomalies(mut self, closes: &[f64], zscore: f64) -> Self {
+        self.anomaly = volatility_bursts(closes, ANOMALY_WINDOW, zscore);
+        self
+    }
+
+    /// Add MACD with the given fast, slow and signal periods
+    pub fn with_macd(mut self, closes: &[f64], fast: usize, slow: usize, signal: usize) -> Self {
+        self.macd = Some(calculate_macd(closes, fast, slow, signal));
+        self
+    }
+
+    /// Add the stochastic oscillator with the given %K period, %K smoothing
+    /// and %D period
+    pub fn with_stochastic(
+        mut self,
+        bars: &[Bar],
+        k_period: usize,
+        k_smooth: usize,
+        d_period: usize,
+    ) -> Self {
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        self.stochastic = Some(calculate_stochastic(
+            &highs,
+            &lows,
+            &closes_of(bars),
+            k_period,
+            k_smooth,
+            d_period,
+        ));
+        self
+    }
+
+    /// Add ADX and the directional indicators over `period` bars
+    pub fn with_adx(mut self, bars: &[Bar], period: usize) -> Self {
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        self.adx = Some(calculate_adx(&highs, &lows, &closes_of(bars), period));
+        self
+    }
+
+    /// Add On-Balance Volume and its slope over `lookback` bars
+    pub fn with_obv(mut self, bars: &[Bar], lookback: usize) -> Self {
+        let volumes: Vec<u64> = bars.iter().map(|b| b.volume).collect();
+        self.obv = calculate_obv(&closes_of(bars), &volumes);
+        self.obv_slope = obv_slope(&self.obv, lookback);
+        self
+    }
+
+    /// Add the Money Flow Index over `period` bars
+    pub fn with_mfi(mut self, bars: &[Bar], period: usize) -> Self {
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        let volumes: Vec<u64> = bars.iter().map(|b| b.volume).collect();
+        self.mfi = calculate_mfi(&highs, &lows, &closes_of(bars), &volumes, period);
+        self
+    }
+
+    /// Add the rolling VWAP over `window` bars, standing in for the bars'
+    /// own VWAP where they have none
+    pub fn with_rolling_vwap(mut self, bars: &[Bar], window: usize) -> Self {
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        let volumes: Vec<u64> = bars.iter().map(|b| b.volume).collect();
+        self.rolling_vwap =
+            calculate_rolling_vwap(&highs, &lows, &closes_of(bars), &volumes, window);
+        self
+    }
+
+    /// Add the VWAP anchored at the first bar of each calendar month
+    pub fn with_monthly_vwap(mut self, bars: &[Bar]) -> Self {
+        self.anchored_vwap = calculate_monthly_vwap(bars);
+        self
+    }
+
+    /// Add Williams %R over `period` bars
+    pub fn with_williams_r(mut self, bars: &[Bar], period: usize) -> Self {
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        self.williams_r = calculate_williams_r(&highs, &lows, &closes_of(bars), period);
+        self
+    }
+
+    /// Add the Commodity Channel Index over `period` bars
+    pub fn with_cci(mut self, bars: &[Bar], period: usize) -> Self {
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        self.cci = calculate_cci(&highs, &lows, &closes_of(bars), period);
+        self
+    }
+
+    /// Add Keltner Channels: the `ema_period` EMA, `multiplier` ATRs over
+    /// `atr_period` bars either side
+    pub fn with_keltner(
+        mut self,
+        bars: &[Bar],
+        ema_period: usize,
+        atr_period: usize,
+        multiplier: f64,
+    ) -> Self {
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        self.keltner = Some(calculate_keltner(
+            &highs,
+            &lows,
+            &closes_of(bars),
+            ema_period,
+            atr_period,
+            multiplier,
+        ));
+        self
+    }
+
+    /// Add Donchian Channels over `period` bars
+    pub fn with_donchian(mut self, bars: &[Bar], period: usize) -> Self {
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        self.donchian = Some(calculate_donchian(&highs, &lows, period));
+        self
+    }
+
+    /// Whether RSI, ATR and the Bollinger Bands have real values at `idx`
+    /// rather than their warmup placeholders (RSI 50, ATR and bands 0)
+    pub fn is_warm(&self, idx: usize) -> bool {
+        idx >= self.warm_from && idx < self.rsi.len()
     }
 
     /// Get indicator values at a specific index
     pub fn get(&self, idx: usize) -> IndicatorValues {
+        let macd = |line: fn(&MacdResult) -> &Vec<Option<f64>>| {
+            self.macd
+                .as_ref()
+                .and_then(|m| line(m).get(idx).copied().flatten())
+        };
+        let stochastic = |line: fn(&StochasticResult) -> &Vec<Option<f64>>| {
+            self.stochastic
+                .as_ref()
+                .and_then(|s| line(s).get(idx).copied().flatten())
+        };
+        let adx = |line: fn(&AdxResult) -> &Vec<Option<f64>>| {
+            self.adx
+                .as_ref()
+                .and_then(|a| line(a).get(idx).copied().flatten())
+        };
+        let keltner = |line: fn(&KeltnerChannels) -> &Vec<Option<f64>>| {
+            self.keltner
+                .as_ref()
+                .and_then(|k| line(k).get(idx).copied().flatten())
+        };
+        let donchian = |line: fn(&DonchianChannels) -> &Vec<Option<f64>>, at: Option<usize>| {
+            let at = at?;
+            self.donchian
+                .as_ref()
+                .and_then(|d| line(d).get(at).copied().flatten())
+        };
         IndicatorValues {
             rsi: self.rsi.get(idx).copied().unwrap_or(50.0),
             sma: self.sma.get(idx).copied().flatten(),
+            sma_slope: self.sma_slope.get(idx).copied().flatten(),
             ema: self.ema.get(idx).copied().unwrap_or(0.0),
             atr: self.atr.get(idx).copied().unwrap_or(0.0),
             bb_upper: self.bb.upper.get(idx).copied().unwrap_or(0.0),
             bb_middle: self.bb.middle.get(idx).copied().unwrap_or(0.0),
             bb_lower: self.bb.lower.get(idx).copied().unwrap_or(0.0),
-            vwap: None,
+            vwap: self.rolling_vwap.get(idx).copied().flatten(),
+            avg_volume: None,
+            bars_held: None,
+            anchored_vwap: self.anchored_vwap.get(idx).copied().flatten(),
+            roc: self.roc.get(idx).copied().flatten(),
+            roc_percentile: self.roc_percentile.get(idx).copied().flatten(),
+            lookback_roc: self.lookback_roc.get(idx).copied().flatten(),
+            close_zscore: self.close_zscore.get(idx).copied().flatten(),
+            log_return: self.log_return.get(idx).copied().flatten(),
             prev_high: None,
             prev_low: None,
+            macd: macd(|m| &m.macd),
+            macd_signal: macd(|m| &m.signal),
+            macd_histogram: macd(|m| &m.histogram),
+            stoch_k: stochastic(|s| &s.k),
+            stoch_d: stochastic(|s| &s.d),
+            adx: adx(|a| &a.adx),
+            plus_di: adx(|a| &a.plus_di),
+            minus_di: adx(|a| &a.minus_di),
+            obv: self.obv.get(idx).copied(),
+            obv_slope: self.obv_slope.get(idx).copied().flatten(),
+            mfi: self.mfi.get(idx).copied().flatten(),
+            williams_r: self.williams_r.get(idx).copied().flatten(),
+            cci: self.cci.get(idx).copied().flatten(),
+            keltner_upper: keltner(|k| &k.upper),
+            keltner_middle: keltner(|k| &k.middle),
+            keltner_lower: keltner(|k| &k.lower),
+            donchian_upper: donchian(|d| &d.upper, Some(idx)),
+            donchian_middle: donchian(|d| &d.middle, Some(idx)),
+            donchian_lower: donchian(|d| &d.lower, Some(idx)),
+            prev_donchian_upper: donchian(|d| &d.upper, idx.checked_sub(1)),
+            prev_donchian_lower: donchian(|d| &d.lower, idx.checked_sub(1)),
+            reseeding: self.reseeding.get(idx).copied().unwrap_or(false),
+            warming_up: !self.is_warm(idx),
+            anomaly: self.anomaly.get(idx).copied().unwrap_or(false),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+
+    #[test]
+    fn test_bar_wrappers_match_slices() {
+        let bars = generate_synthetic_bars_seeded(120, 100.0, 3);
+        let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+
+        assert_eq!(rsi_of_bars(&bars, 2), calculate_rsi(&closes, 2));
+        assert_eq!(
+            atr_of_bars(&bars, 14),
+            calculate_atr(&highs, &lows, &closes, 14)
+        );
+        let bb = bollinger_of_bars(&bars, 20, 2.0);
+        let expected = calculate_bollinger_bands(&closes, 20, 2.0);
+        assert_eq!(bb.upper, expected.upper);
+        assert_eq!(bb.lower, expected.lower);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_from_bars_matches_positional() {
+        let bars = generate_synthetic_bars_seeded(120, 100.0, 3);
+        let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        // Distinct periods so that a swapped argument shows up
+        let config = IndicatorConfig {
+            rsi_period: 3,
+            sma_period: 10,
+            bb_period: 15,
+            bb_std_dev: 1.5,
+            atr_period: 7,
+            rsi_smoothing: Smoothing::Wilder,
+            atr_smoothing: Smoothing::Wilder,
+            rsi_reset_on_gap_pct: None,
+        };
+
+        let series = IndicatorSeries::from_bars(&bars, &config);
+        let positional = IndicatorSeries::calculate(&closes, &highs, &lows, 3, 10, 15, 1.5, 7);
+        assert_eq!(series.rsi, positional.rsi);
+        assert_eq!(series.sma, positional.sma);
+        assert_eq!(series.ema, positional.ema);
+        assert_eq!(series.atr, positional.atr);
+        assert_eq!(series.bb.middle, positional.bb.middle);
+        assert_eq!(series.sma, calculate_sma(&closes, 10));
+    }
+
+    #[test]
+    fn test_macd_values_at_index() {
+        let bars = generate_synthetic_bars_seeded(60, 100.0, 3);
+        let series = IndicatorSeries::from_bars(&bars, &IndicatorConfig::default());
+        assert_eq!(series.get(59).macd_histogram, None);
+
+        let series = series.with_macd(&closes_of(&bars), 12, 26, 9);
+        let macd = calculate_macd(&closes_of(&bars), 12, 26, 9);
+        assert_eq!(series.get(25).macd, macd.macd[25]);
+        assert_eq!(series.get(32).macd_histogram, None);
+        assert_eq!(series.get(33).macd_signal, macd.signal[33]);
+        assert_eq!(series.get(59).macd_histogram, macd.histogram[59]);
+        assert!(series.get(59).macd_histogram.is_some());
+        assert_eq!(series.get(60).macd, None);
+    }
+
+    #[test]
+    fn test_stochastic_values_at_index() {
+        let bars = generate_synthetic_bars_seeded(60, 100.0, 3);
+        let series = IndicatorSeries::from_bars(&bars, &IndicatorConfig::default())
+            .with_stochastic(&bars, 14, 3, 3);
+        let highs: Vec<f64> = bars.iter().map(|b| b.high).collect();
+        let lows: Vec<f64> = bars.iter().map(|b| b.low).collect();
+        let stoch = calculate_stochastic(&highs, &lows, &closes_of(&bars), 14, 3, 3);
+
+        assert_eq!(series.get(14).stoch_k, None);
+        assert_eq!(series.get(15).stoch_k, stoch.k[15]);
+        assert_eq!(series.get(16).stoch_d, None);
+        assert_eq!(series.get(59).stoch_d, stoch.d[59]);
+        assert!(series.get(59).stoch_d.is_some());
+    }
+
+    #[test]
+    fn test_mfi_only_when_requested() {
+        let bars = generate_synthetic_bars_seeded(60, 100.0, 3);
+        let series = IndicatorSeries::from_bars(&bars, &IndicatorConfig::default());
+        assert!(series.mfi.is_empty());
+        assert_eq!(series.get(59).mfi, None);
+
+        let series = series.with_mfi(&bars, MFI_PERIOD);
+        assert_eq!(series.mfi.len(), bars.len());
+        assert_eq!(series.get(MFI_PERIOD - 1).mfi, None);
+        let mfi = series.get(59).mfi.unwrap();
+        assert!((0.0..=100.0).contains(&mfi));
+    }
+
+    #[test]
+    fn test_oscillators_only_when_requested() {
+        let bars = generate_synthetic_bars_seeded(60, 100.0, 3);
+        let series = IndicatorSeries::from_bars(&bars, &IndicatorConfig::default());
+        assert_eq!(series.get(59).williams_r, None);
+        assert_eq!(series.get(59).cci, None);
+
+        let series = series
+            .with_williams_r(&bars, WILLIAMS_R_PERIOD)
+            .with_cci(&bars, CCI_PERIOD);
+        assert_eq!(series.get(WILLIAMS_R_PERIOD - 2).williams_r, None);
+        assert_eq!(series.get(CCI_PERIOD - 2).cci, None);
+        let values = series.get(59);
+        assert!((-100.0..=0.0).contains(&values.williams_r.unwrap()));
+        assert!(values.cci.unwrap().is_finite());
+    }
+
+    #[test]
+    fn test_donchian_breakout_from_values() {
+        let bars = generate_synthetic_bars_seeded(60, 100.0, 3);
+        let series = IndicatorSeries::from_bars(&bars, &IndicatorConfig::default());
+        assert_eq!(series.get(59).donchian_upper, None);
+
+        let series = series.with_donchian(&bars, DONCHIAN_PERIOD);
+        assert_eq!(series.get(DONCHIAN_PERIOD - 1).prev_donchian_upper, None);
+        let values = series.get(59);
+        assert_eq!(values.prev_donchian_upper, series.get(58).donchian_upper);
+        assert!(values.donchian_lower <= values.donchian_middle);
+        assert_eq!(
+            is_breakout_up(bars[59].close, values.prev_donchian_upper),
+            bars[59].close > values.prev_donchian_upper.unwrap()
+        );
+    }
+
+    #[test]
+    fn test_warm_once_rsi_atr_and_bands_have_values() {
+        let bars = generate_synthetic_bars_seeded(40, 100.0, 5);
+        let config = IndicatorConfig {
+            rsi_period: 25,
+            ..IndicatorConfig::default()
+        };
+        let series = IndicatorSeries::from_bars(&bars, &config);
+
+        // RSI's first value is at its period, after ATR's and the bands'
+        assert_eq!(series.warm_from, 25);
+        assert!(!series.is_warm(24) && series.get(24).warming_up);
+        assert_eq!(series.rsi[24], 50.0);
+        assert!(series.is_warm(25) && !series.get(25).warming_up);
+        assert!(!series.is_warm(40));
+
+        let series = IndicatorSeries::from_bars(&bars, &IndicatorConfig::default());
+        assert_eq!(series.warm_from, config.bb_period - 1);
+    }
+
+    #[test]
+    fn test_gap_reset_marks_reseeding_bars() {
+        let mut bars = generate_synthetic_bars_seeded(80, 100.0, 5);
+        for bar in &mut bars[40..] {
+            bar.open *= 0.6;
+            bar.high *= 0.6;
+            bar.low *= 0.6;
+            bar.close *= 0.6;
+        }
+        let config = IndicatorConfig {
+            rsi_period: 2,
+            atr_period: 14,
+            rsi_reset_on_gap_pct: Some(25.0),
+            ..IndicatorConfig::default()
+        };
+        let series = IndicatorSeries::from_bars(&bars, &config);
+
+        assert_eq!(gap_reset_starts(&closes_of(&bars), Some(25.0)), vec![0, 40]);
+        let reseeding: Vec<usize> = (0..bars.len())
+            .filter(|&i| series.get(i).reseeding)
+            .collect();
+        assert_eq!(reseeding, (40..53).collect::<Vec<_>>());
+
+        // Both restart as if the data began at the gap
+        let fresh = IndicatorSeries::from_bars(&bars[40..], &config);
+        assert_eq!(series.rsi[40..], fresh.rsi[..]);
+        assert_eq!(series.atr[40..], fresh.atr[..]);
+
+        let plain = IndicatorSeries::from_bars(&bars, &IndicatorConfig::default());
+        assert!(plain.reseeding.is_empty());
+    }
+}
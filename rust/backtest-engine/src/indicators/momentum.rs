@@ -0,0 +1,42 @@
+/// Calculate momentum: the price change over `lookback` bars, in price
+/// units (see [`calculate_roc`](super::calculate_roc) for the percentage)
+///
+/// # Returns
+/// Vector of Option<f64>, None for the first `lookback` bars or throughout
+/// for a zero lookback
+pub fn calculate_momentum(prices: &[f64], lookback: usize) -> Vec<Option<f64>> {
+    let mut momentum = vec![None; prices.len()];
+    if lookback == 0 {
+        return momentum;
+    }
+
+    for i in lookback..prices.len() {
+        momentum[i] = Some(prices[i] - prices[i - lookback]);
+    }
+    momentum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indicators::calculate_roc;
+
+    #[test]
+    fn test_momentum_matches_roc() {
+        let prices = [100.0, 102.0, 99.0, 110.0, 88.0];
+        let momentum = calculate_momentum(&prices, 2);
+        assert_eq!(
+            momentum,
+            vec![None, None, Some(-1.0), Some(8.0), Some(-11.0)]
+        );
+
+        // The same change as a share of the price `lookback` bars back
+        let roc = calculate_roc(&prices, 2);
+        for i in 2..prices.len() {
+            let pct = momentum[i].unwrap() / prices[i - 2] * 100.0;
+            assert!((roc[i].unwrap() - pct).abs() < 1e-9);
+        }
+        assert!(calculate_momentum(&prices, 0).iter().all(|m| m.is_none()));
+        assert_eq!(calculate_momentum(&prices[..2], 3), vec![None, None]);
+    }
+}
@@ -0,0 +1,117 @@
+//! Normalized price inputs for custom strategies
+//!
+//! Each output at index i depends only on prices at indices ≤ i, so the
+//! series can be fed to a strategy bar by bar without lookahead.
+
+/// Rolling z-score of each price against the trailing `window` prices,
+/// itself included (population standard deviation).
+///
+/// None until `window` prices are available and on flat windows.
+pub fn zscore(prices: &[f64], window: usize) -> Vec<Option<f64>> {
+    let mut scores = vec![None; prices.len()];
+    if window < 2 {
+        return scores;
+    }
+
+    for i in window - 1..prices.len() {
+        let trailing = &prices[i + 1 - window..=i];
+        let mean = trailing.iter().sum::<f64>() / window as f64;
+        let variance = trailing.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / window as f64;
+        let std_dev = variance.sqrt();
+        if std_dev > 0.0 {
+            scores[i] = Some((prices[i] - mean) / std_dev);
+        }
+    }
+    scores
+}
+
+/// Log return from the previous price; None for the first price and after
+/// non-positive prices
+pub fn log_returns(prices: &[f64]) -> Vec<Option<f64>> {
+    let mut returns = vec![None; prices.len()];
+    for i in 1..prices.len() {
+        if prices[i - 1] > 0.0 && prices[i] > 0.0 {
+            returns[i] = Some((prices[i] / prices[i - 1]).ln());
+        }
+    }
+    returns
+}
+
+/// Position (0-1) of each price between the low and high of the trailing
+/// `window` prices, itself included.
+///
+/// None until `window` prices are available and on flat windows.
+pub fn min_max_scaled(prices: &[f64], window: usize) -> Vec<Option<f64>> {
+    let mut scaled = vec![None; prices.len()];
+    if window == 0 {
+        return scaled;
+    }
+
+    for i in window - 1..prices.len() {
+        let trailing = &prices[i + 1 - window..=i];
+        let low = trailing.iter().copied().fold(f64::INFINITY, f64::min);
+        let high = trailing.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if high > low {
+            scaled[i] = Some((prices[i] - low) / (high - low));
+        }
+    }
+    scaled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn test_normalized_values() {
+        let prices = [1.0, 2.0, 3.0, 3.0, 3.0];
+
+        let z = zscore(&prices, 3);
+        assert_eq!(z[..2], [None, None]);
+        assert!((z[2].unwrap() - 1.224744871391589).abs() < 1e-12);
+        assert_eq!(z[4], None); // flat window
+
+        let r = log_returns(&prices);
+        assert_eq!(r[0], None);
+        assert!((r[1].unwrap() - 2f64.ln()).abs() < 1e-12);
+        assert_eq!(r[3], Some(0.0));
+
+        let m = min_max_scaled(&prices, 3);
+        assert_eq!(m, vec![None, None, Some(1.0), Some(1.0), None]);
+    }
+
+    /// Perturbing the price at i + 1 (and everything after it) must leave
+    /// every output at indices ≤ i unchanged, for every i
+    #[test]
+    fn test_no_lookahead() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+        let prices: Vec<f64> = (0..120).map(|_| 50.0 + rng.gen_range(-5.0..5.0)).collect();
+
+        let outputs = |prices: &[f64]| {
+            vec![
+                zscore(prices, 10),
+                log_returns(prices),
+                min_max_scaled(prices, 10),
+            ]
+        };
+        let original = outputs(&prices);
+
+        for i in 0..prices.len() - 1 {
+            let mut perturbed = prices.clone();
+            for price in &mut perturbed[i + 1..] {
+                *price *= rng.gen_range(0.5..1.5);
+            }
+            perturbed[i + 1] += 10.0;
+
+            for (before, after) in original.iter().zip(outputs(&perturbed)) {
+                assert_eq!(
+                    before[..=i],
+                    after[..=i],
+                    "output changed at or before {}",
+                    i
+                );
+            }
+        }
+    }
+}
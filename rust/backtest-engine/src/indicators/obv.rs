@@ -0,0 +1,82 @@
+/// Bars in the OBV slope behind the entry filter
+pub const OBV_SLOPE_LOOKBACK: usize = 10;
+
+/// Calculate On-Balance Volume: the running sum of volume, added on up
+/// closes and subtracted on down closes
+///
+/// The first bar has no previous close and starts the sum at 0; unchanged
+/// closes and zero-volume bars leave it where it is.
+pub fn calculate_obv(closes: &[f64], volumes: &[u64]) -> Vec<f64> {
+    let n = closes.len().min(volumes.len());
+    let mut obv = Vec::with_capacity(n);
+    let mut total = 0.0;
+    for i in 0..n {
+        if i > 0 {
+            let volume = volumes[i] as f64;
+            if closes[i] > closes[i - 1] {
+                total += volume;
+            } else if closes[i] < closes[i - 1] {
+                total -= volume;
+            }
+        }
+        obv.push(total);
+    }
+    obv
+}
+
+/// Least-squares slope of OBV per bar over the `lookback` bars ending at
+/// each bar
+///
+/// # Returns
+/// None until `lookback` bars are available, or throughout for a
+/// `lookback` below 2
+pub fn obv_slope(obv: &[f64], lookback: usize) -> Vec<Option<f64>> {
+    let mut slopes = vec![None; obv.len()];
+    if lookback < 2 {
+        return slopes;
+    }
+
+    let n = lookback as f64;
+    let x_mean = (n - 1.0) / 2.0;
+    let x_var: f64 = (0..lookback).map(|x| (x as f64 - x_mean).powi(2)).sum();
+    for (end, window) in obv.windows(lookback).enumerate() {
+        let y_mean = window.iter().sum::<f64>() / n;
+        let covariance: f64 = window
+            .iter()
+            .enumerate()
+            .map(|(x, y)| (x as f64 - x_mean) * (y - y_mean))
+            .sum();
+        slopes[end + lookback - 1] = Some(covariance / x_var);
+    }
+    slopes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_obv_edge_cases() {
+        let closes = [10.0, 11.0, 11.0, 10.5, 12.0, 11.0];
+        let volumes = [500, 100, 300, 200, 0, 50];
+        // First bar 0, unchanged close and zero volume hold the sum
+        assert_eq!(
+            calculate_obv(&closes, &volumes),
+            vec![0.0, 100.0, 100.0, -100.0, -100.0, -150.0]
+        );
+        assert!(calculate_obv(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_obv_slope() {
+        let rising: Vec<f64> = (0..6).map(|i| 100.0 * i as f64).collect();
+        let slopes = obv_slope(&rising, 3);
+        assert_eq!(&slopes[..2], &[None, None]);
+        assert!(slopes[2..].iter().all(|s| *s == Some(100.0)));
+
+        let falling: Vec<f64> = rising.iter().map(|v| -v).collect();
+        assert_eq!(obv_slope(&falling, 4)[5], Some(-100.0));
+        assert!(obv_slope(&rising, 1).iter().all(|s| s.is_none()));
+        assert!(obv_slope(&rising[..2], 3).iter().all(|s| s.is_none()));
+    }
+}
@@ -0,0 +1,84 @@
+/// Bars in the rolling window ROC is ranked against (one trading year)
+pub const ROC_RANK_WINDOW: usize = 252;
+
+/// Calculate Rate of Change: percentage change over `period` bars
+///
+/// # Returns
+/// Vector of Option<f64>, None for the first `period` bars
+pub fn calculate_roc(closes: &[f64], period: usize) -> Vec<Option<f64>> {
+    let mut roc = vec![None; closes.len()];
+    if period == 0 {
+        return roc;
+    }
+
+    for i in period..closes.len() {
+        let base = closes[i - period];
+        if base != 0.0 {
+            roc[i] = Some((closes[i] / base - 1.0) * 100.0);
+        }
+    }
+    roc
+}
+
+/// Rolling percentile rank (0-100) of each value within the trailing
+/// `window` values, itself included. Ties count half.
+///
+/// None until `window` consecutive values are available.
+pub fn calculate_percentile_rank(values: &[Option<f64>], window: usize) -> Vec<Option<f64>> {
+    let mut ranks = vec![None; values.len()];
+    if window == 0 {
+        return ranks;
+    }
+
+    for i in window.saturating_sub(1)..values.len() {
+        let Some(current) = values[i] else {
+            continue;
+        };
+        let trailing = &values[i + 1 - window..=i];
+        if trailing.iter().any(|v| v.is_none()) {
+            continue;
+        }
+
+        let (mut below, mut ties) = (0.0, 0.0);
+        for v in trailing.iter().flatten() {
+            if *v < current {
+                below += 1.0;
+            } else if *v == current {
+                ties += 1.0;
+            }
+        }
+        ranks[i] = Some((below + ties / 2.0) / window as f64 * 100.0);
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roc() {
+        let closes = vec![100.0, 110.0, 99.0, 121.0];
+        let roc = calculate_roc(&closes, 2);
+
+        assert_eq!(roc[0], None);
+        assert_eq!(roc[1], None);
+        assert!((roc[2].unwrap() - -1.0).abs() < 1e-9);
+        assert!((roc[3].unwrap() - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_percentile_rank() {
+        let values = vec![None, Some(3.0), Some(1.0), Some(2.0), Some(5.0), Some(5.0)];
+        let ranks = calculate_percentile_rank(&values, 3);
+
+        // Warmup (including any None inside the window) stays None
+        assert_eq!(&ranks[..3], &[None, None, None]);
+        // 2.0 within [3, 1, 2]: one below, itself ties
+        assert!((ranks[3].unwrap() - 50.0).abs() < 1e-9);
+        // 5.0 within [1, 2, 5]: new high
+        assert!((ranks[4].unwrap() - 250.0 / 3.0).abs() < 1e-9);
+        // 5.0 within [2, 5, 5]: tied with the previous bar
+        assert!((ranks[5].unwrap() - 200.0 / 3.0).abs() < 1e-9);
+    }
+}
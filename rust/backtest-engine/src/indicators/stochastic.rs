@@ -0,0 +1,114 @@
+use serde::{Deserialize, Serialize};
+
+use super::calculate_sma;
+
+/// Stochastic oscillator lines (0-100), aligned with the input closes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StochasticResult {
+    /// %K: the close's place in the `k_period` high-low range, smoothed
+    /// over `k_smooth` bars
+    pub k: Vec<Option<f64>>,
+    /// %D: SMA of %K over `d_period` bars
+    pub d: Vec<Option<f64>>,
+}
+
+/// Calculate the stochastic oscillator (14, 3, 3 being the usual slow
+/// stochastic; a `k_smooth` of 1 gives the fast one)
+///
+/// A window whose highest high equals its lowest low has no range to place
+/// the close in; its raw %K is 50.
+///
+/// # Returns
+/// StochasticResult with None for warmup bars, or throughout for a zero
+/// period or input shorter than `k_period`
+pub fn calculate_stochastic(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    k_period: usize,
+    k_smooth: usize,
+    d_period: usize,
+) -> StochasticResult {
+    let mut result = StochasticResult {
+        k: vec![None; closes.len()],
+        d: vec![None; closes.len()],
+    };
+    let n = closes.len().min(highs.len()).min(lows.len());
+    if k_period == 0 || k_smooth == 0 || d_period == 0 || n < k_period {
+        return result;
+    }
+
+    let start = k_period - 1;
+    let raw: Vec<f64> = (start..n)
+        .map(|i| {
+            let window = i + 1 - k_period..=i;
+            let high = highs[window.clone()]
+                .iter()
+                .copied()
+                .fold(f64::MIN, f64::max);
+            let low = lows[window].iter().copied().fold(f64::MAX, f64::min);
+            if high > low {
+                (closes[i] - low) / (high - low) * 100.0
+            } else {
+                50.0
+            }
+        })
+        .collect();
+
+    let k = calculate_sma(&raw, k_smooth);
+    let k_start = k_smooth - 1;
+    let smoothed: Vec<f64> = k.iter().flatten().copied().collect();
+    let d = calculate_sma(&smoothed, d_period);
+    for (i, value) in k.into_iter().enumerate() {
+        result.k[start + i] = value;
+    }
+    for (i, value) in d.into_iter().enumerate() {
+        result.d[start + k_start + i] = value;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stochastic_values() {
+        let highs = [10.0, 12.0, 11.0, 13.0, 14.0, 12.0];
+        let lows = [8.0, 9.0, 9.0, 10.0, 11.0, 10.0];
+        let closes = [9.0, 11.0, 10.0, 12.0, 14.0, 10.0];
+        let stoch = calculate_stochastic(&highs, &lows, &closes, 3, 1, 2);
+
+        assert_eq!(stoch.k.len(), closes.len());
+        assert_eq!(&stoch.k[..2], &[None, None]);
+        // Bar 2: range 8..12, close 10
+        assert_eq!(stoch.k[2], Some(50.0));
+        // Bar 4: range 9..14, close at the high
+        assert_eq!(stoch.k[4], Some(100.0));
+        assert_eq!(stoch.d[2], None);
+        let (k2, k3) = (stoch.k[2].unwrap(), stoch.k[3].unwrap());
+        assert_eq!(stoch.d[3], Some((k2 + k3) / 2.0));
+
+        // Slow: %K is the 2-bar mean of the raw values above
+        let slow = calculate_stochastic(&highs, &lows, &closes, 3, 2, 2);
+        assert_eq!(slow.k[2], None);
+        assert_eq!(slow.k[3], Some((k2 + k3) / 2.0));
+        assert_eq!(slow.d[3], None);
+        assert!(slow.d[4].is_some());
+    }
+
+    #[test]
+    fn test_stochastic_flat_and_short_input() {
+        let flat = [5.0; 6];
+        let stoch = calculate_stochastic(&flat, &flat, &flat, 3, 1, 3);
+        assert!(stoch.k[2..].iter().all(|k| *k == Some(50.0)));
+
+        let short = calculate_stochastic(&[1.0, 2.0], &[0.5, 1.0], &[1.0, 1.5], 14, 3, 3);
+        assert_eq!(short.k, vec![None, None]);
+        assert_eq!(short.d, vec![None, None]);
+        assert!(calculate_stochastic(&flat, &flat, &flat, 3, 0, 3)
+            .k
+            .iter()
+            .all(|k| k.is_none()));
+    }
+}
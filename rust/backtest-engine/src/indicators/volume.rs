@@ -0,0 +1,19 @@
+use super::sma::calculate_sma;
+
+/// Trailing average volume over `period` bars, ending at (and including)
+/// each bar. None until `period` bars are available.
+pub fn calculate_avg_volume(volumes: &[u64], period: usize) -> Vec<Option<f64>> {
+    let volumes: Vec<f64> = volumes.iter().map(|&v| v as f64).collect();
+    calculate_sma(&volumes, period)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_avg_volume() {
+        let avg = calculate_avg_volume(&[100, 200, 300, 600], 3);
+        assert_eq!(avg, vec![None, None, Some(200.0), Some(366.6666666666667)]);
+    }
+}
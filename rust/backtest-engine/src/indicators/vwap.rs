@@ -0,0 +1,170 @@
+use chrono::Datelike;
+use common::Bar;
+
+/// Usual rolling VWAP window, in bars
+pub const VWAP_WINDOW: usize = 20;
+
+/// Calculate a rolling VWAP: the volume-weighted mean of the typical price
+/// `(high + low + close) / 3` over the `window` bars ending at each bar,
+/// for data without a VWAP of its own
+///
+/// # Returns
+/// Values aligned with the input closes, None for the first `window - 1`
+/// bars, for windows without volume, or throughout for a zero window
+pub fn calculate_rolling_vwap(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    volumes: &[u64],
+    window: usize,
+) -> Vec<Option<f64>> {
+    let mut vwap = vec![None; closes.len()];
+    let n = closes
+        .len()
+        .min(highs.len())
+        .min(lows.len())
+        .min(volumes.len());
+    if window == 0 || n < window {
+        return vwap;
+    }
+
+    let weighted: Vec<(f64, f64)> = (0..n)
+        .map(|i| {
+            let volume = volumes[i] as f64;
+            ((highs[i] + lows[i] + closes[i]) / 3.0 * volume, volume)
+        })
+        .collect();
+    for (i, value) in vwap.iter_mut().enumerate().take(n).skip(window - 1) {
+        let (dollars, volume) = weighted[i + 1 - window..=i]
+            .iter()
+            .fold((0.0, 0.0), |(d, v), (wd, wv)| (d + wd, v + wv));
+        if volume > 0.0 {
+            *value = Some(dollars / volume);
+        }
+    }
+    vwap
+}
+
+/// Calculate the VWAP anchored at bar `anchor`: the volume-weighted mean of
+/// the typical price from the anchor through each bar
+///
+/// # Returns
+/// Values aligned with `bars`, None before the anchor and until volume
+/// trades after it
+pub fn calculate_anchored_vwap(bars: &[Bar], anchor: usize) -> Vec<Option<f64>> {
+    anchored(bars, |i| i == anchor)
+}
+
+/// Calculate the VWAP anchored afresh at the first bar of each calendar
+/// month (UTC). A month without bars has no anchor of its own: the first
+/// bar after the gap anchors its month.
+pub fn calculate_monthly_vwap(bars: &[Bar]) -> Vec<Option<f64>> {
+    let month = |bar: &Bar| (bar.timestamp.year(), bar.timestamp.month());
+    anchored(bars, |i| i == 0 || month(&bars[i]) != month(&bars[i - 1]))
+}
+
+/// Cumulative VWAP restarted on every bar `is_anchor` picks
+fn anchored(bars: &[Bar], is_anchor: impl Fn(usize) -> bool) -> Vec<Option<f64>> {
+    let mut vwap = vec![None; bars.len()];
+    // Dollar volume and volume since the last anchor, None before the first
+    let mut totals: Option<(f64, f64)> = None;
+    for (i, bar) in bars.iter().enumerate() {
+        if is_anchor(i) {
+            totals = Some((0.0, 0.0));
+        }
+        if let Some((dollars, volume)) = totals.as_mut() {
+            let typical = (bar.high + bar.low + bar.close) / 3.0;
+            *dollars += typical * bar.volume as f64;
+            *volume += bar.volume as f64;
+            if *volume > 0.0 {
+                vwap[i] = Some(*dollars / *volume);
+            }
+        }
+    }
+    vwap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn bar_on(month: u32, day: u32, close: f64, volume: u64) -> Bar {
+        Bar {
+            timestamp: Utc.with_ymd_and_hms(2024, month, day, 16, 0, 0).unwrap(),
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume,
+            vwap: None,
+            halted: false,
+        }
+    }
+
+    #[test]
+    fn test_rolling_vwap() {
+        let closes = [10.0, 11.0, 12.0, 13.0];
+        let highs: Vec<f64> = closes.iter().map(|c| c + 1.0).collect();
+        let lows: Vec<f64> = closes.iter().map(|c| c - 1.0).collect();
+        let vwap = calculate_rolling_vwap(&highs, &lows, &closes, &[100, 300, 100, 0], 2);
+
+        assert_eq!(vwap[0], None);
+        assert_eq!(vwap[1], Some((10.0 * 100.0 + 11.0 * 300.0) / 400.0));
+        assert_eq!(vwap[2], Some((11.0 * 300.0 + 12.0 * 100.0) / 400.0));
+        // An idle bar weighs nothing
+        assert_eq!(vwap[3], Some(12.0));
+    }
+
+    #[test]
+    fn test_rolling_vwap_without_volume_or_bars() {
+        let closes = [10.0; 5];
+        assert_eq!(
+            calculate_rolling_vwap(&closes, &closes, &closes, &[0; 5], 3),
+            vec![None; 5]
+        );
+        assert_eq!(
+            calculate_rolling_vwap(&closes, &closes, &closes, &[100; 5], 6),
+            vec![None; 5]
+        );
+        assert!(
+            calculate_rolling_vwap(&closes, &closes, &closes, &[100; 5], 0)
+                .iter()
+                .all(|v| v.is_none())
+        );
+    }
+
+    #[test]
+    fn test_anchored_vwap() {
+        let bars = [
+            bar_on(1, 2, 10.0, 100),
+            bar_on(1, 3, 20.0, 0),
+            bar_on(1, 4, 30.0, 100),
+            bar_on(1, 5, 40.0, 300),
+        ];
+        let vwap = calculate_anchored_vwap(&bars, 1);
+        // Nothing before the anchor, nor until volume trades at it
+        assert_eq!(&vwap[..2], &[None, None]);
+        assert_eq!(vwap[2], Some(30.0));
+        assert_eq!(vwap[3], Some((30.0 * 100.0 + 40.0 * 300.0) / 400.0));
+        assert_eq!(calculate_anchored_vwap(&bars, 9), vec![None; 4]);
+    }
+
+    #[test]
+    fn test_monthly_vwap_reanchors_across_gaps() {
+        // No bars in February: March's first bar anchors March
+        let bars = [
+            bar_on(1, 30, 10.0, 100),
+            bar_on(1, 31, 20.0, 100),
+            bar_on(3, 4, 50.0, 100),
+            bar_on(3, 5, 60.0, 300),
+            bar_on(4, 1, 70.0, 100),
+        ];
+        let vwap = calculate_monthly_vwap(&bars);
+        assert_eq!(&vwap[..2], &[Some(10.0), Some(15.0)]);
+        assert_eq!(&vwap[2..4], &calculate_anchored_vwap(&bars[..4], 2)[2..]);
+        assert_eq!(vwap[3], Some(57.5));
+        assert_eq!(vwap[4], Some(70.0));
+        assert!(calculate_monthly_vwap(&[]).is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+/// Usual Williams %R period
+pub const WILLIAMS_R_PERIOD: usize = 14;
+
+/// Calculate Williams %R (-100 to 0): where the close sits below the
+/// highest high of the last `period` bars, as a share of their range
+///
+/// -100 is a close at the lowest low, 0 one at the highest high; a window
+/// with no range reads -50.
+///
+/// # Returns
+/// Values aligned with the input closes, None for the first `period - 1`
+/// bars or throughout for a zero period
+pub fn calculate_williams_r(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    period: usize,
+) -> Vec<Option<f64>> {
+    let mut values = vec![None; closes.len()];
+    let n = closes.len().min(highs.len()).min(lows.len());
+    if period == 0 || n < period {
+        return values;
+    }
+
+    for end in period - 1..n {
+        let window = end + 1 - period..=end;
+        let high = highs[window.clone()]
+            .iter()
+            .copied()
+            .fold(f64::MIN, f64::max);
+        let low = lows[window].iter().copied().fold(f64::MAX, f64::min);
+        values[end] = Some(if high > low {
+            (high - closes[end]) / (high - low) * -100.0
+        } else {
+            -50.0
+        });
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_williams_r() {
+        let highs = [11.0, 12.0, 13.0, 12.0, 10.0];
+        let lows = [9.0, 10.0, 11.0, 10.0, 8.0];
+        let closes = [10.0, 12.0, 11.0, 10.0, 8.0];
+        let values = calculate_williams_r(&highs, &lows, &closes, 3);
+
+        assert_eq!(values.len(), closes.len());
+        assert_eq!(&values[..2], &[None, None]);
+        // Window 9..13, close 11
+        assert_eq!(values[2], Some(-50.0));
+        // Window 8..13, close at the low
+        assert_eq!(values[4], Some(-100.0));
+    }
+
+    #[test]
+    fn test_williams_r_flat_and_short() {
+        let flat = [5.0; 6];
+        let values = calculate_williams_r(&flat, &flat, &flat, 3);
+        assert!(values[2..].iter().all(|v| *v == Some(-50.0)));
+        assert_eq!(
+            calculate_williams_r(&flat[..2], &flat[..2], &flat[..2], 3),
+            vec![None; 2]
+        );
+        assert!(calculate_williams_r(&flat, &flat, &flat, 0)
+            .iter()
+            .all(|v| v.is_none()));
+    }
+}
@@ -1,20 +1,55 @@
+pub mod analysis;
+pub mod config;
 pub mod data;
 pub mod engine;
 pub mod execution;
 pub mod indicators;
+pub mod limits;
+pub mod live;
 pub mod metrics;
+pub mod ml;
+pub mod multi;
+pub mod optimizer;
+pub mod orders;
+pub mod output;
 pub mod portfolio;
+pub mod pretrade;
+pub mod report;
+pub mod results;
+pub mod risk;
+pub mod runner;
 pub mod signals;
+pub mod sink;
+pub mod validation;
 
-pub use data::{generate_synthetic_bars, load_file};
+pub use config::{load_multi_config, load_parameters, load_parameters_onto};
+pub use data::{
+    generate_synthetic_bars, generate_synthetic_bars_seeded, load_cash_flows, load_file,
+    load_files, load_files_with, load_trades, LoadedFile,
+};
 pub use engine::BacktestEngine;
 pub use execution::{ExecutionResult, ExecutionSimulator, PriceAdjustments};
+pub use limits::{JobCompletion, JobLimits};
 pub use metrics::MetricsCalculator;
-pub use portfolio::Portfolio;
-pub use signals::SignalGenerator;
+pub use multi::MultiSymbolConfig;
+pub use optimizer::{
+    grid_search, grid_search_two_phase, grid_search_with_limits, OptimizationResult, ParameterGrid,
+    RankBy, ScoreSpec, TwoPhase,
+};
+pub use orders::{BracketEvent, BracketOrder, OrderManager};
+pub use output::Overwrite;
+pub use portfolio::{Portfolio, SyntheticShort};
+pub use pretrade::{
+    MaxNotionalCheck, MaxPositionsCheck, PortfolioSnapshot, PreTradeCheck, PreTradeChecks,
+    PreTradeDecision, ProposedOrder,
+};
+pub use results::{EquityPoint, ResultView, TradeView};
+pub use runner::{BacktestRunner, VerificationReport};
+pub use signals::{FilterCheck, LatestEvaluation, PositionState, SignalGenerator};
+pub use sink::{EquityRecord, JsonlSink, ResultSink};
 
 // Re-export common types
 pub use common::{
     BacktestError, BacktestParameters, BacktestResult, Bar, PerformanceMetrics, Position,
-    PositionSide, Result, Side, Signal, SignalType, Trade,
+    PositionSide, Preset, Result, Side, Signal, SignalType, SuppressedSignal, Trade,
 };
@@ -0,0 +1,123 @@
+//! Budgets for jobs made of many backtests
+//!
+//! A grid search or random baseline runs its backtests on rayon and checks
+//! a shared [`Budget`] before starting each one. Once a limit is hit no
+//! further run starts, runs already going finish, and the job returns what
+//! completed with [`JobCompletion::truncated`] set.
+
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Hard limits on a job; unlimited by default
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct JobLimits {
+    /// Wall-clock time after which no new backtest starts
+    pub max_wall_clock: Option<Duration>,
+    /// Backtests to run at most
+    pub max_backtests: Option<usize>,
+}
+
+impl JobLimits {
+    pub fn with_max_wall_clock(mut self, limit: Duration) -> Self {
+        self.max_wall_clock = Some(limit);
+        self
+    }
+
+    pub fn with_max_backtests(mut self, limit: usize) -> Self {
+        self.max_backtests = Some(limit);
+        self
+    }
+
+    /// Start the clock on a job
+    pub(crate) fn start(self) -> Budget {
+        Budget {
+            limits: self,
+            started_at: Instant::now(),
+            claimed: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+        }
+    }
+}
+
+/// How much of a job ran
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobCompletion {
+    /// A limit stopped the job before every planned run
+    pub truncated: bool,
+    pub completed: usize,
+    pub planned: usize,
+}
+
+/// Limits of one running job, shared by its rayon tasks
+#[derive(Debug)]
+pub(crate) struct Budget {
+    limits: JobLimits,
+    started_at: Instant,
+    claimed: AtomicUsize,
+    stopped: AtomicBool,
+}
+
+impl Budget {
+    /// Whether another backtest may start; once one is refused every later
+    /// call is too
+    pub(crate) fn claim(&self) -> bool {
+        if self.stopped.load(Ordering::Relaxed) {
+            return false;
+        }
+        let out_of_time = self
+            .limits
+            .max_wall_clock
+            .is_some_and(|limit| self.started_at.elapsed() >= limit);
+        let claimed = self.claimed.fetch_add(1, Ordering::Relaxed);
+        let out_of_runs = self
+            .limits
+            .max_backtests
+            .is_some_and(|limit| claimed >= limit);
+        if out_of_time || out_of_runs {
+            self.stopped.store(true, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+
+    pub(crate) fn completion(&self, completed: usize, planned: usize) -> JobCompletion {
+        JobCompletion {
+            truncated: self.stopped.load(Ordering::Relaxed),
+            completed,
+            planned,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_budget_stops_for_good() {
+        let budget = JobLimits::default().with_max_backtests(3).start();
+        let claimed: Vec<bool> = (0..5).map(|_| budget.claim()).collect();
+        assert_eq!(claimed, vec![true, true, true, false, false]);
+        assert_eq!(
+            budget.completion(3, 5),
+            JobCompletion {
+                truncated: true,
+                completed: 3,
+                planned: 5
+            }
+        );
+
+        // Exactly the planned runs is not a truncation
+        let budget = JobLimits::default().with_max_backtests(2).start();
+        assert!(budget.claim() && budget.claim());
+        assert!(!budget.completion(2, 2).truncated);
+
+        let budget = JobLimits::default()
+            .with_max_wall_clock(Duration::ZERO)
+            .start();
+        assert!(!budget.claim());
+    }
+}
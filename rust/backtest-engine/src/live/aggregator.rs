@@ -0,0 +1,339 @@
+//! Tick-to-bar aggregation
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, DurationRound, Utc};
+
+use common::Bar;
+
+use super::Tick;
+
+/// Bar boundary for aggregation. Windows are aligned to UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarInterval {
+    Minutes(u32),
+    Daily,
+}
+
+impl BarInterval {
+    fn duration(self) -> Duration {
+        match self {
+            BarInterval::Minutes(n) => Duration::minutes(n.max(1) as i64),
+            BarInterval::Daily => Duration::days(1),
+        }
+    }
+
+    /// Start of the window containing `timestamp`
+    fn window_start(self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        timestamp
+            .duration_trunc(self.duration())
+            .unwrap_or(timestamp)
+    }
+}
+
+/// What to emit for a window without any ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GapFill {
+    /// Emit nothing for the window
+    #[default]
+    Skip,
+    /// Emit a flat, zero-volume bar at the previous close
+    CarryForward,
+}
+
+/// Ticks collected for one window
+#[derive(Debug, Clone)]
+struct Window {
+    open: (DateTime<Utc>, f64),
+    close: (DateTime<Utc>, f64),
+    high: f64,
+    low: f64,
+    volume: u64,
+    notional: f64,
+}
+
+impl Window {
+    fn new(tick: &Tick) -> Self {
+        Self {
+            open: (tick.timestamp, tick.price),
+            close: (tick.timestamp, tick.price),
+            high: tick.price,
+            low: tick.price,
+            volume: tick.size,
+            notional: tick.price * tick.size as f64,
+        }
+    }
+
+    fn add(&mut self, tick: &Tick) {
+        // Late ticks can still become the open; ties keep arrival order
+        if tick.timestamp < self.open.0 {
+            self.open = (tick.timestamp, tick.price);
+        }
+        if tick.timestamp >= self.close.0 {
+            self.close = (tick.timestamp, tick.price);
+        }
+        self.high = self.high.max(tick.price);
+        self.low = self.low.min(tick.price);
+        self.volume += tick.size;
+        self.notional += tick.price * tick.size as f64;
+    }
+
+    fn to_bar(&self, start: DateTime<Utc>) -> Bar {
+        Bar {
+            timestamp: start,
+            open: self.open.1,
+            high: self.high,
+            low: self.low,
+            close: self.close.1,
+            volume: self.volume,
+            vwap: (self.volume > 0).then(|| self.notional / self.volume as f64),
+            halted: false,
+        }
+    }
+}
+
+/// Builds bars from a stream of ticks.
+///
+/// A window is emitted once a tick arrives at or past its end plus the
+/// out-of-order tolerance, so ticks up to `tolerance` late still land in
+/// their own window. Anything later is dropped and counted. Bars are
+/// stamped with their window start.
+#[derive(Debug)]
+pub struct BarAggregator {
+    interval: BarInterval,
+    tolerance: Duration,
+    gap_fill: GapFill,
+    open_windows: BTreeMap<DateTime<Utc>, Window>,
+    /// Latest tick timestamp seen
+    watermark: Option<DateTime<Utc>>,
+    /// Start and close of the last emitted bar
+    last_emitted: Option<(DateTime<Utc>, f64)>,
+    dropped_ticks: usize,
+}
+
+impl BarAggregator {
+    pub fn new(interval: BarInterval) -> Self {
+        Self {
+            interval,
+            tolerance: Duration::zero(),
+            gap_fill: GapFill::Skip,
+            open_windows: BTreeMap::new(),
+            watermark: None,
+            last_emitted: None,
+            dropped_ticks: 0,
+        }
+    }
+
+    /// Accept ticks arriving up to `tolerance` behind the latest one
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn with_gap_fill(mut self, gap_fill: GapFill) -> Self {
+        self.gap_fill = gap_fill;
+        self
+    }
+
+    /// Ticks discarded for arriving too late
+    pub fn dropped_ticks(&self) -> usize {
+        self.dropped_ticks
+    }
+
+    /// Add a tick, returning any bars it completed
+    pub fn push(&mut self, tick: Tick) -> Vec<Bar> {
+        let start = self.interval.window_start(tick.timestamp);
+        let too_late = self
+            .watermark
+            .is_some_and(|w| tick.timestamp < w - self.tolerance)
+            || self.last_emitted.is_some_and(|(last, _)| start <= last);
+        if too_late {
+            self.dropped_ticks += 1;
+            return Vec::new();
+        }
+
+        self.open_windows
+            .entry(start)
+            .and_modify(|w| w.add(&tick))
+            .or_insert_with(|| Window::new(&tick));
+        let watermark = self
+            .watermark
+            .map_or(tick.timestamp, |w| w.max(tick.timestamp));
+        self.watermark = Some(watermark);
+
+        let cutoff = watermark - self.tolerance;
+        self.emit_while(|end| end <= cutoff)
+    }
+
+    /// Emit every remaining window (end of the feed)
+    pub fn flush(&mut self) -> Vec<Bar> {
+        self.emit_while(|_| true)
+    }
+
+    /// Emit windows in order while `ready(window_end)` holds
+    fn emit_while(&mut self, ready: impl Fn(DateTime<Utc>) -> bool) -> Vec<Bar> {
+        let step = self.interval.duration();
+        let mut bars = Vec::new();
+
+        while let Some((&start, _)) = self.open_windows.first_key_value() {
+            if !ready(start + step) {
+                break;
+            }
+            let window = self.open_windows.remove(&start).expect("window exists");
+
+            if let (GapFill::CarryForward, Some((last, close))) = (self.gap_fill, self.last_emitted)
+            {
+                let mut gap = last + step;
+                while gap < start {
+                    bars.push(Bar {
+                        timestamp: gap,
+                        open: close,
+                        high: close,
+                        low: close,
+                        close,
+                        volume: 0,
+                        vwap: None,
+                        halted: false,
+                    });
+                    gap += step;
+                }
+            }
+
+            let bar = window.to_bar(start);
+            self.last_emitted = Some((start, bar.close));
+            bars.push(bar);
+        }
+        bars
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 4, 14, minute, second)
+            .unwrap()
+    }
+
+    fn tick(minute: u32, second: u32, price: f64, size: u64) -> Tick {
+        Tick {
+            timestamp: at(minute, second),
+            price,
+            size,
+        }
+    }
+
+    /// Minute 30 has four ticks, 31 has none, 32 has one; the 33 tick
+    /// closes out minute 32
+    fn script() -> Vec<Tick> {
+        vec![
+            tick(30, 1, 100.0, 10),
+            tick(30, 15, 101.5, 20),
+            tick(30, 30, 99.5, 10),
+            tick(30, 59, 100.5, 60),
+            tick(32, 10, 102.0, 5),
+            tick(33, 0, 102.5, 1),
+        ]
+    }
+
+    fn run(aggregator: &mut BarAggregator, ticks: Vec<Tick>) -> Vec<Bar> {
+        ticks.into_iter().flat_map(|t| aggregator.push(t)).collect()
+    }
+
+    #[test]
+    fn test_one_minute_bars() {
+        let mut aggregator = BarAggregator::new(BarInterval::Minutes(1));
+        let bars = run(&mut aggregator, script());
+
+        assert_eq!(bars.len(), 2);
+        let first = &bars[0];
+        assert_eq!(first.timestamp, at(30, 0));
+        assert_eq!(
+            (first.open, first.high, first.low, first.close),
+            (100.0, 101.5, 99.5, 100.5)
+        );
+        assert_eq!(first.volume, 100);
+        // (1000 + 2030 + 995 + 6030) / 100
+        assert!((first.vwap.unwrap() - 100.55).abs() < 1e-9);
+
+        // Single tick window; the empty minute 31 is skipped
+        let single = &bars[1];
+        assert_eq!(single.timestamp, at(32, 0));
+        assert_eq!(
+            (single.open, single.high, single.low, single.close),
+            (102.0, 102.0, 102.0, 102.0)
+        );
+        assert_eq!((single.volume, single.vwap), (5, Some(102.0)));
+
+        let rest = aggregator.flush();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].timestamp, at(33, 0));
+    }
+
+    #[test]
+    fn test_carry_forward_fills_empty_window() {
+        let mut aggregator =
+            BarAggregator::new(BarInterval::Minutes(1)).with_gap_fill(GapFill::CarryForward);
+        let bars = run(&mut aggregator, script());
+
+        assert_eq!(bars.len(), 3);
+        let gap = &bars[1];
+        assert_eq!(gap.timestamp, at(31, 0));
+        assert_eq!(
+            (gap.open, gap.high, gap.low, gap.close),
+            (100.5, 100.5, 100.5, 100.5)
+        );
+        assert_eq!((gap.volume, gap.vwap), (0, None));
+    }
+
+    #[test]
+    fn test_out_of_order_ticks() {
+        let mut aggregator =
+            BarAggregator::new(BarInterval::Minutes(1)).with_tolerance(Duration::seconds(5));
+
+        assert!(aggregator.push(tick(30, 55, 100.0, 1)).is_empty());
+        // Earlier than the latest tick but within tolerance: becomes the open
+        assert!(aggregator.push(tick(30, 52, 98.0, 1)).is_empty());
+        // Into the next minute, but minute 30 stays open for the tolerance
+        assert!(aggregator.push(tick(31, 2, 103.0, 1)).is_empty());
+        // Late tick for minute 30 becomes its close
+        assert!(aggregator.push(tick(30, 58, 99.0, 1)).is_empty());
+
+        let bars = aggregator.push(tick(31, 6, 104.0, 1));
+        assert_eq!(bars.len(), 1);
+        assert_eq!(
+            (bars[0].open, bars[0].high, bars[0].low, bars[0].close),
+            (98.0, 100.0, 98.0, 99.0)
+        );
+
+        // Beyond the tolerance
+        assert!(aggregator.push(tick(30, 59, 90.0, 1)).is_empty());
+        assert_eq!(aggregator.dropped_ticks(), 1);
+    }
+
+    #[test]
+    fn test_daily_windows() {
+        let mut aggregator = BarAggregator::new(BarInterval::Daily);
+        let day = |d: u32, h: u32, price: f64| Tick {
+            timestamp: Utc.with_ymd_and_hms(2024, 3, d, h, 0, 0).unwrap(),
+            price,
+            size: 100,
+        };
+
+        assert!(aggregator.push(day(4, 14, 50.0)).is_empty());
+        assert!(aggregator.push(day(4, 20, 52.0)).is_empty());
+        let bars = aggregator.push(day(5, 14, 51.0));
+
+        assert_eq!(bars.len(), 1);
+        assert_eq!(
+            bars[0].timestamp,
+            Utc.with_ymd_and_hms(2024, 3, 4, 0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            (bars[0].open, bars[0].close, bars[0].volume),
+            (50.0, 52.0, 200)
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Live market data handling for the paper trader
+//!
+//! Feeds can deliver finished bars or raw trades; [`BarFeed`] turns either
+//! into the bar stream the strategy consumes.
+
+mod aggregator;
+
+pub use aggregator::{BarAggregator, BarInterval, GapFill};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use common::Bar;
+
+/// A single trade print
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Tick {
+    pub timestamp: DateTime<Utc>,
+    pub price: f64,
+    pub size: u64,
+}
+
+/// Market data event from a live feed
+#[derive(Debug, Clone)]
+pub enum MarketEvent {
+    Bar(Bar),
+    Tick(Tick),
+}
+
+/// Normalizes bar and tick events into completed bars
+#[derive(Debug)]
+pub struct BarFeed {
+    aggregator: BarAggregator,
+}
+
+impl BarFeed {
+    /// Ticks are aggregated with `aggregator`; bars pass straight through
+    pub fn new(aggregator: BarAggregator) -> Self {
+        Self { aggregator }
+    }
+
+    /// Handle one event, returning the bars that are now complete
+    pub fn push(&mut self, event: MarketEvent) -> Vec<Bar> {
+        match event {
+            MarketEvent::Bar(bar) => vec![bar],
+            MarketEvent::Tick(tick) => self.aggregator.push(tick),
+        }
+    }
+
+    /// Emit any partially built bar at the end of the session
+    pub fn finish(&mut self) -> Vec<Bar> {
+        self.aggregator.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_feed_accepts_bars_and_ticks() {
+        let at = |m: u32| Utc.with_ymd_and_hms(2024, 3, 4, 14, m, 0).unwrap();
+        let tick = |m: u32, price: f64| {
+            MarketEvent::Tick(Tick {
+                timestamp: at(m),
+                price,
+                size: 10,
+            })
+        };
+        let mut feed = BarFeed::new(BarAggregator::new(BarInterval::Minutes(1)));
+
+        let bar = Bar::new(at(0), 10.0, 11.0, 9.0, 10.5, 1000);
+        assert_eq!(feed.push(MarketEvent::Bar(bar)).len(), 1);
+
+        assert!(feed.push(tick(1, 20.0)).is_empty());
+        let bars = feed.push(tick(2, 21.0));
+        assert_eq!(bars.len(), 1);
+        assert_eq!(bars[0].close, 20.0);
+        assert_eq!(feed.finish()[0].close, 21.0);
+    }
+}
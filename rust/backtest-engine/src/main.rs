@@ -1,19 +1,65 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 use anyhow::Result;
-use clap::Parser;
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
 
+use backtest_engine::analysis::{
+    ablation_study, capacity_study, indicators_for, inverse_synthesis_error, opportunity_cost,
+    random_baseline_with_limits, reconstruct_equity, regime_breakdown, AblationRow, CapacityReport,
+    MissedEntryStatus, OpportunityReport, RandomBaseline, ReconstructionResolution,
+    RegimeBreakdown, SynthesisErrorReport, SYNTHESIS_DRIFT_WARN_PCT,
+};
+use backtest_engine::data::{auto_rescale, export_quality};
+use backtest_engine::metrics::{
+    benchmark_conditional, session_attribution, ConditionalMetrics, SessionAttribution,
+    ROLLING_BETA_WINDOW,
+};
+use backtest_engine::ml::export_features;
+use backtest_engine::report::meta::{self, MetaReport, RunFilter};
+use backtest_engine::report::{export_annotated_csv, export_chart_markers, export_journal};
+use backtest_engine::validation::{run_cv, summarize, FoldResult, MetricStats, TimeSeriesSplit};
 use backtest_engine::{
-    generate_synthetic_bars, load_file, BacktestEngine, BacktestParameters, BacktestResult,
+    generate_synthetic_bars, generate_synthetic_bars_seeded, grid_search_two_phase,
+    grid_search_with_limits, load_cash_flows, load_file, load_files_with, load_parameters_onto,
+    load_trades, BacktestEngine, BacktestParameters, BacktestResult, BacktestRunner, JobCompletion,
+    JobLimits, JsonlSink, MetricsCalculator, OptimizationResult, Overwrite, ParameterGrid,
+    PositionState, RankBy, ScoreSpec, SignalGenerator, TwoPhase,
 };
-use common::RealisticExecutionConfig;
+use common::{
+    exit_code, AdaptiveSizing, BacktestError, ConcurrentHedgePolicy, DirectionMode,
+    HaltedStopPolicy, MetricField, MetricsScope, Preset, RealisticExecutionConfig,
+    ResampleFrequency, RunManifest, SizingMode, Smoothing, StopSpec, VwapMode,
+};
+use serde_json::json;
 
 #[derive(Parser, Debug)]
 #[command(name = "backtest-engine")]
 #[command(author = "TQQQ Trading System")]
 #[command(version = "0.1.0")]
 #[command(about = "High-performance RSI(2) TQQQ backtest engine", long_about = None)]
+#[command(args_conflicts_with_subcommands = true)]
+#[command(
+    after_help = "Exit codes: 0 success, 2 data error, 3 invalid parameters or \
+arguments, 4 runtime error, 5 insufficient data, 6 output file exists (see --force) or \
+cannot be written. With JSON output, errors are printed to stdout as \
+{\"error\": {\"kind\", \"message\", \"details\"}}."
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Start from a named parameter preset; explicitly passed strategy flags override it
+    #[arg(long, value_parser = parse_preset)]
+    preset: Option<Preset>,
+
+    /// Read parameters from environment variables with this prefix (e.g.
+    /// BT_ for BT_RSI_OVERSOLD, BT_EXEC_LATENCY_BARS); they override the
+    /// preset and are overridden by explicitly passed flags
+    #[arg(long, value_name = "PREFIX")]
+    env_prefix: Option<String>,
     /// Number of days to backtest (used with synthetic data)
     #[arg(short, long, default_value = "30")]
     days: usize,
@@ -22,9 +68,23 @@ struct Args {
     #[arg(short, long, default_value = "100000")]
     capital: f64,
 
-    /// Data file path (CSV/JSON). If not provided, uses synthetic data.
+    /// Data file path (CSV/JSON); repeat to append files as one series.
+    /// If not provided, uses synthetic data.
     #[arg(short = 'f', long)]
-    data_file: Option<PathBuf>,
+    data_file: Vec<PathBuf>,
+
+    /// Load every file matching this glob (in path order) as one series
+    #[arg(long, conflicts_with = "data_file")]
+    data_glob: Option<String>,
+
+    /// Drop bars a file repeats from the previous one instead of failing
+    #[arg(long)]
+    dedupe_overlap: bool,
+
+    /// Divide prices by a detected scale factor (e.g. 100 for cents) when
+    /// the median close is outside `sane_price_range`
+    #[arg(long)]
+    auto_rescale: bool,
 
     /// Symbol to trade
     #[arg(short, long, default_value = "TQQQ")]
@@ -42,34 +102,246 @@ struct Args {
     #[arg(long, default_value = "75")]
     rsi_overbought: f64,
 
+    /// RSI averaging: wilder, simple (Cutler) or ema
+    #[arg(long, default_value = "wilder")]
+    rsi_smoothing: Smoothing,
+
+    /// ATR averaging: wilder, simple or ema
+    #[arg(long, default_value = "wilder")]
+    atr_smoothing: Smoothing,
+
     /// SMA period
     #[arg(long, default_value = "20")]
     sma_period: usize,
 
-    /// Stop loss percentage (0.05 = 5%)
+    /// Consecutive bars the entry condition must hold before buying
+    #[arg(long, default_value = "1")]
+    entry_confirmation_bars: usize,
+
+    /// Stop loss below entry: 5%, 2atr, 1.5usd, none, or a fraction (0.05 = 5%)
     #[arg(long, default_value = "0.05")]
-    stop_loss: f64,
+    stop_loss: StopSpec,
+
+    /// Enter with bracket orders (OCO stop + target) using this take profit (0.1 = 10%)
+    #[arg(long)]
+    bracket_take_profit: Option<f64>,
+
+    /// Skip dips while the 63-day ROC ranks below this percentile (0-100)
+    #[arg(long)]
+    momentum_floor: Option<f64>,
+
+    /// Skip dips while the 14-day ADX is above this value (0-100)
+    #[arg(long)]
+    adx_max: Option<f64>,
+
+    /// Skip dips unless On-Balance Volume slopes upward
+    #[arg(long)]
+    obv_filter: bool,
+
+    /// Skip dips closing below the VWAP anchored at the start of the month
+    #[arg(long)]
+    anchored_vwap_filter: bool,
 
     /// Position size percentage (0.9 = 90%)
     #[arg(long, default_value = "0.9")]
     position_size: f64,
 
+    /// Long entry sizing: compound, notional:<usd> or shares:<n>
+    #[arg(long, default_value = "compound")]
+    sizing_mode: SizingMode,
+
     /// Enable short/hedge trading with SQQQ
     #[arg(long)]
     short_enabled: bool,
 
+    /// Long entry while hedged: block, close-hedge-first or allow
+    #[arg(long, default_value = "close-hedge-first")]
+    hedge_policy: ConcurrentHedgePolicy,
+
+    /// Open the hedge on the bar an extreme overbought reading sells the long
+    #[arg(long)]
+    flip_to_hedge: bool,
+
+    /// Open the long on the bar an oversold reading closes the hedge
+    #[arg(long)]
+    flip_to_long: bool,
+
+    /// Bars after a long or hedge position change during which an entry on
+    /// the other side is suppressed (0 disables)
+    #[arg(long, default_value = "0")]
+    signal_hysteresis_bars: usize,
+
+    /// RSI move since that change which lets the opposite entry through
+    #[arg(long, default_value = "10")]
+    hysteresis_rsi_delta: f64,
+
+    /// Hedge with a cash-settled short on the primary symbol instead of
+    /// the inverse instrument
+    #[arg(long)]
+    synthetic_short: bool,
+
+    /// Leverage of the synthetic short
+    #[arg(long, default_value = "1")]
+    synthetic_short_leverage: f64,
+
+    /// Side the signal trades: long-primary, long-inverse-only or both
+    #[arg(long, default_value = "long-primary")]
+    direction_mode: DirectionMode,
+
+    /// Trade only the inverse side while the close is below the trend SMA
+    #[arg(long)]
+    auto_flip_on_regime: bool,
+
     /// Disable VWAP filter
     #[arg(long)]
     no_vwap_filter: bool,
 
+    /// Entry close versus VWAP: below, above, band:<pct> (within ±pct%) or disabled
+    #[arg(long, default_value = "below", conflicts_with = "no_vwap_filter")]
+    vwap_mode: VwapMode,
+
+    /// Skip entries whose expected ATR move is below this profit (%) plus costs
+    #[arg(long)]
+    min_edge_pct: Option<f64>,
+
+    /// ATR multiple used as the expected move for --min-edge-pct
+    #[arg(long, default_value = "1.0")]
+    edge_atr_multiple: f64,
+
+    /// Maximum number of new positions per calendar month
+    #[arg(long)]
+    max_trades_per_month: Option<u32>,
+
+    /// Maximum number of new positions per calendar day
+    #[arg(long)]
+    max_positions_per_day: Option<u32>,
+
+    /// Bars to wait after an entry fills before entering the same symbol again
+    #[arg(long, default_value = "0")]
+    min_bars_between_entries: usize,
+
+    /// Cap on portfolio heat (% of equity at risk to stops) for new entries
+    #[arg(long)]
+    max_heat_pct: Option<f64>,
+
+    /// Scale long entries by MULT while equity is over DD% below its peak
+    /// (DD:MULT, e.g. 10:0.5); repeatable, the deepest threshold crossed applies
+    #[arg(long, value_parser = parse_throttle_step)]
+    drawdown_throttle: Vec<(f64, f64)>,
+
+    /// Scale long entries with the win rate of the last N closed trades,
+    /// as N:MIN:MAX:SENSITIVITY (multiplier 1 + SENSITIVITY x (win rate - 0.5))
+    #[arg(long, value_parser = parse_adaptive_sizing)]
+    adaptive_sizing: Option<AdaptiveSizing>,
+
+    /// Treat bars with volume below this as halted (0 disables)
+    #[arg(long, default_value = "0")]
+    min_bar_volume: u64,
+
+    /// Skip entries on bars whose data quality score (0-1) is below this
+    #[arg(long, value_name = "SCORE")]
+    min_data_quality: Option<f64>,
+
+    /// With --min-data-quality, also close positions on low-quality bars
+    #[arg(long)]
+    close_on_low_quality: bool,
+
+    /// Write the per-bar data quality scores to this CSV file
+    #[arg(long, value_name = "FILE")]
+    export_quality: Option<PathBuf>,
+
+    /// Close the long at every close and reopen it at the next open
+    #[arg(long)]
+    overnight_flat: bool,
+
+    /// On the first bar after a halt, fill breached stops at the open
+    #[arg(long)]
+    stop_at_open_after_halt: bool,
+
+    /// Account cash in whole cents instead of f64 dollars
+    #[arg(long)]
+    precise_accounting: bool,
+
+    /// Size entries to the volume participation limit on trailing average volume
+    #[arg(long)]
+    liquidity_sizing: bool,
+
+    /// Deposits and withdrawals to apply, as a CSV of date,amount rows
+    #[arg(long, value_name = "FILE")]
+    cash_flows: Option<PathBuf>,
+
+    /// Trades the headline metrics cover: main (hedges reported apart) or combined
+    #[arg(long, default_value = "main")]
+    metrics_scope: MetricsScope,
+
+    /// Only trade within this date range (START:END, inclusive); repeatable
+    #[arg(long = "range", value_parser = parse_date_range)]
+    ranges: Vec<(NaiveDate, NaiveDate)>,
+
+    /// Leave bars between --range segments out of the equity curve
+    #[arg(long)]
+    omit_range_gaps: bool,
+
+    /// Record the indicators on each trade's entry and exit bars
+    #[arg(long)]
+    record_trade_context: bool,
+
     /// Output format (json, text)
     #[arg(short, long, default_value = "json")]
     output: String,
 
+    /// Equity curve points in JSON output: daily (every bar), weekly or monthly
+    #[arg(long, default_value = "daily")]
+    equity_frequency: ResampleFrequency,
+
+    /// Split trades by the volatility tercile at entry, over this many bars
+    #[arg(long, value_name = "LOOKBACK", num_args = 0..=1, default_missing_value = "20")]
+    regime_breakdown: Option<usize>,
+
+    /// Benchmark bars: split results by its trend regime and report rolling beta
+    #[arg(long, value_name = "FILE")]
+    benchmark: Option<PathBuf>,
+
+    /// SMA period separating the benchmark's up and down regimes
+    #[arg(long, default_value = "200")]
+    benchmark_sma: usize,
+
     /// Pretty print JSON output
     #[arg(long)]
     pretty: bool,
 
+    /// Also print the run manifest (parameters, data hash, build) in text output
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// Also write a markdown trade journal to this file
+    #[arg(long)]
+    journal: Option<PathBuf>,
+
+    /// Also write the bars annotated with trades to this CSV, with chart
+    /// markers in a .json file beside it
+    #[arg(long)]
+    annotate: Option<PathBuf>,
+
+    /// Stream trades, equity, signals and fills to JSON lines files in this
+    /// directory instead of the result, for runs too long to hold
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["journal", "annotate", "regime_breakdown", "benchmark"]
+    )]
+    spill_dir: Option<PathBuf>,
+
+    /// Cache indicator series in this directory, keyed by the bars and the
+    /// indicator settings, so repeat runs over the same data skip them
+    #[arg(long, value_name = "DIR")]
+    cache_dir: Option<PathBuf>,
+
+    /// Replace the journal, annotation, quality and spill files when they
+    /// already exist, instead of failing
+    #[arg(long)]
+    force: bool,
+
     /// Initial price for synthetic data
     #[arg(long, default_value = "50.0")]
     initial_price: f64,
@@ -83,8 +355,709 @@ struct Args {
     pessimistic: bool,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Inspect the built-in parameter presets
+    Presets {
+        #[command(subcommand)]
+        action: PresetsAction,
+    },
+    /// Post-run analysis of saved results
+    Analyze {
+        #[command(subcommand)]
+        action: AnalyzeAction,
+    },
+    /// Summaries across a directory of saved results
+    Runs {
+        #[command(subcommand)]
+        action: RunsAction,
+    },
+    /// Grid-search RSI thresholds and stop losses
+    Optimize(OptimizeArgs),
+    /// Time-series cross-validation: backtest each test fold separately
+    Cv(CvArgs),
+    /// Check a config and its data files without running the backtest;
+    /// prints a JSON verification report
+    Verify(VerifyArgs),
+    /// Metrics of an equity curve rebuilt from a trade list alone
+    Metrics(MetricsArgs),
+    /// What the strategy would do on the latest bar; prints JSON for
+    /// alerting scripts
+    SignalCheck(SignalCheckArgs),
+    /// Export per-bar features and labels (CSV) for ML experiments
+    ExportFeatures {
+        /// Data file (CSV or JSON); seeded synthetic data when omitted
+        #[arg(long)]
+        data: Option<PathBuf>,
+
+        /// Number of synthetic days
+        #[arg(long, default_value = "500")]
+        days: usize,
+
+        /// Seed for synthetic data
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        #[command(flatten)]
+        strategy: StrategySource,
+
+        /// Forward-return label horizon in bars
+        #[arg(long, default_value = "5")]
+        horizon: usize,
+
+        /// Output path; CSV is the only format written, so it must end in
+        /// `.csv`
+        #[arg(long, value_parser = parse_csv_path)]
+        out: PathBuf,
+
+        /// Replace `out` when it already exists, instead of failing
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct VerifyArgs {
+    /// Data files (CSV or JSON), loaded in order as one series
+    #[arg(short = 'f', long = "data", required = true)]
+    data: Vec<PathBuf>,
+
+    /// Drop bars a file repeats from the previous one instead of failing
+    #[arg(long)]
+    dedupe_overlap: bool,
+
+    #[command(flatten)]
+    strategy: StrategySource,
+
+    /// Hedge instrument bars, checked for alignment with the data
+    #[arg(long)]
+    hedge: Option<PathBuf>,
+
+    /// Benchmark bars, checked for overlap with the data
+    #[arg(long)]
+    benchmark: Option<PathBuf>,
+
+    /// Regime breakdown lookback, checked against the bar count
+    #[arg(long, value_name = "LOOKBACK")]
+    regime_breakdown: Option<usize>,
+}
+
+#[derive(clap::Args, Debug)]
+struct SignalCheckArgs {
+    /// Data file (CSV or JSON) ending on the bar to check; seeded synthetic
+    /// data when omitted
+    #[arg(long)]
+    data: Option<PathBuf>,
+
+    /// Number of synthetic days
+    #[arg(long, default_value = "500")]
+    days: usize,
+
+    /// Seed for synthetic data
+    #[arg(long, default_value = "42")]
+    seed: u64,
+
+    #[command(flatten)]
+    strategy: StrategySource,
+
+    /// Position held going into the bar
+    #[arg(long, default_value = "flat", value_parser = ["flat", "long", "hedged"])]
+    state: String,
+
+    /// Average entry price of the long
+    #[arg(long, required_if_eq("state", "long"))]
+    entry_price: Option<f64>,
+
+    /// Entry date of the long (YYYY-MM-DD); the latest bar's when omitted
+    #[arg(long, value_parser = parse_date)]
+    entry_date: Option<NaiveDate>,
+}
+
+#[derive(clap::Args, Debug)]
+struct MetricsArgs {
+    /// Trades as CSV (the columns of a result's trades), a JSON list, or a
+    /// result JSON
+    #[arg(long)]
+    trades: PathBuf,
+
+    /// Account value before the first trade
+    #[arg(long, default_value = "10000")]
+    capital: f64,
+
+    /// Book each trade's P&L at its exit (step) or spread it over the
+    /// holding period (smooth)
+    #[arg(long, default_value = "step")]
+    reconstruct: ReconstructionResolution,
+
+    /// Output format (json, text)
+    #[arg(short, long, default_value = "text")]
+    output: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct OptimizeArgs {
+    /// Data file (CSV or JSON); seeded synthetic data when omitted
+    #[arg(long)]
+    data: Option<PathBuf>,
+
+    /// Number of synthetic days
+    #[arg(long, default_value = "500")]
+    days: usize,
+
+    /// Seed for synthetic data
+    #[arg(long, default_value = "42")]
+    seed: u64,
+
+    #[command(flatten)]
+    strategy: StrategySource,
+
+    /// RSI periods to test (comma separated)
+    #[arg(long, value_delimiter = ',', default_value = "2,3,4")]
+    rsi_periods: Vec<usize>,
+
+    /// Oversold thresholds to test
+    #[arg(long, value_delimiter = ',', default_value = "5,10,15")]
+    rsi_oversold: Vec<f64>,
+
+    /// Overbought thresholds to test
+    #[arg(long, value_delimiter = ',', default_value = "60,70,80")]
+    rsi_overbought: Vec<f64>,
+
+    /// Stop loss percentages to test
+    #[arg(long, value_delimiter = ',', default_value = "0.03,0.05,0.07")]
+    stop_loss: Vec<f64>,
+
+    /// Ranking metric: any metric field name (e.g. sortino_ratio), the
+    /// shorthands sharpe, return and calmar, or composite
+    #[arg(long, default_value = "sharpe")]
+    rank_by: String,
+
+    /// Score spec (TOML or JSON) for --rank-by composite; built-in default when omitted
+    #[arg(long)]
+    score_spec: Option<PathBuf>,
+
+    /// Number of ranked runs to print
+    #[arg(long, default_value = "10")]
+    top: usize,
+
+    /// Scan the grid on every Nth bar first, then rerun the best
+    /// --refine-top combinations on every bar
+    #[arg(long)]
+    coarse_stride: Option<usize>,
+
+    /// Combinations to refine after the coarse scan
+    #[arg(long, default_value = "20", requires = "coarse_stride")]
+    refine_top: usize,
+
+    #[command(flatten)]
+    limits: JobLimitArgs,
+
+    /// Output format (json, text)
+    #[arg(short, long, default_value = "text")]
+    output: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct CvArgs {
+    /// Data file (CSV or JSON); seeded synthetic data when omitted
+    #[arg(long)]
+    data: Option<PathBuf>,
+
+    /// Number of synthetic days
+    #[arg(long, default_value = "500")]
+    days: usize,
+
+    /// Seed for synthetic data
+    #[arg(long, default_value = "42")]
+    seed: u64,
+
+    #[command(flatten)]
+    strategy: StrategySource,
+
+    /// Number of test folds
+    #[arg(long, default_value = "5")]
+    folds: usize,
+
+    /// Bars left out of training next to each test fold
+    #[arg(long, default_value = "0")]
+    embargo: usize,
+
+    /// Fewest training bars any fold may have
+    #[arg(long, default_value = "0")]
+    min_train: usize,
+
+    /// Purged K-fold (train on both sides of the test fold) instead of an
+    /// expanding window
+    #[arg(long)]
+    purged: bool,
+
+    /// Output format (json, text)
+    #[arg(short, long, default_value = "text")]
+    output: String,
+}
+
+#[derive(Subcommand, Debug)]
+enum AnalyzeAction {
+    /// Counterfactual P&L of entries the engine skipped
+    Missed {
+        /// Result JSON written by a previous run
+        #[arg(long)]
+        result: PathBuf,
+
+        /// Data file the result was produced from
+        #[arg(long)]
+        data: PathBuf,
+
+        #[command(flatten)]
+        strategy: StrategySource,
+
+        /// Output format (json, text)
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Rerun the strategy with each entry filter removed
+    Ablation {
+        /// Data file (CSV or JSON)
+        #[arg(long)]
+        data: PathBuf,
+
+        #[command(flatten)]
+        strategy: StrategySource,
+
+        /// Output format (json, text)
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Compare the strategy with random-entry runs on the same data
+    Baseline {
+        /// Data file (CSV or JSON)
+        #[arg(long)]
+        data: PathBuf,
+
+        #[command(flatten)]
+        strategy: StrategySource,
+
+        /// Number of random-entry runs
+        #[arg(long, default_value = "200")]
+        runs: usize,
+
+        /// Seed for the random entry bars
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        #[command(flatten)]
+        limits: JobLimitArgs,
+
+        /// Output format (json, text)
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Compare the metrics of two saved results, warning when they were
+    /// produced from different parameters or data
+    Compare {
+        /// Result JSON to compare against
+        #[arg(long)]
+        baseline: PathBuf,
+
+        /// Result JSON to compare
+        #[arg(long)]
+        candidate: PathBuf,
+
+        /// Output format (json, text)
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Rerun at multiples of the initial capital with realistic execution
+    /// and liquidity-aware sizing to find where the edge degrades
+    Capacity {
+        /// Data file (CSV or JSON)
+        #[arg(long)]
+        data: PathBuf,
+
+        #[command(flatten)]
+        strategy: StrategySource,
+
+        /// Capital multipliers to test (comma separated)
+        #[arg(long, value_delimiter = ',', default_value = "1,2,5,10,20")]
+        multipliers: Vec<f64>,
+
+        /// CAGR drop (% of the base CAGR) that counts as degraded
+        #[arg(long, default_value = "25")]
+        max_degradation: f64,
+
+        /// Seed for execution slippage, shared by every level
+        #[arg(long, default_value = "42")]
+        seed: u64,
+
+        /// Output format (json, text)
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+    /// Measure how far a synthesized inverse series, moving `-leverage`
+    /// times the primary each bar, deviates from the real inverse ETF
+    InverseFit {
+        /// Primary symbol data file (CSV or JSON)
+        #[arg(long)]
+        data: PathBuf,
+
+        /// Real inverse ETF data file (CSV or JSON)
+        #[arg(long)]
+        inverse: PathBuf,
+
+        /// Inverse leverage of the synthesized series
+        #[arg(long, default_value = "3")]
+        leverage: f64,
+
+        /// Cumulative drift (%) past which to warn
+        #[arg(long, default_value_t = SYNTHESIS_DRIFT_WARN_PCT)]
+        max_drift: f64,
+
+        /// Output format (json, text)
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+}
+
+/// Where analysis commands get the strategy parameters from
+#[derive(clap::Args, Debug)]
+struct StrategySource {
+    /// Parameter file (JSON or TOML) used for the original run
+    #[arg(long, conflicts_with = "preset")]
+    config: Option<PathBuf>,
+
+    /// Preset used for the original run
+    #[arg(long, value_parser = parse_preset)]
+    preset: Option<Preset>,
+
+    /// Read parameters from environment variables with this prefix; the
+    /// parameter file takes precedence over them
+    #[arg(long, value_name = "PREFIX")]
+    env_prefix: Option<String>,
+}
+
+/// Budgets of commands that run many backtests
+#[derive(clap::Args, Debug)]
+struct JobLimitArgs {
+    /// Start no new backtest after this many minutes; the runs done so far
+    /// are reported as truncated
+    #[arg(long)]
+    max_minutes: Option<f64>,
+
+    /// Run at most this many backtests
+    #[arg(long)]
+    max_runs: Option<usize>,
+}
+
+impl JobLimitArgs {
+    fn limits(&self) -> Result<JobLimits> {
+        let mut limits = JobLimits::default();
+        if let Some(minutes) = self.max_minutes {
+            let limit = std::time::Duration::try_from_secs_f64(minutes * 60.0).map_err(|_| {
+                BacktestError::InvalidParameter(format!("invalid --max-minutes {}", minutes))
+            })?;
+            limits = limits.with_max_wall_clock(limit);
+        }
+        if let Some(runs) = self.max_runs {
+            limits = limits.with_max_backtests(runs);
+        }
+        Ok(limits)
+    }
+}
+
+/// One line on a job a limit cut short
+fn print_truncation(completion: &JobCompletion) {
+    if completion.truncated {
+        println!(
+            "  TRUNCATED: {} of {} planned runs completed",
+            completion.completed, completion.planned
+        );
+    }
+}
+
+impl StrategySource {
+    /// Preset or defaults < environment < parameter file
+    fn resolve(&self) -> Result<BacktestParameters> {
+        let mut params = self.preset.map(|p| p.parameters()).unwrap_or_default();
+        if let Some(prefix) = &self.env_prefix {
+            params = params.with_env(prefix)?;
+        }
+        if let Some(path) = &self.config {
+            params = load_parameters_onto(params, path)?;
+        }
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum PresetsAction {
+    /// Print the parameter table of every preset
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+enum RunsAction {
+    /// Sharpe distribution and parameter correlations across saved runs
+    Report {
+        /// Directory of result JSON files
+        dir: PathBuf,
+
+        /// Only runs whose parameter matches, e.g. symbol=TQQQ or
+        /// execution.latency_bars=1 (repeatable)
+        #[arg(long = "filter", value_parser = parse_run_filter)]
+        filters: Vec<(String, String)>,
+
+        /// Only runs created on or after this date (YYYY-MM-DD)
+        #[arg(long, value_parser = parse_date)]
+        since: Option<NaiveDate>,
+
+        /// Output format (json, text)
+        #[arg(short, long, default_value = "text")]
+        output: String,
+    },
+}
+
+fn parse_preset(name: &str) -> std::result::Result<Preset, String> {
+    Preset::from_name(name).ok_or_else(|| {
+        let names: Vec<&str> = Preset::ALL.iter().map(|p| p.name()).collect();
+        format!("unknown preset '{}' (valid: {})", name, names.join(", "))
+    })
+}
+
+fn parse_date(d: &str) -> std::result::Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(d, "%Y-%m-%d").map_err(|e| format!("invalid date '{}': {}", d, e))
+}
+
+fn parse_csv_path(s: &str) -> std::result::Result<PathBuf, String> {
+    let path = PathBuf::from(s);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("csv") => Ok(path),
+        _ => Err(format!(
+            "'{}' is not a .csv path (features are written as CSV only)",
+            s
+        )),
+    }
+}
+
+fn parse_date_range(s: &str) -> std::result::Result<(NaiveDate, NaiveDate), String> {
+    let (start, end) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected START:END, got '{}'", s))?;
+    Ok((parse_date(start)?, parse_date(end)?))
+}
+
+fn parse_run_filter(s: &str) -> std::result::Result<(String, String), String> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected PARAM=VALUE, got '{}'", s))?;
+    Ok((key.trim().to_string(), value.trim().to_string()))
+}
+
+fn parse_throttle_step(s: &str) -> std::result::Result<(f64, f64), String> {
+    let (drawdown, multiplier) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected DD:MULT, got '{}'", s))?;
+    let parse = |v: &str| {
+        v.parse::<f64>()
+            .map_err(|e| format!("invalid number '{}': {}", v, e))
+    };
+    Ok((parse(drawdown)?, parse(multiplier)?))
+}
+
+fn parse_adaptive_sizing(s: &str) -> std::result::Result<AdaptiveSizing, String> {
+    let parts: Vec<&str> = s.split(':').collect();
+    let [lookback, min, max, sensitivity] = parts[..] else {
+        return Err(format!("expected N:MIN:MAX:SENSITIVITY, got '{}'", s));
+    };
+    let parse = |v: &str| {
+        v.parse::<f64>()
+            .map_err(|e| format!("invalid number '{}': {}", v, e))
+    };
+    Ok(AdaptiveSizing {
+        lookback_trades: lookback
+            .parse()
+            .map_err(|e| format!("invalid trade count '{}': {}", lookback, e))?,
+        min_mult: parse(min)?,
+        max_mult: parse(max)?,
+        sensitivity: parse(sensitivity)?,
+    })
+}
+
+fn main() -> ExitCode {
+    let parsed = Args::command()
+        .try_get_matches()
+        .and_then(|matches| Args::from_arg_matches(&matches).map(|args| (args, matches)));
+    let (args, matches) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) if !e.use_stderr() => e.exit(), // --help / --version
+        Err(e) => {
+            let _ = e.print();
+            return ExitCode::from(exit_code::PARAMETERS);
+        }
+    };
+
+    match run(&args, &matches) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => report_error(&err, wants_json(&args)),
+    }
+}
+
+/// Data files from `-f` or `--data-glob`, in load order
+fn data_paths(args: &Args) -> Result<Vec<PathBuf>> {
+    let Some(pattern) = &args.data_glob else {
+        return Ok(args.data_file.clone());
+    };
+    let entries = glob::glob(pattern).map_err(|e| {
+        BacktestError::InvalidParameter(format!("--data-glob '{}': {}", pattern, e))
+    })?;
+    let mut paths = entries
+        .collect::<std::result::Result<Vec<PathBuf>, _>>()
+        .map_err(|e| BacktestError::DataLoadError(e.to_string()))?;
+    if paths.is_empty() {
+        return Err(BacktestError::DataLoadError(format!("no files match '{}'", pattern)).into());
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Whether the selected command writes JSON, in which case errors do too
+fn wants_json(args: &Args) -> bool {
+    let output = match &args.command {
+        None => &args.output,
+        Some(Command::Optimize(opts)) => &opts.output,
+        Some(Command::Cv(opts)) => &opts.output,
+        Some(Command::Metrics(opts)) => &opts.output,
+        Some(Command::Analyze { action }) => match action {
+            AnalyzeAction::Missed { output, .. }
+            | AnalyzeAction::Ablation { output, .. }
+            | AnalyzeAction::Baseline { output, .. }
+            | AnalyzeAction::Compare { output, .. }
+            | AnalyzeAction::Capacity { output, .. }
+            | AnalyzeAction::InverseFit { output, .. } => output,
+        },
+        Some(Command::Runs {
+            action: RunsAction::Report { output, .. },
+        }) => output,
+        Some(Command::Verify(_) | Command::SignalCheck(_)) => return true,
+        Some(Command::Presets { .. } | Command::ExportFeatures { .. }) => return false,
+    };
+    output == "json"
+}
+
+/// Print `err` (as `{"error": {...}}` on stdout in JSON mode, as prose on
+/// stderr otherwise) and map it to the process exit code
+fn report_error(err: &anyhow::Error, json: bool) -> ExitCode {
+    let (kind, code, details) = match err.downcast_ref::<BacktestError>() {
+        Some(e) => (e.kind(), e.exit_code(), e.details()),
+        None if err.is::<std::io::Error>() => ("io_error", exit_code::DATA, json!({})),
+        None if err.is::<serde_json::Error>() => ("json_error", exit_code::DATA, json!({})),
+        None => ("runtime_error", exit_code::RUNTIME, json!({})),
+    };
+
+    if json {
+        let error = json!({
+            "error": { "kind": kind, "message": err.to_string(), "details": details }
+        });
+        println!("{}", error);
+    } else {
+        eprintln!("Error: {}", err);
+    }
+    ExitCode::from(code)
+}
+
+fn run(args: &Args, matches: &ArgMatches) -> Result<()> {
+    if let Some(command) = &args.command {
+        return match command {
+            Command::Presets {
+                action: PresetsAction::List,
+            } => {
+                print_presets_table();
+                Ok(())
+            }
+            Command::Analyze { action } => run_analyze(action),
+            Command::Runs {
+                action:
+                    RunsAction::Report {
+                        dir,
+                        filters,
+                        since,
+                        output,
+                    },
+            } => {
+                let filter = RunFilter {
+                    fields: filters.clone(),
+                    since: *since,
+                };
+                let (results, skipped) = meta::load_runs(dir)?;
+                for path in &skipped {
+                    eprintln!(
+                        "warning: skipped {} (not a backtest result)",
+                        path.display()
+                    );
+                }
+                let runs: Vec<_> = results
+                    .into_iter()
+                    .filter(|r| filter.accepts(r))
+                    .map(|r| (r.manifest.parameters, r.metrics))
+                    .collect();
+                let report = meta::build(&runs);
+                if output == "json" {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    print_meta_report(&report);
+                }
+                Ok(())
+            }
+            Command::Optimize(opts) => run_optimize(opts),
+            Command::Metrics(opts) => run_reconstructed_metrics(opts),
+            Command::SignalCheck(opts) => run_signal_check(opts),
+            Command::Cv(opts) => run_cross_validation(opts),
+            Command::Verify(opts) => {
+                let mut runner = BacktestRunner::new(opts.strategy.resolve()?, opts.data.clone())
+                    .with_dedupe_overlap(opts.dedupe_overlap);
+                if let Some(path) = &opts.hedge {
+                    runner = runner.with_hedge_file(path.clone());
+                }
+                if let Some(path) = &opts.benchmark {
+                    runner = runner.with_benchmark_file(path.clone());
+                }
+                if let Some(lookback) = opts.regime_breakdown {
+                    runner = runner.with_regime_lookback(lookback);
+                }
+                let report = runner.verify()?;
+                println!("{}", serde_json::to_string_pretty(&report)?);
+                Ok(())
+            }
+            Command::ExportFeatures {
+                data,
+                days,
+                seed,
+                strategy,
+                horizon,
+                out,
+                force,
+            } => {
+                let params = strategy.resolve()?;
+                let bars = match data {
+                    Some(path) => load_file(path)?,
+                    None => generate_synthetic_bars_seeded(*days, 50.0, *seed),
+                };
+                let result = BacktestEngine::new(params.clone()).run(&bars, None);
+                let indicators = indicators_for(&bars, &params);
+                let rows = export_features(
+                    &bars,
+                    &indicators,
+                    &result.trades,
+                    &params,
+                    *horizon,
+                    out,
+                    Overwrite::forced(*force),
+                )?;
+                eprintln!("Wrote {} feature rows to {:?}", rows, out);
+                Ok(())
+            }
+        };
+    }
 
     // Build execution config
     let execution = if args.pessimistic {
@@ -98,25 +1071,36 @@ fn main() -> Result<()> {
     };
 
     // Build parameters
-    let params = BacktestParameters {
-        symbol: args.symbol.clone(),
-        rsi_period: args.rsi_period,
-        rsi_oversold: args.rsi_oversold,
-        rsi_overbought: args.rsi_overbought,
-        sma_period: args.sma_period,
-        stop_loss_pct: args.stop_loss,
-        position_size_pct: args.position_size,
-        short_enabled: args.short_enabled,
-        vwap_filter_enabled: !args.no_vwap_filter,
-        initial_capital: args.capital,
-        execution,
-        ..Default::default()
-    };
+    let params = build_params(args, matches, execution)?;
+    params.validate()?;
 
     // Load or generate data
-    let bars = if let Some(path) = &args.data_file {
-        eprintln!("Loading data from {:?}...", path);
-        load_file(path)?
+    let paths = data_paths(args)?;
+    let source = if !paths.is_empty() {
+        let names: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+        names.join(", ")
+    } else {
+        format!(
+            "synthetic: {} days from ${:.2}",
+            args.days, args.initial_price
+        )
+    };
+    let mut rescaled = None;
+    let bars = if !paths.is_empty() {
+        let (mut bars, files) = load_files_with(&paths, args.dedupe_overlap)?;
+        for file in &files {
+            eprintln!("Loaded {} bars from {:?}", file.bars, file.path);
+            if file.overlapping > 0 {
+                eprintln!(
+                    "  dropped {} bars overlapping the previous file",
+                    file.overlapping
+                );
+            }
+        }
+        if args.auto_rescale {
+            rescaled = auto_rescale(&mut bars, params.execution.sane_price_range);
+        }
+        bars
     } else {
         eprintln!(
             "Generating {} days of synthetic data (initial price: ${:.2})...",
@@ -125,134 +1109,1293 @@ fn main() -> Result<()> {
         generate_synthetic_bars(args.days, args.initial_price)
     };
 
-    eprintln!("Running backtest with {} bars...", bars.len());
-
+    let required = params.warmup_bars() + 1;
+    if bars.len() < required {
+        return Err(BacktestError::InsufficientData {
+            required,
+            actual: bars.len(),
+        }
+        .into());
+    }
+
+    eprintln!("Running backtest with {} bars...", bars.len());
+
     // Run backtest
-    let engine = BacktestEngine::new(params);
-    let result = engine.run(&bars, None);
+    let overwrite = Overwrite::forced(args.force);
+    let mut engine = BacktestEngine::new(params).with_data_source(source);
+    if let Some(dir) = &args.spill_dir {
+        JsonlSink::check(dir, overwrite)?;
+        engine = engine.with_spill_dir(dir, overwrite);
+    }
+    if let Some(dir) = &args.cache_dir {
+        engine = engine.with_indicator_cache(dir);
+    }
+    let mut result = engine.run(&bars, None);
+    result.warnings.splice(0..0, rescaled);
+    if !result.warnings.is_empty() {
+        eprintln!("{} data warning(s):", result.warnings.len());
+        for warning in result.warnings.iter().take(10) {
+            eprintln!("  {}", warning.message);
+        }
+        if result.warnings.len() > 10 {
+            eprintln!("  ... and {} more", result.warnings.len() - 10);
+        }
+    }
+    if let Some(spill) = &result.spill {
+        eprintln!(
+            "Spilled {} trades, {} equity points, {} signals and {} fills to {:?}",
+            spill.trades, spill.equity_points, spill.signals, spill.fills, spill.dir
+        );
+    }
+
+    if let Some(path) = &args.journal {
+        export_journal(&result, &bars, path, overwrite)?;
+        eprintln!("Wrote trade journal to {:?}", path);
+    }
+    if let Some(path) = &args.annotate {
+        let rows = export_annotated_csv(&result, &bars, path, overwrite)?;
+        let markers_path = path.with_extension("json");
+        let markers = export_chart_markers(&result, &markers_path, overwrite)?;
+        eprintln!(
+            "Wrote {} annotated bars to {:?} and {} markers to {:?}",
+            rows, path, markers, markers_path
+        );
+    }
+    if let Some(path) = &args.export_quality {
+        let rows = export_quality(&bars, path, overwrite)?;
+        eprintln!("Wrote {} data quality rows to {:?}", rows, path);
+    }
+
+    let regimes = args
+        .regime_breakdown
+        .map(|lookback| regime_breakdown(&result, &bars, lookback));
+    let conditional = match &args.benchmark {
+        Some(path) => {
+            let benchmark = load_file(path)?;
+            eprintln!("Loaded {} benchmark bars from {:?}", benchmark.len(), path);
+            Some(benchmark_conditional(
+                &result.equity_curve,
+                &result.trades,
+                &benchmark,
+                args.benchmark_sma,
+            ))
+        }
+        None => None,
+    };
+    let sessions = session_attribution(&result.trades, &bars);
 
     // Output result
     match args.output.as_str() {
         "json" => {
+            let mut value = serde_json::to_value(&result)?;
+            if args.equity_frequency != ResampleFrequency::Daily {
+                let resampled =
+                    MetricsCalculator::resample_equity(&result.equity_curve, args.equity_frequency);
+                value["equity_curve"] = serde_json::to_value(resampled)?;
+            }
+            if let Some(regimes) = &regimes {
+                value["regime_breakdown"] = serde_json::to_value(regimes)?;
+            }
+            if let Some(conditional) = &conditional {
+                value["benchmark_conditional"] = serde_json::to_value(conditional)?;
+            }
+            value["session_attribution"] = serde_json::to_value(&sessions)?;
             let json = if args.pretty {
-                serde_json::to_string_pretty(&result)?
+                serde_json::to_string_pretty(&value)?
             } else {
-                serde_json::to_string(&result)?
+                serde_json::to_string(&value)?
             };
             println!("{}", json);
         }
         "text" => {
-            print_text_report(&result);
+            print_text_report(&result, &sessions, regimes.as_ref(), conditional.as_ref());
         }
         _ => {
             eprintln!("Unknown output format: {}. Using text.", args.output);
-            print_text_report(&result);
+            print_text_report(&result, &sessions, regimes.as_ref(), conditional.as_ref());
         }
     }
+    if args.verbose && args.output != "json" {
+        print_manifest(&result.manifest);
+    }
 
     Ok(())
 }
 
-fn print_text_report(result: &BacktestResult) {
+fn run_analyze(action: &AnalyzeAction) -> Result<()> {
+    match action {
+        AnalyzeAction::Missed {
+            result,
+            data,
+            strategy,
+            output,
+        } => {
+            let params = strategy.resolve()?;
+            let file = std::fs::File::open(result)?;
+            let result: BacktestResult = serde_json::from_reader(std::io::BufReader::new(file))?;
+            let bars = load_file(data)?;
+
+            let report = opportunity_cost(&result, &bars, &params);
+            if output == "json" {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_opportunity_report(&report);
+            }
+            Ok(())
+        }
+        AnalyzeAction::Ablation {
+            data,
+            strategy,
+            output,
+        } => {
+            let params = strategy.resolve()?;
+            let bars = load_file(data)?;
+
+            let rows = ablation_study(&params, &bars);
+            if output == "json" {
+                println!("{}", serde_json::to_string_pretty(&rows)?);
+            } else {
+                print_ablation_report(&rows);
+            }
+            Ok(())
+        }
+        AnalyzeAction::Baseline {
+            data,
+            strategy,
+            runs,
+            seed,
+            limits,
+            output,
+        } => {
+            let params = strategy.resolve()?;
+            let limits = limits.limits()?;
+            let bars = load_file(data)?;
+
+            let baseline = random_baseline_with_limits(&params, &bars, *runs, *seed, limits);
+            if output == "json" {
+                println!("{}", serde_json::to_string_pretty(&baseline)?);
+            } else {
+                print_baseline_report(&baseline);
+            }
+            Ok(())
+        }
+        AnalyzeAction::Capacity {
+            data,
+            strategy,
+            multipliers,
+            max_degradation,
+            seed,
+            output,
+        } => {
+            let params = strategy.resolve()?;
+            let bars = load_file(data)?;
+
+            let report = capacity_study(&params, &bars, multipliers, *max_degradation, *seed)?;
+            if output == "json" {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_capacity_report(&report);
+            }
+            Ok(())
+        }
+        AnalyzeAction::InverseFit {
+            data,
+            inverse,
+            leverage,
+            max_drift,
+            output,
+        } => {
+            let (bars, inverse_bars) = (load_file(data)?, load_file(inverse)?);
+
+            let report = inverse_synthesis_error(&bars, &inverse_bars, *leverage)?
+                .with_drift_threshold(*max_drift);
+            if output == "json" {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_inverse_fit_report(&report);
+            }
+            Ok(())
+        }
+        AnalyzeAction::Compare {
+            baseline,
+            candidate,
+            output,
+        } => {
+            let load = |path: &PathBuf| -> Result<BacktestResult> {
+                let file = std::fs::File::open(path)?;
+                Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+            };
+            let (baseline, candidate) = (load(baseline)?, load(candidate)?);
+
+            let warnings = baseline.manifest.differences(&candidate.manifest);
+            for warning in &warnings {
+                eprintln!("warning: {}", warning);
+            }
+            if output == "json" {
+                let metrics: Vec<_> = COMPARE_METRICS
+                    .iter()
+                    .map(|&field| {
+                        let (a, b) = (baseline.metrics.get(field), candidate.metrics.get(field));
+                        json!({ "metric": field, "baseline": a, "candidate": b, "delta": b - a })
+                    })
+                    .collect();
+                let report = json!({ "warnings": warnings, "metrics": metrics });
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                print_comparison(&baseline, &candidate);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn print_ablation_report(rows: &[AblationRow]) {
     println!();
     println!("================================================================");
-    println!("  BACKTEST REPORT - RSI(2) TQQQ Mean Reversion Strategy");
+    println!("  FILTER ABLATION");
     println!("================================================================");
+    println!(
+        "  {:<22} {:>8} {:>8} {:>9} {:>8} {:>7} {:>7}",
+        "Run", "Sharpe", "dSharpe", "Return%", "dRet%", "Trades", "dTrades"
+    );
+    for row in rows {
+        let label = match row.removed {
+            None => "baseline".to_string(),
+            Some(filter) if !row.enabled_in_baseline => format!("-{} (off)", filter.name()),
+            Some(filter) => format!("-{}", filter.name()),
+        };
+        println!(
+            "  {:<22} {:>8.2} {:>+8.2} {:>9.2} {:>+8.2} {:>7} {:>+7}",
+            label,
+            row.metrics.sharpe_ratio,
+            row.sharpe_delta,
+            row.metrics.total_return_pct,
+            row.return_delta_pct,
+            row.metrics.total_trades,
+            row.trades_delta
+        );
+    }
+    println!("----------------------------------------------------------------");
+    println!("  Positive dSharpe: the strategy does better without that filter");
+    println!("================================================================");
+}
+
+fn print_baseline_report(baseline: &RandomBaseline) {
+    let mut equities: Vec<f64> = baseline.runs.iter().map(|r| r.final_equity).collect();
+    equities.sort_by(|a, b| a.total_cmp(b));
+    let quantile = |q: f64| {
+        if equities.is_empty() {
+            return 0.0;
+        }
+        equities[((equities.len() - 1) as f64 * q).round() as usize]
+    };
+
     println!();
-    println!("  Period: {} to {}", result.start_date, result.end_date);
+    println!("================================================================");
     println!(
-        "  Duration: {} trading days",
-        result.equity_curve.len()
+        "  RANDOM ENTRY BASELINE ({} runs, {} entries each)",
+        baseline.runs.len(),
+        baseline.entries
     );
-    println!("  Execution Time: {}ms", result.execution_time_ms);
+    println!("================================================================");
+    println!(
+        "  Strategy Equity:  ${:>12.2}",
+        baseline.strategy_final_equity
+    );
+    println!(
+        "  Strategy Sharpe:  {:>13.2}",
+        baseline.strategy.sharpe_ratio
+    );
+    println!(
+        "  Random Equity:    ${:>12.2} / ${:.2} / ${:.2}  (p5 / median / p95)",
+        quantile(0.05),
+        quantile(0.5),
+        quantile(0.95)
+    );
+    println!("----------------------------------------------------------------");
+    println!("  Equity Pctile:    {:>12.1}%", baseline.equity_percentile);
+    println!("  Sharpe Pctile:    {:>12.1}%", baseline.sharpe_percentile);
+    println!("  p-value:          {:>13.3}", baseline.p_value);
+    print_truncation(&baseline.completion);
+    println!("================================================================");
+}
+
+/// Metrics listed by `analyze compare`
+const COMPARE_METRICS: [MetricField; 8] = [
+    MetricField::TotalReturnPct,
+    MetricField::Cagr,
+    MetricField::SharpeRatio,
+    MetricField::MaxDrawdown,
+    MetricField::WinRate,
+    MetricField::ProfitFactor,
+    MetricField::TotalTrades,
+    MetricField::AvgRoundTripCostBps,
+];
+
+fn print_comparison(baseline: &BacktestResult, candidate: &BacktestResult) {
+    println!();
+    println!("================================================================");
+    println!("  RESULT COMPARISON");
+    println!("================================================================");
+    println!(
+        "  {:<26} {:>11} {:>11} {:>11}",
+        "Metric", "Baseline", "Candidate", "Delta"
+    );
+    for field in COMPARE_METRICS {
+        let (a, b) = (baseline.metrics.get(field), candidate.metrics.get(field));
+        println!(
+            "  {:<26} {:>11.2} {:>11.2} {:>+11.2}",
+            field.to_string(),
+            a,
+            b,
+            b - a
+        );
+    }
+    println!("================================================================");
+}
+
+fn print_meta_report(report: &MetaReport) {
     println!();
+    println!("================================================================");
+    println!("  META-REPORT ({} runs)", report.runs.len());
+    println!("================================================================");
+    let Some(sharpe) = &report.sharpe else {
+        println!("  No runs match.");
+        println!("================================================================");
+        return;
+    };
+    println!(
+        "  {:<8} {:>9} {:>9} {:>9} {:>9} {:>7}",
+        "Symbol", "Return %", "Sharpe", "Max DD %", "Win %", "Trades"
+    );
+    for run in &report.runs {
+        println!(
+            "  {:<8} {:>9.2} {:>9.2} {:>9.2} {:>9.1} {:>7}",
+            run.symbol,
+            run.total_return_pct,
+            run.sharpe_ratio,
+            run.max_drawdown,
+            run.win_rate,
+            run.total_trades
+        );
+    }
     println!("----------------------------------------------------------------");
-    println!("  CAPITAL");
+    println!(
+        "  Sharpe: min {:.2}  p25 {:.2}  median {:.2}  p75 {:.2}  max {:.2}  mean {:.2}",
+        sharpe.min, sharpe.p25, sharpe.median, sharpe.p75, sharpe.max, sharpe.mean
+    );
+    if !report.correlations.is_empty() {
+        println!("----------------------------------------------------------------");
+        println!(
+            "  {:<40} {:>9} {:>6}",
+            "Parameter vs Sharpe", "Spearman", "Runs"
+        );
+        for c in &report.correlations {
+            println!("  {:<40} {:>+9.2} {:>6}", c.parameter, c.spearman, c.runs);
+        }
+    }
+    println!("================================================================");
+}
+
+fn print_manifest(manifest: &RunManifest) {
+    let timestamp = |t: Option<DateTime<Utc>>| {
+        t.map_or("-".to_string(), |t| t.format("%Y-%m-%d %H:%M").to_string())
+    };
+    println!("----------------------------------------------------------------");
+    println!("  MANIFEST");
     println!("----------------------------------------------------------------");
-    println!("  Initial Capital:  ${:>12.2}", result.initial_capital);
-    println!("  Final Equity:     ${:>12.2}", result.final_equity);
     println!(
-        "  Total Return:     ${:>12.2} ({:+.2}%)",
-        result.metrics.total_return, result.metrics.total_return_pct
+        "  Engine:           {} ({})",
+        manifest.crate_version,
+        manifest.git_hash.as_deref().unwrap_or("unknown commit")
     );
-    println!("  CAGR:             {:>12.2}%", result.metrics.cagr);
+    println!(
+        "  Data:             {}",
+        manifest.data.description.as_deref().unwrap_or("-")
+    );
+    println!(
+        "  Bars:             {} ({} to {})",
+        manifest.data.bars,
+        timestamp(manifest.data.first_timestamp),
+        timestamp(manifest.data.last_timestamp)
+    );
+    println!("  Data Hash:        {}", manifest.data.content_hash);
+    if let Some(hedge) = &manifest.hedge_data {
+        println!("  Hedge Data Hash:  {}", hedge.content_hash);
+    }
+    match manifest.execution_seed {
+        Some(seed) => println!("  Execution Seed:   {}", seed),
+        None => println!("  Execution Seed:   none (fresh entropy)"),
+    }
+    println!(
+        "  Host:             {} at {}",
+        manifest.hostname.as_deref().unwrap_or("unknown"),
+        manifest.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    println!("  Parameters:");
+    let parameters = serde_json::to_string_pretty(&manifest.parameters).unwrap_or_default();
+    for line in parameters.lines() {
+        println!("    {}", line);
+    }
+}
+
+fn print_capacity_report(report: &CapacityReport) {
     println!();
+    println!("================================================================");
+    println!(
+        "  CAPACITY (degraded past {:.0}% of base CAGR)",
+        report.degradation_threshold_pct
+    );
+    println!("================================================================");
+    println!(
+        "  {:>6} {:>14} {:>9} {:>9} {:>7} {:>7} {:>9}",
+        "Mult", "Capital", "CAGR", "Return%", "Sharpe", "Capped", "Degr.%"
+    );
+    for level in &report.levels {
+        println!(
+            "  {:>5}x {:>14.0} {:>8.2}% {:>8.2}% {:>7.2} {:>7} {:>8.1}%",
+            level.multiplier,
+            level.initial_capital,
+            level.cagr,
+            level.total_return_pct,
+            level.sharpe_ratio,
+            level.liquidity_capped_entries,
+            level.cagr_degradation_pct
+        );
+    }
     println!("----------------------------------------------------------------");
-    println!("  RISK METRICS");
+    match report.degraded_at {
+        Some(capital) => println!("  Degraded At:      ${:>12.0}", capital),
+        None => println!("  Degraded At:      {:>13}", "none tested"),
+    }
+    println!("  Capacity:         ${:>12.0}", report.capacity);
+    println!("================================================================");
+}
+
+fn print_inverse_fit_report(report: &SynthesisErrorReport) {
+    println!();
+    println!("================================================================");
+    println!("  INVERSE SYNTHESIS FIT (-{}x)", report.leverage);
+    println!("================================================================");
+    println!("  Matched Bars:     {:>13}", report.matched_bars);
+    println!("  Unmatched Bars:   {:>13}", report.unmatched_bars);
+    println!("  Tracking Error:   {:>12.3}%", report.tracking_error_pct);
+    println!(
+        "  Cumulative Drift: {:>+12.2}%",
+        report.cumulative_drift_pct
+    );
+    match report.worst_divergence_at {
+        Some(at) => println!(
+            "  Worst Divergence: {:>12.3}%  ({})",
+            report.worst_divergence_pct,
+            at.date_naive()
+        ),
+        None => println!("  Worst Divergence: {:>13}", "-"),
+    }
     println!("----------------------------------------------------------------");
+    if report.drift_warning {
+        println!(
+            "  WARNING: drift exceeds {:.1}%; use the real inverse data",
+            report.drift_threshold_pct
+        );
+    } else {
+        println!("  Drift within {:.1}%", report.drift_threshold_pct);
+    }
+    println!("================================================================");
+}
+
+fn parse_rank_by(name: &str, score_spec: Option<&Path>) -> Result<RankBy> {
+    let rank_by = match name {
+        "sharpe" => RankBy::Metric(MetricField::SharpeRatio),
+        "return" => RankBy::Metric(MetricField::TotalReturnPct),
+        "calmar" => RankBy::Metric(MetricField::CalmarRatio),
+        "composite" => RankBy::Composite(match score_spec {
+            Some(path) => ScoreSpec::load(path)?,
+            None => ScoreSpec::default(),
+        }),
+        _ => match name.parse::<MetricField>() {
+            Ok(field) => RankBy::Metric(field),
+            Err(_) => {
+                let names: Vec<&str> = MetricField::all().map(|f| f.name()).collect();
+                return Err(BacktestError::InvalidParameter(format!(
+                    "unknown --rank-by '{}' (valid: sharpe, return, calmar, composite, {})",
+                    name,
+                    names.join(", ")
+                ))
+                .into());
+            }
+        },
+    };
+    if score_spec.is_some() && !matches!(rank_by, RankBy::Composite(_)) {
+        return Err(BacktestError::InvalidParameter(
+            "--score-spec requires --rank-by composite".to_string(),
+        )
+        .into());
+    }
+    Ok(rank_by)
+}
+
+fn run_optimize(opts: &OptimizeArgs) -> Result<()> {
+    let base = opts.strategy.resolve()?;
+    let rank_by = parse_rank_by(&opts.rank_by, opts.score_spec.as_deref())?;
+    let grid = ParameterGrid {
+        rsi_periods: opts.rsi_periods.clone(),
+        rsi_oversold_levels: opts.rsi_oversold.clone(),
+        rsi_overbought_levels: opts.rsi_overbought.clone(),
+        stop_loss_pcts: opts.stop_loss.clone(),
+    };
+
+    let bars = match &opts.data {
+        Some(path) => load_file(path)?,
+        None => generate_synthetic_bars_seeded(opts.days, 50.0, opts.seed),
+    };
+    eprintln!(
+        "Testing {} combinations on {} bars...",
+        grid.combinations(&base).len(),
+        bars.len()
+    );
+
+    let limits = opts.limits.limits()?;
+    let result = match opts.coarse_stride {
+        Some(coarse_stride) => {
+            let phases = TwoPhase {
+                coarse_stride,
+                refine_top: opts.refine_top,
+            };
+            grid_search_two_phase(&bars, &base, &grid, &rank_by, limits, phases)?
+        }
+        None => grid_search_with_limits(&bars, &base, &grid, &rank_by, limits)?,
+    };
+    if opts.output == "json" {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        print_optimization_report(&result, opts.top);
+    }
+    Ok(())
+}
+
+fn run_reconstructed_metrics(opts: &MetricsArgs) -> Result<()> {
+    let trades = load_trades(&opts.trades)?;
+    let equity = reconstruct_equity(&trades, opts.capital, opts.reconstruct);
+    let metrics = MetricsCalculator::calculate(&equity, &trades, opts.capital);
+    if opts.output == "json" {
+        let report = json!({
+            "reconstruct": opts.reconstruct,
+            "initial_capital": opts.capital,
+            "final_equity": equity.last().map_or(opts.capital, |(_, e)| *e),
+            "equity_curve": equity,
+            "metrics": metrics,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!();
+    println!("================================================================");
     println!(
-        "  Volatility (Ann): {:>12.2}%",
-        result.metrics.volatility
+        "  RECONSTRUCTED METRICS ({} trades, {:?} mode)",
+        trades.len(),
+        opts.reconstruct
     );
+    println!("================================================================");
+    println!("  Total Return:     {:>12.2}%", metrics.total_return_pct);
+    println!("  CAGR:             {:>12.2}%", metrics.cagr);
+    println!("  Sharpe Ratio:     {:>13.2}", metrics.sharpe_ratio);
+    println!("  Max Drawdown:     {:>12.2}%", metrics.max_drawdown);
+    println!("  Win Rate:         {:>12.1}%", metrics.win_rate);
+    println!("  Profit Factor:    {:>13.2}", metrics.profit_factor);
+    println!("----------------------------------------------------------------");
+    println!("  Approximate: no drawdown inside trades in step mode");
+    println!("================================================================");
+    Ok(())
+}
+
+fn run_signal_check(opts: &SignalCheckArgs) -> Result<()> {
+    let params = opts.strategy.resolve()?;
+    let bars = match &opts.data {
+        Some(path) => load_file(path)?,
+        None => generate_synthetic_bars_seeded(opts.days, 50.0, opts.seed),
+    };
+    let state = match (opts.state.as_str(), opts.entry_price) {
+        ("long", Some(entry_price)) => {
+            let entry_date = match opts.entry_date {
+                Some(date) => date.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+                None => bars.last().map_or_else(Utc::now, |b| b.timestamp),
+            };
+            PositionState::Long {
+                entry_price,
+                entry_date,
+            }
+        }
+        ("hedged", _) => PositionState::Hedged,
+        _ => PositionState::Flat,
+    };
+    let evaluation = SignalGenerator::evaluate_latest(&bars, &params, state)?;
+    println!("{}", serde_json::to_string_pretty(&evaluation)?);
+    Ok(())
+}
+
+fn run_cross_validation(opts: &CvArgs) -> Result<()> {
+    let params = opts.strategy.resolve()?;
+    let bars = match &opts.data {
+        Some(path) => load_file(path)?,
+        None => generate_synthetic_bars_seeded(opts.days, 50.0, opts.seed),
+    };
+    let mut splitter = TimeSeriesSplit::new(opts.folds)
+        .with_embargo(opts.embargo)
+        .with_min_train(opts.min_train);
+    if opts.purged {
+        splitter = splitter.purged();
+    }
+
+    let folds = run_cv(&params, &bars, &splitter)?;
+    let summary = summarize(&folds);
+    if opts.output == "json" {
+        let report = json!({
+            "mode": splitter.mode,
+            "embargo": splitter.embargo,
+            "folds": folds,
+            "summary": summary,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print_cv_report(&splitter, &folds, &summary);
+    }
+    Ok(())
+}
+
+fn print_cv_report(splitter: &TimeSeriesSplit, folds: &[FoldResult], summary: &[MetricStats]) {
+    println!();
+    println!("================================================================");
+    println!(
+        "  CROSS-VALIDATION ({:?}, {} folds, embargo {})",
+        splitter.mode,
+        folds.len(),
+        splitter.embargo
+    );
+    println!("================================================================");
     println!(
-        "  Sharpe Ratio:     {:>12.3}",
-        result.metrics.sharpe_ratio
+        "  {:>4} {:>13} {:>7} {:>8} {:>8} {:>8} {:>7}",
+        "Fold", "Test bars", "Train", "Sharpe", "Return%", "MaxDD%", "Trades"
     );
+    for fold in folds {
+        println!(
+            "  {:>4} {:>13} {:>7} {:>8.2} {:>8.2} {:>8.2} {:>7}",
+            fold.fold + 1,
+            format!("{}-{}", fold.test.start, fold.test.end - 1),
+            fold.train.iter().map(|r| r.len()).sum::<usize>(),
+            fold.metrics.sharpe_ratio,
+            fold.metrics.total_return_pct,
+            fold.metrics.max_drawdown,
+            fold.metrics.total_trades
+        );
+    }
+    println!("----------------------------------------------------------------");
+    println!("  {:<22} {:>10} {:>10}", "Metric", "Mean", "Std");
+    for stats in summary {
+        println!(
+            "  {:<22} {:>10.3} {:>10.3}",
+            stats.metric.to_string(),
+            stats.mean,
+            stats.std_dev
+        );
+    }
+    println!("================================================================");
+}
+
+fn print_optimization_report(result: &OptimizationResult, top: usize) {
+    println!();
+    println!("================================================================");
+    println!("  OPTIMIZATION RESULTS");
+    println!("================================================================");
+    println!(
+        "  {:>4} {:>6} {:>6} {:>6} {:>9} {:>8} {:>8} {:>7}",
+        "RSI", "Buy<", "Sell>", "Stop", "Score", "Sharpe", "Return%", "Trades"
+    );
+    let mut coarse_header = false;
+    for run in result.runs.iter().take(top) {
+        if run.approximate && !coarse_header {
+            println!("  -- coarse scan only (approximate) --");
+            coarse_header = true;
+        }
+        let score = if run.score == f64::NEG_INFINITY {
+            "rejected".to_string()
+        } else {
+            format!("{:.3}", run.score)
+        };
+        println!(
+            "  {:>4} {:>6.0} {:>6.0} {:>5.1}% {:>9} {:>8.2} {:>8.2} {:>7}",
+            run.params.strategy.rsi_period,
+            run.params.strategy.rsi_oversold,
+            run.params.strategy.rsi_overbought,
+            run.params.risk.stop_loss.percent().unwrap_or(0.0) * 100.0,
+            score,
+            run.metrics.sharpe_ratio,
+            run.metrics.total_return_pct,
+            run.metrics.total_trades
+        );
+    }
+    println!("----------------------------------------------------------------");
+    println!("  Total combinations tested: {}", result.runs.len());
+    print_truncation(&result.completion);
+    println!("================================================================");
+}
+
+fn print_opportunity_report(report: &OpportunityReport) {
+    println!();
+    println!("================================================================");
+    println!("  MISSED ENTRIES - COUNTERFACTUAL");
+    println!("================================================================");
+    println!("  {}", report.note);
+    println!();
+    println!("  Skipped Signals:  {:>12}", report.skipped_signals);
+    println!("  Same-Dip Repeats: {:>12}", report.covered_signals);
+    println!("  Replayed Entries: {:>12}", report.missed_entries.len());
+    println!("  Foregone P&L:     ${:>12.2}", report.foregone_pnl);
+    println!("----------------------------------------------------------------");
+    for entry in &report.missed_entries {
+        let status = match entry.status {
+            MissedEntryStatus::Counted => "",
+            MissedEntryStatus::CapturedLater => " [taken later]",
+        };
+        println!(
+            "  {} -> {} | P&L: ${:+.2} ({:+.1}%) | {}{}",
+            entry.timestamp.format("%Y-%m-%d"),
+            entry.exit_timestamp.format("%Y-%m-%d"),
+            entry.hypothetical_pnl,
+            entry.hypothetical_pnl_pct,
+            entry.skip_reason,
+            status
+        );
+    }
+    println!("================================================================");
+}
+
+/// Resolve parameters from the optional preset, the environment and the
+/// CLI flags, in increasing precedence.
+///
+/// Without a preset or environment every strategy flag applies (including
+/// its default). Otherwise only flags given explicitly on the command line
+/// override them; account-level flags (capital, execution, limits) always
+/// apply unless the environment is in use.
+fn build_params(
+    args: &Args,
+    matches: &ArgMatches,
+    execution: RealisticExecutionConfig,
+) -> Result<BacktestParameters> {
+    let explicit = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    let from_cli = |id: &str| (args.preset.is_none() && args.env_prefix.is_none()) || explicit(id);
+    let account = |id: &str| args.env_prefix.is_none() || explicit(id);
+
+    let mut params = args.preset.map(|p| p.parameters()).unwrap_or_default();
+    if let Some(prefix) = &args.env_prefix {
+        params = params.with_env(prefix)?;
+    }
+    if account("symbol") {
+        params.strategy.symbol = args.symbol.clone();
+    }
+    if account("capital") {
+        params.initial_capital = args.capital;
+    }
+    if account("realistic") || account("pessimistic") {
+        params.execution.simulation = execution;
+        if params.execution.simulation.enabled {
+            // The simulation models slippage itself
+            params.execution.slippage_pct = 0.0;
+        }
+    }
+    if account("max_trades_per_month") {
+        params.risk.max_trades_per_month = args.max_trades_per_month;
+    }
+    if account("max_positions_per_day") {
+        params.risk.max_new_positions_per_day = args.max_positions_per_day;
+    }
+    if account("min_bars_between_entries") {
+        params.risk.min_bars_between_entries = args.min_bars_between_entries;
+    }
+    if account("metrics_scope") {
+        params.metrics_scope = args.metrics_scope;
+    }
+    if account("max_heat_pct") {
+        params.risk.max_portfolio_heat_pct = args.max_heat_pct;
+    }
+    if let Some(min_edge) = args.min_edge_pct {
+        params.risk.edge_filter_enabled = true;
+        params.risk.min_expected_profit_pct = min_edge;
+        params.risk.edge_atr_multiple = args.edge_atr_multiple;
+    }
+    if let Some(take_profit) = args.bracket_take_profit {
+        params = params.with_bracket_orders(take_profit);
+    }
+    if let Some(min_quality) = args.min_data_quality {
+        params.risk.min_data_quality = min_quality;
+        params.risk.close_on_low_data_quality = args.close_on_low_quality;
+    }
+    if let Some(floor) = args.momentum_floor {
+        params.strategy.momentum_filter_enabled = true;
+        params.strategy.momentum_min_percentile = floor;
+    }
+    if let Some(adx_max) = args.adx_max {
+        params.strategy.adx_filter_enabled = true;
+        params.strategy.adx_max = adx_max;
+    }
+    if args.obv_filter {
+        params.strategy.obv_filter_enabled = true;
+    }
+    if args.anchored_vwap_filter {
+        params.strategy.anchored_vwap_filter_enabled = true;
+    }
+    if account("min_bar_volume") {
+        params.execution.min_bar_volume = args.min_bar_volume;
+    }
+    if account("precise_accounting") {
+        params.precise_accounting = args.precise_accounting;
+    }
+    if args.overnight_flat {
+        params.risk.overnight_flat = true;
+    }
+    if account("liquidity_sizing") {
+        params.risk.liquidity_aware_sizing = args.liquidity_sizing;
+    }
+    if !args.drawdown_throttle.is_empty() {
+        params.risk.drawdown_throttle = Some(args.drawdown_throttle.clone());
+    }
+    if args.adaptive_sizing.is_some() {
+        params.risk.adaptive_sizing = args.adaptive_sizing;
+    }
+    if !args.ranges.is_empty() {
+        params = params.with_date_ranges(args.ranges.clone());
+        params.omit_range_gaps = args.omit_range_gaps;
+    }
+    if args.stop_at_open_after_halt {
+        params.execution.halted_stop_policy = HaltedStopPolicy::NextOpen;
+    }
+    if args.record_trade_context {
+        params.record_trade_context = true;
+    }
+    if let Some(path) = &args.cash_flows {
+        params = params.with_cash_flows(load_cash_flows(path)?);
+    }
+
+    if from_cli("rsi_period") {
+        params.strategy.rsi_period = args.rsi_period;
+    }
+    if from_cli("rsi_oversold") {
+        params.strategy.rsi_oversold = args.rsi_oversold;
+    }
+    if from_cli("rsi_overbought") {
+        params.strategy.rsi_overbought = args.rsi_overbought;
+    }
+    if from_cli("hedge_policy") {
+        params.hedge.concurrent_hedge_policy = args.hedge_policy;
+    }
+    if from_cli("flip_to_hedge") {
+        params.hedge.flip_to_hedge_on_extreme = args.flip_to_hedge;
+    }
+    if from_cli("flip_to_long") {
+        params.hedge.flip_to_long_on_oversold = args.flip_to_long;
+    }
+    if from_cli("signal_hysteresis_bars") {
+        params.hedge.signal_hysteresis_bars = args.signal_hysteresis_bars;
+    }
+    if from_cli("hysteresis_rsi_delta") {
+        params.hedge.hysteresis_rsi_delta = args.hysteresis_rsi_delta;
+    }
+    if from_cli("synthetic_short") {
+        params.hedge.synthetic_short = args.synthetic_short;
+    }
+    if from_cli("synthetic_short_leverage") {
+        params.hedge.synthetic_short_leverage = args.synthetic_short_leverage;
+    }
+    if from_cli("direction_mode") {
+        params.hedge.direction_mode = args.direction_mode;
+    }
+    if from_cli("auto_flip_on_regime") {
+        params.hedge.auto_flip_on_regime = args.auto_flip_on_regime;
+    }
+    if from_cli("rsi_smoothing") {
+        params.strategy.rsi_smoothing = args.rsi_smoothing;
+    }
+    if from_cli("atr_smoothing") {
+        params.strategy.atr_smoothing = args.atr_smoothing;
+    }
+    if from_cli("sma_period") {
+        params.strategy.sma_period = args.sma_period;
+    }
+    if from_cli("entry_confirmation_bars") {
+        params.strategy.entry_confirmation_bars = args.entry_confirmation_bars;
+    }
+    if from_cli("stop_loss") {
+        params.risk.stop_loss = args.stop_loss;
+    }
+    if from_cli("position_size") {
+        params.risk.position_size_pct = args.position_size;
+    }
+    if from_cli("sizing_mode") {
+        params.risk.sizing_mode = args.sizing_mode;
+    }
+    if from_cli("short_enabled") {
+        params.hedge.short_enabled = args.short_enabled;
+    }
+    if from_cli("vwap_mode") {
+        params.strategy.vwap_mode = args.vwap_mode;
+    }
+    if args.no_vwap_filter {
+        params.strategy.vwap_mode = VwapMode::Disabled;
+    }
+
+    Ok(params)
+}
+
+fn print_presets_table() {
+    let presets: Vec<(Preset, BacktestParameters)> =
+        Preset::ALL.iter().map(|p| (*p, p.parameters())).collect();
+
+    let fmt_days = |d: Option<u32>| d.map(|d| d.to_string()).unwrap_or_else(|| "-".to_string());
+    let fmt_vwap = |m: VwapMode| match m {
+        VwapMode::Below => "below".to_string(),
+        VwapMode::Above => "above".to_string(),
+        VwapMode::WithinBandPct(pct) => format!("±{}%", pct),
+        VwapMode::Disabled => "off".to_string(),
+    };
+    let rows: Vec<(&str, Vec<String>)> = vec![
+        (
+            "RSI Period",
+            presets
+                .iter()
+                .map(|(_, p)| p.strategy.rsi_period.to_string())
+                .collect(),
+        ),
+        (
+            "RSI Oversold",
+            presets
+                .iter()
+                .map(|(_, p)| format!("{:.0}", p.strategy.rsi_oversold))
+                .collect(),
+        ),
+        (
+            "RSI Overbought",
+            presets
+                .iter()
+                .map(|(_, p)| format!("{:.0}", p.strategy.rsi_overbought))
+                .collect(),
+        ),
+        (
+            "SMA Period",
+            presets
+                .iter()
+                .map(|(_, p)| p.strategy.sma_period.to_string())
+                .collect(),
+        ),
+        (
+            "Stop Loss",
+            presets
+                .iter()
+                .map(|(_, p)| p.risk.stop_loss.to_string())
+                .collect(),
+        ),
+        (
+            "Max Holding Days",
+            presets
+                .iter()
+                .map(|(_, p)| fmt_days(p.strategy.max_holding_days))
+                .collect(),
+        ),
+        (
+            "Position Size",
+            presets
+                .iter()
+                .map(|(_, p)| format!("{:.0}%", p.risk.position_size_pct * 100.0))
+                .collect(),
+        ),
+        (
+            "Cash Reserve",
+            presets
+                .iter()
+                .map(|(_, p)| format!("{:.0}%", p.risk.cash_reserve_pct * 100.0))
+                .collect(),
+        ),
+        (
+            "VWAP Filter",
+            presets
+                .iter()
+                .map(|(_, p)| fmt_vwap(p.strategy.vwap_mode))
+                .collect(),
+        ),
+        (
+            "Hedge Enabled",
+            presets
+                .iter()
+                .map(|(_, p)| p.hedge.short_enabled.to_string())
+                .collect(),
+        ),
+    ];
+
+    println!();
+    println!("================================================================");
+    println!("  PARAMETER PRESETS");
+    println!("================================================================");
+    for (preset, _) in &presets {
+        println!("  {:<16} {}", preset.name(), preset.description());
+    }
+    println!("----------------------------------------------------------------");
+    print!("  {:<18}", "");
+    for (preset, _) in &presets {
+        print!("{:>16}", preset.name());
+    }
+    println!();
+    for (label, values) in rows {
+        print!("  {:<18}", label);
+        for value in values {
+            print!("{:>16}", value);
+        }
+        println!();
+    }
+    println!("================================================================");
+}
+
+fn print_text_report(
+    result: &BacktestResult,
+    sessions: &SessionAttribution,
+    regimes: Option<&RegimeBreakdown>,
+    conditional: Option<&ConditionalMetrics>,
+) {
+    println!();
+    println!("================================================================");
+    println!("  BACKTEST REPORT - RSI(2) TQQQ Mean Reversion Strategy");
+    println!("================================================================");
+    println!();
+    println!("  Period: {} to {}", result.start_date, result.end_date);
+    println!("  Duration: {} trading days", result.equity_curve.len());
+    println!("  Execution Time: {}ms", result.execution_time_ms);
+    println!();
+    println!("----------------------------------------------------------------");
+    println!("  CAPITAL");
+    println!("----------------------------------------------------------------");
+    println!("  Initial Capital:  ${:>12.2}", result.initial_capital);
+    println!("  Final Equity:     ${:>12.2}", result.final_equity);
+    println!("  Sizing:           {}", result.sizing_mode.describe());
     println!(
-        "  Sortino Ratio:    {:>12.3}",
-        result.metrics.sortino_ratio
+        "  Total Return:     ${:>12.2} ({:+.2}%)",
+        result.metrics.total_return, result.metrics.total_return_pct
     );
+    println!("  CAGR:             {:>12.2}%", result.metrics.cagr);
+    println!("  Time-Weighted:    {:>12.2}%", result.metrics.twr_pct);
     println!(
-        "  Max Drawdown:     {:>12.2}%",
-        result.metrics.max_drawdown
+        "  Money-Weighted:   {:>12.2}% (annualized)",
+        result.metrics.mwr_pct
     );
+    println!();
+    println!("----------------------------------------------------------------");
+    println!("  RISK METRICS");
+    println!("----------------------------------------------------------------");
+    println!("  Volatility (Ann): {:>12.2}%", result.metrics.volatility);
+    println!("  Sharpe Ratio:     {:>12.3}", result.metrics.sharpe_ratio);
+    println!("  Sortino Ratio:    {:>12.3}", result.metrics.sortino_ratio);
+    println!("  Max Drawdown:     {:>12.2}%", result.metrics.max_drawdown);
     println!(
         "  Max DD Duration:  {:>12} days",
         result.metrics.max_drawdown_duration_days
     );
-    println!(
-        "  Calmar Ratio:     {:>12.3}",
-        result.metrics.calmar_ratio
-    );
+    println!("  Calmar Ratio:     {:>12.3}", result.metrics.calmar_ratio);
     println!();
     println!("----------------------------------------------------------------");
     println!("  TRADE STATISTICS");
     println!("----------------------------------------------------------------");
+    let scope = match (result.metrics_scope, &result.hedge_metrics) {
+        (_, None) => "all trades",
+        (MetricsScope::Main, Some(_)) => "main-symbol trades (hedges below)",
+        (MetricsScope::Combined, Some(_)) => "all trades, hedges included",
+    };
+    println!("  Scope:            {}", scope);
+    println!("  Total Trades:     {:>12}", result.metrics.total_trades);
+    println!("  Winning Trades:   {:>12}", result.metrics.winning_trades);
+    println!("  Losing Trades:    {:>12}", result.metrics.losing_trades);
+    println!("  Win Rate:         {:>12.1}%", result.metrics.win_rate);
+    println!("  Avg Win:          ${:>12.2}", result.metrics.avg_win);
+    println!("  Avg Loss:         ${:>12.2}", result.metrics.avg_loss);
+    println!("  Profit Factor:    {:>12.3}", result.metrics.profit_factor);
     println!(
-        "  Total Trades:     {:>12}",
-        result.metrics.total_trades
+        "  Gross PF:         {:>12.3}  (before commissions)",
+        result.metrics.gross_profit_factor
     );
+    println!("  Expectancy:       ${:>12.2}", result.metrics.expectancy);
     println!(
-        "  Winning Trades:   {:>12}",
-        result.metrics.winning_trades
+        "  Avg Trade Dur.:   {:>12.1} days",
+        result.metrics.avg_trade_duration_days
     );
+    println!("  Best Trade:       ${:>12.2}", result.metrics.best_trade);
+    println!("  Worst Trade:      ${:>12.2}", result.metrics.worst_trade);
+    println!("  Exposure:         {:>12.1}%", result.metrics.exposure_pct);
     println!(
-        "  Losing Trades:    {:>12}",
-        result.metrics.losing_trades
+        "  R (avg/total):    {:>+6.2}R / {:+.2}R",
+        result.metrics.avg_r_multiple, result.metrics.total_r_multiple
+    );
+    for bucket in &result.metrics.r_multiple_histogram {
+        let bar = "#".repeat(bucket.count.min(40) as usize);
+        println!(
+            "    [{:+.0}R, {:+.0}R) {:>5} {}",
+            bucket.lower_r,
+            bucket.lower_r + 1.0,
+            bucket.count,
+            bar
+        );
+    }
+    println!(
+        "  Heat (max/avg):   {:>6.1}% / {:.1}%",
+        result.metrics.max_heat_pct, result.metrics.avg_heat_pct
+    );
+    if result.manifest.parameters.hedge.short_enabled {
+        println!(
+            "  Net Exp (max/avg):{:>6.1}% / {:.1}%",
+            result.metrics.max_net_exposure_pct, result.metrics.avg_net_exposure_pct
+        );
+    }
+    println!(
+        "  Worst Gap Held:   {:>12.2}%",
+        result.metrics.worst_overnight_gap_in_trade_pct
     );
-    println!("  Win Rate:         {:>12.1}%", result.metrics.win_rate);
     println!(
-        "  Avg Win:          ${:>12.2}",
-        result.metrics.avg_win
+        "  Efficiency (e/x): {:>6.2} / {:.2}",
+        result.metrics.avg_entry_efficiency, result.metrics.avg_exit_efficiency
     );
+    if result.metrics.suppressed_entries > 0 {
+        println!(
+            "  Suppressed Entr.: {:>12}",
+            result.metrics.suppressed_entries
+        );
+    }
+    if result.metrics.liquidity_capped_entries > 0 {
+        println!(
+            "  Liquidity Capped: {:>12}",
+            result.metrics.liquidity_capped_entries
+        );
+    }
+    if result.metrics.multi_bar_exits > 0 {
+        println!("  Multi-Bar Exits:  {:>12}", result.metrics.multi_bar_exits);
+    }
+    if let (Some(hedge), Some(combined)) = (&result.hedge_metrics, &result.combined_metrics) {
+        println!();
+        println!("----------------------------------------------------------------");
+        println!("  HEDGE TRADES");
+        println!("----------------------------------------------------------------");
+        println!("  Total Trades:     {:>12}", hedge.total_trades);
+        println!("  Win Rate:         {:>12.1}%", hedge.win_rate);
+        println!("  Expectancy:       ${:>12.2}", hedge.expectancy);
+        println!("  Exposure:         {:>12.1}%", hedge.exposure_pct);
+        println!(
+            "  Combined Win Rate:{:>12.1}% over {} trades",
+            combined.win_rate, combined.total_trades
+        );
+    }
+    let sequence = &result.metrics.trade_sequence;
+    let pct_or_na = |pct: Option<f64>| pct.map_or("n/a".to_string(), |p| format!("{:.1}%", p));
+    println!();
+    println!("----------------------------------------------------------------");
+    println!("  TRADE SEQUENCE");
+    println!("----------------------------------------------------------------");
+    println!("  Sign Correlation: {:>+12.3}", sequence.sign_correlation);
     println!(
-        "  Avg Loss:         ${:>12.2}",
-        result.metrics.avg_loss
+        "  Runs (exp.):      {:>12} ({:.1})",
+        sequence.runs, sequence.expected_runs
     );
+    println!("  Runs Z-Score:     {:>+12.2}", sequence.runs_z_score);
     println!(
-        "  Profit Factor:    {:>12.3}",
-        result.metrics.profit_factor
+        "  Win After W / L:  {:>12} / {}",
+        pct_or_na(sequence.win_after_win_pct),
+        pct_or_na(sequence.win_after_loss_pct)
     );
+    if sequence.tied_exits > 0 {
+        println!("  Tied Exits:       {:>12}", sequence.tied_exits);
+    }
+    println!();
+    println!("----------------------------------------------------------------");
+    println!("  COSTS");
+    println!("----------------------------------------------------------------");
     println!(
-        "  Expectancy:       ${:>12.2}",
-        result.metrics.expectancy
+        "  Avg Round Trip:   ${:>12.2} ({:.1} bps)",
+        result.metrics.avg_round_trip_cost, result.metrics.avg_round_trip_cost_bps
     );
     println!(
-        "  Avg Trade Dur.:   {:>12.1} days",
-        result.metrics.avg_trade_duration_days
+        "  Breakeven Win %:  {:>12.1}%",
+        result.metrics.breakeven_win_rate_pct
     );
+    println!();
+    println!("----------------------------------------------------------------");
     println!(
-        "  Best Trade:       ${:>12.2}",
-        result.metrics.best_trade
+        "  SESSION ATTRIBUTION ({} trades, before costs)",
+        sessions.trades
     );
+    println!("----------------------------------------------------------------");
     println!(
-        "  Worst Trade:      ${:>12.2}",
-        result.metrics.worst_trade
+        "  Overnight P&L:    ${:>12.2} ({:.1}%)",
+        sessions.overnight_pnl, sessions.overnight_share_pct
     );
     println!(
-        "  Exposure:         {:>12.1}%",
-        result.metrics.exposure_pct
+        "  Intraday P&L:     ${:>12.2} ({:.1}%)",
+        sessions.intraday_pnl, sessions.intraday_share_pct
     );
+    if let Some(regimes) = regimes {
+        println!();
+        println!("----------------------------------------------------------------");
+        println!("  VOLATILITY REGIMES ({}-bar lookback)", regimes.lookback);
+        println!("----------------------------------------------------------------");
+        println!(
+            "  Cutoffs:          {:.1}% / {:.1}% annualized",
+            regimes.low_cutoff, regimes.high_cutoff
+        );
+        println!(
+            "  {:<12} {:>7} {:>8} {:>11} {:>8}",
+            "Regime", "Trades", "Win%", "Avg P&L", "PF"
+        );
+        for stats in &regimes.regimes {
+            println!(
+                "  {:<12} {:>7} {:>7.1}% {:>+11.2} {:>8.2}",
+                stats.regime.name(),
+                stats.trades,
+                stats.win_rate,
+                stats.avg_pnl,
+                stats.profit_factor
+            );
+        }
+        if regimes.unclassified > 0 {
+            println!("  Unclassified:     {:>12}", regimes.unclassified);
+        }
+    }
+    if let Some(conditional) = conditional {
+        let (up, down) = (&conditional.up, &conditional.down);
+        println!();
+        println!("----------------------------------------------------------------");
+        println!(
+            "  BENCHMARK REGIMES (vs {}-bar SMA)",
+            conditional.sma_period
+        );
+        println!("----------------------------------------------------------------");
+        println!("  {:<18} {:>12} {:>12}", "", "Up", "Down");
+        println!("  {:<18} {:>12} {:>12}", "Days", up.days, down.days);
+        println!("  {:<18} {:>11.2}% {:>11.2}%", "CAGR", up.cagr, down.cagr);
+        println!(
+            "  {:<18} {:>11.2}% {:>11.2}%",
+            "Max Drawdown", up.max_drawdown, down.max_drawdown
+        );
+        println!("  {:<18} {:>12} {:>12}", "Trades", up.trades, down.trades);
+        println!(
+            "  {:<18} {:>11.1}% {:>11.1}%",
+            "Win Rate", up.win_rate, down.win_rate
+        );
+        if conditional.unclassified_trades > 0 {
+            println!(
+                "  Unclassified:     {:>12}",
+                conditional.unclassified_trades
+            );
+        }
+        if let Some((_, beta)) = conditional.rolling_beta.last() {
+            println!(
+                "  Rolling Beta:     {:>12.3} (last {} days)",
+                beta, ROLLING_BETA_WINDOW
+            );
+        }
+    }
     println!();
     println!("================================================================");
 
@@ -266,12 +2409,18 @@ fn print_text_report(result: &BacktestResult) {
                 .exit_date
                 .map(|d| d.format("%Y-%m-%d").to_string())
                 .unwrap_or_else(|| "open".to_string());
+            let r = trade
+                .r_multiple
+                .map(|r| format!(" {:+.2}R", r))
+                .unwrap_or_default();
             println!(
-                "  {} -> {} | P&L: ${:+.2} ({:+.1}%) | {} days",
+                "  {} -> {} | P&L: ${:+.2} net, ${:+.2} gross ({:+.1}%){} | {} days",
                 trade.entry_date.format("%Y-%m-%d"),
                 exit_date,
-                trade.pnl,
+                trade.net_pnl,
+                trade.gross_pnl,
                 trade.pnl_pct,
+                r,
                 trade.holding_days
             );
         }
@@ -1,5 +1,12 @@
-use chrono::{DateTime, Utc};
-use common::{PerformanceMetrics, Trade};
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{
+    Bar, GapBucket, PerformanceMetrics, RBucket, ResampleFrequency, SequenceStats, Side, Trade,
+};
+use serde::Serialize;
+
+use crate::indicators::calculate_sma;
 
 const TRADING_DAYS_PER_YEAR: f64 = 252.0;
 const RISK_FREE_RATE: f64 = 0.05; // 5% annual risk-free rate
@@ -18,7 +25,10 @@ impl MetricsCalculator {
             return PerformanceMetrics::default();
         }
 
-        let final_equity = equity_curve.last().map(|(_, e)| *e).unwrap_or(initial_capital);
+        let final_equity = equity_curve
+            .last()
+            .map(|(_, e)| *e)
+            .unwrap_or(initial_capital);
         let total_return = final_equity - initial_capital;
         let total_return_pct = (total_return / initial_capital) * 100.0;
 
@@ -53,10 +63,28 @@ impl MetricsCalculator {
         // Exposure percentage
         let exposure_pct = Self::calculate_exposure(equity_curve, trades);
 
+        // R-multiples
+        let r_multiples: Vec<f64> = trades.iter().filter_map(|t| t.r_multiple).collect();
+        let total_r_multiple: f64 = r_multiples.iter().sum();
+        let avg_r_multiple = if r_multiples.is_empty() {
+            0.0
+        } else {
+            total_r_multiple / r_multiples.len() as f64
+        };
+
+        // Breakeven win rate: p * avg_win = (1 - p) * avg_loss
+        let breakeven_win_rate_pct = if trade_stats.avg_win + trade_stats.avg_loss > 0.0 {
+            trade_stats.avg_loss / (trade_stats.avg_win + trade_stats.avg_loss) * 100.0
+        } else {
+            0.0
+        };
+
         PerformanceMetrics {
             total_return,
             total_return_pct,
             cagr,
+            twr_pct: total_return_pct,
+            mwr_pct: Self::money_weighted_return(equity_curve, &[], initial_capital),
             volatility,
             sharpe_ratio,
             sortino_ratio,
@@ -70,12 +98,342 @@ impl MetricsCalculator {
             avg_win: trade_stats.avg_win,
             avg_loss: trade_stats.avg_loss,
             profit_factor: trade_stats.profit_factor,
+            gross_profit_factor: trade_stats.gross_profit_factor,
             expectancy: trade_stats.expectancy,
             avg_trade_duration_days: trade_stats.avg_duration,
             best_trade,
             worst_trade,
             exposure_pct,
+            // Filled in by the engine, which owns the suppression log
+            suppressed_entries: 0,
+            liquidity_capped_entries: 0,
+            multi_bar_exits: trades.iter().filter(|t| !t.fills.is_empty()).count() as u32,
+            // Filled in by the engine, which tracks open-position risk
+            max_heat_pct: 0.0,
+            avg_heat_pct: 0.0,
+            max_net_exposure_pct: 0.0,
+            avg_net_exposure_pct: 0.0,
+            // Filled in by `apply_costs`, which needs the cost model
+            avg_round_trip_cost: 0.0,
+            avg_round_trip_cost_bps: 0.0,
+            breakeven_win_rate_pct,
+            avg_r_multiple,
+            total_r_multiple,
+            r_multiple_histogram: Self::r_multiple_histogram(trades),
+            // Filled in by `apply_overnight_gaps`, which needs the bars
+            worst_overnight_gap_in_trade_pct: 0.0,
+            overnight_gap_histogram: Vec::new(),
+            avg_entry_efficiency: mean(trades.iter().filter_map(|t| t.entry_efficiency)),
+            avg_exit_efficiency: mean(trades.iter().filter_map(|t| t.exit_efficiency)),
+            trade_sequence: Self::trade_sequence_stats(trades),
+        }
+    }
+
+    /// `equity_curve` with external cash flows taken out. Each point's
+    /// return over the previous one, less the flows booked at that point
+    /// (at the close), is chain-linked from the initial capital, so metrics
+    /// computed on this curve are time-weighted.
+    pub fn time_weighted_curve(
+        equity_curve: &[(DateTime<Utc>, f64)],
+        flows: &[(DateTime<Utc>, f64)],
+        initial_capital: f64,
+    ) -> Vec<(DateTime<Utc>, f64)> {
+        let mut flows = flows.iter().peekable();
+        let mut previous = initial_capital;
+        let mut nav = initial_capital;
+
+        equity_curve
+            .iter()
+            .map(|&(timestamp, equity)| {
+                let mut flow = 0.0;
+                while let Some((_, amount)) = flows.next_if(|(t, _)| *t <= timestamp) {
+                    flow += amount;
+                }
+                if previous > 0.0 {
+                    nav *= (equity - flow) / previous;
+                }
+                previous = equity;
+                (timestamp, nav)
+            })
+            .collect()
+    }
+
+    /// [`calculate`](Self::calculate) in one pass over streamed equity
+    /// points and trades (in exit order), holding neither
+    pub fn calculate_streamed(
+        equity_curve: impl IntoIterator<Item = (DateTime<Utc>, f64)>,
+        trades: impl IntoIterator<Item = Trade>,
+        initial_capital: f64,
+    ) -> PerformanceMetrics {
+        let mut metrics = StreamingMetrics::new(initial_capital);
+        for (timestamp, equity) in equity_curve {
+            metrics.push_equity(timestamp, equity);
+        }
+        for trade in trades {
+            metrics.push_trade(&trade, 0.0, None);
+        }
+        metrics.finish()
+    }
+
+    /// Money-weighted return: the annualized IRR (%) of investing the
+    /// initial capital at the first point, the cash `flows` (deposits paid
+    /// in, withdrawals paid out) and receiving the final equity, with time
+    /// in calendar days over 365. 0 when no rate solves it.
+    pub fn money_weighted_return(
+        equity_curve: &[(DateTime<Utc>, f64)],
+        flows: &[(DateTime<Utc>, f64)],
+        initial_capital: f64,
+    ) -> f64 {
+        let (Some(&(start, _)), Some(&(end, final_equity))) =
+            (equity_curve.first(), equity_curve.last())
+        else {
+            return 0.0;
+        };
+        let years = |t: DateTime<Utc>| (t - start).num_seconds() as f64 / (365.0 * 86_400.0);
+        if years(end) <= 0.0 {
+            return 0.0;
         }
+
+        let mut cash_flows = vec![(0.0, -initial_capital)];
+        cash_flows.extend(flows.iter().map(|&(t, amount)| (years(t), -amount)));
+        cash_flows.push((years(end), final_equity));
+        let npv = |rate: f64| -> f64 {
+            cash_flows
+                .iter()
+                .map(|(t, amount)| amount / (1.0 + rate).powf(*t))
+                .sum()
+        };
+
+        let (mut low, mut high) = (-0.9999, 1.0);
+        while npv(high) > 0.0 && high < 1e6 {
+            high *= 2.0;
+        }
+        if npv(low).signum() == npv(high).signum() {
+            return 0.0;
+        }
+        for _ in 0..200 {
+            let mid = (low + high) / 2.0;
+            if npv(mid).signum() == npv(low).signum() {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        (low + high) / 2.0 * 100.0
+    }
+
+    /// Fill in the cash-flow figures of metrics computed over the
+    /// [`time_weighted_curve`](Self::time_weighted_curve) of `equity_curve`
+    pub fn apply_cash_flows(
+        metrics: &mut PerformanceMetrics,
+        equity_curve: &[(DateTime<Utc>, f64)],
+        flows: &[(DateTime<Utc>, f64)],
+        initial_capital: f64,
+    ) {
+        let Some(&(_, final_equity)) = equity_curve.last() else {
+            return;
+        };
+        let net_flows: f64 = flows.iter().map(|(_, amount)| amount).sum();
+        metrics.total_return = final_equity - initial_capital - net_flows;
+        metrics.mwr_pct = Self::money_weighted_return(equity_curve, flows, initial_capital);
+    }
+
+    /// Serial dependence of trade outcomes. Trades are taken in exit order
+    /// whatever order they are passed in; the sort is stable, so trades
+    /// exiting at the same time keep their recorded order (and are counted
+    /// in `tied_exits`), and trades still open go last.
+    pub fn trade_sequence_stats(trades: &[Trade]) -> SequenceStats {
+        let mut ordered: Vec<&Trade> = trades.iter().collect();
+        ordered.sort_by_key(|t| (t.exit_date.is_none(), t.exit_date));
+        let tied_exits = ordered
+            .windows(2)
+            .filter(|w| w[0].exit_date.is_some() && w[0].exit_date == w[1].exit_date)
+            .count() as u32;
+
+        let wins: Vec<bool> = ordered.iter().map(|t| t.pnl > 0.0).collect();
+        if wins.is_empty() {
+            return SequenceStats::default();
+        }
+
+        // Wald-Wolfowitz runs test
+        let runs = 1 + wins.windows(2).filter(|w| w[0] != w[1]).count() as u32;
+        let n = wins.len() as f64;
+        let n_win = wins.iter().filter(|&&w| w).count() as f64;
+        let n_loss = n - n_win;
+        let product = 2.0 * n_win * n_loss;
+        let expected_runs = product / n + 1.0;
+        let runs_z_score = if n_win > 0.0 && n_loss > 0.0 && n > 1.0 {
+            let variance = product * (product - n) / (n * n * (n - 1.0));
+            if variance > 0.0 {
+                (runs as f64 - expected_runs) / variance.sqrt()
+            } else {
+                0.0
+            }
+        } else {
+            0.0
+        };
+
+        let signs: Vec<f64> = wins.iter().map(|&w| if w { 1.0 } else { -1.0 }).collect();
+        let win_rate_after = |previous: bool| {
+            let next: Vec<bool> = wins
+                .windows(2)
+                .filter(|w| w[0] == previous)
+                .map(|w| w[1])
+                .collect();
+            if next.is_empty() {
+                None
+            } else {
+                Some(next.iter().filter(|&&w| w).count() as f64 / next.len() as f64 * 100.0)
+            }
+        };
+
+        SequenceStats {
+            sign_correlation: Self::lag1_correlation(&signs),
+            runs,
+            expected_runs,
+            runs_z_score,
+            win_after_win_pct: win_rate_after(true),
+            win_after_loss_pct: win_rate_after(false),
+            tied_exits,
+        }
+    }
+
+    /// Pearson correlation of each value with the next; 0 when either side
+    /// has no variance
+    fn lag1_correlation(values: &[f64]) -> f64 {
+        if values.len() < 3 {
+            return 0.0;
+        }
+        let (x, y) = (&values[..values.len() - 1], &values[1..]);
+        let m = x.len() as f64;
+        let mean_x = x.iter().sum::<f64>() / m;
+        let mean_y = y.iter().sum::<f64>() / m;
+        let cov: f64 = x
+            .iter()
+            .zip(y)
+            .map(|(a, b)| (a - mean_x) * (b - mean_y))
+            .sum();
+        let var_x: f64 = x.iter().map(|a| (a - mean_x).powi(2)).sum();
+        let var_y: f64 = y.iter().map(|b| (b - mean_y).powi(2)).sum();
+        if var_x > 0.0 && var_y > 0.0 {
+            cov / (var_x * var_y).sqrt()
+        } else {
+            0.0
+        }
+    }
+
+    /// Count values per unit-wide bucket `[floor, floor + 1)`, contiguous
+    /// from the lowest to the highest; non-finite values are left out
+    fn unit_buckets(values: impl Iterator<Item = f64>) -> Vec<(f64, u32)> {
+        let lowers: Vec<f64> = values.filter(|v| v.is_finite()).map(f64::floor).collect();
+        let (Some(low), Some(high)) = (
+            lowers.iter().copied().reduce(f64::min),
+            lowers.iter().copied().reduce(f64::max),
+        ) else {
+            return Vec::new();
+        };
+
+        let mut buckets: Vec<(f64, u32)> = (0..=(high - low) as usize)
+            .map(|k| (low + k as f64, 0))
+            .collect();
+        for lower in lowers {
+            buckets[(lower - low) as usize].1 += 1;
+        }
+        buckets
+    }
+
+    /// Count trades per 1R bucket, contiguous from the lowest to the highest
+    /// R-multiple. Trades without an R-multiple are left out.
+    pub fn r_multiple_histogram(trades: &[Trade]) -> Vec<RBucket> {
+        Self::unit_buckets(trades.iter().filter_map(|t| t.r_multiple))
+            .into_iter()
+            .map(|(lower_r, count)| RBucket { lower_r, count })
+            .collect()
+    }
+
+    /// Worst overnight gap (%) `trade` sat through: the lowest open versus
+    /// prior close over the bars after its entry bar, up to its exit bar.
+    /// None when it never held past a close.
+    pub fn worst_overnight_gap(trade: &Trade, bars: &[Bar]) -> Option<f64> {
+        let exit = trade.exit_date?;
+        let start = bars
+            .partition_point(|b| b.timestamp <= trade.entry_date)
+            .max(1);
+        let end = bars.partition_point(|b| b.timestamp <= exit);
+        (start..end)
+            .map(|i| (bars[i].open / bars[i - 1].close - 1.0) * 100.0)
+            .reduce(f64::min)
+    }
+
+    /// Entry and exit efficiency of a closed trade over the bars from its
+    /// entry to its exit: for a long, the entry's distance below the highest
+    /// high over the range to the lowest low, and the exit's above the low;
+    /// mirrored for a short. 0.5 each when the trade spans a single bar or
+    /// the range is empty. None for open trades and trades outside `bars`.
+    pub fn trade_efficiency(trade: &Trade, bars: &[Bar]) -> Option<(f64, f64)> {
+        let (exit_date, exit_price) = (trade.exit_date?, trade.exit_price?);
+        let start = bars.partition_point(|b| b.timestamp < trade.entry_date);
+        let end = bars.partition_point(|b| b.timestamp <= exit_date);
+        if start >= end {
+            return None;
+        }
+        let held = &bars[start..end];
+        let high = held.iter().map(|b| b.high).fold(f64::MIN, f64::max);
+        let low = held.iter().map(|b| b.low).fold(f64::MAX, f64::min);
+        let range = high - low;
+        if held.len() < 2 || range <= 0.0 {
+            return Some((0.5, 0.5));
+        }
+
+        let (entry, exit) = match trade.side {
+            Side::Cover | Side::SyntheticCover => (trade.entry_price - low, high - exit_price),
+            _ => (high - trade.entry_price, exit_price - low),
+        };
+        Some((
+            (entry / range).clamp(0.0, 1.0),
+            (exit / range).clamp(0.0, 1.0),
+        ))
+    }
+
+    /// Fill in the overnight gap metrics, measuring long trades against
+    /// `bars` and hedge trades against `hedge_bars`
+    pub fn apply_overnight_gaps(
+        metrics: &mut PerformanceMetrics,
+        trades: &[Trade],
+        bars: &[Bar],
+        hedge_bars: Option<&[Bar]>,
+    ) {
+        let gaps: Vec<f64> = trades
+            .iter()
+            .filter_map(|t| match t.side {
+                Side::HedgeSell => hedge_bars.and_then(|h| Self::worst_overnight_gap(t, h)),
+                _ => Self::worst_overnight_gap(t, bars),
+            })
+            .collect();
+
+        metrics.worst_overnight_gap_in_trade_pct = gaps.iter().copied().fold(0.0, f64::min);
+        metrics.overnight_gap_histogram = Self::unit_buckets(gaps.into_iter())
+            .into_iter()
+            .map(|(lower_pct, count)| GapBucket { lower_pct, count })
+            .collect();
+    }
+
+    /// Fill in per-trade cost metrics, `costs[i]` being the modeled
+    /// round-trip cost of `trades[i]` in dollars
+    pub fn apply_costs(metrics: &mut PerformanceMetrics, trades: &[Trade], costs: &[f64]) {
+        if trades.is_empty() {
+            return;
+        }
+
+        let total_cost: f64 = costs.iter().sum();
+        let total_notional: f64 = trades.iter().map(|t| t.entry_price * t.quantity).sum();
+
+        metrics.avg_round_trip_cost = total_cost / trades.len() as f64;
+        metrics.avg_round_trip_cost_bps = if total_notional > 0.0 {
+            total_cost / total_notional * 10_000.0
+        } else {
+            0.0
+        };
     }
 
     /// Calculate daily returns from equity curve
@@ -106,7 +464,11 @@ impl MetricsCalculator {
 
         let n = daily_returns.len() as f64;
         let mean: f64 = daily_returns.iter().sum::<f64>() / n;
-        let variance: f64 = daily_returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n;
+        let variance: f64 = daily_returns
+            .iter()
+            .map(|r| (r - mean).powi(2))
+            .sum::<f64>()
+            / n;
 
         variance.sqrt() * TRADING_DAYS_PER_YEAR.sqrt() * 100.0
     }
@@ -164,7 +526,6 @@ impl MetricsCalculator {
 
         let mut max_equity = equity_curve[0].1;
         let mut max_drawdown = 0.0;
-        let mut max_dd_start = 0;
         let mut max_dd_duration = 0i64;
         let mut current_dd_start = 0;
 
@@ -177,14 +538,22 @@ impl MetricsCalculator {
             let drawdown = (max_equity - equity) / max_equity * 100.0;
             if drawdown > max_drawdown {
                 max_drawdown = drawdown;
-                max_dd_start = current_dd_start;
-                max_dd_duration = (i - max_dd_start) as i64;
+                max_dd_duration = (i - current_dd_start) as i64;
             }
         }
 
         (max_drawdown, max_dd_duration)
     }
 
+    /// Resample an equity curve to one point per calendar period, keeping
+    /// the last observation of each (see [`ResampleFrequency::resample`])
+    pub fn resample_equity(
+        equity_curve: &[(DateTime<Utc>, f64)],
+        frequency: ResampleFrequency,
+    ) -> Vec<(NaiveDate, f64)> {
+        frequency.resample(equity_curve)
+    }
+
     /// Calculate drawdown curve
     pub fn calculate_drawdown_curve(
         equity_curve: &[(DateTime<Utc>, f64)],
@@ -220,6 +589,7 @@ impl MetricsCalculator {
         let mut losing = 0u32;
         let mut total_wins = 0.0;
         let mut total_losses = 0.0;
+        let (mut gross_wins, mut gross_losses) = (0.0, 0.0);
         let mut total_duration = 0i64;
         let mut best = f64::MIN;
         let mut worst = f64::MAX;
@@ -232,6 +602,11 @@ impl MetricsCalculator {
                 losing += 1;
                 total_losses += trade.pnl.abs();
             }
+            if trade.gross_pnl > 0.0 {
+                gross_wins += trade.gross_pnl;
+            } else {
+                gross_losses -= trade.gross_pnl;
+            }
 
             total_duration += trade.holding_days;
             best = best.max(trade.pnl);
@@ -257,16 +632,9 @@ impl MetricsCalculator {
             0.0
         };
 
-        let profit_factor = if total_losses > 0.0 {
-            total_wins / total_losses
-        } else if total_wins > 0.0 {
-            f64::INFINITY
-        } else {
-            0.0
-        };
+        let profit_factor = wins_over_losses(total_wins, total_losses);
 
-        let expectancy =
-            (win_rate / 100.0 * avg_win) - ((1.0 - win_rate / 100.0) * avg_loss);
+        let expectancy = (win_rate / 100.0 * avg_win) - ((1.0 - win_rate / 100.0) * avg_loss);
 
         let avg_duration = total_duration as f64 / n;
 
@@ -278,6 +646,7 @@ impl MetricsCalculator {
                 avg_win,
                 avg_loss,
                 profit_factor,
+                gross_profit_factor: wins_over_losses(gross_wins, gross_losses),
                 expectancy,
                 avg_duration,
             },
@@ -287,10 +656,7 @@ impl MetricsCalculator {
     }
 
     /// Calculate exposure percentage
-    fn calculate_exposure(
-        equity_curve: &[(DateTime<Utc>, f64)],
-        trades: &[Trade],
-    ) -> f64 {
+    fn calculate_exposure(equity_curve: &[(DateTime<Utc>, f64)], trades: &[Trade]) -> f64 {
         if equity_curve.is_empty() || trades.is_empty() {
             return 0.0;
         }
@@ -310,14 +676,742 @@ struct TradeStats {
     avg_win: f64,
     avg_loss: f64,
     profit_factor: f64,
+    gross_profit_factor: f64,
     expectancy: f64,
     avg_duration: f64,
 }
 
+/// One-pass counterpart of [`MetricsCalculator::calculate`] for runs too
+/// long to hold: equity points and trades are pushed as they happen and only
+/// running aggregates are kept. Volatility uses Welford's update, so figures
+/// agree with the two-pass ones to rounding.
+///
+/// Trades must arrive in exit order, as the engine closes them; the
+/// sequence statistics take them in the order pushed.
+#[derive(Debug, Clone)]
+pub struct StreamingMetrics {
+    initial_capital: f64,
+    points: usize,
+    first: Option<DateTime<Utc>>,
+    last: Option<(DateTime<Utc>, f64)>,
+    returns: Welford,
+    /// Returns below the daily risk-free rate, and their summed squared
+    /// shortfall
+    downside: (usize, f64),
+    peak: f64,
+    peak_index: usize,
+    max_drawdown: f64,
+    max_drawdown_duration: i64,
+    trades: u32,
+    winning: u32,
+    losing: u32,
+    total_wins: f64,
+    total_losses: f64,
+    /// Gross P&L of the winning and (as a positive sum) losing trades
+    gross_wins: f64,
+    gross_losses: f64,
+    total_duration: i64,
+    invested_days: i64,
+    best: f64,
+    worst: f64,
+    r_multiples: u32,
+    total_r_multiple: f64,
+    r_buckets: BTreeMap<i64, u32>,
+    multi_bar_exits: u32,
+    sequence: SequenceAccumulator,
+    total_cost: f64,
+    total_notional: f64,
+    worst_gap: f64,
+    gap_buckets: BTreeMap<i64, u32>,
+    /// Trades with efficiencies, and their summed entry and exit efficiency
+    efficiencies: (u32, f64, f64),
+}
+
+/// Running mean and sum of squared deviations
+#[derive(Debug, Clone, Copy, Default)]
+struct Welford {
+    n: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl Welford {
+    fn push(&mut self, x: f64) {
+        self.n += 1;
+        let delta = x - self.mean;
+        self.mean += delta / self.n as f64;
+        self.m2 += delta * (x - self.mean);
+    }
+}
+
+/// What [`MetricsCalculator::trade_sequence_stats`] needs, kept per trade
+#[derive(Debug, Clone, Default)]
+struct SequenceAccumulator {
+    last: Option<(Option<DateTime<Utc>>, bool)>,
+    tied_exits: u32,
+    wins: u32,
+    changes: u32,
+    /// (trades, wins) following a win and following a loss
+    after_win: (u32, u32),
+    after_loss: (u32, u32),
+    /// Sums over consecutive (previous, next) outcome signs
+    pairs: u32,
+    sum_x: f64,
+    sum_y: f64,
+    sum_xy: f64,
+    sum_xx: f64,
+    sum_yy: f64,
+}
+
+impl SequenceAccumulator {
+    fn push(&mut self, exit: Option<DateTime<Utc>>, win: bool) {
+        if let Some((last_exit, last_win)) = self.last {
+            if last_exit.is_some() && last_exit == exit {
+                self.tied_exits += 1;
+            }
+            if last_win != win {
+                self.changes += 1;
+            }
+            let after = if last_win {
+                &mut self.after_win
+            } else {
+                &mut self.after_loss
+            };
+            after.0 += 1;
+            after.1 += win as u32;
+
+            let sign = |w: bool| if w { 1.0 } else { -1.0 };
+            let (x, y) = (sign(last_win), sign(win));
+            self.pairs += 1;
+            self.sum_x += x;
+            self.sum_y += y;
+            self.sum_xy += x * y;
+            self.sum_xx += x * x;
+            self.sum_yy += y * y;
+        }
+        self.wins += win as u32;
+        self.last = Some((exit, win));
+    }
+
+    fn stats(&self, n: u32) -> SequenceStats {
+        if n == 0 {
+            return SequenceStats::default();
+        }
+        let runs = 1 + self.changes;
+        let n = n as f64;
+        let n_win = self.wins as f64;
+        let n_loss = n - n_win;
+        let product = 2.0 * n_win * n_loss;
+        let expected_runs = product / n + 1.0;
+        let runs_z_score = if n_win > 0.0 && n_loss > 0.0 && n > 1.0 {
+            let variance = product * (product - n) / (n * n * (n - 1.0));
+            if variance > 0.0 {
+                (runs as f64 - expected_runs) / variance.sqrt()
+            } else {
+                0.0
+            }
+        } else {
+            0.0
+        };
+        let rate =
+            |(trades, wins): (u32, u32)| (trades > 0).then(|| wins as f64 / trades as f64 * 100.0);
+
+        SequenceStats {
+            sign_correlation: self.sign_correlation(),
+            runs,
+            expected_runs,
+            runs_z_score,
+            win_after_win_pct: rate(self.after_win),
+            win_after_loss_pct: rate(self.after_loss),
+            tied_exits: self.tied_exits,
+        }
+    }
+
+    fn sign_correlation(&self) -> f64 {
+        if self.pairs < 2 {
+            return 0.0;
+        }
+        let m = self.pairs as f64;
+        let cov = self.sum_xy - self.sum_x * self.sum_y / m;
+        let var_x = self.sum_xx - self.sum_x * self.sum_x / m;
+        let var_y = self.sum_yy - self.sum_y * self.sum_y / m;
+        if var_x > 0.0 && var_y > 0.0 {
+            cov / (var_x * var_y).sqrt()
+        } else {
+            0.0
+        }
+    }
+}
+
+impl StreamingMetrics {
+    pub fn new(initial_capital: f64) -> Self {
+        Self {
+            initial_capital,
+            points: 0,
+            first: None,
+            last: None,
+            returns: Welford::default(),
+            downside: (0, 0.0),
+            peak: 0.0,
+            peak_index: 0,
+            max_drawdown: 0.0,
+            max_drawdown_duration: 0,
+            trades: 0,
+            winning: 0,
+            losing: 0,
+            total_wins: 0.0,
+            total_losses: 0.0,
+            gross_wins: 0.0,
+            gross_losses: 0.0,
+            total_duration: 0,
+            invested_days: 0,
+            best: f64::MIN,
+            worst: f64::MAX,
+            r_multiples: 0,
+            total_r_multiple: 0.0,
+            r_buckets: BTreeMap::new(),
+            multi_bar_exits: 0,
+            sequence: SequenceAccumulator::default(),
+            total_cost: 0.0,
+            total_notional: 0.0,
+            worst_gap: 0.0,
+            gap_buckets: BTreeMap::new(),
+            efficiencies: (0, 0.0, 0.0),
+        }
+    }
+
+    /// Add the next equity point; returns its drawdown (%) from the peak
+    /// so far
+    pub fn push_equity(&mut self, timestamp: DateTime<Utc>, equity: f64) -> f64 {
+        if let Some((_, prev)) = self.last {
+            let r = if prev != 0.0 {
+                (equity - prev) / prev
+            } else {
+                0.0
+            };
+            self.returns.push(r);
+            let daily_risk_free = RISK_FREE_RATE / TRADING_DAYS_PER_YEAR;
+            if r < daily_risk_free {
+                self.downside.0 += 1;
+                self.downside.1 += (r - daily_risk_free).powi(2);
+            }
+        } else {
+            self.first = Some(timestamp);
+            self.peak = equity;
+        }
+
+        if equity > self.peak {
+            self.peak = equity;
+            self.peak_index = self.points;
+        }
+        let drawdown = (self.peak - equity) / self.peak * 100.0;
+        if drawdown > self.max_drawdown {
+            self.max_drawdown = drawdown;
+            self.max_drawdown_duration = (self.points - self.peak_index) as i64;
+        }
+
+        self.points += 1;
+        self.last = Some((timestamp, equity));
+        if self.peak > 0.0 {
+            drawdown
+        } else {
+            0.0
+        }
+    }
+
+    /// Add the next closed trade, with its modeled round-trip cost (dollars)
+    /// and worst overnight gap (%) as [`MetricsCalculator::apply_costs`] and
+    /// [`MetricsCalculator::apply_overnight_gaps`] take them; 0 and None
+    /// leave those metrics as `calculate` does
+    pub fn push_trade(&mut self, trade: &Trade, cost: f64, overnight_gap: Option<f64>) {
+        self.trades += 1;
+        if trade.pnl > 0.0 {
+            self.winning += 1;
+            self.total_wins += trade.pnl;
+        } else if trade.pnl < 0.0 {
+            self.losing += 1;
+            self.total_losses += trade.pnl.abs();
+        }
+        if trade.gross_pnl > 0.0 {
+            self.gross_wins += trade.gross_pnl;
+        } else {
+            self.gross_losses -= trade.gross_pnl;
+        }
+        self.total_duration += trade.holding_days;
+        self.invested_days += trade.holding_days.max(1);
+        self.best = self.best.max(trade.pnl);
+        self.worst = self.worst.min(trade.pnl);
+        if let Some(r) = trade.r_multiple {
+            self.r_multiples += 1;
+            self.total_r_multiple += r;
+            if r.is_finite() {
+                *self.r_buckets.entry(r.floor() as i64).or_default() += 1;
+            }
+        }
+        if let (Some(entry), Some(exit)) = (trade.entry_efficiency, trade.exit_efficiency) {
+            self.efficiencies.0 += 1;
+            self.efficiencies.1 += entry;
+            self.efficiencies.2 += exit;
+        }
+        self.multi_bar_exits += !trade.fills.is_empty() as u32;
+        self.sequence.push(trade.exit_date, trade.pnl > 0.0);
+
+        self.total_cost += cost;
+        self.total_notional += trade.entry_price * trade.quantity;
+        if let Some(gap) = overnight_gap {
+            self.worst_gap = self.worst_gap.min(gap);
+            if gap.is_finite() {
+                *self.gap_buckets.entry(gap.floor() as i64).or_default() += 1;
+            }
+        }
+    }
+
+    /// The metrics of everything pushed so far
+    pub fn finish(&self) -> PerformanceMetrics {
+        let Some((end, final_equity)) = self.last else {
+            return PerformanceMetrics::default();
+        };
+        let initial_capital = self.initial_capital;
+        let total_return = final_equity - initial_capital;
+        let total_return_pct = (total_return / initial_capital) * 100.0;
+
+        let returns = self.returns;
+        let volatility = if returns.n > 0 {
+            (returns.m2 / returns.n as f64).sqrt() * TRADING_DAYS_PER_YEAR.sqrt() * 100.0
+        } else {
+            0.0
+        };
+        let sharpe_ratio = if returns.n == 0 || volatility == 0.0 {
+            0.0
+        } else {
+            (returns.mean * TRADING_DAYS_PER_YEAR * 100.0 - RISK_FREE_RATE * 100.0) / volatility
+        };
+        let sortino_ratio = match self.downside {
+            _ if returns.n == 0 => 0.0,
+            (0, _) => f64::INFINITY,
+            (_, shortfall) => {
+                let deviation =
+                    (shortfall / returns.n as f64).sqrt() * TRADING_DAYS_PER_YEAR.sqrt();
+                if deviation == 0.0 {
+                    0.0
+                } else {
+                    (returns.mean * TRADING_DAYS_PER_YEAR - RISK_FREE_RATE) / deviation
+                }
+            }
+        };
+
+        let years = self.points as f64 / TRADING_DAYS_PER_YEAR;
+        let cagr = if years > 0.0 && final_equity > 0.0 && initial_capital > 0.0 {
+            ((final_equity / initial_capital).powf(1.0 / years) - 1.0) * 100.0
+        } else {
+            0.0
+        };
+        let calmar_ratio = if self.max_drawdown != 0.0 {
+            cagr / self.max_drawdown.abs()
+        } else {
+            0.0
+        };
+
+        let n = self.trades as f64;
+        let win_rate = if self.trades > 0 {
+            self.winning as f64 / n * 100.0
+        } else {
+            0.0
+        };
+        let avg_win = if self.winning > 0 {
+            self.total_wins / self.winning as f64
+        } else {
+            0.0
+        };
+        let avg_loss = if self.losing > 0 {
+            self.total_losses / self.losing as f64
+        } else {
+            0.0
+        };
+        let profit_factor = wins_over_losses(self.total_wins, self.total_losses);
+        let (expectancy, avg_trade_duration_days, best_trade, worst_trade) = if self.trades > 0 {
+            (
+                (win_rate / 100.0 * avg_win) - ((1.0 - win_rate / 100.0) * avg_loss),
+                self.total_duration as f64 / n,
+                self.best,
+                self.worst,
+            )
+        } else {
+            (0.0, 0.0, 0.0, 0.0)
+        };
+        let exposure_pct = if self.trades > 0 {
+            (self.invested_days as f64 / self.points as f64 * 100.0).min(100.0)
+        } else {
+            0.0
+        };
+        let avg_r_multiple = if self.r_multiples > 0 {
+            self.total_r_multiple / self.r_multiples as f64
+        } else {
+            0.0
+        };
+        let breakeven_win_rate_pct = if avg_win + avg_loss > 0.0 {
+            avg_loss / (avg_win + avg_loss) * 100.0
+        } else {
+            0.0
+        };
+        let (avg_round_trip_cost, avg_round_trip_cost_bps) = if self.trades > 0 {
+            let bps = if self.total_notional > 0.0 {
+                self.total_cost / self.total_notional * 10_000.0
+            } else {
+                0.0
+            };
+            (self.total_cost / n, bps)
+        } else {
+            (0.0, 0.0)
+        };
+        let (avg_entry_efficiency, avg_exit_efficiency) = match self.efficiencies {
+            (0, _, _) => (0.0, 0.0),
+            (n, entry, exit) => (entry / n as f64, exit / n as f64),
+        };
+        // Only the ends of the curve enter the IRR without cash flows
+        let ends = [
+            (self.first.unwrap_or(end), initial_capital),
+            (end, final_equity),
+        ];
+
+        PerformanceMetrics {
+            total_return,
+            total_return_pct,
+            cagr,
+            twr_pct: total_return_pct,
+            mwr_pct: MetricsCalculator::money_weighted_return(&ends, &[], initial_capital),
+            volatility,
+            sharpe_ratio,
+            sortino_ratio,
+            max_drawdown: self.max_drawdown,
+            max_drawdown_duration_days: self.max_drawdown_duration,
+            calmar_ratio,
+            total_trades: self.trades,
+            winning_trades: self.winning,
+            losing_trades: self.losing,
+            win_rate,
+            avg_win,
+            avg_loss,
+            profit_factor,
+            gross_profit_factor: wins_over_losses(self.gross_wins, self.gross_losses),
+            expectancy,
+            avg_trade_duration_days,
+            best_trade,
+            worst_trade,
+            exposure_pct,
+            suppressed_entries: 0,
+            liquidity_capped_entries: 0,
+            multi_bar_exits: self.multi_bar_exits,
+            max_heat_pct: 0.0,
+            avg_heat_pct: 0.0,
+            max_net_exposure_pct: 0.0,
+            avg_net_exposure_pct: 0.0,
+            avg_round_trip_cost,
+            avg_round_trip_cost_bps,
+            breakeven_win_rate_pct,
+            avg_r_multiple,
+            total_r_multiple: self.total_r_multiple,
+            r_multiple_histogram: contiguous(&self.r_buckets)
+                .map(|(lower_r, count)| RBucket { lower_r, count })
+                .collect(),
+            worst_overnight_gap_in_trade_pct: self.worst_gap,
+            overnight_gap_histogram: contiguous(&self.gap_buckets)
+                .map(|(lower_pct, count)| GapBucket { lower_pct, count })
+                .collect(),
+            avg_entry_efficiency,
+            avg_exit_efficiency,
+            trade_sequence: self.sequence.stats(self.trades),
+        }
+    }
+}
+
+/// Set each closed trade's entry and exit efficiency, measuring long trades
+/// against `bars` and hedge trades against `hedge_bars`
+pub fn efficiency(trades: &mut [Trade], bars: &[Bar], hedge_bars: Option<&[Bar]>) {
+    for trade in trades {
+        let measured = match trade.side {
+            Side::HedgeSell => {
+                hedge_bars.and_then(|h| MetricsCalculator::trade_efficiency(trade, h))
+            }
+            _ => MetricsCalculator::trade_efficiency(trade, bars),
+        };
+        trade.entry_efficiency = measured.map(|(entry, _)| entry);
+        trade.exit_efficiency = measured.map(|(_, exit)| exit);
+    }
+}
+
+/// Net exposure as a percentage of `equity` (0 without positive equity)
+pub(crate) fn net_exposure_pct(net: f64, equity: f64) -> f64 {
+    if equity > 0.0 {
+        net / equity * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Whichever of `a` and `b` is larger in magnitude, folding a signed maximum
+pub(crate) fn largest_either_way(a: f64, b: f64) -> f64 {
+    if b.abs() > a.abs() {
+        b
+    } else {
+        a
+    }
+}
+
+/// Price P&L of closed trades split between the gaps from one close to the
+/// next open and the moves from each open to its close
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionAttribution {
+    /// Closed trades measured
+    pub trades: usize,
+    pub overnight_pnl: f64,
+    pub intraday_pnl: f64,
+    /// Shares (%) of the two together, outside 0..100 when they differ in
+    /// sign; 0 when they cancel out
+    pub overnight_share_pct: f64,
+    pub intraday_share_pct: f64,
+}
+
+/// Split each closed trade's P&L before costs into its overnight and
+/// intraday parts, walking the bars it was held over.
+///
+/// The move from the entry fill to the entry bar's close is intraday: next
+/// to nothing for a fill at the close, the whole session for one at the
+/// open (the next-open re-entries of `overnight_flat`). Each later bar adds
+/// its gap from the prior close as overnight and its move from the open as
+/// intraday, the exit bar's ending at the exit fill. Hedge trades, priced
+/// on another symbol, and trades outside `bars` are left out.
+pub fn session_attribution(trades: &[Trade], bars: &[Bar]) -> SessionAttribution {
+    let mut attribution = SessionAttribution::default();
+    for trade in trades {
+        let (Some(exit_date), Some(exit_price)) = (trade.exit_date, trade.exit_price) else {
+            continue;
+        };
+        let start = bars.partition_point(|b| b.timestamp < trade.entry_date);
+        let end = bars.partition_point(|b| b.timestamp <= exit_date);
+        if trade.side == Side::HedgeSell || start >= end {
+            continue;
+        }
+
+        // Per share, as a long
+        let close_of = |i: usize| {
+            if i + 1 == end {
+                exit_price
+            } else {
+                bars[i].close
+            }
+        };
+        let mut overnight = 0.0;
+        let mut intraday = close_of(start) - trade.entry_price;
+        for i in start + 1..end {
+            overnight += bars[i].open - bars[i - 1].close;
+            intraday += close_of(i) - bars[i].open;
+        }
+        let shares = match trade.side {
+            Side::Cover | Side::SyntheticCover => -trade.quantity,
+            _ => trade.quantity,
+        };
+        attribution.trades += 1;
+        attribution.overnight_pnl += overnight * shares;
+        attribution.intraday_pnl += intraday * shares;
+    }
+
+    let total = attribution.overnight_pnl + attribution.intraday_pnl;
+    if total != 0.0 {
+        attribution.overnight_share_pct = attribution.overnight_pnl / total * 100.0;
+        attribution.intraday_share_pct = attribution.intraday_pnl / total * 100.0;
+    }
+    attribution
+}
+
+/// Mean of `values`, 0 when there are none
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, n) = values.fold((0.0, 0usize), |(sum, n), v| (sum + v, n + 1));
+    if n > 0 {
+        sum / n as f64
+    } else {
+        0.0
+    }
+}
+
+/// Wins over losses: infinite without losses, 0 without either
+fn wins_over_losses(wins: f64, losses: f64) -> f64 {
+    if losses > 0.0 {
+        wins / losses
+    } else if wins > 0.0 {
+        f64::INFINITY
+    } else {
+        0.0
+    }
+}
+
+/// Unit buckets from the lowest to the highest key, empty ones included,
+/// as [`MetricsCalculator::unit_buckets`] lays them out
+fn contiguous(buckets: &BTreeMap<i64, u32>) -> impl Iterator<Item = (f64, u32)> + '_ {
+    let low = buckets.keys().next().copied().unwrap_or(0);
+    let high = buckets.keys().next_back().copied().unwrap_or(-1);
+    (low..=high).map(|k| (k as f64, buckets.get(&k).copied().unwrap_or(0)))
+}
+
+/// Daily returns in the trailing window of [`rolling_beta`] as reported by
+/// [`benchmark_conditional`]
+pub const ROLLING_BETA_WINDOW: usize = 63;
+
+/// Strategy results over the days and trades of one benchmark regime
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RegimeMetrics {
+    /// Daily returns that fell in the regime
+    pub days: usize,
+    /// Annualized compound return of those days (%)
+    pub cagr: f64,
+    /// Worst drawdown (%) of those days' returns chained together
+    pub max_drawdown: f64,
+    /// Trades entered in the regime
+    pub trades: usize,
+    pub win_rate: f64,
+}
+
+/// Strategy results split by whether the benchmark closed at or above its
+/// SMA (`up`) or below it (`down`)
+#[derive(Debug, Clone, Serialize)]
+pub struct ConditionalMetrics {
+    pub sma_period: usize,
+    pub up: RegimeMetrics,
+    pub down: RegimeMetrics,
+    /// Trades entered on a day without a benchmark bar or before its SMA
+    /// filled
+    pub unclassified_trades: usize,
+    /// Beta of daily returns to the benchmark's over the trailing
+    /// [`ROLLING_BETA_WINDOW`] days
+    pub rolling_beta: Vec<(DateTime<Utc>, f64)>,
+}
+
+/// Split the run by benchmark regime, matching equity points and trade
+/// entries to benchmark bars by calendar date.
+///
+/// A day's return belongs to the regime of the previous close, the last one
+/// known when it began; a trade belongs to the regime of its entry day.
+pub fn benchmark_conditional(
+    equity_curve: &[(DateTime<Utc>, f64)],
+    trades: &[Trade],
+    benchmark_bars: &[Bar],
+    sma_period: usize,
+) -> ConditionalMetrics {
+    let closes: Vec<f64> = benchmark_bars.iter().map(|b| b.close).collect();
+    let sma = calculate_sma(&closes, sma_period);
+    let up_on: HashMap<NaiveDate, bool> = benchmark_bars
+        .iter()
+        .zip(&sma)
+        .filter_map(|(bar, sma)| sma.map(|sma| (bar.timestamp.date_naive(), bar.close >= sma)))
+        .collect();
+    let regime = |t: DateTime<Utc>| up_on.get(&t.date_naive()).copied();
+
+    let (mut up_returns, mut down_returns) = (Vec::new(), Vec::new());
+    for w in equity_curve.windows(2) {
+        if w[0].1 <= 0.0 {
+            continue;
+        }
+        let ret = w[1].1 / w[0].1 - 1.0;
+        match regime(w[0].0) {
+            Some(true) => up_returns.push(ret),
+            Some(false) => down_returns.push(ret),
+            None => {}
+        }
+    }
+
+    let (mut up_trades, mut down_trades) = (Vec::new(), Vec::new());
+    let mut unclassified_trades = 0;
+    for trade in trades {
+        match regime(trade.entry_date) {
+            Some(true) => up_trades.push(trade),
+            Some(false) => down_trades.push(trade),
+            None => unclassified_trades += 1,
+        }
+    }
+
+    ConditionalMetrics {
+        sma_period,
+        up: regime_metrics(&up_returns, &up_trades),
+        down: regime_metrics(&down_returns, &down_trades),
+        unclassified_trades,
+        rolling_beta: rolling_beta(equity_curve, benchmark_bars, ROLLING_BETA_WINDOW),
+    }
+}
+
+fn regime_metrics(returns: &[f64], trades: &[&Trade]) -> RegimeMetrics {
+    let (mut equity, mut peak, mut max_drawdown) = (1.0_f64, 1.0_f64, 0.0_f64);
+    for ret in returns {
+        equity *= 1.0 + ret;
+        peak = peak.max(equity);
+        max_drawdown = max_drawdown.max((peak - equity) / peak * 100.0);
+    }
+    let cagr = if returns.is_empty() || equity <= 0.0 {
+        0.0
+    } else {
+        (equity.powf(TRADING_DAYS_PER_YEAR / returns.len() as f64) - 1.0) * 100.0
+    };
+    let winners = trades.iter().filter(|t| t.pnl > 0.0).count();
+
+    RegimeMetrics {
+        days: returns.len(),
+        cagr,
+        max_drawdown,
+        trades: trades.len(),
+        win_rate: if trades.is_empty() {
+            0.0
+        } else {
+            winners as f64 / trades.len() as f64 * 100.0
+        },
+    }
+}
+
+/// Beta of the equity curve's daily returns to the benchmark's over each
+/// trailing `window` of days both series cover, dated by the window's last
+/// day. Windows where the benchmark did not move are skipped.
+pub fn rolling_beta(
+    equity_curve: &[(DateTime<Utc>, f64)],
+    benchmark_bars: &[Bar],
+    window: usize,
+) -> Vec<(DateTime<Utc>, f64)> {
+    if window < 2 {
+        return Vec::new();
+    }
+    let close_on: HashMap<NaiveDate, f64> = benchmark_bars
+        .iter()
+        .map(|b| (b.timestamp.date_naive(), b.close))
+        .collect();
+    let close = |t: DateTime<Utc>| close_on.get(&t.date_naive()).copied();
+
+    // (date, strategy return, benchmark return) for days both series cover
+    let paired: Vec<(DateTime<Utc>, f64, f64)> = equity_curve
+        .windows(2)
+        .filter_map(|w| {
+            let (before, after) = (close(w[0].0)?, close(w[1].0)?);
+            (w[0].1 > 0.0 && before > 0.0)
+                .then(|| (w[1].0, w[1].1 / w[0].1 - 1.0, after / before - 1.0))
+        })
+        .collect();
+
+    paired
+        .windows(window)
+        .filter_map(|w| {
+            let n = w.len() as f64;
+            let mean_s = w.iter().map(|p| p.1).sum::<f64>() / n;
+            let mean_b = w.iter().map(|p| p.2).sum::<f64>() / n;
+            let covariance = w
+                .iter()
+                .map(|p| (p.1 - mean_s) * (p.2 - mean_b))
+                .sum::<f64>();
+            let variance = w.iter().map(|p| (p.2 - mean_b).powi(2)).sum::<f64>();
+            (variance > 0.0).then(|| (w[w.len() - 1].0, covariance / variance))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::TimeZone;
+    use chrono::{Datelike, TimeZone};
 
     fn make_equity_curve(values: &[f64]) -> Vec<(DateTime<Utc>, f64)> {
         values
@@ -325,7 +1419,8 @@ mod tests {
             .enumerate()
             .map(|(i, &v)| {
                 (
-                    Utc.with_ymd_and_hms(2024, 1, 1 + i as u32, 12, 0, 0).unwrap(),
+                    Utc.with_ymd_and_hms(2024, 1, 1 + i as u32, 12, 0, 0)
+                        .unwrap(),
                     v,
                 )
             })
@@ -374,4 +1469,521 @@ mod tests {
         assert!((dd_curve[2].1 - 9.09).abs() < 0.1); // 10000/11000 = ~9.09%
         assert!((dd_curve[3].1 - 18.18).abs() < 0.1); // 9000/11000 = ~18.18%
     }
+
+    #[test]
+    fn test_resample_monthly() {
+        // Every calendar day of Q1 2024, equity = 10000 + day index
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 21, 0, 0).unwrap();
+        let curve: Vec<(DateTime<Utc>, f64)> = (0..91)
+            .map(|i| (start + chrono::Duration::days(i), 10000.0 + i as f64))
+            .collect();
+
+        let monthly = MetricsCalculator::resample_equity(&curve, ResampleFrequency::Monthly);
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+        assert_eq!(
+            monthly,
+            vec![
+                (date(1, 31), 10030.0),
+                (date(2, 29), 10059.0),
+                (date(3, 31), 10090.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resample_weekly_keeps_partial_periods() {
+        // Wed 2024-01-03 .. Tue 2024-01-16, weekdays only
+        let curve: Vec<(DateTime<Utc>, f64)> = (3..=16)
+            .map(|d| Utc.with_ymd_and_hms(2024, 1, d, 21, 0, 0).unwrap())
+            .filter(|ts| ts.weekday().num_days_from_monday() < 5)
+            .enumerate()
+            .map(|(i, ts)| (ts, i as f64))
+            .collect();
+
+        let weekly = MetricsCalculator::resample_equity(&curve, ResampleFrequency::Weekly);
+        let date = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        assert_eq!(
+            weekly,
+            vec![(date(5), 2.0), (date(12), 7.0), (date(16), 9.0)]
+        );
+    }
+
+    #[test]
+    fn test_round_trip_costs_and_breakeven() {
+        // $20 round trips on $10k positions; gross +$200, +$200, -$100
+        let trade = |pnl: f64| Trade {
+            entry_date: Utc.with_ymd_and_hms(2024, 1, 2, 12, 0, 0).unwrap(),
+            entry_price: 100.0,
+            exit_date: Some(Utc.with_ymd_and_hms(2024, 1, 3, 12, 0, 0).unwrap()),
+            exit_price: Some(100.0 + pnl / 100.0),
+            quantity: 100.0,
+            side: common::Side::Sell,
+            pnl,
+            pnl_pct: pnl / 100.0,
+            gross_pnl: pnl,
+            net_pnl: pnl,
+            holding_days: 1,
+            entry_reason: String::new(),
+            exit_reason: String::new(),
+            entry_rsi: None,
+            exit_rsi: None,
+            initial_risk: None,
+            r_multiple: None,
+            initial_stop_price: None,
+            stop_spec: None,
+            stop_triggered_at: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            exit_order_id: None,
+            fills: Vec::new(),
+            entry_context: None,
+            exit_context: None,
+            entry_efficiency: None,
+            exit_efficiency: None,
+        };
+        let cost = 20.0;
+        let trades = vec![
+            trade(200.0 - cost),
+            trade(200.0 - cost),
+            trade(-100.0 - cost),
+        ];
+        let equity = make_equity_curve(&[10000.0, 10140.0, 10240.0]);
+
+        let mut metrics = MetricsCalculator::calculate(&equity, &trades, 10000.0);
+        MetricsCalculator::apply_costs(&mut metrics, &trades, &[cost; 3]);
+
+        assert!((metrics.avg_round_trip_cost - 20.0).abs() < 1e-9);
+        assert!((metrics.avg_round_trip_cost_bps - 20.0).abs() < 1e-9);
+        // (loss + cost) / (win + loss) on gross figures = 120 / 300
+        let gross_breakeven = (100.0 + cost) / (200.0 + 100.0) * 100.0;
+        assert!((metrics.breakeven_win_rate_pct - gross_breakeven).abs() < 1e-9);
+        assert!((metrics.breakeven_win_rate_pct - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_r_multiple_histogram() {
+        let trade = |r: Option<f64>| Trade {
+            entry_date: Utc.with_ymd_and_hms(2024, 1, 2, 12, 0, 0).unwrap(),
+            entry_price: 100.0,
+            exit_date: None,
+            exit_price: None,
+            quantity: 1.0,
+            side: common::Side::Sell,
+            pnl: r.unwrap_or(0.0) * 5.0,
+            pnl_pct: 0.0,
+            gross_pnl: r.unwrap_or(0.0) * 5.0,
+            net_pnl: r.unwrap_or(0.0) * 5.0,
+            holding_days: 1,
+            entry_reason: String::new(),
+            exit_reason: String::new(),
+            entry_rsi: None,
+            exit_rsi: None,
+            initial_risk: r.map(|_| 5.0),
+            r_multiple: r,
+            initial_stop_price: None,
+            stop_spec: None,
+            stop_triggered_at: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            exit_order_id: None,
+            fills: Vec::new(),
+            entry_context: None,
+            exit_context: None,
+            entry_efficiency: None,
+            exit_efficiency: None,
+        };
+        let trades = vec![
+            trade(Some(-1.0)),
+            trade(Some(-0.4)),
+            trade(Some(2.5)),
+            trade(None),
+        ];
+        let equity = make_equity_curve(&[10000.0, 10000.0]);
+        let metrics = MetricsCalculator::calculate(&equity, &trades, 10000.0);
+
+        assert!((metrics.total_r_multiple - 1.1).abs() < 1e-9);
+        assert!((metrics.avg_r_multiple - 1.1 / 3.0).abs() < 1e-9);
+        let counts: Vec<(f64, u32)> = metrics
+            .r_multiple_histogram
+            .iter()
+            .map(|b| (b.lower_r, b.count))
+            .collect();
+        assert_eq!(counts, vec![(-1.0, 2), (0.0, 0), (1.0, 0), (2.0, 1)]);
+    }
+
+    #[test]
+    fn test_overnight_gap_attribution() {
+        // Flat closes at 100; bar 5 gaps down 8% and bar 7 up 1%
+        let bars: Vec<Bar> = (0..10)
+            .map(|i| Bar {
+                timestamp: Utc.with_ymd_and_hms(2024, 1, 1 + i, 16, 0, 0).unwrap(),
+                open: match i {
+                    5 => 92.0,
+                    7 => 101.0,
+                    _ => 100.0,
+                },
+                high: 101.0,
+                low: 92.0,
+                close: 100.0,
+                volume: 1_000_000,
+                vwap: None,
+                halted: false,
+            })
+            .collect();
+        let trade = |entry: usize, exit: usize| Trade {
+            entry_date: bars[entry].timestamp,
+            entry_price: 100.0,
+            exit_date: Some(bars[exit].timestamp),
+            exit_price: Some(100.0),
+            quantity: 1.0,
+            side: Side::Sell,
+            pnl: 0.0,
+            pnl_pct: 0.0,
+            gross_pnl: 0.0,
+            net_pnl: 0.0,
+            holding_days: (exit - entry) as i64,
+            entry_reason: String::new(),
+            exit_reason: String::new(),
+            entry_rsi: None,
+            exit_rsi: None,
+            initial_risk: None,
+            r_multiple: None,
+            initial_stop_price: None,
+            stop_spec: None,
+            stop_triggered_at: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            exit_order_id: None,
+            fills: Vec::new(),
+            entry_context: None,
+            exit_context: None,
+            entry_efficiency: None,
+            exit_efficiency: None,
+        };
+        // Held through the gap-down, through the gap-up only, and intrabar
+        let trades = vec![trade(3, 6), trade(6, 8), trade(9, 9)];
+
+        let gap = MetricsCalculator::worst_overnight_gap(&trades[0], &bars).unwrap();
+        assert!((gap + 8.0).abs() < 1e-9);
+        assert_eq!(
+            MetricsCalculator::worst_overnight_gap(&trades[1], &bars),
+            Some(0.0)
+        );
+        assert_eq!(
+            MetricsCalculator::worst_overnight_gap(&trades[2], &bars),
+            None
+        );
+
+        let mut metrics = PerformanceMetrics::default();
+        MetricsCalculator::apply_overnight_gaps(&mut metrics, &trades, &bars, None);
+        assert_eq!(metrics.worst_overnight_gap_in_trade_pct, gap);
+        let counts: Vec<u32> = metrics
+            .overnight_gap_histogram
+            .iter()
+            .map(|b| b.count)
+            .collect();
+        assert_eq!(counts, vec![1, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(metrics.overnight_gap_histogram[0].lower_pct, -8.0);
+    }
+
+    #[test]
+    fn test_trade_efficiency() {
+        // Days 1-5: the entry day's low of 100 and a high of 110 on day 3
+        let bars: Vec<Bar> = (1..=5)
+            .map(|d| Bar {
+                timestamp: Utc.with_ymd_and_hms(2024, 1, d, 12, 0, 0).unwrap(),
+                open: 103.0,
+                high: if d == 3 { 110.0 } else { 105.0 },
+                low: if d == 1 { 100.0 } else { 102.0 },
+                close: 103.0,
+                volume: 1_000_000,
+                vwap: None,
+                halted: false,
+            })
+            .collect();
+        // Bought at the exact low, sold at 105
+        let long = sequence_trade(5, 5.0);
+        assert_eq!(
+            MetricsCalculator::trade_efficiency(&long, &bars),
+            Some((1.0, 0.5))
+        );
+        let short = Trade {
+            side: Side::SyntheticCover,
+            ..long.clone()
+        };
+        assert_eq!(
+            MetricsCalculator::trade_efficiency(&short, &bars),
+            Some((0.0, 0.5))
+        );
+        assert_eq!(
+            MetricsCalculator::trade_efficiency(&sequence_trade(1, 5.0), &bars),
+            Some((0.5, 0.5))
+        );
+
+        let open = Trade {
+            exit_date: None,
+            ..long.clone()
+        };
+        let mut trades = vec![long, sequence_trade(1, 5.0), open];
+        efficiency(&mut trades, &bars, None);
+        assert_eq!(trades[0].entry_efficiency, Some(1.0));
+        assert_eq!(trades[2].entry_efficiency, None);
+        let curve = make_equity_curve(&[100_000.0; 5]);
+        let metrics = MetricsCalculator::calculate(&curve, &trades, 100_000.0);
+        assert_eq!(metrics.avg_entry_efficiency, 0.75);
+        assert_eq!(metrics.avg_exit_efficiency, 0.5);
+    }
+
+    #[test]
+    fn test_session_attribution() {
+        // Every bar opens 2 above the prior close and closes at its open
+        let bars: Vec<Bar> = (1..=5)
+            .map(|d| {
+                let price = 98.0 + 2.0 * d as f64;
+                Bar {
+                    timestamp: Utc.with_ymd_and_hms(2024, 1, d, 12, 0, 0).unwrap(),
+                    open: price,
+                    high: price + 1.0,
+                    low: price - 1.0,
+                    close: price,
+                    volume: 1_000_000,
+                    vwap: None,
+                    halted: false,
+                }
+            })
+            .collect();
+        // Bought at day 1's close of 100, sold at day 5's close of 108
+        let gapped = Trade {
+            quantity: 10.0,
+            ..sequence_trade(5, 8.0)
+        };
+        let attribution = session_attribution(std::slice::from_ref(&gapped), &bars);
+        assert_eq!(attribution.trades, 1);
+        assert!((attribution.overnight_pnl - 80.0).abs() < 1e-9);
+        assert!(attribution.intraday_pnl.abs() < 1e-9);
+        assert!((attribution.overnight_share_pct - 100.0).abs() < 1e-9);
+
+        // Shorted at 103 on day 2 and covered at 103 on day 3: the gap
+        // lost is made back within the two sessions
+        let short = Trade {
+            entry_date: bars[1].timestamp,
+            entry_price: 103.0,
+            exit_date: Some(bars[2].timestamp),
+            exit_price: Some(103.0),
+            quantity: 1.0,
+            side: Side::SyntheticCover,
+            ..sequence_trade(3, 0.0)
+        };
+        let open = Trade {
+            exit_date: None,
+            ..gapped
+        };
+        let attribution = session_attribution(&[short, open], &bars);
+        assert_eq!(attribution.trades, 1);
+        assert!((attribution.overnight_pnl + 2.0).abs() < 1e-9);
+        assert!((attribution.intraday_pnl - 2.0).abs() < 1e-9);
+        assert_eq!(attribution.overnight_share_pct, 0.0);
+    }
+
+    fn sequence_trade(exit_day: u32, pnl: f64) -> Trade {
+        Trade {
+            entry_date: Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap(),
+            entry_price: 100.0,
+            exit_date: Some(Utc.with_ymd_and_hms(2024, 1, exit_day, 12, 0, 0).unwrap()),
+            exit_price: Some(100.0 + pnl),
+            quantity: 1.0,
+            side: common::Side::Sell,
+            pnl,
+            pnl_pct: pnl,
+            gross_pnl: pnl,
+            net_pnl: pnl,
+            holding_days: 1,
+            entry_reason: String::new(),
+            exit_reason: String::new(),
+            entry_rsi: None,
+            exit_rsi: None,
+            initial_risk: None,
+            r_multiple: None,
+            initial_stop_price: None,
+            stop_spec: None,
+            stop_triggered_at: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            exit_order_id: None,
+            fills: Vec::new(),
+            entry_context: None,
+            exit_context: None,
+            entry_efficiency: None,
+            exit_efficiency: None,
+        }
+    }
+
+    #[test]
+    fn test_alternating_trade_sequence() {
+        // W L W L ... by exit date, passed in reverse order
+        let mut trades: Vec<Trade> = (1..=20)
+            .map(|day| sequence_trade(day, if day % 2 == 1 { 50.0 } else { -50.0 }))
+            .collect();
+        trades.reverse();
+
+        let stats = MetricsCalculator::trade_sequence_stats(&trades);
+        assert!((stats.sign_correlation + 1.0).abs() < 1e-12);
+        assert_eq!(stats.runs, 20);
+        assert!((stats.expected_runs - 11.0).abs() < 1e-12);
+        assert!(stats.runs_z_score > 4.0);
+        assert_eq!(stats.win_after_win_pct, Some(0.0));
+        assert_eq!(stats.win_after_loss_pct, Some(100.0));
+        assert_eq!(stats.tied_exits, 0);
+    }
+
+    #[test]
+    fn test_single_outcome_sequence() {
+        let trades = vec![
+            sequence_trade(2, 10.0),
+            sequence_trade(2, 20.0),
+            sequence_trade(5, 30.0),
+        ];
+        let stats = MetricsCalculator::trade_sequence_stats(&trades);
+
+        assert_eq!(stats.sign_correlation, 0.0);
+        assert_eq!(stats.runs, 1);
+        assert_eq!(stats.expected_runs, 1.0);
+        assert_eq!(stats.runs_z_score, 0.0);
+        assert_eq!(stats.win_after_win_pct, Some(100.0));
+        assert_eq!(stats.win_after_loss_pct, None);
+        assert_eq!(stats.tied_exits, 1);
+
+        assert_eq!(
+            MetricsCalculator::trade_sequence_stats(&[]),
+            SequenceStats::default()
+        );
+    }
+
+    #[test]
+    fn test_cash_flow_returns() {
+        let day =
+            |d: i64| Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::days(d);
+
+        // +10%, then a 5000 deposit on 0% performance, then +10%
+        let curve = [
+            (day(0), 10000.0),
+            (day(1), 11000.0),
+            (day(2), 16000.0),
+            (day(3), 17600.0),
+        ];
+        let flows = [(day(2), 5000.0)];
+        let twr = MetricsCalculator::time_weighted_curve(&curve, &flows, 10000.0);
+        let navs: Vec<f64> = twr.iter().map(|(_, nav)| *nav).collect();
+        assert!((navs[3] - 12100.0).abs() < 1e-9);
+        assert!((navs[2] - navs[1]).abs() < 1e-9);
+
+        // 10000 invested, 1000 more after a year, 13200 two years in: 10%
+        let curve = [(day(0), 10000.0), (day(365), 12000.0), (day(730), 13200.0)];
+        let flows = [(day(365), 1000.0)];
+        let irr = MetricsCalculator::money_weighted_return(&curve, &flows, 10000.0);
+        assert!((irr - 10.0).abs() < 1e-6, "{}", irr);
+
+        let mut metrics = MetricsCalculator::calculate(&curve, &[], 10000.0);
+        MetricsCalculator::apply_cash_flows(&mut metrics, &curve, &flows, 10000.0);
+        assert!((metrics.total_return - 2200.0).abs() < 1e-9);
+        assert!((metrics.mwr_pct - 10.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rolling_beta() {
+        let benchmark: Vec<Bar> = (0..10)
+            .map(|i| {
+                let t = Utc.with_ymd_and_hms(2024, 1, 1 + i, 21, 0, 0).unwrap();
+                let close = 100.0 + if i % 2 == 0 { 0.0 } else { 2.0 };
+                Bar::new(t, close, close, close, close, 1_000)
+            })
+            .collect();
+        // Twice the benchmark's daily moves
+        let mut equity = vec![(benchmark[0].timestamp, 10_000.0)];
+        for w in benchmark.windows(2) {
+            let last = equity[equity.len() - 1].1;
+            equity.push((
+                w[1].timestamp,
+                last * (1.0 + 2.0 * (w[1].close / w[0].close - 1.0)),
+            ));
+        }
+
+        let beta = rolling_beta(&equity, &benchmark, 5);
+        assert_eq!(beta.len(), 5);
+        assert_eq!(beta[0].0, benchmark[5].timestamp);
+        assert!(beta.iter().all(|(_, b)| (b - 2.0).abs() < 1e-9));
+        assert!(rolling_beta(&equity, &benchmark[..3], 5).is_empty());
+    }
+
+    #[test]
+    fn test_benchmark_conditional_with_trend_filter() {
+        // Entries need the close at or above its SMA, so with the traded bars
+        // as the benchmark none can land in the down regime
+        let bars = crate::data::generate_synthetic_bars_seeded(500, 100.0, 7);
+        let params = common::BacktestParameters::default();
+        let result = crate::BacktestEngine::new(params.clone()).run(&bars, None);
+        assert!(!result.trades.is_empty());
+
+        let conditional = benchmark_conditional(
+            &result.equity_curve,
+            &result.trades,
+            &bars,
+            params.strategy.sma_period,
+        );
+        assert_eq!(conditional.down.trades, 0);
+        assert_eq!(conditional.up.trades, result.trades.len());
+        assert_eq!(conditional.unclassified_trades, 0);
+        assert!(conditional.down.days > 0);
+        // The curve starts after warmup, when the SMA has filled
+        assert_eq!(
+            conditional.up.days + conditional.down.days,
+            result.equity_curve.len() - 1
+        );
+        assert_eq!(
+            conditional.rolling_beta.len(),
+            result.equity_curve.len() - ROLLING_BETA_WINDOW
+        );
+    }
+
+    #[test]
+    fn test_streamed_matches_two_pass() {
+        let bars = crate::data::generate_synthetic_bars_seeded(500, 100.0, 5);
+        let params = common::BacktestParameters::default().without_vwap_filter();
+        let result = crate::BacktestEngine::new(params).run(&bars, Some(&bars));
+        assert!(result.trades.len() > 10);
+
+        let (curve, trades) = (&result.equity_curve, &result.trades);
+        let held = MetricsCalculator::calculate(curve, trades, result.initial_capital);
+        let streamed = MetricsCalculator::calculate_streamed(
+            curve.iter().copied(),
+            trades.iter().cloned(),
+            result.initial_capital,
+        );
+        assert_eq!(streamed.total_trades, held.total_trades);
+        assert_eq!(
+            streamed.max_drawdown_duration_days,
+            held.max_drawdown_duration_days
+        );
+        assert_eq!(streamed.r_multiple_histogram, held.r_multiple_histogram);
+        assert_eq!(streamed.trade_sequence.runs, held.trade_sequence.runs);
+        let pairs = [
+            (streamed.sharpe_ratio, held.sharpe_ratio),
+            (streamed.sortino_ratio, held.sortino_ratio),
+            (streamed.volatility, held.volatility),
+            (streamed.max_drawdown, held.max_drawdown),
+            (streamed.cagr, held.cagr),
+            (streamed.win_rate, held.win_rate),
+            (
+                streamed.trade_sequence.sign_correlation,
+                held.trade_sequence.sign_correlation,
+            ),
+            (
+                streamed.trade_sequence.runs_z_score,
+                held.trade_sequence.runs_z_score,
+            ),
+        ];
+        for (a, b) in pairs {
+            assert!((a - b).abs() <= 1e-9 * b.abs().max(1.0), "{} vs {}", a, b);
+        }
+    }
 }
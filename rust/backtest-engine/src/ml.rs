@@ -0,0 +1,409 @@
+//! Bar-level feature export for ML experiments
+//!
+//! One row per bar: indicator values, a few derived features and two labels
+//! (forward return and whether an entry on that bar would have won under the
+//! standard exit rules). Features only ever see bars up to and including the
+//! row's own bar; labels are the only columns that look ahead.
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use common::{BacktestError, BacktestParameters, Bar, Position, Result, Side, SignalType, Trade};
+
+use crate::analysis::replay::{exit_values, replay_position};
+use crate::indicators::{percent_b, IndicatorSeries};
+use crate::output::{write_csv, Overwrite};
+use crate::signals::SignalGenerator;
+
+/// Trailing window for the volume ratio feature
+pub const VOLUME_AVG_PERIOD: usize = 20;
+
+/// Longest past-return lookback
+const MAX_RETURN_LOOKBACK: usize = 5;
+
+/// One exported bar
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureRow {
+    pub timestamp: DateTime<Utc>,
+    pub close: f64,
+    pub volume: u64,
+    /// 1 once every feature has a full lookback window
+    pub warmup_complete: u8,
+    // Indicators
+    pub rsi: f64,
+    pub sma: Option<f64>,
+    pub ema: f64,
+    pub atr: f64,
+    pub bb_upper: f64,
+    pub bb_middle: f64,
+    pub bb_lower: f64,
+    pub vwap: Option<f64>,
+    // Derived features
+    pub sma_distance_pct: Option<f64>,
+    pub percent_b: Option<f64>,
+    pub volume_ratio: Option<f64>,
+    pub return_1d: Option<f64>,
+    pub return_3d: Option<f64>,
+    pub return_5d: Option<f64>,
+    /// 1 when the backtest actually opened a long position on this bar
+    pub entered: u8,
+    // Labels (look ahead)
+    /// Close-to-close return over the horizon; empty when the data ends first
+    pub forward_return: Option<f64>,
+    /// 1 when a long entered at this close would have exited at a profit
+    /// under the standard stop/exit rules; empty when the replay ran out of data
+    pub entry_win: Option<u8>,
+}
+
+/// Number of leading bars whose features are incomplete
+pub fn warmup_bars(params: &BacktestParameters) -> usize {
+    params
+        .strategy
+        .sma_period
+        .max(params.strategy.bb_period)
+        .max(params.strategy.rsi_period + 1)
+        .max(VOLUME_AVG_PERIOD)
+        .max(MAX_RETURN_LOOKBACK + 1)
+}
+
+/// Build one feature row per bar.
+///
+/// `indicators` must be computed from `bars` with `params` (the same series
+/// the engine uses); `horizon` is the forward-return window in bars.
+pub fn build_features(
+    bars: &[Bar],
+    indicators: &IndicatorSeries,
+    trades: &[Trade],
+    params: &BacktestParameters,
+    horizon: usize,
+) -> Vec<FeatureRow> {
+    let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+    let warmup = warmup_bars(params);
+    let wins = entry_wins(bars, indicators, &generator, params);
+
+    (0..bars.len())
+        .map(|i| {
+            // Leakage guard: features are computed from the history slice only
+            let mut row = features_at(&bars[..=i], indicators, warmup);
+
+            row.entered = trades
+                .iter()
+                .any(|t| t.side == Side::Sell && t.entry_date == bars[i].timestamp)
+                as u8;
+            row.forward_return = bars
+                .get(i + horizon)
+                .filter(|_| horizon > 0)
+                .map(|future| future.close / bars[i].close - 1.0);
+            row.entry_win = wins[i];
+            row
+        })
+        .collect()
+}
+
+/// Whether a long entered at each bar's close would have exited at a
+/// profit, as [`crate::analysis::replay_exit`] would find for each bar, in
+/// one pass over the bars; `None` while still open at the end of the data.
+///
+/// Every hypothetical long still open is carried forward together: stops
+/// come off a heap highest first, and unless the exit threshold depends on
+/// the entry (`profit_adjusted_exit`, `exit_threshold_decay`,
+/// `max_holding_days`) one generator call on the bar closes all the rest.
+fn entry_wins(
+    bars: &[Bar],
+    indicators: &IndicatorSeries,
+    generator: &SignalGenerator,
+    params: &BacktestParameters,
+) -> Vec<Option<u8>> {
+    let strategy = &params.strategy;
+    let per_entry = strategy.profit_adjusted_exit.is_some()
+        || strategy.exit_threshold_decay.is_some()
+        || strategy.max_holding_days.is_some();
+
+    let mut exits: Vec<Option<f64>> = vec![None; bars.len()];
+    let mut open: Vec<(usize, Position)> = Vec::new();
+    let mut stops = BinaryHeap::new();
+    for i in 1..bars.len() {
+        let entry = i - 1;
+        let position = replay_position(bars, indicators, params, entry, bars[entry].close);
+        // Stand-in for every open long when their exits agree; stops are
+        // the heap's to check
+        let probe = Position {
+            stop_loss_price: None,
+            ..position.clone()
+        };
+        if let Some(stop) = position.stop_loss_price {
+            stops.push(Stop(stop, entry));
+        }
+        open.push((entry, position));
+
+        let bar = &bars[i];
+        while let Some(&Stop(stop, entry)) = stops.peek() {
+            if bar.close > stop {
+                break;
+            }
+            stops.pop();
+            exits[entry].get_or_insert(bar.close);
+        }
+
+        let exits_on = |entry: usize, position: &Position| {
+            let values = exit_values(bars, indicators, i, entry);
+            generator
+                .generate(bar, &values, true, Some(position), false)
+                .is_some_and(|signal| signal.signal_type == SignalType::Sell)
+        };
+        if per_entry {
+            open.retain(|(entry, _)| exits[*entry].is_none());
+            for (entry, position) in &open {
+                if exits_on(*entry, position) {
+                    exits[*entry] = Some(bar.close);
+                }
+            }
+        } else if exits_on(entry, &probe) {
+            for (entry, _) in open.drain(..) {
+                exits[entry].get_or_insert(bar.close);
+            }
+            stops.clear();
+        }
+    }
+
+    exits
+        .iter()
+        .zip(bars)
+        .map(|(exit, bar)| exit.map(|price| (price > bar.close) as u8))
+        .collect()
+}
+
+/// A replayed long's stop price, ordered so the heap yields the highest
+#[derive(Clone, Copy)]
+struct Stop(f64, usize);
+
+impl PartialEq for Stop {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Stop {}
+
+impl PartialOrd for Stop {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Stop {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+/// Build features and write them to a CSV file, returning the row count
+pub fn export_features(
+    bars: &[Bar],
+    indicators: &IndicatorSeries,
+    trades: &[Trade],
+    params: &BacktestParameters,
+    horizon: usize,
+    path: &Path,
+    overwrite: Overwrite,
+) -> Result<usize> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    if ext != "csv" {
+        return Err(BacktestError::ConfigError(format!(
+            "Unsupported feature export format: {} (use .csv)",
+            ext
+        )));
+    }
+
+    let rows = build_features(bars, indicators, trades, params, horizon);
+    write_csv(path, overwrite, &rows)?;
+    Ok(rows.len())
+}
+
+/// Features of the last bar in `history`
+fn features_at(history: &[Bar], indicators: &IndicatorSeries, warmup: usize) -> FeatureRow {
+    let i = history.len() - 1;
+    let bar = &history[i];
+    let ind = indicators.get(i);
+    let complete = history.len() > warmup;
+
+    let past_return =
+        |lookback: usize| (i >= lookback).then(|| bar.close / history[i - lookback].close - 1.0);
+
+    let volume_ratio = (history.len() >= VOLUME_AVG_PERIOD).then(|| {
+        let window = &history[history.len() - VOLUME_AVG_PERIOD..];
+        let avg = window.iter().map(|b| b.volume as f64).sum::<f64>() / VOLUME_AVG_PERIOD as f64;
+        if avg > 0.0 {
+            bar.volume as f64 / avg
+        } else {
+            0.0
+        }
+    });
+
+    FeatureRow {
+        timestamp: bar.timestamp,
+        close: bar.close,
+        volume: bar.volume,
+        warmup_complete: complete as u8,
+        rsi: ind.rsi,
+        sma: ind.sma,
+        ema: ind.ema,
+        atr: ind.atr,
+        bb_upper: ind.bb_upper,
+        bb_middle: ind.bb_middle,
+        bb_lower: ind.bb_lower,
+        vwap: bar.vwap,
+        sma_distance_pct: ind
+            .sma
+            .filter(|sma| *sma > 0.0)
+            .map(|sma| (bar.close / sma - 1.0) * 100.0),
+        percent_b: complete.then(|| percent_b(bar.close, ind.bb_lower, ind.bb_upper)),
+        volume_ratio,
+        return_1d: past_return(1),
+        return_3d: past_return(3),
+        return_5d: past_return(5),
+        entered: 0,
+        forward_return: None,
+        entry_win: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::indicators_for;
+    use crate::data::{generate_bars_with_rsi_pattern, generate_synthetic_bars_seeded};
+    use crate::engine::BacktestEngine;
+
+    fn params() -> BacktestParameters {
+        BacktestParameters::default()
+            .without_vwap_filter()
+            .without_short()
+    }
+
+    #[test]
+    fn test_row_count_and_complete_rows() {
+        let bars = generate_synthetic_bars_seeded(200, 50.0, 11);
+        let params = params();
+        let indicators = indicators_for(&bars, &params);
+
+        let rows = build_features(&bars, &indicators, &[], &params, 5);
+        assert_eq!(rows.len(), bars.len());
+
+        let warmup = warmup_bars(&params);
+        for row in &rows[warmup..] {
+            assert_eq!(row.warmup_complete, 1);
+            let features = [
+                Some(row.rsi),
+                row.sma,
+                row.sma_distance_pct,
+                row.percent_b,
+                row.volume_ratio,
+                row.return_1d,
+                row.return_3d,
+                row.return_5d,
+            ];
+            assert!(features.iter().all(|f| f.is_some_and(|v| v.is_finite())));
+        }
+        assert!(rows[..warmup].iter().all(|r| r.warmup_complete == 0));
+
+        // The last `horizon` rows have no forward return
+        assert!(rows[..195].iter().all(|r| r.forward_return.is_some()));
+        assert!(rows[195..].iter().all(|r| r.forward_return.is_none()));
+    }
+
+    #[test]
+    fn test_known_winning_dip_is_labelled() {
+        let bars = generate_bars_with_rsi_pattern(40, 100.0, &[22], &[]);
+        let params = params().with_sma_period(1);
+        let indicators = indicators_for(&bars, &params);
+        let result = BacktestEngine::new(params.clone()).run(&bars, None);
+
+        let rows = build_features(&bars, &indicators, &result.trades, &params, 5);
+
+        assert_eq!(rows[22].entry_win, Some(1));
+        assert_eq!(rows[22].entered, 1);
+        assert_eq!(rows.iter().filter(|r| r.entered == 1).count(), 1);
+    }
+
+    #[test]
+    fn test_features_ignore_future_bars() {
+        let bars = generate_synthetic_bars_seeded(120, 50.0, 5);
+        let params = params();
+        let full = build_features(&bars, &indicators_for(&bars, &params), &[], &params, 5);
+
+        let cut = 80;
+        let truncated_bars = &bars[..cut];
+        let truncated = build_features(
+            truncated_bars,
+            &indicators_for(truncated_bars, &params),
+            &[],
+            &params,
+            5,
+        );
+
+        for (a, b) in full[..cut].iter().zip(&truncated) {
+            assert_eq!(a.rsi, b.rsi);
+            assert_eq!(a.sma_distance_pct, b.sma_distance_pct);
+            assert_eq!(a.percent_b, b.percent_b);
+            assert_eq!(a.volume_ratio, b.volume_ratio);
+            assert_eq!(a.return_5d, b.return_5d);
+        }
+    }
+
+    #[test]
+    fn test_export_writes_csv() {
+        let bars = generate_synthetic_bars_seeded(60, 50.0, 3);
+        let params = params();
+        let indicators = indicators_for(&bars, &params);
+        let path = std::env::temp_dir().join("ml_export_features_test.csv");
+
+        let forced = Overwrite::Force;
+        let written = export_features(&bars, &indicators, &[], &params, 5, &path, forced).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(written, 60);
+        assert_eq!(content.lines().count(), 61);
+        assert!(content.starts_with("timestamp,close,volume,warmup_complete,rsi"));
+
+        let parquet = std::env::temp_dir().join("features.parquet");
+        assert!(export_features(&bars, &indicators, &[], &params, 5, &parquet, forced).is_err());
+    }
+
+    #[test]
+    fn test_labels_match_per_bar_replay() {
+        let bars = generate_synthetic_bars_seeded(300, 50.0, 7);
+        let mut timed = params();
+        timed.strategy.max_holding_days = Some(4);
+        for params in [params(), timed] {
+            let indicators = indicators_for(&bars, &params);
+            let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+            let rows = build_features(&bars, &indicators, &[], &params, 5);
+
+            for (i, row) in rows.iter().enumerate().take(bars.len() - 1) {
+                let exit = crate::analysis::replay_exit(
+                    &bars,
+                    &indicators,
+                    &generator,
+                    &params,
+                    i,
+                    bars[i].close,
+                );
+                let replayed = (exit.exit_reason != "end of data")
+                    .then(|| (exit.exit_price > bars[i].close) as u8);
+                assert_eq!(row.entry_win, replayed, "bar {}", i);
+            }
+            assert!(rows.iter().any(|r| r.entry_win == Some(0)));
+            assert!(rows.iter().any(|r| r.entry_win == Some(1)));
+        }
+    }
+}
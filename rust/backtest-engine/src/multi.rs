@@ -0,0 +1,143 @@
+//! Running one strategy across several symbols
+//!
+//! Each symbol is backtested independently with the shared base parameters,
+//! optionally adjusted by a per-symbol `PartialParameters` override.
+
+use common::{BacktestParameters, BacktestResult, Bar, PartialParameters, Result, SymbolOverrides};
+
+use crate::engine::BacktestEngine;
+
+/// Base parameters plus per-symbol overrides
+#[derive(Debug, Clone, Default)]
+pub struct MultiSymbolConfig {
+    pub base: BacktestParameters,
+    pub overrides: SymbolOverrides,
+}
+
+impl MultiSymbolConfig {
+    pub fn new(base: BacktestParameters) -> Self {
+        Self {
+            base,
+            overrides: SymbolOverrides::new(),
+        }
+    }
+
+    pub fn with_override(
+        mut self,
+        symbol: impl Into<String>,
+        overrides: PartialParameters,
+    ) -> Self {
+        self.overrides.insert(symbol.into(), overrides);
+        self
+    }
+
+    /// Merged and validated parameters for `symbol`
+    pub fn params_for(&self, symbol: &str) -> Result<BacktestParameters> {
+        let mut params = match self.overrides.get(symbol) {
+            Some(partial) => partial.apply_to(&self.base),
+            None => self.base.clone(),
+        };
+        params.strategy.symbol = symbol.to_string();
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Validate the merged parameters of every overridden symbol
+    pub fn validate(&self) -> Result<()> {
+        self.base.validate()?;
+        for symbol in self.overrides.keys() {
+            self.params_for(symbol)?;
+        }
+        Ok(())
+    }
+
+    /// Backtest each `(symbol, bars)` pair with its merged parameters.
+    ///
+    /// Results come back in input order. Every symbol's parameters are
+    /// validated before any backtest runs.
+    pub fn run(&self, data: &[(String, Vec<Bar>)]) -> Result<Vec<(String, BacktestResult)>> {
+        let params = data
+            .iter()
+            .map(|(symbol, _)| self.params_for(symbol))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(data
+            .iter()
+            .zip(params)
+            .map(|((symbol, bars), params)| {
+                (symbol.clone(), BacktestEngine::new(params).run(bars, None))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+    use common::{BacktestError, StopSpec};
+
+    fn data() -> Vec<(String, Vec<Bar>)> {
+        vec![
+            (
+                "TQQQ".to_string(),
+                generate_synthetic_bars_seeded(250, 50.0, 1),
+            ),
+            (
+                "SOXL".to_string(),
+                generate_synthetic_bars_seeded(250, 30.0, 2),
+            ),
+        ]
+    }
+
+    fn base() -> BacktestParameters {
+        BacktestParameters::default()
+            .without_vwap_filter()
+            .without_short()
+    }
+
+    fn wide_soxl_stop() -> MultiSymbolConfig {
+        MultiSymbolConfig::new(base()).with_override(
+            "SOXL",
+            PartialParameters {
+                stop_loss: Some(StopSpec::Percent(0.12)),
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_override_only_touches_its_symbol() {
+        let data = data();
+        let plain = MultiSymbolConfig::new(base()).run(&data).unwrap();
+        let overridden = wide_soxl_stop().run(&data).unwrap();
+
+        let trades = |runs: &[(String, BacktestResult)], symbol: &str| {
+            let (_, result) = runs.iter().find(|(s, _)| s == symbol).unwrap();
+            serde_json::to_string(&result.trades).unwrap()
+        };
+        assert_eq!(trades(&plain, "TQQQ"), trades(&overridden, "TQQQ"));
+
+        let soxl = wide_soxl_stop().params_for("SOXL").unwrap();
+        assert_eq!(soxl.risk.stop_loss, StopSpec::Percent(0.12));
+        assert_eq!(soxl.strategy.symbol, "SOXL");
+        assert_eq!(soxl.initial_capital, base().initial_capital);
+    }
+
+    #[test]
+    fn test_merged_params_are_validated() {
+        let config = MultiSymbolConfig::new(base()).with_override(
+            "SOXL",
+            PartialParameters {
+                stop_loss: Some(StopSpec::Percent(1.5)),
+                ..Default::default()
+            },
+        );
+
+        assert!(config.params_for("TQQQ").is_ok());
+        assert!(matches!(
+            config.run(&data()),
+            Err(BacktestError::InvalidParameter(_))
+        ));
+    }
+}
@@ -0,0 +1,402 @@
+//! Grid-search parameter optimization
+
+pub mod score;
+
+pub use score::{score, Constraint, ScoreSpec};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use common::{
+    BacktestError, BacktestParameters, Bar, MetricField, PerformanceMetrics, Result, StopSpec,
+};
+
+use crate::engine::BacktestEngine;
+use crate::limits::{Budget, JobCompletion, JobLimits};
+
+/// Values to sweep; every combination is run on top of a base parameter set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterGrid {
+    pub rsi_periods: Vec<usize>,
+    pub rsi_oversold_levels: Vec<f64>,
+    pub rsi_overbought_levels: Vec<f64>,
+    pub stop_loss_pcts: Vec<f64>,
+}
+
+impl Default for ParameterGrid {
+    fn default() -> Self {
+        Self {
+            rsi_periods: vec![2, 3, 4],
+            rsi_oversold_levels: vec![5.0, 10.0, 15.0],
+            rsi_overbought_levels: vec![60.0, 70.0, 80.0],
+            stop_loss_pcts: vec![0.03, 0.05, 0.07],
+        }
+    }
+}
+
+impl ParameterGrid {
+    /// All valid parameter sets of the grid; combinations that fail
+    /// `validate()` (e.g. oversold >= overbought) are skipped
+    pub fn combinations(&self, base: &BacktestParameters) -> Vec<BacktestParameters> {
+        let mut combos = Vec::new();
+        for &rsi_period in &self.rsi_periods {
+            for &oversold in &self.rsi_oversold_levels {
+                for &overbought in &self.rsi_overbought_levels {
+                    for &stop_loss in &self.stop_loss_pcts {
+                        let mut params = base.clone();
+                        params.strategy.rsi_period = rsi_period;
+                        params.strategy.rsi_oversold = oversold;
+                        params.strategy.rsi_overbought = overbought;
+                        params.risk.stop_loss = StopSpec::from_pct(stop_loss);
+                        if params.validate().is_ok() {
+                            combos.push(params);
+                        }
+                    }
+                }
+            }
+        }
+        combos
+    }
+}
+
+/// Metric used to rank grid runs (higher is better)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankBy {
+    /// A single metric
+    Metric(MetricField),
+    /// Weighted blend of metrics with rejection constraints
+    Composite(ScoreSpec),
+}
+
+impl Default for RankBy {
+    fn default() -> Self {
+        RankBy::Metric(MetricField::SharpeRatio)
+    }
+}
+
+impl RankBy {
+    pub fn score(&self, metrics: &PerformanceMetrics) -> f64 {
+        match self {
+            RankBy::Metric(field) => metrics.get(*field),
+            RankBy::Composite(spec) => score(metrics, spec),
+        }
+    }
+}
+
+/// One evaluated grid point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GridRun {
+    pub params: BacktestParameters,
+    pub metrics: PerformanceMetrics,
+    pub score: f64,
+    /// Evaluated on strided bars only (see [`TwoPhase`])
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub approximate: bool,
+}
+
+impl GridRun {
+    fn evaluate(params: BacktestParameters, bars: &[Bar], rank_by: &RankBy, stride: usize) -> Self {
+        let result = BacktestEngine::new(params.clone())
+            .with_bar_stride(stride)
+            .run(bars, None);
+        GridRun {
+            params,
+            score: rank_by.score(&result.metrics),
+            metrics: result.metrics,
+            approximate: result.approximate,
+        }
+    }
+}
+
+/// Coarse-to-fine search: every combination on every `coarse_stride`th
+/// bar, then the best `refine_top` again on every bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TwoPhase {
+    pub coarse_stride: usize,
+    pub refine_top: usize,
+}
+
+/// All grid runs, best first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationResult {
+    pub rank_by: RankBy,
+    pub runs: Vec<GridRun>,
+    #[serde(flatten)]
+    pub completion: JobCompletion,
+}
+
+impl OptimizationResult {
+    pub fn best(&self) -> Option<&GridRun> {
+        self.runs.first()
+    }
+}
+
+/// Run every grid combination in parallel and rank the results
+pub fn grid_search(
+    bars: &[Bar],
+    base: &BacktestParameters,
+    grid: &ParameterGrid,
+    rank_by: &RankBy,
+) -> Result<OptimizationResult> {
+    grid_search_with_limits(bars, base, grid, rank_by, JobLimits::default())
+}
+
+/// `grid_search` within `limits`: once one is hit the combinations run so
+/// far are ranked and the result is marked truncated
+pub fn grid_search_with_limits(
+    bars: &[Bar],
+    base: &BacktestParameters,
+    grid: &ParameterGrid,
+    rank_by: &RankBy,
+    limits: JobLimits,
+) -> Result<OptimizationResult> {
+    let combos = valid_combinations(base, grid)?;
+    let planned = combos.len();
+    let budget = limits.start();
+    let runs = ranked_runs(combos, bars, rank_by, 1, &budget);
+
+    Ok(OptimizationResult {
+        rank_by: rank_by.clone(),
+        completion: budget.completion(runs.len(), planned),
+        runs,
+    })
+}
+
+/// `grid_search_with_limits` in two phases. The refined runs rank first,
+/// best first, then the remaining coarse ones marked `approximate`; a
+/// limit hit while refining leaves the unrefined ones coarse. Both phases
+/// count against `limits`.
+pub fn grid_search_two_phase(
+    bars: &[Bar],
+    base: &BacktestParameters,
+    grid: &ParameterGrid,
+    rank_by: &RankBy,
+    limits: JobLimits,
+    phases: TwoPhase,
+) -> Result<OptimizationResult> {
+    let combos = valid_combinations(base, grid)?;
+    // A stride of 1 is already full resolution
+    let refine_top = if phases.coarse_stride > 1 {
+        phases.refine_top.min(combos.len())
+    } else {
+        0
+    };
+    let planned = combos.len() + refine_top;
+    let budget = limits.start();
+    let mut runs = ranked_runs(combos, bars, rank_by, phases.coarse_stride, &budget);
+    let coarse_runs = runs.len();
+
+    let rest = runs.split_off(refine_top.min(runs.len()));
+    let mut runs: Vec<GridRun> = runs
+        .into_par_iter()
+        .map(|run| {
+            if budget.claim() {
+                GridRun::evaluate(run.params, bars, rank_by, 1)
+            } else {
+                run
+            }
+        })
+        .collect();
+    let refined = runs.iter().filter(|r| !r.approximate).count();
+    runs.sort_by(|a, b| {
+        a.approximate
+            .cmp(&b.approximate)
+            .then(b.score.total_cmp(&a.score))
+    });
+    runs.extend(rest);
+
+    Ok(OptimizationResult {
+        rank_by: rank_by.clone(),
+        completion: budget.completion(coarse_runs + refined, planned),
+        runs,
+    })
+}
+
+fn valid_combinations(
+    base: &BacktestParameters,
+    grid: &ParameterGrid,
+) -> Result<Vec<BacktestParameters>> {
+    let combos = grid.combinations(base);
+    if combos.is_empty() {
+        return Err(BacktestError::InvalidParameter(
+            "parameter grid has no valid combinations".to_string(),
+        ));
+    }
+    Ok(combos)
+}
+
+/// Runs of `combos` on every `stride`th bar while `budget` allows, best first
+fn ranked_runs(
+    combos: Vec<BacktestParameters>,
+    bars: &[Bar],
+    rank_by: &RankBy,
+    stride: usize,
+    budget: &Budget,
+) -> Vec<GridRun> {
+    let mut runs: Vec<GridRun> = combos
+        .into_par_iter()
+        .filter(|_| budget.claim())
+        .map(|params| GridRun::evaluate(params, bars, rank_by, stride))
+        .collect();
+    runs.sort_by(|a, b| b.score.total_cmp(&a.score));
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+
+    #[test]
+    fn test_combinations_skip_invalid() {
+        let grid = ParameterGrid {
+            rsi_periods: vec![2],
+            rsi_oversold_levels: vec![10.0, 70.0],
+            rsi_overbought_levels: vec![60.0, 80.0],
+            stop_loss_pcts: vec![0.05],
+        };
+        // 70/60 is inverted and dropped
+        assert_eq!(grid.combinations(&BacktestParameters::default()).len(), 3);
+    }
+
+    #[test]
+    fn test_grid_search_ranks_best_first() {
+        let bars = generate_synthetic_bars_seeded(300, 50.0, 42);
+        let base = BacktestParameters::default().without_vwap_filter();
+        let result = grid_search(
+            &bars,
+            &base,
+            &ParameterGrid::default(),
+            &RankBy::Metric(MetricField::TotalReturnPct),
+        )
+        .unwrap();
+
+        assert_eq!(result.runs.len(), 81);
+        assert!(result.runs.windows(2).all(|w| w[0].score >= w[1].score));
+        let best = result.best().unwrap();
+        assert_eq!(best.score, best.metrics.total_return_pct);
+    }
+
+    #[test]
+    fn test_composite_rejected_runs_rank_last() {
+        let bars = generate_synthetic_bars_seeded(300, 50.0, 42);
+        let base = BacktestParameters::default().without_vwap_filter();
+        let spec = ScoreSpec {
+            weights: vec![(common::MetricField::SharpeRatio, 1.0)],
+            penalties: vec![Constraint::min(common::MetricField::TotalTrades, 5.0)],
+        };
+        let result = grid_search(
+            &bars,
+            &base,
+            &ParameterGrid::default(),
+            &RankBy::Composite(spec),
+        )
+        .unwrap();
+
+        let first_rejected = result
+            .runs
+            .iter()
+            .position(|r| r.score == f64::NEG_INFINITY)
+            .unwrap_or(result.runs.len());
+        assert!(result.runs[..first_rejected]
+            .iter()
+            .all(|r| r.metrics.total_trades >= 5));
+        assert!(result.runs[first_rejected..]
+            .iter()
+            .all(|r| r.metrics.total_trades < 5));
+    }
+
+    #[test]
+    fn test_two_phase_picks_refined_winner() {
+        let bars = generate_synthetic_bars_seeded(600, 50.0, 42);
+        let base = BacktestParameters::default().without_vwap_filter();
+        let grid = ParameterGrid::default();
+        let rank_by = RankBy::Metric(MetricField::TotalReturnPct);
+        let phases = TwoPhase {
+            coarse_stride: 3,
+            refine_top: 5,
+        };
+        let result =
+            grid_search_two_phase(&bars, &base, &grid, &rank_by, JobLimits::default(), phases)
+                .unwrap();
+
+        assert_eq!(result.runs.len(), 81);
+        assert_eq!(result.completion.completed, 86);
+        assert!(result.runs[..5].iter().all(|r| !r.approximate));
+        assert!(result.runs[5..].iter().all(|r| r.approximate));
+        assert!(result.runs[..5]
+            .windows(2)
+            .all(|w| w[0].score >= w[1].score));
+
+        // The winner's score is its full-resolution one
+        let full = grid_search(&bars, &base, &grid, &rank_by).unwrap();
+        let best = result.best().unwrap();
+        let key = |params: &BacktestParameters| serde_json::to_value(params).unwrap();
+        let same = full
+            .runs
+            .iter()
+            .find(|r| key(&r.params) == key(&best.params))
+            .unwrap();
+        assert_eq!(best.score, same.score);
+        assert!(full.runs.iter().all(|r| !r.approximate));
+
+        // Stride 1 skips the refinement
+        let phases = TwoPhase {
+            coarse_stride: 1,
+            refine_top: 5,
+        };
+        let single =
+            grid_search_two_phase(&bars, &base, &grid, &rank_by, JobLimits::default(), phases)
+                .unwrap();
+        assert_eq!(single.completion.completed, 81);
+        assert_eq!(single.best().unwrap().score, full.best().unwrap().score);
+    }
+
+    #[test]
+    fn test_empty_grid_is_an_error() {
+        let grid = ParameterGrid {
+            rsi_periods: vec![],
+            ..Default::default()
+        };
+        assert!(grid_search(
+            &[],
+            &BacktestParameters::default(),
+            &grid,
+            &RankBy::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_wall_clock_budget_truncates_large_grid() {
+        let bars = generate_synthetic_bars_seeded(300, 50.0, 42);
+        let base = BacktestParameters::default().without_vwap_filter();
+        let grid = ParameterGrid {
+            rsi_periods: (2..12).collect(),
+            rsi_oversold_levels: vec![5.0, 10.0, 15.0, 20.0, 25.0],
+            rsi_overbought_levels: vec![60.0, 65.0, 70.0, 75.0, 80.0],
+            stop_loss_pcts: vec![0.02, 0.03, 0.04, 0.05, 0.06, 0.07],
+        };
+        let limits = JobLimits::default().with_max_wall_clock(std::time::Duration::from_millis(1));
+        let result =
+            grid_search_with_limits(&bars, &base, &grid, &RankBy::default(), limits).unwrap();
+
+        assert!(result.completion.truncated);
+        assert_eq!(result.completion.planned, 1500);
+        assert_eq!(result.completion.completed, result.runs.len());
+        assert!(result.runs.len() < 1500);
+        assert!(result.runs.windows(2).all(|w| w[0].score >= w[1].score));
+
+        let limits = JobLimits::default().with_max_backtests(7);
+        let result =
+            grid_search_with_limits(&bars, &base, &grid, &RankBy::default(), limits).unwrap();
+        assert_eq!(result.runs.len(), 7);
+        assert!(result.completion.truncated);
+
+        let full =
+            grid_search(&bars, &base, &ParameterGrid::default(), &RankBy::default()).unwrap();
+        assert!(!full.completion.truncated);
+        assert_eq!(full.completion.completed, 81);
+    }
+}
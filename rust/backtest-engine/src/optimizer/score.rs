@@ -0,0 +1,221 @@
+//! Weighted composite score for ranking optimizer runs
+//!
+//! A single metric such as Sharpe tends to pick fragile corners of the grid
+//! (few trades, one lucky streak). A composite blends several metrics and
+//! hard-rejects runs that break a constraint.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use common::{BacktestError, MetricField, PerformanceMetrics, Result};
+
+/// Infinite metric values (e.g. profit factor of a run without losers) are
+/// clamped to this magnitude so they cannot swamp the weighted sum
+pub const NON_FINITE_CAP: f64 = 10.0;
+
+/// `value` with infinities clamped to ±[`NON_FINITE_CAP`] and NaN as 0
+pub fn capped(value: f64) -> f64 {
+    if value.is_finite() {
+        value
+    } else if value.is_nan() {
+        0.0
+    } else {
+        value.signum() * NON_FINITE_CAP
+    }
+}
+
+/// Hard bound on a metric; a run outside it scores `-inf`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Constraint {
+    pub metric: MetricField,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+impl Constraint {
+    pub fn min(metric: MetricField, min: f64) -> Self {
+        Self {
+            metric,
+            min: Some(min),
+            max: None,
+        }
+    }
+
+    pub fn max(metric: MetricField, max: f64) -> Self {
+        Self {
+            metric,
+            min: None,
+            max: Some(max),
+        }
+    }
+
+    pub fn is_satisfied(&self, metrics: &PerformanceMetrics) -> bool {
+        let value = metrics.get(self.metric);
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+/// Weighted sum of metrics plus rejection constraints
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreSpec {
+    pub weights: Vec<(MetricField, f64)>,
+    #[serde(default)]
+    pub penalties: Vec<Constraint>,
+}
+
+impl Default for ScoreSpec {
+    /// 0.4 Sharpe + 0.3 Calmar + 0.3 profit factor, rejecting runs with
+    /// fewer than 20 trades
+    fn default() -> Self {
+        Self {
+            weights: vec![
+                (MetricField::SharpeRatio, 0.4),
+                (MetricField::CalmarRatio, 0.3),
+                (MetricField::ProfitFactor, 0.3),
+            ],
+            penalties: vec![Constraint::min(MetricField::TotalTrades, 20.0)],
+        }
+    }
+}
+
+impl ScoreSpec {
+    /// Load a spec from a TOML or JSON file, detecting format from extension
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| BacktestError::ConfigError(format!("{}: {}", path.display(), e)))?;
+
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+
+        let spec: ScoreSpec = match ext.as_str() {
+            "json" => serde_json::from_str(&content)?,
+            "toml" => toml::from_str(&content)
+                .map_err(|e| BacktestError::ConfigError(format!("{}: {}", path.display(), e)))?,
+            _ => {
+                return Err(BacktestError::ConfigError(format!(
+                    "Unsupported score spec format: {}",
+                    ext
+                )))
+            }
+        };
+
+        if spec.weights.is_empty() {
+            return Err(BacktestError::ConfigError(
+                "score spec needs at least one weight".to_string(),
+            ));
+        }
+        Ok(spec)
+    }
+}
+
+/// Composite score of a run; `-inf` when any constraint is violated
+pub fn score(metrics: &PerformanceMetrics, spec: &ScoreSpec) -> f64 {
+    if !spec.penalties.iter().all(|c| c.is_satisfied(metrics)) {
+        return f64::NEG_INFINITY;
+    }
+
+    spec.weights
+        .iter()
+        .map(|(field, weight)| weight * capped(metrics.get(*field)))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics(sharpe: f64, calmar: f64, profit_factor: f64, trades: u32) -> PerformanceMetrics {
+        PerformanceMetrics {
+            sharpe_ratio: sharpe,
+            calmar_ratio: calmar,
+            profit_factor,
+            total_trades: trades,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_spec_weights() {
+        let m = metrics(1.0, 2.0, 1.5, 25);
+        let expected = 0.4 * 1.0 + 0.3 * 2.0 + 0.3 * 1.5;
+        assert!((score(&m, &ScoreSpec::default()) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_constraints_reject() {
+        let spec = ScoreSpec::default();
+        assert_eq!(score(&metrics(3.0, 5.0, 4.0, 19), &spec), f64::NEG_INFINITY);
+
+        let spec = ScoreSpec {
+            weights: vec![(MetricField::SharpeRatio, 1.0)],
+            penalties: vec![Constraint::max(MetricField::MaxDrawdown, 30.0)],
+        };
+        let mut deep = metrics(2.0, 0.0, 0.0, 50);
+        deep.max_drawdown = 31.0;
+        assert_eq!(score(&deep, &spec), f64::NEG_INFINITY);
+        deep.max_drawdown = 30.0;
+        assert_eq!(score(&deep, &spec), 2.0);
+    }
+
+    #[test]
+    fn test_weights_change_ranking() {
+        let high_sharpe = metrics(2.0, 0.5, 1.2, 30);
+        let high_calmar = metrics(0.8, 3.0, 1.2, 30);
+
+        let sharpe_heavy = ScoreSpec {
+            weights: vec![
+                (MetricField::SharpeRatio, 0.8),
+                (MetricField::CalmarRatio, 0.2),
+            ],
+            penalties: vec![],
+        };
+        let calmar_heavy = ScoreSpec {
+            weights: vec![
+                (MetricField::SharpeRatio, 0.2),
+                (MetricField::CalmarRatio, 0.8),
+            ],
+            penalties: vec![],
+        };
+
+        assert!(score(&high_sharpe, &sharpe_heavy) > score(&high_calmar, &sharpe_heavy));
+        assert!(score(&high_calmar, &calmar_heavy) > score(&high_sharpe, &calmar_heavy));
+    }
+
+    #[test]
+    fn test_infinite_profit_factor_is_capped() {
+        let m = metrics(0.0, 0.0, f64::INFINITY, 25);
+        assert_eq!(score(&m, &ScoreSpec::default()), 0.3 * NON_FINITE_CAP);
+    }
+
+    #[test]
+    fn test_load_toml_spec() {
+        let path = std::env::temp_dir().join("score_spec_test.toml");
+        fs::write(
+            &path,
+            r#"
+weights = [["sharpe_ratio", 0.5], ["win_rate", 0.01]]
+
+[[penalties]]
+metric = "max_drawdown"
+max = 30.0
+"#,
+        )
+        .unwrap();
+
+        let spec = ScoreSpec::load(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(spec.weights[1], (MetricField::WinRate, 0.01));
+        assert_eq!(
+            spec.penalties,
+            vec![Constraint::max(MetricField::MaxDrawdown, 30.0)]
+        );
+    }
+}
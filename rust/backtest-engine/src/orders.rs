@@ -0,0 +1,303 @@
+//! Bracket orders
+//!
+//! A bracket is a limit entry with an attached stop-loss and take-profit
+//! that form a one-cancels-other pair. The exits only become active once the
+//! entry fills; the first one touched fills and cancels the other.
+//!
+//! Fills happen at the order price, or at the open when the bar gaps
+//! through it. Orders are evaluated from the bar after they were submitted
+//! (entry) or filled (exits), so nothing fills on the bar that created it.
+//!
+//! Every order the engine places, bracket or not, gets an id from the run's
+//! [`OrderAudit`], which keeps the signals and fills stamped with it.
+
+use common::{Bar, Fill, SameBarExit, Signal};
+
+/// Long bracket: buy limit at `entry`, then sell at `stop` or `target`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BracketOrder {
+    pub quantity: f64,
+    pub entry: f64,
+    pub stop: f64,
+    pub target: f64,
+}
+
+/// Fill produced by a bracket on one bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BracketEvent {
+    EntryFilled {
+        price: f64,
+        quantity: f64,
+        stop: f64,
+    },
+    StopFilled {
+        price: f64,
+    },
+    TargetFilled {
+        price: f64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BracketState {
+    /// Entry working since the given bar
+    PendingEntry(usize),
+    /// Entry filled on the given bar; stop and target working
+    Active(usize),
+}
+
+/// Tracks the single working bracket of a run
+#[derive(Debug)]
+pub struct OrderManager {
+    same_bar_exit: SameBarExit,
+    working: Option<(BracketOrder, BracketState)>,
+}
+
+impl OrderManager {
+    pub fn new(same_bar_exit: SameBarExit) -> Self {
+        Self {
+            same_bar_exit,
+            working: None,
+        }
+    }
+
+    /// Submit a bracket on `bar_index`, replacing any unfilled entry.
+    /// Returns false (and keeps the current bracket) while exits are active.
+    pub fn submit(&mut self, order: BracketOrder, bar_index: usize) -> bool {
+        if self.has_active_exits() {
+            return false;
+        }
+        self.working = Some((order, BracketState::PendingEntry(bar_index)));
+        true
+    }
+
+    /// Whether an entry is waiting to fill
+    pub fn has_pending_entry(&self) -> bool {
+        matches!(self.working, Some((_, BracketState::PendingEntry(_))))
+    }
+
+    /// Whether the stop/target pair is working
+    pub fn has_active_exits(&self) -> bool {
+        matches!(self.working, Some((_, BracketState::Active(_))))
+    }
+
+    /// Cancel the working bracket (entry or exits)
+    pub fn cancel(&mut self) {
+        self.working = None;
+    }
+
+    /// Evaluate the working bracket against `bar`
+    pub fn process_bar(&mut self, bar: &Bar, bar_index: usize) -> Option<BracketEvent> {
+        let (order, state) = self.working.as_ref()?;
+
+        match *state {
+            BracketState::PendingEntry(submitted) if bar_index > submitted => {
+                if bar.low > order.entry {
+                    return None;
+                }
+                let event = BracketEvent::EntryFilled {
+                    price: bar.open.min(order.entry),
+                    quantity: order.quantity,
+                    stop: order.stop,
+                };
+                self.working = Some((order.clone(), BracketState::Active(bar_index)));
+                Some(event)
+            }
+            BracketState::Active(filled) if bar_index > filled => {
+                let event = self.exit_on(order, bar)?;
+                self.working = None;
+                Some(event)
+            }
+            _ => None,
+        }
+    }
+
+    fn exit_on(&self, order: &BracketOrder, bar: &Bar) -> Option<BracketEvent> {
+        // Gaps fill at the open, which settles the order unambiguously
+        if bar.open <= order.stop {
+            return Some(BracketEvent::StopFilled { price: bar.open });
+        }
+        if bar.open >= order.target {
+            return Some(BracketEvent::TargetFilled { price: bar.open });
+        }
+
+        let stop = BracketEvent::StopFilled { price: order.stop };
+        let target = BracketEvent::TargetFilled {
+            price: order.target,
+        };
+        match (bar.low <= order.stop, bar.high >= order.target) {
+            (true, true) => Some(match self.same_bar_exit {
+                SameBarExit::StopFirst => stop,
+                SameBarExit::TargetFirst => target,
+            }),
+            (true, false) => Some(stop),
+            (false, true) => Some(target),
+            (false, false) => None,
+        }
+    }
+}
+
+/// Issues a run's order ids, in increasing order from 1, and keeps the
+/// records stamped with them
+#[derive(Debug, Default)]
+pub struct OrderAudit {
+    last_id: u64,
+    signals: Vec<Signal>,
+    fills: Vec<Fill>,
+}
+
+impl OrderAudit {
+    /// Id for a new order, recording a stamped copy of the signal behind it
+    /// (None for orders the engine places on its own, such as stops)
+    pub fn place(&mut self, signal: Option<&Signal>) -> u64 {
+        self.last_id += 1;
+        if let Some(signal) = signal {
+            self.signals.push(Signal {
+                order_id: Some(self.last_id),
+                ..signal.clone()
+            });
+        }
+        self.last_id
+    }
+
+    pub fn record_fill(&mut self, fill: Fill) {
+        self.fills.push(fill);
+    }
+
+    /// The stamped signals and the fills, each in the order they happened
+    pub fn into_records(self) -> (Vec<Signal>, Vec<Fill>) {
+        (self.signals, self.fills)
+    }
+
+    /// The records since the last call, leaving order ids counting on
+    pub fn take_records(&mut self) -> (Vec<Signal>, Vec<Fill>) {
+        (
+            std::mem::take(&mut self.signals),
+            std::mem::take(&mut self.fills),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn bar(open: f64, high: f64, low: f64, close: f64) -> Bar {
+        Bar {
+            timestamp: Utc.with_ymd_and_hms(2024, 1, 2, 16, 0, 0).unwrap(),
+            open,
+            high,
+            low,
+            close,
+            volume: 1_000_000,
+            vwap: None,
+            halted: false,
+        }
+    }
+
+    fn bracket() -> BracketOrder {
+        BracketOrder {
+            quantity: 10.0,
+            entry: 100.0,
+            stop: 95.0,
+            target: 110.0,
+        }
+    }
+
+    fn filled_manager(policy: SameBarExit) -> OrderManager {
+        let mut orders = OrderManager::new(policy);
+        orders.submit(bracket(), 0);
+        orders.process_bar(&bar(101.0, 102.0, 99.5, 100.5), 1);
+        orders
+    }
+
+    #[test]
+    fn test_entry_fills_then_target() {
+        let mut orders = OrderManager::new(SameBarExit::StopFirst);
+        assert!(orders.submit(bracket(), 0));
+        // Nothing fills on the submitting bar
+        assert_eq!(orders.process_bar(&bar(100.0, 101.0, 99.0, 100.0), 0), None);
+
+        let entry = orders.process_bar(&bar(101.0, 102.0, 99.5, 100.5), 1);
+        assert_eq!(
+            entry,
+            Some(BracketEvent::EntryFilled {
+                price: 100.0,
+                quantity: 10.0,
+                stop: 95.0
+            })
+        );
+        assert!(orders.has_active_exits());
+        assert!(!orders.submit(bracket(), 1));
+
+        assert_eq!(
+            orders.process_bar(&bar(104.0, 108.0, 103.0, 107.0), 2),
+            None
+        );
+        assert_eq!(
+            orders.process_bar(&bar(107.0, 111.0, 106.0, 109.0), 3),
+            Some(BracketEvent::TargetFilled { price: 110.0 })
+        );
+        // The stop was cancelled with the target fill
+        assert_eq!(orders.process_bar(&bar(90.0, 91.0, 80.0, 85.0), 4), None);
+        assert!(!orders.has_active_exits());
+    }
+
+    #[test]
+    fn test_entry_fills_then_stop() {
+        let mut orders = filled_manager(SameBarExit::StopFirst);
+
+        assert_eq!(
+            orders.process_bar(&bar(97.0, 98.0, 94.0, 96.0), 2),
+            Some(BracketEvent::StopFilled { price: 95.0 })
+        );
+        assert_eq!(
+            orders.process_bar(&bar(109.0, 120.0, 108.0, 115.0), 3),
+            None
+        );
+    }
+
+    #[test]
+    fn test_entry_never_fills() {
+        let mut orders = OrderManager::new(SameBarExit::StopFirst);
+        orders.submit(bracket(), 0);
+
+        // Price runs away from the limit: the entry stays pending and the
+        // exits never activate, even when price crosses their levels
+        for (i, b) in [
+            bar(102.0, 112.0, 101.0, 111.0),
+            bar(111.0, 115.0, 100.5, 114.0),
+        ]
+        .iter()
+        .enumerate()
+        {
+            assert_eq!(orders.process_bar(b, i + 1), None);
+        }
+        assert!(orders.has_pending_entry());
+        assert!(!orders.has_active_exits());
+    }
+
+    #[test]
+    fn test_same_bar_ambiguity_and_gaps() {
+        let wide = bar(100.0, 112.0, 94.0, 100.0);
+
+        let mut pessimistic = filled_manager(SameBarExit::StopFirst);
+        assert_eq!(
+            pessimistic.process_bar(&wide, 2),
+            Some(BracketEvent::StopFilled { price: 95.0 })
+        );
+        let mut optimistic = filled_manager(SameBarExit::TargetFirst);
+        assert_eq!(
+            optimistic.process_bar(&wide, 2),
+            Some(BracketEvent::TargetFilled { price: 110.0 })
+        );
+
+        // A gap through the stop fills at the open regardless of policy
+        let mut gapped = filled_manager(SameBarExit::TargetFirst);
+        assert_eq!(
+            gapped.process_bar(&bar(90.0, 115.0, 89.0, 112.0), 2),
+            Some(BracketEvent::StopFilled { price: 90.0 })
+        );
+    }
+}
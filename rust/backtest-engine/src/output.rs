@@ -0,0 +1,166 @@
+//! Output files written whole or not at all
+//!
+//! Every file the engine exports goes through [`write_file`]: it is written
+//! to a temporary file beside its destination and renamed over it once
+//! complete, so a failure mid-write never leaves a partial file behind. A
+//! file already at the destination is kept unless the caller passes
+//! [`Overwrite::Force`], the CLI's `--force`.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use common::{BacktestError, Result};
+
+/// Whether a write may replace a file already at its destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overwrite {
+    /// Fail with [`BacktestError::OutputError`] instead
+    #[default]
+    Refuse,
+    Force,
+}
+
+impl Overwrite {
+    /// `Force` when `force` is set, as by the `--force` flag
+    pub fn forced(force: bool) -> Self {
+        if force {
+            Self::Force
+        } else {
+            Self::Refuse
+        }
+    }
+}
+
+/// Write `path` with `write`, creating its directory as needed
+///
+/// # Errors
+/// OutputError naming `path` when it exists and `overwrite` refuses, or
+/// when `write` or the file system fails; the destination is then as it was
+pub fn write_file<F>(path: &Path, overwrite: Overwrite, write: F) -> Result<()>
+where
+    F: FnOnce(&mut dyn Write) -> Result<()>,
+{
+    let failed = |reason: String| BacktestError::OutputError {
+        path: path.display().to_string(),
+        reason,
+    };
+    if overwrite == Overwrite::Refuse && path.exists() {
+        return Err(failed(
+            "already exists (pass --force to overwrite)".to_string(),
+        ));
+    }
+    let Some(name) = path.file_name() else {
+        return Err(failed("not a file name".to_string()));
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(dir).map_err(|e| failed(e.to_string()))?;
+
+    let temp = dir.join(format!(
+        ".{}.{}.tmp",
+        name.to_string_lossy(),
+        std::process::id()
+    ));
+    let written = File::create(&temp)
+        .map_err(BacktestError::from)
+        .and_then(|file| {
+            let mut out = BufWriter::new(file);
+            write(&mut out)?;
+            let file = out.into_inner().map_err(|e| e.into_error())?;
+            file.sync_all()?;
+            Ok(fs::rename(&temp, path)?)
+        });
+    written.map_err(|e| {
+        let _ = fs::remove_file(&temp);
+        failed(e.to_string())
+    })
+}
+
+/// Fail as [`write_file`] does when any of `paths` exists and `overwrite`
+/// refuses, before the caller creates any of them
+pub fn refuse_existing(paths: &[PathBuf], overwrite: Overwrite) -> Result<()> {
+    if overwrite == Overwrite::Force {
+        return Ok(());
+    }
+    match paths.iter().find(|path| path.exists()) {
+        Some(path) => Err(BacktestError::OutputError {
+            path: path.display().to_string(),
+            reason: "already exists (pass --force to overwrite)".to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Write `bytes` to `path` (see [`write_file`])
+pub fn write_bytes(path: &Path, overwrite: Overwrite, bytes: &[u8]) -> Result<()> {
+    write_file(path, overwrite, |out| Ok(out.write_all(bytes)?))
+}
+
+/// Write `rows` to `path` as CSV, headed by the field names of struct rows
+/// (see [`write_file`])
+pub fn write_csv<T: Serialize>(path: &Path, overwrite: Overwrite, rows: &[T]) -> Result<()> {
+    write_file(path, overwrite, |out| {
+        let mut writer = csv::Writer::from_writer(out);
+        for row in rows {
+            writer.serialize(row).map_err(io::Error::from)?;
+        }
+        Ok(writer.flush()?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn output_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_existing_file_needs_force() {
+        let dir = output_dir("output_no_clobber");
+        let path = dir.join("nested").join("report.txt");
+        write_bytes(&path, Overwrite::Refuse, b"first").unwrap();
+
+        let err = write_bytes(&path, Overwrite::Refuse, b"second").unwrap_err();
+        assert!(
+            matches!(&err, BacktestError::OutputError { path: p, reason }
+                if p.ends_with("report.txt") && reason.contains("--force")),
+            "{}",
+            err
+        );
+        assert_eq!(fs::read(&path).unwrap(), b"first");
+
+        write_bytes(&path, Overwrite::Force, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_write_leaves_no_partial_file() {
+        let dir = output_dir("output_failed_write");
+        let path = dir.join("rows.csv");
+        let failing = |out: &mut dyn Write| -> Result<()> {
+            out.write_all(b"date,close\n2024-01-02,")?;
+            Err(BacktestError::CsvError("disk full".to_string()))
+        };
+
+        let err = write_file(&path, Overwrite::Refuse, failing).unwrap_err();
+        assert!(err.to_string().contains("disk full"), "{}", err);
+        assert!(!path.exists());
+
+        // Nor is a file it was to replace touched, and no temporary is left
+        write_csv(&path, Overwrite::Refuse, &[("2024-01-02", 100.5)]).unwrap();
+        assert!(write_file(&path, Overwrite::Force, failing).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2024-01-02,100.5\n");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
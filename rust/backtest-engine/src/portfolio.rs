@@ -1,15 +1,68 @@
 use chrono::{DateTime, Utc};
-use common::{Position, PositionSide, Result, Side, Trade};
+use common::{
+    ExitFill, HedgeParams, Money, Position, PositionSide, Result, Side, SizingMode, StopSpec, Trade,
+};
 
 /// Portfolio manager for tracking positions and calculating P&L
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Portfolio {
     initial_capital: f64,
     cash: f64,
     position: Option<Position>,
     hedge_position: Option<Position>,
+    synthetic_short: Option<SyntheticShort>,
+    /// Tranches of the main position sold so far by an exit still filling
+    staged_exit: Option<StagedExit>,
     realized_pnl: f64,
     trades: Vec<Trade>,
+    /// Leading entries of `trades` already handed out by `spill_trades`
+    spilled: usize,
+    /// Precise accounting: the cent ledger is authoritative and `cash` /
+    /// `realized_pnl` mirror it
+    ledger: Option<CentsLedger>,
+}
+
+/// Cash and realized P&L in whole cents
+#[derive(Debug, Clone, Copy)]
+struct CentsLedger {
+    cash: Money,
+    realized_pnl: Money,
+}
+
+/// What the tranches of a staged exit sold, until the last one closes the
+/// position into a single trade
+#[derive(Debug, Clone, Default)]
+struct StagedExit {
+    fills: Vec<ExitFill>,
+    quantity: f64,
+    cost_basis: f64,
+    pnl: f64,
+    /// Commissions of the tranches' sales and their share of the entry's
+    commission: f64,
+}
+
+/// Cash-settled short on the primary symbol, the hedge when there is no
+/// inverse instrument. No shares change hands: the contract pays
+/// `-leverage` times the primary's return since entry on `notional`.
+#[derive(Debug, Clone)]
+pub struct SyntheticShort {
+    pub symbol: String,
+    pub notional: f64,
+    pub leverage: f64,
+    pub entry_price: f64,
+    pub current_price: f64,
+    pub entry_date: DateTime<Utc>,
+    pub entry_reason: String,
+    pub entry_rsi: Option<f64>,
+    pub entry_order_id: Option<u64>,
+    pub entry_commission: f64,
+}
+
+impl SyntheticShort {
+    /// Settlement value with the primary at `price`
+    pub fn pnl_at(&self, price: f64) -> f64 {
+        -self.leverage * (price / self.entry_price - 1.0) * self.notional
+    }
 }
 
 impl Portfolio {
@@ -19,14 +72,55 @@ impl Portfolio {
             cash: initial_capital,
             position: None,
             hedge_position: None,
+            synthetic_short: None,
+            staged_exit: None,
             realized_pnl: 0.0,
             trades: Vec::new(),
+            spilled: 0,
+            ledger: None,
         }
     }
 
+    /// Start out holding `positions` (at most one long or short and one
+    /// hedge). `capital` is the account value at their entry, so cash is
+    /// what remains after their cost basis.
+    pub fn new_with_positions(capital: f64, positions: Vec<Position>) -> Self {
+        let mut portfolio = Self::new(capital);
+        for mut position in positions {
+            portfolio.cash -= position.quantity * position.avg_entry_price;
+            position.initial_stop_price = position.initial_stop_price.or(position.stop_loss_price);
+            match position.side {
+                PositionSide::Hedge => portfolio.hedge_position = Some(position),
+                _ => portfolio.position = Some(position),
+            }
+        }
+        portfolio
+    }
+
+    /// Account cash, cost basis and P&L in whole cents. Fill values are
+    /// rounded to the cent; prices stay f64.
+    pub fn with_precise_accounting(mut self) -> Self {
+        let ledger = CentsLedger {
+            cash: Money::from_dollars(self.cash),
+            realized_pnl: Money::from_dollars(self.realized_pnl),
+        };
+        self.cash = ledger.cash.to_dollars();
+        self.realized_pnl = ledger.realized_pnl.to_dollars();
+        self.ledger = Some(ledger);
+        self
+    }
+
+    /// Cash in cents when precise accounting is enabled
+    pub fn cash_cents(&self) -> Option<Money> {
+        self.ledger.map(|l| l.cash)
+    }
+
     /// Get current equity (cash + position value)
     pub fn equity(&self) -> f64 {
-        self.cash + self.position_value() + self.hedge_position_value()
+        self.cash
+            + self.position_value()
+            + self.hedge_position_value()
+            + self.synthetic_short_value()
     }
 
     /// Get position market value
@@ -45,6 +139,19 @@ impl Portfolio {
             .unwrap_or(0.0)
     }
 
+    /// Unrealized P&L of the synthetic short; it holds nothing else
+    pub fn synthetic_short_value(&self) -> f64 {
+        self.synthetic_short
+            .as_ref()
+            .map(|s| s.pnl_at(s.current_price))
+            .unwrap_or(0.0)
+    }
+
+    /// Get starting capital
+    pub fn initial_capital(&self) -> f64 {
+        self.initial_capital
+    }
+
     /// Get available cash
     pub fn cash(&self) -> f64 {
         self.cash
@@ -60,6 +167,16 @@ impl Portfolio {
         self.hedge_position.is_some()
     }
 
+    /// Check if there's a synthetic short
+    pub fn has_synthetic_short(&self) -> bool {
+        self.synthetic_short.is_some()
+    }
+
+    /// Check if either kind of hedge is open
+    pub fn is_hedged(&self) -> bool {
+        self.has_hedge_position() || self.has_synthetic_short()
+    }
+
     /// Get current position reference
     pub fn current_position(&self) -> Option<&Position> {
         self.position.as_ref()
@@ -70,11 +187,36 @@ impl Portfolio {
         self.hedge_position.as_ref()
     }
 
+    /// Get current synthetic short reference
+    pub fn current_synthetic_short(&self) -> Option<&SyntheticShort> {
+        self.synthetic_short.as_ref()
+    }
+
     /// Get all closed trades
     pub fn trades(&self) -> &[Trade] {
         &self.trades
     }
 
+    /// Trades closed since the last call, oldest first. Afterwards
+    /// [`trades`](Self::trades) only goes back to the `keep`th latest
+    /// main-symbol trade, for the rules that look back over recent ones.
+    pub fn spill_trades(&mut self, keep: usize) -> Vec<Trade> {
+        let fresh = self.trades[self.spilled..].to_vec();
+        let main = self
+            .trades
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, t)| !t.is_hedge());
+        let dropped = match keep {
+            0 => self.trades.len(),
+            _ => main.map(|(i, _)| i).nth(keep - 1).unwrap_or(0),
+        };
+        self.trades.drain(..dropped);
+        self.spilled = self.trades.len();
+        fresh
+    }
+
     /// Get realized P&L
     pub fn realized_pnl(&self) -> f64 {
         self.realized_pnl
@@ -90,9 +232,13 @@ impl Portfolio {
                 pos.current_price = price;
             }
         }
+        if let Some(short) = self.synthetic_short.as_mut() {
+            short.current_price = main_price;
+        }
     }
 
     /// Open a new position
+    #[allow(clippy::too_many_arguments)]
     pub fn open_position(
         &mut self,
         symbol: &str,
@@ -103,16 +249,28 @@ impl Portfolio {
         stop_loss_price: Option<f64>,
         commission: f64,
     ) -> Result<()> {
-        let cost = quantity * price + commission;
+        if let Some(ledger) = self.ledger.as_mut() {
+            let cost = Money::notional(quantity, price) + Money::from_dollars(commission);
+            if cost > ledger.cash {
+                return Err(common::BacktestError::InsufficientCash {
+                    required: cost.to_dollars(),
+                    available: ledger.cash.to_dollars(),
+                });
+            }
+            ledger.cash -= cost;
+            self.cash = ledger.cash.to_dollars();
+        } else {
+            let cost = quantity * price + commission;
 
-        if cost > self.cash {
-            return Err(common::BacktestError::InsufficientCash {
-                required: cost,
-                available: self.cash,
-            });
-        }
+            if cost > self.cash {
+                return Err(common::BacktestError::InsufficientCash {
+                    required: cost,
+                    available: self.cash,
+                });
+            }
 
-        self.cash -= cost;
+            self.cash -= cost;
+        }
 
         let position = Position {
             symbol: symbol.to_string(),
@@ -122,6 +280,13 @@ impl Portfolio {
             current_price: price,
             side,
             stop_loss_price,
+            initial_stop_price: stop_loss_price,
+            stop_spec: None,
+            entry_reason: String::new(),
+            entry_rsi: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            entry_commission: commission,
         };
 
         match side {
@@ -136,6 +301,116 @@ impl Portfolio {
         Ok(())
     }
 
+    /// Buy `quantity` more shares of the position on `side` at `price`,
+    /// averaging them into its entry price
+    pub fn add_to_position(
+        &mut self,
+        side: PositionSide,
+        quantity: f64,
+        price: f64,
+        commission: f64,
+    ) -> Result<()> {
+        let position = match side {
+            PositionSide::Hedge => self.hedge_position.as_mut(),
+            _ => self.position.as_mut(),
+        };
+        let Some(position) = position else {
+            return Err(common::BacktestError::NoPositionToClose);
+        };
+
+        if let Some(ledger) = self.ledger.as_mut() {
+            let cost = Money::notional(quantity, price) + Money::from_dollars(commission);
+            if cost > ledger.cash {
+                return Err(common::BacktestError::InsufficientCash {
+                    required: cost.to_dollars(),
+                    available: ledger.cash.to_dollars(),
+                });
+            }
+            ledger.cash -= cost;
+            self.cash = ledger.cash.to_dollars();
+        } else {
+            let cost = quantity * price + commission;
+            if cost > self.cash {
+                return Err(common::BacktestError::InsufficientCash {
+                    required: cost,
+                    available: self.cash,
+                });
+            }
+            self.cash -= cost;
+        }
+
+        let total = position.quantity + quantity;
+        position.avg_entry_price =
+            (position.avg_entry_price * position.quantity + price * quantity) / total;
+        position.quantity = total;
+        position.current_price = price;
+        position.entry_commission += commission;
+        Ok(())
+    }
+
+    /// Record the signal that opened the position on `side`
+    pub fn annotate_entry(&mut self, side: PositionSide, reason: &str, rsi: f64) {
+        let position = match side {
+            PositionSide::Hedge => self.hedge_position.as_mut(),
+            _ => self.position.as_mut(),
+        };
+        if let Some(pos) = position {
+            pos.entry_reason = reason.to_string();
+            pos.entry_rsi = Some(rsi);
+        }
+    }
+
+    /// Record the stop setting the position on `side` was opened with, when
+    /// it resolved to a stop
+    pub fn annotate_stop_spec(&mut self, side: PositionSide, spec: StopSpec) {
+        let position = match side {
+            PositionSide::Hedge => self.hedge_position.as_mut(),
+            _ => self.position.as_mut(),
+        };
+        if let Some(pos) = position.filter(|pos| pos.initial_stop_price.is_some()) {
+            pos.stop_spec = Some(spec);
+        }
+    }
+
+    /// Record the drawdown throttle multiplier behind the long position's size
+    pub fn annotate_size_multiplier(&mut self, multiplier: Option<f64>) {
+        if let Some(pos) = self.position.as_mut() {
+            pos.size_multiplier = multiplier;
+        }
+    }
+
+    /// Record the order that opened the position on `side`
+    pub fn annotate_entry_order(&mut self, side: PositionSide, order_id: u64) {
+        let position = match side {
+            PositionSide::Hedge => self.hedge_position.as_mut(),
+            _ => self.position.as_mut(),
+        };
+        if let Some(pos) = position {
+            pos.entry_order_id = Some(order_id);
+        }
+    }
+
+    /// Record the order that closed the most recent trade
+    pub fn annotate_exit_order(&mut self, order_id: u64) {
+        if let Some(trade) = self.trades.last_mut() {
+            trade.exit_order_id = Some(order_id);
+        }
+    }
+
+    /// Record the RSI of the bar that closed the most recent trade
+    pub fn annotate_last_exit(&mut self, rsi: f64) {
+        if let Some(trade) = self.trades.last_mut() {
+            trade.exit_rsi = Some(rsi);
+        }
+    }
+
+    /// Record the bar that triggered the stop behind the most recent trade
+    pub fn annotate_stop_trigger(&mut self, timestamp: DateTime<Utc>) {
+        if let Some(trade) = self.trades.last_mut() {
+            trade.stop_triggered_at = Some(timestamp);
+        }
+    }
+
     /// Close current position
     pub fn close_position(
         &mut self,
@@ -145,7 +420,44 @@ impl Portfolio {
         commission: f64,
     ) -> Option<Trade> {
         let position = self.position.take()?;
-        self.close_position_internal(position, price, timestamp, reason, commission)
+        let staged = self.staged_exit.take();
+        self.close_position_internal(position, price, timestamp, reason, commission, staged)
+    }
+
+    /// Sell `quantity` shares of the main position as one tranche of an
+    /// exit filling over several bars. The trade is recorded when a close
+    /// sells the rest, with every tranche in `Trade::fills`. Selling the
+    /// whole position closes it.
+    pub fn partial_exit(
+        &mut self,
+        quantity: f64,
+        price: f64,
+        timestamp: DateTime<Utc>,
+        reason: &str,
+        commission: f64,
+    ) -> Option<Trade> {
+        let position = self.position.as_mut()?;
+        if quantity >= position.quantity {
+            return self.close_position(price, timestamp, reason, commission);
+        }
+        let tranche = split_off(position, quantity);
+        let (cost_basis, pnl) = self.book_sale(&tranche, price, commission);
+        let staged = self.staged_exit.get_or_insert_with(StagedExit::default);
+        staged.fills.push(ExitFill {
+            timestamp,
+            quantity,
+            price,
+        });
+        staged.quantity += quantity;
+        staged.cost_basis += cost_basis;
+        staged.pnl += pnl;
+        staged.commission += commission + tranche.entry_commission;
+        None
+    }
+
+    /// Shares sold so far by the main position's staged exit
+    pub fn staged_exit_quantity(&self) -> f64 {
+        self.staged_exit.as_ref().map_or(0.0, |s| s.quantity)
     }
 
     /// Close hedge position
@@ -157,27 +469,240 @@ impl Portfolio {
         commission: f64,
     ) -> Option<Trade> {
         let position = self.hedge_position.take()?;
-        self.close_position_internal(position, price, timestamp, reason, commission)
+        self.close_position_internal(position, price, timestamp, reason, commission, None)
     }
 
-    fn close_position_internal(
+    /// Sell `quantity` shares of the position on `side` (the whole position
+    /// when it holds no more), recording the sold shares as a trade
+    pub fn reduce_position(
         &mut self,
-        position: Position,
+        side: PositionSide,
+        quantity: f64,
         price: f64,
         timestamp: DateTime<Utc>,
         reason: &str,
         commission: f64,
     ) -> Option<Trade> {
-        let proceeds = position.quantity * price - commission;
-        let cost_basis = position.quantity * position.avg_entry_price;
+        let slot = match side {
+            PositionSide::Hedge => &mut self.hedge_position,
+            _ => &mut self.position,
+        };
+        let remaining = slot.as_ref()?.quantity - quantity;
+        let (sold, staged) = if remaining > 0.0 {
+            (split_off(slot.as_mut()?, quantity), None)
+        } else if side == PositionSide::Hedge {
+            (slot.take()?, None)
+        } else {
+            (slot.take()?, self.staged_exit.take())
+        };
+        self.close_position_internal(sold, price, timestamp, reason, commission, staged)
+    }
 
-        let pnl = match position.side {
-            PositionSide::Short => cost_basis - proceeds,
-            _ => proceeds - cost_basis,
+    /// Open a synthetic short of `notional` on `symbol` at `price`. Only
+    /// the commission is paid now; the rest settles in cash at the close.
+    pub fn open_synthetic_short(
+        &mut self,
+        symbol: &str,
+        (notional, leverage): (f64, f64),
+        price: f64,
+        timestamp: DateTime<Utc>,
+        commission: f64,
+    ) -> Result<()> {
+        if self.synthetic_short.is_some() {
+            return Err(common::BacktestError::PositionAlreadyExists {
+                symbol: symbol.to_string(),
+            });
+        }
+        if commission > self.cash {
+            return Err(common::BacktestError::InsufficientCash {
+                required: commission,
+                available: self.cash,
+            });
+        }
+        self.settle(-commission);
+
+        self.synthetic_short = Some(SyntheticShort {
+            symbol: symbol.to_string(),
+            notional,
+            leverage,
+            entry_price: price,
+            current_price: price,
+            entry_date: timestamp,
+            entry_reason: String::new(),
+            entry_rsi: None,
+            entry_order_id: None,
+            entry_commission: commission,
+        });
+        Ok(())
+    }
+
+    /// Record the signal and order that opened the synthetic short
+    pub fn annotate_synthetic_entry(&mut self, reason: &str, rsi: f64, order_id: u64) {
+        if let Some(short) = self.synthetic_short.as_mut() {
+            short.entry_reason = reason.to_string();
+            short.entry_rsi = Some(rsi);
+            short.entry_order_id = Some(order_id);
+        }
+    }
+
+    /// Settle the synthetic short at `price`. The trade's quantity is the
+    /// share equivalent of the notional at entry.
+    pub fn close_synthetic_short(
+        &mut self,
+        price: f64,
+        timestamp: DateTime<Utc>,
+        reason: &str,
+        commission: f64,
+    ) -> Option<Trade> {
+        let short = self.synthetic_short.take()?;
+        let gross_pnl = short.pnl_at(price);
+        let pnl = self.settle(gross_pnl - commission) - short.entry_commission;
+        if let Some(ledger) = self.ledger.as_mut() {
+            ledger.realized_pnl += Money::from_dollars(pnl);
+            self.realized_pnl = ledger.realized_pnl.to_dollars();
+        } else {
+            self.realized_pnl += pnl;
+        }
+
+        let trade = Trade {
+            entry_date: short.entry_date,
+            entry_price: short.entry_price,
+            exit_date: Some(timestamp),
+            exit_price: Some(price),
+            quantity: short.notional / short.entry_price,
+            side: Side::SyntheticCover,
+            pnl,
+            pnl_pct: if short.notional > 0.0 {
+                (pnl / short.notional) * 100.0
+            } else {
+                0.0
+            },
+            gross_pnl,
+            net_pnl: pnl,
+            holding_days: (timestamp - short.entry_date).num_days(),
+            entry_reason: short.entry_reason,
+            exit_reason: reason.to_string(),
+            entry_rsi: short.entry_rsi,
+            exit_rsi: None,
+            initial_risk: None,
+            r_multiple: None,
+            initial_stop_price: None,
+            stop_spec: None,
+            stop_triggered_at: None,
+            size_multiplier: None,
+            entry_order_id: short.entry_order_id,
+            exit_order_id: None,
+            fills: Vec::new(),
+            entry_context: None,
+            exit_context: None,
+            entry_efficiency: None,
+            exit_efficiency: None,
         };
 
-        self.cash += proceeds;
-        self.realized_pnl += pnl;
+        self.trades.push(trade.clone());
+        Some(trade)
+    }
+
+    /// This portfolio with its hedge and synthetic short sold at their
+    /// current value (before costs), to vet an entry that closes them first
+    pub fn without_hedges(&self) -> Self {
+        let mut projected = self.clone();
+        projected.settle(self.hedge_position_value() + self.synthetic_short_value());
+        projected.hedge_position = None;
+        projected.synthetic_short = None;
+        projected
+    }
+
+    /// Move `amount` into (or out of) cash, returning what was booked
+    fn settle(&mut self, amount: f64) -> f64 {
+        if let Some(ledger) = self.ledger.as_mut() {
+            let amount = Money::from_dollars(amount);
+            ledger.cash += amount;
+            self.cash = ledger.cash.to_dollars();
+            amount.to_dollars()
+        } else {
+            self.cash += amount;
+            amount
+        }
+    }
+
+    /// Deposit (positive) or withdraw (negative) cash. Withdrawals are
+    /// limited to the cash on hand; returns the amount actually moved.
+    pub fn apply_cash_flow(&mut self, amount: f64) -> f64 {
+        if let Some(ledger) = self.ledger.as_mut() {
+            let floor = Money::default() - ledger.cash.max(Money::default());
+            let flow = Money::from_dollars(amount).max(floor);
+            ledger.cash += flow;
+            self.cash = ledger.cash.to_dollars();
+            flow.to_dollars()
+        } else {
+            let flow = amount.max(-self.cash.max(0.0));
+            self.cash += flow;
+            flow
+        }
+    }
+
+    /// Book the sale of `position` at `price` into cash and realized P&L,
+    /// returning its cost basis and P&L net of the entry and exit
+    /// commissions
+    fn book_sale(&mut self, position: &Position, price: f64, commission: f64) -> (f64, f64) {
+        if let Some(ledger) = self.ledger.as_mut() {
+            let proceeds =
+                Money::notional(position.quantity, price) - Money::from_dollars(commission);
+            let cost_basis = Money::notional(position.quantity, position.avg_entry_price);
+            let pnl = match position.side {
+                PositionSide::Short => cost_basis - proceeds,
+                _ => proceeds - cost_basis,
+            } - Money::from_dollars(position.entry_commission);
+
+            ledger.cash += proceeds;
+            ledger.realized_pnl += pnl;
+            self.cash = ledger.cash.to_dollars();
+            self.realized_pnl = ledger.realized_pnl.to_dollars();
+            (cost_basis.to_dollars(), pnl.to_dollars())
+        } else {
+            let proceeds = position.quantity * price - commission;
+            let cost_basis = position.quantity * position.avg_entry_price;
+
+            let pnl = match position.side {
+                PositionSide::Short => cost_basis - proceeds,
+                _ => proceeds - cost_basis,
+            } - position.entry_commission;
+
+            self.cash += proceeds;
+            self.realized_pnl += pnl;
+            (cost_basis, pnl)
+        }
+    }
+
+    /// Settle `staged`'s tranches and the sale of `position` into one trade
+    fn close_position_internal(
+        &mut self,
+        mut position: Position,
+        price: f64,
+        timestamp: DateTime<Utc>,
+        reason: &str,
+        commission: f64,
+        staged: Option<StagedExit>,
+    ) -> Option<Trade> {
+        let (mut cost_basis, mut pnl) = self.book_sale(&position, price, commission);
+        let mut commissions = commission + position.entry_commission;
+        let mut exit_price = price;
+        let mut fills = Vec::new();
+        if let Some(staged) = staged {
+            fills = staged.fills;
+            fills.push(ExitFill {
+                timestamp,
+                quantity: position.quantity,
+                price,
+            });
+            position.quantity += staged.quantity;
+            cost_basis += staged.cost_basis;
+            pnl += staged.pnl;
+            commissions += staged.commission;
+            exit_price =
+                fills.iter().map(|f| f.quantity * f.price).sum::<f64>() / position.quantity;
+        }
 
         let exit_side = match position.side {
             PositionSide::Long => Side::Sell,
@@ -186,12 +711,16 @@ impl Portfolio {
         };
 
         let holding_days = (timestamp - position.entry_date).num_days();
+        let initial_risk = position
+            .initial_stop_price
+            .map(|stop| (position.avg_entry_price - stop).abs() * position.quantity)
+            .filter(|risk| *risk > 0.0);
 
         let trade = Trade {
             entry_date: position.entry_date,
             entry_price: position.avg_entry_price,
             exit_date: Some(timestamp),
-            exit_price: Some(price),
+            exit_price: Some(exit_price),
             quantity: position.quantity,
             side: exit_side,
             pnl,
@@ -200,15 +729,94 @@ impl Portfolio {
             } else {
                 0.0
             },
+            gross_pnl: pnl + commissions,
+            net_pnl: pnl,
             holding_days,
-            entry_reason: String::new(),
+            entry_reason: position.entry_reason,
             exit_reason: reason.to_string(),
+            entry_rsi: position.entry_rsi,
+            exit_rsi: None,
+            initial_risk,
+            initial_stop_price: position.initial_stop_price,
+            stop_spec: position.stop_spec,
+            r_multiple: initial_risk.map(|risk| pnl / risk),
+            stop_triggered_at: None,
+            size_multiplier: position.size_multiplier,
+            entry_order_id: position.entry_order_id,
+            exit_order_id: None,
+            fills,
+            entry_context: None,
+            exit_context: None,
+            entry_efficiency: None,
+            exit_efficiency: None,
         };
 
         self.trades.push(trade.clone());
         Some(trade)
     }
 
+    /// Amount lost across open positions if every stop were hit at its
+    /// price. A position without a stop risks its full market value, and
+    /// the synthetic short its leveraged notional.
+    pub fn risk_amount(&self) -> f64 {
+        let synthetic = self
+            .synthetic_short
+            .as_ref()
+            .map_or(0.0, |s| s.notional * s.leverage);
+        [self.position.as_ref(), self.hedge_position.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(position_risk)
+            .sum::<f64>()
+            + synthetic
+    }
+
+    /// Portfolio heat: total risk to stop as a percentage of equity
+    pub fn heat_pct(&self) -> f64 {
+        let equity = self.equity();
+        if equity > 0.0 {
+            self.risk_amount() / equity * 100.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Net market exposure in dollars of `primary`: each position's market
+    /// value times its leverage factor, short and hedge positions negative,
+    /// over the primary's factor. A $9,000 TQQQ long with a $3,000 SQQQ
+    /// hedge is net $6,000 long; the synthetic short counts its leveraged
+    /// notional at the current price.
+    pub fn net_exposure(&self, hedge: &HedgeParams, primary: &str) -> f64 {
+        let weighed = |p: &Position| {
+            let value = p.quantity * p.current_price * hedge.leverage_factor(&p.symbol);
+            match p.side {
+                PositionSide::Long => value,
+                PositionSide::Short | PositionSide::Hedge => -value,
+            }
+        };
+        let synthetic = self.synthetic_short.as_ref().map_or(0.0, |s| {
+            s.leverage * s.notional * s.current_price / s.entry_price
+                * hedge.leverage_factor(&s.symbol)
+        });
+        let net = [self.position.as_ref(), self.hedge_position.as_ref()]
+            .into_iter()
+            .flatten()
+            .fold(0.0, |net, p| net + weighed(p))
+            - synthetic;
+        net / hedge.leverage_factor(primary)
+    }
+
+    /// Raise the long position's stop to `price` (a stop is never lowered)
+    pub fn tighten_stop(&mut self, price: f64) {
+        if let Some(pos) = self
+            .position
+            .as_mut()
+            .filter(|p| p.side == PositionSide::Long)
+        {
+            pos.stop_loss_price = Some(pos.stop_loss_price.map_or(price, |stop| stop.max(price)));
+        }
+    }
+
     /// Check if stop loss is triggered
     pub fn check_stop_loss(&self, current_price: f64) -> bool {
         if let Some(pos) = &self.position {
@@ -237,6 +845,51 @@ impl Portfolio {
         let target_value = available * position_size_pct;
         (target_value / price).floor()
     }
+
+    /// Whole shares for a long entry at `price` under `mode`; 0 when not
+    /// even one share (or the fixed share count) fits in available cash
+    pub fn calculate_entry_size(
+        &self,
+        price: f64,
+        mode: SizingMode,
+        position_size_pct: f64,
+        cash_reserve_pct: f64,
+    ) -> f64 {
+        let available = self.cash * (1.0 - cash_reserve_pct);
+        match mode {
+            SizingMode::CompoundPctEquity => {
+                self.calculate_position_size(price, position_size_pct, cash_reserve_pct)
+            }
+            SizingMode::FixedNotional(notional) => (notional.min(available) / price).floor(),
+            SizingMode::FixedShares(shares) if shares.floor() * price <= available => {
+                shares.floor()
+            }
+            SizingMode::FixedShares(_) => 0.0,
+        }
+    }
+}
+
+/// Risk to stop of a single position
+fn position_risk(position: &Position) -> f64 {
+    let per_share = match (position.side, position.stop_loss_price) {
+        (PositionSide::Short, Some(stop)) => stop - position.current_price,
+        (_, Some(stop)) => position.current_price - stop,
+        (_, None) => position.current_price,
+    };
+    per_share.max(0.0) * position.quantity
+}
+
+/// Take `quantity` shares, with their share of the entry commission, out
+/// of `position`
+fn split_off(position: &mut Position, quantity: f64) -> Position {
+    let commission = position.entry_commission * quantity / position.quantity;
+    position.quantity -= quantity;
+    position.entry_commission -= commission;
+    Position {
+        quantity,
+        entry_commission: commission,
+        ..position.clone()
+    }
 }
 
 #[cfg(test)]
@@ -286,19 +939,203 @@ mod tests {
         assert_eq!(trade.pnl_pct, 10.0);
     }
 
+    #[test]
+    fn test_gross_and_net_pnl() {
+        let mut portfolio = Portfolio::new(10000.0);
+        portfolio
+            .open_position("TQQQ", 100.0, 50.0, PositionSide::Long, now(), None, 10.0)
+            .unwrap();
+        let trade = portfolio
+            .close_position(51.0, now(), "take profit", 10.0)
+            .unwrap();
+
+        assert_eq!(trade.gross_pnl, 100.0);
+        assert_eq!(trade.net_pnl, 80.0);
+        assert_eq!(trade.pnl, trade.net_pnl);
+        assert_eq!(portfolio.cash(), 10080.0);
+        let json = serde_json::to_value(&trade).unwrap();
+        assert_eq!(json["gross_pnl"], 100.0);
+        assert_eq!(json["net_pnl"], 80.0);
+
+        // A partial sale takes its share of the entry commission along
+        portfolio
+            .open_position("TQQQ", 100.0, 50.0, PositionSide::Long, now(), None, 10.0)
+            .unwrap();
+        let sold = portfolio
+            .reduce_position(PositionSide::Long, 40.0, 51.0, now(), "trim", 10.0)
+            .unwrap();
+        assert_eq!((sold.gross_pnl, sold.net_pnl), (40.0, 26.0));
+        let rest = portfolio
+            .close_position(51.0, now(), "take profit", 10.0)
+            .unwrap();
+        assert_eq!((rest.gross_pnl, rest.net_pnl), (60.0, 44.0));
+    }
+
+    #[test]
+    fn test_add_to_position_averages_entry() {
+        let mut portfolio = Portfolio::new(10000.0);
+        assert!(portfolio
+            .add_to_position(PositionSide::Long, 10.0, 50.0, 0.0)
+            .is_err());
+
+        portfolio
+            .open_position("TQQQ", 60.0, 50.0, PositionSide::Long, now(), None, 0.0)
+            .unwrap();
+        portfolio
+            .add_to_position(PositionSide::Long, 40.0, 55.0, 1.0)
+            .unwrap();
+        let position = portfolio.current_position().unwrap();
+        assert_eq!(position.quantity, 100.0);
+        assert_eq!(position.avg_entry_price, 52.0);
+        assert_eq!(portfolio.cash(), 10000.0 - 3000.0 - 2200.0 - 1.0);
+
+        let trade = portfolio.close_position(52.0, now(), "flat", 0.0).unwrap();
+        assert_eq!(trade.gross_pnl, 0.0);
+        assert_eq!(trade.pnl, -1.0);
+    }
+
+    #[test]
+    fn test_r_multiple() {
+        let mut portfolio = Portfolio::new(20000.0);
+        portfolio
+            .open_position(
+                "TQQQ",
+                100.0,
+                100.0,
+                PositionSide::Long,
+                now(),
+                Some(95.0),
+                0.0,
+            )
+            .unwrap();
+        // A later stop move does not change the risk unit
+        portfolio.position.as_mut().unwrap().stop_loss_price = Some(100.0);
+
+        let trade = portfolio
+            .close_position(110.0, now(), "take profit", 0.0)
+            .unwrap();
+        assert_eq!(trade.initial_risk, Some(500.0));
+        assert_eq!(trade.r_multiple, Some(2.0));
+
+        portfolio
+            .open_position("TQQQ", 100.0, 100.0, PositionSide::Long, now(), None, 0.0)
+            .unwrap();
+        let trade = portfolio
+            .close_position(90.0, now(), "signal", 0.0)
+            .unwrap();
+        assert_eq!(trade.initial_risk, None);
+        assert_eq!(trade.r_multiple, None);
+    }
+
+    #[test]
+    fn test_heat() {
+        let mut portfolio = Portfolio::new(10000.0);
+        assert_eq!(portfolio.heat_pct(), 0.0);
+
+        // 90% of equity with a 5% stop
+        portfolio
+            .open_position(
+                "TQQQ",
+                180.0,
+                50.0,
+                PositionSide::Long,
+                now(),
+                Some(47.5),
+                0.0,
+            )
+            .unwrap();
+        assert!((portfolio.heat_pct() - 4.5).abs() < 1e-9);
+
+        // Once the price sits below the stop nothing more is at risk to it
+        portfolio.update_prices(47.0, None);
+        assert_eq!(portfolio.risk_amount(), 0.0);
+    }
+
+    #[test]
+    fn test_net_exposure() {
+        let hedge = HedgeParams::default();
+        let mut portfolio = Portfolio::new(20000.0);
+        assert_eq!(portfolio.net_exposure(&hedge, "TQQQ"), 0.0);
+
+        portfolio
+            .open_position("TQQQ", 180.0, 50.0, PositionSide::Long, now(), None, 0.0)
+            .unwrap();
+        portfolio
+            .open_position("SQQQ", 100.0, 30.0, PositionSide::Hedge, now(), None, 0.0)
+            .unwrap();
+        assert!((portfolio.net_exposure(&hedge, "TQQQ") - 6000.0).abs() < 1e-9);
+
+        // A 1x hedge offsets a third as much of the 3x long
+        let unlevered = HedgeParams {
+            leverage_factors: [("TQQQ".to_string(), 3.0)].into(),
+            ..HedgeParams::default()
+        };
+        assert!((portfolio.net_exposure(&unlevered, "TQQQ") - 8000.0).abs() < 1e-9);
+    }
+
+    /// Random fills with sub-cent prices, replayed through both modes
+    fn random_round_trips(portfolio: &mut Portfolio, seed: u64) {
+        use rand::{Rng, SeedableRng};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        for _ in 0..2000 {
+            let entry = rng.gen_range(10.0..200.0);
+            let exit = entry * rng.gen_range(0.9..1.1);
+            let quantity = rng.gen_range(1..40) as f64;
+            portfolio
+                .open_position(
+                    "TQQQ",
+                    quantity,
+                    entry,
+                    PositionSide::Long,
+                    now(),
+                    None,
+                    0.0,
+                )
+                .unwrap();
+            portfolio.close_position(exit, now(), "exit", 0.0);
+        }
+    }
+
+    #[test]
+    fn test_precise_accounting_reconciles() {
+        let mut precise = Portfolio::new(10000.0).with_precise_accounting();
+        let mut float = Portfolio::new(10000.0);
+        random_round_trips(&mut precise, 42);
+        random_round_trips(&mut float, 42);
+
+        // Cash is exactly the starting capital plus the (cent) trade P&L
+        let total: Money = precise
+            .trades()
+            .iter()
+            .map(|t| Money::from_dollars(t.pnl))
+            .sum();
+        assert_eq!(
+            precise.cash_cents(),
+            Some(Money::from_dollars(10000.0) + total)
+        );
+        assert_eq!(precise.cash(), precise.cash_cents().unwrap().to_dollars());
+        assert_eq!(Money::from_dollars(precise.realized_pnl()), total);
+
+        // Each trade agrees with f64 accounting to within a cent
+        assert_eq!(precise.trades().len(), float.trades().len());
+        for (p, f) in precise.trades().iter().zip(float.trades()) {
+            assert!(
+                (p.pnl - f.pnl).abs() <= 0.01 + 1e-9,
+                "{} vs {}",
+                p.pnl,
+                f.pnl
+            );
+        }
+        assert!(float.cash_cents().is_none());
+    }
+
     #[test]
     fn test_insufficient_cash() {
         let mut portfolio = Portfolio::new(1000.0);
 
-        let result = portfolio.open_position(
-            "TQQQ",
-            100.0,
-            50.0,
-            PositionSide::Long,
-            now(),
-            None,
-            0.0,
-        );
+        let result =
+            portfolio.open_position("TQQQ", 100.0, 50.0, PositionSide::Long, now(), None, 0.0);
 
         assert!(result.is_err());
     }
@@ -321,6 +1158,14 @@ mod tests {
 
         assert!(!portfolio.check_stop_loss(48.0));
         assert!(portfolio.check_stop_loss(47.0));
+
+        portfolio.tighten_stop(48.5);
+        assert!(portfolio.check_stop_loss(48.0));
+        portfolio.tighten_stop(40.0); // never loosened
+        assert_eq!(
+            portfolio.current_position().unwrap().stop_loss_price,
+            Some(48.5)
+        );
     }
 
     #[test]
@@ -334,4 +1179,106 @@ mod tests {
         // Shares: 8100 / 50 = 162
         assert_eq!(size, 162.0);
     }
+
+    #[test]
+    fn test_reduce_position_and_cash_flows() {
+        let mut portfolio = Portfolio::new(10000.0);
+        portfolio
+            .open_position("TQQQ", 100.0, 50.0, PositionSide::Long, now(), None, 0.0)
+            .unwrap();
+        portfolio.update_prices(60.0, None);
+
+        let trade = portfolio
+            .reduce_position(PositionSide::Long, 40.0, 60.0, now(), "withdrawal", 0.0)
+            .unwrap();
+        assert_eq!(trade.quantity, 40.0);
+        assert_eq!(trade.pnl, 400.0);
+        assert_eq!(portfolio.current_position().unwrap().quantity, 60.0);
+        assert_eq!(portfolio.cash(), 7400.0);
+
+        assert_eq!(portfolio.apply_cash_flow(-8000.0), -7400.0);
+        assert_eq!(portfolio.cash(), 0.0);
+        assert_eq!(portfolio.apply_cash_flow(500.0), 500.0);
+        assert_eq!(portfolio.equity(), 500.0 + 60.0 * 60.0);
+
+        // More than is held closes the position
+        portfolio.reduce_position(PositionSide::Long, 100.0, 60.0, now(), "withdrawal", 0.0);
+        assert!(!portfolio.has_position());
+        assert_eq!(portfolio.trades().len(), 2);
+    }
+
+    #[test]
+    fn test_staged_exit_closes_into_one_trade() {
+        let mut portfolio = Portfolio::new(10000.0);
+        portfolio
+            .open_position("TQQQ", 100.0, 50.0, PositionSide::Long, now(), None, 0.0)
+            .unwrap();
+
+        let later = |days| now() + chrono::Duration::days(days);
+        assert!(portfolio
+            .partial_exit(30.0, 60.0, later(1), "overbought", 1.0)
+            .is_none());
+        assert!(portfolio
+            .partial_exit(30.0, 55.0, later(2), "overbought", 1.0)
+            .is_none());
+        assert_eq!(portfolio.staged_exit_quantity(), 60.0);
+        assert!(portfolio.trades().is_empty());
+        assert_eq!(portfolio.cash(), 5000.0 + 1800.0 + 1650.0 - 2.0);
+
+        let trade = portfolio
+            .close_position(40.0, later(3), "overbought", 1.0)
+            .unwrap();
+        assert_eq!(trade.quantity, 100.0);
+        assert_eq!(trade.exit_date, Some(later(3)));
+        assert_eq!(trade.exit_price, Some((1800.0 + 1650.0 + 1600.0) / 100.0));
+        assert_eq!(trade.pnl, 5050.0 - 5000.0 - 3.0);
+        let fills: Vec<f64> = trade.fills.iter().map(|f| f.quantity).collect();
+        assert_eq!(fills, vec![30.0, 30.0, 40.0]);
+        assert_eq!(portfolio.staged_exit_quantity(), 0.0);
+        assert_eq!(portfolio.equity(), 10047.0);
+    }
+
+    #[test]
+    fn test_fixed_entry_sizes() {
+        let portfolio = Portfolio::new(10000.0);
+        let size = |mode| portfolio.calculate_entry_size(50.0, mode, 0.9, 0.1);
+
+        assert_eq!(size(SizingMode::CompoundPctEquity), 162.0);
+        assert_eq!(size(SizingMode::FixedNotional(5000.0)), 100.0);
+        // Clamped to the $9000 available after the reserve
+        assert_eq!(size(SizingMode::FixedNotional(20000.0)), 180.0);
+        assert_eq!(size(SizingMode::FixedShares(180.0)), 180.0);
+        assert_eq!(size(SizingMode::FixedShares(181.0)), 0.0);
+    }
+
+    #[test]
+    fn test_synthetic_short_settles_in_cash() {
+        let mut portfolio = Portfolio::new(10000.0);
+        portfolio
+            .open_synthetic_short("TQQQ", (3000.0, 2.0), 100.0, now(), 1.0)
+            .unwrap();
+        // Nothing but the commission leaves cash
+        assert_eq!(portfolio.cash(), 9999.0);
+        assert!(portfolio.is_hedged() && !portfolio.has_hedge_position());
+        assert!(portfolio
+            .open_synthetic_short("TQQQ", (3000.0, 2.0), 100.0, now(), 1.0)
+            .is_err());
+
+        portfolio.update_prices(95.0, None);
+        assert!((portfolio.equity() - (9999.0 + 300.0)).abs() < 1e-9);
+        portfolio.update_prices(110.0, None);
+        assert!((portfolio.equity() - (9999.0 - 600.0)).abs() < 1e-9);
+
+        let trade = portfolio
+            .close_synthetic_short(90.0, now(), "cover", 1.0)
+            .unwrap();
+        assert_eq!(trade.side, Side::SyntheticCover);
+        assert!(trade.is_hedge());
+        assert_eq!(trade.quantity, 30.0);
+        assert!((trade.gross_pnl - 600.0).abs() < 1e-9);
+        assert!((trade.pnl - 598.0).abs() < 1e-9);
+        assert!((portfolio.cash() - 10598.0).abs() < 1e-9);
+        assert!((portfolio.realized_pnl() - 598.0).abs() < 1e-9);
+        assert!(!portfolio.is_hedged());
+    }
 }
@@ -0,0 +1,280 @@
+//! Pre-trade checks: the last gate before an entry is executed
+//!
+//! The engine puts every entry through its [`PreTradeChecks`] after sizing
+//! (heat and liquidity caps included) and before execution or queueing.
+//! Checks run in order, each seeing the quantity the previous ones left: a
+//! rejection stops the entry, recorded as a suppressed signal and a
+//! [`PreTradeEvent`]; a modification carries on with the new quantity.
+//! Exits are never reviewed, so no check can trap a position.
+//!
+//! The built-ins come from the risk parameters (`max_order_notional`,
+//! `max_open_positions`) and run first; checks added with
+//! [`BacktestEngine::with_pre_trade_check`] follow in the order added.
+//!
+//! [`BacktestEngine::with_pre_trade_check`]: crate::BacktestEngine::with_pre_trade_check
+
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use common::{PreTradeEvent, RiskParams, Side};
+
+use crate::portfolio::Portfolio;
+
+/// An entry about to be executed
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProposedOrder {
+    pub timestamp: DateTime<Utc>,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    /// Price the entry was sized at
+    pub price: f64,
+}
+
+impl ProposedOrder {
+    pub fn notional(&self) -> f64 {
+        self.quantity * self.price
+    }
+}
+
+/// The account as it stands before the entry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PortfolioSnapshot {
+    pub cash: f64,
+    pub equity: f64,
+    /// Long, hedge and synthetic short positions held
+    pub open_positions: usize,
+    /// Market value of the long and of the hedge position
+    pub long_value: f64,
+    pub hedge_value: f64,
+}
+
+impl From<&Portfolio> for PortfolioSnapshot {
+    fn from(portfolio: &Portfolio) -> Self {
+        let open = [
+            portfolio.has_position(),
+            portfolio.has_hedge_position(),
+            portfolio.has_synthetic_short(),
+        ];
+        Self {
+            cash: portfolio.cash(),
+            equity: portfolio.equity(),
+            open_positions: open.iter().filter(|held| **held).count(),
+            long_value: portfolio.position_value(),
+            hedge_value: portfolio.hedge_position_value(),
+        }
+    }
+}
+
+/// What a check makes of an order
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreTradeDecision {
+    Approve,
+    Reject(String),
+    /// Continue with this many shares; only shrinking takes effect and the
+    /// result is rounded down to whole shares
+    ModifyQuantity(f64),
+}
+
+/// A gate every entry passes before execution
+pub trait PreTradeCheck: Send + Sync {
+    fn review(&self, order: &ProposedOrder, portfolio: &PortfolioSnapshot) -> PreTradeDecision;
+
+    /// Name recorded with the check's rejections and modifications
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+/// Shrinks entries to a maximum notional
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaxNotionalCheck {
+    pub max_notional: f64,
+}
+
+impl PreTradeCheck for MaxNotionalCheck {
+    fn review(&self, order: &ProposedOrder, _: &PortfolioSnapshot) -> PreTradeDecision {
+        if order.notional() <= self.max_notional {
+            PreTradeDecision::Approve
+        } else {
+            PreTradeDecision::ModifyQuantity(self.max_notional / order.price)
+        }
+    }
+
+    fn name(&self) -> &str {
+        "max_notional"
+    }
+}
+
+/// Rejects entries while a number of positions are open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaxPositionsCheck {
+    pub max_positions: usize,
+}
+
+impl PreTradeCheck for MaxPositionsCheck {
+    fn review(&self, _: &ProposedOrder, portfolio: &PortfolioSnapshot) -> PreTradeDecision {
+        if portfolio.open_positions < self.max_positions {
+            PreTradeDecision::Approve
+        } else {
+            PreTradeDecision::Reject(format!("{} positions open", portfolio.open_positions))
+        }
+    }
+
+    fn name(&self) -> &str {
+        "max_positions"
+    }
+}
+
+/// Checks consulted in order
+#[derive(Clone, Default)]
+pub struct PreTradeChecks {
+    checks: Vec<Arc<dyn PreTradeCheck>>,
+}
+
+impl fmt::Debug for PreTradeChecks {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.checks.iter().map(|check| check.name()))
+            .finish()
+    }
+}
+
+impl PreTradeChecks {
+    /// The built-in checks the risk parameters configure
+    pub fn from_risk(risk: &RiskParams) -> Self {
+        let mut checks = Self::default();
+        if let Some(max_notional) = risk.max_order_notional {
+            checks.push(Arc::new(MaxNotionalCheck { max_notional }));
+        }
+        if let Some(max_positions) = risk.max_open_positions {
+            checks.push(Arc::new(MaxPositionsCheck { max_positions }));
+        }
+        checks
+    }
+
+    pub fn push(&mut self, check: Arc<dyn PreTradeCheck>) {
+        self.checks.push(check);
+    }
+
+    pub fn extend(&mut self, other: &PreTradeChecks) {
+        self.checks.extend(other.checks.iter().cloned());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    /// Quantity to go ahead with, or the reason the entry is rejected.
+    /// Rejections and modifications are appended to `events`.
+    pub fn review(
+        &self,
+        order: &ProposedOrder,
+        portfolio: &PortfolioSnapshot,
+        events: &mut Vec<PreTradeEvent>,
+    ) -> Result<f64, String> {
+        let mut order = order.clone();
+        for check in &self.checks {
+            let event = |approved_quantity: f64, reason: Option<String>| PreTradeEvent {
+                timestamp: order.timestamp,
+                symbol: order.symbol.clone(),
+                side: order.side,
+                check: check.name().to_string(),
+                requested_quantity: order.quantity,
+                approved_quantity,
+                reason,
+            };
+            match check.review(&order, portfolio) {
+                PreTradeDecision::Approve => {}
+                PreTradeDecision::Reject(reason) => {
+                    events.push(event(0.0, Some(reason.clone())));
+                    return Err(format!("pre-trade check {}: {}", check.name(), reason));
+                }
+                PreTradeDecision::ModifyQuantity(quantity) => {
+                    let quantity = quantity.min(order.quantity).max(0.0).floor();
+                    if quantity == order.quantity {
+                        continue;
+                    }
+                    if quantity < 1.0 {
+                        let reason = "reduced below 1 share".to_string();
+                        events.push(event(0.0, Some(reason.clone())));
+                        return Err(format!("pre-trade check {}: {}", check.name(), reason));
+                    }
+                    events.push(event(quantity, None));
+                    order.quantity = quantity;
+                }
+            }
+        }
+        Ok(order.quantity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(quantity: f64, price: f64) -> ProposedOrder {
+        ProposedOrder {
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            symbol: "TQQQ".to_string(),
+            side: Side::Buy,
+            quantity,
+            price,
+        }
+    }
+
+    #[test]
+    fn test_checks_compose_in_order() {
+        let risk = RiskParams {
+            max_order_notional: Some(1_000.0),
+            max_open_positions: Some(1),
+            ..RiskParams::default()
+        };
+        let checks = PreTradeChecks::from_risk(&risk);
+        assert_eq!(
+            format!("{:?}", checks),
+            r#"["max_notional", "max_positions"]"#
+        );
+        let mut flat = PortfolioSnapshot {
+            cash: 10_000.0,
+            equity: 10_000.0,
+            open_positions: 0,
+            long_value: 0.0,
+            hedge_value: 0.0,
+        };
+
+        let mut events = Vec::new();
+        assert_eq!(
+            checks.review(&order(30.0, 50.0), &flat, &mut events),
+            Ok(20.0)
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            (events[0].requested_quantity, events[0].approved_quantity),
+            (30.0, 20.0)
+        );
+        assert_eq!(
+            checks.review(&order(10.0, 50.0), &flat, &mut events),
+            Ok(10.0)
+        );
+        assert_eq!(events.len(), 1);
+
+        flat.open_positions = 1;
+        let rejected = checks.review(&order(10.0, 50.0), &flat, &mut events);
+        assert_eq!(
+            rejected,
+            Err("pre-trade check max_positions: 1 positions open".to_string())
+        );
+        assert_eq!(events[1].check, "max_positions");
+        assert_eq!(events[1].approved_quantity, 0.0);
+
+        // Shrunk below a share by the notional cap
+        flat.open_positions = 0;
+        assert!(checks
+            .review(&order(10.0, 5_000.0), &flat, &mut events)
+            .is_err());
+        assert!(PreTradeChecks::from_risk(&RiskParams::default()).is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+//! Bars annotated with trades, for charting
+//!
+//! The CSV has one row per bar with the trade columns filled on the bars
+//! they apply to, ready for a Pine Script import or a lightweight-charts
+//! series. The marker JSON holds the same entries and exits as
+//! lightweight-charts markers. Hedge trades fill on the hedge instrument,
+//! not these bars, and are left out of both.
+
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use common::{BacktestError, BacktestResult, Bar, Result, Side, Trade};
+
+use crate::output::{write_bytes, write_csv, Overwrite};
+
+/// One bar with the trade activity on it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnnotatedBar {
+    pub time: DateTime<Utc>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: u64,
+    /// Fill price of a trade opened on this bar
+    pub entry: Option<f64>,
+    /// Fill price of a trade closed on this bar
+    pub exit: Option<f64>,
+    /// Initial stop of the trade held during the bar, entry and exit bars
+    /// included
+    pub stop_level: Option<f64>,
+    /// 1 when a position is held at the bar's close
+    pub position_open: u8,
+    /// Indicator context of the trade opened / closed on this bar, when the
+    /// run recorded it
+    pub entry_rsi: Option<f64>,
+    pub entry_atr: Option<f64>,
+    pub entry_volume_ratio: Option<f64>,
+    pub exit_rsi: Option<f64>,
+}
+
+/// A chart marker in the lightweight-charts layout
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChartMarker {
+    /// Unix seconds
+    pub time: i64,
+    /// `aboveBar` or `belowBar`
+    pub position: &'static str,
+    pub text: String,
+}
+
+/// Annotate `bars` with the main-symbol trades of `result`. With two
+/// trades opening (or closing) on one bar the first in `result.trades`
+/// fills the cell.
+pub fn annotate(result: &BacktestResult, bars: &[Bar]) -> Vec<AnnotatedBar> {
+    let mut rows: Vec<AnnotatedBar> = bars
+        .iter()
+        .map(|b| AnnotatedBar {
+            time: b.timestamp,
+            open: b.open,
+            high: b.high,
+            low: b.low,
+            close: b.close,
+            volume: b.volume,
+            entry: None,
+            exit: None,
+            stop_level: None,
+            position_open: 0,
+            entry_rsi: None,
+            entry_atr: None,
+            entry_volume_ratio: None,
+            exit_rsi: None,
+        })
+        .collect();
+    if rows.is_empty() {
+        return rows;
+    }
+
+    for trade in result.trades.iter().filter(|t| !t.is_hedge()) {
+        let Some(start) = bar_at(bars, trade.entry_date) else {
+            continue;
+        };
+        // Trades without an exit are still open at the last bar
+        let (end, closed) = match trade.exit_date.and_then(|d| bar_at(bars, d)) {
+            Some(end) => (end, true),
+            None => (bars.len() - 1, false),
+        };
+
+        if rows[start].entry.is_none() {
+            let row = &mut rows[start];
+            row.entry = Some(trade.entry_price);
+            if let Some(context) = &trade.entry_context {
+                row.entry_rsi = Some(context.rsi);
+                row.entry_atr = Some(context.atr);
+                row.entry_volume_ratio = context.volume_ratio;
+            }
+        }
+        if closed && rows[end].exit.is_none() {
+            if let Some(price) = trade.exit_price {
+                rows[end].exit = Some(price);
+                rows[end].exit_rsi = trade.exit_context.map(|c| c.rsi);
+            }
+        }
+        let stop = initial_stop(trade);
+        for (i, row) in rows.iter_mut().enumerate().take(end + 1).skip(start) {
+            if i < end || !closed {
+                row.position_open = 1;
+            }
+            if row.stop_level.is_none() {
+                row.stop_level = stop;
+            }
+        }
+    }
+    rows
+}
+
+/// Entry and exit markers of the main-symbol trades, in time order
+pub fn chart_markers(result: &BacktestResult) -> Vec<ChartMarker> {
+    let mut markers = Vec::new();
+    for trade in result.trades.iter().filter(|t| !t.is_hedge()) {
+        // Longs are bought below the bar and sold above it; shorts the reverse
+        let (entry_at, exit_at, label) = match trade.side {
+            Side::Cover => ("aboveBar", "belowBar", "Short"),
+            _ => ("belowBar", "aboveBar", "Long"),
+        };
+        markers.push(ChartMarker {
+            time: trade.entry_date.timestamp(),
+            position: entry_at,
+            text: format!("{} @ {:.2}", label, trade.entry_price),
+        });
+        if let (Some(date), Some(price)) = (trade.exit_date, trade.exit_price) {
+            markers.push(ChartMarker {
+                time: date.timestamp(),
+                position: exit_at,
+                text: format!("Exit @ {:.2} ({})", price, trade.exit_reason),
+            });
+        }
+    }
+    markers.sort_by_key(|m| m.time);
+    markers
+}
+
+/// Write the annotated bars to a CSV file, returning the row count
+pub fn export_annotated_csv(
+    result: &BacktestResult,
+    bars: &[Bar],
+    path: &Path,
+    overwrite: Overwrite,
+) -> Result<usize> {
+    let rows = annotate(result, bars);
+    write_csv(path, overwrite, &rows)?;
+    Ok(rows.len())
+}
+
+/// Write the chart markers to a JSON file, returning the marker count
+pub fn export_chart_markers(
+    result: &BacktestResult,
+    path: &Path,
+    overwrite: Overwrite,
+) -> Result<usize> {
+    let markers = chart_markers(result);
+    let json = serde_json::to_string_pretty(&markers)
+        .map_err(|e| BacktestError::ConfigError(e.to_string()))?;
+    write_bytes(path, overwrite, json.as_bytes())?;
+    Ok(markers.len())
+}
+
+/// Index of the bar at `time`, or of the first bar after it
+fn bar_at(bars: &[Bar], time: DateTime<Utc>) -> Option<usize> {
+    let i = bars.partition_point(|b| b.timestamp < time);
+    (i < bars.len()).then_some(i)
+}
+
+/// Stop at entry, recovered from the risk it defined; None for trades
+/// opened without a stop
+fn initial_stop(trade: &Trade) -> Option<f64> {
+    let risk = trade.initial_risk? / trade.quantity;
+    Some(match trade.side {
+        Side::Cover => trade.entry_price + risk,
+        _ => trade.entry_price - risk,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_bars_with_rsi_pattern;
+    use crate::engine::BacktestEngine;
+    use chrono::{Duration, TimeZone};
+    use common::BacktestParameters;
+    use std::fs;
+
+    fn run() -> (BacktestResult, Vec<Bar>) {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let mut bars = generate_bars_with_rsi_pattern(60, 100.0, &[22, 30, 58], &[]);
+        for (i, bar) in bars.iter_mut().enumerate() {
+            bar.timestamp = start + Duration::days(i as i64);
+        }
+        let params = BacktestParameters::default()
+            .without_vwap_filter()
+            .without_short()
+            .with_sma_period(1);
+        (BacktestEngine::new(params).run(&bars, None), bars)
+    }
+
+    #[test]
+    fn test_one_entry_cell_per_trade() {
+        let (result, bars) = run();
+        let rows = annotate(&result, &bars);
+
+        assert_eq!(rows.len(), bars.len());
+        assert!(result.trades.len() >= 2);
+        let entries = rows.iter().filter(|r| r.entry.is_some()).count();
+        assert_eq!(entries, result.trades.len());
+
+        for trade in &result.trades {
+            let stop = trade.entry_price - trade.initial_risk.unwrap() / trade.quantity;
+            let start = bar_at(&bars, trade.entry_date).unwrap();
+            assert_eq!(rows[start].entry, Some(trade.entry_price));
+            assert!((rows[start].stop_level.unwrap() - stop).abs() < 1e-9);
+            assert_eq!(rows[start].position_open, 1);
+        }
+        // Flat before the first entry
+        assert!(rows[..10]
+            .iter()
+            .all(|r| r.position_open == 0 && r.stop_level.is_none()));
+
+        let markers = chart_markers(&result);
+        assert_eq!(markers[0].position, "belowBar");
+        assert!(markers.windows(2).all(|w| w[0].time <= w[1].time));
+    }
+
+    #[test]
+    fn test_same_bar_entry_and_exit() {
+        let (mut result, bars) = run();
+        result.trades.truncate(1);
+        let trade = &mut result.trades[0];
+        trade.exit_date = Some(trade.entry_date);
+        trade.exit_price = Some(trade.entry_price + 1.0);
+        let (entry_date, entry_price) = (trade.entry_date, trade.entry_price);
+
+        let rows = annotate(&result, &bars);
+        let row = rows.iter().find(|r| r.time == entry_date).unwrap();
+        assert_eq!(row.entry, Some(entry_price));
+        assert_eq!(row.exit, Some(entry_price + 1.0));
+        assert!(row.stop_level.is_some());
+        assert_eq!(row.position_open, 0);
+        assert_eq!(chart_markers(&result).len(), 2);
+    }
+
+    #[test]
+    fn test_export_writes_files() {
+        let (result, bars) = run();
+        let csv_path = std::env::temp_dir().join("annotated_bars_test.csv");
+        let json_path = csv_path.with_extension("json");
+
+        assert_eq!(
+            export_annotated_csv(&result, &bars, &csv_path, Overwrite::Force).unwrap(),
+            bars.len()
+        );
+        let markers = export_chart_markers(&result, &json_path, Overwrite::Force).unwrap();
+        let csv = fs::read_to_string(&csv_path).unwrap();
+        let json = fs::read_to_string(&json_path).unwrap();
+        fs::remove_file(&csv_path).ok();
+        fs::remove_file(&json_path).ok();
+
+        assert!(
+            csv.starts_with("time,open,high,low,close,volume,entry,exit,stop_level,position_open")
+        );
+        let parsed: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.as_array().unwrap().len(), markers);
+    }
+}
@@ -0,0 +1,338 @@
+//! Markdown trade journal
+//!
+//! One `##` section per trade with dates, reasons, RSI, size, P&L, MAE/MFE and
+//! an ASCII sparkline of the closes while the trade was held, plus the
+//! indicator context of entry and exit when the run recorded it.
+
+use std::fmt::Write as _;
+use std::path::Path;
+
+use common::{BacktestResult, Bar, IndicatorSnapshot, Result, Side, Trade};
+
+use crate::output::{write_bytes, Overwrite};
+
+/// Exit reason the engine uses for positions still open when the data ends
+const END_OF_BACKTEST: &str = "end of backtest";
+
+/// Sparkline levels, lowest to highest
+const SPARK_LEVELS: &[u8] = b"_.-~=*^";
+
+/// Render the journal and write it to `path`
+pub fn export_journal(
+    result: &BacktestResult,
+    bars: &[Bar],
+    path: &Path,
+    overwrite: Overwrite,
+) -> Result<()> {
+    write_bytes(path, overwrite, render_journal(result, bars).as_bytes())
+}
+
+/// Render the journal as a markdown document
+pub fn render_journal(result: &BacktestResult, bars: &[Bar]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Trade Journal");
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "{} to {} | {} trades | ${:.2} -> ${:.2} ({:+.2}%)",
+        result.start_date,
+        result.end_date,
+        result.trades.len(),
+        result.initial_capital,
+        result.final_equity,
+        result.metrics.total_return_pct
+    );
+
+    for (n, trade) in result.trades.iter().enumerate() {
+        let _ = writeln!(out);
+        write_trade(&mut out, n + 1, trade, bars);
+    }
+    out
+}
+
+fn write_trade(out: &mut String, number: usize, trade: &Trade, bars: &[Bar]) {
+    let direction = match trade.side {
+        Side::Sell => "Long",
+        Side::Cover => "Short",
+        Side::HedgeSell => "Hedge",
+        Side::SyntheticCover => "Synthetic short",
+        _ => "Trade",
+    };
+    let is_open = trade.exit_date.is_none() || trade.exit_reason == END_OF_BACKTEST;
+    let entry_day = trade.entry_date.format("%Y-%m-%d");
+    let exit_day = trade
+        .exit_date
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let rsi = |r: Option<f64>| {
+        r.map(|r| format!("{:.1}", r))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let reason = |r: &str| {
+        if r.is_empty() {
+            "-".to_string()
+        } else {
+            r.to_string()
+        }
+    };
+
+    if is_open {
+        let _ = writeln!(
+            out,
+            "## Trade {}: {} {} - OPEN",
+            number, direction, entry_day
+        );
+    } else {
+        let _ = writeln!(
+            out,
+            "## Trade {}: {} {} to {} ({:+.2}%)",
+            number, direction, entry_day, exit_day, trade.pnl_pct
+        );
+    }
+    let _ = writeln!(out);
+    let _ = writeln!(out, "{}", narrative(direction, trade, is_open));
+    let _ = writeln!(out);
+    let _ = writeln!(
+        out,
+        "- **Entry:** {} @ ${:.2} | RSI {} | {}",
+        entry_day,
+        trade.entry_price,
+        rsi(trade.entry_rsi),
+        reason(&trade.entry_reason)
+    );
+    match trade.exit_price {
+        Some(price) if !is_open => {
+            let _ = writeln!(
+                out,
+                "- **Exit:** {} @ ${:.2} | RSI {} | {}",
+                exit_day,
+                price,
+                rsi(trade.exit_rsi),
+                reason(&trade.exit_reason)
+            );
+            if let Some(first) = trade.fills.first() {
+                let _ = writeln!(
+                    out,
+                    "- **Exit fills:** {} tranches from {} (volume capped)",
+                    trade.fills.len(),
+                    first.timestamp.format("%Y-%m-%d")
+                );
+            }
+        }
+        Some(price) => {
+            let _ = writeln!(
+                out,
+                "- **Marked:** {} @ ${:.2} (still open)",
+                exit_day, price
+            );
+        }
+        None => {
+            let _ = writeln!(out, "- **Marked:** - (still open)");
+        }
+    }
+    let _ = writeln!(
+        out,
+        "- **Size:** {:.0} shares (${:.2})",
+        trade.quantity,
+        trade.quantity * trade.entry_price
+    );
+    let r = match (trade.r_multiple, trade.initial_risk) {
+        (Some(r), Some(risk)) => format!(" | {:+.2}R on ${:.2} risk", r, risk),
+        _ => String::new(),
+    };
+    let _ = writeln!(
+        out,
+        "- **P&L:** ${:+.2} ({:+.2}%){} over {} days",
+        trade.pnl, trade.pnl_pct, r, trade.holding_days
+    );
+
+    for (label, context) in [
+        ("Entry", &trade.entry_context),
+        ("Exit", &trade.exit_context),
+    ] {
+        if let Some(context) = context {
+            let _ = writeln!(out, "- **{} context:** {}", label, describe(context));
+        }
+    }
+
+    let held = holding_bars(trade, bars);
+    if trade.side == Side::Sell && !held.is_empty() {
+        let (mae, mfe) = excursions(trade.entry_price, held);
+        let _ = writeln!(out, "- **MAE / MFE:** {:+.2}% / {:+.2}%", mae, mfe);
+        let _ = writeln!(out, "- **Price path:** `{}`", sparkline(held));
+    } else {
+        // Hedge and short trades are priced off bars we were not given
+        let _ = writeln!(out, "- **MAE / MFE:** n/a");
+    }
+}
+
+/// The journal's selection of a snapshot's indicators
+fn describe(context: &IndicatorSnapshot) -> String {
+    let price = |p: Option<f64>| {
+        p.map(|p| format!("${:.2}", p))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let volume = context
+        .volume_ratio
+        .map(|r| format!("{:.2}x avg", r))
+        .unwrap_or_else(|| "-".to_string());
+    format!(
+        "SMA {} | ATR {:.2} | BB ${:.2} - ${:.2} | VWAP {} | volume {}",
+        price(context.sma),
+        context.atr,
+        context.bb_lower,
+        context.bb_upper,
+        price(context.vwap),
+        volume
+    )
+}
+
+/// One-sentence summary of the trade
+fn narrative(direction: &str, trade: &Trade, is_open: bool) -> String {
+    let why = if trade.entry_reason.is_empty() {
+        String::new()
+    } else {
+        format!(" on {}", trade.entry_reason)
+    };
+    let opened = format!(
+        "Opened a {} position of {:.0} shares at ${:.2}{}",
+        direction.to_lowercase(),
+        trade.quantity,
+        trade.entry_price,
+        why
+    );
+
+    if is_open {
+        return format!(
+            "{}; still open when the data ended, marked at ${:+.2}.",
+            opened, trade.pnl
+        );
+    }
+    let outcome = if trade.pnl >= 0.0 { "gain" } else { "loss" };
+    format!(
+        "{}; closed after {} days on {} for a {} of ${:.2}.",
+        opened,
+        trade.holding_days,
+        trade.exit_reason,
+        outcome,
+        trade.pnl.abs()
+    )
+}
+
+/// Bars from entry to exit (inclusive); open trades run to the last bar
+pub fn holding_bars<'a>(trade: &Trade, bars: &'a [Bar]) -> &'a [Bar] {
+    let start = bars.partition_point(|b| b.timestamp < trade.entry_date);
+    let end = match trade.exit_date {
+        Some(exit) => bars.partition_point(|b| b.timestamp <= exit),
+        None => bars.len(),
+    };
+    if start < end {
+        &bars[start..end]
+    } else {
+        &[]
+    }
+}
+
+/// Maximum adverse / favorable excursion in percent of the entry price
+fn excursions(entry_price: f64, held: &[Bar]) -> (f64, f64) {
+    let low = held.iter().map(|b| b.low).fold(f64::INFINITY, f64::min);
+    let high = held
+        .iter()
+        .map(|b| b.high)
+        .fold(f64::NEG_INFINITY, f64::max);
+    (
+        ((low / entry_price - 1.0) * 100.0).min(0.0),
+        ((high / entry_price - 1.0) * 100.0).max(0.0),
+    )
+}
+
+/// One character per bar, scaled between the lowest and highest close
+pub fn sparkline(bars: &[Bar]) -> String {
+    let min = bars.iter().map(|b| b.close).fold(f64::INFINITY, f64::min);
+    let max = bars
+        .iter()
+        .map(|b| b.close)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let top = (SPARK_LEVELS.len() - 1) as f64;
+
+    bars.iter()
+        .map(|b| {
+            let level = if max > min {
+                ((b.close - min) / (max - min) * top).round() as usize
+            } else {
+                SPARK_LEVELS.len() / 2
+            };
+            SPARK_LEVELS[level] as char
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_bars_with_rsi_pattern;
+    use crate::engine::BacktestEngine;
+    use chrono::{Duration, TimeZone, Utc};
+    use common::BacktestParameters;
+    use std::fs;
+
+    fn run() -> (BacktestResult, Vec<Bar>) {
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let mut bars = generate_bars_with_rsi_pattern(60, 100.0, &[22, 30, 58], &[]);
+        for (i, bar) in bars.iter_mut().enumerate() {
+            bar.timestamp = start + Duration::days(i as i64);
+        }
+        let params = BacktestParameters::default()
+            .without_vwap_filter()
+            .without_short()
+            .with_sma_period(1);
+        (BacktestEngine::new(params).run(&bars, None), bars)
+    }
+
+    #[test]
+    fn test_one_section_per_trade() {
+        let (result, bars) = run();
+        let journal = render_journal(&result, &bars);
+
+        assert!(result.trades.len() >= 2);
+        let sections = journal.lines().filter(|l| l.starts_with("## ")).count();
+        assert_eq!(sections, result.trades.len());
+        // Default 5% stops: every trade reports its R-multiple
+        let with_r = journal.lines().filter(|l| l.contains("R on $")).count();
+        assert_eq!(with_r, result.trades.len());
+        assert!(journal.contains("RSI(") && journal.contains("take profit"));
+
+        // The dip on the second-to-last bar is still open at the end
+        let last = journal.lines().rfind(|l| l.starts_with("## ")).unwrap();
+        assert!(last.ends_with("OPEN"), "{}", last);
+    }
+
+    #[test]
+    fn test_sparkline_covers_holding_bars() {
+        let (result, bars) = run();
+        let journal = render_journal(&result, &bars);
+        let sparklines: Vec<&str> = journal
+            .lines()
+            .filter_map(|l| l.strip_prefix("- **Price path:** `"))
+            .map(|l| l.trim_end_matches('`'))
+            .collect();
+
+        assert_eq!(sparklines.len(), result.trades.len());
+        for (trade, line) in result.trades.iter().zip(sparklines) {
+            assert_eq!(line.len(), holding_bars(trade, &bars).len());
+            assert!(line.len() >= 2);
+        }
+    }
+
+    #[test]
+    fn test_export_writes_file() {
+        let (result, bars) = run();
+        let path = std::env::temp_dir().join("trade_journal_test.md");
+
+        export_journal(&result, &bars, &path, Overwrite::Force).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert!(content.starts_with("# Trade Journal"));
+    }
+}
@@ -0,0 +1,343 @@
+//! Meta-report across many saved runs
+//!
+//! Summarizes a set of results: their headline metrics, how Sharpe is
+//! distributed across them, and which numeric parameters move with it. The
+//! correlation is Spearman's (Pearson over ranks, ties averaged), so it
+//! only assumes a monotonic relationship.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+use serde_json::Value;
+
+use common::{BacktestError, BacktestParameters, BacktestResult, PerformanceMetrics, Result};
+
+/// Fewest runs with a parameter set before its correlation is reported
+const MIN_CORRELATION_RUNS: usize = 3;
+
+/// One run's headline metrics
+#[derive(Debug, Clone, Serialize)]
+pub struct MetaRow {
+    pub symbol: String,
+    pub total_return_pct: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub win_rate: f64,
+    pub total_trades: u32,
+}
+
+/// Spread of a metric across runs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Distribution {
+    pub min: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+/// Rank correlation of one numeric parameter with Sharpe across the runs
+#[derive(Debug, Clone, Serialize)]
+pub struct ParameterCorrelation {
+    /// Field name, dotted within tables (e.g. `execution.latency_bars`)
+    pub parameter: String,
+    pub spearman: f64,
+    /// Runs that set the parameter
+    pub runs: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MetaReport {
+    pub runs: Vec<MetaRow>,
+    /// None without runs
+    pub sharpe: Option<Distribution>,
+    /// Parameters that vary across the runs, strongest correlation first
+    pub correlations: Vec<ParameterCorrelation>,
+}
+
+/// Summarize a set of runs
+pub fn build(run_results: &[(BacktestParameters, PerformanceMetrics)]) -> MetaReport {
+    let runs = run_results
+        .iter()
+        .map(|(params, m)| MetaRow {
+            symbol: params.strategy.symbol.clone(),
+            total_return_pct: m.total_return_pct,
+            sharpe_ratio: m.sharpe_ratio,
+            max_drawdown: m.max_drawdown,
+            win_rate: m.win_rate,
+            total_trades: m.total_trades,
+        })
+        .collect();
+    let sharpes: Vec<f64> = run_results.iter().map(|(_, m)| m.sharpe_ratio).collect();
+
+    // (run, value) of every numeric field, by dotted name
+    let mut fields: Vec<(String, Vec<(usize, f64)>)> = Vec::new();
+    for (run, (params, _)) in run_results.iter().enumerate() {
+        let mut leaves = Vec::new();
+        numeric_fields(
+            "",
+            &serde_json::to_value(params).unwrap_or_default(),
+            &mut leaves,
+        );
+        for (name, value) in leaves {
+            match fields.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, values)) => values.push((run, value)),
+                None => fields.push((name, vec![(run, value)])),
+            }
+        }
+    }
+
+    let mut correlations: Vec<ParameterCorrelation> = fields
+        .into_iter()
+        .filter(|(_, values)| values.len() >= MIN_CORRELATION_RUNS)
+        .filter_map(|(parameter, values)| {
+            let xs: Vec<f64> = values.iter().map(|(_, v)| *v).collect();
+            let ys: Vec<f64> = values.iter().map(|(run, _)| sharpes[*run]).collect();
+            spearman(&xs, &ys).map(|spearman| ParameterCorrelation {
+                parameter,
+                spearman,
+                runs: values.len(),
+            })
+        })
+        .collect();
+    correlations.sort_by(|a, b| {
+        b.spearman
+            .abs()
+            .total_cmp(&a.spearman.abs())
+            .then_with(|| a.parameter.cmp(&b.parameter))
+    });
+
+    MetaReport {
+        runs,
+        sharpe: distribution(&sharpes),
+        correlations,
+    }
+}
+
+/// Which saved runs a report covers
+#[derive(Debug, Clone, Default)]
+pub struct RunFilter {
+    /// (dotted parameter name, value) pairs that must all match; strings
+    /// compare unquoted
+    pub fields: Vec<(String, String)>,
+    /// Only runs created on or after this date
+    pub since: Option<NaiveDate>,
+}
+
+impl RunFilter {
+    pub fn accepts(&self, result: &BacktestResult) -> bool {
+        if self
+            .since
+            .is_some_and(|since| result.manifest.created_at.date_naive() < since)
+        {
+            return false;
+        }
+        let params = serde_json::to_value(&result.manifest.parameters).unwrap_or_default();
+        self.fields.iter().all(|(name, expected)| {
+            let value = name
+                .split('.')
+                .try_fold(&params, |value, key| value.get(key));
+            match value {
+                Some(Value::String(s)) => s == expected,
+                Some(value) => serde_json::from_str::<Value>(expected).is_ok_and(|e| e == *value),
+                None => false,
+            }
+        })
+    }
+}
+
+/// Read every `.json` file in `dir` that holds a backtest result, in file
+/// name order, with the paths of those that do not
+pub fn load_runs(dir: &Path) -> Result<(Vec<BacktestResult>, Vec<PathBuf>)> {
+    let read_error =
+        |e: std::io::Error| BacktestError::DataLoadError(format!("{}: {}", dir.display(), e));
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(read_error)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| {
+            p.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+        })
+        .collect();
+    paths.sort();
+
+    let mut results = Vec::new();
+    let mut skipped = Vec::new();
+    for path in paths {
+        let parsed = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<BacktestResult>(&text).ok());
+        match parsed {
+            Some(result) => results.push(result),
+            None => skipped.push(path),
+        }
+    }
+    Ok((results, skipped))
+}
+
+/// Numeric leaves of `value`, dotted within tables; options left unset
+/// and non-numeric fields are skipped
+fn numeric_fields(path: &str, value: &Value, out: &mut Vec<(String, f64)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                let name = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                numeric_fields(&name, value, out);
+            }
+        }
+        Value::Number(n) => {
+            if let Some(v) = n.as_f64().filter(|v| v.is_finite()) {
+                out.push((path.to_string(), v));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn distribution(values: &[f64]) -> Option<Distribution> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let quantile = |q: f64| {
+        let pos = q * (sorted.len() - 1) as f64;
+        let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+        sorted[lo] + (sorted[hi] - sorted[lo]) * (pos - lo as f64)
+    };
+    Some(Distribution {
+        min: sorted[0],
+        p25: quantile(0.25),
+        median: quantile(0.5),
+        p75: quantile(0.75),
+        max: sorted[sorted.len() - 1],
+        mean: values.iter().sum::<f64>() / values.len() as f64,
+    })
+}
+
+/// Ranks from 1, ties sharing their average rank
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+    let mut ranks = vec![0.0; values.len()];
+    let mut i = 0;
+    while i < order.len() {
+        let mut j = i;
+        while j + 1 < order.len() && values[order[j + 1]] == values[order[i]] {
+            j += 1;
+        }
+        let rank = (i + j) as f64 / 2.0 + 1.0;
+        for &k in &order[i..=j] {
+            ranks[k] = rank;
+        }
+        i = j + 1;
+    }
+    ranks
+}
+
+/// Spearman's rank correlation; None when either side is constant
+fn spearman(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let (rx, ry) = (ranks(xs), ranks(ys));
+    let n = rx.len() as f64;
+    let (mx, my) = (rx.iter().sum::<f64>() / n, ry.iter().sum::<f64>() / n);
+    let cov: f64 = rx.iter().zip(&ry).map(|(x, y)| (x - mx) * (y - my)).sum();
+    let vx: f64 = rx.iter().map(|x| (x - mx).powi(2)).sum();
+    let vy: f64 = ry.iter().map(|y| (y - my).powi(2)).sum();
+    if vx == 0.0 || vy == 0.0 {
+        return None;
+    }
+    Some(cov / (vx * vy).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs where Sharpe rises with `rsi_oversold` and other parameters
+    /// vary without a pattern
+    fn run_set() -> Vec<(BacktestParameters, PerformanceMetrics)> {
+        let stops = [0.03, 0.08, 0.05, 0.02, 0.07, 0.04];
+        let periods = [20, 10, 50, 30, 10, 40];
+        (0..6)
+            .map(|i| {
+                let mut params = BacktestParameters::default()
+                    .with_stop_loss(stops[i])
+                    .with_sma_period(periods[i]);
+                params.strategy.rsi_oversold = 10.0 + 3.0 * i as f64;
+                let metrics = PerformanceMetrics {
+                    sharpe_ratio: 0.2 * i as f64 - 0.3,
+                    total_trades: 10 + i as u32,
+                    ..Default::default()
+                };
+                (params, metrics)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_correlated_parameter_ranks_first() {
+        let report = build(&run_set());
+        assert_eq!(report.runs.len(), 6);
+        assert_eq!(report.correlations[0].parameter, "rsi_oversold");
+        assert!((report.correlations[0].spearman - 1.0).abs() < 1e-12);
+        assert!(report.correlations[1..]
+            .iter()
+            .all(|c| c.spearman.abs() < 0.9));
+        // Constant parameters carry no information
+        assert!(!report
+            .correlations
+            .iter()
+            .any(|c| c.parameter == "rsi_period"));
+
+        let sharpe = report.sharpe.unwrap();
+        assert!((sharpe.min + 0.3).abs() < 1e-12);
+        assert!((sharpe.max - 0.7).abs() < 1e-12);
+        assert!((sharpe.median - 0.2).abs() < 1e-12);
+        assert!(build(&[]).sharpe.is_none());
+    }
+
+    #[test]
+    fn test_ranks_average_ties() {
+        assert_eq!(ranks(&[3.0, 1.0, 3.0, 2.0]), vec![3.5, 1.0, 3.5, 2.0]);
+        assert_eq!(spearman(&[1.0, 2.0, 3.0], &[9.0, 4.0, 1.0]), Some(-1.0));
+        assert_eq!(spearman(&[1.0, 1.0, 1.0], &[9.0, 4.0, 1.0]), None);
+    }
+
+    #[test]
+    fn test_filter_by_field_and_date() {
+        let mut result: BacktestResult = serde_json::from_value(serde_json::json!({
+            "metrics": PerformanceMetrics::default(),
+            "equity_curve": [],
+            "drawdown_curve": [],
+            "trades": [],
+            "start_date": "2024-01-01",
+            "end_date": "2024-06-30",
+            "initial_capital": 10000.0,
+            "final_equity": 10000.0,
+            "execution_time_ms": 0
+        }))
+        .unwrap();
+        result.manifest.parameters = BacktestParameters::default();
+        result.manifest.created_at = "2024-03-01T12:00:00Z".parse().unwrap();
+
+        let filter = |fields: &[(&str, &str)], since: Option<&str>| RunFilter {
+            fields: fields
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            since: since.map(|s| s.parse().unwrap()),
+        };
+        assert!(filter(&[("symbol", "TQQQ")], Some("2024-01-01")).accepts(&result));
+        assert!(filter(&[("execution.latency_bars", "0")], None).accepts(&result));
+        assert!(!filter(&[("symbol", "SPY")], None).accepts(&result));
+        assert!(!filter(&[("no_such_field", "1")], None).accepts(&result));
+        assert!(!filter(&[], Some("2024-03-02")).accepts(&result));
+    }
+}
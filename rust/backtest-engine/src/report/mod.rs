@@ -0,0 +1,9 @@
+//! Human-readable exports and summaries of finished backtest results
+
+pub mod annotated;
+pub mod markdown;
+pub mod meta;
+
+pub use annotated::{export_annotated_csv, export_chart_markers};
+pub use markdown::{export_journal, render_journal};
+pub use meta::{MetaReport, RunFilter};
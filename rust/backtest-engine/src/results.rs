@@ -0,0 +1,244 @@
+//! Stable read-only view of a backtest result
+//!
+//! `BacktestResult` and the structs under it change shape as the engine
+//! grows. [`ResultView`] is the surface to bind dashboards and other
+//! consumers to: its getters keep their names, types and meaning under
+//! semver, while the raw structs remain free to evolve.
+//!
+//! A view reads results written by any earlier version. Every field added
+//! to `BacktestResult` since the first release has a serde default, so an
+//! older document deserializes with those fields empty; the getters here
+//! only read fields every version wrote.
+
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+
+use common::{BacktestError, BacktestResult, MetricField, Result, Side, Trade};
+
+/// A backtest result behind stable getters
+#[derive(Debug, Clone)]
+pub struct ResultView {
+    result: BacktestResult,
+}
+
+/// One point of the equity or drawdown curve
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EquityPoint {
+    pub time: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// One trade of a [`ResultView`]
+#[derive(Debug, Clone, Copy)]
+pub struct TradeView<'a> {
+    trade: &'a Trade,
+}
+
+impl ResultView {
+    pub fn new(result: BacktestResult) -> Self {
+        Self { result }
+    }
+
+    /// Read a result JSON written by this or any earlier version
+    pub fn from_json_str(json: &str) -> Result<Self> {
+        Ok(Self::new(serde_json::from_str(json)?))
+    }
+
+    /// Read a result JSON file written by this or any earlier version
+    pub fn from_json_file(path: &Path) -> Result<Self> {
+        let file = File::open(path)
+            .map_err(|e| BacktestError::DataLoadError(format!("{}: {}", path.display(), e)))?;
+        Ok(Self::new(serde_json::from_reader(BufReader::new(file))?))
+    }
+
+    /// The underlying result; its layout is not covered by the stability
+    /// promise
+    pub fn raw(&self) -> &BacktestResult {
+        &self.result
+    }
+
+    pub fn start_date(&self) -> NaiveDate {
+        self.result.start_date
+    }
+
+    pub fn end_date(&self) -> NaiveDate {
+        self.result.end_date
+    }
+
+    pub fn initial_capital(&self) -> f64 {
+        self.result.initial_capital
+    }
+
+    pub fn final_equity(&self) -> f64 {
+        self.result.final_equity
+    }
+
+    /// Total return (%)
+    pub fn total_return_pct(&self) -> f64 {
+        self.result.metrics.total_return_pct
+    }
+
+    /// Compound annual growth rate (%)
+    pub fn cagr(&self) -> f64 {
+        self.result.metrics.cagr
+    }
+
+    pub fn sharpe(&self) -> f64 {
+        self.result.metrics.sharpe_ratio
+    }
+
+    /// Infinite without a losing period
+    pub fn sortino(&self) -> f64 {
+        self.result.metrics.sortino_ratio
+    }
+
+    /// Maximum drawdown (%)
+    pub fn max_drawdown(&self) -> f64 {
+        self.result.metrics.max_drawdown
+    }
+
+    /// Winning trades (%)
+    pub fn win_rate(&self) -> f64 {
+        self.result.metrics.win_rate
+    }
+
+    /// Infinite without a losing trade
+    pub fn profit_factor(&self) -> f64 {
+        self.result.metrics.profit_factor
+    }
+
+    pub fn trade_count(&self) -> usize {
+        self.result.trades.len()
+    }
+
+    /// Any headline metric by its serialized name (`sharpe_ratio`,
+    /// `max_drawdown`, ...); None for a name this version does not know.
+    /// Metrics newer than the result read as their default.
+    pub fn metric(&self, name: &str) -> Option<f64> {
+        let field: MetricField = name.parse().ok()?;
+        Some(self.result.metrics.get(field))
+    }
+
+    /// Trades in the order they closed, open ones last
+    pub fn trades(&self) -> impl Iterator<Item = TradeView<'_>> {
+        self.result.trades.iter().map(|trade| TradeView { trade })
+    }
+
+    /// Account value at each bar
+    pub fn equity_points(&self) -> impl Iterator<Item = EquityPoint> + '_ {
+        points(&self.result.equity_curve)
+    }
+
+    /// Drawdown (%) from the running peak at each bar
+    pub fn drawdown_points(&self) -> impl Iterator<Item = EquityPoint> + '_ {
+        points(&self.result.drawdown_curve)
+    }
+}
+
+impl From<BacktestResult> for ResultView {
+    fn from(result: BacktestResult) -> Self {
+        Self::new(result)
+    }
+}
+
+impl TradeView<'_> {
+    pub fn entry_time(&self) -> DateTime<Utc> {
+        self.trade.entry_date
+    }
+
+    pub fn entry_price(&self) -> f64 {
+        self.trade.entry_price
+    }
+
+    /// None while the trade is open
+    pub fn exit_time(&self) -> Option<DateTime<Utc>> {
+        self.trade.exit_date
+    }
+
+    pub fn exit_price(&self) -> Option<f64> {
+        self.trade.exit_price
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.trade.exit_date.is_none()
+    }
+
+    /// A short sale rather than a long or hedge purchase
+    pub fn is_short(&self) -> bool {
+        matches!(self.trade.side, Side::Cover | Side::SyntheticCover)
+    }
+
+    /// The hedge rather than the main symbol
+    pub fn is_hedge(&self) -> bool {
+        self.trade.is_hedge()
+    }
+
+    pub fn quantity(&self) -> f64 {
+        self.trade.quantity
+    }
+
+    /// Dollar P&L, net of costs
+    pub fn pnl(&self) -> f64 {
+        self.trade.pnl
+    }
+
+    pub fn pnl_pct(&self) -> f64 {
+        self.trade.pnl_pct
+    }
+
+    pub fn holding_days(&self) -> i64 {
+        self.trade.holding_days
+    }
+
+    pub fn entry_reason(&self) -> &str {
+        &self.trade.entry_reason
+    }
+
+    pub fn exit_reason(&self) -> &str {
+        &self.trade.exit_reason
+    }
+}
+
+fn points(curve: &[(DateTime<Utc>, f64)]) -> impl Iterator<Item = EquityPoint> + '_ {
+    curve
+        .iter()
+        .map(|&(time, value)| EquityPoint { time, value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+    use crate::engine::BacktestEngine;
+    use common::BacktestParameters;
+
+    #[test]
+    fn test_view_reads_the_result() {
+        let bars = generate_synthetic_bars_seeded(300, 50.0, 4);
+        let params = BacktestParameters::default()
+            .without_vwap_filter()
+            .without_short();
+        let result = BacktestEngine::new(params).run(&bars, None);
+        let view = ResultView::from(result.clone());
+
+        assert_eq!(view.sharpe(), result.metrics.sharpe_ratio);
+        assert_eq!(view.metric("sharpe_ratio"), Some(view.sharpe()));
+        assert_eq!(view.metric("no_such_metric"), None);
+        assert_eq!(view.trade_count(), result.trades.len());
+        let pnl: f64 = view.trades().map(|t| t.pnl()).sum();
+        assert!((pnl - result.trades.iter().map(|t| t.pnl).sum::<f64>()).abs() < 1e-9);
+        assert_eq!(
+            view.equity_points().last().map(|p| p.value),
+            result.equity_curve.last().map(|p| p.1)
+        );
+
+        let json = serde_json::to_string(&result).unwrap();
+        let parsed = ResultView::from_json_str(&json).unwrap();
+        assert_eq!(parsed.final_equity(), view.final_equity());
+        assert!(ResultView::from_json_str("{}").is_err());
+    }
+}
@@ -0,0 +1,282 @@
+//! Entry guard rails
+//!
+//! Limits that can block new positions independently of the signal logic.
+//! Exits are never subject to these checks.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Datelike, FixedOffset, NaiveDate, Utc};
+use common::{BacktestParameters, ExecutionParams, RiskParams};
+
+/// Tracks entry counts per calendar day and month, and the spacing of
+/// entry fills
+#[derive(Debug)]
+pub struct EntryLimiter {
+    max_per_month: Option<u32>,
+    max_per_day: Option<u32>,
+    offset: FixedOffset,
+    current_month: Option<(i32, u32)>,
+    current_day: Option<NaiveDate>,
+    month_count: u32,
+    day_count: u32,
+    min_bars_between: usize,
+    /// Bar index of each symbol's last entry fill
+    last_fill: HashMap<String, usize>,
+    /// Bar index of the last entry fill of any symbol
+    last_fill_bar: Option<usize>,
+}
+
+impl EntryLimiter {
+    pub fn new(params: &BacktestParameters) -> Self {
+        let offset = FixedOffset::east_opt(params.display_utc_offset_minutes * 60)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+
+        Self {
+            max_per_month: params.risk.max_trades_per_month,
+            max_per_day: params.risk.max_new_positions_per_day,
+            offset,
+            current_month: None,
+            current_day: None,
+            month_count: 0,
+            day_count: 0,
+            min_bars_between: params.risk.min_bars_between_entries,
+            last_fill: HashMap::new(),
+            last_fill_bar: None,
+        }
+    }
+
+    /// Check whether a new entry is allowed at this timestamp.
+    ///
+    /// Returns the suppression reason when a limit has been reached.
+    pub fn check(&mut self, timestamp: DateTime<Utc>) -> Option<String> {
+        self.roll(timestamp);
+
+        if let Some(max) = self.max_per_month {
+            if self.month_count >= max {
+                return Some(format!("monthly trade limit reached ({})", max));
+            }
+        }
+
+        if let Some(max) = self.max_per_day {
+            if self.day_count >= max {
+                return Some(format!("daily new position limit reached ({})", max));
+            }
+        }
+
+        None
+    }
+
+    /// Record an entry filled at this timestamp
+    pub fn record(&mut self, timestamp: DateTime<Utc>) {
+        self.roll(timestamp);
+        self.month_count += 1;
+        self.day_count += 1;
+    }
+
+    /// Check whether an entry into `symbol` may be placed on `bar_index`
+    /// given `min_bars_between_entries`, counted from the last fill.
+    ///
+    /// Returns the suppression reason when it is too soon.
+    pub fn check_spacing(&self, symbol: &str, bar_index: usize) -> Option<String> {
+        if self.min_bars_between == 0 {
+            return None;
+        }
+        if self.last_fill_bar == Some(bar_index) {
+            return Some("a position already opened on this bar".to_string());
+        }
+        let elapsed = bar_index - self.last_fill.get(symbol)?;
+        if elapsed < self.min_bars_between {
+            return Some(format!(
+                "{} bar(s) since the last {} entry (min {})",
+                elapsed, symbol, self.min_bars_between
+            ));
+        }
+        None
+    }
+
+    /// Record that an entry into `symbol` filled on `bar_index`
+    pub fn record_fill(&mut self, symbol: &str, bar_index: usize) {
+        self.last_fill.insert(symbol.to_string(), bar_index);
+        self.last_fill_bar = Some(bar_index);
+    }
+
+    /// Reset counters when the calendar day or month changes
+    fn roll(&mut self, timestamp: DateTime<Utc>) {
+        let local = timestamp.with_timezone(&self.offset);
+        let day = local.date_naive();
+        let month = (local.year(), local.month());
+
+        if self.current_month != Some(month) {
+            self.current_month = Some(month);
+            self.month_count = 0;
+        }
+        if self.current_day != Some(day) {
+            self.current_day = Some(day);
+            self.day_count = 0;
+        }
+    }
+}
+
+/// Skips entries whose expected move does not cover costs plus a margin.
+///
+/// The expected move is `edge_atr_multiple × ATR`. The round-trip cost is the
+/// commission on both legs plus twice the execution simulator's base spread
+/// (when spread simulation is active), or twice the flat `slippage_pct` when
+/// the simulator is off.
+#[derive(Debug)]
+pub struct EdgeFilter {
+    enabled: bool,
+    atr_multiple: f64,
+    min_profit_pct: f64,
+    commission: f64,
+    spread_pct: f64,
+    slippage_pct: f64,
+}
+
+impl EdgeFilter {
+    pub fn new(risk: &RiskParams, execution: &ExecutionParams) -> Self {
+        let simulation = &execution.simulation;
+        let spread_pct = if simulation.enabled && simulation.spread_enabled {
+            simulation.spread_base_pct * 100.0
+        } else {
+            0.0
+        };
+        let slippage_pct = if simulation.enabled {
+            0.0
+        } else {
+            execution.slippage_pct * 100.0
+        };
+
+        Self {
+            enabled: risk.edge_filter_enabled,
+            atr_multiple: risk.edge_atr_multiple,
+            min_profit_pct: risk.min_expected_profit_pct,
+            commission: execution.commission,
+            spread_pct,
+            slippage_pct,
+        }
+    }
+
+    /// Modeled round-trip cost (%) of a position worth `notional`
+    pub fn round_trip_cost_pct(&self, notional: f64) -> f64 {
+        let commission_pct = if notional > 0.0 {
+            2.0 * self.commission / notional * 100.0
+        } else {
+            0.0
+        };
+        commission_pct + 2.0 * (self.spread_pct + self.slippage_pct)
+    }
+
+    /// Check an entry at `price` with the bar's `atr`.
+    ///
+    /// Returns the suppression reason when the edge is insufficient.
+    pub fn check(&self, price: f64, atr: f64, notional: f64) -> Option<String> {
+        if !self.enabled || price <= 0.0 {
+            return None;
+        }
+
+        let expected_pct = self.atr_multiple * atr / price * 100.0;
+        let required_pct = self.min_profit_pct + self.round_trip_cost_pct(notional);
+        if expected_pct < required_pct {
+            return Some(format!(
+                "insufficient edge (expected {:.2}% < required {:.2}%)",
+                expected_pct, required_pct
+            ));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn ts(month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, month, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_unlimited_by_default() {
+        let mut limiter = EntryLimiter::new(&BacktestParameters::default());
+        for day in 1..=20 {
+            assert!(limiter.check(ts(1, day, 12)).is_none());
+            limiter.record(ts(1, day, 12));
+        }
+    }
+
+    #[test]
+    fn test_monthly_limit_resets_next_month() {
+        let params = BacktestParameters::default().with_trade_limits(Some(2), None);
+        let mut limiter = EntryLimiter::new(&params);
+
+        limiter.record(ts(1, 3, 12));
+        limiter.record(ts(1, 10, 12));
+        assert!(limiter.check(ts(1, 20, 12)).is_some());
+        assert!(limiter.check(ts(2, 1, 12)).is_none());
+    }
+
+    #[test]
+    fn test_daily_limit() {
+        let params = BacktestParameters::default().with_trade_limits(None, Some(1));
+        let mut limiter = EntryLimiter::new(&params);
+
+        limiter.record(ts(1, 3, 10));
+        assert!(limiter.check(ts(1, 3, 15)).is_some());
+        assert!(limiter.check(ts(1, 4, 10)).is_none());
+    }
+
+    #[test]
+    fn test_entry_spacing_per_symbol() {
+        let mut params = BacktestParameters::default();
+        params.risk.min_bars_between_entries = 3;
+        let mut limiter = EntryLimiter::new(&params);
+        assert!(limiter.check_spacing("TQQQ", 10).is_none());
+
+        limiter.record_fill("TQQQ", 10);
+        assert!(limiter.check_spacing("SQQQ", 10).is_some());
+        assert!(limiter.check_spacing("SQQQ", 11).is_none());
+        let reason = limiter.check_spacing("TQQQ", 12).unwrap();
+        assert_eq!(reason, "2 bar(s) since the last TQQQ entry (min 3)");
+        assert!(limiter.check_spacing("TQQQ", 13).is_none());
+
+        let mut unthrottled = EntryLimiter::new(&BacktestParameters::default());
+        unthrottled.record_fill("TQQQ", 10);
+        assert!(unthrottled.check_spacing("TQQQ", 10).is_none());
+    }
+
+    #[test]
+    fn test_edge_filter_costs() {
+        let mut params = BacktestParameters::default();
+        params.risk.edge_filter_enabled = true;
+        params.risk.min_expected_profit_pct = 0.5;
+        params.execution.commission = 10.0;
+        let filter = EdgeFilter::new(&params.risk, &params.execution);
+
+        // $10 each way on $10,000 is 0.2% round trip, plus 0.1% flat
+        // slippage on both legs
+        assert!((filter.round_trip_cost_pct(10_000.0) - 0.4).abs() < 1e-12);
+        // 1 ATR of $1 on a $100 stock: 1% expected vs 0.9% required
+        assert!(filter.check(100.0, 1.0, 10_000.0).is_none());
+        // Half that ATR falls short
+        let reason = filter.check(100.0, 0.5, 10_000.0).unwrap();
+        assert!(reason.starts_with("insufficient edge"));
+
+        params.risk.edge_filter_enabled = false;
+        assert!(EdgeFilter::new(&params.risk, &params.execution)
+            .check(100.0, 0.0, 1.0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_month_boundary_uses_display_offset() {
+        let mut params = BacktestParameters::default().with_trade_limits(Some(1), None);
+        // UTC-5: 2024-02-01 03:00 UTC is still January 31st locally
+        params.display_utc_offset_minutes = -300;
+        let mut limiter = EntryLimiter::new(&params);
+
+        limiter.record(ts(1, 15, 12));
+        assert!(limiter.check(ts(2, 1, 3)).is_some());
+        assert!(limiter.check(ts(2, 1, 6)).is_none());
+    }
+}
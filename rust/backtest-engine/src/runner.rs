@@ -0,0 +1,336 @@
+//! Backtests set up from files
+//!
+//! `BacktestRunner` loads and checks everything a run needs before the
+//! first bar is simulated: parameters, data files, and the hedge and
+//! benchmark series they are aligned with. `verify` stops there and
+//! reports what it found, so a config and its data can be checked in CI
+//! without paying for the simulation.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use serde::Serialize;
+
+use common::{BacktestError, BacktestParameters, BacktestResult, Bar, DataWarning, Result};
+
+use crate::data::{load_file, load_files_with, validate_bars};
+use crate::engine::BacktestEngine;
+use crate::pretrade::{PreTradeCheck, PreTradeChecks};
+
+/// Parameters and data files of one backtest
+#[derive(Debug, Clone)]
+pub struct BacktestRunner {
+    params: BacktestParameters,
+    data_files: Vec<PathBuf>,
+    dedupe_overlap: bool,
+    hedge_file: Option<PathBuf>,
+    benchmark_file: Option<PathBuf>,
+    regime_lookback: Option<usize>,
+    pre_trade_checks: PreTradeChecks,
+}
+
+/// What `verify` checked, for a setup that passed
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationReport {
+    pub files: Vec<VerifiedFile>,
+    pub bars: usize,
+    pub hedge_bars: Option<usize>,
+    /// Benchmark bars on dates the data covers
+    pub benchmark_overlap: Option<usize>,
+    /// Bars with a trailing volatility to classify the regime by
+    pub regime_bars: Option<usize>,
+    pub warmup_bars: usize,
+    /// Bars left to trade after warmup
+    pub tradable_bars: usize,
+    /// First and last date traded
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// Data problems the run would report; none of them stop it
+    pub warnings: Vec<DataWarning>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifiedFile {
+    pub path: PathBuf,
+    pub bars: usize,
+    pub overlapping: usize,
+}
+
+/// Bars loaded and aligned for a run
+struct Prepared {
+    bars: Vec<Bar>,
+    hedge: Option<Vec<Bar>>,
+    report: VerificationReport,
+}
+
+impl BacktestRunner {
+    pub fn new(params: BacktestParameters, data_files: Vec<PathBuf>) -> Self {
+        Self {
+            params,
+            data_files,
+            dedupe_overlap: false,
+            hedge_file: None,
+            benchmark_file: None,
+            regime_lookback: None,
+            pre_trade_checks: PreTradeChecks::default(),
+        }
+    }
+
+    /// Drop bars overlapping an earlier data file instead of failing
+    pub fn with_dedupe_overlap(mut self, dedupe: bool) -> Self {
+        self.dedupe_overlap = dedupe;
+        self
+    }
+
+    /// Hedge instrument bars; they must share the data's timestamps
+    pub fn with_hedge_file(mut self, path: PathBuf) -> Self {
+        self.hedge_file = Some(path);
+        self
+    }
+
+    /// Benchmark bars; they must cover some of the data's dates
+    pub fn with_benchmark_file(mut self, path: PathBuf) -> Self {
+        self.benchmark_file = Some(path);
+        self
+    }
+
+    /// Volatility lookback of the regime breakdown
+    pub fn with_regime_lookback(mut self, lookback: usize) -> Self {
+        self.regime_lookback = Some(lookback);
+        self
+    }
+
+    /// Review every entry with `check`, after the checks the parameters
+    /// configure and those added before
+    pub fn with_pre_trade_check(mut self, check: impl PreTradeCheck + 'static) -> Self {
+        self.pre_trade_checks.push(std::sync::Arc::new(check));
+        self
+    }
+
+    /// Run every setup check of `run` without simulating
+    pub fn verify(&self) -> Result<VerificationReport> {
+        self.prepare().map(|prepared| prepared.report)
+    }
+
+    /// Set up and run the backtest
+    pub fn run(&self) -> Result<BacktestResult> {
+        let prepared = self.prepare()?;
+        let engine =
+            BacktestEngine::new(self.params.clone()).with_pre_trade_checks(&self.pre_trade_checks);
+        Ok(engine.run(&prepared.bars, prepared.hedge.as_deref()))
+    }
+
+    fn prepare(&self) -> Result<Prepared> {
+        self.params.validate()?;
+        if self.data_files.is_empty() {
+            return Err(BacktestError::ConfigError(
+                "no data files given".to_string(),
+            ));
+        }
+
+        let (bars, loaded) = load_files_with(&self.data_files, self.dedupe_overlap)?;
+        let warmup_bars = self.params.warmup_bars();
+        let required = warmup_bars + 1;
+        if bars.len() < required {
+            return Err(BacktestError::InsufficientData {
+                required,
+                actual: bars.len(),
+            });
+        }
+
+        let hedge = match &self.hedge_file {
+            Some(path) => {
+                let hedge = load_file(path)?;
+                check_aligned(&bars, &hedge, path)?;
+                Some(hedge)
+            }
+            None => None,
+        };
+
+        let benchmark_overlap = match &self.benchmark_file {
+            Some(path) => {
+                let benchmark = load_file(path)?;
+                let overlap = date_overlap(&bars, &benchmark);
+                if overlap == 0 {
+                    return Err(BacktestError::DataLoadError(format!(
+                        "{}: benchmark shares no dates with the data",
+                        path.display()
+                    )));
+                }
+                Some(overlap)
+            }
+            None => None,
+        };
+
+        let regime_bars = match self.regime_lookback {
+            Some(lookback) if lookback < 2 => {
+                return Err(BacktestError::InvalidParameter(format!(
+                    "regime lookback must be at least 2, got {}",
+                    lookback
+                )));
+            }
+            Some(lookback) if bars.len() <= lookback => {
+                return Err(BacktestError::InsufficientData {
+                    required: lookback + 1,
+                    actual: bars.len(),
+                });
+            }
+            Some(lookback) => Some(bars.len() - lookback),
+            None => None,
+        };
+
+        let report = VerificationReport {
+            files: loaded
+                .into_iter()
+                .map(|f| VerifiedFile {
+                    path: f.path,
+                    bars: f.bars,
+                    overlapping: f.overlapping,
+                })
+                .collect(),
+            bars: bars.len(),
+            hedge_bars: hedge.as_ref().map(Vec::len),
+            benchmark_overlap,
+            regime_bars,
+            warmup_bars,
+            tradable_bars: bars.len() - warmup_bars,
+            start_date: bars[warmup_bars].timestamp.date_naive(),
+            end_date: bars[bars.len() - 1].timestamp.date_naive(),
+            warnings: validate_bars(&bars, &self.params.execution),
+        };
+        Ok(Prepared {
+            bars,
+            hedge,
+            report,
+        })
+    }
+}
+
+/// The engine pairs hedge bars with data bars by position, so both need
+/// the same timestamps
+fn check_aligned(bars: &[Bar], hedge: &[Bar], path: &Path) -> Result<()> {
+    if let Some(i) =
+        (0..bars.len().min(hedge.len())).find(|&i| bars[i].timestamp != hedge[i].timestamp)
+    {
+        return Err(BacktestError::DataLoadError(format!(
+            "{}: hedge bar {} is at {}, data bar at {}",
+            path.display(),
+            i,
+            hedge[i].timestamp,
+            bars[i].timestamp
+        )));
+    }
+    if hedge.len() != bars.len() {
+        return Err(BacktestError::DataLoadError(format!(
+            "{}: {} hedge bars for {} data bars",
+            path.display(),
+            hedge.len(),
+            bars.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Bars of `other` on a date some bar of `bars` is on
+fn date_overlap(bars: &[Bar], other: &[Bar]) -> usize {
+    let dates: HashSet<NaiveDate> = bars.iter().map(|b| b.timestamp.date_naive()).collect();
+    other
+        .iter()
+        .filter(|b| dates.contains(&b.timestamp.date_naive()))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+    use std::fs;
+
+    fn write_csv(name: &str, header: &str, bars: &[Bar]) -> PathBuf {
+        let path = std::env::temp_dir().join(name);
+        let mut csv = format!("{}\n", header);
+        let full = header.split(',').count() >= 6;
+        for b in bars {
+            csv += &format!(
+                "{},{},{},{},{}",
+                b.timestamp.to_rfc3339(),
+                b.open,
+                b.high,
+                b.low,
+                b.close
+            );
+            if full {
+                csv += &format!(",{}", b.volume);
+            }
+            csv += "\n";
+        }
+        fs::write(&path, csv).unwrap();
+        path
+    }
+
+    const HEADER: &str = "timestamp,open,high,low,close,volume";
+
+    #[test]
+    fn test_verify_good_setup() {
+        let bars = generate_synthetic_bars_seeded(120, 50.0, 1);
+        let hedge = generate_synthetic_bars_seeded(120, 30.0, 2);
+        let data = write_csv("verify_good_data.csv", HEADER, &bars);
+        let hedge_path = write_csv("verify_good_hedge.csv", HEADER, &hedge);
+
+        let params = BacktestParameters::default();
+        let report = BacktestRunner::new(params.clone(), vec![data.clone()])
+            .with_hedge_file(hedge_path.clone())
+            .with_benchmark_file(hedge_path.clone())
+            .with_regime_lookback(20)
+            .verify();
+        fs::remove_file(&data).ok();
+        fs::remove_file(&hedge_path).ok();
+        let report = report.unwrap();
+
+        assert_eq!(report.bars, 120);
+        assert_eq!(report.files[0].bars, 120);
+        assert_eq!(report.hedge_bars, Some(120));
+        assert_eq!(report.benchmark_overlap, Some(120));
+        assert_eq!(report.regime_bars, Some(100));
+        assert_eq!(report.warmup_bars, params.warmup_bars());
+        assert_eq!(report.tradable_bars, 120 - params.warmup_bars());
+        assert_eq!(
+            report.start_date,
+            bars[params.warmup_bars()].timestamp.date_naive()
+        );
+        assert_eq!(report.end_date, bars[119].timestamp.date_naive());
+    }
+
+    #[test]
+    fn test_verify_broken_setups() {
+        let bars = generate_synthetic_bars_seeded(120, 50.0, 1);
+        let verify = |name: &str, header: &str, bars: &[Bar], hedge: Option<&[Bar]>| {
+            let data = write_csv(name, header, bars);
+            let hedge_path = hedge.map(|h| write_csv(&format!("hedge_{}", name), HEADER, h));
+            let mut runner = BacktestRunner::new(BacktestParameters::default(), vec![data.clone()]);
+            if let Some(path) = &hedge_path {
+                runner = runner.with_hedge_file(path.clone());
+            }
+            let err = runner.verify().unwrap_err();
+            fs::remove_file(&data).ok();
+            hedge_path.map(fs::remove_file);
+            err.kind()
+        };
+
+        let no_volume = "timestamp,open,high,low,close";
+        assert_eq!(
+            verify("verify_no_volume.csv", no_volume, &bars, None),
+            "csv_error"
+        );
+        let shifted = generate_synthetic_bars_seeded(121, 30.0, 2);
+        assert_eq!(
+            verify("verify_shifted.csv", HEADER, &bars, Some(&shifted[1..])),
+            "data_load_error"
+        );
+        assert_eq!(
+            verify("verify_short.csv", HEADER, &bars[..10], None),
+            "insufficient_data"
+        );
+    }
+}
@@ -1,20 +1,53 @@
-use chrono::{DateTime, Utc};
-use common::{BacktestParameters, Bar, Position, PositionSide, Signal, SignalType};
+use serde::Serialize;
+
+use common::{
+    Bar, DirectionMode, HedgeParams, Position, Signal, SignalType, StrategyParams, VwapMode,
+};
 
 use crate::indicators::IndicatorValues;
 
+/// Outcome of one entry check on a bar
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilterCheck {
+    pub name: String,
+    /// Disabled filters are listed as passed
+    pub enabled: bool,
+    pub passed: bool,
+    /// The values compared, or why the check could not apply
+    pub detail: String,
+}
+
 /// Signal generator based on RSI(2) mean reversion strategy
+///
+/// Sees only the signal rules; sizing, costs and order handling stay with
+/// the engine.
 pub struct SignalGenerator {
-    params: BacktestParameters,
+    strategy: StrategyParams,
+    hedge: HedgeParams,
+    /// Consecutive observed bars, up to the last, meeting the entry condition
+    entry_streak: usize,
 }
 
 impl SignalGenerator {
-    pub fn new(params: &BacktestParameters) -> Self {
+    pub fn new(strategy: &StrategyParams, hedge: &HedgeParams) -> Self {
         Self {
-            params: params.clone(),
+            strategy: strategy.clone(),
+            hedge: hedge.clone(),
+            entry_streak: 0,
         }
     }
 
+    /// Advance the entry confirmation streak by one bar. Call once per bar,
+    /// before generating its signal; only needed with
+    /// `entry_confirmation_bars` above 1.
+    pub fn observe(&mut self, bar: &Bar, indicators: &IndicatorValues) {
+        self.entry_streak = if self.entry_condition(bar, indicators) {
+            self.entry_streak + 1
+        } else {
+            0
+        };
+    }
+
     /// Generate trading signal based on current market state
     pub fn generate(
         &self,
@@ -24,6 +57,11 @@ impl SignalGenerator {
         current_position: Option<&Position>,
         has_hedge: bool,
     ) -> Option<Signal> {
+        // Warmup placeholders are no readings to act on
+        if indicators.warming_up {
+            return None;
+        }
+
         // Check for exit signals first (if we have a position)
         if has_position {
             if let Some(signal) = self.check_exit_signal(bar, indicators, current_position) {
@@ -31,21 +69,20 @@ impl SignalGenerator {
             }
         }
 
-        // Check for hedge signals
-        if self.params.short_enabled {
-            if has_hedge {
-                if let Some(signal) = self.check_hedge_exit_signal(bar, indicators) {
-                    return Some(signal);
-                }
-            } else if !has_position {
-                if let Some(signal) = self.check_hedge_entry_signal(bar, indicators) {
-                    return Some(signal);
-                }
+        // Check for hedge signals (inverse-side signals in the inverse modes)
+        let mode = self.direction_mode(bar, indicators);
+        if has_hedge && (self.hedge.short_enabled || self.hedge.inverse_primary()) {
+            if let Some(signal) = self.check_hedge_exit_signal(bar, indicators) {
+                return Some(signal);
+            }
+        } else if !has_hedge && !has_position && self.trades_inverse(mode) {
+            if let Some(signal) = self.check_hedge_entry_signal(bar, indicators) {
+                return Some(signal);
             }
         }
 
         // Check for entry signals (if no position)
-        if !has_position {
+        if !has_position && mode != DirectionMode::LongInverseOnly {
             if let Some(signal) = self.check_entry_signal(bar, indicators) {
                 return Some(signal);
             }
@@ -54,52 +91,330 @@ impl SignalGenerator {
         None
     }
 
-    /// Check for entry signal (BUY)
-    fn check_entry_signal(&self, bar: &Bar, indicators: &IndicatorValues) -> Option<Signal> {
-        // RSI oversold condition
-        if indicators.rsi > self.params.rsi_oversold {
-            return None;
+    /// Opening signal on the other side of the book after `closing` has
+    /// executed: a hedge entry after an extreme overbought long exit, or a
+    /// long entry after an oversold hedge exit, when the flip is enabled
+    pub fn flip_signal(
+        &self,
+        closing: &Signal,
+        bar: &Bar,
+        indicators: &IndicatorValues,
+    ) -> Option<Signal> {
+        match closing.signal_type {
+            SignalType::Sell if self.hedge.flip_to_hedge_on_extreme && self.hedge.short_enabled => {
+                self.check_hedge_entry_signal(bar, indicators)
+            }
+            SignalType::HedgeSell if self.hedge.flip_to_long_on_oversold => {
+                self.check_entry_signal(bar, indicators)
+            }
+            _ => None,
         }
+    }
 
-        // VWAP filter: price should be below VWAP for better entry
-        if self.params.vwap_filter_enabled && self.params.vwap_entry_below {
-            if let Some(vwap) = indicators.vwap.or(bar.vwap) {
-                if bar.close >= vwap {
-                    return None;
-                }
+    /// Direction in force on the bar: the inverse side only below the trend
+    /// SMA with `auto_flip_on_regime`, the configured mode otherwise
+    pub fn direction_mode(&self, bar: &Bar, indicators: &IndicatorValues) -> DirectionMode {
+        let bearish = indicators.sma.is_some_and(|sma| bar.close < sma);
+        if self.hedge.auto_flip_on_regime && bearish {
+            DirectionMode::LongInverseOnly
+        } else {
+            self.hedge.direction_mode
+        }
+    }
+
+    /// Whether inverse-side entries are open under `mode`
+    fn trades_inverse(&self, mode: DirectionMode) -> bool {
+        if self.hedge.inverse_primary() {
+            mode != DirectionMode::LongPrimary
+        } else {
+            self.hedge.short_enabled
+        }
+    }
+
+    /// RSI levels (entry, exit) of the inverse instrument: the long levels
+    /// mirrored when it is traded in its own right, the hedge levels
+    /// otherwise
+    fn inverse_thresholds(&self) -> (f64, f64) {
+        if self.hedge.inverse_primary() {
+            (self.strategy.rsi_overbought, self.strategy.rsi_oversold)
+        } else {
+            (
+                self.hedge.rsi_overbought_short,
+                self.hedge.rsi_oversold_short,
+            )
+        }
+    }
+
+    /// Whether the bar meets the full entry condition: oversold RSI and
+    /// every enabled filter
+    fn entry_condition(&self, bar: &Bar, indicators: &IndicatorValues) -> bool {
+        self.entry_checks(bar, indicators, &mut Checklist::default())
+    }
+
+    /// Evaluate the entry condition into `checks`. A plain checklist stops
+    /// at the first failure; an explaining one runs and records every check.
+    fn entry_checks(
+        &self,
+        bar: &Bar,
+        indicators: &IndicatorValues,
+        checks: &mut Checklist,
+    ) -> bool {
+        let s = &self.strategy;
+
+        // RSI oversold condition, once warm (again after a gap day)
+        let warm = !indicators.reseeding && !indicators.warming_up;
+        let oversold = warm && indicators.rsi <= s.rsi_oversold;
+        if !checks.record("rsi_oversold", true, oversold, || {
+            if indicators.warming_up {
+                "warming up".to_string()
+            } else if indicators.reseeding {
+                "reseeding after a gap".to_string()
+            } else {
+                format!("RSI {:.1} vs {:.0}", indicators.rsi, s.rsi_oversold)
             }
+        }) {
+            return false;
+        }
+
+        // VWAP filter: below VWAP for a discounted dip by default
+        let vwap = indicators.vwap.or(bar.vwap);
+        let enabled = s.vwap_mode != VwapMode::Disabled;
+        if !checks.record(
+            "vwap",
+            enabled,
+            s.vwap_mode.accepts(bar.close, vwap),
+            || match vwap {
+                Some(vwap) => {
+                    format!(
+                        "close {:.2} vs VWAP {:.2} ({:?})",
+                        bar.close, vwap, s.vwap_mode
+                    )
+                }
+                None => "no VWAP".to_string(),
+            },
+        ) {
+            return false;
         }
 
         // SMA trend filter: price should be above SMA (uptrend)
-        if let Some(sma) = indicators.sma {
-            if bar.close < sma {
-                return None;
+        let above = indicators.sma.is_none_or(|sma| bar.close >= sma);
+        if !checks.record(
+            "sma_trend",
+            s.sma_filter_enabled,
+            above,
+            || match indicators.sma {
+                Some(sma) => format!("close {:.2} vs SMA {:.2}", bar.close, sma),
+                None => "SMA not warmed up".to_string(),
+            },
+        ) {
+            return false;
+        }
+
+        // Anchored VWAP regime filter: above the month's VWAP (inert until
+        // volume trades in the month)
+        let anchored = indicators.anchored_vwap;
+        let above = anchored.is_none_or(|vwap| bar.close > vwap);
+        let enabled = s.anchored_vwap_filter_enabled;
+        if !checks.record("anchored_vwap", enabled, above, || match anchored {
+            Some(vwap) => format!("close {:.2} vs monthly VWAP {:.2}", bar.close, vwap),
+            None => "no monthly VWAP".to_string(),
+        }) {
+            return false;
+        }
+
+        // Momentum filter: don't buy dips while ROC ranks low against its
+        // own history (inert until the percentile has warmed up)
+        let percentile = indicators.roc_percentile;
+        let strong = percentile.is_none_or(|p| p >= s.momentum_min_percentile);
+        if !checks.record(
+            "momentum",
+            s.momentum_filter_enabled,
+            strong,
+            || match percentile {
+                Some(p) => format!(
+                    "ROC percentile {:.1} vs {:.1}",
+                    p, s.momentum_min_percentile
+                ),
+                None => "ROC percentile not warmed up".to_string(),
+            },
+        ) {
+            return false;
+        }
+
+        // Free-fall floor: no dip buying while the market is collapsing
+        // (inert until the ROC has warmed up)
+        let roc = indicators.lookback_roc;
+        let floor = s.momentum_min_pct.unwrap_or(f64::NEG_INFINITY);
+        let falling = roc.is_some_and(|roc| roc <= floor);
+        if !checks.record(
+            "roc_floor",
+            s.momentum_min_pct.is_some(),
+            !falling,
+            || match roc {
+                Some(roc) => format!(
+                    "{}-bar ROC {:+.1}% vs {:+.1}%",
+                    s.momentum_lookback, roc, floor
+                ),
+                None => "ROC not warmed up".to_string(),
+            },
+        ) {
+            return false;
+        }
+
+        // ADX filter: don't fade a trend this strong (inert during warmup)
+        let ranging = indicators.adx.is_none_or(|adx| adx <= s.adx_max);
+        if !checks.record("adx", s.adx_filter_enabled, ranging, || {
+            match indicators.adx {
+                Some(adx) => format!("ADX {:.1} vs {:.1}", adx, s.adx_max),
+                None => "ADX not warmed up".to_string(),
             }
+        }) {
+            return false;
         }
 
-        // Bollinger Band filter (optional)
-        if self.params.bb_filter_enabled && indicators.bb_lower > 0.0 {
-            if bar.close > indicators.bb_lower {
-                return None;
+        // OBV filter: volume has to be flowing in (inert during warmup)
+        let inflow = indicators.obv_slope.is_none_or(|slope| slope > 0.0);
+        if !checks.record("obv", s.obv_filter_enabled, inflow, || {
+            match indicators.obv_slope {
+                Some(slope) => format!("OBV slope {:.0}", slope),
+                None => "OBV slope not warmed up".to_string(),
             }
+        }) {
+            return false;
+        }
+
+        // Bollinger Band filter (optional)
+        let touched = indicators.bb_lower <= 0.0 || bar.close <= indicators.bb_lower;
+        if !checks.record("bollinger", s.bb_filter_enabled, touched, || {
+            format!(
+                "close {:.2} vs lower band {:.2}",
+                bar.close, indicators.bb_lower
+            )
+        }) {
+            return false;
+        }
+
+        // Keltner Channel filter (optional, inert during warmup)
+        let lower = indicators.keltner_lower;
+        let touched = lower.is_none_or(|lower| bar.close <= lower);
+        checks.record(
+            "keltner",
+            s.keltner_filter_enabled,
+            touched,
+            || match lower {
+                Some(lower) => format!("close {:.2} vs lower channel {:.2}", bar.close, lower),
+                None => "Keltner Channel not warmed up".to_string(),
+            },
+        );
+        !checks.failed
+    }
+
+    /// Every entry check on the bar, passed or not, in evaluation order,
+    /// ending with the confirmation streak when one is required
+    pub fn explain_entry(&self, bar: &Bar, indicators: &IndicatorValues) -> Vec<FilterCheck> {
+        let mut checks = Checklist {
+            explain: true,
+            ..Checklist::default()
+        };
+        self.entry_checks(bar, indicators, &mut checks);
+
+        let required = self.strategy.entry_confirmation_bars;
+        if required > 1 {
+            let confirmed = self.entry_streak >= required;
+            checks.record("confirmation", true, confirmed, || {
+                format!("{} of {} bars", self.entry_streak, required)
+            });
+        }
+        checks.checks
+    }
+
+    /// RSI take-profit threshold for `position` at the bar's close, with a
+    /// note of which one applied when `profit_adjusted_exit` or
+    /// `exit_threshold_decay` is set
+    fn take_profit_threshold(
+        &self,
+        bar: &Bar,
+        indicators: &IndicatorValues,
+        position: Option<&Position>,
+    ) -> (f64, String) {
+        let (threshold, note) = self.profit_adjusted_threshold(bar, position);
+        let (Some(decay), Some(held)) = (&self.strategy.exit_threshold_decay, indicators.bars_held)
+        else {
+            return (threshold, note);
+        };
+        let decayed = decay.threshold(threshold, held);
+        if decayed < threshold {
+            let note = format!(
+                "{} (decayed from {:.0} after {} bars)",
+                note, threshold, held
+            );
+            (decayed, note)
+        } else {
+            (threshold, note)
+        }
+    }
+
+    fn profit_adjusted_threshold(&self, bar: &Bar, position: Option<&Position>) -> (f64, String) {
+        let full = self.strategy.rsi_overbought;
+        let (Some(exit), Some(position)) = (&self.strategy.profit_adjusted_exit, position) else {
+            return (full, String::new());
+        };
+        let marked = Position {
+            current_price: bar.close,
+            ..position.clone()
+        };
+        let profit_pct = marked.unrealized_pnl_pct();
+        if profit_pct > exit.profit_trigger_pct {
+            let note = format!(
+                " (reduced threshold: up {:.1}% > {:.1}%)",
+                profit_pct, exit.profit_trigger_pct
+            );
+            (exit.reduced_overbought, note)
+        } else {
+            let note = format!(
+                " (full threshold: {:+.1}% <= {:.1}%)",
+                profit_pct, exit.profit_trigger_pct
+            );
+            (full, note)
+        }
+    }
+
+    /// Check for entry signal (BUY)
+    fn check_entry_signal(&self, bar: &Bar, indicators: &IndicatorValues) -> Option<Signal> {
+        if !self.entry_condition(bar, indicators) {
+            return None;
+        }
+
+        // Debounce: the condition must have held for the last N bars
+        let confirming = self.strategy.entry_confirmation_bars > 1;
+        if confirming && self.entry_streak < self.strategy.entry_confirmation_bars {
+            return None;
         }
 
         // Calculate signal strength (lower RSI = stronger signal)
-        let strength = 1.0 - (indicators.rsi / self.params.rsi_oversold);
+        let strength = 1.0 - (indicators.rsi / self.strategy.rsi_oversold);
 
         Some(Signal {
             timestamp: bar.timestamp,
             signal_type: SignalType::Buy,
-            symbol: self.params.symbol.clone(),
+            symbol: self.strategy.symbol.clone(),
             price: bar.close,
             rsi: indicators.rsi,
-            reason: format!(
-                "RSI({:.1}) <= {:.0}, price below VWAP",
-                indicators.rsi, self.params.rsi_oversold
-            ),
+            reason: match self.strategy.vwap_mode.describe() {
+                Some(vwap) => format!(
+                    "RSI({:.1}) <= {:.0}, {}",
+                    indicators.rsi, self.strategy.rsi_oversold, vwap
+                ),
+                None => format!(
+                    "RSI({:.1}) <= {:.0}",
+                    indicators.rsi, self.strategy.rsi_oversold
+                ),
+            },
             strength,
             vwap: indicators.vwap.or(bar.vwap),
             sma: indicators.sma,
+            order_id: None,
+            confirmation_bars: confirming.then_some(self.entry_streak),
         })
     }
 
@@ -111,24 +426,46 @@ impl SignalGenerator {
         position: Option<&Position>,
     ) -> Option<Signal> {
         // RSI overbought - take profit
-        if indicators.rsi >= self.params.rsi_overbought {
+        let (overbought, threshold) = self.take_profit_threshold(bar, indicators, position);
+        if indicators.rsi >= overbought {
             return Some(Signal {
                 timestamp: bar.timestamp,
                 signal_type: SignalType::Sell,
-                symbol: self.params.symbol.clone(),
+                symbol: self.strategy.symbol.clone(),
                 price: bar.close,
                 rsi: indicators.rsi,
                 reason: format!(
-                    "RSI({:.1}) >= {:.0} - take profit",
-                    indicators.rsi, self.params.rsi_overbought
+                    "RSI({:.1}) >= {:.0} - take profit{}",
+                    indicators.rsi, overbought, threshold
                 ),
-                strength: (indicators.rsi - self.params.rsi_overbought)
-                    / (100.0 - self.params.rsi_overbought),
+                strength: (indicators.rsi - overbought) / (100.0 - overbought),
                 vwap: indicators.vwap.or(bar.vwap),
                 sma: indicators.sma,
+                order_id: None,
+                confirmation_bars: None,
             });
         }
 
+        // Time exit
+        if let (Some(max_days), Some(pos)) = (self.strategy.max_holding_days, position) {
+            let held_days = (bar.timestamp - pos.entry_date).num_days();
+            if held_days >= max_days as i64 {
+                return Some(Signal {
+                    timestamp: bar.timestamp,
+                    signal_type: SignalType::Sell,
+                    symbol: self.strategy.symbol.clone(),
+                    price: bar.close,
+                    rsi: indicators.rsi,
+                    reason: format!("Time exit after {} days (max {})", held_days, max_days),
+                    strength: 1.0,
+                    vwap: indicators.vwap.or(bar.vwap),
+                    sma: indicators.sma,
+                    order_id: None,
+                    confirmation_bars: None,
+                });
+            }
+        }
+
         // Stop loss check
         if let Some(pos) = position {
             if let Some(stop_price) = pos.stop_loss_price {
@@ -136,7 +473,7 @@ impl SignalGenerator {
                     return Some(Signal {
                         timestamp: bar.timestamp,
                         signal_type: SignalType::Sell,
-                        symbol: self.params.symbol.clone(),
+                        symbol: self.strategy.symbol.clone(),
                         price: bar.close,
                         rsi: indicators.rsi,
                         reason: format!(
@@ -146,6 +483,8 @@ impl SignalGenerator {
                         strength: 1.0,
                         vwap: indicators.vwap.or(bar.vwap),
                         sma: indicators.sma,
+                        order_id: None,
+                        confirmation_bars: None,
                     });
                 }
             }
@@ -156,23 +495,25 @@ impl SignalGenerator {
 
     /// Check for hedge entry signal (when RSI is extremely overbought)
     fn check_hedge_entry_signal(&self, bar: &Bar, indicators: &IndicatorValues) -> Option<Signal> {
-        if indicators.rsi >= self.params.rsi_overbought_short {
-            let strength =
-                (indicators.rsi - self.params.rsi_overbought_short) / (100.0 - self.params.rsi_overbought_short);
+        let (overbought, _) = self.inverse_thresholds();
+        if !indicators.reseeding && indicators.rsi >= overbought {
+            let strength = (indicators.rsi - overbought) / (100.0 - overbought);
 
             return Some(Signal {
                 timestamp: bar.timestamp,
                 signal_type: SignalType::HedgeBuy,
-                symbol: self.params.inverse_symbol.clone(),
+                symbol: self.hedge.inverse_symbol.clone(),
                 price: bar.close,
                 rsi: indicators.rsi,
                 reason: format!(
                     "RSI({:.1}) >= {:.0} - hedge with {}",
-                    indicators.rsi, self.params.rsi_overbought_short, self.params.inverse_symbol
+                    indicators.rsi, overbought, self.hedge.inverse_symbol
                 ),
                 strength,
                 vwap: indicators.vwap.or(bar.vwap),
                 sma: indicators.sma,
+                order_id: None,
+                confirmation_bars: None,
             });
         }
 
@@ -181,20 +522,23 @@ impl SignalGenerator {
 
     /// Check for hedge exit signal
     fn check_hedge_exit_signal(&self, bar: &Bar, indicators: &IndicatorValues) -> Option<Signal> {
-        if indicators.rsi <= self.params.rsi_oversold_short {
+        let (_, oversold) = self.inverse_thresholds();
+        if indicators.rsi <= oversold {
             return Some(Signal {
                 timestamp: bar.timestamp,
                 signal_type: SignalType::HedgeSell,
-                symbol: self.params.inverse_symbol.clone(),
+                symbol: self.hedge.inverse_symbol.clone(),
                 price: bar.close,
                 rsi: indicators.rsi,
                 reason: format!(
                     "RSI({:.1}) <= {:.0} - close hedge",
-                    indicators.rsi, self.params.rsi_oversold_short
+                    indicators.rsi, oversold
                 ),
-                strength: 1.0 - (indicators.rsi / self.params.rsi_oversold_short),
+                strength: 1.0 - (indicators.rsi / oversold),
                 vwap: indicators.vwap.or(bar.vwap),
                 sma: indicators.sma,
+                order_id: None,
+                confirmation_bars: None,
             });
         }
 
@@ -202,10 +546,47 @@ impl SignalGenerator {
     }
 }
 
+/// Entry checks recorded on a bar
+#[derive(Debug, Default)]
+struct Checklist {
+    /// Run and record every check rather than stop at the first failure
+    explain: bool,
+    failed: bool,
+    checks: Vec<FilterCheck>,
+}
+
+impl Checklist {
+    /// Record a check, a disabled one passing; whether to carry on
+    fn record(
+        &mut self,
+        name: &str,
+        enabled: bool,
+        passed: bool,
+        detail: impl FnOnce() -> String,
+    ) -> bool {
+        let passed = passed || !enabled;
+        self.failed |= !passed;
+        if self.explain {
+            self.checks.push(FilterCheck {
+                name: name.to_string(),
+                enabled,
+                passed,
+                detail: if enabled {
+                    detail()
+                } else {
+                    "disabled".to_string()
+                },
+            });
+        }
+        passed || self.explain
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::TimeZone;
+    use chrono::{TimeZone, Utc};
+    use common::{BacktestParameters, ExitDecay, PositionSide, ProfitAdjustedExit, VwapMode};
 
     fn make_bar(close: f64) -> Bar {
         Bar {
@@ -216,6 +597,26 @@ mod tests {
             close,
             volume: 1000000,
             vwap: Some(close + 0.1),
+            halted: false,
+        }
+    }
+
+    fn long_at(entry: f64) -> Position {
+        Position {
+            symbol: "TQQQ".to_string(),
+            quantity: 10.0,
+            avg_entry_price: entry,
+            entry_date: make_bar(50.0).timestamp,
+            current_price: entry,
+            side: PositionSide::Long,
+            stop_loss_price: None,
+            initial_stop_price: None,
+            stop_spec: None,
+            entry_reason: String::new(),
+            entry_rsi: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            entry_commission: 0.0,
         }
     }
 
@@ -228,10 +629,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_no_signal_while_warming_up() {
+        let params = BacktestParameters::default().without_vwap_filter();
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        let bar = make_bar(50.0);
+        let warming = |rsi: f64| IndicatorValues {
+            warming_up: true,
+            ..make_indicators(rsi, 48.0)
+        };
+
+        assert!(generator
+            .generate(&bar, &warming(25.0), false, None, false)
+            .is_none());
+        let position = long_at(40.0);
+        assert!(generator
+            .generate(&bar, &warming(80.0), true, Some(&position), false)
+            .is_none());
+        let checks = generator.explain_entry(&bar, &warming(25.0));
+        assert!(!checks[0].passed);
+        assert_eq!(checks[0].detail, "warming up");
+    }
+
     #[test]
     fn test_buy_signal() {
         let params = BacktestParameters::default().without_vwap_filter();
-        let generator = SignalGenerator::new(&params);
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
 
         let bar = make_bar(50.0);
         let indicators = make_indicators(25.0, 48.0); // RSI < 30, price > SMA
@@ -247,7 +670,7 @@ mod tests {
     #[test]
     fn test_no_buy_when_rsi_high() {
         let params = BacktestParameters::default();
-        let generator = SignalGenerator::new(&params);
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
 
         let bar = make_bar(50.0);
         let indicators = make_indicators(50.0, 48.0); // RSI > 30
@@ -260,7 +683,7 @@ mod tests {
     #[test]
     fn test_sell_signal_rsi_overbought() {
         let params = BacktestParameters::default();
-        let generator = SignalGenerator::new(&params);
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
 
         let bar = make_bar(55.0);
         let indicators = make_indicators(80.0, 48.0); // RSI > 75
@@ -272,10 +695,234 @@ mod tests {
         assert_eq!(s.signal_type, SignalType::Sell);
     }
 
+    #[test]
+    fn test_time_exit() {
+        use chrono::Duration;
+
+        let params = BacktestParameters::default().with_max_holding_days(5);
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+
+        let bar = make_bar(50.0);
+        let indicators = make_indicators(50.0, 48.0);
+        let mut position = Position {
+            symbol: "TQQQ".to_string(),
+            quantity: 10.0,
+            avg_entry_price: 50.0,
+            entry_date: bar.timestamp - Duration::days(4),
+            current_price: 50.0,
+            side: PositionSide::Long,
+            stop_loss_price: None,
+            initial_stop_price: None,
+            stop_spec: None,
+            entry_reason: String::new(),
+            entry_rsi: None,
+            size_multiplier: None,
+            entry_order_id: None,
+            entry_commission: 0.0,
+        };
+
+        let signal = generator.generate(&bar, &indicators, true, Some(&position), false);
+        assert!(signal.is_none());
+
+        position.entry_date = bar.timestamp - Duration::days(5);
+        let signal = generator
+            .generate(&bar, &indicators, true, Some(&position), false)
+            .unwrap();
+        assert_eq!(signal.signal_type, SignalType::Sell);
+        assert!(signal.reason.contains("Time exit"));
+    }
+
+    #[test]
+    fn test_momentum_filter() {
+        let mut params = BacktestParameters::default().without_vwap_filter();
+        params.strategy.momentum_filter_enabled = true;
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        let bar = make_bar(50.0);
+        let with_rank = |rank: Option<f64>| IndicatorValues {
+            roc_percentile: rank,
+            ..make_indicators(25.0, 48.0)
+        };
+
+        assert!(generator
+            .generate(&bar, &with_rank(Some(5.0)), false, None, false)
+            .is_none());
+        assert!(generator
+            .generate(&bar, &with_rank(Some(50.0)), false, None, false)
+            .is_some());
+        // Still warming up: the filter stays out of the way
+        assert!(generator
+            .generate(&bar, &with_rank(None), false, None, false)
+            .is_some());
+    }
+
+    #[test]
+    fn test_roc_floor() {
+        let mut params = BacktestParameters::default().without_vwap_filter();
+        let bar = make_bar(50.0);
+        let with_roc = |roc: Option<f64>| IndicatorValues {
+            lookback_roc: roc,
+            ..make_indicators(25.0, 48.0)
+        };
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        assert!(generator
+            .generate(&bar, &with_roc(Some(-30.0)), false, None, false)
+            .is_some());
+
+        params.strategy.momentum_min_pct = Some(-15.0);
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        assert!(generator
+            .generate(&bar, &with_roc(Some(-30.0)), false, None, false)
+            .is_none());
+        assert!(generator
+            .generate(&bar, &with_roc(Some(-15.0)), false, None, false)
+            .is_none());
+        assert!(generator
+            .generate(&bar, &with_roc(Some(-5.0)), false, None, false)
+            .is_some());
+        assert!(generator
+            .generate(&bar, &with_roc(None), false, None, false)
+            .is_some());
+    }
+
+    #[test]
+    fn test_adx_filter() {
+        let mut params = BacktestParameters::default().without_vwap_filter();
+        let bar = make_bar(50.0);
+        let with_adx = |adx: Option<f64>| IndicatorValues {
+            adx,
+            ..make_indicators(25.0, 48.0)
+        };
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        assert!(generator
+            .generate(&bar, &with_adx(Some(40.0)), false, None, false)
+            .is_some());
+
+        params.strategy.adx_filter_enabled = true;
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        // Trending: a dip is no reversion setup
+        assert!(generator
+            .generate(&bar, &with_adx(Some(40.0)), false, None, false)
+            .is_none());
+        assert!(generator
+            .generate(&bar, &with_adx(Some(15.0)), false, None, false)
+            .is_some());
+        assert!(generator
+            .generate(&bar, &with_adx(None), false, None, false)
+            .is_some());
+    }
+
+    #[test]
+    fn test_obv_filter() {
+        let mut params = BacktestParameters::default().without_vwap_filter();
+        params.strategy.obv_filter_enabled = true;
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        let bar = make_bar(50.0);
+        let with_slope = |obv_slope: Option<f64>| IndicatorValues {
+            obv_slope,
+            ..make_indicators(25.0, 48.0)
+        };
+
+        assert!(generator
+            .generate(&bar, &with_slope(Some(-50.0)), false, None, false)
+            .is_none());
+        assert!(generator
+            .generate(&bar, &with_slope(Some(0.0)), false, None, false)
+            .is_none());
+        assert!(generator
+            .generate(&bar, &with_slope(Some(50.0)), false, None, false)
+            .is_some());
+        assert!(generator
+            .generate(&bar, &with_slope(None), false, None, false)
+            .is_some());
+    }
+
+    #[test]
+    fn test_anchored_vwap_filter() {
+        let mut params = BacktestParameters::default().without_vwap_filter();
+        params.strategy.anchored_vwap_filter_enabled = true;
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        let bar = make_bar(50.0);
+        let with_anchored = |anchored_vwap: Option<f64>| IndicatorValues {
+            anchored_vwap,
+            ..make_indicators(25.0, 48.0)
+        };
+
+        assert!(generator
+            .generate(&bar, &with_anchored(Some(51.0)), false, None, false)
+            .is_none());
+        assert!(generator
+            .generate(&bar, &with_anchored(Some(50.0)), false, None, false)
+            .is_none());
+        assert!(generator
+            .generate(&bar, &with_anchored(Some(49.0)), false, None, false)
+            .is_some());
+        assert!(generator
+            .generate(&bar, &with_anchored(None), false, None, false)
+            .is_some());
+        let checks = generator.explain_entry(&bar, &with_anchored(Some(51.0)));
+        let anchored = checks.iter().find(|c| c.name == "anchored_vwap").unwrap();
+        assert_eq!(anchored.detail, "close 50.00 vs monthly VWAP 51.00");
+    }
+
+    #[test]
+    fn test_keltner_filter() {
+        let mut params = BacktestParameters::default().without_vwap_filter();
+        params.strategy.keltner_filter_enabled = true;
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        let bar = make_bar(50.0);
+        let with_lower = |keltner_lower: Option<f64>| IndicatorValues {
+            keltner_lower,
+            ..make_indicators(25.0, 48.0)
+        };
+
+        assert!(generator
+            .generate(&bar, &with_lower(Some(49.0)), false, None, false)
+            .is_none());
+        assert!(generator
+            .generate(&bar, &with_lower(Some(50.0)), false, None, false)
+            .is_some());
+        assert!(generator
+            .generate(&bar, &with_lower(None), false, None, false)
+            .is_some());
+        let checks = generator.explain_entry(&bar, &with_lower(Some(49.0)));
+        let keltner = checks.iter().find(|c| c.name == "keltner").unwrap();
+        assert!(keltner.enabled && !keltner.passed);
+    }
+
+    #[test]
+    fn test_vwap_modes() {
+        // VWAP 50.0; closes below, at and above it
+        let buys = |mode: VwapMode| -> Vec<bool> {
+            let mut params = BacktestParameters::default();
+            params.strategy.vwap_mode = mode;
+            let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+            [49.0, 50.0, 51.0]
+                .iter()
+                .map(|&close| {
+                    let bar = Bar {
+                        vwap: Some(50.0),
+                        ..make_bar(close)
+                    };
+                    generator
+                        .generate(&bar, &make_indicators(25.0, 40.0), false, None, false)
+                        .is_some()
+                })
+                .collect()
+        };
+
+        // At VWAP is neither below nor above
+        assert_eq!(buys(VwapMode::Below), vec![true, false, false]);
+        assert_eq!(buys(VwapMode::Above), vec![false, false, true]);
+        // ±2% of 50 is [49, 51], bounds included
+        assert_eq!(buys(VwapMode::WithinBandPct(2.0)), vec![true, true, true]);
+        assert_eq!(buys(VwapMode::WithinBandPct(1.0)), vec![false, true, false]);
+        assert_eq!(buys(VwapMode::Disabled), vec![true, true, true]);
+    }
+
     #[test]
     fn test_hedge_signal() {
         let params = BacktestParameters::default();
-        let generator = SignalGenerator::new(&params);
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
 
         let bar = make_bar(55.0);
         let indicators = make_indicators(92.0, 48.0); // RSI > 90
@@ -287,4 +934,118 @@ mod tests {
         assert_eq!(s.signal_type, SignalType::HedgeBuy);
         assert_eq!(s.symbol, "SQQQ");
     }
+
+    #[test]
+    fn test_direction_modes() {
+        let bar = make_bar(50.0);
+        let signal = |mode: DirectionMode, rsi: f64, sma: f64, hedged: bool| {
+            let mut params = BacktestParameters::default()
+                .without_vwap_filter()
+                .without_short()
+                .with_direction_mode(mode);
+            params.strategy.sma_filter_enabled = false;
+            params.hedge.auto_flip_on_regime = sma > 50.0;
+            SignalGenerator::new(&params.strategy, &params.hedge)
+                .generate(&bar, &make_indicators(rsi, sma), false, None, hedged)
+                .map(|s| s.signal_type)
+        };
+
+        // Inverse side on the mirrored long levels, without short_enabled
+        let inverse = DirectionMode::LongInverseOnly;
+        assert_eq!(
+            signal(inverse, 80.0, 48.0, false),
+            Some(SignalType::HedgeBuy)
+        );
+        assert_eq!(signal(inverse, 25.0, 48.0, false), None);
+        assert_eq!(
+            signal(inverse, 25.0, 48.0, true),
+            Some(SignalType::HedgeSell)
+        );
+        assert_eq!(
+            signal(DirectionMode::Both, 25.0, 48.0, false),
+            Some(SignalType::Buy)
+        );
+        assert_eq!(
+            signal(DirectionMode::Both, 80.0, 48.0, false),
+            Some(SignalType::HedgeBuy)
+        );
+        assert_eq!(signal(DirectionMode::LongPrimary, 80.0, 48.0, false), None);
+
+        // Auto flip: below the SMA the long entry becomes an inverse one
+        let primary = DirectionMode::LongPrimary;
+        assert_eq!(signal(primary, 25.0, 52.0, false), None);
+        assert_eq!(
+            signal(primary, 80.0, 52.0, false),
+            Some(SignalType::HedgeBuy)
+        );
+    }
+
+    #[test]
+    fn test_profit_adjusted_exit() {
+        let mut params = BacktestParameters::default();
+        params.strategy.profit_adjusted_exit = Some(ProfitAdjustedExit {
+            profit_trigger_pct: 5.0,
+            reduced_overbought: 65.0,
+        });
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+
+        // Same closes and RSI path; one long bought 10% lower than the other
+        let rsi_path = [60.0, 68.0, 72.0, 78.0];
+        let exit = |entry: f64| {
+            let position = long_at(entry);
+            let bar = make_bar(50.0);
+            rsi_path.iter().enumerate().find_map(|(i, &rsi)| {
+                let indicators = make_indicators(rsi, 48.0);
+                generator
+                    .generate(&bar, &indicators, true, Some(&position), false)
+                    .map(|s| (i, s.reason))
+            })
+        };
+
+        let (bar, reason) = exit(45.0).unwrap();
+        assert_eq!(bar, 1);
+        assert_eq!(
+            reason,
+            "RSI(68.0) >= 65 - take profit (reduced threshold: up 11.1% > 5.0%)"
+        );
+        let (bar, reason) = exit(50.0).unwrap();
+        assert_eq!(bar, 3);
+        assert_eq!(
+            reason,
+            "RSI(78.0) >= 75 - take profit (full threshold: +0.0% <= 5.0%)"
+        );
+    }
+
+    #[test]
+    fn test_exit_threshold_decay() {
+        let mut params = BacktestParameters::default();
+        params.strategy.exit_threshold_decay = Some(ExitDecay {
+            per_bar_decay: 2.0,
+            floor: 60.0,
+        });
+        let generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        let position = long_at(50.0);
+        let exit = |rsi: f64, bars_held: usize| {
+            let indicators = IndicatorValues {
+                bars_held: Some(bars_held),
+                ..make_indicators(rsi, 48.0)
+            };
+            generator
+                .generate(&make_bar(50.0), &indicators, true, Some(&position), false)
+                .map(|s| s.reason)
+        };
+
+        // 75 less 2 a bar: 71 after 2 bars, floored at 60 after 8
+        assert_eq!(exit(60.0, 2), None);
+        assert_eq!(
+            exit(71.0, 2).unwrap(),
+            "RSI(71.0) >= 71 - take profit (decayed from 75 after 2 bars)"
+        );
+        assert_eq!(exit(59.0, 8), None);
+        assert_eq!(
+            exit(60.0, 8).unwrap(),
+            "RSI(60.0) >= 60 - take profit (decayed from 75 after 8 bars)"
+        );
+        assert_eq!(exit(74.0, 0), None);
+    }
 }
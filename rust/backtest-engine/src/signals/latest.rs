@@ -0,0 +1,196 @@
+//! What the strategy would do on the latest bar, without a backtest
+//!
+//! [`SignalGenerator::evaluate_latest`] computes the indicators over the
+//! history the engine would, replays the entry confirmation streak, and
+//! generates the final bar's signal for a position state the caller
+//! supplies, along with every entry check. Meant for alerting on today's
+//! data rather than for simulation: no sizing, costs or orders.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use common::{
+    BacktestError, BacktestParameters, Bar, DirectionMode, IndicatorSnapshot, Position,
+    PositionSide, Result, Signal,
+};
+
+use super::generator::{FilterCheck, SignalGenerator};
+use crate::analysis::replay::indicators_for;
+use crate::engine::LIQUIDITY_AVG_PERIOD;
+use crate::indicators::{calculate_avg_volume, IndicatorSeries, IndicatorValues};
+
+/// Holdings assumed on the latest bar
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PositionState {
+    #[default]
+    Flat,
+    /// A long position, its stop set from the latest bar's ATR
+    Long {
+        entry_price: f64,
+        entry_date: DateTime<Utc>,
+    },
+    /// A hedge (or inverse) position and no long
+    Hedged,
+}
+
+/// The strategy's verdict on the latest bar
+#[derive(Debug, Clone, Serialize)]
+pub struct LatestEvaluation {
+    pub timestamp: DateTime<Utc>,
+    pub close: f64,
+    pub state: PositionState,
+    pub direction_mode: DirectionMode,
+    /// Exit, hedge or entry signal the bar produces under `state`
+    pub signal: Option<Signal>,
+    pub indicators: IndicatorSnapshot,
+    /// Every entry check on the bar, whether or not `state` allows an entry
+    pub entry_filters: Vec<FilterCheck>,
+}
+
+impl SignalGenerator {
+    /// Evaluate the strategy on the last of `bars` holding `state`
+    ///
+    /// # Errors
+    /// InsufficientData when `bars` do not cover the warmup
+    pub fn evaluate_latest(
+        bars: &[Bar],
+        params: &BacktestParameters,
+        state: PositionState,
+    ) -> Result<LatestEvaluation> {
+        let warmup = params.warmup_bars();
+        if bars.len() <= warmup {
+            return Err(BacktestError::InsufficientData {
+                required: warmup + 1,
+                actual: bars.len(),
+            });
+        }
+
+        let series = indicators_for(bars, params);
+        let volumes: Vec<u64> = bars.iter().map(|b| b.volume).collect();
+        let avg_volumes = calculate_avg_volume(&volumes, LIQUIDITY_AVG_PERIOD);
+
+        // The engine observes from the first bar after the warmup
+        let mut generator = SignalGenerator::new(&params.strategy, &params.hedge);
+        for i in warmup..bars.len() {
+            let values = values_at(bars, &series, &avg_volumes, i);
+            generator.observe(&bars[i], &values);
+        }
+
+        let last = bars.len() - 1;
+        let bar = &bars[last];
+        let mut values = values_at(bars, &series, &avg_volumes, last);
+        let position = match state {
+            PositionState::Long {
+                entry_price,
+                entry_date,
+            } => {
+                let stop = params
+                    .risk
+                    .stop_loss
+                    .stop_price(entry_price, series.atr[last]);
+                let entry = bars.partition_point(|b| b.timestamp < entry_date);
+                values.bars_held = Some(last.saturating_sub(entry));
+                Some(Position {
+                    symbol: params.strategy.symbol.clone(),
+                    quantity: 1.0,
+                    avg_entry_price: entry_price,
+                    entry_date,
+                    current_price: bar.close,
+                    side: PositionSide::Long,
+                    stop_loss_price: stop,
+                    initial_stop_price: stop,
+                    stop_spec: None,
+                    entry_reason: String::new(),
+                    entry_rsi: None,
+                    size_multiplier: None,
+                    entry_order_id: None,
+                    entry_commission: 0.0,
+                })
+            }
+            PositionState::Flat | PositionState::Hedged => None,
+        };
+        let hedged = state == PositionState::Hedged;
+        let signal =
+            generator.generate(bar, &values, position.is_some(), position.as_ref(), hedged);
+
+        Ok(LatestEvaluation {
+            timestamp: bar.timestamp,
+            close: bar.close,
+            state,
+            direction_mode: generator.direction_mode(bar, &values),
+            signal,
+            indicators: values.snapshot(bar.volume),
+            entry_filters: generator.explain_entry(bar, &values),
+        })
+    }
+}
+
+/// Indicator values of bar `i` as the engine sees them
+fn values_at(
+    bars: &[Bar],
+    series: &IndicatorSeries,
+    avg_volumes: &[Option<f64>],
+    i: usize,
+) -> IndicatorValues {
+    let mut values = series.get(i);
+    values.vwap = bars[i].vwap.or(values.vwap);
+    values.avg_volume = avg_volumes[i];
+    if i > 0 {
+        values.prev_high = Some(bars[i - 1].high);
+        values.prev_low = Some(bars[i - 1].low);
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_bars_with_rsi_pattern;
+    use common::SignalType;
+
+    #[test]
+    fn test_oversold_latest_bar_buys() {
+        let bars = generate_bars_with_rsi_pattern(60, 100.0, &[59], &[]);
+        // SMA(1) keeps the trend filter from rejecting the dip
+        let params = BacktestParameters::default()
+            .without_vwap_filter()
+            .with_sma_period(1);
+
+        let eval = SignalGenerator::evaluate_latest(&bars, &params, PositionState::Flat).unwrap();
+        assert_eq!(eval.timestamp, bars[59].timestamp);
+        let signal = eval.signal.expect("buy on the oversold day");
+        assert_eq!(signal.signal_type, SignalType::Buy);
+
+        let names: Vec<&str> = eval.entry_filters.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "rsi_oversold",
+                "vwap",
+                "sma_trend",
+                "anchored_vwap",
+                "momentum",
+                "roc_floor",
+                "adx",
+                "obv",
+                "bollinger",
+                "keltner"
+            ]
+        );
+        assert!(eval.entry_filters.iter().all(|c| c.passed));
+        assert!(!eval.entry_filters[1].enabled);
+        assert!(eval.entry_filters[0].detail.starts_with("RSI"));
+
+        // Already long: no second entry, though the checks still pass
+        let long = PositionState::Long {
+            entry_price: bars[50].close,
+            entry_date: bars[50].timestamp,
+        };
+        let eval = SignalGenerator::evaluate_latest(&bars, &params, long).unwrap();
+        assert!(eval.signal.is_none_or(|s| s.signal_type != SignalType::Buy));
+        assert!(
+            SignalGenerator::evaluate_latest(&bars[..5], &params, PositionState::Flat).is_err()
+        );
+    }
+}
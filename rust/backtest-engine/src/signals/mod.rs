@@ -1,3 +1,5 @@
 pub mod generator;
+pub mod latest;
 
-pub use generator::SignalGenerator;
+pub use generator::{FilterCheck, SignalGenerator};
+pub use latest::{LatestEvaluation, PositionState};
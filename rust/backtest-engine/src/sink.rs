@@ -0,0 +1,337 @@
+//! Streaming run output for runs too long to hold in memory
+//!
+//! With a spill directory ([`BacktestEngine::with_spill_dir`]) the engine
+//! writes trades, equity points, signals and fills to a [`ResultSink`] as it
+//! produces them, one bar at a time, and keeps only running aggregates: the
+//! [`StreamingMetrics`] of each trade scope and the heat and net exposure
+//! figures. The result
+//! then carries empty lists and a [`SpillSummary`] pointing at the files.
+//!
+//! [`JsonlSink`] is the one sink: a JSON object per line in
+//! [`TRADES_FILE`], [`EQUITY_FILE`], [`SIGNALS_FILE`] and [`FILLS_FILE`].
+//! Only the last `lookback_trades` trades stay behind for adaptive sizing.
+//!
+//! [`BacktestEngine::with_spill_dir`]: crate::BacktestEngine::with_spill_dir
+
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use common::{BacktestError, Fill, PerformanceMetrics, Result, Signal, SpillSummary, Trade};
+
+use crate::metrics::{largest_either_way, net_exposure_pct, StreamingMetrics};
+use crate::output::{refuse_existing, Overwrite};
+
+pub const TRADES_FILE: &str = "trades.jsonl";
+pub const EQUITY_FILE: &str = "equity.jsonl";
+pub const SIGNALS_FILE: &str = "signals.jsonl";
+pub const FILLS_FILE: &str = "fills.jsonl";
+
+/// One line of [`EQUITY_FILE`]: the equity, drawdown, heat and net
+/// exposure curves' points at one bar
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EquityRecord {
+    pub timestamp: DateTime<Utc>,
+    pub equity: f64,
+    /// Below the running peak (%)
+    pub drawdown_pct: f64,
+    pub heat_pct: f64,
+    /// In dollars of the primary symbol
+    #[serde(default)]
+    pub net_exposure: f64,
+}
+
+/// Destination of a run's records, in the order the run produces them
+pub trait ResultSink {
+    fn trade(&mut self, trade: &Trade) -> Result<()>;
+    fn equity(&mut self, record: &EquityRecord) -> Result<()>;
+    fn signal(&mut self, signal: &Signal) -> Result<()>;
+    fn fill(&mut self, fill: &Fill) -> Result<()>;
+    fn flush(&mut self) -> Result<()>;
+}
+
+/// JSON lines files in one directory
+pub struct JsonlSink {
+    trades: BufWriter<File>,
+    equity: BufWriter<File>,
+    signals: BufWriter<File>,
+    fills: BufWriter<File>,
+}
+
+impl JsonlSink {
+    /// Create the files in `dir`, creating it as needed
+    ///
+    /// # Errors
+    /// OutputError naming the first file already in `dir` unless `overwrite`
+    /// is [`Overwrite::Force`], before any file is touched
+    pub fn create(dir: &Path, overwrite: Overwrite) -> Result<Self> {
+        Self::check(dir, overwrite)?;
+        fs::create_dir_all(dir)?;
+        let open = |name: &str| -> Result<BufWriter<File>> {
+            Ok(BufWriter::new(File::create(dir.join(name))?))
+        };
+        Ok(Self {
+            trades: open(TRADES_FILE)?,
+            equity: open(EQUITY_FILE)?,
+            signals: open(SIGNALS_FILE)?,
+            fills: open(FILLS_FILE)?,
+        })
+    }
+
+    /// Whether [`JsonlSink::create`] may write into `dir`
+    pub fn check(dir: &Path, overwrite: Overwrite) -> Result<()> {
+        let paths = [TRADES_FILE, EQUITY_FILE, SIGNALS_FILE, FILLS_FILE].map(|name| dir.join(name));
+        refuse_existing(&paths, overwrite)
+    }
+}
+
+fn write_line<T: Serialize>(out: &mut BufWriter<File>, record: &T) -> Result<()> {
+    serde_json::to_writer(&mut *out, record)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+impl ResultSink for JsonlSink {
+    fn trade(&mut self, trade: &Trade) -> Result<()> {
+        write_line(&mut self.trades, trade)
+    }
+
+    fn equity(&mut self, record: &EquityRecord) -> Result<()> {
+        write_line(&mut self.equity, record)
+    }
+
+    fn signal(&mut self, signal: &Signal) -> Result<()> {
+        write_line(&mut self.signals, signal)
+    }
+
+    fn fill(&mut self, fill: &Fill) -> Result<()> {
+        write_line(&mut self.fills, fill)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        for out in [
+            &mut self.trades,
+            &mut self.equity,
+            &mut self.signals,
+            &mut self.fills,
+        ] {
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Read one of a spill directory's files back a record at a time
+pub fn read_records<T: DeserializeOwned>(path: &Path) -> Result<impl Iterator<Item = Result<T>>> {
+    let file = File::open(path)
+        .map_err(|e| BacktestError::DataLoadError(format!("{}: {}", path.display(), e)))?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map(|line| Ok(serde_json::from_str(&line?)?)))
+}
+
+/// The trades of a spilled run, in exit order
+pub fn read_trades(summary: &SpillSummary) -> Result<impl Iterator<Item = Result<Trade>>> {
+    read_records(&summary.dir.join(TRADES_FILE))
+}
+
+pub fn read_equity(summary: &SpillSummary) -> Result<impl Iterator<Item = Result<EquityRecord>>> {
+    read_records(&summary.dir.join(EQUITY_FILE))
+}
+
+/// A run's sink with the aggregates kept in place of its records
+pub(crate) struct Spill {
+    sink: Box<dyn ResultSink>,
+    summary: SpillSummary,
+    /// First write error; later records are dropped
+    error: Option<String>,
+    peak: Option<f64>,
+    all: StreamingMetrics,
+    main: StreamingMetrics,
+    hedge: StreamingMetrics,
+    hedge_trades: u64,
+    /// Heat points in the metrics: count, sum and maximum
+    heat: (u64, f64, f64),
+    /// Net exposure (% of equity) of the same points: sum and largest
+    /// either way
+    net_exposure: (f64, f64),
+}
+
+/// Aggregates of a finished spill
+pub(crate) struct Spilled {
+    pub summary: SpillSummary,
+    pub error: Option<String>,
+    pub combined: PerformanceMetrics,
+    pub main: PerformanceMetrics,
+    pub hedge: PerformanceMetrics,
+    pub hedge_trades: u64,
+}
+
+impl Spill {
+    pub(crate) fn create(dir: &Path, initial_capital: f64, overwrite: Overwrite) -> Result<Self> {
+        Ok(Self {
+            sink: Box::new(JsonlSink::create(dir, overwrite)?),
+            summary: SpillSummary {
+                dir: PathBuf::from(dir),
+                ..SpillSummary::default()
+            },
+            error: None,
+            peak: None,
+            all: StreamingMetrics::new(initial_capital),
+            main: StreamingMetrics::new(initial_capital),
+            hedge: StreamingMetrics::new(initial_capital),
+            hedge_trades: 0,
+            heat: (0, 0.0, 0.0),
+            net_exposure: (0.0, 0.0),
+        })
+    }
+
+    fn write(&mut self, write: impl FnOnce(&mut dyn ResultSink) -> Result<()>) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+        match write(self.sink.as_mut()) {
+            Ok(()) => true,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                false
+            }
+        }
+    }
+
+    /// Record one equity point; `measured` points also enter the metrics
+    pub(crate) fn record_point(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        equity: f64,
+        heat_pct: f64,
+        net_exposure: f64,
+        measured: bool,
+    ) {
+        let peak = self.peak.map_or(equity, |peak| peak.max(equity));
+        self.peak = Some(peak);
+        let drawdown_pct = if peak > 0.0 {
+            (peak - equity) / peak * 100.0
+        } else {
+            0.0
+        };
+        let record = EquityRecord {
+            timestamp,
+            equity,
+            drawdown_pct,
+            heat_pct,
+            net_exposure,
+        };
+        if self.write(|sink| sink.equity(&record)) {
+            self.summary.equity_points += 1;
+        }
+
+        if measured {
+            for metrics in [&mut self.all, &mut self.main, &mut self.hedge] {
+                metrics.push_equity(timestamp, equity);
+            }
+            self.heat.0 += 1;
+            self.heat.1 += heat_pct;
+            self.heat.2 = self.heat.2.max(heat_pct);
+            let pct = net_exposure_pct(net_exposure, equity);
+            self.net_exposure.0 += pct;
+            self.net_exposure.1 = largest_either_way(self.net_exposure.1, pct);
+        }
+    }
+
+    /// Record closed trades with the cost and overnight gap of each
+    pub(crate) fn record_trades(
+        &mut self,
+        trades: Vec<Trade>,
+        mut costs: impl FnMut(&Trade) -> (f64, Option<f64>),
+    ) {
+        for trade in trades {
+            if self.write(|sink| sink.trade(&trade)) {
+                self.summary.trades += 1;
+            }
+            let (cost, gap) = costs(&trade);
+            self.all.push_trade(&trade, cost, gap);
+            if trade.is_hedge() {
+                self.hedge_trades += 1;
+                self.hedge.push_trade(&trade, cost, gap);
+            } else {
+                self.main.push_trade(&trade, cost, gap);
+            }
+        }
+    }
+
+    pub(crate) fn record_audit(&mut self, signals: Vec<Signal>, fills: Vec<Fill>) {
+        for signal in signals {
+            if self.write(|sink| sink.signal(&signal)) {
+                self.summary.signals += 1;
+            }
+        }
+        for fill in fills {
+            if self.write(|sink| sink.fill(&fill)) {
+                self.summary.fills += 1;
+            }
+        }
+    }
+
+    pub(crate) fn finish(mut self) -> Spilled {
+        self.write(|sink| sink.flush());
+        let (points, sum, max) = self.heat;
+        let (exposure_sum, exposure_max) = self.net_exposure;
+        let metrics = |streamed: &StreamingMetrics| {
+            let mut metrics = streamed.finish();
+            if points > 0 {
+                metrics.max_heat_pct = max;
+                metrics.avg_heat_pct = sum / points as f64;
+                metrics.max_net_exposure_pct = exposure_max;
+                metrics.avg_net_exposure_pct = exposure_sum / points as f64;
+            }
+            metrics
+        };
+        Spilled {
+            combined: metrics(&self.all),
+            main: metrics(&self.main),
+            hedge: metrics(&self.hedge),
+            hedge_trades: self.hedge_trades,
+            summary: self.summary,
+            error: self.error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_jsonl_round_trip() {
+        let dir = std::env::temp_dir().join(format!("spill_round_trip_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut spill = Spill::create(&dir, 1000.0, Overwrite::Refuse).unwrap();
+        let day = |d: u32| Utc.with_ymd_and_hms(2024, 1, d, 16, 0, 0).unwrap();
+        for (d, equity) in [(2, 1000.0), (3, 1100.0), (4, 990.0)] {
+            spill.record_point(day(d), equity, 10.0, equity / 2.0, true);
+        }
+        let spilled = spill.finish();
+        assert_eq!(spilled.summary.equity_points, 3);
+        assert!(spilled.error.is_none());
+
+        let records: Vec<EquityRecord> = read_equity(&spilled.summary)
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].equity, 990.0);
+        assert!((records[2].drawdown_pct - 10.0).abs() < 1e-9);
+        assert!((spilled.combined.max_drawdown - 10.0).abs() < 1e-9);
+        assert_eq!(spilled.combined.avg_heat_pct, 10.0);
+        assert_eq!(spilled.combined.max_net_exposure_pct, 50.0);
+        assert_eq!(records[1].net_exposure, 550.0);
+        assert_eq!(read_trades(&spilled.summary).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
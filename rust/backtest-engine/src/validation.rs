@@ -0,0 +1,329 @@
+//! Time-series cross-validation
+//!
+//! Folds are contiguous index ranges in time order. An embargo of bars is
+//! left out of training on both sides of each test segment so indicator
+//! warmup and open positions cannot leak between training and test data.
+//! Each test segment is backtested on its own, with the indicator warmup
+//! taken from the bars just before it.
+
+use std::ops::Range;
+
+use serde::Serialize;
+
+use common::{BacktestError, BacktestParameters, Bar, MetricField, PerformanceMetrics, Result};
+
+use crate::engine::BacktestEngine;
+use crate::optimizer::score::capped;
+
+/// Metrics averaged across folds by [`summarize`]
+pub const CV_METRICS: [MetricField; 6] = [
+    MetricField::TotalReturnPct,
+    MetricField::SharpeRatio,
+    MetricField::MaxDrawdown,
+    MetricField::WinRate,
+    MetricField::ProfitFactor,
+    MetricField::TotalTrades,
+];
+
+/// How training data is laid out around the test segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitMode {
+    /// Train on everything before the test segment (less the embargo); the
+    /// test segments cover the bars after the first `min_train + embargo`
+    Expanding,
+    /// Test segments partition all bars; train on everything outside the
+    /// test segment and the embargo on either side of it
+    PurgedKFold,
+}
+
+/// One train/test split of a bar slice
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Fold {
+    /// Training bars: one range when expanding, up to two when purged
+    pub train: Vec<Range<usize>>,
+    pub test: Range<usize>,
+}
+
+impl Fold {
+    pub fn train_len(&self) -> usize {
+        self.train.iter().map(|r| r.len()).sum()
+    }
+}
+
+/// Splits a bar slice into cross-validation folds
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeriesSplit {
+    pub folds: usize,
+    pub mode: SplitMode,
+    /// Bars dropped from training next to each test segment
+    pub embargo: usize,
+    /// Fewest training bars any fold may have
+    pub min_train: usize,
+}
+
+impl TimeSeriesSplit {
+    /// `folds` expanding-window folds without embargo or training minimum
+    pub fn new(folds: usize) -> Self {
+        Self {
+            folds,
+            mode: SplitMode::Expanding,
+            embargo: 0,
+            min_train: 0,
+        }
+    }
+
+    pub fn purged(mut self) -> Self {
+        self.mode = SplitMode::PurgedKFold;
+        self
+    }
+
+    pub fn with_embargo(mut self, bars: usize) -> Self {
+        self.embargo = bars;
+        self
+    }
+
+    pub fn with_min_train(mut self, bars: usize) -> Self {
+        self.min_train = bars;
+        self
+    }
+
+    /// Folds over `len` bars, in time order of their test segments
+    pub fn split(&self, len: usize) -> Result<Vec<Fold>> {
+        if self.folds == 0 {
+            return Err(BacktestError::InvalidParameter(
+                "cross-validation needs at least one fold".to_string(),
+            ));
+        }
+
+        let start = match self.mode {
+            SplitMode::Expanding => self.min_train + self.embargo,
+            SplitMode::PurgedKFold => 0,
+        };
+        let size = len.saturating_sub(start) / self.folds;
+        if size == 0 {
+            return Err(BacktestError::InsufficientData {
+                required: start + self.folds,
+                actual: len,
+            });
+        }
+
+        let mut folds = Vec::with_capacity(self.folds);
+        for k in 0..self.folds {
+            let test_start = start + k * size;
+            let test_end = if k + 1 == self.folds {
+                len
+            } else {
+                test_start + size
+            };
+            let before = 0..test_start.saturating_sub(self.embargo);
+            let after = (test_end + self.embargo).min(len)..len;
+
+            let train: Vec<Range<usize>> = match self.mode {
+                SplitMode::Expanding => vec![before],
+                SplitMode::PurgedKFold => [before, after]
+                    .into_iter()
+                    .filter(|r| !r.is_empty())
+                    .collect(),
+            };
+            let fold = Fold {
+                train,
+                test: test_start..test_end,
+            };
+            if fold.train_len() < self.min_train {
+                return Err(BacktestError::InsufficientData {
+                    required: self.min_train,
+                    actual: fold.train_len(),
+                });
+            }
+            folds.push(fold);
+        }
+        Ok(folds)
+    }
+}
+
+/// Backtest of one test segment
+#[derive(Debug, Clone, Serialize)]
+pub struct FoldResult {
+    pub fold: usize,
+    pub train: Vec<Range<usize>>,
+    pub test: Range<usize>,
+    /// Bars before the test segment used only to warm up indicators
+    pub warmup_bars: usize,
+    pub metrics: PerformanceMetrics,
+    pub final_equity: f64,
+}
+
+/// Mean and sample standard deviation of a metric across folds
+#[derive(Debug, Clone, Serialize)]
+pub struct MetricStats {
+    pub metric: MetricField,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+/// Backtest every test segment of `splitter` over `bars`.
+///
+/// Each segment runs on its own slice, prefixed with the `warmup_bars()`
+/// bars before it so trading starts on the segment's first bar. A segment
+/// at the very start of the data (purged fold 0) has no prior bars and
+/// spends its own first bars on warmup instead.
+pub fn run_cv(
+    params: &BacktestParameters,
+    bars: &[Bar],
+    splitter: &TimeSeriesSplit,
+) -> Result<Vec<FoldResult>> {
+    let warmup = params.warmup_bars();
+    let engine = BacktestEngine::new(params.clone());
+
+    let results = splitter
+        .split(bars.len())?
+        .into_iter()
+        .enumerate()
+        .map(|(fold, split)| {
+            let from = split.test.start.saturating_sub(warmup);
+            let result = engine.run(&bars[from..split.test.end], None);
+            FoldResult {
+                fold,
+                warmup_bars: split.test.start - from,
+                train: split.train,
+                test: split.test,
+                metrics: result.metrics,
+                final_equity: result.final_equity,
+            }
+        })
+        .collect();
+    Ok(results)
+}
+
+/// Mean and spread of [`CV_METRICS`] across fold results; infinite values
+/// (profit factor of a fold without losers) are capped as in scoring
+pub fn summarize(folds: &[FoldResult]) -> Vec<MetricStats> {
+    CV_METRICS
+        .iter()
+        .map(|&metric| {
+            let values: Vec<f64> = folds
+                .iter()
+                .map(|f| capped(f.metrics.get(metric)))
+                .collect();
+            let n = values.len() as f64;
+            let mean = if values.is_empty() {
+                0.0
+            } else {
+                values.iter().sum::<f64>() / n
+            };
+            let std_dev = if values.len() < 2 {
+                0.0
+            } else {
+                (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt()
+            };
+            MetricStats {
+                metric,
+                mean,
+                std_dev,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::generate_synthetic_bars_seeded;
+
+    fn assert_disjoint_and_embargoed(folds: &[Fold], embargo: usize) {
+        for pair in folds.windows(2) {
+            assert_eq!(pair[0].test.end, pair[1].test.start);
+        }
+        for fold in folds {
+            for train in &fold.train {
+                let gap = if train.end <= fold.test.start {
+                    fold.test.start - train.end
+                } else {
+                    train.start - fold.test.end
+                };
+                assert!(
+                    gap >= embargo,
+                    "{:?} within {} of {:?}",
+                    train,
+                    embargo,
+                    fold.test
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_expanding_folds() {
+        let folds = TimeSeriesSplit::new(5)
+            .with_embargo(20)
+            .with_min_train(100)
+            .split(623)
+            .unwrap();
+
+        assert_eq!(folds.len(), 5);
+        assert_disjoint_and_embargoed(&folds, 20);
+        // Tests cover everything after the first training window and embargo
+        assert_eq!(folds[0].test.start, 120);
+        assert_eq!(folds[4].test.end, 623);
+        assert_eq!(folds[0].train, vec![0..100]);
+        assert!(folds
+            .iter()
+            .all(|f| f.train.len() == 1 && f.train[0].start == 0));
+        assert!(folds
+            .windows(2)
+            .all(|w| w[0].train_len() < w[1].train_len()));
+    }
+
+    #[test]
+    fn test_purged_folds() {
+        let splitter = TimeSeriesSplit::new(4).purged().with_embargo(10);
+        let folds = splitter.split(400).unwrap();
+
+        assert_disjoint_and_embargoed(&folds, 10);
+        assert_eq!(folds[0].test, 0..100);
+        assert_eq!(folds[3].test.end, 400);
+        // Inner folds train on both sides of the embargoed test segment
+        assert_eq!(folds[0].train, vec![110..400]);
+        assert_eq!(folds[1].train, vec![0..90, 210..400]);
+        assert_eq!(folds[3].train, vec![0..290]);
+
+        assert!(splitter.clone().with_min_train(300).split(400).is_err());
+        assert!(TimeSeriesSplit::new(5).split(4).is_err());
+        assert!(TimeSeriesSplit::new(0).split(400).is_err());
+    }
+
+    #[test]
+    fn test_run_cv_warms_up_from_prior_bars() {
+        let bars = generate_synthetic_bars_seeded(500, 50.0, 7);
+        let params = BacktestParameters::default();
+        let splitter = TimeSeriesSplit::new(4).with_embargo(20).with_min_train(80);
+
+        let results = run_cv(&params, &bars, &splitter).unwrap();
+        assert_eq!(results.len(), 4);
+        assert!(results
+            .iter()
+            .all(|r| r.warmup_bars == params.warmup_bars()));
+
+        // Each fold matches a standalone run on its warmup-prefixed slice
+        let fold = &results[2];
+        let standalone = BacktestEngine::new(params.clone()).run(
+            &bars[fold.test.start - fold.warmup_bars..fold.test.end],
+            None,
+        );
+        assert_eq!(fold.final_equity, standalone.final_equity);
+        assert_eq!(
+            standalone.equity_curve[0].0,
+            bars[fold.test.start].timestamp
+        );
+
+        let summary = summarize(&results);
+        let sharpe = summary
+            .iter()
+            .find(|s| s.metric == MetricField::SharpeRatio)
+            .unwrap();
+        let mean = results.iter().map(|r| r.metrics.sharpe_ratio).sum::<f64>() / 4.0;
+        assert!((sharpe.mean - mean).abs() < 1e-12);
+        assert!(sharpe.std_dev >= 0.0);
+    }
+}
@@ -0,0 +1,106 @@
+//! Exit codes and JSON error objects of the CLI, one failure class each
+
+use assert_cmd::Command;
+use common::exit_code;
+use serde_json::Value;
+
+fn cli() -> Command {
+    Command::cargo_bin("backtest-engine").unwrap()
+}
+
+/// Run with `args`, check the exit code and return the `error` object
+/// printed on stdout
+fn json_error(args: &[&str], code: u8) -> Value {
+    let output = cli()
+        .args(args)
+        .assert()
+        .code(code as i32)
+        .get_output()
+        .clone();
+    let mut value: Value = serde_json::from_slice(&output.stdout)
+        .unwrap_or_else(|e| panic!("stdout is not JSON ({}): {:?}", e, output.stdout));
+    let error = value["error"].take();
+    assert!(error["message"].as_str().is_some_and(|m| !m.is_empty()));
+    assert!(error["details"].is_object());
+    error
+}
+
+#[test]
+fn unreadable_data_file_is_a_data_error() {
+    let error = json_error(&["--data-file", "no/such/bars.csv"], exit_code::DATA);
+    assert_eq!(error["kind"], "data_load_error");
+}
+
+#[test]
+fn invalid_parameters_are_parameter_errors() {
+    let error = json_error(
+        &["--rsi-oversold", "80", "--rsi-overbought", "70"],
+        exit_code::PARAMETERS,
+    );
+    assert_eq!(error["kind"], "invalid_parameter");
+
+    // Malformed arguments never reach the engine but share the class
+    cli()
+        .args(["--range", "2024-01-01"])
+        .assert()
+        .code(exit_code::PARAMETERS as i32);
+    cli()
+        .args(["export-features", "--out", "features.parquet"])
+        .assert()
+        .code(exit_code::PARAMETERS as i32);
+}
+
+#[test]
+fn too_few_bars_is_insufficient_data() {
+    let error = json_error(&["--days", "5"], exit_code::INSUFFICIENT_DATA);
+    assert_eq!(error["kind"], "insufficient_data");
+    assert_eq!(error["details"]["actual"], 5);
+    assert_eq!(error["details"]["required"], 21);
+}
+
+#[test]
+fn text_output_reports_errors_on_stderr() {
+    let output = cli()
+        .args(["--days", "5", "--output", "text"])
+        .assert()
+        .code(exit_code::INSUFFICIENT_DATA as i32)
+        .get_output()
+        .clone();
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Error: Insufficient data"));
+}
+
+#[test]
+fn existing_output_is_an_output_error_without_force() {
+    let dir = std::env::temp_dir().join(format!("cli_output_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let journal = dir.join("journal.md");
+    let journal = journal.to_str().unwrap();
+    cli()
+        .args(["--days", "60", "--journal", journal])
+        .assert()
+        .success();
+
+    let error = json_error(&["--days", "60", "--journal", journal], exit_code::OUTPUT);
+    assert_eq!(error["kind"], "output_error");
+    assert_eq!(error["details"]["path"], journal);
+
+    cli()
+        .args(["--days", "60", "--journal", journal, "--force"])
+        .assert()
+        .success();
+
+    let spill = dir.join("spill");
+    let spill = spill.to_str().unwrap();
+    cli()
+        .args(["--days", "60", "--spill-dir", spill])
+        .assert()
+        .success();
+    let error = json_error(&["--days", "60", "--spill-dir", spill], exit_code::OUTPUT);
+    assert_eq!(error["kind"], "output_error");
+    cli()
+        .args(["--days", "60", "--spill-dir", spill, "--force"])
+        .assert()
+        .success();
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,182 @@
+//! Golden snapshots of `MetricsCalculator` output
+//!
+//! Each fixture in `tests/fixtures/metrics/` holds an equity curve and a
+//! trade list; its metrics are compared field by field with the snapshot of
+//! the same name in `tests/snapshots/metrics/`. Non-finite values are stored
+//! as the strings `"inf"`, `"-inf"` and `"NaN"` since JSON has no encoding
+//! for them.
+//!
+//! After an intended formula change, re-bless and review the diff:
+//!
+//! ```text
+//! BLESS=1 cargo test -p backtest-engine --test metrics_snapshots
+//! git diff tests/snapshots
+//! ```
+//!
+//! A fixture without a snapshot fails until it is blessed.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use backtest_engine::MetricsCalculator;
+use chrono::{DateTime, Duration, Utc};
+use common::{MetricField, PerformanceMetrics, Trade};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+/// Relative tolerance for finite values, to ride out last-bit differences
+/// in `powf` / `sqrt` across platforms
+const TOLERANCE: f64 = 1e-9;
+
+#[derive(Deserialize)]
+struct Fixture {
+    initial_capital: f64,
+    /// Timestamp of the first equity point; the rest follow daily
+    start: DateTime<Utc>,
+    equity: Vec<f64>,
+    trades: Vec<Trade>,
+}
+
+fn dir(kind: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(kind)
+        .join("metrics")
+}
+
+fn metrics_for(fixture: &Fixture) -> PerformanceMetrics {
+    let curve: Vec<(DateTime<Utc>, f64)> = fixture
+        .equity
+        .iter()
+        .enumerate()
+        .map(|(i, &e)| (fixture.start + Duration::days(i as i64), e))
+        .collect();
+    MetricsCalculator::calculate(&curve, &fixture.trades, fixture.initial_capital)
+}
+
+fn encode(value: f64) -> Value {
+    if value.is_nan() {
+        Value::from("NaN")
+    } else if value.is_infinite() {
+        Value::from(if value > 0.0 { "inf" } else { "-inf" })
+    } else {
+        Value::from(value)
+    }
+}
+
+/// Metrics as a JSON object keyed by field name. Serializing alone would
+/// turn non-finite floats into null, so numeric fields are re-read through
+/// [`MetricField`].
+fn snapshot_of(metrics: &PerformanceMetrics) -> Map<String, Value> {
+    let Value::Object(fields) = serde_json::to_value(metrics).unwrap() else {
+        panic!("metrics did not serialize to an object");
+    };
+    fields
+        .into_iter()
+        .map(|(name, raw)| {
+            let value = match serde_json::from_value::<MetricField>(Value::from(name.clone())) {
+                Ok(field) => encode(metrics.get(field)),
+                Err(_) => raw,
+            };
+            (name, value)
+        })
+        .collect()
+}
+
+/// Numbers match within tolerance, also inside nested objects and arrays
+fn matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => {
+            e.len() == a.len()
+                && e.iter()
+                    .all(|(name, value)| a.get(name).is_some_and(|v| matches(value, v)))
+        }
+        (Value::Array(e), Value::Array(a)) => {
+            e.len() == a.len() && e.iter().zip(a).all(|(e, a)| matches(e, a))
+        }
+        _ => match (expected.as_f64(), actual.as_f64()) {
+            (Some(e), Some(a)) => (e - a).abs() <= TOLERANCE * e.abs().max(1.0),
+            _ => expected == actual,
+        },
+    }
+}
+
+/// Field-by-field differences between a stored snapshot and fresh output
+fn diff(expected: &Map<String, Value>, actual: &Map<String, Value>) -> Vec<String> {
+    let mut diffs: Vec<String> = actual
+        .iter()
+        .filter_map(|(name, value)| match expected.get(name) {
+            Some(old) if matches(old, value) => None,
+            Some(old) => Some(format!("{}: {} -> {}", name, old, value)),
+            None => Some(format!("{}: new field = {}", name, value)),
+        })
+        .collect();
+    diffs.extend(
+        expected
+            .keys()
+            .filter(|name| !actual.contains_key(*name))
+            .map(|name| format!("{}: removed", name)),
+    );
+    diffs
+}
+
+#[test]
+fn metrics_match_snapshots() {
+    let bless = std::env::var_os("BLESS").is_some();
+    let mut fixtures: Vec<PathBuf> = fs::read_dir(dir("fixtures"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    fixtures.sort();
+    assert!(!fixtures.is_empty(), "no metric fixtures found");
+
+    let mut failures = Vec::new();
+    for path in &fixtures {
+        let name = path.file_name().unwrap();
+        let fixture: Fixture = serde_json::from_str(&fs::read_to_string(path).unwrap())
+            .unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        let actual = snapshot_of(&metrics_for(&fixture));
+        let snapshot_path = dir("snapshots").join(name);
+
+        if bless {
+            fs::create_dir_all(dir("snapshots")).unwrap();
+            let json = serde_json::to_string_pretty(&actual).unwrap();
+            fs::write(&snapshot_path, json + "\n").unwrap();
+            continue;
+        }
+
+        let Ok(stored) = fs::read_to_string(&snapshot_path) else {
+            failures.push(format!("{:?}: no snapshot (run with BLESS=1)", name));
+            continue;
+        };
+        let expected: Map<String, Value> = serde_json::from_str(&stored).unwrap();
+        for change in diff(&expected, &actual) {
+            failures.push(format!("{:?}: {}", name, change));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "metric snapshots changed (re-bless with BLESS=1 if intended):\n  {}",
+        failures.join("\n  ")
+    );
+}
+
+#[test]
+fn non_finite_values_survive_snapshots() {
+    let metrics = PerformanceMetrics {
+        profit_factor: f64::INFINITY,
+        sortino_ratio: f64::NAN,
+        ..Default::default()
+    };
+    let snapshot = snapshot_of(&metrics);
+
+    assert_eq!(snapshot["profit_factor"], Value::from("inf"));
+    assert_eq!(snapshot["sortino_ratio"], Value::from("NaN"));
+    assert_eq!(snapshot["sharpe_ratio"], Value::from(0.0));
+
+    let mut changed = snapshot.clone();
+    changed.insert("profit_factor".to_string(), Value::from(2.5));
+    assert_eq!(diff(&snapshot, &changed).len(), 1);
+}
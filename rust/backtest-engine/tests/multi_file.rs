@@ -0,0 +1,63 @@
+//! Multi-file input: two yearly-style files sharing one day, against the
+//! same series stored as a single file
+
+use std::path::{Path, PathBuf};
+
+use backtest_engine::{
+    load_file, load_files, load_files_with, BacktestEngine, BacktestParameters, Bar,
+};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/bars")
+        .join(name)
+}
+
+fn parts() -> Vec<PathBuf> {
+    vec![fixture("part1.csv"), fixture("part2.csv")]
+}
+
+#[test]
+fn overlapping_files_fail_by_default() {
+    let err = load_files(&parts()).unwrap_err().to_string();
+    assert!(
+        err.contains("part2.csv") && err.contains("overlap"),
+        "{}",
+        err
+    );
+
+    // Files in the wrong order overlap entirely
+    let mut reversed = parts();
+    reversed.reverse();
+    assert!(load_files(&reversed).is_err());
+}
+
+#[test]
+fn dedupe_merges_into_the_single_file_series() {
+    let (bars, files) = load_files_with(&parts(), true).unwrap();
+    let merged = load_file(&fixture("merged.csv")).unwrap();
+
+    let key = |b: &Bar| (b.timestamp, b.close, b.volume);
+    assert_eq!(
+        bars.iter().map(key).collect::<Vec<_>>(),
+        merged.iter().map(key).collect::<Vec<_>>()
+    );
+    assert_eq!(files[0].bars, 41);
+    assert_eq!((files[1].bars, files[1].overlapping), (39, 1));
+    assert!(bars.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+}
+
+#[test]
+fn merged_backtest_matches_single_file_run() {
+    let params = BacktestParameters::default().without_vwap_filter();
+    let (bars, _) = load_files_with(&parts(), true).unwrap();
+    let merged = load_file(&fixture("merged.csv")).unwrap();
+
+    let split = BacktestEngine::new(params.clone()).run(&bars, None);
+    let whole = BacktestEngine::new(params).run(&merged, None);
+
+    assert!(!whole.trades.is_empty());
+    assert_eq!(split.final_equity, whole.final_equity);
+    assert_eq!(split.trades.len(), whole.trades.len());
+    assert_eq!(split.equity_curve, whole.equity_curve);
+}
@@ -0,0 +1,142 @@
+//! Back-compat of the parameter layout
+//!
+//! `tests/fixtures/params/flat_v1.json` was written by the flat
+//! `BacktestParameters` that preceded the strategy / risk / hedge /
+//! execution split; it must keep loading unchanged. The golden run in
+//! `tests/snapshots/golden_run.json` pins a seeded backtest with those
+//! parameters so that regrouping fields cannot change results.
+//!
+//! Re-bless the golden run only after an intended behavior change:
+//!
+//! ```text
+//! BLESS=1 cargo test -p backtest-engine --test params_compat
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use backtest_engine::{generate_synthetic_bars_seeded, BacktestEngine};
+use common::config::compat::FlatBacktestParameters;
+use common::{BacktestParameters, StopSpec};
+use serde_json::Value;
+
+/// Relative tolerance for floats: the snapshot is parsed back from text,
+/// which can move the last bit
+const TOLERANCE: f64 = 1e-12;
+
+fn path(parts: &[&str]) -> PathBuf {
+    parts.iter().fold(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests"),
+        |p, part| p.join(part),
+    )
+}
+
+fn flat_fixture() -> String {
+    fs::read_to_string(path(&["fixtures", "params", "flat_v1.json"])).unwrap()
+}
+
+/// Panic at the first path where `actual` and `expected` differ
+fn assert_matches(at: &str, actual: &Value, expected: &Value) {
+    match (actual, expected) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let missing = Value::Null;
+                assert_matches(
+                    &format!("{}.{}", at, key),
+                    a.get(key).unwrap_or(&missing),
+                    b.get(key).unwrap_or(&missing),
+                );
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            assert_eq!(a.len(), b.len(), "{}: length", at);
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                assert_matches(&format!("{}[{}]", at, i), x, y);
+            }
+        }
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap(), b.as_f64().unwrap());
+            let scale = a.abs().max(b.abs()).max(1.0);
+            assert!((a - b).abs() <= TOLERANCE * scale, "{}: {} vs {}", at, a, b);
+        }
+        (a, b) => assert_eq!(a, b, "{}", at),
+    }
+}
+
+/// Every field of the fixture has its value in `actual`
+fn assert_fields_kept(actual: &Value, fixture: &Value) {
+    for (key, value) in fixture.as_object().unwrap() {
+        assert_eq!(actual.get(key), Some(value), "{}", key);
+    }
+}
+
+#[test]
+fn test_flat_config_still_loads() {
+    let fixture: Value = serde_json::from_str(&flat_fixture()).unwrap();
+    let params: BacktestParameters = serde_json::from_str(&flat_fixture()).unwrap();
+    params.validate().unwrap();
+
+    assert_eq!(params.strategy.rsi_oversold, 25.0);
+    assert_eq!(params.risk.stop_loss, StopSpec::Percent(0.08));
+    assert_eq!(params.hedge.short_stop_loss, StopSpec::Percent(0.04));
+    assert_eq!(params.execution.commission, 1.0);
+    assert_eq!(params.execution.simulation.latency_bars, 1);
+
+    // Serialized, the nested layout is the same flat document, plus any
+    // settings added since
+    assert_fields_kept(&serde_json::to_value(&params).unwrap(), &fixture);
+
+    let flat: FlatBacktestParameters = serde_json::from_str(&flat_fixture()).unwrap();
+    assert_eq!(flat.rsi_oversold, 25.0);
+    assert_eq!(serde_json::to_value(&flat).unwrap(), fixture);
+    let converted = BacktestParameters::from(flat.clone());
+    assert_fields_kept(&serde_json::to_value(&converted).unwrap(), &fixture);
+    let back = FlatBacktestParameters::from(converted);
+    assert_eq!(
+        serde_json::to_value(&back).unwrap(),
+        serde_json::to_value(&flat).unwrap()
+    );
+}
+
+#[test]
+fn test_golden_run_unchanged() {
+    let params: BacktestParameters = serde_json::from_str(&flat_fixture()).unwrap();
+    let bars = generate_synthetic_bars_seeded(400, 50.0, 42);
+    let hedge = generate_synthetic_bars_seeded(400, 30.0, 43);
+    let result = BacktestEngine::new(params)
+        .with_execution_seed(7)
+        .run(&bars, Some(&hedge));
+
+    let Value::Object(mut run) = serde_json::to_value(&result).unwrap() else {
+        panic!("result did not serialize to an object");
+    };
+    // Provenance and timing differ between runs; the per-bar curves follow
+    // from the fills and only bloat the snapshot
+    for key in [
+        "manifest",
+        "execution_time_ms",
+        "equity_curve",
+        "drawdown_curve",
+        "heat_curve",
+        "net_exposure_curve",
+    ] {
+        run.remove(key);
+    }
+    let run = Value::Object(run);
+
+    let snapshot = path(&["snapshots", "golden_run.json"]);
+    if std::env::var_os("BLESS").is_some() {
+        fs::write(
+            &snapshot,
+            serde_json::to_string_pretty(&run).unwrap() + "\n",
+        )
+        .unwrap();
+        return;
+    }
+    let expected: Value = serde_json::from_str(&fs::read_to_string(&snapshot).unwrap()).unwrap();
+    assert!(result.trades.len() > 5, "golden run barely trades");
+    assert_matches("result", &run, &expected);
+}
@@ -0,0 +1,66 @@
+//! Runs every shipped preset end-to-end on a deterministic fixture
+
+use backtest_engine::{BacktestEngine, Bar};
+use chrono::{Duration, TimeZone, Utc};
+use common::Preset;
+
+/// 400 daily bars of an uptrend with regular pullbacks, long enough for the
+/// 200-bar SMA of the Connors preset
+fn fixture_bars() -> Vec<Bar> {
+    let start = Utc.with_ymd_and_hms(2022, 1, 3, 16, 0, 0).unwrap();
+    (0..400)
+        .map(|i| {
+            let t = i as f64;
+            let close = 40.0 + t * 0.05 + (t * 0.7).sin() * 2.0 + (t * 0.13).cos() * 1.5;
+            Bar {
+                timestamp: start + Duration::days(i as i64),
+                open: close - 0.2,
+                high: close + 0.8,
+                low: close - 0.8,
+                close,
+                volume: 40_000_000,
+                vwap: Some(close + 0.3),
+                halted: false,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn every_preset_produces_a_non_degenerate_run() {
+    let bars = fixture_bars();
+
+    for preset in Preset::ALL {
+        let params = preset.parameters();
+        params.validate().unwrap();
+
+        let result = BacktestEngine::new(params.clone()).run(&bars, None);
+
+        assert!(
+            !result.equity_curve.is_empty(),
+            "preset {} produced an empty equity curve",
+            preset.name()
+        );
+        assert!(result.final_equity.is_finite() && result.final_equity > 0.0);
+        assert!(
+            result.metrics.total_trades > 0,
+            "preset {} never traded",
+            preset.name()
+        );
+        assert_eq!(result.initial_capital, params.initial_capital);
+    }
+}
+
+#[test]
+fn connors_classic_respects_time_exit() {
+    let bars = fixture_bars();
+    let result = BacktestEngine::new(Preset::ConnorsClassic.parameters()).run(&bars, None);
+
+    for trade in &result.trades {
+        assert!(
+            trade.holding_days <= 5,
+            "trade held {} days",
+            trade.holding_days
+        );
+    }
+}
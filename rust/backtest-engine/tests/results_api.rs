@@ -0,0 +1,104 @@
+//! The stable results API across result versions
+//!
+//! `tests/fixtures/results/result_v1.json` holds only the fields the first
+//! release wrote. A `ResultView` must read it, and read the same values
+//! once the result is rewritten in the current layout.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use backtest_engine::ResultView;
+use common::BacktestResult;
+
+fn fixture() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("results")
+        .join("result_v1.json")
+}
+
+/// Everything a dashboard reads, as one comparable value
+fn getters(view: &ResultView) -> Vec<String> {
+    let mut out = vec![format!(
+        "{} {} {} {} {} {} {} {} {} {} {} {}",
+        view.start_date(),
+        view.end_date(),
+        view.initial_capital(),
+        view.final_equity(),
+        view.total_return_pct(),
+        view.cagr(),
+        view.sharpe(),
+        view.sortino(),
+        view.max_drawdown(),
+        view.win_rate(),
+        view.profit_factor(),
+        view.trade_count(),
+    )];
+    out.extend(view.trades().map(|t| {
+        format!(
+            "{} {} {:?} {:?} {} {} {} {} {} {} {} {}",
+            t.entry_time(),
+            t.entry_price(),
+            t.exit_time(),
+            t.exit_price(),
+            t.quantity(),
+            t.pnl(),
+            t.pnl_pct(),
+            t.holding_days(),
+            t.is_open(),
+            t.is_short(),
+            t.entry_reason(),
+            t.exit_reason(),
+        )
+    }));
+    out.extend(
+        view.equity_points()
+            .map(|p| format!("{} {}", p.time, p.value)),
+    );
+    out.extend(
+        view.drawdown_points()
+            .map(|p| format!("{} {}", p.time, p.value)),
+    );
+    out
+}
+
+#[test]
+fn test_old_result_loads() {
+    let view = ResultView::from_json_file(&fixture()).unwrap();
+
+    assert_eq!(view.initial_capital(), 10_000.0);
+    assert_eq!(view.final_equity(), 10_150.0);
+    assert_eq!(view.sharpe(), 1.25);
+    assert_eq!(view.metric("profit_factor"), Some(2.5));
+    // Newer than the fixture, so its default
+    assert_eq!(view.metric("multi_bar_exits"), Some(0.0));
+
+    let trades: Vec<_> = view.trades().collect();
+    assert_eq!(trades.len(), 2);
+    assert_eq!(trades[0].entry_price(), 100.0);
+    assert_eq!(trades[0].exit_price(), Some(105.0));
+    assert_eq!(trades[1].pnl(), -100.0);
+    assert_eq!(trades[1].exit_reason(), "stop loss");
+    assert!(trades
+        .iter()
+        .all(|t| !t.is_open() && !t.is_short() && !t.is_hedge()));
+
+    assert_eq!(view.equity_points().count(), 9);
+    assert_eq!(view.equity_points().last().unwrap().value, 10_150.0);
+}
+
+#[test]
+fn test_current_layout_reads_the_same() {
+    let old = ResultView::from_json_file(&fixture()).unwrap();
+
+    let result: BacktestResult =
+        serde_json::from_str(&fs::read_to_string(fixture()).unwrap()).unwrap();
+    let path = std::env::temp_dir().join(format!("result_current_{}.json", std::process::id()));
+    fs::write(&path, serde_json::to_string_pretty(&result).unwrap()).unwrap();
+    let current = ResultView::from_json_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(getters(&current), getters(&old));
+    assert!(ResultView::from_json_file(Path::new("no/such/result.json")).is_err());
+}
@@ -1,5 +1,19 @@
+pub mod compat;
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
+use crate::error::{BacktestError, Result};
+use crate::types::{Position, PositionSide, Trade};
+
+pub use crate::indicators::Smoothing;
+
+/// ATR lookback behind the ATR stops and the volatility features; not a
+/// parameter
+pub const ATR_PERIOD: usize = 14;
+
 /// Realistic execution simulation settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RealisticExecutionConfig {
@@ -29,10 +43,17 @@ pub struct RealisticExecutionConfig {
     pub volume_participation_max_pct: f64,
     /// Enable partial fills when order exceeds volume limit
     pub partial_fill_enabled: bool,
+    /// What happens to the unfilled rest of a partially filled entry
+    #[serde(default)]
+    pub partial_fill_policy: PartialFillPolicy,
 
     // === Latency Simulation ===
     /// Number of bars to delay order execution (0 = same bar)
     pub latency_bars: usize,
+    /// Delay stop-loss exits by `latency_bars` as well: the stop triggers on
+    /// one bar's close and fills on a later bar
+    #[serde(default)]
+    pub stops_subject_to_latency: bool,
 
     // === Market Impact ===
     /// Enable market impact simulation for large orders
@@ -68,9 +89,11 @@ impl Default for RealisticExecutionConfig {
             volume_limit_enabled: true,
             volume_participation_max_pct: 0.02,
             partial_fill_enabled: true,
+            partial_fill_policy: PartialFillPolicy::AcceptPartial,
 
             // Latency: execute on same bar (0) or next bar (1)
             latency_bars: 0,
+            stops_subject_to_latency: false,
 
             // Market impact: 0.1% price impact per 1% volume
             market_impact_enabled: true,
@@ -111,75 +134,1311 @@ impl RealisticExecutionConfig {
     }
 }
 
-/// Backtest parameters
+/// What an entry does with the shares a volume-constrained fill left over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PartialFillPolicy {
+    /// Keep the smaller position; the remainder is dropped
+    #[default]
+    AcceptPartial,
+    /// Keep working the remainder on each of up to `max_bars` following
+    /// bars, adding every tranche filled to the position
+    WorkRemainder { max_bars: usize },
+    /// Treat a partial fill as a rejection
+    Cancel,
+}
+
+/// How stop losses are handled around halted (below `min_bar_volume`) bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HaltedStopPolicy {
+    /// Stops are not evaluated on halted bars; normal close-based checks
+    /// resume on the next tradable bar
+    #[default]
+    Skip,
+    /// After a halt, the stop is checked against the next tradable bar's open
+    /// and filled there if breached
+    NextOpen,
+}
+
+/// Which bracket exit fills when a single bar touches both the stop and the
+/// target. Bar data cannot tell which came first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SameBarExit {
+    /// Assume the stop was hit first (pessimistic)
+    #[default]
+    StopFirst,
+    /// Assume the target was hit first (optimistic)
+    TargetFirst,
+}
+
+/// What a long entry does while the inverse-ETF hedge is still open.
+/// Holding both nets the exposure out while paying costs on each leg.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConcurrentHedgePolicy {
+    /// Suppress the long entry until the hedge exits on its own
+    Block,
+    /// Close the hedge on the same bar, then enter the long
+    #[default]
+    CloseHedgeFirst,
+    /// Hold both positions at once
+    Allow,
+}
+
+impl std::str::FromStr for ConcurrentHedgePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "block" => Ok(Self::Block),
+            "close_hedge_first" => Ok(Self::CloseHedgeFirst),
+            "allow" => Ok(Self::Allow),
+            other => Err(format!(
+                "unknown hedge policy '{}' (expected block, close-hedge-first or allow)",
+                other
+            )),
+        }
+    }
+}
+
+/// Which instrument the RSI signal trades. The inverse side mirrors the
+/// long rules: RSI at or above `rsi_overbought` buys the inverse
+/// instrument and RSI at or below `rsi_oversold` sells it, sized and stopped
+/// as the hedge and filled on the inverse bars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectionMode {
+    /// Long the primary symbol; the inverse instrument is only a hedge
+    #[default]
+    LongPrimary,
+    /// Long the inverse instrument only; no primary-symbol entries
+    LongInverseOnly,
+    /// Long either side on its own signal
+    Both,
+}
+
+impl std::str::FromStr for DirectionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "long_primary" => Ok(Self::LongPrimary),
+            "long_inverse_only" | "inverse" => Ok(Self::LongInverseOnly),
+            "both" => Ok(Self::Both),
+            other => Err(format!(
+                "unknown direction mode '{}' (expected long-primary, long-inverse-only or both)",
+                other
+            )),
+        }
+    }
+}
+
+/// Which trades the headline `PerformanceMetrics` of a result cover. Many
+/// small hedge scratches would otherwise dilute the core strategy's stats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricsScope {
+    /// Main-symbol (long and short) trades only; hedges are reported apart
+    #[default]
+    Main,
+    /// Every trade, hedges included
+    Combined,
+}
+
+impl std::str::FromStr for MetricsScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "main" => Ok(Self::Main),
+            "combined" => Ok(Self::Combined),
+            other => Err(format!(
+                "unknown metrics scope '{}' (expected main or combined)",
+                other
+            )),
+        }
+    }
+}
+
+/// Where the close must sit relative to VWAP for a long entry
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VwapMode {
+    /// Close strictly below VWAP (buying the dip at a discount)
+    #[default]
+    Below,
+    /// Close strictly above VWAP (momentum variant)
+    Above,
+    /// Close within ± this many percent of VWAP, bounds included
+    WithinBandPct(f64),
+    /// No VWAP condition
+    Disabled,
+}
+
+impl VwapMode {
+    /// Whether an entry at `close` passes the filter. Bars without a VWAP
+    /// always pass.
+    pub fn accepts(self, close: f64, vwap: Option<f64>) -> bool {
+        let Some(vwap) = vwap else {
+            return true;
+        };
+        match self {
+            Self::Below => close < vwap,
+            Self::Above => close > vwap,
+            Self::WithinBandPct(pct) => (close - vwap).abs() <= vwap.abs() * pct / 100.0,
+            Self::Disabled => true,
+        }
+    }
+
+    /// Entry reason fragment, e.g. "price below VWAP"
+    pub fn describe(self) -> Option<String> {
+        match self {
+            Self::Below => Some("price below VWAP".to_string()),
+            Self::Above => Some("price above VWAP".to_string()),
+            Self::WithinBandPct(pct) => Some(format!("price within {}% of VWAP", pct)),
+            Self::Disabled => None,
+        }
+    }
+}
+
+impl std::str::FromStr for VwapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        if let Some(pct) = s.strip_prefix("band:") {
+            return pct
+                .parse()
+                .map(Self::WithinBandPct)
+                .map_err(|_| format!("invalid VWAP band '{}' (expected band:<pct>)", pct));
+        }
+        match s.as_str() {
+            "below" => Ok(Self::Below),
+            "above" => Ok(Self::Above),
+            "disabled" | "off" => Ok(Self::Disabled),
+            other => Err(format!(
+                "unknown VWAP mode '{}' (expected below, above, band:<pct> or disabled)",
+                other
+            )),
+        }
+    }
+}
+
+/// How long entries are sized
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizingMode {
+    /// `position_size_pct` of available cash, so size grows with equity
+    #[default]
+    CompoundPctEquity,
+    /// This many dollars per entry, clamped to available cash
+    FixedNotional(f64),
+    /// This many shares per entry; skipped when unaffordable
+    FixedShares(f64),
+}
+
+impl SizingMode {
+    /// Short label for reports, e.g. "fixed $5000 notional"
+    pub fn describe(self) -> String {
+        match self {
+            Self::CompoundPctEquity => "compounding % of equity".to_string(),
+            Self::FixedNotional(notional) => format!("fixed ${} notional", notional),
+            Self::FixedShares(shares) => format!("fixed {} shares", shares),
+        }
+    }
+}
+
+impl std::str::FromStr for SizingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        if let Some(notional) = s.strip_prefix("notional:") {
+            return notional
+                .parse()
+                .map(Self::FixedNotional)
+                .map_err(|_| format!("invalid notional '{}' (expected notional:<usd>)", notional));
+        }
+        if let Some(shares) = s.strip_prefix("shares:") {
+            return shares
+                .parse()
+                .map(Self::FixedShares)
+                .map_err(|_| format!("invalid share count '{}' (expected shares:<n>)", shares));
+        }
+        match s.as_str() {
+            "compound" => Ok(Self::CompoundPctEquity),
+            other => Err(format!(
+                "unknown sizing mode '{}' (expected compound, notional:<usd> or shares:<n>)",
+                other
+            )),
+        }
+    }
+}
+
+/// `vwap_mode` as stored in configs, also accepting the fields it replaced
+#[derive(Serialize, Deserialize)]
+struct VwapFields {
+    #[serde(default)]
+    vwap_mode: Option<VwapMode>,
+    /// Legacy: false disables the filter
+    #[serde(default, skip_serializing)]
+    vwap_filter_enabled: Option<bool>,
+    /// Legacy: the filter only applied while this was true
+    #[serde(default, skip_serializing)]
+    vwap_entry_below: Option<bool>,
+}
+
+/// Read `vwap_mode`, mapping the legacy booleans when present. They win
+/// over `vwap_mode` since parameter files are overlaid on serialized
+/// defaults, which always carry the new field.
+fn deserialize_vwap_mode<'de, D>(deserializer: D) -> std::result::Result<VwapMode, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let fields = VwapFields::deserialize(deserializer)?;
+    Ok(
+        match (fields.vwap_filter_enabled, fields.vwap_entry_below) {
+            (Some(false), _) | (_, Some(false)) => VwapMode::Disabled,
+            (Some(true), _) | (_, Some(true)) => VwapMode::Below,
+            (None, None) => fields.vwap_mode.unwrap_or_default(),
+        },
+    )
+}
+
+fn serialize_vwap_mode<S>(mode: &VwapMode, serializer: S) -> std::result::Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    VwapFields {
+        vwap_mode: Some(*mode),
+        vwap_filter_enabled: None,
+        vwap_entry_below: None,
+    }
+    .serialize(serializer)
+}
+
+impl std::str::FromStr for Smoothing {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "wilder" => Ok(Self::Wilder),
+            "simple" | "sma" => Ok(Self::Simple),
+            "ema" => Ok(Self::Ema),
+            other => Err(format!(
+                "unknown smoothing '{}' (expected wilder, simple or ema)",
+                other
+            )),
+        }
+    }
+}
+
+/// Entry and exit signal rules of the main symbol
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BacktestParameters {
-    // Symbol
+pub struct StrategyParams {
     pub symbol: String,
-    pub inverse_symbol: String,
-    // RSI parameters
     pub rsi_period: usize,
     pub rsi_oversold: f64,
     pub rsi_overbought: f64,
-    // SMA parameters
+    #[serde(default)]
+    pub rsi_smoothing: Smoothing,
+    /// Restart the RSI and ATR smoothing after a bar whose close-to-close
+    /// move exceeds this many percent; no entries until they re-seed
+    #[serde(default)]
+    pub rsi_reset_on_gap_pct: Option<f64>,
     pub sma_period: usize,
-    // Risk management
-    pub stop_loss_pct: f64,
-    pub position_size_pct: f64,
-    pub cash_reserve_pct: f64,
-    // Filters
-    pub vwap_filter_enabled: bool,
-    pub vwap_entry_below: bool,
+    /// Only enter while the close is at or above the SMA
+    #[serde(default = "default_sma_filter_enabled")]
+    pub sma_filter_enabled: bool,
+    /// Also require the SMA itself to be rising
+    #[serde(default)]
+    pub sma_slope_filter: Option<SmaSlopeFilter>,
+    /// Consecutive bars the full entry condition must hold before a buy
+    /// fires (1 buys on the first bar)
+    #[serde(default = "default_entry_confirmation_bars")]
+    pub entry_confirmation_bars: usize,
+    /// Time exit: close the position once held this many calendar days
+    #[serde(default)]
+    pub max_holding_days: Option<u32>,
+    /// Replaces `vwap_filter_enabled` / `vwap_entry_below`, which are still
+    /// read from older configs
+    #[serde(
+        flatten,
+        deserialize_with = "deserialize_vwap_mode",
+        serialize_with = "serialize_vwap_mode"
+    )]
+    pub vwap_mode: VwapMode,
+    /// Bars of the rolling VWAP the filter falls back on where bars carry
+    /// no VWAP of their own
+    #[serde(default = "default_vwap_window")]
+    pub vwap_window: usize,
     pub bb_filter_enabled: bool,
     pub bb_period: usize,
     pub bb_std_dev: f64,
     pub volume_filter_enabled: bool,
     pub volume_min_ratio: f64,
-    // Short/Hedge
+    /// Skip entries while the `momentum_roc_period`-bar rate of change ranks
+    /// below `momentum_min_percentile` (0-100) over the past year
+    #[serde(default)]
+    pub momentum_filter_enabled: bool,
+    #[serde(default = "default_momentum_roc_period")]
+    pub momentum_roc_period: usize,
+    #[serde(default = "default_momentum_min_percentile")]
+    pub momentum_min_percentile: f64,
+    /// Skip entries while the `momentum_lookback`-bar rate of change is at
+    /// or below `momentum_min_pct` (%), a market in free fall; no floor
+    /// when None
+    #[serde(default)]
+    pub momentum_min_pct: Option<f64>,
+    #[serde(default = "default_momentum_lookback")]
+    pub momentum_lookback: usize,
+    /// Skip entries while the 14-bar ADX is above `adx_max`, a trend too
+    /// strong to fade
+    #[serde(default)]
+    pub adx_filter_enabled: bool,
+    #[serde(default = "default_adx_max")]
+    pub adx_max: f64,
+    /// Enter only while On-Balance Volume slopes upward over the last 10
+    /// bars
+    #[serde(default)]
+    pub obv_filter_enabled: bool,
+    /// Enter only at or below the lower Keltner Channel (20-bar EMA, two
+    /// 10-bar ATRs wide), the ATR-based counterpart of `bb_filter_enabled`
+    #[serde(default)]
+    pub keltner_filter_enabled: bool,
+    /// Enter only above the VWAP anchored at the start of the month, a
+    /// regime filter alongside (or instead of) `sma_filter_enabled`
+    #[serde(default)]
+    pub anchored_vwap_filter_enabled: bool,
+    /// Window of the rolling close z-score offered to strategies (with log
+    /// returns); not computed when None
+    #[serde(default)]
+    pub zscore_window: Option<usize>,
+    /// Averaging for the ATR used by the edge filter and ATR-based features
+    #[serde(default)]
+    pub atr_smoothing: Smoothing,
+    /// Take profit at a lower RSI while the long is well in profit
+    #[serde(default)]
+    pub profit_adjusted_exit: Option<ProfitAdjustedExit>,
+    /// Take profit at a lower RSI the longer the long is held
+    #[serde(default)]
+    pub exit_threshold_decay: Option<ExitDecay>,
+}
+
+/// Minimum rise (%) of the SMA over the last `lookback_bars` bars for an
+/// entry; a falling SMA fails it even with the close above
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SmaSlopeFilter {
+    pub lookback_bars: usize,
+    pub min_slope_pct: f64,
+}
+
+/// RSI take-profit threshold that drops to `reduced_overbought` once the
+/// long's unrealized gain exceeds `profit_trigger_pct` (%)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProfitAdjustedExit {
+    pub profit_trigger_pct: f64,
+    pub reduced_overbought: f64,
+}
+
+/// RSI take-profit threshold lowered by `per_bar_decay` for each bar the
+/// long has been held, down to `floor`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExitDecay {
+    pub per_bar_decay: f64,
+    pub floor: f64,
+}
+
+impl ExitDecay {
+    /// Threshold after `bars_held` bars from `threshold`; never above it
+    pub fn threshold(&self, threshold: f64, bars_held: usize) -> f64 {
+        (threshold - self.per_bar_decay * bars_held as f64)
+            .max(self.floor)
+            .min(threshold)
+    }
+}
+
+/// Distance of a protective stop below the entry, resolved to a price when
+/// the position opens. Stored as a bare fraction for a percent stop, as
+/// `stop_loss_pct` always was, and as text otherwise (`"2atr"`, `"1.5usd"`).
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(try_from = "StopSpecRepr", into = "StopSpecRepr")]
+pub enum StopSpec {
+    /// This fraction of the entry price (0.05 = 5%)
+    Percent(f64),
+    /// This many ATRs of the instrument
+    AtrMultiple(f64),
+    /// This many dollars
+    Dollars(f64),
+    #[default]
+    None,
+}
+
+impl StopSpec {
+    /// A percent stop of `fraction`; 0 is no stop, as it was for
+    /// `stop_loss_pct`
+    pub fn from_pct(fraction: f64) -> Self {
+        if fraction == 0.0 {
+            Self::None
+        } else {
+            Self::Percent(fraction)
+        }
+    }
+
+    pub fn is_set(self) -> bool {
+        match self {
+            Self::Percent(v) | Self::AtrMultiple(v) | Self::Dollars(v) => v > 0.0,
+            Self::None => false,
+        }
+    }
+
+    /// Dollars per share between `entry` and the stop, given the
+    /// instrument's `atr`; 0 without a stop or an ATR to measure it by
+    pub fn distance(self, entry: f64, atr: f64) -> f64 {
+        let distance = match self {
+            Self::Percent(fraction) => entry * fraction,
+            Self::AtrMultiple(multiple) => multiple * atr,
+            Self::Dollars(dollars) => dollars,
+            Self::None => 0.0,
+        };
+        if distance.is_finite() && distance > 0.0 {
+            distance
+        } else {
+            0.0
+        }
+    }
+
+    /// Stop price of a long entered at `entry`, never below zero
+    pub fn stop_price(self, entry: f64, atr: f64) -> Option<f64> {
+        let distance = self.distance(entry, atr);
+        if distance <= 0.0 {
+            return None;
+        }
+        Some(match self {
+            Self::Percent(fraction) => entry * (1.0 - fraction),
+            _ => (entry - distance).max(0.0),
+        })
+    }
+
+    /// The fraction of a percent stop, 0 for no stop; None for the other
+    /// kinds, which the flat layout cannot express
+    pub fn percent(self) -> Option<f64> {
+        match self {
+            Self::Percent(fraction) => Some(fraction),
+            Self::None => Some(0.0),
+            _ => None,
+        }
+    }
+}
+
+impl From<f64> for StopSpec {
+    fn from(fraction: f64) -> Self {
+        Self::from_pct(fraction)
+    }
+}
+
+impl std::fmt::Display for StopSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Percent(fraction) => write!(f, "{}%", fraction * 100.0),
+            Self::AtrMultiple(multiple) => write!(f, "{}atr", multiple),
+            Self::Dollars(dollars) => write!(f, "{}usd", dollars),
+            Self::None => write!(f, "none"),
+        }
+    }
+}
+
+impl std::str::FromStr for StopSpec {
+    type Err = String;
+
+    /// `5%`, `2atr`, `1.5usd` (or `$1.5`), `none`, or a bare fraction
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        let amount = |text: &str| {
+            text.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite() && *v >= 0.0)
+        };
+        let parsed = if matches!(s.as_str(), "none" | "off") {
+            Some(Self::None)
+        } else if let Some(multiple) = s.strip_suffix("atr") {
+            amount(multiple).map(Self::AtrMultiple)
+        } else if let Some(dollars) = s.strip_suffix("usd").or_else(|| s.strip_prefix('$')) {
+            amount(dollars).map(Self::Dollars)
+        } else if let Some(pct) = s.strip_suffix('%') {
+            amount(pct).map(|pct| Self::from_pct(pct / 100.0))
+        } else {
+            amount(&s).map(Self::from_pct)
+        };
+        parsed.ok_or_else(|| {
+            format!(
+                "invalid stop '{}' (expected <pct>%, <n>atr, <n>usd, none or a fraction)",
+                s
+            )
+        })
+    }
+}
+
+/// `StopSpec` as stored in configs
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum StopSpecRepr {
+    Fraction(f64),
+    Text(String),
+}
+
+impl TryFrom<StopSpecRepr> for StopSpec {
+    type Error = String;
+
+    fn try_from(repr: StopSpecRepr) -> std::result::Result<Self, Self::Error> {
+        match repr {
+            StopSpecRepr::Fraction(fraction) => Ok(Self::from_pct(fraction)),
+            StopSpecRepr::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl From<StopSpec> for StopSpecRepr {
+    fn from(spec: StopSpec) -> Self {
+        match spec.percent() {
+            Some(fraction) => Self::Fraction(fraction),
+            None => Self::Text(spec.to_string()),
+        }
+    }
+}
+
+/// Position sizing, protective exits and entry limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskParams {
+    /// Stored as `stop_loss_pct`, named when stops were percent-only
+    #[serde(rename = "stop_loss_pct")]
+    pub stop_loss: StopSpec,
+    /// Close the long at every bar's close and reopen it at the next open
+    /// while no exit has fired, so it never carries an overnight gap
+    #[serde(default)]
+    pub overnight_flat: bool,
+    #[serde(default)]
+    pub take_profit_pct: Option<f64>,
+    pub position_size_pct: f64,
+    pub cash_reserve_pct: f64,
+    /// Long entry sizing; `position_size_pct` only applies when compounding
+    #[serde(default)]
+    pub sizing_mode: SizingMode,
+    /// Cap long entries at the execution volume participation limit applied
+    /// to the trailing average volume, instead of relying on fill truncation
+    #[serde(default)]
+    pub liquidity_aware_sizing: bool,
+    /// Skip entries whose expected move (`edge_atr_multiple` × ATR) is below
+    /// `min_expected_profit_pct` (%) plus the modeled round-trip cost
+    #[serde(default)]
+    pub edge_filter_enabled: bool,
+    #[serde(default = "default_edge_atr_multiple")]
+    pub edge_atr_multiple: f64,
+    #[serde(default = "default_min_expected_profit_pct")]
+    pub min_expected_profit_pct: f64,
+    /// Flag a bar as a volatility burst when its log return lies more than
+    /// this many standard deviations from the trailing returns' mean
+    #[serde(default = "default_anomaly_zscore")]
+    pub anomaly_zscore: f64,
+    /// Skip entries on a burst bar and the `anomaly_quarantine_bars` after it
+    #[serde(default)]
+    pub skip_entries_on_anomaly: bool,
+    #[serde(default)]
+    pub anomaly_quarantine_bars: usize,
+    /// On a burst bar, raise the long's stop to this fraction below the
+    /// close (never lowering it)
+    #[serde(default)]
+    pub tighten_stops_on_anomaly_pct: Option<f64>,
+    /// Skip entries on bars whose data quality score (0-1, see
+    /// `data_quality`) is below this (0 disables)
+    #[serde(default)]
+    pub min_data_quality: f64,
+    /// Also close open positions on low-quality bars
+    #[serde(default)]
+    pub close_on_low_data_quality: bool,
+    #[serde(default)]
+    pub max_trades_per_month: Option<u32>,
+    #[serde(default)]
+    pub max_new_positions_per_day: Option<u32>,
+    /// Bars that must pass after an entry fills before the same symbol can
+    /// be entered again; when set, at most one position opens per bar
+    #[serde(default)]
+    pub min_bars_between_entries: usize,
+    /// Portfolio heat cap (% of equity at risk to stops); entries are shrunk
+    /// to fit and skipped when not even one share fits
+    #[serde(default)]
+    pub max_portfolio_heat_pct: Option<f64>,
+    /// (drawdown %, size multiplier) pairs: while equity is more than the
+    /// threshold below its running peak, long entries are scaled by the
+    /// multiplier of the deepest threshold crossed
+    #[serde(default)]
+    pub drawdown_throttle: Option<Vec<(f64, f64)>>,
+    /// Scale long entries with the recent win rate (anti-martingale),
+    /// after every other sizing rule
+    #[serde(default)]
+    pub adaptive_sizing: Option<AdaptiveSizing>,
+    /// Pre-trade check: entries are shrunk to at most this notional ($)
+    #[serde(default)]
+    pub max_order_notional: Option<f64>,
+    /// Pre-trade check: entries are rejected while this many positions
+    /// (long, hedge and synthetic short) are open
+    #[serde(default)]
+    pub max_open_positions: Option<usize>,
+}
+
+/// Long entry size multiplier from the win rate of recent closed trades:
+/// `1 + sensitivity × (win_rate − 0.5)`, clamped to `[min_mult, max_mult]`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AdaptiveSizing {
+    /// Closed trades the win rate is taken over
+    pub lookback_trades: usize,
+    pub min_mult: f64,
+    pub max_mult: f64,
+    pub sensitivity: f64,
+}
+
+impl AdaptiveSizing {
+    /// Multiplier after the main-symbol trades in `closed`, oldest first.
+    /// Until `lookback_trades` have closed the empty slots count as half a
+    /// win, so sizing starts neutral.
+    pub fn multiplier(&self, closed: &[Trade]) -> f64 {
+        let recent: Vec<&Trade> = closed
+            .iter()
+            .filter(|t| !t.is_hedge())
+            .rev()
+            .take(self.lookback_trades)
+            .collect();
+        let wins = recent.iter().filter(|t| t.pnl > 0.0).count() as f64;
+        let unfilled = (self.lookback_trades - recent.len()) as f64;
+        let win_rate = (wins + 0.5 * unfilled) / self.lookback_trades as f64;
+        (1.0 + self.sensitivity * (win_rate - 0.5)).clamp(self.min_mult, self.max_mult)
+    }
+}
+
+/// Short side: inverse ETF hedge or direct short
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HedgeParams {
+    pub inverse_symbol: String,
     pub short_enabled: bool,
     pub use_inverse_etf: bool,
     pub rsi_overbought_short: f64,
     pub rsi_oversold_short: f64,
-    pub short_stop_loss_pct: f64,
+    /// Stop of the hedge, in the hedge's own price; an ATR stop scales the
+    /// main symbol's ATR to it
+    #[serde(rename = "short_stop_loss_pct")]
+    pub short_stop_loss: StopSpec,
     pub short_position_size_pct: f64,
-    // Backtest settings
-    pub initial_capital: f64,
+    #[serde(default)]
+    pub concurrent_hedge_policy: ConcurrentHedgePolicy,
+    /// On RSI at or above `rsi_overbought_short` while long, open the hedge
+    /// on the bar the long is sold instead of waiting a bar
+    #[serde(default)]
+    pub flip_to_hedge_on_extreme: bool,
+    /// On RSI at or below `rsi_oversold` while hedged, open the long on the
+    /// bar the hedge is closed
+    #[serde(default)]
+    pub flip_to_long_on_oversold: bool,
+    /// Bars after a long or hedge position change during which an entry on
+    /// the other side is suppressed (0 disables)
+    #[serde(default)]
+    pub signal_hysteresis_bars: usize,
+    /// RSI move since that change which lets the opposite entry through
+    #[serde(default = "default_hysteresis_rsi_delta")]
+    pub hysteresis_rsi_delta: f64,
+    /// Hedge without an inverse instrument: a cash-settled short on the
+    /// primary symbol paying `-leverage` times its return on the notional
+    #[serde(default)]
+    pub synthetic_short: bool,
+    #[serde(default = "default_synthetic_short_leverage")]
+    pub synthetic_short_leverage: f64,
+    /// Which side the signal trades
+    #[serde(default)]
+    pub direction_mode: DirectionMode,
+    /// Trade the inverse side only while the close is below the trend SMA,
+    /// and `direction_mode` otherwise
+    #[serde(default)]
+    pub auto_flip_on_regime: bool,
+    /// Leverage of each symbol to its index (3 for TQQQ and SQQQ), weighing
+    /// positions in the net exposure; symbols not listed count as 1x
+    #[serde(default = "default_leverage_factors")]
+    pub leverage_factors: BTreeMap<String, f64>,
+}
+
+impl HedgeParams {
+    /// Leverage factor of `symbol`, 1 when not listed
+    pub fn leverage_factor(&self, symbol: &str) -> f64 {
+        self.leverage_factors.get(symbol).copied().unwrap_or(1.0)
+    }
+
+    /// Whether the inverse instrument follows the mirrored long rules
+    /// rather than the hedge thresholds
+    pub fn inverse_primary(&self) -> bool {
+        self.direction_mode != DirectionMode::LongPrimary || self.auto_flip_on_regime
+    }
+}
+
+/// Trading costs and order handling
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionParams {
+    /// Enter with bracket orders: a limit entry at the signal close plus an
+    /// OCO stop (`stop_loss_pct`) and target (`take_profit_pct`)
+    #[serde(default)]
+    pub use_bracket_orders: bool,
+    #[serde(default)]
+    pub same_bar_exit: SameBarExit,
     pub commission: f64,
     pub slippage_pct: f64,
-    // Realistic execution simulation
+    /// Bars with volume below this are treated as halted: no orders execute
+    /// and pending orders roll forward (0 disables)
     #[serde(default)]
-    pub execution: RealisticExecutionConfig,
+    pub min_bar_volume: u64,
+    /// Median close range (min, max) plausible for the symbol; a median
+    /// outside it is reported as a likely price scale problem
+    #[serde(default = "default_sane_price_range")]
+    pub sane_price_range: (f64, f64),
+    #[serde(default)]
+    pub halted_stop_policy: HaltedStopPolicy,
+    /// Realistic execution simulation
+    #[serde(rename = "execution", default)]
+    pub simulation: RealisticExecutionConfig,
 }
 
-impl Default for BacktestParameters {
+/// Backtest parameters
+///
+/// Grouped by concern; every group is flattened, so configs are one flat
+/// document as before (see [`compat`] for the flat struct itself).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestParameters {
+    #[serde(flatten)]
+    pub strategy: StrategyParams,
+    #[serde(flatten)]
+    pub risk: RiskParams,
+    #[serde(flatten)]
+    pub hedge: HedgeParams,
+    #[serde(flatten)]
+    pub execution: ExecutionParams,
+    pub initial_capital: f64,
+    /// Keep cash and realized P&L in whole cents (see `Money`) instead of f64
+    #[serde(default)]
+    pub precise_accounting: bool,
+    /// UTC offset (minutes) used to derive calendar day/month boundaries
+    #[serde(default)]
+    pub display_utc_offset_minutes: i32,
+    /// Trades the headline metrics cover (all of them when the inverse side
+    /// is traded in its own right, see `DirectionMode`)
+    #[serde(default)]
+    pub metrics_scope: MetricsScope,
+    /// Only trade within these inclusive date ranges; positions are closed at
+    /// the last bar of each range (None trades every bar)
+    #[serde(default)]
+    pub date_ranges: Option<Vec<(NaiveDate, NaiveDate)>>,
+    /// Leave bars between date ranges out of the equity curve instead of
+    /// recording them as flat
+    #[serde(default)]
+    pub omit_range_gaps: bool,
+    /// External cash flows: deposits (positive) and withdrawals (negative)
+    /// applied to cash at the close of the first bar on or after each date.
+    /// A withdrawal beyond available cash sells down positions to cover it.
+    #[serde(default)]
+    pub cash_flows: Option<Vec<(NaiveDate, f64)>>,
+    /// Positions already held when the run starts (at most one long or short
+    /// and one hedge). They exit under the normal rules and their trades keep
+    /// the original entry; `initial_capital` includes their cost basis.
+    #[serde(default)]
+    pub initial_positions: Vec<Position>,
+    /// Snapshot the indicators on each trade's entry and exit bars
+    /// (`Trade::entry_context` / `exit_context`)
+    #[serde(default)]
+    pub record_trade_context: bool,
+}
+
+/// Per-symbol strategy overrides, keyed by symbol
+pub type SymbolOverrides = HashMap<String, PartialParameters>;
+
+/// A sparse set of strategy parameters; `None` keeps the base value.
+///
+/// Account-level settings (capital, commission, execution) are deliberately
+/// absent so they stay shared across symbols.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PartialParameters {
+    pub inverse_symbol: Option<String>,
+    pub rsi_period: Option<usize>,
+    pub rsi_oversold: Option<f64>,
+    pub rsi_overbought: Option<f64>,
+    pub rsi_smoothing: Option<Smoothing>,
+    pub rsi_reset_on_gap_pct: Option<f64>,
+    pub sma_period: Option<usize>,
+    pub sma_filter_enabled: Option<bool>,
+    pub entry_confirmation_bars: Option<usize>,
+    #[serde(rename = "stop_loss_pct")]
+    pub stop_loss: Option<StopSpec>,
+    pub max_holding_days: Option<u32>,
+    pub overnight_flat: Option<bool>,
+    pub take_profit_pct: Option<f64>,
+    pub position_size_pct: Option<f64>,
+    pub cash_reserve_pct: Option<f64>,
+    pub sizing_mode: Option<SizingMode>,
+    pub liquidity_aware_sizing: Option<bool>,
+    pub vwap_mode: Option<VwapMode>,
+    /// Legacy: false is `vwap_mode = "disabled"`, true `"below"`
+    pub vwap_filter_enabled: Option<bool>,
+    pub vwap_window: Option<usize>,
+    pub bb_filter_enabled: Option<bool>,
+    pub bb_period: Option<usize>,
+    pub bb_std_dev: Option<f64>,
+    pub volume_filter_enabled: Option<bool>,
+    pub volume_min_ratio: Option<f64>,
+    pub momentum_filter_enabled: Option<bool>,
+    pub momentum_roc_period: Option<usize>,
+    pub momentum_min_percentile: Option<f64>,
+    pub momentum_min_pct: Option<f64>,
+    pub momentum_lookback: Option<usize>,
+    pub adx_filter_enabled: Option<bool>,
+    pub adx_max: Option<f64>,
+    pub obv_filter_enabled: Option<bool>,
+    pub keltner_filter_enabled: Option<bool>,
+    pub anchored_vwap_filter_enabled: Option<bool>,
+    pub zscore_window: Option<usize>,
+    pub atr_smoothing: Option<Smoothing>,
+    pub profit_adjusted_exit: Option<ProfitAdjustedExit>,
+    pub exit_threshold_decay: Option<ExitDecay>,
+    pub sma_slope_filter: Option<SmaSlopeFilter>,
+    pub edge_filter_enabled: Option<bool>,
+    pub edge_atr_multiple: Option<f64>,
+    pub min_expected_profit_pct: Option<f64>,
+    pub anomaly_zscore: Option<f64>,
+    pub skip_entries_on_anomaly: Option<bool>,
+    pub anomaly_quarantine_bars: Option<usize>,
+    pub tighten_stops_on_anomaly_pct: Option<f64>,
+    pub min_data_quality: Option<f64>,
+    pub close_on_low_data_quality: Option<bool>,
+    pub short_enabled: Option<bool>,
+    pub rsi_overbought_short: Option<f64>,
+    pub rsi_oversold_short: Option<f64>,
+    #[serde(rename = "short_stop_loss_pct")]
+    pub short_stop_loss: Option<StopSpec>,
+    pub concurrent_hedge_policy: Option<ConcurrentHedgePolicy>,
+    pub flip_to_hedge_on_extreme: Option<bool>,
+    pub flip_to_long_on_oversold: Option<bool>,
+    pub signal_hysteresis_bars: Option<usize>,
+    pub hysteresis_rsi_delta: Option<f64>,
+    pub synthetic_short: Option<bool>,
+    pub synthetic_short_leverage: Option<f64>,
+    pub direction_mode: Option<DirectionMode>,
+    pub auto_flip_on_regime: Option<bool>,
+    pub short_position_size_pct: Option<f64>,
+    pub slippage_pct: Option<f64>,
+    pub min_bar_volume: Option<u64>,
+    pub sane_price_range: Option<(f64, f64)>,
+    pub max_portfolio_heat_pct: Option<f64>,
+    pub drawdown_throttle: Option<Vec<(f64, f64)>>,
+    pub adaptive_sizing: Option<AdaptiveSizing>,
+}
+
+impl PartialParameters {
+    /// Merge onto `base`: every field set here replaces the base value
+    pub fn apply_to(&self, base: &BacktestParameters) -> BacktestParameters {
+        let mut params = base.clone();
+        if let Some(v) = &self.inverse_symbol {
+            params.hedge.inverse_symbol = v.clone();
+        }
+        if let Some(v) = self.rsi_period {
+            params.strategy.rsi_period = v;
+        }
+        if let Some(v) = self.rsi_oversold {
+            params.strategy.rsi_oversold = v;
+        }
+        if let Some(v) = self.rsi_overbought {
+            params.strategy.rsi_overbought = v;
+        }
+        if let Some(v) = self.rsi_smoothing {
+            params.strategy.rsi_smoothing = v;
+        }
+        if let Some(v) = self.rsi_reset_on_gap_pct {
+            params.strategy.rsi_reset_on_gap_pct = Some(v);
+        }
+        if let Some(v) = self.sma_period {
+            params.strategy.sma_period = v;
+        }
+        if let Some(v) = self.sma_filter_enabled {
+            params.strategy.sma_filter_enabled = v;
+        }
+        if let Some(v) = self.entry_confirmation_bars {
+            params.strategy.entry_confirmation_bars = v;
+        }
+        if let Some(v) = self.stop_loss {
+            params.risk.stop_loss = v;
+        }
+        if let Some(v) = self.max_holding_days {
+            params.strategy.max_holding_days = Some(v);
+        }
+        if let Some(v) = self.overnight_flat {
+            params.risk.overnight_flat = v;
+        }
+        if let Some(v) = self.take_profit_pct {
+            params.risk.take_profit_pct = Some(v);
+        }
+        if let Some(v) = self.position_size_pct {
+            params.risk.position_size_pct = v;
+        }
+        if let Some(v) = self.cash_reserve_pct {
+            params.risk.cash_reserve_pct = v;
+        }
+        if let Some(v) = self.sizing_mode {
+            params.risk.sizing_mode = v;
+        }
+        if let Some(v) = self.liquidity_aware_sizing {
+            params.risk.liquidity_aware_sizing = v;
+        }
+        if let Some(v) = self.vwap_filter_enabled {
+            params.strategy.vwap_mode = if v {
+                VwapMode::Below
+            } else {
+                VwapMode::Disabled
+            };
+        }
+        if let Some(v) = self.vwap_mode {
+            params.strategy.vwap_mode = v;
+        }
+        if let Some(v) = self.vwap_window {
+            params.strategy.vwap_window = v;
+        }
+        if let Some(v) = self.bb_filter_enabled {
+            params.strategy.bb_filter_enabled = v;
+        }
+        if let Some(v) = self.bb_period {
+            params.strategy.bb_period = v;
+        }
+        if let Some(v) = self.bb_std_dev {
+            params.strategy.bb_std_dev = v;
+        }
+        if let Some(v) = self.volume_filter_enabled {
+            params.strategy.volume_filter_enabled = v;
+        }
+        if let Some(v) = self.volume_min_ratio {
+            params.strategy.volume_min_ratio = v;
+        }
+        if let Some(v) = self.momentum_filter_enabled {
+            params.strategy.momentum_filter_enabled = v;
+        }
+        if let Some(v) = self.momentum_roc_period {
+            params.strategy.momentum_roc_period = v;
+        }
+        if let Some(v) = self.zscore_window {
+            params.strategy.zscore_window = Some(v);
+        }
+        if let Some(v) = self.momentum_min_percentile {
+            params.strategy.momentum_min_percentile = v;
+        }
+        if let Some(v) = self.momentum_min_pct {
+            params.strategy.momentum_min_pct = Some(v);
+        }
+        if let Some(v) = self.momentum_lookback {
+            params.strategy.momentum_lookback = v;
+        }
+        if let Some(v) = self.adx_filter_enabled {
+            params.strategy.adx_filter_enabled = v;
+        }
+        if let Some(v) = self.adx_max {
+            params.strategy.adx_max = v;
+        }
+        if let Some(v) = self.obv_filter_enabled {
+            params.strategy.obv_filter_enabled = v;
+        }
+        if let Some(v) = self.keltner_filter_enabled {
+            params.strategy.keltner_filter_enabled = v;
+        }
+        if let Some(v) = self.anchored_vwap_filter_enabled {
+            params.strategy.anchored_vwap_filter_enabled = v;
+        }
+        if let Some(v) = self.atr_smoothing {
+            params.strategy.atr_smoothing = v;
+        }
+        if let Some(v) = self.profit_adjusted_exit {
+            params.strategy.profit_adjusted_exit = Some(v);
+        }
+        if let Some(v) = self.exit_threshold_decay {
+            params.strategy.exit_threshold_decay = Some(v);
+        }
+        if let Some(v) = self.sma_slope_filter {
+            params.strategy.sma_slope_filter = Some(v);
+        }
+        if let Some(v) = self.edge_filter_enabled {
+            params.risk.edge_filter_enabled = v;
+        }
+        if let Some(v) = self.edge_atr_multiple {
+            params.risk.edge_atr_multiple = v;
+        }
+        if let Some(v) = self.min_expected_profit_pct {
+            params.risk.min_expected_profit_pct = v;
+        }
+        if let Some(v) = self.anomaly_zscore {
+            params.risk.anomaly_zscore = v;
+        }
+        if let Some(v) = self.skip_entries_on_anomaly {
+            params.risk.skip_entries_on_anomaly = v;
+        }
+        if let Some(v) = self.anomaly_quarantine_bars {
+            params.risk.anomaly_quarantine_bars = v;
+        }
+        if let Some(v) = self.tighten_stops_on_anomaly_pct {
+            params.risk.tighten_stops_on_anomaly_pct = Some(v);
+        }
+        if let Some(v) = self.min_data_quality {
+            params.risk.min_data_quality = v;
+        }
+        if let Some(v) = self.close_on_low_data_quality {
+            params.risk.close_on_low_data_quality = v;
+        }
+        if let Some(v) = self.short_enabled {
+            params.hedge.short_enabled = v;
+        }
+        if let Some(v) = self.rsi_overbought_short {
+            params.hedge.rsi_overbought_short = v;
+        }
+        if let Some(v) = self.rsi_oversold_short {
+            params.hedge.rsi_oversold_short = v;
+        }
+        if let Some(v) = self.short_stop_loss {
+            params.hedge.short_stop_loss = v;
+        }
+        if let Some(v) = self.short_position_size_pct {
+            params.hedge.short_position_size_pct = v;
+        }
+        if let Some(v) = self.concurrent_hedge_policy {
+            params.hedge.concurrent_hedge_policy = v;
+        }
+        if let Some(v) = self.flip_to_hedge_on_extreme {
+            params.hedge.flip_to_hedge_on_extreme = v;
+        }
+        if let Some(v) = self.flip_to_long_on_oversold {
+            params.hedge.flip_to_long_on_oversold = v;
+        }
+        if let Some(v) = self.signal_hysteresis_bars {
+            params.hedge.signal_hysteresis_bars = v;
+        }
+        if let Some(v) = self.hysteresis_rsi_delta {
+            params.hedge.hysteresis_rsi_delta = v;
+        }
+        if let Some(v) = self.synthetic_short {
+            params.hedge.synthetic_short = v;
+        }
+        if let Some(v) = self.synthetic_short_leverage {
+            params.hedge.synthetic_short_leverage = v;
+        }
+        if let Some(v) = self.direction_mode {
+            params.hedge.direction_mode = v;
+        }
+        if let Some(v) = self.auto_flip_on_regime {
+            params.hedge.auto_flip_on_regime = v;
+        }
+        if let Some(v) = self.slippage_pct {
+            params.execution.slippage_pct = v;
+        }
+        if let Some(v) = self.min_bar_volume {
+            params.execution.min_bar_volume = v;
+        }
+        if let Some(v) = self.sane_price_range {
+            params.execution.sane_price_range = v;
+        }
+        if let Some(v) = self.max_portfolio_heat_pct {
+            params.risk.max_portfolio_heat_pct = Some(v);
+        }
+        if let Some(v) = &self.drawdown_throttle {
+            params.risk.drawdown_throttle = Some(v.clone());
+        }
+        if let Some(v) = self.adaptive_sizing {
+            params.risk.adaptive_sizing = Some(v);
+        }
+        params
+    }
+}
+
+fn default_momentum_roc_period() -> usize {
+    63
+}
+
+fn default_momentum_min_percentile() -> f64 {
+    20.0
+}
+
+fn default_momentum_lookback() -> usize {
+    10
+}
+
+fn default_vwap_window() -> usize {
+    20
+}
+
+fn default_adx_max() -> f64 {
+    25.0
+}
+
+fn default_sma_filter_enabled() -> bool {
+    true
+}
+
+fn default_entry_confirmation_bars() -> usize {
+    1
+}
+
+fn default_hysteresis_rsi_delta() -> f64 {
+    10.0
+}
+
+fn default_synthetic_short_leverage() -> f64 {
+    1.0
+}
+
+fn default_leverage_factors() -> BTreeMap<String, f64> {
+    BTreeMap::from([("SQQQ".to_string(), 3.0), ("TQQQ".to_string(), 3.0)])
+}
+
+fn default_sane_price_range() -> (f64, f64) {
+    (0.5, 10_000.0)
+}
+
+fn default_edge_atr_multiple() -> f64 {
+    1.0
+}
+
+fn default_min_expected_profit_pct() -> f64 {
+    0.5
+}
+
+fn default_anomaly_zscore() -> f64 {
+    6.0
+}
+
+impl Default for StrategyParams {
     fn default() -> Self {
         Self {
             symbol: "TQQQ".to_string(),
-            inverse_symbol: "SQQQ".to_string(),
             rsi_period: 2,
             rsi_oversold: 30.0,
             rsi_overbought: 75.0,
+            rsi_smoothing: Smoothing::Wilder,
+            rsi_reset_on_gap_pct: None,
             sma_period: 20,
-            stop_loss_pct: 0.05,
-            position_size_pct: 0.90,
-            cash_reserve_pct: 0.10,
-            vwap_filter_enabled: true,
-            vwap_entry_below: true,
+            sma_filter_enabled: default_sma_filter_enabled(),
+            sma_slope_filter: None,
+            entry_confirmation_bars: default_entry_confirmation_bars(),
+            max_holding_days: None,
+            vwap_mode: VwapMode::Below,
+            vwap_window: default_vwap_window(),
             bb_filter_enabled: false,
             bb_period: 20,
             bb_std_dev: 2.0,
             volume_filter_enabled: false,
             volume_min_ratio: 1.0,
+            momentum_filter_enabled: false,
+            momentum_roc_period: default_momentum_roc_period(),
+            momentum_min_percentile: default_momentum_min_percentile(),
+            momentum_min_pct: None,
+            momentum_lookback: default_momentum_lookback(),
+            adx_filter_enabled: false,
+            adx_max: default_adx_max(),
+            obv_filter_enabled: false,
+            keltner_filter_enabled: false,
+            anchored_vwap_filter_enabled: false,
+            zscore_window: None,
+            atr_smoothing: Smoothing::Wilder,
+            profit_adjusted_exit: None,
+            exit_threshold_decay: None,
+        }
+    }
+}
+
+impl Default for RiskParams {
+    fn default() -> Self {
+        Self {
+            stop_loss: StopSpec::Percent(0.05),
+            overnight_flat: false,
+            take_profit_pct: None,
+            position_size_pct: 0.90,
+            cash_reserve_pct: 0.10,
+            sizing_mode: SizingMode::CompoundPctEquity,
+            liquidity_aware_sizing: false,
+            edge_filter_enabled: false,
+            edge_atr_multiple: default_edge_atr_multiple(),
+            min_expected_profit_pct: default_min_expected_profit_pct(),
+            anomaly_zscore: default_anomaly_zscore(),
+            skip_entries_on_anomaly: false,
+            anomaly_quarantine_bars: 0,
+            tighten_stops_on_anomaly_pct: None,
+            min_data_quality: 0.0,
+            close_on_low_data_quality: false,
+            max_trades_per_month: None,
+            max_new_positions_per_day: None,
+            min_bars_between_entries: 0,
+            max_portfolio_heat_pct: None,
+            drawdown_throttle: None,
+            adaptive_sizing: None,
+            max_order_notional: None,
+            max_open_positions: None,
+        }
+    }
+}
+
+impl Default for HedgeParams {
+    fn default() -> Self {
+        Self {
+            inverse_symbol: "SQQQ".to_string(),
             short_enabled: true,
             use_inverse_etf: true,
             rsi_overbought_short: 90.0,
             rsi_oversold_short: 60.0,
-            short_stop_loss_pct: 0.05,
+            short_stop_loss: StopSpec::Percent(0.05),
             short_position_size_pct: 0.30,
-            initial_capital: 10000.0,
+            concurrent_hedge_policy: ConcurrentHedgePolicy::CloseHedgeFirst,
+            flip_to_hedge_on_extreme: false,
+            flip_to_long_on_oversold: false,
+            signal_hysteresis_bars: 0,
+            hysteresis_rsi_delta: default_hysteresis_rsi_delta(),
+            synthetic_short: false,
+            synthetic_short_leverage: default_synthetic_short_leverage(),
+            direction_mode: DirectionMode::LongPrimary,
+            auto_flip_on_regime: false,
+            leverage_factors: default_leverage_factors(),
+        }
+    }
+}
+
+impl Default for ExecutionParams {
+    fn default() -> Self {
+        Self {
+            use_bracket_orders: false,
+            same_bar_exit: SameBarExit::StopFirst,
             commission: 0.0,
             slippage_pct: 0.001,
-            execution: RealisticExecutionConfig::default(),
+            min_bar_volume: 0,
+            sane_price_range: default_sane_price_range(),
+            halted_stop_policy: HaltedStopPolicy::Skip,
+            simulation: RealisticExecutionConfig::default(),
+        }
+    }
+}
+
+impl Default for BacktestParameters {
+    fn default() -> Self {
+        Self {
+            strategy: StrategyParams::default(),
+            risk: RiskParams::default(),
+            hedge: HedgeParams::default(),
+            execution: ExecutionParams::default(),
+            initial_capital: 10000.0,
+            precise_accounting: false,
+            display_utc_offset_minutes: 0,
+            metrics_scope: MetricsScope::Main,
+            date_ranges: None,
+            omit_range_gaps: false,
+            cash_flows: None,
+            initial_positions: Vec::new(),
+            record_trade_context: false,
         }
     }
 }
@@ -191,28 +1450,526 @@ impl BacktestParameters {
     }
 
     pub fn with_rsi_thresholds(mut self, oversold: f64, overbought: f64) -> Self {
-        self.rsi_oversold = oversold;
-        self.rsi_overbought = overbought;
+        self.strategy.rsi_oversold = oversold;
+        self.strategy.rsi_overbought = overbought;
         self
     }
 
-    pub fn with_stop_loss(mut self, stop_loss_pct: f64) -> Self {
-        self.stop_loss_pct = stop_loss_pct;
+    /// A bare fraction is a percent stop, as before
+    pub fn with_stop_loss(mut self, stop: impl Into<StopSpec>) -> Self {
+        self.risk.stop_loss = stop.into();
         self
     }
 
     pub fn with_sma_period(mut self, period: usize) -> Self {
-        self.sma_period = period;
+        self.strategy.sma_period = period;
+        self
+    }
+
+    /// Require the SMA to have risen `min_slope_pct` (%) over the last
+    /// `lookback_bars` bars before an entry
+    pub fn with_sma_slope_filter(mut self, lookback_bars: usize, min_slope_pct: f64) -> Self {
+        self.strategy.sma_slope_filter = Some(SmaSlopeFilter {
+            lookback_bars,
+            min_slope_pct,
+        });
+        self
+    }
+
+    pub fn with_max_holding_days(mut self, days: u32) -> Self {
+        self.strategy.max_holding_days = Some(days);
+        self
+    }
+
+    /// Enter through bracket orders with the given take-profit fraction
+    pub fn with_bracket_orders(mut self, take_profit_pct: f64) -> Self {
+        self.execution.use_bracket_orders = true;
+        self.risk.take_profit_pct = Some(take_profit_pct);
+        self
+    }
+
+    /// Flat slippage fraction charged on every fill without the execution
+    /// simulation
+    pub fn with_slippage(mut self, slippage_pct: f64) -> Self {
+        self.execution.slippage_pct = slippage_pct;
+        self
+    }
+
+    pub fn with_sizing_mode(mut self, mode: SizingMode) -> Self {
+        self.risk.sizing_mode = mode;
+        self
+    }
+
+    pub fn with_direction_mode(mut self, mode: DirectionMode) -> Self {
+        self.hedge.direction_mode = mode;
         self
     }
 
     pub fn without_short(mut self) -> Self {
-        self.short_enabled = false;
+        self.hedge.short_enabled = false;
         self
     }
 
     pub fn without_vwap_filter(mut self) -> Self {
-        self.vwap_filter_enabled = false;
+        self.strategy.vwap_mode = VwapMode::Disabled;
+        self
+    }
+
+    /// Bars consumed by indicator warmup before the first trading bar: the
+    /// trend SMA's and the first bar with RSI, ATR and the bands all warm
+    pub fn warmup_bars(&self) -> usize {
+        self.strategy
+            .sma_period
+            .max(self.strategy.bb_period)
+            .max(self.strategy.rsi_period)
+            .max(ATR_PERIOD)
+    }
+
+    /// Long entry size multiplier at `drawdown_pct` below the equity peak:
+    /// that of the deepest `drawdown_throttle` threshold exceeded, else 1
+    pub fn drawdown_size_multiplier(&self, drawdown_pct: f64) -> f64 {
+        self.risk
+            .drawdown_throttle
+            .iter()
+            .flatten()
+            .filter(|(threshold, _)| drawdown_pct > *threshold)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map_or(1.0, |(_, multiplier)| *multiplier)
+    }
+
+    /// Trade only within the given inclusive date ranges
+    pub fn with_date_ranges(mut self, ranges: Vec<(NaiveDate, NaiveDate)>) -> Self {
+        self.date_ranges = Some(ranges);
+        self
+    }
+
+    /// Apply these deposits (positive) and withdrawals (negative)
+    pub fn with_cash_flows(mut self, flows: Vec<(NaiveDate, f64)>) -> Self {
+        self.cash_flows = Some(flows);
+        self
+    }
+
+    /// Start the run already holding these positions
+    pub fn with_initial_positions(mut self, positions: Vec<Position>) -> Self {
+        self.initial_positions = positions;
+        self
+    }
+
+    /// Index of the date range containing `date`, if any. Every date is in
+    /// range 0 when trading is unrestricted.
+    pub fn date_range_index(&self, date: NaiveDate) -> Option<usize> {
+        match &self.date_ranges {
+            None => Some(0),
+            Some(ranges) => ranges
+                .iter()
+                .position(|(start, end)| (*start..=*end).contains(&date)),
+        }
+    }
+
+    pub fn with_trade_limits(
+        mut self,
+        max_trades_per_month: Option<u32>,
+        max_new_positions_per_day: Option<u32>,
+    ) -> Self {
+        self.risk.max_trades_per_month = max_trades_per_month;
+        self.risk.max_new_positions_per_day = max_new_positions_per_day;
+        self
+    }
+
+    /// Skip entries on volatility-burst bars and for `quarantine_bars` after
+    pub fn with_anomaly_quarantine(mut self, quarantine_bars: usize) -> Self {
+        self.risk.skip_entries_on_anomaly = true;
+        self.risk.anomaly_quarantine_bars = quarantine_bars;
         self
     }
+
+    /// Whether any rule reads the volatility-burst flag
+    pub fn anomaly_rules_enabled(&self) -> bool {
+        self.risk.skip_entries_on_anomaly || self.risk.tighten_stops_on_anomaly_pct.is_some()
+    }
+
+    /// Check that the parameter set is internally consistent
+    pub fn validate(&self) -> Result<()> {
+        fn invalid(msg: String) -> Result<()> {
+            Err(BacktestError::InvalidParameter(msg))
+        }
+        fn check_fraction(name: &str, value: f64) -> Result<()> {
+            if !(0.0..=1.0).contains(&value) {
+                return invalid(format!("{} must be between 0 and 1, got {}", name, value));
+            }
+            Ok(())
+        }
+        fn check_stop(name: &str, stop: StopSpec) -> Result<()> {
+            match stop {
+                StopSpec::Percent(fraction) => check_fraction(name, fraction),
+                StopSpec::AtrMultiple(v) | StopSpec::Dollars(v) if !(v.is_finite() && v > 0.0) => {
+                    invalid(format!("{} must be positive, got {}", name, stop))
+                }
+                _ => Ok(()),
+            }
+        }
+        fn check_rsi(name: &str, value: f64) -> Result<()> {
+            if !(0.0..=100.0).contains(&value) {
+                return invalid(format!("{} must be between 0 and 100, got {}", name, value));
+            }
+            Ok(())
+        }
+
+        if self.strategy.symbol.is_empty() {
+            return invalid("symbol must not be empty".to_string());
+        }
+        for (name, period) in [
+            ("rsi_period", self.strategy.rsi_period),
+            ("sma_period", self.strategy.sma_period),
+            ("vwap_window", self.strategy.vwap_window),
+            ("bb_period", self.strategy.bb_period),
+            ("momentum_roc_period", self.strategy.momentum_roc_period),
+            ("momentum_lookback", self.strategy.momentum_lookback),
+            (
+                "entry_confirmation_bars",
+                self.strategy.entry_confirmation_bars,
+            ),
+        ] {
+            if period == 0 {
+                return invalid(format!("{} must be at least 1", name));
+            }
+        }
+
+        if self.strategy.zscore_window.is_some_and(|w| w < 2) {
+            return invalid("zscore_window must be at least 2".to_string());
+        }
+        if let Some(filter) = &self.strategy.sma_slope_filter {
+            if filter.lookback_bars == 0 {
+                return invalid("sma_slope_filter lookback_bars must be at least 1".to_string());
+            }
+            if !filter.min_slope_pct.is_finite() {
+                return invalid(format!(
+                    "sma_slope_filter min_slope_pct must be finite, got {}",
+                    filter.min_slope_pct
+                ));
+            }
+        }
+        if let Some(pct) = self.strategy.momentum_min_pct {
+            if !pct.is_finite() {
+                return invalid(format!("momentum_min_pct must be finite, got {}", pct));
+            }
+        }
+
+        check_rsi("rsi_oversold", self.strategy.rsi_oversold)?;
+        check_rsi("rsi_overbought", self.strategy.rsi_overbought)?;
+        check_rsi(
+            "momentum_min_percentile",
+            self.strategy.momentum_min_percentile,
+        )?;
+        check_rsi("adx_max", self.strategy.adx_max)?;
+        if self.strategy.rsi_oversold >= self.strategy.rsi_overbought {
+            return invalid(format!(
+                "rsi_oversold ({}) must be below rsi_overbought ({})",
+                self.strategy.rsi_oversold, self.strategy.rsi_overbought
+            ));
+        }
+        if let Some(exit) = &self.strategy.profit_adjusted_exit {
+            if !(exit.profit_trigger_pct.is_finite() && exit.profit_trigger_pct >= 0.0) {
+                return invalid(format!(
+                    "profit_adjusted_exit profit_trigger_pct must not be negative, got {}",
+                    exit.profit_trigger_pct
+                ));
+            }
+            let reduced = exit.reduced_overbought;
+            if !(reduced > self.strategy.rsi_oversold && reduced <= self.strategy.rsi_overbought) {
+                return invalid(format!(
+                    "profit_adjusted_exit reduced_overbought ({}) must be above rsi_oversold ({}) \
+                     and at most rsi_overbought ({})",
+                    reduced, self.strategy.rsi_oversold, self.strategy.rsi_overbought
+                ));
+            }
+        }
+        if let Some(decay) = &self.strategy.exit_threshold_decay {
+            if !(decay.per_bar_decay.is_finite() && decay.per_bar_decay >= 0.0) {
+                return invalid(format!(
+                    "exit_threshold_decay per_bar_decay must not be negative, got {}",
+                    decay.per_bar_decay
+                ));
+            }
+            let floor = decay.floor;
+            if !(floor > self.strategy.rsi_oversold && floor <= self.strategy.rsi_overbought) {
+                return invalid(format!(
+                    "exit_threshold_decay floor ({}) must be above rsi_oversold ({}) and at most \
+                     rsi_overbought ({})",
+                    floor, self.strategy.rsi_oversold, self.strategy.rsi_overbought
+                ));
+            }
+        }
+        check_rsi("hysteresis_rsi_delta", self.hedge.hysteresis_rsi_delta)?;
+        let leverage = self.hedge.synthetic_short_leverage;
+        if !(leverage.is_finite() && leverage > 0.0) {
+            return invalid(format!(
+                "synthetic_short_leverage must be positive, got {}",
+                leverage
+            ));
+        }
+        if let Some((symbol, factor)) = self
+            .hedge
+            .leverage_factors
+            .iter()
+            .find(|(_, f)| !(f.is_finite() && **f > 0.0))
+        {
+            return invalid(format!(
+                "leverage factor of {} must be positive, got {}",
+                symbol, factor
+            ));
+        }
+        if self.hedge.short_enabled {
+            check_rsi("rsi_overbought_short", self.hedge.rsi_overbought_short)?;
+            check_rsi("rsi_oversold_short", self.hedge.rsi_oversold_short)?;
+            if self.hedge.rsi_oversold_short >= self.hedge.rsi_overbought_short {
+                return invalid(format!(
+                    "rsi_oversold_short ({}) must be below rsi_overbought_short ({})",
+                    self.hedge.rsi_oversold_short, self.hedge.rsi_overbought_short
+                ));
+            }
+        }
+
+        check_stop("stop_loss_pct", self.risk.stop_loss)?;
+        check_fraction("position_size_pct", self.risk.position_size_pct)?;
+        check_fraction("cash_reserve_pct", self.risk.cash_reserve_pct)?;
+        check_stop("short_stop_loss_pct", self.hedge.short_stop_loss)?;
+        check_fraction(
+            "short_position_size_pct",
+            self.hedge.short_position_size_pct,
+        )?;
+        check_fraction("slippage_pct", self.execution.slippage_pct)?;
+        if self.execution.simulation.enabled && self.execution.slippage_pct != 0.0 {
+            return invalid(format!(
+                "slippage_pct ({}) would be charged on top of the execution simulation's \
+                 slippage; set it to 0 when simulation is enabled",
+                self.execution.slippage_pct
+            ));
+        }
+
+        match self.risk.sizing_mode {
+            SizingMode::FixedNotional(notional) if !(notional.is_finite() && notional > 0.0) => {
+                return invalid(format!("fixed notional must be positive, got {}", notional));
+            }
+            SizingMode::FixedShares(shares) if !(shares.is_finite() && shares >= 1.0) => {
+                return invalid(format!(
+                    "fixed share count must be at least 1, got {}",
+                    shares
+                ));
+            }
+            _ => {}
+        }
+        if let VwapMode::WithinBandPct(pct) = self.strategy.vwap_mode {
+            if !pct.is_finite() || pct < 0.0 {
+                return invalid(format!(
+                    "VWAP band must be a non-negative percent, got {}",
+                    pct
+                ));
+            }
+        }
+        if self.strategy.bb_std_dev <= 0.0 {
+            return invalid(format!(
+                "bb_std_dev must be positive, got {}",
+                self.strategy.bb_std_dev
+            ));
+        }
+        if self.risk.edge_atr_multiple.is_nan() || self.risk.edge_atr_multiple <= 0.0 {
+            return invalid(format!(
+                "edge_atr_multiple must be positive, got {}",
+                self.risk.edge_atr_multiple
+            ));
+        }
+        if self.risk.min_expected_profit_pct < 0.0 {
+            return invalid(format!(
+                "min_expected_profit_pct must not be negative, got {}",
+                self.risk.min_expected_profit_pct
+            ));
+        }
+        if self.risk.anomaly_zscore.is_nan() || self.risk.anomaly_zscore <= 0.0 {
+            return invalid(format!(
+                "anomaly_zscore must be positive, got {}",
+                self.risk.anomaly_zscore
+            ));
+        }
+        if let Some(pct) = self.risk.tighten_stops_on_anomaly_pct {
+            check_fraction("tighten_stops_on_anomaly_pct", pct)?;
+        }
+        check_fraction("min_data_quality", self.risk.min_data_quality)?;
+        let (min_price, max_price) = self.execution.sane_price_range;
+        if !(min_price > 0.0 && min_price < max_price) {
+            return invalid(format!(
+                "sane_price_range must satisfy 0 < min < max, got ({}, {})",
+                min_price, max_price
+            ));
+        }
+        if let Some(take_profit) = self.risk.take_profit_pct {
+            if take_profit.is_nan() || take_profit <= 0.0 {
+                return invalid(format!(
+                    "take_profit_pct must be positive, got {}",
+                    take_profit
+                ));
+            }
+        }
+        if self.execution.use_bracket_orders {
+            if self.risk.take_profit_pct.is_none() {
+                return invalid("use_bracket_orders requires take_profit_pct".to_string());
+            }
+            if !self.risk.stop_loss.is_set() {
+                return invalid("use_bracket_orders requires a stop loss".to_string());
+            }
+        }
+        if self.risk.overnight_flat && self.execution.use_bracket_orders {
+            return invalid("overnight_flat does not support bracket orders".to_string());
+        }
+        if self.strategy.max_holding_days == Some(0) {
+            return invalid("max_holding_days must be at least 1 when set".to_string());
+        }
+        if let Some(cap) = self.risk.max_portfolio_heat_pct {
+            if cap.is_nan() || cap <= 0.0 {
+                return invalid(format!(
+                    "max_portfolio_heat_pct must be positive, got {}",
+                    cap
+                ));
+            }
+        }
+        if let Some(cap) = self.risk.max_order_notional {
+            if !(cap.is_finite() && cap > 0.0) {
+                return invalid(format!("max_order_notional must be positive, got {}", cap));
+            }
+        }
+        if self.risk.max_open_positions == Some(0) {
+            return invalid("max_open_positions must be at least 1 when set".to_string());
+        }
+        for &(threshold, multiplier) in self.risk.drawdown_throttle.iter().flatten() {
+            if !(threshold.is_finite() && threshold > 0.0 && threshold < 100.0) {
+                return invalid(format!(
+                    "drawdown_throttle threshold must be between 0 and 100%, got {}",
+                    threshold
+                ));
+            }
+            if !(multiplier.is_finite() && multiplier >= 0.0) {
+                return invalid(format!(
+                    "drawdown_throttle multiplier must not be negative, got {}",
+                    multiplier
+                ));
+            }
+        }
+        if let Some(sizing) = &self.risk.adaptive_sizing {
+            if sizing.lookback_trades == 0 {
+                return invalid("adaptive_sizing lookback_trades must be at least 1".to_string());
+            }
+            let (min, max) = (sizing.min_mult, sizing.max_mult);
+            if !(min >= 0.0 && min <= max && max.is_finite()) {
+                return invalid(format!(
+                    "adaptive_sizing needs 0 <= min_mult <= max_mult, got {} and {}",
+                    min, max
+                ));
+            }
+            if !(sizing.sensitivity.is_finite() && sizing.sensitivity >= 0.0) {
+                return invalid(format!(
+                    "adaptive_sizing sensitivity must not be negative, got {}",
+                    sizing.sensitivity
+                ));
+            }
+        }
+        if self.initial_capital.is_nan() || self.initial_capital <= 0.0 {
+            return invalid(format!(
+                "initial_capital must be positive, got {}",
+                self.initial_capital
+            ));
+        }
+        if self.execution.commission < 0.0 {
+            return invalid(format!(
+                "commission must not be negative, got {}",
+                self.execution.commission
+            ));
+        }
+        if let Some(pct) = self.strategy.rsi_reset_on_gap_pct {
+            if !(pct > 0.0 && pct.is_finite()) {
+                return invalid(format!(
+                    "rsi_reset_on_gap_pct must be positive, got {}",
+                    pct
+                ));
+            }
+        }
+        for (date, amount) in self.cash_flows.iter().flatten() {
+            if !amount.is_finite() {
+                return invalid(format!(
+                    "cash flow on {} must be finite, got {}",
+                    date, amount
+                ));
+            }
+        }
+        let mut cost_basis = 0.0;
+        for (i, position) in self.initial_positions.iter().enumerate() {
+            let expected = match position.side {
+                PositionSide::Hedge => &self.hedge.inverse_symbol,
+                _ => &self.strategy.symbol,
+            };
+            if &position.symbol != expected {
+                return invalid(format!(
+                    "initial {:?} position is in {}, expected {}",
+                    position.side, position.symbol, expected
+                ));
+            }
+            if !(position.avg_entry_price > 0.0 && position.avg_entry_price.is_finite()) {
+                return invalid(format!(
+                    "initial position entry price must be positive, got {}",
+                    position.avg_entry_price
+                ));
+            }
+            if !(position.quantity > 0.0 && position.quantity.is_finite()) {
+                return invalid(format!(
+                    "initial position quantity must be positive, got {}",
+                    position.quantity
+                ));
+            }
+            let is_hedge = |p: &Position| p.side == PositionSide::Hedge;
+            if self.initial_positions[..i]
+                .iter()
+                .any(|p| is_hedge(p) == is_hedge(position))
+            {
+                return invalid(format!(
+                    "at most one initial {} position is supported",
+                    if is_hedge(position) {
+                        "hedge"
+                    } else {
+                        "long or short"
+                    }
+                ));
+            }
+            cost_basis += position.quantity * position.avg_entry_price;
+        }
+        if cost_basis > self.initial_capital {
+            return invalid(format!(
+                "initial positions cost {:.2}, more than initial_capital {}",
+                cost_basis, self.initial_capital
+            ));
+        }
+        if let Some(ranges) = &self.date_ranges {
+            if ranges.is_empty() {
+                return invalid("date_ranges must not be empty when set".to_string());
+            }
+            let mut sorted = ranges.clone();
+            sorted.sort();
+            for (start, end) in &sorted {
+                if start > end {
+                    return invalid(format!(
+                        "date range {} to {} ends before it starts",
+                        start, end
+                    ));
+                }
+            }
+            for pair in sorted.windows(2) {
+                if pair[1].0 <= pair[0].1 {
+                    return invalid(format!(
+                        "date ranges {} to {} and {} to {} overlap",
+                        pair[0].0, pair[0].1, pair[1].0, pair[1].1
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,356 @@
+//! The flat parameter struct from before the strategy / risk / hedge /
+//! execution split
+//!
+//! Both layouts read and write the same documents; this one is kept for
+//! library code that builds parameters field by field. Convert with `From`.
+//!
+//! The struct is frozen at the split: settings added since keep their
+//! defaults when converting from it and are dropped when converting to it.
+//! Stops are percent-only here; ATR and dollar stops convert to none.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    default_anomaly_zscore, default_edge_atr_multiple, default_min_expected_profit_pct,
+    default_momentum_min_percentile, default_momentum_roc_period, default_sma_filter_enabled,
+    deserialize_vwap_mode, serialize_vwap_mode, BacktestParameters, ConcurrentHedgePolicy,
+    ExecutionParams, HaltedStopPolicy, HedgeParams, MetricsScope, RealisticExecutionConfig,
+    RiskParams, SameBarExit, SizingMode, Smoothing, StopSpec, StrategyParams, VwapMode,
+};
+use crate::types::Position;
+
+/// The flat `BacktestParameters` layout, one field per setting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlatBacktestParameters {
+    // Symbol
+    pub symbol: String,
+    pub inverse_symbol: String,
+    // RSI parameters
+    pub rsi_period: usize,
+    pub rsi_oversold: f64,
+    pub rsi_overbought: f64,
+    #[serde(default)]
+    pub rsi_smoothing: Smoothing,
+    /// Restart the RSI and ATR smoothing after a bar whose close-to-close
+    /// move exceeds this many percent; no entries until they re-seed
+    #[serde(default)]
+    pub rsi_reset_on_gap_pct: Option<f64>,
+    // SMA parameters
+    pub sma_period: usize,
+    /// Only enter while the close is at or above the SMA
+    #[serde(default = "default_sma_filter_enabled")]
+    pub sma_filter_enabled: bool,
+    // Risk management
+    pub stop_loss_pct: f64,
+    /// Time exit: close the position once held this many calendar days
+    #[serde(default)]
+    pub max_holding_days: Option<u32>,
+    /// Close the long at every bar's close and reopen it at the next open
+    /// while no exit has fired, so it never carries an overnight gap
+    #[serde(default)]
+    pub overnight_flat: bool,
+    /// Enter with bracket orders: a limit entry at the signal close plus an
+    /// OCO stop (`stop_loss_pct`) and target (`take_profit_pct`)
+    #[serde(default)]
+    pub use_bracket_orders: bool,
+    #[serde(default)]
+    pub take_profit_pct: Option<f64>,
+    #[serde(default)]
+    pub same_bar_exit: SameBarExit,
+    pub position_size_pct: f64,
+    pub cash_reserve_pct: f64,
+    /// Long entry sizing; `position_size_pct` only applies when compounding
+    #[serde(default)]
+    pub sizing_mode: SizingMode,
+    /// Cap long entries at the execution volume participation limit applied
+    /// to the trailing average volume, instead of relying on fill truncation
+    #[serde(default)]
+    pub liquidity_aware_sizing: bool,
+    // Filters
+    /// Replaces `vwap_filter_enabled` / `vwap_entry_below`, which are still
+    /// read from older configs
+    #[serde(
+        flatten,
+        deserialize_with = "deserialize_vwap_mode",
+        serialize_with = "serialize_vwap_mode"
+    )]
+    pub vwap_mode: VwapMode,
+    pub bb_filter_enabled: bool,
+    pub bb_period: usize,
+    pub bb_std_dev: f64,
+    pub volume_filter_enabled: bool,
+    pub volume_min_ratio: f64,
+    /// Skip entries while the `momentum_roc_period`-bar rate of change ranks
+    /// below `momentum_min_percentile` (0-100) over the past year
+    #[serde(default)]
+    pub momentum_filter_enabled: bool,
+    #[serde(default = "default_momentum_roc_period")]
+    pub momentum_roc_period: usize,
+    #[serde(default = "default_momentum_min_percentile")]
+    pub momentum_min_percentile: f64,
+    /// Window of the rolling close z-score offered to strategies (with log
+    /// returns); not computed when None
+    #[serde(default)]
+    pub zscore_window: Option<usize>,
+    /// Averaging for the ATR used by the edge filter and ATR-based features
+    #[serde(default)]
+    pub atr_smoothing: Smoothing,
+    /// Skip entries whose expected move (`edge_atr_multiple` × ATR) is below
+    /// `min_expected_profit_pct` (%) plus the modeled round-trip cost
+    #[serde(default)]
+    pub edge_filter_enabled: bool,
+    #[serde(default = "default_edge_atr_multiple")]
+    pub edge_atr_multiple: f64,
+    #[serde(default = "default_min_expected_profit_pct")]
+    pub min_expected_profit_pct: f64,
+    /// Flag a bar as a volatility burst when its log return lies more than
+    /// this many standard deviations from the trailing returns' mean
+    #[serde(default = "default_anomaly_zscore")]
+    pub anomaly_zscore: f64,
+    /// Skip entries on a burst bar and the `anomaly_quarantine_bars` after it
+    #[serde(default)]
+    pub skip_entries_on_anomaly: bool,
+    #[serde(default)]
+    pub anomaly_quarantine_bars: usize,
+    /// On a burst bar, raise the long's stop to this fraction below the
+    /// close (never lowering it)
+    #[serde(default)]
+    pub tighten_stops_on_anomaly_pct: Option<f64>,
+    // Short/Hedge
+    pub short_enabled: bool,
+    pub use_inverse_etf: bool,
+    pub rsi_overbought_short: f64,
+    pub rsi_oversold_short: f64,
+    pub short_stop_loss_pct: f64,
+    pub short_position_size_pct: f64,
+    #[serde(default)]
+    pub concurrent_hedge_policy: ConcurrentHedgePolicy,
+    /// On RSI at or above `rsi_overbought_short` while long, open the hedge
+    /// on the bar the long is sold instead of waiting a bar
+    #[serde(default)]
+    pub flip_to_hedge_on_extreme: bool,
+    /// On RSI at or below `rsi_oversold` while hedged, open the long on the
+    /// bar the hedge is closed
+    #[serde(default)]
+    pub flip_to_long_on_oversold: bool,
+    // Backtest settings
+    pub initial_capital: f64,
+    pub commission: f64,
+    pub slippage_pct: f64,
+    /// Keep cash and realized P&L in whole cents (see `Money`) instead of f64
+    #[serde(default)]
+    pub precise_accounting: bool,
+    /// Bars with volume below this are treated as halted: no orders execute
+    /// and pending orders roll forward (0 disables)
+    #[serde(default)]
+    pub min_bar_volume: u64,
+    #[serde(default)]
+    pub halted_stop_policy: HaltedStopPolicy,
+    // Trade frequency limits (entries only, exits are never blocked)
+    #[serde(default)]
+    pub max_trades_per_month: Option<u32>,
+    #[serde(default)]
+    pub max_new_positions_per_day: Option<u32>,
+    /// Bars that must pass after an entry fills before the same symbol can
+    /// be entered again; when set, at most one position opens per bar
+    #[serde(default)]
+    pub min_bars_between_entries: usize,
+    /// Portfolio heat cap (% of equity at risk to stops); entries are shrunk
+    /// to fit and skipped when not even one share fits
+    #[serde(default)]
+    pub max_portfolio_heat_pct: Option<f64>,
+    /// (drawdown %, size multiplier) pairs: while equity is more than the
+    /// threshold below its running peak, long entries are scaled by the
+    /// multiplier of the deepest threshold crossed
+    #[serde(default)]
+    pub drawdown_throttle: Option<Vec<(f64, f64)>>,
+    /// UTC offset (minutes) used to derive calendar day/month boundaries
+    #[serde(default)]
+    pub display_utc_offset_minutes: i32,
+    /// Trades the headline metrics cover
+    #[serde(default)]
+    pub metrics_scope: MetricsScope,
+    /// Only trade within these inclusive date ranges; positions are closed at
+    /// the last bar of each range (None trades every bar)
+    #[serde(default)]
+    pub date_ranges: Option<Vec<(NaiveDate, NaiveDate)>>,
+    /// Leave bars between date ranges out of the equity curve instead of
+    /// recording them as flat
+    #[serde(default)]
+    pub omit_range_gaps: bool,
+    /// External cash flows: deposits (positive) and withdrawals (negative)
+    /// applied to cash at the close of the first bar on or after each date.
+    /// A withdrawal beyond available cash sells down positions to cover it.
+    #[serde(default)]
+    pub cash_flows: Option<Vec<(NaiveDate, f64)>>,
+    /// Positions already held when the run starts (at most one long or short
+    /// and one hedge). They exit under the normal rules and their trades keep
+    /// the original entry; `initial_capital` includes their cost basis.
+    #[serde(default)]
+    pub initial_positions: Vec<Position>,
+    // Realistic execution simulation
+    #[serde(default)]
+    pub execution: RealisticExecutionConfig,
+}
+
+impl From<FlatBacktestParameters> for BacktestParameters {
+    fn from(flat: FlatBacktestParameters) -> Self {
+        Self {
+            strategy: StrategyParams {
+                symbol: flat.symbol,
+                rsi_period: flat.rsi_period,
+                rsi_oversold: flat.rsi_oversold,
+                rsi_overbought: flat.rsi_overbought,
+                rsi_smoothing: flat.rsi_smoothing,
+                rsi_reset_on_gap_pct: flat.rsi_reset_on_gap_pct,
+                sma_period: flat.sma_period,
+                sma_filter_enabled: flat.sma_filter_enabled,
+                max_holding_days: flat.max_holding_days,
+                vwap_mode: flat.vwap_mode,
+                bb_filter_enabled: flat.bb_filter_enabled,
+                bb_period: flat.bb_period,
+                bb_std_dev: flat.bb_std_dev,
+                volume_filter_enabled: flat.volume_filter_enabled,
+                volume_min_ratio: flat.volume_min_ratio,
+                momentum_filter_enabled: flat.momentum_filter_enabled,
+                momentum_roc_period: flat.momentum_roc_period,
+                momentum_min_percentile: flat.momentum_min_percentile,
+                zscore_window: flat.zscore_window,
+                atr_smoothing: flat.atr_smoothing,
+                ..Default::default()
+            },
+            risk: RiskParams {
+                stop_loss: StopSpec::from_pct(flat.stop_loss_pct),
+                overnight_flat: flat.overnight_flat,
+                take_profit_pct: flat.take_profit_pct,
+                position_size_pct: flat.position_size_pct,
+                cash_reserve_pct: flat.cash_reserve_pct,
+                sizing_mode: flat.sizing_mode,
+                liquidity_aware_sizing: flat.liquidity_aware_sizing,
+                edge_filter_enabled: flat.edge_filter_enabled,
+                edge_atr_multiple: flat.edge_atr_multiple,
+                min_expected_profit_pct: flat.min_expected_profit_pct,
+                anomaly_zscore: flat.anomaly_zscore,
+                skip_entries_on_anomaly: flat.skip_entries_on_anomaly,
+                anomaly_quarantine_bars: flat.anomaly_quarantine_bars,
+                tighten_stops_on_anomaly_pct: flat.tighten_stops_on_anomaly_pct,
+                max_trades_per_month: flat.max_trades_per_month,
+                max_new_positions_per_day: flat.max_new_positions_per_day,
+                min_bars_between_entries: flat.min_bars_between_entries,
+                max_portfolio_heat_pct: flat.max_portfolio_heat_pct,
+                drawdown_throttle: flat.drawdown_throttle,
+                ..Default::default()
+            },
+            hedge: HedgeParams {
+                inverse_symbol: flat.inverse_symbol,
+                short_enabled: flat.short_enabled,
+                use_inverse_etf: flat.use_inverse_etf,
+                rsi_overbought_short: flat.rsi_overbought_short,
+                rsi_oversold_short: flat.rsi_oversold_short,
+                short_stop_loss: StopSpec::from_pct(flat.short_stop_loss_pct),
+                short_position_size_pct: flat.short_position_size_pct,
+                concurrent_hedge_policy: flat.concurrent_hedge_policy,
+                flip_to_hedge_on_extreme: flat.flip_to_hedge_on_extreme,
+                flip_to_long_on_oversold: flat.flip_to_long_on_oversold,
+                ..Default::default()
+            },
+            execution: ExecutionParams {
+                use_bracket_orders: flat.use_bracket_orders,
+                same_bar_exit: flat.same_bar_exit,
+                commission: flat.commission,
+                slippage_pct: flat.slippage_pct,
+                min_bar_volume: flat.min_bar_volume,
+                halted_stop_policy: flat.halted_stop_policy,
+                simulation: flat.execution,
+                ..Default::default()
+            },
+            initial_capital: flat.initial_capital,
+            precise_accounting: flat.precise_accounting,
+            display_utc_offset_minutes: flat.display_utc_offset_minutes,
+            metrics_scope: flat.metrics_scope,
+            date_ranges: flat.date_ranges,
+            omit_range_gaps: flat.omit_range_gaps,
+            cash_flows: flat.cash_flows,
+            initial_positions: flat.initial_positions,
+            record_trade_context: false,
+        }
+    }
+}
+
+impl From<BacktestParameters> for FlatBacktestParameters {
+    fn from(params: BacktestParameters) -> Self {
+        Self {
+            symbol: params.strategy.symbol,
+            inverse_symbol: params.hedge.inverse_symbol,
+            rsi_period: params.strategy.rsi_period,
+            rsi_oversold: params.strategy.rsi_oversold,
+            rsi_overbought: params.strategy.rsi_overbought,
+            rsi_smoothing: params.strategy.rsi_smoothing,
+            rsi_reset_on_gap_pct: params.strategy.rsi_reset_on_gap_pct,
+            sma_period: params.strategy.sma_period,
+            sma_filter_enabled: params.strategy.sma_filter_enabled,
+            stop_loss_pct: params.risk.stop_loss.percent().unwrap_or(0.0),
+            max_holding_days: params.strategy.max_holding_days,
+            overnight_flat: params.risk.overnight_flat,
+            use_bracket_orders: params.execution.use_bracket_orders,
+            take_profit_pct: params.risk.take_profit_pct,
+            same_bar_exit: params.execution.same_bar_exit,
+            position_size_pct: params.risk.position_size_pct,
+            cash_reserve_pct: params.risk.cash_reserve_pct,
+            sizing_mode: params.risk.sizing_mode,
+            liquidity_aware_sizing: params.risk.liquidity_aware_sizing,
+            vwap_mode: params.strategy.vwap_mode,
+            bb_filter_enabled: params.strategy.bb_filter_enabled,
+            bb_period: params.strategy.bb_period,
+            bb_std_dev: params.strategy.bb_std_dev,
+            volume_filter_enabled: params.strategy.volume_filter_enabled,
+            volume_min_ratio: params.strategy.volume_min_ratio,
+            momentum_filter_enabled: params.strategy.momentum_filter_enabled,
+            momentum_roc_period: params.strategy.momentum_roc_period,
+            momentum_min_percentile: params.strategy.momentum_min_percentile,
+            zscore_window: params.strategy.zscore_window,
+            atr_smoothing: params.strategy.atr_smoothing,
+            edge_filter_enabled: params.risk.edge_filter_enabled,
+            edge_atr_multiple: params.risk.edge_atr_multiple,
+            min_expected_profit_pct: params.risk.min_expected_profit_pct,
+            anomaly_zscore: params.risk.anomaly_zscore,
+            skip_entries_on_anomaly: params.risk.skip_entries_on_anomaly,
+            anomaly_quarantine_bars: params.risk.anomaly_quarantine_bars,
+            tighten_stops_on_anomaly_pct: params.risk.tighten_stops_on_anomaly_pct,
+            short_enabled: params.hedge.short_enabled,
+            use_inverse_etf: params.hedge.use_inverse_etf,
+            rsi_overbought_short: params.hedge.rsi_overbought_short,
+            rsi_oversold_short: params.hedge.rsi_oversold_short,
+            short_stop_loss_pct: params.hedge.short_stop_loss.percent().unwrap_or(0.0),
+            short_position_size_pct: params.hedge.short_position_size_pct,
+            concurrent_hedge_policy: params.hedge.concurrent_hedge_policy,
+            flip_to_hedge_on_extreme: params.hedge.flip_to_hedge_on_extreme,
+            flip_to_long_on_oversold: params.hedge.flip_to_long_on_oversold,
+            initial_capital: params.initial_capital,
+            commission: params.execution.commission,
+            slippage_pct: params.execution.slippage_pct,
+            precise_accounting: params.precise_accounting,
+            min_bar_volume: params.execution.min_bar_volume,
+            halted_stop_policy: params.execution.halted_stop_policy,
+            max_trades_per_month: params.risk.max_trades_per_month,
+            max_new_positions_per_day: params.risk.max_new_positions_per_day,
+            min_bars_between_entries: params.risk.min_bars_between_entries,
+            max_portfolio_heat_pct: params.risk.max_portfolio_heat_pct,
+            drawdown_throttle: params.risk.drawdown_throttle,
+            display_utc_offset_minutes: params.display_utc_offset_minutes,
+            metrics_scope: params.metrics_scope,
+            date_ranges: params.date_ranges,
+            omit_range_gaps: params.omit_range_gaps,
+            cash_flows: params.cash_flows,
+            initial_positions: params.initial_positions,
+            execution: params.execution.simulation,
+        }
+    }
+}
+
+impl Default for FlatBacktestParameters {
+    fn default() -> Self {
+        BacktestParameters::default().into()
+    }
+}
@@ -0,0 +1,230 @@
+//! Strategy parameters from environment variables
+//!
+//! Every top-level field of [`BacktestParameters`] is read from
+//! `<prefix><FIELD>` (the field name upper-cased, e.g. `BT_RSI_OVERSOLD=25`)
+//! and every execution setting from `<prefix>EXEC_<FIELD>`
+//! (`BT_EXEC_LATENCY_BARS=1`). Scalars are written plainly; lists, tables
+//! and data-carrying enums as JSON (`BT_SIZING_MODE='{"fixed_notional":5000}'`),
+//! and `none`/`null` clears an optional field.
+//!
+//! When combined with other sources the precedence is, lowest first:
+//! defaults or preset < environment < parameter file < command-line flags.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::config::BacktestParameters;
+use crate::error::{BacktestError, Result};
+
+/// Field holding the execution settings, read from `<prefix>EXEC_<FIELD>`
+const EXECUTION_FIELD: &str = "execution";
+const EXECUTION_PREFIX: &str = "EXEC_";
+
+impl BacktestParameters {
+    /// Default parameters overridden by the `prefix` environment variables
+    pub fn from_env(prefix: &str) -> Result<Self> {
+        Self::default().with_env(prefix)
+    }
+
+    /// Override these parameters with the `prefix` environment variables.
+    ///
+    /// A variable under `prefix` that names no field is an error, as is a
+    /// value that does not parse as its field's type; both name the variable.
+    pub fn with_env(self, prefix: &str) -> Result<Self> {
+        let vars: HashMap<String, String> = std::env::vars()
+            .filter(|(name, _)| name.starts_with(prefix))
+            .collect();
+        self.with_vars(prefix, vars)
+    }
+
+    fn with_vars(self, prefix: &str, mut vars: HashMap<String, String>) -> Result<Self> {
+        let base = serde_json::to_value(&self)?;
+        let mut value = base.clone();
+        let set = take_vars(&mut value, prefix, &mut vars)?;
+
+        if let Some(name) = vars.keys().min() {
+            return Err(BacktestError::ConfigError(format!(
+                "{}: no parameter named {}",
+                name,
+                name[prefix.len()..].to_lowercase()
+            )));
+        }
+
+        serde_json::from_value(value).map_err(|e| {
+            // Name the first variable whose value alone does not deserialize
+            for (name, raw) in &set {
+                let mut single = base.clone();
+                let mut one: HashMap<String, String> =
+                    [(name.clone(), raw.clone())].into_iter().collect();
+                if take_vars(&mut single, prefix, &mut one).is_ok() {
+                    if let Err(e) = serde_json::from_value::<Self>(single) {
+                        return BacktestError::ConfigError(format!("{}={:?}: {}", name, raw, e));
+                    }
+                }
+            }
+            BacktestError::ConfigError(format!("environment parameters: {}", e))
+        })
+    }
+}
+
+fn var_name(prefix: &str, key: &str) -> String {
+    format!("{}{}", prefix, key.to_uppercase())
+}
+
+/// Apply the variables naming fields of `value` (and of its execution
+/// table), removing them from `vars`; returns the variables applied
+fn take_vars(
+    value: &mut Value,
+    prefix: &str,
+    vars: &mut HashMap<String, String>,
+) -> Result<Vec<(String, String)>> {
+    let Value::Object(fields) = value else {
+        unreachable!("parameters serialize to an object");
+    };
+    let nested_prefix = format!("{}{}", prefix, EXECUTION_PREFIX);
+
+    let mut set = Vec::new();
+    for (key, field) in fields.iter_mut() {
+        if key == EXECUTION_FIELD {
+            if let Value::Object(nested) = field {
+                for (key, field) in nested.iter_mut() {
+                    set.extend(take_var(field, var_name(&nested_prefix, key), vars)?);
+                }
+                continue;
+            }
+        }
+        set.extend(take_var(field, var_name(prefix, key), vars)?);
+    }
+    Ok(set)
+}
+
+fn take_var(
+    field: &mut Value,
+    name: String,
+    vars: &mut HashMap<String, String>,
+) -> Result<Option<(String, String)>> {
+    match vars.remove(&name) {
+        Some(raw) => {
+            *field = parse_var(&name, &raw, field)?;
+            Ok(Some((name, raw)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Parse `raw` into the JSON shape of the field's current value
+fn parse_var(name: &str, raw: &str, current: &Value) -> Result<Value> {
+    let error = |expected: &str| {
+        BacktestError::ConfigError(format!("{}={:?}: expected {}", name, raw, expected))
+    };
+    let trimmed = raw.trim();
+    match current {
+        Value::Bool(_) => match trimmed.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(Value::Bool(true)),
+            "false" | "0" | "no" | "off" => Ok(Value::Bool(false)),
+            _ => Err(error("a boolean (true/false)")),
+        },
+        Value::Number(_) => match serde_json::from_str(trimmed) {
+            Ok(Value::Number(n)) => Ok(Value::Number(n)),
+            _ => Err(error("a number")),
+        },
+        // Unit enum variants serialize as strings; other variants are tables
+        Value::String(_) if trimmed.starts_with('{') => {
+            serde_json::from_str(trimmed).map_err(|_| error("a JSON table"))
+        }
+        Value::String(_) => Ok(Value::String(trimmed.to_string())),
+        Value::Null => match trimmed.to_lowercase().as_str() {
+            "" | "none" | "null" => Ok(Value::Null),
+            _ => Ok(serde_json::from_str(trimmed).unwrap_or(Value::String(trimmed.to_string()))),
+        },
+        Value::Array(_) | Value::Object(_) => match trimmed.to_lowercase().as_str() {
+            "none" | "null" => Ok(Value::Null),
+            _ => serde_json::from_str(trimmed).map_err(|_| error("a JSON value")),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{SizingMode, VwapMode};
+
+    /// Sets variables for the lifetime of the guard. Each test uses its own
+    /// prefix since tests share the process environment.
+    struct ScopedEnv(Vec<String>);
+
+    impl ScopedEnv {
+        fn set(vars: &[(&str, &str)]) -> Self {
+            for (name, value) in vars {
+                std::env::set_var(name, value);
+            }
+            Self(vars.iter().map(|(name, _)| name.to_string()).collect())
+        }
+    }
+
+    impl Drop for ScopedEnv {
+        fn drop(&mut self) {
+            for name in &self.0 {
+                std::env::remove_var(name);
+            }
+        }
+    }
+
+    #[test]
+    fn test_env_overrides_defaults() {
+        let _env = ScopedEnv::set(&[
+            ("BTENV1_RSI_OVERSOLD", "25"),
+            ("BTENV1_SHORT_ENABLED", "false"),
+            ("BTENV1_SYMBOL", "SPY"),
+            ("BTENV1_VWAP_MODE", "above"),
+            ("BTENV1_SIZING_MODE", r#"{"fixed_notional": 5000}"#),
+            ("BTENV1_MAX_HOLDING_DAYS", "7"),
+            ("BTENV1_EXEC_LATENCY_BARS", "1"),
+            ("BTENV1_EXEC_ENABLED", "yes"),
+        ]);
+        let params = BacktestParameters::from_env("BTENV1_").unwrap();
+        let defaults = BacktestParameters::default();
+
+        assert_eq!(params.strategy.rsi_oversold, 25.0);
+        assert!(!params.hedge.short_enabled);
+        assert_eq!(params.strategy.symbol, "SPY");
+        assert_eq!(params.strategy.vwap_mode, VwapMode::Above);
+        assert_eq!(params.risk.sizing_mode, SizingMode::FixedNotional(5000.0));
+        assert_eq!(params.strategy.max_holding_days, Some(7));
+        assert_eq!(params.execution.simulation.latency_bars, 1);
+        assert!(params.execution.simulation.enabled);
+        assert_eq!(
+            params.strategy.rsi_overbought,
+            defaults.strategy.rsi_overbought
+        );
+        assert_eq!(
+            params.execution.simulation.market_impact_factor,
+            defaults.execution.simulation.market_impact_factor
+        );
+    }
+
+    #[test]
+    fn test_malformed_env_names_variable() {
+        let message = |vars: &[(&str, &str)]| {
+            let _env = ScopedEnv::set(vars);
+            BacktestParameters::from_env("BTENV2_")
+                .unwrap_err()
+                .to_string()
+        };
+
+        let err = message(&[("BTENV2_RSI_OVERSOLD", "low")]);
+        assert!(err.contains("BTENV2_RSI_OVERSOLD=\"low\""), "{}", err);
+        assert!(err.contains("expected a number"), "{}", err);
+
+        // Parses as a number but not as the field's integer type
+        let err = message(&[
+            ("BTENV2_SMA_PERIOD", "20"),
+            ("BTENV2_EXEC_LATENCY_BARS", "1.5"),
+        ]);
+        assert!(err.contains("BTENV2_EXEC_LATENCY_BARS=\"1.5\""), "{}", err);
+
+        let err = message(&[("BTENV2_RSI_OVERSLD", "25")]);
+        assert!(err.contains("BTENV2_RSI_OVERSLD"), "{}", err);
+    }
+}
@@ -17,6 +17,9 @@ pub enum BacktestError {
     #[error("Invalid parameter: {0}")]
     InvalidParameter(String),
 
+    #[error("Config error: {0}")]
+    ConfigError(String),
+
     #[error("Data loading error: {0}")]
     DataLoadError(String),
 
@@ -28,6 +31,73 @@ pub enum BacktestError {
 
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
+
+    #[error("Cannot write {path}: {reason}")]
+    OutputError { path: String, reason: String },
+}
+
+/// Process exit codes of the command-line tools, one per error class
+pub mod exit_code {
+    /// Data file missing, unreadable or malformed
+    pub const DATA: u8 = 2;
+    /// Invalid parameters, config files or command-line arguments
+    pub const PARAMETERS: u8 = 3;
+    /// Failure while running the backtest itself
+    pub const RUNTIME: u8 = 4;
+    /// Data loaded, but too few bars to get past indicator warmup
+    pub const INSUFFICIENT_DATA: u8 = 5;
+    /// Output file already exists (without `--force`) or cannot be written
+    pub const OUTPUT: u8 = 6;
+}
+
+impl BacktestError {
+    /// Stable snake_case name of the variant, for machine-readable output
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::InsufficientData { .. } => "insufficient_data",
+            Self::InsufficientCash { .. } => "insufficient_cash",
+            Self::NoPositionToClose => "no_position_to_close",
+            Self::PositionAlreadyExists { .. } => "position_already_exists",
+            Self::InvalidParameter(_) => "invalid_parameter",
+            Self::ConfigError(_) => "config_error",
+            Self::DataLoadError(_) => "data_load_error",
+            Self::IoError(_) => "io_error",
+            Self::CsvError(_) => "csv_error",
+            Self::JsonError(_) => "json_error",
+            Self::OutputError { .. } => "output_error",
+        }
+    }
+
+    /// Process exit code for this error (see [`exit_code`])
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::InsufficientData { .. } => exit_code::INSUFFICIENT_DATA,
+            Self::InvalidParameter(_) | Self::ConfigError(_) => exit_code::PARAMETERS,
+            Self::DataLoadError(_) | Self::IoError(_) | Self::CsvError(_) | Self::JsonError(_) => {
+                exit_code::DATA
+            }
+            Self::InsufficientCash { .. }
+            | Self::NoPositionToClose
+            | Self::PositionAlreadyExists { .. } => exit_code::RUNTIME,
+            Self::OutputError { .. } => exit_code::OUTPUT,
+        }
+    }
+
+    /// Structured fields of the variant; empty for message-only variants
+    pub fn details(&self) -> serde_json::Value {
+        match self {
+            Self::InsufficientData { required, actual } => {
+                serde_json::json!({ "required": required, "actual": actual })
+            }
+            Self::InsufficientCash {
+                required,
+                available,
+            } => serde_json::json!({ "required": required, "available": available }),
+            Self::PositionAlreadyExists { symbol } => serde_json::json!({ "symbol": symbol }),
+            Self::OutputError { path, .. } => serde_json::json!({ "path": path }),
+            _ => serde_json::json!({}),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, BacktestError>;
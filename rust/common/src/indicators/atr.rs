@@ -0,0 +1,189 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{gap_reset_starts, Smoothing};
+
+/// Calculate Average True Range
+///
+/// # Arguments
+/// * `highs` - Slice of high prices
+/// * `lows` - Slice of low prices
+/// * `closes` - Slice of closing prices
+/// * `period` - ATR period (typically 14)
+///
+/// # Returns
+/// Vector of ATR values
+pub fn calculate_atr(highs: &[f64], lows: &[f64], closes: &[f64], period: usize) -> Vec<f64> {
+    calculate_atr_with(highs, lows, closes, period, Smoothing::Wilder)
+}
+
+/// Calculate Average True Range with a choice of averaging
+///
+/// The first value (at index `period - 1`) is always the simple average of
+/// the first `period` true ranges; `smoothing` picks how later values roll.
+pub fn calculate_atr_with(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    period: usize,
+    smoothing: Smoothing,
+) -> Vec<f64> {
+    let n = highs.len();
+    if n < 2 || period == 0 {
+        return vec![0.0; n];
+    }
+
+    let mut atr = vec![0.0; n];
+    let alpha = match smoothing {
+        Smoothing::Ema => 2.0 / (period as f64 + 1.0),
+        _ => 1.0 / period as f64,
+    };
+
+    // Calculate True Range
+    let mut tr = vec![0.0; n];
+    tr[0] = highs[0] - lows[0];
+
+    for i in 1..n {
+        let hl = highs[i] - lows[i];
+        let hc = (highs[i] - closes[i - 1]).abs();
+        let lc = (lows[i] - closes[i - 1]).abs();
+        tr[i] = hl.max(hc).max(lc);
+    }
+
+    // Calculate initial ATR as SMA of first 'period' TR values
+    if n >= period {
+        atr[period - 1] = tr[..period].iter().sum::<f64>() / period as f64;
+
+        for i in period..n {
+            atr[i] = match smoothing {
+                Smoothing::Wilder | Smoothing::Ema => atr[i - 1] * (1.0 - alpha) + tr[i] * alpha,
+                Smoothing::Simple => tr[i + 1 - period..=i].iter().sum::<f64>() / period as f64,
+            };
+        }
+    }
+
+    atr
+}
+
+/// Calculate Average True Range, restarting after gap days.
+///
+/// Like [`calculate_atr_with`] run afresh from each bar whose absolute
+/// close-to-close return exceeds `reset_on_gap_pct` percent: the gap bar's
+/// true range is its own high-low range. `None` is [`calculate_atr_with`].
+pub fn calculate_atr_with_gap_reset(
+    highs: &[f64],
+    lows: &[f64],
+    closes: &[f64],
+    period: usize,
+    smoothing: Smoothing,
+    reset_on_gap_pct: Option<f64>,
+) -> Vec<f64> {
+    let starts = gap_reset_starts(closes, reset_on_gap_pct);
+    let mut atr = Vec::with_capacity(highs.len());
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(highs.len());
+        atr.extend(calculate_atr_with(
+            &highs[start..end],
+            &lows[start..end],
+            &closes[start..end],
+            period,
+            smoothing,
+        ));
+    }
+    atr
+}
+
+/// Calculate True Range for a single bar
+pub fn true_range(high: f64, low: f64, prev_close: f64) -> f64 {
+    let hl = high - low;
+    let hc = (high - prev_close).abs();
+    let lc = (low - prev_close).abs();
+    hl.max(hc).max(lc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_atr_basic() {
+        let highs = vec![
+            48.7, 48.72, 48.9, 48.87, 48.82, 49.05, 49.2, 49.35, 49.92, 50.19,
+        ];
+        let lows = vec![
+            47.79, 48.14, 48.39, 48.37, 48.24, 48.64, 48.94, 48.86, 49.5, 49.87,
+        ];
+        let closes = vec![
+            48.16, 48.61, 48.75, 48.63, 48.74, 49.03, 49.07, 49.32, 49.91, 50.13,
+        ];
+
+        let atr = calculate_atr(&highs, &lows, &closes, 5);
+
+        assert_eq!(atr.len(), highs.len());
+        // ATR should be positive
+        for value in atr.iter().skip(4) {
+            assert!(*value > 0.0);
+        }
+    }
+
+    /// Wilder's ATR example as published by StockCharts (first 15 bars)
+    const HIGHS: [f64; 15] = [
+        48.70, 48.72, 48.90, 48.87, 48.82, 49.05, 49.20, 49.35, 49.92, 50.19, 50.12, 49.66, 49.88,
+        50.19, 50.36,
+    ];
+    const LOWS: [f64; 15] = [
+        47.79, 48.14, 48.39, 48.37, 48.24, 48.64, 48.94, 48.86, 49.50, 49.87, 49.20, 48.90, 49.43,
+        49.73, 49.26,
+    ];
+    const CLOSES: [f64; 15] = [
+        48.16, 48.61, 48.75, 48.63, 48.74, 49.03, 49.07, 49.32, 49.91, 50.13, 49.53, 49.50, 49.75,
+        50.03, 50.31,
+    ];
+
+    #[test]
+    fn test_atr_wilder_reference() {
+        let atr = calculate_atr_with(&HIGHS, &LOWS, &CLOSES, 14, Smoothing::Wilder);
+
+        assert!((atr[13] - 0.555).abs() < 0.001);
+        assert!((atr[14] - 0.593).abs() < 0.001);
+        assert_eq!(atr, calculate_atr(&HIGHS, &LOWS, &CLOSES, 14));
+    }
+
+    #[test]
+    fn test_atr_simple_and_ema() {
+        let tr: Vec<f64> = (0..15)
+            .map(|i| {
+                if i == 0 {
+                    HIGHS[0] - LOWS[0]
+                } else {
+                    true_range(HIGHS[i], LOWS[i], CLOSES[i - 1])
+                }
+            })
+            .collect();
+        let seed = tr[..14].iter().sum::<f64>() / 14.0;
+
+        let simple = calculate_atr_with(&HIGHS, &LOWS, &CLOSES, 14, Smoothing::Simple);
+        assert!((simple[13] - seed).abs() < 1e-12);
+        assert!((simple[14] - tr[1..].iter().sum::<f64>() / 14.0).abs() < 1e-12);
+
+        let ema = calculate_atr_with(&HIGHS, &LOWS, &CLOSES, 14, Smoothing::Ema);
+        let alpha = 2.0 / 15.0;
+        assert!((ema[13] - seed).abs() < 1e-12);
+        assert!((ema[14] - (seed * (1.0 - alpha) + tr[14] * alpha)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_true_range() {
+        let tr = true_range(50.0, 48.0, 49.0);
+        // TR should be max(50-48, |50-49|, |48-49|) = max(2, 1, 1) = 2
+        assert_eq!(tr, 2.0);
+    }
+
+    #[test]
+    fn test_true_range_gap_up() {
+        // Gap up scenario
+        let tr = true_range(52.0, 51.0, 48.0);
+        // TR should be max(52-51, |52-48|, |51-48|) = max(1, 4, 3) = 4
+        assert_eq!(tr, 4.0);
+    }
+}
@@ -1,5 +1,14 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::sqrt;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
 /// Bollinger Bands result
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 pub struct BollingerBands {
     pub upper: Vec<f64>,
     pub middle: Vec<f64>,
@@ -35,8 +44,9 @@ pub fn calculate_bollinger_bands(prices: &[f64], period: usize, std_dev: f64) ->
         let mean: f64 = window.iter().sum::<f64>() / period as f64;
 
         // Calculate standard deviation
-        let variance: f64 = window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / period as f64;
-        let std = variance.sqrt();
+        let variance: f64 =
+            window.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / period as f64;
+        let std = sqrt(variance);
 
         bb.middle[i] = mean;
         bb.upper[i] = mean + std * std_dev;
@@ -0,0 +1,98 @@
+//! Pure indicator math over price slices.
+//!
+//! Needs only `core` and `alloc`, so it builds without the `std` feature
+//! (`--no-default-features --features core`) for targets with no standard
+//! library. Everything else in this crate requires `std`.
+
+pub mod atr;
+pub mod bollinger;
+pub mod ema;
+pub mod rsi;
+pub mod sma;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+pub use atr::{calculate_atr, calculate_atr_with, calculate_atr_with_gap_reset, true_range};
+pub use bollinger::{bandwidth, calculate_bollinger_bands, percent_b, BollingerBands};
+pub use ema::{calculate_ema, calculate_ema_with_sma_seed};
+pub use rsi::{calculate_rsi, calculate_rsi_with, calculate_rsi_with_gap_reset};
+pub use sma::{calculate_sma, calculate_sma_filled};
+
+/// Averaging used for RSI gains/losses and ATR true ranges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "snake_case"))]
+pub enum Smoothing {
+    /// Wilder's smoothing, alpha = 1/period (TradingView / StockCharts default)
+    #[default]
+    Wilder,
+    /// Simple moving average over the last `period` values (Cutler's RSI)
+    Simple,
+    /// Exponential moving average, alpha = 2/(period+1)
+    Ema,
+}
+
+/// Indices where a smoothed indicator starts over: 0, then every bar whose
+/// absolute close-to-close return exceeds `threshold_pct` percent
+pub fn gap_reset_starts(closes: &[f64], threshold_pct: Option<f64>) -> Vec<usize> {
+    let mut starts = vec![0];
+    if let Some(threshold) = threshold_pct {
+        starts.extend((1..closes.len()).filter(|&i| {
+            closes[i - 1] != 0.0 && (closes[i] / closes[i - 1] - 1.0).abs() * 100.0 > threshold
+        }));
+    }
+    starts
+}
+
+#[cfg(feature = "std")]
+fn sqrt(x: f64) -> f64 {
+    x.sqrt()
+}
+
+/// Square root by Newton's method, as `core` has no `f64::sqrt`. Agrees with
+/// the hardware root to within an ulp or two for normal inputs.
+#[cfg(not(feature = "std"))]
+fn sqrt(x: f64) -> f64 {
+    if x.is_nan() || x < 0.0 {
+        return f64::NAN;
+    }
+    if x == 0.0 || x == f64::INFINITY {
+        return x;
+    }
+    // Halving the exponent bits gives a first guess within a few percent
+    let mut root = f64::from_bits((x.to_bits() >> 1) + (1023 << 51));
+    for _ in 0..8 {
+        root = 0.5 * (root + x / root);
+    }
+    root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqrt() {
+        for x in [1e-12_f64, 0.04, 1.0, 2.0, 12.25, 1e9, 3.7e150] {
+            let expected = x.sqrt();
+            assert!(
+                (sqrt(x) - expected).abs() <= expected * 1e-15,
+                "sqrt({})",
+                x
+            );
+        }
+        assert_eq!(sqrt(0.0), 0.0);
+        assert!(sqrt(-1.0).is_nan());
+    }
+
+    #[test]
+    fn test_gap_reset_starts() {
+        let closes = [100.0, 101.0, 110.0, 109.0, 98.0];
+        assert_eq!(gap_reset_starts(&closes, None), vec![0]);
+        assert_eq!(gap_reset_starts(&closes, Some(5.0)), vec![0, 2, 4]);
+    }
+}
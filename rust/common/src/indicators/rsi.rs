@@ -0,0 +1,243 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{gap_reset_starts, Smoothing};
+
+/// Calculate RSI using Wilder's Smoothing (Exponential Moving Average)
+///
+/// # Arguments
+/// * `prices` - Slice of closing prices
+/// * `period` - RSI period (typically 2 for this strategy)
+///
+/// # Returns
+/// Vector of RSI values (same length as input, with warmup period values set to 50.0)
+pub fn calculate_rsi(prices: &[f64], period: usize) -> Vec<f64> {
+    calculate_rsi_with(prices, period, Smoothing::Wilder)
+}
+
+/// Calculate RSI with a choice of gain/loss averaging
+///
+/// * `Wilder` - Wilder's smoothing, alpha = 1/period (TradingView / StockCharts)
+/// * `Simple` - Cutler's RSI, simple average of the last `period` changes
+/// * `Ema` - exponential average, alpha = 2/(period+1)
+///
+/// Every mode seeds the first value (at index `period`) with simple averages
+/// of the first `period` changes; earlier values are set to 50.0.
+pub fn calculate_rsi_with(prices: &[f64], period: usize, smoothing: Smoothing) -> Vec<f64> {
+    let n = prices.len();
+    if n < period + 1 {
+        return vec![50.0; n];
+    }
+
+    let mut rsi = vec![50.0; n];
+
+    // Per-bar gains and losses (index 0 has no prior close)
+    let mut gains = vec![0.0; n];
+    let mut losses = vec![0.0; n];
+    for i in 1..n {
+        let delta = prices[i] - prices[i - 1];
+        if delta > 0.0 {
+            gains[i] = delta;
+        } else {
+            losses[i] = delta.abs();
+        }
+    }
+
+    // Calculate initial averages
+    let mut avg_gain = gains[1..=period].iter().sum::<f64>() / period as f64;
+    let mut avg_loss = losses[1..=period].iter().sum::<f64>() / period as f64;
+    rsi[period] = rsi_from_averages(avg_gain, avg_loss);
+
+    let alpha = match smoothing {
+        Smoothing::Ema => 2.0 / (period as f64 + 1.0),
+        _ => 1.0 / period as f64,
+    };
+
+    for i in (period + 1)..n {
+        match smoothing {
+            Smoothing::Wilder | Smoothing::Ema => {
+                avg_gain = avg_gain * (1.0 - alpha) + gains[i] * alpha;
+                avg_loss = avg_loss * (1.0 - alpha) + losses[i] * alpha;
+            }
+            Smoothing::Simple => {
+                let window = (i + 1 - period)..=i;
+                avg_gain = gains[window.clone()].iter().sum::<f64>() / period as f64;
+                avg_loss = losses[window].iter().sum::<f64>() / period as f64;
+            }
+        }
+        rsi[i] = rsi_from_averages(avg_gain, avg_loss);
+    }
+
+    rsi
+}
+
+/// Calculate RSI, restarting after gap days.
+///
+/// After each bar whose absolute close-to-close return exceeds
+/// `reset_on_gap_pct` percent, the RSI starts over from that bar as if it
+/// were the first of the series: the next `period` values are 50.0 and the
+/// averages re-seed from the following `period` changes, so the gap itself
+/// never enters them. `None` is [`calculate_rsi_with`].
+pub fn calculate_rsi_with_gap_reset(
+    prices: &[f64],
+    period: usize,
+    smoothing: Smoothing,
+    reset_on_gap_pct: Option<f64>,
+) -> Vec<f64> {
+    let starts = gap_reset_starts(prices, reset_on_gap_pct);
+    let mut rsi = Vec::with_capacity(prices.len());
+    for (i, &start) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).copied().unwrap_or(prices.len());
+        rsi.extend(calculate_rsi_with(&prices[start..end], period, smoothing));
+    }
+    rsi
+}
+
+fn rsi_from_averages(avg_gain: f64, avg_loss: f64) -> f64 {
+    if avg_loss == 0.0 {
+        100.0
+    } else {
+        let rs = avg_gain / avg_loss;
+        100.0 - (100.0 / (1.0 + rs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rsi_basic() {
+        let prices = vec![
+            44.0, 44.25, 44.5, 43.75, 44.5, 44.25, 44.0, 43.5, 44.25, 44.5,
+        ];
+        let rsi = calculate_rsi(&prices, 2);
+
+        assert_eq!(rsi.len(), prices.len());
+        // First values should be default
+        assert_eq!(rsi[0], 50.0);
+        assert_eq!(rsi[1], 50.0);
+        // RSI should be between 0 and 100
+        for val in &rsi {
+            assert!(*val >= 0.0 && *val <= 100.0);
+        }
+    }
+
+    #[test]
+    fn test_rsi_all_gains() {
+        let prices = vec![10.0, 11.0, 12.0, 13.0, 14.0, 15.0];
+        let rsi = calculate_rsi(&prices, 2);
+
+        // All gains should result in RSI = 100
+        assert_eq!(rsi[rsi.len() - 1], 100.0);
+    }
+
+    /// StockCharts' published 14-day RSI example (Wilder smoothing). The
+    /// published sheet works from unrounded closes, so the two-decimal
+    /// closes here land within 0.1 of its RSI column.
+    const STOCKCHARTS_CLOSES: [f64; 33] = [
+        44.34, 44.09, 44.15, 43.61, 44.33, 44.83, 45.10, 45.42, 45.84, 46.08, 45.89, 46.03, 45.61,
+        46.28, 46.28, 46.00, 46.03, 46.41, 46.22, 45.64, 46.21, 46.25, 45.71, 46.45, 45.78, 45.35,
+        44.03, 44.18, 44.22, 44.57, 43.42, 42.66, 43.13,
+    ];
+
+    #[test]
+    fn test_rsi_wilder_reference() {
+        let expected = [
+            70.53, 66.32, 66.55, 69.41, 66.36, 57.97, 62.93, 63.26, 56.06, 62.38, 54.71, 50.42,
+            39.99, 41.46, 41.87, 45.46, 37.30, 33.08, 37.77,
+        ];
+        let rsi = calculate_rsi_with(&STOCKCHARTS_CLOSES, 14, Smoothing::Wilder);
+
+        for (i, want) in expected.iter().enumerate() {
+            assert!(
+                (rsi[14 + i] - want).abs() < 0.1,
+                "RSI[{}] = {:.4}, expected {}",
+                14 + i,
+                rsi[14 + i],
+                want
+            );
+        }
+        assert_eq!(rsi, calculate_rsi(&STOCKCHARTS_CLOSES, 14));
+    }
+
+    #[test]
+    fn test_rsi_simple_is_cutler() {
+        let rsi = calculate_rsi_with(&STOCKCHARTS_CLOSES, 14, Smoothing::Simple);
+
+        // Same simple seed as Wilder
+        assert!((rsi[14] - 70.53).abs() < 0.1);
+        // Then a plain 14-change window: changes 2..=15
+        let (mut gain, mut loss) = (0.0, 0.0);
+        for i in 2..=15 {
+            let delta: f64 = STOCKCHARTS_CLOSES[i] - STOCKCHARTS_CLOSES[i - 1];
+            if delta > 0.0 {
+                gain += delta;
+            } else {
+                loss -= delta;
+            }
+        }
+        let expected = 100.0 - 100.0 / (1.0 + gain / loss);
+        assert!((rsi[15] - expected).abs() < 1e-9);
+        assert!((rsi[15] - 70.02).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_rsi_ema_alpha() {
+        let rsi = calculate_rsi_with(&STOCKCHARTS_CLOSES, 14, Smoothing::Ema);
+        let wilder = calculate_rsi(&STOCKCHARTS_CLOSES, 14);
+
+        assert!((rsi[14] - wilder[14]).abs() < 1e-12);
+        // The next change is a 0.28 loss; EMA weights it at 2/15 instead of 1/14
+        let alpha = 2.0 / 15.0;
+        let seed_gain = 3.34 / 14.0;
+        let seed_loss = 1.40 / 14.0;
+        let expected = 100.0
+            - 100.0
+                / (1.0 + (seed_gain * (1.0 - alpha)) / (seed_loss * (1.0 - alpha) + 0.28 * alpha));
+        assert!((rsi[15] - expected).abs() < 1e-9);
+        assert!(rsi[15] < wilder[15]);
+    }
+
+    #[test]
+    fn test_rsi_gap_reset() {
+        // Choppy around 100, a -40% gap, then choppy around 60
+        let mut prices: Vec<f64> = (0..40)
+            .map(|i| if i % 2 == 0 { 100.0 } else { 101.0 })
+            .collect();
+        let gap = prices.len();
+        prices.extend((0..40).map(|i| if i % 2 == 0 { 60.0 } else { 60.6 }));
+
+        let plain = calculate_rsi_with(&prices, 14, Smoothing::Wilder);
+        let reset = calculate_rsi_with_gap_reset(&prices, 14, Smoothing::Wilder, Some(20.0));
+        assert_eq!(reset.len(), prices.len());
+        assert_eq!(reset[..gap], plain[..gap]);
+
+        // Re-seeding: neutral, then back in the chop's range by period + 1
+        assert!(reset[gap..gap + 14].iter().all(|v| *v == 50.0));
+        for value in &reset[gap + 14..] {
+            assert!((40.0..=60.0).contains(value), "{}", value);
+        }
+        // Without the reset the gap pins RSI low for many bars
+        let pinned = plain[gap..].iter().take_while(|v| **v < 30.0).count();
+        assert!(pinned > 20, "{}", pinned);
+
+        assert_eq!(
+            calculate_rsi_with_gap_reset(&prices, 14, Smoothing::Wilder, None),
+            plain
+        );
+        assert_eq!(
+            calculate_rsi_with_gap_reset(&prices, 14, Smoothing::Wilder, Some(50.0)),
+            plain
+        );
+    }
+
+    #[test]
+    fn test_rsi_all_losses() {
+        let prices = vec![15.0, 14.0, 13.0, 12.0, 11.0, 10.0];
+        let rsi = calculate_rsi(&prices, 2);
+
+        // All losses should result in RSI = 0
+        assert_eq!(rsi[rsi.len() - 1], 0.0);
+    }
+}
@@ -1,7 +1,40 @@
+//! Types shared by the backtest engine and its tools.
+//!
+//! With the default `std` feature this is the whole crate. Built with
+//! `--no-default-features --features core` only [`indicators`] remains,
+//! under `#![no_std]` with `alloc`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
 pub mod config;
+#[cfg(feature = "std")]
+mod env;
+#[cfg(feature = "std")]
 pub mod error;
+#[cfg(feature = "core")]
+pub mod indicators;
+#[cfg(feature = "std")]
+pub mod manifest;
+#[cfg(feature = "std")]
+pub mod presets;
+#[cfg(feature = "std")]
 pub mod types;
 
-pub use config::{BacktestParameters, RealisticExecutionConfig};
-pub use error::{BacktestError, Result};
+#[cfg(feature = "std")]
+pub use config::{
+    AdaptiveSizing, BacktestParameters, ConcurrentHedgePolicy, DirectionMode, ExecutionParams,
+    ExitDecay, HaltedStopPolicy, HedgeParams, MetricsScope, PartialFillPolicy, PartialParameters,
+    ProfitAdjustedExit, RealisticExecutionConfig, RiskParams, SameBarExit, SizingMode,
+    SmaSlopeFilter, Smoothing, StopSpec, StrategyParams, SymbolOverrides, VwapMode,
+};
+#[cfg(feature = "std")]
+pub use error::{exit_code, BacktestError, Result};
+#[cfg(feature = "std")]
+pub use manifest::{DataSource, RunManifest};
+#[cfg(feature = "std")]
+pub use presets::Preset;
+#[cfg(feature = "std")]
 pub use types::*;
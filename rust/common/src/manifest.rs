@@ -0,0 +1,213 @@
+//! Provenance of a backtest result: what produced it, and from which data
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::config::BacktestParameters;
+use crate::types::Bar;
+
+/// The bars a run consumed
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataSource {
+    /// Where the bars came from: file paths, or how they were generated
+    #[serde(default)]
+    pub description: Option<String>,
+    pub bars: usize,
+    pub first_timestamp: Option<DateTime<Utc>>,
+    pub last_timestamp: Option<DateTime<Utc>>,
+    /// Fingerprint of every bar field; see [`content_hash`]
+    pub content_hash: String,
+}
+
+impl DataSource {
+    pub fn from_bars(bars: &[Bar]) -> Self {
+        Self {
+            description: None,
+            bars: bars.len(),
+            first_timestamp: bars.first().map(|b| b.timestamp),
+            last_timestamp: bars.last().map(|b| b.timestamp),
+            content_hash: content_hash(bars),
+        }
+    }
+
+    pub fn with_description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+}
+
+/// 64-bit FNV-1a over the bars' timestamps, prices, volumes and VWAPs, as
+/// hex. Unlike `std`'s hasher it is the same on every platform and release.
+pub fn content_hash(bars: &[Bar]) -> String {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    let mut hash = OFFSET;
+    let mut feed = |bytes: [u8; 8]| {
+        for byte in bytes {
+            hash = (hash ^ byte as u64).wrapping_mul(PRIME);
+        }
+    };
+    for bar in bars {
+        feed(bar.timestamp.timestamp_millis().to_le_bytes());
+        for price in [bar.open, bar.high, bar.low, bar.close] {
+            feed(price.to_bits().to_le_bytes());
+        }
+        feed(bar.volume.to_le_bytes());
+        feed(bar.vwap.map_or(u64::MAX, f64::to_bits).to_le_bytes());
+    }
+    format!("{:016x}", hash)
+}
+
+/// Everything needed to reproduce a result
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// Fully resolved parameters, execution settings included
+    pub parameters: BacktestParameters,
+    /// Version of the engine that ran
+    pub crate_version: String,
+    /// Commit the engine was built from, when known at build time
+    #[serde(default)]
+    pub git_hash: Option<String>,
+    pub data: DataSource,
+    #[serde(default)]
+    pub hedge_data: Option<DataSource>,
+    /// Seed of the execution simulator's draws (fresh entropy when None)
+    #[serde(default)]
+    pub execution_seed: Option<u64>,
+    #[serde(default)]
+    pub hostname: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RunManifest {
+    /// Ways `other` was produced differently that make the two results not
+    /// directly comparable: changed parameters (by name) and changed data
+    pub fn differences(&self, other: &RunManifest) -> Vec<String> {
+        let mut differences = Vec::new();
+
+        let ours = serde_json::to_value(&self.parameters).unwrap_or_default();
+        let theirs = serde_json::to_value(&other.parameters).unwrap_or_default();
+        let mut changed = Vec::new();
+        diff_fields("", &ours, &theirs, &mut changed);
+        if !changed.is_empty() {
+            differences.push(format!("parameters differ: {}", changed.join(", ")));
+        }
+
+        if self.data.content_hash != other.data.content_hash {
+            differences.push(format!(
+                "data differs: {} bars hashing {} vs {} bars hashing {}",
+                self.data.bars, self.data.content_hash, other.data.bars, other.data.content_hash
+            ));
+        }
+        let hedge_hash = |m: &RunManifest| m.hedge_data.as_ref().map(|d| d.content_hash.clone());
+        if hedge_hash(self) != hedge_hash(other) {
+            differences.push("hedge data differs".to_string());
+        }
+        differences
+    }
+}
+
+/// Names of the fields (dotted within tables) whose values differ
+fn diff_fields(path: &str, ours: &Value, theirs: &Value, changed: &mut Vec<String>) {
+    match (ours, theirs) {
+        (Value::Object(a), Value::Object(b)) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let name = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                let missing = Value::Null;
+                diff_fields(
+                    &name,
+                    a.get(key).unwrap_or(&missing),
+                    b.get(key).unwrap_or(&missing),
+                    changed,
+                );
+            }
+        }
+        (a, b) if a != b => changed.push(path.to_string()),
+        _ => {}
+    }
+}
+
+/// Name of this machine, read once per process
+pub fn hostname() -> Option<String> {
+    static HOSTNAME: OnceLock<Option<String>> = OnceLock::new();
+    HOSTNAME
+        .get_or_init(|| {
+            std::env::var("HOSTNAME")
+                .ok()
+                .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+        })
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn bars() -> Vec<Bar> {
+        (0..5)
+            .map(|i| {
+                let t = Utc.with_ymd_and_hms(2024, 1, 1 + i, 0, 0, 0).unwrap();
+                let price = 100.0 + i as f64;
+                Bar::new(t, price, price + 1.0, price - 1.0, price, 1_000)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_content_hash_tracks_every_bar() {
+        let original = bars();
+        let source = DataSource::from_bars(&original);
+        assert_eq!(source.bars, 5);
+        assert_eq!(source.first_timestamp, Some(original[0].timestamp));
+        assert_eq!(source.content_hash, content_hash(&bars()));
+
+        let mut changed = bars();
+        changed[3].close += 0.01;
+        assert_ne!(content_hash(&changed), source.content_hash);
+
+        let mut vwap = bars();
+        vwap[0].vwap = Some(100.0);
+        assert_ne!(content_hash(&vwap), source.content_hash);
+        assert_ne!(content_hash(&original[..4]), source.content_hash);
+    }
+
+    #[test]
+    fn test_manifest_differences() {
+        let manifest = RunManifest {
+            data: DataSource::from_bars(&bars()),
+            ..Default::default()
+        };
+        assert!(manifest.differences(&manifest.clone()).is_empty());
+
+        let mut other = manifest.clone();
+        other.parameters.strategy.rsi_oversold = 25.0;
+        other.parameters.execution.simulation.latency_bars = 2;
+        let mut changed = bars();
+        changed[0].volume = 0;
+        other.data = DataSource::from_bars(&changed);
+        // Not provenance of the numbers themselves
+        other.hostname = Some("elsewhere".to_string());
+
+        let differences = manifest.differences(&other);
+        assert_eq!(differences.len(), 2, "{:?}", differences);
+        assert_eq!(
+            differences[0],
+            "parameters differ: execution.latency_bars, rsi_oversold"
+        );
+        assert!(differences[1].starts_with("data differs"));
+    }
+}
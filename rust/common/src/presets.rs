@@ -0,0 +1,188 @@
+//! Named parameter presets
+//!
+//! Every preset is reachable through [`Preset::ALL`], so tooling (CLI listing,
+//! validation tests) picks up new presets without further wiring.
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::{
+    BacktestParameters, HedgeParams, RiskParams, StopSpec, StrategyParams, VwapMode,
+};
+
+/// Shippable strategy presets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    Conservative,
+    Aggressive,
+    ConnorsClassic,
+}
+
+impl Preset {
+    /// All presets, in display order
+    pub const ALL: [Preset; 3] = [
+        Preset::Conservative,
+        Preset::Aggressive,
+        Preset::ConnorsClassic,
+    ];
+
+    /// CLI / config name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Conservative => "conservative",
+            Preset::Aggressive => "aggressive",
+            Preset::ConnorsClassic => "connors-classic",
+        }
+    }
+
+    /// One-line description of the preset's intent
+    pub fn description(&self) -> &'static str {
+        match self {
+            Preset::Conservative => "Deep dips only, half-size positions, tight stop, no hedge",
+            Preset::Aggressive => "Shallow dips, near-full size, wide stop, SQQQ hedge enabled",
+            Preset::ConnorsClassic => {
+                "Connors RSI(2): buy < 10 above 200-SMA, sell > 90 or after 5 days, no stop"
+            }
+        }
+    }
+
+    /// Build the parameter set for this preset
+    pub fn parameters(&self) -> BacktestParameters {
+        match self {
+            Preset::Conservative => BacktestParameters::preset_conservative(),
+            Preset::Aggressive => BacktestParameters::preset_aggressive(),
+            Preset::ConnorsClassic => BacktestParameters::preset_connors_classic(),
+        }
+    }
+
+    /// Look up a preset by its CLI name
+    pub fn from_name(name: &str) -> Option<Preset> {
+        Preset::ALL.iter().copied().find(|p| p.name() == name)
+    }
+}
+
+impl BacktestParameters {
+    /// Capital preservation first.
+    ///
+    /// Waits for deeper RSI(2) dips (<= 20) inside a 50-bar uptrend, commits
+    /// half of the deployable cash, and cuts losers at 3%. Hedging is off so
+    /// the account is never exposed to inverse-ETF decay.
+    pub fn preset_conservative() -> Self {
+        Self {
+            strategy: StrategyParams {
+                rsi_oversold: 20.0,
+                rsi_overbought: 70.0,
+                sma_period: 50,
+                ..Default::default()
+            },
+            risk: RiskParams {
+                stop_loss: StopSpec::Percent(0.03),
+                position_size_pct: 0.50,
+                cash_reserve_pct: 0.20,
+                ..Default::default()
+            },
+            hedge: HedgeParams {
+                short_enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Maximum participation.
+    ///
+    /// Buys shallow dips (RSI <= 35), rides them to RSI 80 with a wide 8% stop,
+    /// and keeps almost no cash in reserve. Extreme overbought readings are
+    /// hedged with the inverse ETF.
+    pub fn preset_aggressive() -> Self {
+        Self {
+            strategy: StrategyParams {
+                rsi_oversold: 35.0,
+                rsi_overbought: 80.0,
+                sma_period: 10,
+                ..Default::default()
+            },
+            risk: RiskParams {
+                stop_loss: StopSpec::Percent(0.08),
+                position_size_pct: 0.95,
+                cash_reserve_pct: 0.05,
+                ..Default::default()
+            },
+            hedge: HedgeParams {
+                short_enabled: true,
+                rsi_overbought_short: 85.0,
+                short_position_size_pct: 0.40,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Larry Connors' classic RSI(2) rules.
+    ///
+    /// Long when RSI(2) < 10 and the close is above the 200-day SMA, exit
+    /// when RSI(2) > 90 or after 5 days in the trade. The original rules use
+    /// no stop loss and no VWAP filter.
+    pub fn preset_connors_classic() -> Self {
+        Self {
+            strategy: StrategyParams {
+                rsi_period: 2,
+                rsi_oversold: 10.0,
+                rsi_overbought: 90.0,
+                sma_period: 200,
+                max_holding_days: Some(5),
+                vwap_mode: VwapMode::Disabled,
+                ..Default::default()
+            },
+            risk: RiskParams {
+                stop_loss: StopSpec::None,
+                ..Default::default()
+            },
+            hedge: HedgeParams {
+                short_enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_presets_validate() {
+        for preset in Preset::ALL {
+            preset
+                .parameters()
+                .validate()
+                .unwrap_or_else(|e| panic!("preset {} is invalid: {}", preset.name(), e));
+        }
+    }
+
+    #[test]
+    fn test_preset_names_round_trip() {
+        for preset in Preset::ALL {
+            assert_eq!(Preset::from_name(preset.name()), Some(preset));
+        }
+        assert_eq!(Preset::from_name("yolo"), None);
+    }
+
+    #[test]
+    fn test_connors_classic_rules() {
+        let params = BacktestParameters::preset_connors_classic();
+        assert_eq!(params.strategy.rsi_period, 2);
+        assert_eq!(params.strategy.rsi_oversold, 10.0);
+        assert_eq!(params.strategy.rsi_overbought, 90.0);
+        assert_eq!(params.strategy.sma_period, 200);
+        assert_eq!(params.strategy.max_holding_days, Some(5));
+    }
+
+    #[test]
+    fn test_validate_rejects_inverted_thresholds() {
+        let params = BacktestParameters::default().with_rsi_thresholds(80.0, 20.0);
+        assert!(params.validate().is_err());
+        assert!(BacktestParameters::default().validate().is_ok());
+    }
+}
@@ -1,6 +1,9 @@
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::config::{MetricsScope, SizingMode, StopSpec};
+use crate::manifest::RunManifest;
+
 /// OHLCV bar data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bar {
@@ -12,6 +15,9 @@ pub struct Bar {
     pub volume: u64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub vwap: Option<f64>,
+    /// Trading was halted (e.g. limit-down): nothing fills on this bar
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub halted: bool,
 }
 
 impl Bar {
@@ -31,6 +37,7 @@ impl Bar {
             close,
             volume,
             vwap: None,
+            halted: false,
         }
     }
 }
@@ -45,6 +52,9 @@ pub enum Side {
     Cover,
     HedgeBuy,
     HedgeSell,
+    /// Opening and closing a cash-settled synthetic short
+    SyntheticShort,
+    SyntheticCover,
 }
 
 /// Signal type
@@ -74,6 +84,184 @@ pub struct Signal {
     pub vwap: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sma: Option<f64>,
+    /// Order the engine placed on this signal, once acted on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<u64>,
+    /// Consecutive bars the entry condition had held, on entries that
+    /// required confirmation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirmation_bars: Option<usize>,
+}
+
+/// An order of the engine's that filled
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    pub order_id: u64,
+    pub timestamp: DateTime<Utc>,
+    pub symbol: String,
+    pub side: Side,
+    pub quantity: f64,
+    pub price: f64,
+    /// Execution simulator notes: partial fills, volume caps
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// Signal that fired but was not acted on by the engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuppressedSignal {
+    pub timestamp: DateTime<Utc>,
+    pub signal_type: SignalType,
+    pub symbol: String,
+    pub price: f64,
+    pub rsi: f64,
+    /// Why the engine declined to act on the signal
+    pub reason: String,
+}
+
+/// Entry a pre-trade check rejected or shrank
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PreTradeEvent {
+    pub timestamp: DateTime<Utc>,
+    pub symbol: String,
+    pub side: Side,
+    /// Name of the check
+    pub check: String,
+    pub requested_quantity: f64,
+    /// Quantity the check let through; 0 for a rejection
+    pub approved_quantity: f64,
+    /// The check's reason for a rejection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Category of a data quality warning
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataWarningKind {
+    HaltedBar,
+    NonMonotonicTimestamp,
+    InvalidPrice,
+    InvalidRange,
+    /// A stretch of bars scoring below `min_data_quality`
+    LowDataQuality,
+    /// Median close outside `sane_price_range` (e.g. prices in cents)
+    PriceScale,
+    /// A supplementary column (vwap) on a different scale than the closes
+    SupplementaryScale,
+    /// Volume that looks like dollar volume rather than shares
+    DollarVolume,
+    /// Prices were divided by a detected scale factor on load
+    PricesRescaled,
+    /// `slippage_pct` set alongside the execution simulation, which
+    /// replaces it
+    FlatSlippageIgnored,
+    /// The run could not stream to its spill directory; the message says
+    /// whether it was kept in memory instead or records are missing
+    SpillUnavailable,
+    /// The parameters fail validation; the engine ran with them regardless
+    InvalidParameters,
+    /// The indicator cache could not be read or written; the indicators
+    /// were computed instead
+    IndicatorCacheUnavailable,
+}
+
+/// Data quality issue found while validating bars
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataWarning {
+    pub kind: DataWarningKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    pub message: String,
+}
+
+/// Dollar amount held as whole cents, for deterministic cash accounting.
+///
+/// Conversions from dollars (and `quantity × price` products) round to the
+/// nearest cent, halves away from zero. Addition and subtraction are exact
+/// and panic on overflow; `checked_*` variants return `None` instead.
+/// Serializes as a dollar `f64`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub fn from_cents(cents: i64) -> Self {
+        Money(cents)
+    }
+
+    /// Round a dollar amount to the nearest cent
+    pub fn from_dollars(dollars: f64) -> Self {
+        Money((dollars * 100.0).round() as i64)
+    }
+
+    /// Value of `quantity` units at `price`, rounded to the nearest cent
+    pub fn notional(quantity: f64, price: f64) -> Self {
+        Self::from_dollars(quantity * price)
+    }
+
+    pub fn cents(self) -> i64 {
+        self.0
+    }
+
+    pub fn to_dollars(self) -> f64 {
+        self.0 as f64 / 100.0
+    }
+
+    pub fn checked_add(self, other: Money) -> Option<Money> {
+        self.0.checked_add(other.0).map(Money)
+    }
+
+    pub fn checked_sub(self, other: Money) -> Option<Money> {
+        self.0.checked_sub(other.0).map(Money)
+    }
+}
+
+impl std::ops::Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        self.checked_add(other).expect("Money overflow")
+    }
+}
+
+impl std::ops::Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        self.checked_sub(other).expect("Money overflow")
+    }
+}
+
+impl std::ops::AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl std::ops::SubAssign for Money {
+    fn sub_assign(&mut self, other: Money) {
+        *self = *self - other;
+    }
+}
+
+impl std::iter::Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, |acc, m| acc + m)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_dollars())
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        f64::deserialize(deserializer).map(Money::from_dollars)
+    }
 }
 
 /// Position side
@@ -95,6 +283,26 @@ pub struct Position {
     pub current_price: f64,
     pub side: PositionSide,
     pub stop_loss_price: Option<f64>,
+    /// Stop at entry, kept when `stop_loss_price` later moves; defines 1R
+    #[serde(default)]
+    pub initial_stop_price: Option<f64>,
+    /// Stop setting `initial_stop_price` was resolved from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_spec: Option<StopSpec>,
+    /// Signal that opened the position
+    #[serde(default)]
+    pub entry_reason: String,
+    #[serde(default)]
+    pub entry_rsi: Option<f64>,
+    /// Drawdown throttle multiplier applied to the entry size, when throttling
+    #[serde(default)]
+    pub size_multiplier: Option<f64>,
+    /// Order that opened the position (None for seeded positions)
+    #[serde(default)]
+    pub entry_order_id: Option<u64>,
+    /// Commission paid buying the shares held, charged to the trade
+    #[serde(default)]
+    pub entry_commission: f64,
 }
 
 impl Position {
@@ -117,6 +325,32 @@ impl Position {
     }
 }
 
+/// One tranche of an exit filled over several bars
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExitFill {
+    pub timestamp: DateTime<Utc>,
+    pub quantity: f64,
+    pub price: f64,
+}
+
+/// Indicator state of the bar a trade opened or closed on
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IndicatorSnapshot {
+    pub rsi: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sma: Option<f64>,
+    pub ema: f64,
+    pub atr: f64,
+    pub bb_upper: f64,
+    pub bb_middle: f64,
+    pub bb_lower: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vwap: Option<f64>,
+    /// Bar volume over its trailing average
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volume_ratio: Option<f64>,
+}
+
 /// Individual trade record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
@@ -128,23 +362,144 @@ pub struct Trade {
     pub exit_price: Option<f64>,
     pub quantity: f64,
     pub side: Side,
+    /// Net P&L, the same as `net_pnl`
     pub pnl: f64,
     pub pnl_pct: f64,
+    /// Price difference times quantity, before commissions. Fill prices
+    /// already carry the modeled slippage and spread.
+    #[serde(default)]
+    pub gross_pnl: f64,
+    /// Gross P&L less the entry and exit commissions
+    #[serde(default)]
+    pub net_pnl: f64,
     pub holding_days: i64,
     pub entry_reason: String,
     pub exit_reason: String,
+    /// RSI of the entry / exit signal bar, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_rsi: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_rsi: Option<f64>,
+    /// Dollars at risk to the initial stop, and P&L in multiples of it.
+    /// None for trades opened without a stop.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_risk: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r_multiple: Option<f64>,
+    /// Stop price resolved at entry and the setting it came from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_stop_price: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_spec: Option<StopSpec>,
+    /// Bar whose close triggered a stop that filled later (`exit_date`)
+    /// because of order latency
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_triggered_at: Option<DateTime<Utc>>,
+    /// Drawdown throttle multiplier in effect at entry, when throttling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_multiplier: Option<f64>,
+    /// Orders that opened and closed the position; see `BacktestResult::trace`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_order_id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_order_id: Option<u64>,
+    /// Exit tranches, first to last, when the volume cap spread the exit
+    /// over several bars; `exit_date` is the last one's and `exit_price`
+    /// their volume-weighted average
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fills: Vec<ExitFill>,
+    /// Indicators on the entry and exit bars, with `record_trade_context`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_context: Option<IndicatorSnapshot>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_context: Option<IndicatorSnapshot>,
+    /// Where the entry / exit price sat in the range of the bars held, 1
+    /// being the best price of the holding period and 0 the worst. Set by
+    /// the engine once the trade has closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry_efficiency: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_efficiency: Option<f64>,
+}
+
+impl Trade {
+    /// Whether this trade was the hedge (inverse ETF or synthetic short)
+    /// rather than the main symbol position
+    pub fn is_hedge(&self) -> bool {
+        matches!(
+            self.side,
+            Side::HedgeBuy | Side::HedgeSell | Side::SyntheticShort | Side::SyntheticCover
+        )
+    }
+}
+
+/// Trades whose R-multiple falls in `[lower_r, lower_r + 1)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RBucket {
+    pub lower_r: f64,
+    pub count: u32,
+}
+
+/// Trades whose worst overnight gap (%) falls in `[lower_pct, lower_pct + 1)`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GapBucket {
+    pub lower_pct: f64,
+    pub count: u32,
+}
+
+/// Dependence between consecutive trade outcomes, trades taken in exit
+/// order. A trade wins when its P&L is positive; anything else is a loss.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SequenceStats {
+    /// Lag-1 correlation of consecutive P&L signs (+1 win, -1 loss);
+    /// 0 with fewer than three trades or a single outcome
+    pub sign_correlation: f64,
+    /// Runs of consecutive wins or losses, and the number expected if
+    /// outcomes were independent
+    pub runs: u32,
+    pub expected_runs: f64,
+    /// Wald-Wolfowitz runs test: negative means streakier than chance,
+    /// positive means more alternation; 0 with a single outcome
+    pub runs_z_score: f64,
+    /// Win rate (%) of trades straight after a win / after a loss; None
+    /// when no trade follows one
+    pub win_after_win_pct: Option<f64>,
+    pub win_after_loss_pct: Option<f64>,
+    /// Trades sharing their exit time with the previous trade, whose
+    /// relative order is only the order they were recorded in
+    pub tied_exits: u32,
+}
+
+/// Read a ratio that is infinite when its denominator is zero. JSON has no
+/// infinity, so it was written as null.
+fn deserialize_ratio<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<f64>::deserialize(deserializer)?.unwrap_or(f64::INFINITY))
 }
 
 /// Performance metrics
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
     // Returns
+    /// Dollar P&L: final equity less initial capital and net cash flows
     pub total_return: f64,
+    /// Time-weighted when the account has external cash flows
     pub total_return_pct: f64,
     pub cagr: f64,
+    /// Time-weighted return (%): sub-period returns between cash flows,
+    /// chain-linked, so deposits and withdrawals do not count as performance
+    #[serde(default)]
+    pub twr_pct: f64,
+    /// Money-weighted return: annualized IRR (%) of the initial capital,
+    /// cash flows and final equity over calendar days; 0 when undefined
+    #[serde(default)]
+    pub mwr_pct: f64,
     // Risk metrics
     pub volatility: f64,
     pub sharpe_ratio: f64,
+    #[serde(deserialize_with = "deserialize_ratio")]
     pub sortino_ratio: f64,
     pub max_drawdown: f64,
     pub max_drawdown_duration_days: i64,
@@ -156,24 +511,584 @@ pub struct PerformanceMetrics {
     pub win_rate: f64,
     pub avg_win: f64,
     pub avg_loss: f64,
+    /// Win rate, profit factor and expectancy take net trade P&L
+    #[serde(deserialize_with = "deserialize_ratio")]
     pub profit_factor: f64,
+    /// Profit factor on gross trade P&L, before commissions
+    #[serde(default, deserialize_with = "deserialize_ratio")]
+    pub gross_profit_factor: f64,
     pub expectancy: f64,
     pub avg_trade_duration_days: f64,
     pub best_trade: f64,
     pub worst_trade: f64,
     pub exposure_pct: f64,
+    // Guard rails
+    #[serde(default)]
+    pub suppressed_entries: u32,
+    /// Entries shrunk to fit the liquidity cap
+    #[serde(default)]
+    pub liquidity_capped_entries: u32,
+    /// Exits the volume cap spread over several bars
+    #[serde(default)]
+    pub multi_bar_exits: u32,
+    /// Highest / mean per-bar portfolio heat (risk to stop, % of equity)
+    #[serde(default)]
+    pub max_heat_pct: f64,
+    #[serde(default)]
+    pub avg_heat_pct: f64,
+    /// Largest (either way) / mean per-bar net market exposure, long minus
+    /// hedge weighed by leverage, as % of equity (see `net_exposure_curve`)
+    #[serde(default)]
+    pub max_net_exposure_pct: f64,
+    #[serde(default)]
+    pub avg_net_exposure_pct: f64,
+    // Costs
+    /// Mean modeled round-trip cost per trade ($ and bps of entry notional)
+    #[serde(default)]
+    pub avg_round_trip_cost: f64,
+    #[serde(default)]
+    pub avg_round_trip_cost_bps: f64,
+    /// Win rate (%) at which the observed avg win / avg loss break even.
+    /// Trade P&L is net of costs, so this already accounts for them.
+    #[serde(default)]
+    pub breakeven_win_rate_pct: f64,
+    // R-multiples, over trades opened with a stop
+    #[serde(default)]
+    pub avg_r_multiple: f64,
+    #[serde(default)]
+    pub total_r_multiple: f64,
+    /// 1R-wide buckets from the lowest to the highest R-multiple
+    #[serde(default)]
+    pub r_multiple_histogram: Vec<RBucket>,
+    // Overnight gaps, over trades held past at least one close
+    /// Most negative open-versus-prior-close gap (%) a trade sat through;
+    /// 0 when none did
+    #[serde(default)]
+    pub worst_overnight_gap_in_trade_pct: f64,
+    /// Trades by the worst gap they sat through, in 1%-wide buckets
+    #[serde(default)]
+    pub overnight_gap_histogram: Vec<GapBucket>,
+    // Entry and exit efficiency, over closed trades measured against bars
+    #[serde(default)]
+    pub avg_entry_efficiency: f64,
+    #[serde(default)]
+    pub avg_exit_efficiency: f64,
+    #[serde(default)]
+    pub trade_sequence: SequenceStats,
+}
+
+/// Numeric fields of [`PerformanceMetrics`], for selecting a metric by name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricField {
+    TotalReturn,
+    TotalReturnPct,
+    Cagr,
+    TwrPct,
+    MwrPct,
+    Volatility,
+    SharpeRatio,
+    SortinoRatio,
+    MaxDrawdown,
+    MaxDrawdownDurationDays,
+    CalmarRatio,
+    TotalTrades,
+    WinningTrades,
+    LosingTrades,
+    WinRate,
+    AvgWin,
+    AvgLoss,
+    ProfitFactor,
+    GrossProfitFactor,
+    Expectancy,
+    AvgTradeDurationDays,
+    BestTrade,
+    WorstTrade,
+    ExposurePct,
+    SuppressedEntries,
+    LiquidityCappedEntries,
+    MultiBarExits,
+    MaxHeatPct,
+    AvgHeatPct,
+    MaxNetExposurePct,
+    AvgNetExposurePct,
+    AvgRoundTripCost,
+    AvgRoundTripCostBps,
+    BreakevenWinRatePct,
+    AvgRMultiple,
+    TotalRMultiple,
+    WorstOvernightGapInTradePct,
+    AvgEntryEfficiency,
+    AvgExitEfficiency,
+}
+
+impl PerformanceMetrics {
+    /// Value of a metric field as f64
+    pub fn get(&self, field: MetricField) -> f64 {
+        match field {
+            MetricField::TotalReturn => self.total_return,
+            MetricField::TotalReturnPct => self.total_return_pct,
+            MetricField::Cagr => self.cagr,
+            MetricField::TwrPct => self.twr_pct,
+            MetricField::MwrPct => self.mwr_pct,
+            MetricField::Volatility => self.volatility,
+            MetricField::SharpeRatio => self.sharpe_ratio,
+            MetricField::SortinoRatio => self.sortino_ratio,
+            MetricField::MaxDrawdown => self.max_drawdown,
+            MetricField::MaxDrawdownDurationDays => self.max_drawdown_duration_days as f64,
+            MetricField::CalmarRatio => self.calmar_ratio,
+            MetricField::TotalTrades => self.total_trades as f64,
+            MetricField::WinningTrades => self.winning_trades as f64,
+            MetricField::LosingTrades => self.losing_trades as f64,
+            MetricField::WinRate => self.win_rate,
+            MetricField::AvgWin => self.avg_win,
+            MetricField::AvgLoss => self.avg_loss,
+            MetricField::ProfitFactor => self.profit_factor,
+            MetricField::GrossProfitFactor => self.gross_profit_factor,
+            MetricField::Expectancy => self.expectancy,
+            MetricField::AvgTradeDurationDays => self.avg_trade_duration_days,
+            MetricField::BestTrade => self.best_trade,
+            MetricField::WorstTrade => self.worst_trade,
+            MetricField::ExposurePct => self.exposure_pct,
+            MetricField::SuppressedEntries => self.suppressed_entries as f64,
+            MetricField::LiquidityCappedEntries => self.liquidity_capped_entries as f64,
+            MetricField::MultiBarExits => self.multi_bar_exits as f64,
+            MetricField::MaxHeatPct => self.max_heat_pct,
+            MetricField::AvgHeatPct => self.avg_heat_pct,
+            MetricField::MaxNetExposurePct => self.max_net_exposure_pct,
+            MetricField::AvgNetExposurePct => self.avg_net_exposure_pct,
+            MetricField::AvgRoundTripCost => self.avg_round_trip_cost,
+            MetricField::AvgRoundTripCostBps => self.avg_round_trip_cost_bps,
+            MetricField::BreakevenWinRatePct => self.breakeven_win_rate_pct,
+            MetricField::AvgRMultiple => self.avg_r_multiple,
+            MetricField::TotalRMultiple => self.total_r_multiple,
+            MetricField::WorstOvernightGapInTradePct => self.worst_overnight_gap_in_trade_pct,
+            MetricField::AvgEntryEfficiency => self.avg_entry_efficiency,
+            MetricField::AvgExitEfficiency => self.avg_exit_efficiency,
+        }
+    }
+}
+
+impl MetricField {
+    /// Every variant with its serde name and display label
+    const TABLE: [(MetricField, &'static str, &'static str); 39] = [
+        (MetricField::TotalReturn, "total_return", "Total Return"),
+        (
+            MetricField::TotalReturnPct,
+            "total_return_pct",
+            "Total Return %",
+        ),
+        (MetricField::Cagr, "cagr", "CAGR"),
+        (MetricField::TwrPct, "twr_pct", "Time-Weighted Return %"),
+        (MetricField::MwrPct, "mwr_pct", "Money-Weighted Return %"),
+        (MetricField::Volatility, "volatility", "Volatility"),
+        (MetricField::SharpeRatio, "sharpe_ratio", "Sharpe Ratio"),
+        (MetricField::SortinoRatio, "sortino_ratio", "Sortino Ratio"),
+        (MetricField::MaxDrawdown, "max_drawdown", "Max Drawdown"),
+        (
+            MetricField::MaxDrawdownDurationDays,
+            "max_drawdown_duration_days",
+            "Max Drawdown Duration (days)",
+        ),
+        (MetricField::CalmarRatio, "calmar_ratio", "Calmar Ratio"),
+        (MetricField::TotalTrades, "total_trades", "Total Trades"),
+        (
+            MetricField::WinningTrades,
+            "winning_trades",
+            "Winning Trades",
+        ),
+        (MetricField::LosingTrades, "losing_trades", "Losing Trades"),
+        (MetricField::WinRate, "win_rate", "Win Rate"),
+        (MetricField::AvgWin, "avg_win", "Avg Win"),
+        (MetricField::AvgLoss, "avg_loss", "Avg Loss"),
+        (MetricField::ProfitFactor, "profit_factor", "Profit Factor"),
+        (
+            MetricField::GrossProfitFactor,
+            "gross_profit_factor",
+            "Gross Profit Factor",
+        ),
+        (MetricField::Expectancy, "expectancy", "Expectancy"),
+        (
+            MetricField::AvgTradeDurationDays,
+            "avg_trade_duration_days",
+            "Avg Trade Duration (days)",
+        ),
+        (MetricField::BestTrade, "best_trade", "Best Trade"),
+        (MetricField::WorstTrade, "worst_trade", "Worst Trade"),
+        (MetricField::ExposurePct, "exposure_pct", "Exposure %"),
+        (
+            MetricField::SuppressedEntries,
+            "suppressed_entries",
+            "Suppressed Entries",
+        ),
+        (
+            MetricField::LiquidityCappedEntries,
+            "liquidity_capped_entries",
+            "Liquidity-Capped Entries",
+        ),
+        (
+            MetricField::MultiBarExits,
+            "multi_bar_exits",
+            "Multi-Bar Exits",
+        ),
+        (MetricField::MaxHeatPct, "max_heat_pct", "Max Heat %"),
+        (MetricField::AvgHeatPct, "avg_heat_pct", "Avg Heat %"),
+        (
+            MetricField::MaxNetExposurePct,
+            "max_net_exposure_pct",
+            "Max Net Exposure %",
+        ),
+        (
+            MetricField::AvgNetExposurePct,
+            "avg_net_exposure_pct",
+            "Avg Net Exposure %",
+        ),
+        (
+            MetricField::AvgRoundTripCost,
+            "avg_round_trip_cost",
+            "Avg Round-Trip Cost",
+        ),
+        (
+            MetricField::AvgRoundTripCostBps,
+            "avg_round_trip_cost_bps",
+            "Avg Round-Trip Cost (bps)",
+        ),
+        (
+            MetricField::BreakevenWinRatePct,
+            "breakeven_win_rate_pct",
+            "Breakeven Win Rate %",
+        ),
+        (
+            MetricField::AvgRMultiple,
+            "avg_r_multiple",
+            "Avg R-Multiple",
+        ),
+        (
+            MetricField::TotalRMultiple,
+            "total_r_multiple",
+            "Total R-Multiple",
+        ),
+        (
+            MetricField::WorstOvernightGapInTradePct,
+            "worst_overnight_gap_in_trade_pct",
+            "Worst Overnight Gap in Trade %",
+        ),
+        (
+            MetricField::AvgEntryEfficiency,
+            "avg_entry_efficiency",
+            "Avg Entry Efficiency",
+        ),
+        (
+            MetricField::AvgExitEfficiency,
+            "avg_exit_efficiency",
+            "Avg Exit Efficiency",
+        ),
+    ];
+
+    /// Every field, in declaration order
+    pub fn all() -> impl Iterator<Item = MetricField> {
+        Self::TABLE.iter().map(|(field, _, _)| *field)
+    }
+
+    /// Serialized name (the `PerformanceMetrics` field name)
+    pub fn name(self) -> &'static str {
+        Self::TABLE
+            .iter()
+            .find(|(f, _, _)| *f == self)
+            .map_or("", |(_, name, _)| name)
+    }
+
+    /// Human-readable label, e.g. "Sharpe Ratio"
+    pub fn label(self) -> &'static str {
+        Self::TABLE
+            .iter()
+            .find(|(f, _, _)| *f == self)
+            .map_or("", |(_, _, label)| label)
+    }
+}
+
+impl std::fmt::Display for MetricField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl std::str::FromStr for MetricField {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+        Self::all().find(|f| f.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = Self::all().map(|f| f.name()).collect();
+            format!("unknown metric '{}' (valid: {})", s, names.join(", "))
+        })
+    }
+}
+
+/// Calendar period for resampling an equity curve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResampleFrequency {
+    #[default]
+    Daily,
+    /// Weeks ending Friday
+    Weekly,
+    Monthly,
+}
+
+impl ResampleFrequency {
+    /// Last calendar day of the period containing `date`
+    fn period_end(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            ResampleFrequency::Daily => date,
+            ResampleFrequency::Weekly => {
+                // Friday is day 4 counting from Monday; weekends roll forward
+                let days_to_friday = (11 - date.weekday().num_days_from_monday()) % 7;
+                date + chrono::Duration::days(days_to_friday as i64)
+            }
+            ResampleFrequency::Monthly => {
+                let (year, month) = if date.month() == 12 {
+                    (date.year() + 1, 1)
+                } else {
+                    (date.year(), date.month() + 1)
+                };
+                NaiveDate::from_ymd_opt(year, month, 1)
+                    .and_then(|d| d.pred_opt())
+                    .unwrap_or(date)
+            }
+        }
+    }
+
+    /// Last observation of each period, dated by that observation (UTC).
+    /// Partial first and last periods are kept.
+    pub fn resample(self, curve: &[(DateTime<Utc>, f64)]) -> Vec<(NaiveDate, f64)> {
+        let mut points: Vec<(NaiveDate, f64)> = Vec::new();
+        let mut current: Option<NaiveDate> = None;
+
+        for (ts, value) in curve {
+            let date = ts.date_naive();
+            let period = self.period_end(date);
+            match points.last_mut() {
+                Some(last) if current == Some(period) => *last = (date, *value),
+                _ => points.push((date, *value)),
+            }
+            current = Some(period);
+        }
+        points
+    }
+}
+
+impl std::str::FromStr for ResampleFrequency {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "daily" => Ok(Self::Daily),
+            "weekly" => Ok(Self::Weekly),
+            "monthly" => Ok(Self::Monthly),
+            other => Err(format!(
+                "unknown frequency '{}' (expected daily, weekly or monthly)",
+                other
+            )),
+        }
+    }
 }
 
 /// Backtest result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BacktestResult {
+    /// Headline metrics, over the trades `metrics_scope` selects. Figures
+    /// from the equity curve (returns, drawdown, Sharpe) are portfolio-wide.
     pub metrics: PerformanceMetrics,
+    #[serde(default)]
+    pub metrics_scope: MetricsScope,
+    /// Metrics over hedge trades alone, when any hedge traded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hedge_metrics: Option<PerformanceMetrics>,
+    /// Metrics over every trade, when any hedge traded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub combined_metrics: Option<PerformanceMetrics>,
     pub equity_curve: Vec<(DateTime<Utc>, f64)>,
     pub drawdown_curve: Vec<(DateTime<Utc>, f64)>,
+    /// Portfolio heat (%) recorded alongside each equity point
+    #[serde(default)]
+    pub heat_curve: Vec<(DateTime<Utc>, f64)>,
+    /// Net market exposure in dollars of the primary symbol (see
+    /// `Portfolio::net_exposure`) alongside each equity point
+    #[serde(default)]
+    pub net_exposure_curve: Vec<(DateTime<Utc>, f64)>,
     pub trades: Vec<Trade>,
+    /// Signals the engine placed orders on, stamped with their order ids
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signals: Vec<Signal>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fills: Vec<Fill>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suppressed_signals: Vec<SuppressedSignal>,
+    /// Entries pre-trade checks rejected or shrank, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_trade_events: Vec<PreTradeEvent>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<DataWarning>,
+    /// Bars skipped because trading was halted (flagged, or below
+    /// `min_bar_volume`)
+    #[serde(default)]
+    pub halted_bars: usize,
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
     pub initial_capital: f64,
     pub final_equity: f64,
+    /// How long entries were sized
+    #[serde(default)]
+    pub sizing_mode: SizingMode,
     pub execution_time_ms: u64,
+    /// What produced this result
+    #[serde(default)]
+    pub manifest: RunManifest,
+    /// Where the run streamed its trades, equity and signals instead of
+    /// holding them here; those lists are then empty and the metrics were
+    /// computed as the records were written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spill: Option<SpillSummary>,
+    /// Run on every Nth bar only (`BacktestEngine::with_bar_stride`), a
+    /// coarse estimate of the full-resolution result
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub approximate: bool,
+}
+
+/// Records a run wrote to its spill directory, one JSON object per line
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpillSummary {
+    pub dir: std::path::PathBuf,
+    pub trades: u64,
+    /// Equity points, each with its drawdown and heat
+    pub equity_points: u64,
+    pub signals: u64,
+    pub fills: u64,
+}
+
+impl BacktestResult {
+    /// Equity at the last bar of each week (weeks end Friday)
+    pub fn equity_weekly(&self) -> Vec<(NaiveDate, f64)> {
+        ResampleFrequency::Weekly.resample(&self.equity_curve)
+    }
+
+    /// Equity at the last bar of each calendar month
+    pub fn equity_monthly(&self) -> Vec<(NaiveDate, f64)> {
+        ResampleFrequency::Monthly.resample(&self.equity_curve)
+    }
+
+    /// The records stamped with `order_id`: the signal behind it, its fill,
+    /// and the trades it opened or closed
+    pub fn trace(&self, order_id: u64) -> OrderTrace {
+        OrderTrace {
+            order_id,
+            signal: self
+                .signals
+                .iter()
+                .find(|s| s.order_id == Some(order_id))
+                .cloned(),
+            fills: self
+                .fills
+                .iter()
+                .filter(|f| f.order_id == order_id)
+                .cloned()
+                .collect(),
+            opened: self
+                .trades
+                .iter()
+                .filter(|t| t.entry_order_id == Some(order_id))
+                .cloned()
+                .collect(),
+            closed: self
+                .trades
+                .iter()
+                .find(|t| t.exit_order_id == Some(order_id))
+                .cloned(),
+        }
+    }
+}
+
+/// Everything recorded about one order; see [`BacktestResult::trace`]
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderTrace {
+    pub order_id: u64,
+    /// None for orders the engine placed on its own: stops, forced exits
+    pub signal: Option<Signal>,
+    /// Every tranche filled, in order; empty when the order was rejected
+    /// or never filled
+    pub fills: Vec<Fill>,
+    /// Trades out of the position the order opened; more than one when the
+    /// position was sold down in parts
+    pub opened: Vec<Trade>,
+    pub closed: Option<Trade>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_money_rounding() {
+        assert_eq!(
+            Money::from_dollars(0.1) + Money::from_dollars(0.2),
+            Money::from_cents(30)
+        );
+        assert_eq!(Money::from_dollars(1.005).cents(), 100); // 1.005 is 1.00499.. in binary
+        assert_eq!(Money::from_dollars(2.345).cents(), 235);
+        assert_eq!(Money::from_dollars(-2.345).cents(), -235);
+        assert_eq!(Money::notional(3.0, 33.333), Money::from_cents(10000));
+        assert_eq!(
+            Money::from_cents(i64::MAX).checked_add(Money::from_cents(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_money_serializes_as_dollars() {
+        let json = serde_json::to_string(&Money::from_cents(123_456)).unwrap();
+        assert_eq!(json, "1234.56");
+        let back: Money = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.cents(), 123_456);
+    }
+
+    #[test]
+    fn test_infinite_ratios_round_trip() {
+        let metrics = PerformanceMetrics {
+            profit_factor: f64::INFINITY,
+            sortino_ratio: 1.5,
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&metrics).unwrap();
+        assert!(json.contains("\"profit_factor\":null"));
+        let back: PerformanceMetrics = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.profit_factor, f64::INFINITY);
+        assert_eq!(back.sortino_ratio, 1.5);
+    }
+
+    #[test]
+    fn test_every_numeric_metric_has_a_field() {
+        let metrics = serde_json::to_value(PerformanceMetrics::default()).unwrap();
+        let numeric: Vec<&String> = metrics
+            .as_object()
+            .unwrap()
+            .iter()
+            .filter(|(_, v)| v.is_number())
+            .map(|(k, _)| k)
+            .collect();
+        for key in &numeric {
+            let field: MetricField = key.parse().unwrap();
+            assert_eq!(serde_json::to_value(field).unwrap(), key.as_str());
+        }
+        assert_eq!(MetricField::all().count(), numeric.len());
+
+        let metrics = PerformanceMetrics {
+            calmar_ratio: 1.5,
+            ..Default::default()
+        };
+        assert_eq!(metrics.get("calmar_ratio".parse().unwrap()), 1.5);
+        assert_eq!(MetricField::SharpeRatio.to_string(), "Sharpe Ratio");
+        let err = "sharpe".parse::<MetricField>().unwrap_err();
+        assert!(err.starts_with("unknown metric 'sharpe' (valid: total_return, "));
+    }
 }
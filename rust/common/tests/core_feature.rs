@@ -0,0 +1,27 @@
+//! The indicator core must keep building without `std`; a stray std-only
+//! import there would otherwise only show up on an embedded target.
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn test_core_feature_builds_without_std() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let output = Command::new(env!("CARGO"))
+        .args(["check", "--quiet", "--offline", "--no-default-features"])
+        .args(["--features", "core"])
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("Cargo.toml"))
+        // A separate target directory, so the outer build's lock is not held
+        .env(
+            "CARGO_TARGET_DIR",
+            manifest_dir.join("../target/core-check"),
+        )
+        .output()
+        .expect("failed to run cargo");
+    assert!(
+        output.status.success(),
+        "core feature failed to build:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
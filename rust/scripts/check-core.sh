@@ -0,0 +1,10 @@
+#!/usr/bin/env bash
+# Build and test the `no_std` indicator core of `common` on its own, the
+# way an embedded or WASM target would consume it.
+set -euo pipefail
+
+cd "$(dirname "$0")/.."
+
+cargo check -p common --no-default-features --features core
+cargo clippy -p common --no-default-features --features core --all-targets -- -D warnings
+cargo test -p common --no-default-features --features core